@@ -0,0 +1,344 @@
+use super::loader::{load_app_data, save_app_data};
+use crate::errors::{AppError, ErrorKind};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+const ANNOTATIONS_FILE: &str = "annotations.json";
+
+/// Serializes access to the annotations file, so commands changing it at the same time don't
+/// drop each other's changes
+static ANNOTATIONS_LOCK: Mutex<()> = Mutex::new(());
+
+/// What an annotation is attached to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AnnotationTarget {
+    Request,
+    History,
+}
+
+/// Tags and free-text notes attached to a saved request or history entry
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Annotation {
+    /// ID of the request or history entry
+    pub target_id: String,
+    pub target: AnnotationTarget,
+    /// Owning collection, when the target is a saved request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collection_id: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// Last update timestamp, ISO 8601
+    #[serde(default)]
+    pub updated: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AnnotationsFile {
+    #[serde(default)]
+    entries: BTreeMap<String, Annotation>,
+}
+
+/// Filter for [`query_annotations`]
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnnotationQuery {
+    /// Query string, e.g. `tag:smoke -tag:flaky timeout`. Bare words match notes.
+    pub query: Option<String>,
+    pub target: Option<AnnotationTarget>,
+    pub collection_id: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct ParsedQuery {
+    include_tags: Vec<String>,
+    exclude_tags: Vec<String>,
+    words: Vec<String>,
+}
+
+fn normalize_tag(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_lowercase()
+}
+
+fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = tags
+        .iter()
+        .map(|t| normalize_tag(t))
+        .filter(|t| !t.is_empty())
+        .collect();
+    out.sort();
+    out.dedup();
+    out
+}
+
+fn parse_query(query: &str) -> ParsedQuery {
+    let mut parsed = ParsedQuery::default();
+    for term in query.split_whitespace() {
+        if let Some(tag) = term.strip_prefix("-tag:") {
+            parsed.exclude_tags.push(normalize_tag(tag));
+        } else if let Some(tag) = term.strip_prefix("tag:") {
+            parsed.include_tags.push(normalize_tag(tag));
+        } else {
+            parsed.words.push(term.to_lowercase());
+        }
+    }
+    parsed
+}
+
+fn matches(annotation: &Annotation, filter: &AnnotationQuery, parsed: &ParsedQuery) -> bool {
+    if filter.target.is_some_and(|t| t != annotation.target) {
+        return false;
+    }
+    if let Some(cid) = &filter.collection_id
+        && annotation.collection_id.as_ref() != Some(cid)
+    {
+        return false;
+    }
+    if !parsed
+        .include_tags
+        .iter()
+        .all(|t| annotation.tags.contains(t))
+    {
+        return false;
+    }
+    if parsed
+        .exclude_tags
+        .iter()
+        .any(|t| annotation.tags.contains(t))
+    {
+        return false;
+    }
+    let notes = annotation.notes.as_deref().unwrap_or("").to_lowercase();
+    parsed.words.iter().all(|w| notes.contains(w.as_str()))
+}
+
+/// Applies `change` to the annotations `load` reads, and writes them with `save` when it
+/// reports a change, holding `lock` throughout.
+fn update_locked<T>(
+    lock: &Mutex<()>,
+    load: impl FnOnce() -> Result<AnnotationsFile, AppError>,
+    save: impl FnOnce(&AnnotationsFile) -> Result<(), AppError>,
+    change: impl FnOnce(&mut AnnotationsFile) -> (T, bool),
+) -> Result<T, AppError> {
+    let _guard = lock.lock().unwrap();
+    let mut file = load()?;
+    let (result, changed) = change(&mut file);
+    if changed {
+        save(&file)?;
+    }
+    Ok(result)
+}
+
+fn load(app: &AppHandle) -> Result<AnnotationsFile, AppError> {
+    match load_app_data(app, ANNOTATIONS_FILE) {
+        Ok(value) => Ok(serde_json::from_value(value)?),
+        Err(e) if e.kind == ErrorKind::FileNotFound => Ok(AnnotationsFile::default()),
+        Err(e) => Err(e),
+    }
+}
+
+fn save(app: &AppHandle, file: &AnnotationsFile) -> Result<(), AppError> {
+    save_app_data(app, ANNOTATIONS_FILE, serde_json::to_value(file)?)
+}
+
+/// The annotations, read under the lock so a write in progress is never seen half done
+fn read(app: &AppHandle) -> Result<AnnotationsFile, AppError> {
+    let _guard = ANNOTATIONS_LOCK.lock().unwrap();
+    load(app)
+}
+
+/// Changes the annotations under the lock; `change` reports whether they need saving
+fn update<T>(
+    app: &AppHandle,
+    change: impl FnOnce(&mut AnnotationsFile) -> (T, bool),
+) -> Result<T, AppError> {
+    update_locked(
+        &ANNOTATIONS_LOCK,
+        || load(app),
+        |file| save(app, file),
+        change,
+    )
+}
+
+pub fn get_annotation(app: &AppHandle, target_id: &str) -> Result<Option<Annotation>, AppError> {
+    Ok(read(app)?.entries.remove(target_id))
+}
+
+/// Creates or replaces the annotation for its target. Empty annotations are removed.
+pub fn set_annotation(app: &AppHandle, mut annotation: Annotation) -> Result<Annotation, AppError> {
+    if annotation.target_id.trim().is_empty() {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            "Annotation target id is required",
+        ));
+    }
+    annotation.tags = normalize_tags(&annotation.tags);
+    annotation.notes = annotation.notes.filter(|n| !n.trim().is_empty());
+    annotation.updated = chrono::Utc::now().to_rfc3339();
+
+    update(app, |file| {
+        if annotation.tags.is_empty() && annotation.notes.is_none() {
+            file.entries.remove(&annotation.target_id);
+        } else {
+            file.entries
+                .insert(annotation.target_id.clone(), annotation.clone());
+        }
+        ((), true)
+    })?;
+    Ok(annotation)
+}
+
+pub fn delete_annotation(app: &AppHandle, target_id: &str) -> Result<(), AppError> {
+    update(app, |file| ((), file.entries.remove(target_id).is_some()))
+}
+
+pub fn query_annotations(
+    app: &AppHandle,
+    filter: AnnotationQuery,
+) -> Result<Vec<Annotation>, AppError> {
+    let file = read(app)?;
+    Ok(filter_annotations(file.entries.into_values(), &filter))
+}
+
+/// Lists every tag in use with its number of occurrences
+pub fn list_tags(app: &AppHandle) -> Result<BTreeMap<String, usize>, AppError> {
+    let mut counts = BTreeMap::new();
+    for annotation in read(app)?.entries.values() {
+        for tag in &annotation.tags {
+            *counts.entry(tag.clone()).or_insert(0) += 1;
+        }
+    }
+    Ok(counts)
+}
+
+fn filter_annotations(
+    entries: impl Iterator<Item = Annotation>,
+    filter: &AnnotationQuery,
+) -> Vec<Annotation> {
+    let parsed = parse_query(filter.query.as_deref().unwrap_or(""));
+    entries.filter(|a| matches(a, filter, &parsed)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Annotation, AnnotationQuery, AnnotationTarget, AnnotationsFile, ParsedQuery,
+        filter_annotations, normalize_tags, parse_query, update_locked,
+    };
+    use std::sync::Mutex;
+    use std::thread;
+    use std::time::Duration;
+
+    fn annotation(id: &str, tags: &[&str], notes: Option<&str>) -> Annotation {
+        Annotation {
+            target_id: id.to_string(),
+            target: AnnotationTarget::Request,
+            collection_id: Some("c1".to_string()),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            notes: notes.map(|n| n.to_string()),
+            updated: String::new(),
+        }
+    }
+
+    #[test]
+    fn parses_tag_and_word_terms() {
+        let parsed = parse_query("tag:Smoke -tag:#flaky Timeout");
+        assert_eq!(
+            parsed,
+            ParsedQuery {
+                include_tags: vec!["smoke".into()],
+                exclude_tags: vec!["flaky".into()],
+                words: vec!["timeout".into()],
+            }
+        );
+    }
+
+    #[test]
+    fn normalizes_and_dedups_tags() {
+        let tags = vec!["Smoke".into(), " smoke ".into(), "#Auth".into(), "".into()];
+        assert_eq!(normalize_tags(&tags), vec!["auth", "smoke"]);
+    }
+
+    #[test]
+    fn filters_by_tags_notes_and_scope() {
+        let entries = vec![
+            annotation("a", &["smoke"], Some("Times out on staging")),
+            annotation("b", &["smoke", "flaky"], None),
+            annotation("c", &["auth"], Some("needs token")),
+        ];
+        let query = |q: &str| AnnotationQuery {
+            query: Some(q.to_string()),
+            ..Default::default()
+        };
+
+        let ids =
+            |found: Vec<Annotation>| found.into_iter().map(|a| a.target_id).collect::<Vec<_>>();
+        assert_eq!(
+            ids(filter_annotations(
+                entries.clone().into_iter(),
+                &query("tag:smoke")
+            )),
+            vec!["a", "b"]
+        );
+        assert_eq!(
+            ids(filter_annotations(
+                entries.clone().into_iter(),
+                &query("tag:smoke -tag:flaky")
+            )),
+            vec!["a"]
+        );
+        assert_eq!(
+            ids(filter_annotations(
+                entries.clone().into_iter(),
+                &query("TOKEN")
+            )),
+            vec!["c"]
+        );
+
+        let history_only = AnnotationQuery {
+            target: Some(AnnotationTarget::History),
+            ..Default::default()
+        };
+        assert!(filter_annotations(entries.into_iter(), &history_only).is_empty());
+    }
+
+    #[test]
+    fn concurrent_updates_keep_every_change() {
+        // Stands in for the file, as serialized JSON
+        let disk = Mutex::new(serde_json::to_string(&AnnotationsFile::default()).unwrap());
+        let lock = Mutex::new(());
+        thread::scope(|scope| {
+            for i in 0..8 {
+                let (disk, lock) = (&disk, &lock);
+                scope.spawn(move || {
+                    update_locked(
+                        lock,
+                        || Ok(serde_json::from_str(&disk.lock().unwrap())?),
+                        |file| {
+                            *disk.lock().unwrap() = serde_json::to_string(file)?;
+                            Ok(())
+                        },
+                        |file| {
+                            // Widens the window another update would slip into without the lock
+                            thread::sleep(Duration::from_millis(5));
+                            let id = format!("r{i}");
+                            file.entries
+                                .insert(id.clone(), annotation(&id, &["smoke"], None));
+                            ((), true)
+                        },
+                    )
+                    .unwrap();
+                });
+            }
+        });
+
+        let file: AnnotationsFile = serde_json::from_str(&disk.lock().unwrap()).unwrap();
+        assert_eq!(file.entries.len(), 8);
+    }
+}
@@ -1,3 +1,6 @@
+use super::key_protection::{
+    self, KeyProtection, KeyProtectionStatus, decode_entry, encode_entry, protection_of,
+};
 use crate::app_error;
 use crate::errors::{AppError, ErrorKind};
 // AES-GCM with 256-bit key
@@ -7,40 +10,81 @@ use base64::{DecodeError, Engine, engine::general_purpose as b64};
 use keyring::Entry;
 use rand::RngCore;
 use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
 use tauri::AppHandle;
 
-#[cfg(not(test))]
-pub fn get_or_create_key(app: &AppHandle, key_name: &str) -> Result<[u8; 32], AppError> {
+/// Version prefix of blobs that record the key they were encrypted with: `k1.<key id>.<base64>`.
+/// Blobs without it predate key ids and are plain base64 (which never contains a `.`).
+const KEYED_BLOB_PREFIX: &str = "k1.";
+
+/// Short, stable identifier of a key: the first 8 bytes of its SHA-256 digest, hex encoded.
+/// Safe to store and display since it can't be used to recover the key.
+pub fn key_id(key_bytes: &[u8]) -> String {
+    hex::encode(&Sha256::digest(key_bytes)[..8])
+}
+
+/// Splits a blob into the id of the key it was encrypted with (if recorded) and its payload.
+pub fn split_blob(encoded: &str) -> (Option<&str>, &str) {
+    encoded
+        .strip_prefix(KEYED_BLOB_PREFIX)
+        .and_then(|rest| rest.split_once('.'))
+        .map_or((None, encoded), |(id, payload)| (Some(id), payload))
+}
+
+/// Keyring entries holding data encryption keys
+const KEY_NAMES: [&str; 2] = ["app_data", "default"];
+
+/// Keys already read from the keyring. Hardware-wrapped keys are slow to unwrap, so each is
+/// unwrapped once per run.
+static KEY_CACHE: Mutex<BTreeMap<String, [u8; 32]>> = Mutex::new(BTreeMap::new());
+
+pub(super) fn keyring_entry(app: &AppHandle, key_name: &str) -> Result<Entry, AppError> {
     let target = format!("{}:{}", app.config().identifier, app.package_info().name);
     let service = app.package_info().name.clone();
-    let entry =
-        Entry::new_with_target(&target, &service, key_name).map_err(|e: keyring::Error| {
-            app_error!(ErrorKind::KeyringAttributeInvalid, e.to_string())
-        })?;
+    Entry::new_with_target(&target, &service, key_name)
+        .map_err(|e: keyring::Error| app_error!(ErrorKind::KeyringAttributeInvalid, e.to_string()))
+}
 
-    if let Ok(encoded) = entry.get_password() {
-        let decoded = b64::URL_SAFE_NO_PAD
-            .decode(&encoded)
-            .map_err(|e: DecodeError| app_error!(ErrorKind::KeyringBadEncoding, e.to_string()))?;
-
-        let key: [u8; 32] = decoded.try_into().map_err(|v: Vec<u8>| {
-            app_error!(
-                ErrorKind::InvalidKeyLength,
-                format!("Expected 32-byte key, got {} bytes", v.len())
-            )
-        })?;
+/// Stores `key` under `key_name`, keeping the protection of the entry it replaces.
+fn store_key(app: &AppHandle, key_name: &str, key: &[u8; 32]) -> Result<(), AppError> {
+    let entry = keyring_entry(app, key_name)?;
+    let protection = entry
+        .get_password()
+        .map_or(KeyProtection::Software, |existing| protection_of(&existing));
+    let (encoded, _) = encode_entry(key, protection)?;
+    entry.set_password(&encoded).map_err(|e: keyring::Error| {
+        app_error!(ErrorKind::KeyringPlatformFailure, e.to_string())
+    })?;
+    KEY_CACHE.lock().unwrap().insert(key_name.to_string(), *key);
+    Ok(())
+}
+
+#[cfg(not(test))]
+pub fn get_or_create_key(app: &AppHandle, key_name: &str) -> Result<[u8; 32], AppError> {
+    if let Some(key) = KEY_CACHE.lock().unwrap().get(key_name) {
+        return Ok(*key);
+    }
+
+    // The startup preload and the first data load may both get here; only one may create a key
+    static KEY_INIT: Mutex<()> = Mutex::new(());
+    let _init = KEY_INIT.lock().unwrap();
+    if let Some(key) = KEY_CACHE.lock().unwrap().get(key_name) {
+        return Ok(*key);
+    }
 
+    let entry = keyring_entry(app, key_name)?;
+    if let Ok(encoded) = entry.get_password() {
+        let key = decode_entry(&encoded)?;
+        KEY_CACHE.lock().unwrap().insert(key_name.to_string(), key);
         return Ok(key);
     }
 
     // Generate and store a new key
     let mut key = [0u8; 32];
     rand::rng().fill_bytes(&mut key);
-
-    let encoded = b64::URL_SAFE_NO_PAD.encode(key);
-    entry.set_password(&encoded).map_err(|e: keyring::Error| {
-        app_error!(ErrorKind::KeyringPlatformFailure, e.to_string())
-    })?;
+    store_key(app, key_name, &key)?;
 
     Ok(key)
 }
@@ -51,7 +95,8 @@ pub fn get_or_create_key(_app: &AppHandle, _key_name: &str) -> Result<[u8; 32],
     Ok([42u8; 32])
 }
 
-/// Encrypts plaintext using AES-256-GCM, returning a base64-encoded blob (nonce + ciphertext).
+/// Encrypts plaintext using AES-256-GCM, returning a blob tagged with the key id followed by
+/// the base64-encoded nonce and ciphertext.
 pub fn encrypt(plain_text: &str, key_bytes: &[u8]) -> Result<String, AppError> {
     let key = Key::<Aes256Gcm>::from_slice(key_bytes);
     let cipher = Aes256Gcm::new(key);
@@ -67,12 +112,34 @@ pub fn encrypt(plain_text: &str, key_bytes: &[u8]) -> Result<String, AppError> {
     let mut combined = nonce_bytes.to_vec();
     combined.extend(ciphertext);
 
-    Ok(b64::URL_SAFE_NO_PAD.encode(combined))
+    Ok(format!(
+        "{KEYED_BLOB_PREFIX}{}.{}",
+        key_id(key_bytes),
+        b64::URL_SAFE_NO_PAD.encode(combined)
+    ))
 }
 
-/// Decrypts a base64-encoded AES-GCM blob into plaintext.
+/// Decrypts an AES-GCM blob into plaintext.
+///
+/// Fails with `KeyMismatch` when the blob records a different key than `key_bytes`.
 pub fn decrypt(encoded: &str, key_bytes: &[u8]) -> Result<String, AppError> {
-    let combined = b64::URL_SAFE_NO_PAD.decode(encoded)?;
+    let (blob_key_id, payload) = split_blob(encoded);
+    if let Some(blob_key_id) = blob_key_id {
+        let active_key_id = key_id(key_bytes);
+        if blob_key_id != active_key_id {
+            return Err(AppError::with_context(
+                ErrorKind::KeyMismatch,
+                format!(
+                    "Value was encrypted with key {blob_key_id}, but the active key is {active_key_id}"
+                ),
+                HashMap::from([
+                    ("keyId".to_string(), blob_key_id.to_string()),
+                    ("activeKeyId".to_string(), active_key_id),
+                ]),
+            ));
+        }
+    }
+    let combined = b64::URL_SAFE_NO_PAD.decode(payload)?;
     if combined.len() < 12 {
         return Err(app_error!(
             ErrorKind::DecryptionFailed,
@@ -97,38 +164,76 @@ pub fn decrypt(encoded: &str, key_bytes: &[u8]) -> Result<String, AppError> {
     Ok(utf8)
 }
 
-pub fn decrypt_in_place(value: &mut Value, key_bytes: &[u8]) {
-    decrypt_recursive(value, key_bytes, &mut Vec::new());
+/// Decrypts every secure value in a JSON tree.
+///
+/// All values are attempted. Values encrypted with another key are reported in a single
+/// `KeyMismatch` error, as the tree can't be used with the active key. Values that fail to
+/// decrypt for any other reason (e.g. a corrupt blob) are logged and left as-is.
+pub fn decrypt_in_place(value: &mut Value, key_bytes: &[u8]) -> Result<(), AppError> {
+    let mut failures = Vec::new();
+    decrypt_recursive(value, key_bytes, &mut Vec::new(), &mut failures);
+
+    let mut paths = Vec::new();
+    let mut mismatched: BTreeMap<String, usize> = BTreeMap::new();
+    for (path, err) in &failures {
+        match err.context.as_ref().and_then(|c| c.get("keyId")) {
+            Some(id) if err.kind == ErrorKind::KeyMismatch => {
+                paths.push(path.as_str());
+                *mismatched.entry(id.clone()).or_insert(0) += 1;
+            }
+            _ => log::warn!("Decryption failed at path {path}: {}", err.message),
+        }
+    }
+    if mismatched.is_empty() {
+        return Ok(());
+    }
+
+    let key_ids: Vec<&str> = mismatched.keys().map(String::as_str).collect();
+    let active_key_id = key_id(key_bytes);
+    let context = HashMap::from([
+        ("paths".to_string(), paths.join(",")),
+        ("keyIds".to_string(), key_ids.join(",")),
+        ("activeKeyId".to_string(), active_key_id.clone()),
+    ]);
+    Err(AppError::with_context(
+        ErrorKind::KeyMismatch,
+        format!(
+            "{} value(s) were encrypted with key {}, but the active key is {active_key_id}",
+            paths.len(),
+            key_ids.join(", ")
+        ),
+        context,
+    ))
 }
 
 /// Recursively traverses a JSON tree and decrypts any objects with the `{"secure": true, "value": "<blob>"}` structure.
-fn decrypt_recursive(value: &mut Value, key_bytes: &[u8], path: &mut Vec<String>) {
+fn decrypt_recursive(
+    value: &mut Value,
+    key_bytes: &[u8],
+    path: &mut Vec<String>,
+    failures: &mut Vec<(String, AppError)>,
+) {
     match value {
         Value::Object(map) => {
             let is_secure = map.get("secure").and_then(Value::as_bool) == Some(true);
 
             if is_secure {
                 if let Some(Value::String(current)) = map.get_mut("value") {
-                    let encoded = current.clone();
-                    match decrypt(&encoded, key_bytes) {
+                    match decrypt(current, key_bytes) {
                         Ok(decrypted) => {
                             *current = decrypted;
                         }
                         Err(e) => {
                             let mut value_path = path.clone();
                             value_path.push("value".to_string());
-                            log::error!(
-                                "Decryption failed at path {}: {}",
-                                format_json_path(&value_path),
-                                e
-                            );
+                            failures.push((format_json_path(&value_path), e));
                         }
                     }
                 }
             } else {
                 for (k, v) in map.iter_mut() {
                     path.push(k.clone());
-                    decrypt_recursive(v, key_bytes, path);
+                    decrypt_recursive(v, key_bytes, path, failures);
                     path.pop();
                 }
             }
@@ -136,7 +241,7 @@ fn decrypt_recursive(value: &mut Value, key_bytes: &[u8], path: &mut Vec<String>
         Value::Array(arr) => {
             for (i, v) in arr.iter_mut().enumerate() {
                 path.push(format!("[{i}]"));
-                decrypt_recursive(v, key_bytes, path);
+                decrypt_recursive(v, key_bytes, path, failures);
                 path.pop();
             }
         }
@@ -211,36 +316,76 @@ pub fn get_data_encryption_key(app: &AppHandle) -> Result<String, AppError> {
     Ok(b64::URL_SAFE_NO_PAD.encode(key))
 }
 
-pub fn set_data_encryption_key(app: &AppHandle, key_b64: &str) -> Result<(), AppError> {
-    // Validate the key is valid base64 and 32 bytes long after decoding.
+/// Decodes a base64 (URL-safe, unpadded) key and checks that it is 32 bytes long.
+pub fn decode_key(key_b64: &str) -> Result<[u8; 32], AppError> {
     let decoded = b64::URL_SAFE_NO_PAD
         .decode(key_b64)
         .map_err(|e: DecodeError| app_error!(ErrorKind::KeyringBadEncoding, e.to_string()))?;
 
-    if decoded.len() != 32 {
-        return Err(app_error!(
+    decoded.try_into().map_err(|v: Vec<u8>| {
+        app_error!(
             ErrorKind::InvalidKeyLength,
-            format!("Expected 32-byte key, got {} bytes", decoded.len())
-        ));
-    }
+            format!("Expected 32-byte key, got {} bytes", v.len())
+        )
+    })
+}
 
-    let target = format!("{}:{}", app.config().identifier, app.package_info().name);
-    let service = app.package_info().name.clone();
-    let entry =
-        Entry::new_with_target(&target, &service, "default").map_err(|e: keyring::Error| {
-            app_error!(ErrorKind::KeyringAttributeInvalid, e.to_string())
-        })?;
+pub fn set_data_encryption_key(app: &AppHandle, key_b64: &str) -> Result<(), AppError> {
+    // Validate the key is valid base64 and 32 bytes long after decoding.
+    let key = decode_key(key_b64)?;
+    store_key(app, "default", &key)
+}
 
-    entry.set_password(key_b64).map_err(|e: keyring::Error| {
-        app_error!(ErrorKind::KeyringPlatformFailure, e.to_string())
-    })?;
+/// Reports how the data encryption keys are stored. Keys are only reported as hardware
+/// protected when every stored key is.
+pub fn get_key_protection(app: &AppHandle) -> Result<KeyProtectionStatus, AppError> {
+    let mut protection = KeyProtection::Software;
+    for key_name in KEY_NAMES {
+        match keyring_entry(app, key_name)?.get_password() {
+            Ok(encoded) if protection_of(&encoded) == KeyProtection::Software => {
+                return Ok(key_protection::status(KeyProtection::Software));
+            }
+            Ok(_) => protection = KeyProtection::Hardware,
+            Err(_) => {}
+        }
+    }
+    Ok(key_protection::status(protection))
+}
 
-    Ok(())
+/// Re-stores the data encryption keys with the requested protection. The key bytes don't
+/// change, so app data doesn't need to be re-encrypted. Requesting hardware protection where
+/// none is available leaves the keys in software; the returned status reports the outcome.
+pub fn set_key_protection(
+    app: &AppHandle,
+    protection: KeyProtection,
+) -> Result<KeyProtectionStatus, AppError> {
+    let mut applied = protection;
+    for key_name in KEY_NAMES {
+        let entry = keyring_entry(app, key_name)?;
+        let Ok(existing) = entry.get_password() else {
+            continue;
+        };
+        if protection_of(&existing) == protection {
+            continue;
+        }
+        let key = decode_entry(&existing)?;
+        let (encoded, stored) = encode_entry(&key, protection)?;
+        entry.set_password(&encoded).map_err(|e: keyring::Error| {
+            app_error!(ErrorKind::KeyringPlatformFailure, e.to_string())
+        })?;
+        if stored != protection {
+            applied = stored;
+        }
+    }
+    Ok(key_protection::status(applied))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{decrypt, decrypt_in_place, encrypt, encrypt_in_place, format_json_path};
+    use super::{
+        decrypt, decrypt_in_place, encrypt, encrypt_in_place, format_json_path, key_id, split_blob,
+    };
+    use crate::errors::ErrorKind;
     use base64::Engine;
     use serde_json::json;
 
@@ -305,7 +450,7 @@ mod tests {
         assert_eq!(data["plain"].as_str().unwrap(), "visible");
 
         // Decrypt
-        decrypt_in_place(&mut data, &KEY);
+        decrypt_in_place(&mut data, &KEY).expect("decrypt");
         assert_eq!(data["nested"]["value"].as_str().unwrap(), "tok123");
         assert_eq!(data["arr"][1]["value"].as_str().unwrap(), "tok456");
         assert_eq!(data["plain"].as_str().unwrap(), "visible");
@@ -318,8 +463,8 @@ mod tests {
             "secure": true,
             "value": 12345
         });
-        // Should not panic
-        decrypt_in_place(&mut data, &KEY);
+        // Should not panic; non-string values are not encrypted blobs
+        decrypt_in_place(&mut data, &KEY).expect("nothing to decrypt");
         // Value remains unchanged
         assert_eq!(data["value"], 12345);
 
@@ -328,8 +473,8 @@ mod tests {
             "secure": true,
             "value": "@@not-base64@@"
         });
-        // Should not panic; value remains the same string since decryption fails and is logged
-        decrypt_in_place(&mut data2, &KEY);
+        // Should not panic; value remains the same string and the failure is only logged
+        decrypt_in_place(&mut data2, &KEY).expect("corrupt values are skipped");
         assert_eq!(data2["value"], "@@not-base64@@");
     }
 
@@ -361,7 +506,7 @@ mod tests {
         // base64 of 1 byte => less than required 12-byte nonce
         let too_short = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode([1u8]);
         let err = decrypt(&too_short, &KEY).expect_err("should fail");
-        assert_eq!(err.kind, crate::errors::ErrorKind::DecryptionFailed);
+        assert_eq!(err.kind, ErrorKind::DecryptionFailed);
     }
 
     #[test]
@@ -373,4 +518,34 @@ mod tests {
             "should be URL-safe"
         );
     }
+
+    #[test]
+    fn blobs_record_key_id_and_legacy_blobs_still_decrypt() {
+        let encoded = encrypt("abc", &KEY).expect("encrypt ok");
+        let (id, payload) = split_blob(&encoded);
+        assert_eq!(id, Some(key_id(&KEY).as_str()));
+
+        // Blobs written before key ids were recorded are the bare payload
+        assert_eq!(split_blob(payload), (None, payload));
+        assert_eq!(decrypt(payload, &KEY).expect("legacy decrypt"), "abc");
+    }
+
+    #[test]
+    fn decrypt_with_other_key_reports_key_mismatch() {
+        let other = [7u8; 32];
+        let mut data = json!({
+            "a": {"secure": true, "value": encrypt("one", &KEY).unwrap()},
+            "b": [{"secure": true, "value": encrypt("two", &KEY).unwrap()}],
+        });
+
+        let err = decrypt(data["a"]["value"].as_str().unwrap(), &other).expect_err("mismatch");
+        assert_eq!(err.kind, ErrorKind::KeyMismatch);
+
+        let err = decrypt_in_place(&mut data, &other).expect_err("mismatch");
+        assert_eq!(err.kind, ErrorKind::KeyMismatch);
+        let context = err.context.expect("context");
+        assert_eq!(context["keyIds"], key_id(&KEY));
+        assert_eq!(context["activeKeyId"], key_id(&other));
+        assert_eq!(context["paths"], "a.value,b[0].value");
+    }
 }
@@ -0,0 +1,223 @@
+use super::crypto::{decrypt_in_place, encrypt_in_place, get_or_create_key};
+use super::loader::app_data_file_path;
+use crate::errors::{AppError, ErrorKind};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+const SNAPSHOT_DIR: &str = "env_snapshots";
+
+/// Serializes appends so concurrent commands can't interleave lines.
+static SNAPSHOT_LOCK: Mutex<()> = Mutex::new(());
+
+/// An environment as it was at one point in time, one line of its snapshot file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct EnvironmentSnapshot {
+    id: String,
+    /// Time the snapshot was taken, ISO 8601
+    taken_at: String,
+    /// The environment, with secure variable values encrypted on disk
+    environment: Value,
+}
+
+/// A snapshot without its values, for listing
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotSummary {
+    pub id: String,
+    pub taken_at: String,
+    /// Environment name when the snapshot was taken
+    pub name: String,
+    pub variable_count: usize,
+}
+
+impl EnvironmentSnapshot {
+    fn summary(&self) -> SnapshotSummary {
+        SnapshotSummary {
+            id: self.id.clone(),
+            taken_at: self.taken_at.clone(),
+            name: self.environment["name"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            variable_count: self.environment["variables"]
+                .as_object()
+                .map_or(0, |variables| variables.len()),
+        }
+    }
+}
+
+/// IDs become path segments, so only plain identifiers are accepted
+fn checked_id<'a>(what: &str, id: &'a str) -> Result<&'a str, AppError> {
+    let valid = !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(id)
+    } else {
+        Err(AppError::new(
+            ErrorKind::InvalidPath,
+            format!("Invalid {what} id '{id}'"),
+        ))
+    }
+}
+
+fn snapshot_file_name(collection_id: &str, environment_id: &str) -> Result<String, AppError> {
+    Ok(format!(
+        "{SNAPSHOT_DIR}/{}/{}.jsonl",
+        checked_id("collection", collection_id)?,
+        checked_id("environment", environment_id)?
+    ))
+}
+
+/// Parses snapshot lines, skipping any that are incomplete or corrupt
+fn parse_lines(contents: &str) -> Vec<EnvironmentSnapshot> {
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(snapshot) => Some(snapshot),
+            Err(e) => {
+                log::warn!("Skipping unreadable environment snapshot: {e}");
+                None
+            }
+        })
+        .collect()
+}
+
+/// All snapshots of an environment, oldest first, still encrypted
+fn read_snapshots(
+    app: &AppHandle,
+    collection_id: &str,
+    environment_id: &str,
+) -> Result<Vec<EnvironmentSnapshot>, AppError> {
+    let path = app_data_file_path(app, &snapshot_file_name(collection_id, environment_id)?)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(parse_lines(&fs::read_to_string(path)?))
+}
+
+/// Appends a snapshot of `environment` to its history, unless it's unchanged since the last
+/// one. Returns the new snapshot, if any.
+pub fn snapshot_environment(
+    app: &AppHandle,
+    collection_id: &str,
+    environment: Value,
+) -> Result<Option<SnapshotSummary>, AppError> {
+    let environment_id = environment["id"].as_str().unwrap_or_default().to_string();
+    let file_name = snapshot_file_name(collection_id, &environment_id)?;
+    let key = get_or_create_key(app, "app_data")?;
+
+    let _guard = SNAPSHOT_LOCK.lock().unwrap();
+    if let Some(mut last) = read_snapshots(app, collection_id, &environment_id)?.pop() {
+        decrypt_in_place(&mut last.environment, &key)?;
+        if last.environment == environment {
+            return Ok(None);
+        }
+    }
+
+    let mut snapshot = EnvironmentSnapshot {
+        id: uuid::Uuid::new_v4().to_string(),
+        taken_at: chrono::Utc::now().to_rfc3339(),
+        environment,
+    };
+    let summary = snapshot.summary();
+    encrypt_in_place(&mut snapshot.environment, &key);
+    let mut line = serde_json::to_string(&snapshot)?;
+    line.push('\n');
+
+    let path = app_data_file_path(app, &file_name)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(line.as_bytes())?;
+    file.sync_data()?;
+    Ok(Some(summary))
+}
+
+/// Snapshots of an environment, newest first.
+pub fn list_environment_snapshots(
+    app: &AppHandle,
+    collection_id: &str,
+    environment_id: &str,
+) -> Result<Vec<SnapshotSummary>, AppError> {
+    let _guard = SNAPSHOT_LOCK.lock().unwrap();
+    Ok(read_snapshots(app, collection_id, environment_id)?
+        .iter()
+        .rev()
+        .map(EnvironmentSnapshot::summary)
+        .collect())
+}
+
+/// The environment as it was in a snapshot, for the frontend to put back in place. Restoring
+/// is itself a change, so it is snapshotted in turn and can be undone.
+pub fn restore_environment_snapshot(
+    app: &AppHandle,
+    collection_id: &str,
+    environment_id: &str,
+    snapshot_id: &str,
+) -> Result<Value, AppError> {
+    let _guard = SNAPSHOT_LOCK.lock().unwrap();
+    let mut snapshot = read_snapshots(app, collection_id, environment_id)?
+        .into_iter()
+        .find(|snapshot| snapshot.id == snapshot_id)
+        .ok_or_else(|| {
+            AppError::new(
+                ErrorKind::FileNotFound,
+                format!("No snapshot '{snapshot_id}' of environment '{environment_id}'"),
+            )
+        })?;
+    decrypt_in_place(
+        &mut snapshot.environment,
+        &get_or_create_key(app, "app_data")?,
+    )?;
+    Ok(snapshot.environment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EnvironmentSnapshot, parse_lines, snapshot_file_name};
+    use serde_json::json;
+
+    #[test]
+    fn summarizes_snapshots_and_skips_torn_lines() {
+        let snapshot = EnvironmentSnapshot {
+            id: "s1".to_string(),
+            taken_at: "2025-01-01T00:00:00Z".to_string(),
+            environment: json!({
+                "id": "e1",
+                "name": "Staging",
+                "variables": {
+                    "v1": { "id": "v1", "name": "host", "value": "a", "secure": false },
+                    "v2": { "id": "v2", "name": "token", "value": "b", "secure": true }
+                }
+            }),
+        };
+        let line = serde_json::to_string(&snapshot).unwrap();
+        let contents = format!("{line}\n{}", &line[..line.len() / 2]);
+
+        let snapshots = parse_lines(&contents);
+        assert_eq!(snapshots, vec![snapshot]);
+        let summary = snapshots[0].summary();
+        assert_eq!(summary.name, "Staging");
+        assert_eq!(summary.variable_count, 2);
+    }
+
+    #[test]
+    fn rejects_ids_that_are_not_plain_path_segments() {
+        assert_eq!(
+            snapshot_file_name("c-1", "e_2").unwrap(),
+            "env_snapshots/c-1/e_2.jsonl"
+        );
+        assert!(snapshot_file_name("../c", "e").is_err());
+        assert!(snapshot_file_name("c", "").is_err());
+        assert!(snapshot_file_name("c", "a/b").is_err());
+    }
+}
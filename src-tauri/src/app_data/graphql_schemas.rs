@@ -0,0 +1,72 @@
+//! Introspected GraphQL schemas, one file per endpoint, so the editor can complete and
+//! validate queries without introspecting again on every start.
+
+use super::loader::app_data_file_path;
+use crate::errors::AppError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fs;
+use tauri::AppHandle;
+
+const SCHEMA_DIR: &str = "graphql_schemas";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CachedSchema {
+    /// Endpoint the schema was introspected from
+    pub url: String,
+    /// Time of the introspection, ISO 8601
+    pub fetched_at: String,
+    /// The `__schema` object of the introspection result
+    pub schema: Value,
+}
+
+/// URLs can hold anything, so files are named after their SHA-256
+fn schema_file_name(url: &str) -> String {
+    format!("{SCHEMA_DIR}/{}.json", hex::encode(Sha256::digest(url)))
+}
+
+/// Replaces the cached schema of `url`.
+pub fn save_schema(app: &AppHandle, url: &str, schema: Value) -> Result<CachedSchema, AppError> {
+    let cached = CachedSchema {
+        url: url.to_string(),
+        fetched_at: chrono::Utc::now().to_rfc3339(),
+        schema,
+    };
+    let path = app_data_file_path(app, &schema_file_name(url))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_vec(&cached)?)?;
+    Ok(cached)
+}
+
+/// The cached schema of `url`, if it was introspected before.
+pub fn load_schema(app: &AppHandle, url: &str) -> Result<Option<CachedSchema>, AppError> {
+    let path = app_data_file_path(app, &schema_file_name(url))?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    match serde_json::from_slice(&fs::read(path)?) {
+        Ok(cached) => Ok(Some(cached)),
+        Err(e) => {
+            // A cache, so a damaged file is as good as none
+            log::warn!("Ignoring unreadable GraphQL schema of {url}: {e}");
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::schema_file_name;
+
+    #[test]
+    fn names_files_after_the_url_digest() {
+        let name = schema_file_name("https://example.com/graphql?x=../../etc");
+        assert!(name.starts_with("graphql_schemas/"));
+        assert_eq!(name.len(), "graphql_schemas/".len() + 64 + ".json".len());
+        assert_ne!(name, schema_file_name("https://example.com/graphql"));
+    }
+}
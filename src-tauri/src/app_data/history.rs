@@ -0,0 +1,351 @@
+//! Sent requests and the responses they got. Response bodies are content-addressed: each is
+//! stored once, zstd-compressed, under its SHA-256, so identical responses across runs (as when
+//! polling) share one blob and history grows with distinct bodies rather than sends.
+
+use super::loader::app_data_file_path;
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::spool;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tempfile::Builder as TempFileBuilder;
+
+pub(super) const ENTRIES_FILE: &str = "history/entries.jsonl";
+const BLOB_DIR: &str = "history/blobs";
+
+/// Entries kept; older ones are dropped along with the bodies no other entry shares
+const MAX_ENTRIES: usize = 1000;
+
+/// Bodies are mostly text, so even the fastest level shrinks them several times over
+const ZSTD_LEVEL: i32 = 1;
+
+/// Serializes writers so concurrent sends can't interleave lines or collect a blob in use.
+static HISTORY_LOCK: Mutex<()> = Mutex::new(());
+
+/// A send to record, as the frontend has it
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryRecord {
+    /// ID of the saved request that was sent, if any
+    pub request_id: Option<String>,
+    pub collection_id: Option<String>,
+    pub method: String,
+    pub url: String,
+    pub status: u16,
+    pub status_text: String,
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+    /// Response duration in milliseconds
+    pub duration: u64,
+    /// The body, when the response kept it in memory
+    #[serde(default)]
+    pub body: Vec<u8>,
+    /// The file the body was spooled to when it was too large to keep in memory
+    pub file_path: Option<String>,
+}
+
+/// One line of the history file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub id: String,
+    /// Time the response was recorded, ISO 8601
+    pub recorded_at: String,
+    pub request_id: Option<String>,
+    pub collection_id: Option<String>,
+    pub method: String,
+    pub url: String,
+    pub status: u16,
+    pub status_text: String,
+    pub headers: Vec<(String, String)>,
+    pub duration: u64,
+    /// SHA-256 of the body, naming the blob it's stored in; none for an empty body
+    pub body_digest: Option<String>,
+    pub body_size: u64,
+}
+
+fn io_error(e: io::Error) -> AppError {
+    AppError::new(ErrorKind::IoError, format!("History error: {e}"))
+}
+
+/// Digests become file names, so only SHA-256 hex is accepted
+fn blob_path(blob_dir: &Path, digest: &str) -> Result<PathBuf, AppError> {
+    let valid = digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit());
+    if valid {
+        Ok(blob_dir.join(format!("{}.zst", digest.to_ascii_lowercase())))
+    } else {
+        Err(AppError::new(
+            ErrorKind::InvalidPath,
+            format!("Invalid body digest '{digest}'"),
+        ))
+    }
+}
+
+/// Stores a body in `blob_dir` unless a blob with the same content is already there. Returns
+/// its digest and size, or `None` for an empty body.
+fn store_body(blob_dir: &Path, mut body: impl Read) -> Result<Option<(String, u64)>, AppError> {
+    fs::create_dir_all(blob_dir).map_err(io_error)?;
+    // Written under a temp name so an interrupted write never passes for a stored body
+    let file = TempFileBuilder::new()
+        .prefix("knurl-")
+        .tempfile_in(blob_dir)
+        .map_err(io_error)?;
+    let mut encoder = zstd::stream::write::Encoder::new(file, ZSTD_LEVEL).map_err(io_error)?;
+    let mut hasher = Sha256::new();
+    let mut size = 0u64;
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = body.read(&mut buf).map_err(io_error)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        encoder.write_all(&buf[..n]).map_err(io_error)?;
+        size += n as u64;
+    }
+    if size == 0 {
+        return Ok(None);
+    }
+    let digest = hex::encode(hasher.finalize());
+    let path = blob_path(blob_dir, &digest)?;
+    let file = encoder.finish().map_err(io_error)?;
+    if !path.exists() {
+        file.persist(&path).map_err(|e| io_error(e.error))?;
+    }
+    Ok(Some((digest, size)))
+}
+
+/// Deletes the blobs no entry refers to, and temp files left by interrupted writes.
+fn collect_garbage(blob_dir: &Path, entries: &[HistoryEntry]) -> Result<(), AppError> {
+    if !blob_dir.exists() {
+        return Ok(());
+    }
+    let referenced: HashSet<String> = entries
+        .iter()
+        .filter_map(|entry| entry.body_digest.as_deref())
+        .map(|digest| format!("{digest}.zst"))
+        .collect();
+    for file in fs::read_dir(blob_dir).map_err(io_error)? {
+        let file = file.map_err(io_error)?;
+        if !referenced.contains(&*file.file_name().to_string_lossy()) {
+            fs::remove_file(file.path()).map_err(io_error)?;
+        }
+    }
+    Ok(())
+}
+
+/// Parses history lines, skipping any that are incomplete or corrupt
+fn parse_lines(contents: &str) -> Vec<HistoryEntry> {
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                log::warn!("Skipping unreadable history entry: {e}");
+                None
+            }
+        })
+        .collect()
+}
+
+fn read_entries(path: &Path) -> Result<Vec<HistoryEntry>, AppError> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(parse_lines(&fs::read_to_string(path).map_err(io_error)?))
+}
+
+fn to_line(entry: &HistoryEntry) -> Result<String, AppError> {
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    Ok(line)
+}
+
+/// Appends `entry`, or when that takes history past `max_entries`, rewrites it without the
+/// oldest entries and drops the bodies only they used.
+fn append_entry(
+    entries_path: &Path,
+    blob_dir: &Path,
+    entry: &HistoryEntry,
+    max_entries: usize,
+) -> Result<(), AppError> {
+    let mut entries = read_entries(entries_path)?;
+    if entries.len() < max_entries {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(entries_path)
+            .map_err(io_error)?;
+        file.write_all(to_line(entry)?.as_bytes())
+            .map_err(io_error)?;
+        return file.sync_data().map_err(io_error);
+    }
+
+    entries.push(entry.clone());
+    entries.drain(..entries.len() - max_entries);
+    let parent = entries_path.parent().unwrap_or(Path::new("."));
+    let mut file = TempFileBuilder::new()
+        .prefix("knurl-")
+        .tempfile_in(parent)
+        .map_err(io_error)?;
+    for entry in &entries {
+        file.write_all(to_line(entry)?.as_bytes())
+            .map_err(io_error)?;
+    }
+    file.as_file().sync_data().map_err(io_error)?;
+    file.persist(entries_path).map_err(|e| io_error(e.error))?;
+    collect_garbage(blob_dir, &entries)
+}
+
+/// Records a send, storing its body unless an identical one is already stored.
+pub fn record_history_entry(
+    app: &AppHandle,
+    record: HistoryRecord,
+) -> Result<HistoryEntry, AppError> {
+    let entries_path = app_data_file_path(app, ENTRIES_FILE)?;
+    let blob_dir = app_data_file_path(app, BLOB_DIR)?;
+
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    let stored = match &record.file_path {
+        Some(path) => store_body(&blob_dir, spool::open(Path::new(path))?)?,
+        None => store_body(&blob_dir, record.body.as_slice())?,
+    };
+    let (body_digest, body_size) = match stored {
+        Some((digest, size)) => (Some(digest), size),
+        None => (None, 0),
+    };
+    let entry = HistoryEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        recorded_at: chrono::Utc::now().to_rfc3339(),
+        request_id: record.request_id,
+        collection_id: record.collection_id,
+        method: record.method,
+        url: record.url,
+        status: record.status,
+        status_text: record.status_text,
+        headers: record.headers,
+        duration: record.duration,
+        body_digest,
+        body_size,
+    };
+    append_entry(&entries_path, &blob_dir, &entry, MAX_ENTRIES)?;
+    Ok(entry)
+}
+
+/// History entries, newest first, optionally only those of one saved request.
+pub fn list_history(
+    app: &AppHandle,
+    request_id: Option<&str>,
+    limit: Option<usize>,
+) -> Result<Vec<HistoryEntry>, AppError> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    Ok(read_entries(&app_data_file_path(app, ENTRIES_FILE)?)?
+        .into_iter()
+        .rev()
+        .filter(|entry| request_id.is_none() || entry.request_id.as_deref() == request_id)
+        .take(limit.unwrap_or(usize::MAX))
+        .collect())
+}
+
+/// The body stored under `digest`.
+pub fn read_history_body(app: &AppHandle, digest: &str) -> Result<Vec<u8>, AppError> {
+    let path = blob_path(&app_data_file_path(app, BLOB_DIR)?, digest)?;
+    let file = fs::File::open(&path).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => AppError::new(
+            ErrorKind::FileNotFound,
+            format!("No history body with digest '{digest}'"),
+        ),
+        _ => io_error(e),
+    })?;
+    zstd::stream::decode_all(file).map_err(io_error)
+}
+
+/// Deletes all history and stored bodies.
+pub fn clear_history(app: &AppHandle) -> Result<(), AppError> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    let entries_path = app_data_file_path(app, ENTRIES_FILE)?;
+    if entries_path.exists() {
+        fs::remove_file(entries_path).map_err(io_error)?;
+    }
+    collect_garbage(&app_data_file_path(app, BLOB_DIR)?, &[])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HistoryEntry, append_entry, blob_path, read_entries, store_body};
+    use std::fs;
+
+    fn entry(id: &str, body_digest: Option<String>) -> HistoryEntry {
+        HistoryEntry {
+            id: id.to_string(),
+            recorded_at: "2025-01-01T00:00:00Z".to_string(),
+            request_id: Some("r1".to_string()),
+            collection_id: None,
+            method: "GET".to_string(),
+            url: "https://example.com/status".to_string(),
+            status: 200,
+            status_text: "OK".to_string(),
+            headers: Vec::new(),
+            duration: 12,
+            body_digest,
+            body_size: 0,
+        }
+    }
+
+    fn blob_count(dir: &std::path::Path) -> usize {
+        fs::read_dir(dir).unwrap().count()
+    }
+
+    #[test]
+    fn identical_bodies_share_one_blob() {
+        let dir = tempfile::tempdir().unwrap();
+        let blobs = dir.path().join("blobs");
+        let body = br#"{"state":"pending"}"#.repeat(100);
+
+        let (first, size) = store_body(&blobs, body.as_slice()).unwrap().unwrap();
+        let (second, _) = store_body(&blobs, body.as_slice()).unwrap().unwrap();
+        assert_eq!(first, second);
+        assert_eq!(size, body.len() as u64);
+        assert_eq!(blob_count(&blobs), 1);
+
+        let stored = fs::File::open(blob_path(&blobs, &first).unwrap()).unwrap();
+        assert_eq!(zstd::stream::decode_all(stored).unwrap(), body);
+
+        store_body(&blobs, &b"done"[..]).unwrap().unwrap();
+        assert_eq!(blob_count(&blobs), 2);
+        // Empty bodies store nothing
+        assert!(store_body(&blobs, &b""[..]).unwrap().is_none());
+        assert_eq!(blob_count(&blobs), 2);
+
+        assert!(blob_path(&blobs, "../../etc/passwd").is_err());
+    }
+
+    #[test]
+    fn pruning_drops_the_oldest_entries_and_their_unshared_bodies() {
+        let dir = tempfile::tempdir().unwrap();
+        let blobs = dir.path().join("blobs");
+        let entries_path = dir.path().join("entries.jsonl");
+        let shared = store_body(&blobs, &b"same"[..]).unwrap().map(|(d, _)| d);
+        let only_first = store_body(&blobs, &b"first"[..]).unwrap().map(|(d, _)| d);
+
+        append_entry(&entries_path, &blobs, &entry("1", only_first), 2).unwrap();
+        append_entry(&entries_path, &blobs, &entry("2", shared.clone()), 2).unwrap();
+        assert_eq!(blob_count(&blobs), 2);
+        append_entry(&entries_path, &blobs, &entry("3", shared.clone()), 2).unwrap();
+
+        let ids: Vec<String> = read_entries(&entries_path)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.id)
+            .collect();
+        assert_eq!(ids, ["2", "3"]);
+        assert_eq!(blob_count(&blobs), 1);
+        assert!(blob_path(&blobs, &shared.unwrap()).unwrap().exists());
+    }
+}
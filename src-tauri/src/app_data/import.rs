@@ -0,0 +1,254 @@
+//! Converts collections exported by other tools into knurl's native export format, the
+//! document the import sheet previews and imports. Large exports are parsed here rather than
+//! in the webview. `.http` files also convert back, so they can round-trip through git.
+
+mod http_file;
+mod insomnia;
+mod postman;
+
+pub use http_file::{http_file_to_native, native_to_http_file};
+pub use insomnia::insomnia_to_native;
+pub use postman::postman_to_native;
+
+use serde_json::{Map, Value, json};
+
+/// ID of the folder every collection is rooted at
+const ROOT_FOLDER_ID: &str = "root";
+
+/// Methods knurl can send; others are imported as `GET`
+const HTTP_METHODS: [&str; 16] = [
+    "GET",
+    "POST",
+    "PUT",
+    "PATCH",
+    "DELETE",
+    "HEAD",
+    "OPTIONS",
+    "TRACE",
+    "PROPFIND",
+    "PROPPATCH",
+    "MKCOL",
+    "COPY",
+    "MOVE",
+    "LOCK",
+    "UNLOCK",
+    "REPORT",
+];
+
+fn new_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+fn http_method(method: Option<&str>) -> &'static str {
+    let method = method.unwrap_or_default().to_ascii_uppercase();
+    HTTP_METHODS
+        .into_iter()
+        .find(|known| *known == method)
+        .unwrap_or("GET")
+}
+
+/// A string, number or boolean as text; anything else is empty
+fn text(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Number(n)) => n.to_string(),
+        Some(Value::Bool(b)) => b.to_string(),
+        _ => String::new(),
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit(['/', '\\'])
+        .next()
+        .filter(|name| !name.is_empty())
+        .unwrap_or(path)
+}
+
+/// The body language a content type implies, guessing JSON for an untyped `{` or `[` body
+fn text_language(content_type: &str, content: &str) -> &'static str {
+    let content_type = content_type.to_ascii_lowercase();
+    if content_type.contains("graphql") {
+        "graphql"
+    } else if content_type.contains("json") {
+        "json"
+    } else if content_type.contains("xml") {
+        "xml"
+    } else if content_type.contains("html") {
+        "html"
+    } else if content_type.contains("yaml") {
+        "yaml"
+    } else if content_type.contains("javascript") {
+        "javascript"
+    } else if content_type.contains("css") {
+        "css"
+    } else if content_type.is_empty() && content.trim_start().starts_with(['{', '[']) {
+        "json"
+    } else {
+        "text"
+    }
+}
+
+/// A GraphQL body. knurl edits a query and its variables as one text, so the variables follow
+/// the query under a `# Variables` comment.
+fn graphql_body(query: String, variables: Option<&Value>) -> Value {
+    let variables = match variables {
+        Some(Value::String(variables)) => variables.clone(),
+        Some(variables @ Value::Object(_)) => {
+            serde_json::to_string_pretty(variables).unwrap_or_default()
+        }
+        _ => String::new(),
+    };
+    let content = if variables.is_empty() {
+        query
+    } else {
+        format!("{query}\n\n# Variables\n{variables}")
+    };
+    json!({"type": "text", "language": "graphql", "content": content})
+}
+
+/// Header names whose values are kept as secure
+fn is_sensitive_header(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name.contains("authorization") || name.contains("token") || name.contains("secret")
+}
+
+/// Accumulates the folders, requests and environments of a converted collection
+struct NativeCollectionBuilder {
+    collection_id: String,
+    folders: Map<String, Value>,
+    requests: Map<String, Value>,
+    environments: Map<String, Value>,
+}
+
+impl NativeCollectionBuilder {
+    fn new() -> Self {
+        let mut folders = Map::new();
+        folders.insert(
+            ROOT_FOLDER_ID.to_string(),
+            json!({
+                "id": ROOT_FOLDER_ID,
+                "name": "Root",
+                "parentId": null,
+                "order": 0,
+                "childFolderIds": [],
+                "requestIds": [],
+            }),
+        );
+        Self {
+            collection_id: new_id(),
+            folders,
+            requests: Map::new(),
+            environments: Map::new(),
+        }
+    }
+
+    /// Adds a folder under `parent_id`, returning its ID
+    fn add_folder(&mut self, name: Option<&str>, parent_id: &str) -> String {
+        let id = new_id();
+        let name = name.map(str::trim).filter(|name| !name.is_empty());
+        let parent = &mut self.folders[parent_id];
+        let siblings = parent["childFolderIds"].as_array_mut().expect("folder");
+        let order = siblings.len();
+        siblings.push(json!(id));
+        self.folders.insert(
+            id.clone(),
+            json!({
+                "id": id,
+                "name": name.unwrap_or("Folder"),
+                "parentId": parent_id,
+                "order": order,
+                "childFolderIds": [],
+                "requestIds": [],
+            }),
+        );
+        id
+    }
+
+    /// Adds a request to `folder_id`. `fields` holds what the source defines: name, method,
+    /// url, params, headers, body, authentication and tests.
+    fn add_request(&mut self, fields: Map<String, Value>, folder_id: &str) {
+        let id = new_id();
+        let request_ids = self.folders[folder_id]["requestIds"]
+            .as_array_mut()
+            .expect("folder");
+        let order = request_ids.len();
+        request_ids.push(json!(id));
+        let mut request = json!({
+            "id": id,
+            "order": order,
+            "collectionId": self.collection_id,
+            "folderId": folder_id,
+            "autoSave": false,
+            "pathParams": {},
+            "queryParams": {},
+            "headers": {},
+            "cookieParams": {},
+            "body": {"type": "none"},
+            "authentication": {"type": "none"},
+            "patch": {},
+            "updated": 0,
+        });
+        request.as_object_mut().expect("object").extend(fields);
+        self.requests.insert(id, request);
+    }
+
+    /// Adds an environment of `(name, value, secure)` variables
+    fn add_environment(
+        &mut self,
+        name: String,
+        description: &str,
+        variables: impl IntoIterator<Item = (String, String, bool)>,
+    ) {
+        let variables: Map<String, Value> = variables
+            .into_iter()
+            .map(|(name, value, secure)| {
+                let id = new_id();
+                let variable = json!({"id": id, "name": name, "value": value, "secure": secure});
+                (id, variable)
+            })
+            .collect();
+        let id = new_id();
+        self.environments.insert(
+            id.clone(),
+            json!({"id": id, "name": name, "description": description, "variables": variables}),
+        );
+    }
+
+    fn build(self, name: &str, description: &str, authentication: Value) -> Value {
+        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        json!({
+            "format": "native",
+            "version": "1.0.0",
+            "exportedAt": now,
+            "collection": {
+                "id": self.collection_id,
+                "name": name,
+                "description": description,
+                "updated": now,
+                "requests": self.requests,
+                "folders": self.folders,
+                "environments": self.environments,
+                "authentication": authentication,
+            },
+        })
+    }
+}
+
+/// Name/value rows keyed by new IDs, as query, path and header params are stored
+fn params<'a>(rows: impl IntoIterator<Item = (&'a str, String, bool, bool)>) -> Value {
+    let rows: Map<String, Value> = rows
+        .into_iter()
+        .map(|(name, value, enabled, secure)| {
+            let id = new_id();
+            let row = json!({
+                "id": id,
+                "name": name,
+                "value": value,
+                "enabled": enabled,
+                "secure": secure,
+            });
+            (id, row)
+        })
+        .collect();
+    Value::Object(rows)
+}
@@ -0,0 +1,957 @@
+//! VS Code REST Client / JetBrains HTTP Client `.http` (or `.rest`) files, in both directions so
+//! requests can live in git next to the code that serves them. `###` separates requests and
+//! names the one that follows, `@name = value` lines are file variables, and `< ./path` sends a
+//! file. Folders have no equivalent, so exports list the requests folder by folder.
+
+use super::{
+    NativeCollectionBuilder, ROOT_FOLDER_ID, file_name, http_method, is_sensitive_header, params,
+    text_language,
+};
+use crate::errors::{AppError, ErrorKind};
+use serde_json::{Map, Value, json};
+
+/// Boundary of multipart bodies written on export
+const MULTIPART_BOUNDARY: &str = "KnurlFormBoundary";
+
+/// One request of a `.http` file, as written
+#[derive(Debug, Default)]
+struct HttpRequest {
+    /// From `# @name`, or else the text after the `###` that opened the request
+    name: Option<String>,
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Vec<String>,
+}
+
+impl HttpRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Where the parser is within the current request
+enum Section {
+    /// Comments and variables before the request line
+    Preamble,
+    /// Query continuation lines and headers after the request line
+    Headers,
+    Body,
+    /// A response handler or output redirect, which knurl doesn't run
+    Handler,
+}
+
+/// A `# @key value` or `// @key value` directive in a comment
+fn directive<'a>(comment: &'a str, key: &str) -> Option<&'a str> {
+    let rest = comment.trim_start().strip_prefix('@')?.strip_prefix(key)?;
+    (rest.is_empty() || rest.starts_with(char::is_whitespace)).then(|| rest.trim())
+}
+
+fn comment(line: &str) -> Option<&str> {
+    line.strip_prefix('#').or_else(|| line.strip_prefix("//"))
+}
+
+/// A `@name = value` file variable
+fn file_variable(line: &str) -> Option<(String, String)> {
+    let (name, value) = line.strip_prefix('@')?.split_once('=')?;
+    let name = name.trim();
+    (!name.is_empty() && !name.contains(char::is_whitespace))
+        .then(|| (name.to_string(), value.trim().to_string()))
+}
+
+/// Splits `METHOD URL HTTP/1.1`, or a bare URL, which is a `GET`
+fn request_line(line: &str) -> (String, String) {
+    let mut tokens: Vec<&str> = line.split_whitespace().collect();
+    if tokens.len() > 1 && tokens.last().is_some_and(|t| t.starts_with("HTTP/")) {
+        tokens.pop();
+    }
+    match tokens.as_slice() {
+        [method, rest @ ..]
+            if !rest.is_empty() && method.chars().all(|c| c.is_ascii_alphabetic()) =>
+        {
+            (method.to_ascii_uppercase(), rest.join(" "))
+        }
+        _ => ("GET".to_string(), tokens.join(" ")),
+    }
+}
+
+/// Splits a file into its requests and file variables
+fn parse(source: &str) -> (Vec<HttpRequest>, Vec<(String, String)>) {
+    let mut requests = Vec::new();
+    let mut variables = Vec::new();
+    let mut current = HttpRequest::default();
+    let mut section = Section::Preamble;
+    let mut title: Option<String> = None;
+
+    let mut finish = |request: HttpRequest, title: Option<String>| {
+        if !request.url.is_empty() {
+            requests.push(HttpRequest {
+                name: request.name.or(title),
+                ..request
+            });
+        }
+    };
+
+    for line in source.lines() {
+        let trimmed = line.trim();
+        if let Some(rest) = trimmed.strip_prefix("###") {
+            finish(std::mem::take(&mut current), title.take());
+            let rest = rest.trim();
+            title = (!rest.is_empty()).then(|| rest.to_string());
+            section = Section::Preamble;
+            continue;
+        }
+        match section {
+            Section::Preamble => {
+                if trimmed.is_empty() {
+                    continue;
+                }
+                if let Some(comment) = comment(trimmed) {
+                    if let Some(name) = directive(comment, "name").filter(|n| !n.is_empty()) {
+                        current.name = Some(name.to_string());
+                    }
+                    continue;
+                }
+                if let Some(variable) = file_variable(trimmed) {
+                    variables.push(variable);
+                    continue;
+                }
+                let (method, url) = request_line(trimmed);
+                current.method = method;
+                current.url = url;
+                section = Section::Headers;
+            }
+            Section::Headers => {
+                if trimmed.is_empty() {
+                    section = Section::Body;
+                } else if trimmed.starts_with('?') || trimmed.starts_with('&') {
+                    current.url.push_str(trimmed);
+                } else if comment(trimmed).is_some() {
+                    continue;
+                } else if let Some((name, value)) = trimmed.split_once(':') {
+                    current
+                        .headers
+                        .push((name.trim().to_string(), value.trim().to_string()));
+                }
+            }
+            Section::Body => {
+                if trimmed.starts_with('>') || trimmed.starts_with("<>") {
+                    section = Section::Handler;
+                } else {
+                    current.body.push(line.to_string());
+                }
+            }
+            Section::Handler => {}
+        }
+    }
+    finish(current, title);
+
+    for request in &mut requests {
+        while request
+            .body
+            .last()
+            .is_some_and(|line| line.trim().is_empty())
+        {
+            request.body.pop();
+        }
+    }
+    (requests, variables)
+}
+
+/// The path of a `< ./path` file reference
+fn file_reference(line: &str) -> Option<&str> {
+    let path = line.trim().strip_prefix('<')?.trim();
+    (!path.is_empty()).then_some(path)
+}
+
+fn form_field(key: &str, value: &str) -> (String, Value) {
+    let id = super::new_id();
+    let field = json!({
+        "id": id,
+        "key": key,
+        "value": value,
+        "enabled": true,
+        "secure": false,
+        "kind": "text",
+    });
+    (id, field)
+}
+
+/// The value of `key="value"` in a `Content-Disposition` or `Content-Type` header
+fn header_param<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    header.split(';').find_map(|part| {
+        let (name, value) = part.trim().split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case(key)
+            .then(|| value.trim().trim_matches('"'))
+    })
+}
+
+/// The parts of a multipart body, with `< path` parts as file fields
+fn multipart_fields(body: &[String], boundary: &str) -> Map<String, Value> {
+    let delimiter = format!("--{boundary}");
+    let mut fields = Map::new();
+    let mut parts: Vec<Vec<&str>> = Vec::new();
+    for line in body {
+        let line = line.trim_end();
+        if line == delimiter || line == format!("{delimiter}--") {
+            parts.push(Vec::new());
+        } else if let Some(part) = parts.last_mut() {
+            part.push(line);
+        }
+    }
+    for part in parts {
+        let Some(blank) = part.iter().position(|line| line.trim().is_empty()) else {
+            continue;
+        };
+        let (headers, content) = part.split_at(blank);
+        let header = |name: &str| {
+            headers.iter().find_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                key.trim().eq_ignore_ascii_case(name).then(|| value.trim())
+            })
+        };
+        let Some(name) = header("Content-Disposition").and_then(|d| header_param(d, "name")) else {
+            continue;
+        };
+        let content = content[1..].join("\n");
+        let (id, mut field) = form_field(name, &content);
+        if let Some(path) = file_reference(&content) {
+            field["kind"] = json!("file");
+            field["value"] = json!("");
+            field["filePath"] = json!(path);
+            let file_name = header("Content-Disposition")
+                .and_then(|d| header_param(d, "filename"))
+                .unwrap_or_else(|| file_name(path));
+            field["fileName"] = json!(file_name);
+            if let Some(content_type) = header("Content-Type") {
+                field["contentType"] = json!(content_type);
+            }
+        }
+        fields.insert(id, field);
+    }
+    fields
+}
+
+fn convert_body(request: &HttpRequest) -> Value {
+    if request.body.is_empty() {
+        return json!({"type": "none"});
+    }
+    let content_type = request.header("Content-Type").unwrap_or_default();
+    let binary_path = match request.body.as_slice() {
+        [line] => file_reference(line),
+        _ => None,
+    };
+    if let Some(path) = binary_path {
+        let mut body = json!({
+            "type": "binary",
+            "binaryPath": path,
+            "binaryFileName": file_name(path),
+        });
+        if !content_type.is_empty() {
+            body["binaryContentType"] = json!(content_type);
+        }
+        return body;
+    }
+    let lower = content_type.to_ascii_lowercase();
+    if lower.starts_with("application/x-www-form-urlencoded") {
+        let content: String = request.body.iter().map(|line| line.trim()).collect();
+        let fields: Map<String, Value> = content
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                form_field(key, value)
+            })
+            .collect();
+        return json!({"type": "form", "encoding": "url", "formData": fields});
+    }
+    let boundary = lower
+        .starts_with("multipart/form-data")
+        .then(|| header_param(content_type, "boundary"))
+        .flatten();
+    if let Some(boundary) = boundary {
+        let fields = multipart_fields(&request.body, boundary);
+        return json!({"type": "form", "encoding": "multipart", "formData": fields});
+    }
+    let content = request.body.join("\n");
+    let language = if request
+        .header("X-Request-Type")
+        .is_some_and(|kind| kind.eq_ignore_ascii_case("graphql"))
+    {
+        "graphql"
+    } else {
+        text_language(content_type, &content)
+    };
+    json!({"type": "text", "language": language, "content": content})
+}
+
+/// The query string of `url` as params
+fn query_params(url: &str) -> Value {
+    let query = url
+        .split_once('?')
+        .map(|(_, query)| query.split('#').next().unwrap_or_default())
+        .unwrap_or_default();
+    params(
+        query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (name, value.to_string(), true, false)
+            }),
+    )
+}
+
+/// Converts the text of a `.http` file into a native collection export
+pub fn http_file_to_native(source: &str) -> Result<Value, AppError> {
+    let (requests, variables) = parse(source);
+    if requests.is_empty() {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            "No requests found in the .http file",
+        ));
+    }
+    let mut builder = NativeCollectionBuilder::new();
+    for request in &requests {
+        let method = http_method(Some(&request.method));
+        // Multipart bodies are re-encoded when sent, with a boundary of their own
+        let multipart = request
+            .header("Content-Type")
+            .is_some_and(|value| value.to_ascii_lowercase().starts_with("multipart/"));
+        let headers = params(
+            request
+                .headers
+                .iter()
+                .filter(|(name, _)| !(multipart && name.eq_ignore_ascii_case("Content-Type")))
+                .map(|(name, value)| {
+                    (
+                        name.as_str(),
+                        value.clone(),
+                        true,
+                        is_sensitive_header(name),
+                    )
+                }),
+        );
+        let mut fields = Map::new();
+        fields.insert(
+            "name".to_string(),
+            json!(
+                request
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format!("{method} {}", request.url))
+            ),
+        );
+        fields.insert("method".to_string(), json!(method));
+        fields.insert("url".to_string(), json!(request.url));
+        fields.insert("queryParams".to_string(), query_params(&request.url));
+        fields.insert("headers".to_string(), headers);
+        fields.insert("body".to_string(), convert_body(request));
+        builder.add_request(fields, ROOT_FOLDER_ID);
+    }
+    if !variables.is_empty() {
+        builder.add_environment(
+            "File Variables".to_string(),
+            "Imported from .http file variables.",
+            variables.into_iter().map(|(name, value)| {
+                let secure = is_sensitive_header(&name);
+                (name, value, secure)
+            }),
+        );
+    }
+    Ok(builder.build("HTTP File", "", json!({"type": "none"})))
+}
+
+/// The entries of a record, or of the array the export sheet narrows it to
+fn entries(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Object(map) => map.values().collect(),
+        Value::Array(items) => items.iter().collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn str_at<'a>(value: &'a Value, pointer: &str) -> &'a str {
+    value
+        .pointer(pointer)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+}
+
+/// A setting of the `kind` auth block
+fn auth_param<'a>(auth: &'a Value, kind: &str, key: &str) -> &'a str {
+    auth.get(kind)
+        .and_then(|settings| settings.get(key))
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+}
+
+/// The enabled `(name, value)` rows of a params record
+fn enabled_rows(value: &Value) -> Vec<(&str, &str)> {
+    entries(value)
+        .into_iter()
+        .filter(|row| row["enabled"].as_bool().unwrap_or(true))
+        .map(|row| (str_at(row, "/name"), str_at(row, "/value")))
+        .filter(|(name, _)| !name.is_empty())
+        .collect()
+}
+
+/// A request being written out
+struct HttpExport {
+    url: String,
+    headers: Vec<(String, String)>,
+    cookies: Vec<String>,
+    notes: Vec<String>,
+}
+
+impl HttpExport {
+    fn has_header(&self, name: &str) -> bool {
+        self.headers
+            .iter()
+            .any(|(key, _)| key.eq_ignore_ascii_case(name))
+    }
+
+    fn add_query(&mut self, name: &str, value: &str) {
+        let separator = if self.url.contains('?') { '&' } else { '?' };
+        self.url.push_str(&format!("{separator}{name}={value}"));
+    }
+
+    /// Puts a credential where its auth placement says
+    fn place(&mut self, placement: &Value, default_header: &str, value: String) {
+        let name = str_at(placement, "/name");
+        match str_at(placement, "/type") {
+            "query" => self.add_query(name, &value),
+            "cookie" => self.cookies.push(format!("{name}={value}")),
+            "body" => self
+                .notes
+                .push("Auth sent in the body isn't exported".to_string()),
+            _ => {
+                let name = if name.is_empty() {
+                    default_header
+                } else {
+                    name
+                };
+                self.headers.push((name.to_string(), value));
+            }
+        }
+    }
+
+    fn add_auth(&mut self, auth: &Value) {
+        let kind = str_at(auth, "/type");
+        let param = |key: &str| auth_param(auth, kind, key);
+        match kind {
+            "" | "none" | "inherit" => {}
+            "basic" | "digest" => {
+                let scheme = if kind == "basic" { "Basic" } else { "Digest" };
+                let value = format!("{scheme} {} {}", param("username"), param("password"));
+                self.headers.push(("Authorization".to_string(), value));
+            }
+            "bearer" => {
+                let scheme = Some(param("scheme"))
+                    .filter(|scheme| !scheme.is_empty())
+                    .unwrap_or("Bearer");
+                let token = param("token").to_string();
+                let placement = &auth["bearer"]["placement"];
+                let value = match str_at(placement, "/type") {
+                    "" | "header" => format!("{scheme} {token}"),
+                    _ => token,
+                };
+                self.place(placement, "Authorization", value);
+            }
+            "apiKey" => {
+                let key = param("key");
+                let placement = auth["apiKey"]["placement"].clone();
+                let placement = if str_at(&placement, "/name").is_empty() {
+                    json!({"type": str_at(&placement, "/type"), "name": key})
+                } else {
+                    placement
+                };
+                self.place(&placement, key, param("value").to_string());
+            }
+            other => self
+                .notes
+                .push(format!("{other} authentication isn't exported")),
+        }
+    }
+}
+
+fn default_content_type(language: &str) -> &'static str {
+    match language {
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "html" => "text/html",
+        "graphql" => "application/graphql",
+        "yaml" => "application/yaml",
+        "javascript" => "application/javascript",
+        "css" => "text/css",
+        _ => "text/plain",
+    }
+}
+
+/// The body's lines, adding the `Content-Type` it needs unless the request sets one
+fn export_body(body: &Value, export: &mut HttpExport) -> Vec<String> {
+    let mut content_type = None;
+    let lines = match str_at(body, "/type") {
+        "text" => {
+            let content = str_at(body, "/content");
+            content_type = Some(default_content_type(str_at(body, "/language")).to_string());
+            content.lines().map(str::to_string).collect()
+        }
+        "binary" => {
+            let path = str_at(body, "/binaryPath");
+            if path.is_empty() {
+                return Vec::new();
+            }
+            let binary_type = Some(str_at(body, "/binaryContentType"))
+                .filter(|value| !value.is_empty())
+                .unwrap_or("application/octet-stream");
+            content_type = Some(binary_type.to_string());
+            vec![format!("< {path}")]
+        }
+        "form" => {
+            let fields: Vec<&Value> = entries(&body["formData"])
+                .into_iter()
+                .filter(|field| field["enabled"].as_bool().unwrap_or(true))
+                .collect();
+            match str_at(body, "/encoding") {
+                "multipart" => {
+                    content_type = Some(format!(
+                        "multipart/form-data; boundary={MULTIPART_BOUNDARY}"
+                    ));
+                    let mut lines = Vec::new();
+                    for field in fields {
+                        let key = str_at(field, "/key");
+                        lines.push(format!("--{MULTIPART_BOUNDARY}"));
+                        if str_at(field, "/kind") == "file" {
+                            let path = str_at(field, "/filePath");
+                            let name = Some(str_at(field, "/fileName"))
+                                .filter(|name| !name.is_empty())
+                                .unwrap_or_else(|| file_name(path));
+                            lines.push(format!(
+                                "Content-Disposition: form-data; name=\"{key}\"; filename=\"{name}\""
+                            ));
+                            let part_type = str_at(field, "/contentType");
+                            if !part_type.is_empty() {
+                                lines.push(format!("Content-Type: {part_type}"));
+                            }
+                            lines.push(String::new());
+                            lines.push(format!("< {path}"));
+                        } else {
+                            lines.push(format!("Content-Disposition: form-data; name=\"{key}\""));
+                            lines.push(String::new());
+                            lines.push(str_at(field, "/value").to_string());
+                        }
+                    }
+                    lines.push(format!("--{MULTIPART_BOUNDARY}--"));
+                    lines
+                }
+                "plain" => {
+                    content_type = Some("text/plain".to_string());
+                    fields
+                        .into_iter()
+                        .map(|f| format!("{}={}", str_at(f, "/key"), str_at(f, "/value")))
+                        .collect()
+                }
+                _ => {
+                    content_type = Some("application/x-www-form-urlencoded".to_string());
+                    let pairs: Vec<String> = fields
+                        .into_iter()
+                        .map(|f| format!("{}={}", str_at(f, "/key"), str_at(f, "/value")))
+                        .collect();
+                    vec![pairs.join("&")]
+                }
+            }
+        }
+        _ => Vec::new(),
+    };
+    if let Some(content_type) = content_type.filter(|_| !export.has_header("Content-Type")) {
+        export
+            .headers
+            .push(("Content-Type".to_string(), content_type));
+    }
+    lines
+}
+
+fn export_request(request: &Value, collection_auth: &Value, out: &mut String) {
+    let mut export = HttpExport {
+        url: str_at(request, "/url").to_string(),
+        headers: enabled_rows(&request["headers"])
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect(),
+        cookies: enabled_rows(&request["cookieParams"])
+            .into_iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect(),
+        notes: Vec::new(),
+    };
+    let auth = &request["authentication"];
+    if matches!(str_at(auth, "/type"), "" | "inherit") {
+        export.add_auth(collection_auth);
+    } else {
+        export.add_auth(auth);
+    }
+    let body = export_body(&request["body"], &mut export);
+    if !export.cookies.is_empty() {
+        let cookies = export.cookies.join("; ");
+        export.headers.push(("Cookie".to_string(), cookies));
+    }
+
+    let name = str_at(request, "/name");
+    out.push_str(format!("### {name}").trim_end());
+    out.push('\n');
+    for note in &export.notes {
+        out.push_str(&format!("# {note}\n"));
+    }
+    let method = Some(str_at(request, "/method"))
+        .filter(|method| !method.is_empty())
+        .unwrap_or("GET");
+    out.push_str(&format!("{method} {}\n", export.url));
+    for (name, value) in &export.headers {
+        out.push_str(&format!("{name}: {value}\n"));
+    }
+    if !body.is_empty() {
+        out.push('\n');
+        for line in body {
+            out.push_str(&line);
+            out.push('\n');
+        }
+    }
+    out.push('\n');
+}
+
+/// The requests of `collection` in folder order, then any that no folder lists
+fn ordered_requests(collection: &Value) -> Vec<&Value> {
+    let requests = entries(&collection["requests"]);
+    let folders = &collection["folders"];
+    let mut ordered: Vec<&Value> = Vec::new();
+    let mut pending = vec![ROOT_FOLDER_ID.to_string()];
+    while let Some(folder_id) = pending.pop() {
+        let folder = &folders[&folder_id];
+        for request_id in entries(&folder["requestIds"]) {
+            if let Some(request) = requests
+                .iter()
+                .find(|request| request["id"] == *request_id)
+                .filter(|request| !ordered.iter().any(|seen| std::ptr::eq(*seen, **request)))
+            {
+                ordered.push(*request);
+            }
+        }
+        let children = entries(&folder["childFolderIds"]);
+        pending.extend(
+            children
+                .into_iter()
+                .rev()
+                .filter_map(|id| id.as_str().map(str::to_string)),
+        );
+    }
+    for request in requests {
+        if !ordered.iter().any(|seen| std::ptr::eq(*seen, request)) {
+            ordered.push(request);
+        }
+    }
+    ordered
+}
+
+/// Writes a native collection export as a `.http` file. The active environment, or else the
+/// first, becomes file variables.
+pub fn native_to_http_file(export: &Value) -> Result<String, AppError> {
+    let collection = export
+        .get("collection")
+        .filter(|collection| collection.is_object())
+        .ok_or_else(|| AppError::new(ErrorKind::BadRequest, "Not a native collection export"))?;
+
+    let mut out = String::new();
+    let name = str_at(collection, "/name");
+    if !name.is_empty() {
+        out.push_str(&format!("# {name}\n"));
+    }
+    for line in str_at(collection, "/description").lines() {
+        out.push_str(format!("# {line}").trim_end());
+        out.push('\n');
+    }
+    if !out.is_empty() {
+        out.push('\n');
+    }
+
+    let environments = entries(&collection["environments"]);
+    let active_id = &collection["activeEnvironmentId"];
+    let environment = environments
+        .iter()
+        .find(|environment| !active_id.is_null() && environment["id"] == *active_id)
+        .or(environments.first());
+    if let Some(environment) = environment {
+        let variables = entries(&environment["variables"]);
+        for variable in &variables {
+            let name = str_at(variable, "/name");
+            if !name.is_empty() {
+                out.push_str(&format!("@{name} = {}\n", str_at(variable, "/value")));
+            }
+        }
+        if !variables.is_empty() {
+            out.push('\n');
+        }
+    }
+
+    for request in ordered_requests(collection) {
+        export_request(request, &collection["authentication"], &mut out);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{http_file_to_native, native_to_http_file};
+    use serde_json::{Value, json};
+
+    fn requests(doc: &Value) -> Vec<&Value> {
+        let mut requests: Vec<&Value> = doc["collection"]["requests"]
+            .as_object()
+            .unwrap()
+            .values()
+            .collect();
+        requests.sort_by_key(|request| request["order"].as_u64());
+        requests
+    }
+
+    fn header<'a>(request: &'a Value, name: &str) -> Option<&'a str> {
+        request["headers"]
+            .as_object()
+            .unwrap()
+            .values()
+            .find(|row| row["name"] == name)
+            .and_then(|row| row["value"].as_str())
+    }
+
+    const SAMPLE: &str = "\
+@host = https://api.example.com
+@token = abc123
+
+### List users
+GET {{host}}/users
+    ?page=2
+    &limit=10
+Accept: application/json
+
+###
+# @name createUser
+POST {{host}}/users HTTP/1.1
+Content-Type: application/json
+Authorization: Bearer {{token}}
+
+{
+  \"name\": \"Ada\"
+}
+
+> {% client.global.set(\"id\", response.body.id); %}
+
+### Upload
+POST {{host}}/avatar
+Content-Type: image/png
+
+< ./avatar.png
+
+###
+https://example.com/health
+";
+
+    #[test]
+    fn parses_requests_and_file_variables() {
+        let doc = http_file_to_native(SAMPLE).unwrap();
+        let requests = requests(&doc);
+        assert_eq!(requests.len(), 4);
+
+        let list = requests[0];
+        assert_eq!(list["name"], "List users");
+        assert_eq!(list["method"], "GET");
+        assert_eq!(list["url"], "{{host}}/users?page=2&limit=10");
+        let query: Vec<&Value> = list["queryParams"].as_object().unwrap().values().collect();
+        assert_eq!(query.len(), 2);
+        assert_eq!(header(list, "Accept"), Some("application/json"));
+        assert_eq!(list["body"]["type"], "none");
+
+        let create = requests[1];
+        assert_eq!(create["name"], "createUser");
+        assert_eq!(create["method"], "POST");
+        assert_eq!(create["url"], "{{host}}/users");
+        assert_eq!(create["body"]["language"], "json");
+        assert_eq!(create["body"]["content"], "{\n  \"name\": \"Ada\"\n}");
+        let auth = create["headers"]
+            .as_object()
+            .unwrap()
+            .values()
+            .find(|row| row["name"] == "Authorization")
+            .unwrap();
+        assert_eq!(auth["secure"], true);
+
+        let upload = requests[2];
+        assert_eq!(upload["body"]["type"], "binary");
+        assert_eq!(upload["body"]["binaryPath"], "./avatar.png");
+        assert_eq!(upload["body"]["binaryContentType"], "image/png");
+
+        let health = requests[3];
+        assert_eq!(health["method"], "GET");
+        assert_eq!(health["name"], "GET https://example.com/health");
+
+        let environment = doc["collection"]["environments"]
+            .as_object()
+            .unwrap()
+            .values()
+            .next()
+            .unwrap();
+        let variables: Vec<(&str, bool)> = environment["variables"]
+            .as_object()
+            .unwrap()
+            .values()
+            .map(|v| (v["name"].as_str().unwrap(), v["secure"].as_bool().unwrap()))
+            .collect();
+        assert!(variables.contains(&("host", false)));
+        assert!(variables.contains(&("token", true)));
+    }
+
+    #[test]
+    fn parses_form_bodies() {
+        let source = "\
+POST https://example.com/login
+Content-Type: application/x-www-form-urlencoded
+
+user=ada
+&pass=secret
+
+###
+POST https://example.com/upload
+Content-Type: multipart/form-data; boundary=XYZ
+
+--XYZ
+Content-Disposition: form-data; name=\"title\"
+
+Holiday
+--XYZ
+Content-Disposition: form-data; name=\"photo\"; filename=\"beach.jpg\"
+Content-Type: image/jpeg
+
+< ./beach.jpg
+--XYZ--
+";
+        let doc = http_file_to_native(source).unwrap();
+        let requests = requests(&doc);
+
+        let login = &requests[0]["body"];
+        assert_eq!(login["encoding"], "url");
+        let fields: Vec<(&str, &str)> = login["formData"]
+            .as_object()
+            .unwrap()
+            .values()
+            .map(|f| (f["key"].as_str().unwrap(), f["value"].as_str().unwrap()))
+            .collect();
+        assert_eq!(fields.len(), 2);
+        assert!(fields.contains(&("pass", "secret")));
+
+        let upload = requests[1];
+        assert_eq!(header(upload, "Content-Type"), None);
+        assert_eq!(upload["body"]["encoding"], "multipart");
+        let fields = upload["body"]["formData"].as_object().unwrap();
+        let photo = fields.values().find(|f| f["key"] == "photo").unwrap();
+        assert_eq!(photo["kind"], "file");
+        assert_eq!(photo["filePath"], "./beach.jpg");
+        assert_eq!(photo["fileName"], "beach.jpg");
+        assert_eq!(photo["contentType"], "image/jpeg");
+        let title = fields.values().find(|f| f["key"] == "title").unwrap();
+        assert_eq!(title["value"], "Holiday");
+    }
+
+    #[test]
+    fn rejects_files_without_requests() {
+        assert!(http_file_to_native("# nothing here\n@host = x\n").is_err());
+    }
+
+    #[test]
+    fn exports_requests_auth_and_variables() {
+        let export = json!({
+            "format": "native",
+            "collection": {
+                "name": "Sample",
+                "activeEnvironmentId": "e2",
+                "environments": [
+                    {"id": "e1", "variables": {"v": {"name": "host", "value": "http://dev"}}},
+                    {"id": "e2", "variables": {"v": {"name": "host", "value": "http://prod"}}},
+                ],
+                "authentication": {"type": "bearer", "bearer": {"token": "{{token}}"}},
+                "folders": {
+                    "root": {"id": "root", "childFolderIds": ["f"], "requestIds": ["b"]},
+                    "f": {"id": "f", "childFolderIds": [], "requestIds": ["a"]},
+                },
+                "requests": [
+                    {
+                        "id": "a",
+                        "name": "Search",
+                        "method": "GET",
+                        "url": "{{host}}/search",
+                        "headers": {"h": {"name": "Accept", "value": "*/*", "enabled": false}},
+                        "authentication": {
+                            "type": "apiKey",
+                            "apiKey": {"key": "api_key", "value": "k", "placement": {"type": "query"}},
+                        },
+                        "body": {"type": "none"},
+                    },
+                    {
+                        "id": "b",
+                        "name": "Create",
+                        "method": "POST",
+                        "url": "{{host}}/items",
+                        "authentication": {"type": "inherit"},
+                        "body": {"type": "text", "language": "json", "content": "{\"a\": 1}"},
+                    },
+                ],
+            },
+        });
+        let text = native_to_http_file(&export).unwrap();
+        assert_eq!(
+            text,
+            "\
+# Sample
+
+@host = http://prod
+
+### Create
+POST {{host}}/items
+Authorization: Bearer {{token}}
+Content-Type: application/json
+
+{\"a\": 1}
+
+### Search
+GET {{host}}/search?api_key=k
+
+"
+        );
+    }
+
+    #[test]
+    fn round_trips_through_native() {
+        let doc = http_file_to_native(SAMPLE).unwrap();
+        let text = native_to_http_file(&doc).unwrap();
+        let again = http_file_to_native(&text).unwrap();
+        let summary = |doc: &Value| -> Vec<(String, String, String, String)> {
+            requests(doc)
+                .into_iter()
+                .map(|r| {
+                    (
+                        r["name"].as_str().unwrap().to_string(),
+                        r["method"].as_str().unwrap().to_string(),
+                        r["url"].as_str().unwrap().to_string(),
+                        r["body"]["content"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .to_string(),
+                    )
+                })
+                .collect()
+        };
+        assert_eq!(summary(&doc), summary(&again));
+    }
+}
@@ -0,0 +1,765 @@
+//! Insomnia exports: the v4 `resources` format and the v5 `collection.insomnia.rest` format.
+//! Request groups become folders, with their auth copied to the requests that inherit it as for
+//! Postman. Sub-environments become environments holding the base environment's variables
+//! overlaid with their own, and `{{ _.name }}` references become knurl's `{{name}}`.
+
+use super::{
+    NativeCollectionBuilder, ROOT_FOLDER_ID, file_name, graphql_body, http_method,
+    is_sensitive_header, params, text, text_language,
+};
+use crate::errors::{AppError, ErrorKind};
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+
+#[derive(Debug, Deserialize)]
+struct Export {
+    /// `export` in v4
+    #[serde(rename = "_type")]
+    export_type: Option<String>,
+    /// `collection.insomnia.rest/5.0` in v5
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    #[serde(default)]
+    resources: Vec<Resource>,
+    name: Option<String>,
+    #[serde(default)]
+    collection: Vec<Item>,
+    environments: Option<Environment>,
+}
+
+/// A v4 workspace, request group, request or environment
+#[derive(Debug, Deserialize)]
+struct Resource {
+    #[serde(rename = "_id")]
+    id: String,
+    #[serde(rename = "_type")]
+    kind: String,
+    #[serde(rename = "parentId")]
+    parent_id: Option<String>,
+    name: Option<String>,
+    #[serde(rename = "metaSortKey")]
+    sort_key: Option<f64>,
+    /// Variables of an environment
+    data: Option<Map<String, Value>>,
+    /// Variables of a request group
+    environment: Option<Map<String, Value>>,
+    #[serde(flatten)]
+    request: RequestFields,
+}
+
+/// A v5 folder, which has `children`, or request
+#[derive(Debug, Default, Deserialize)]
+struct Item {
+    name: Option<String>,
+    children: Option<Vec<Item>>,
+    environment: Option<Map<String, Value>>,
+    #[serde(flatten)]
+    request: RequestFields,
+}
+
+/// What requests define; folders define only `authentication`
+#[derive(Debug, Default, Clone, Deserialize)]
+struct RequestFields {
+    method: Option<String>,
+    url: Option<String>,
+    body: Option<Body>,
+    #[serde(default)]
+    parameters: Vec<Param>,
+    #[serde(default)]
+    headers: Vec<Param>,
+    authentication: Option<Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Param {
+    name: Option<String>,
+    value: Option<Value>,
+    #[serde(default)]
+    disabled: bool,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    #[serde(rename = "fileName")]
+    file_name: Option<String>,
+}
+
+impl Param {
+    fn value(&self) -> String {
+        template(&text(self.value.as_ref()))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Body {
+    #[serde(rename = "mimeType")]
+    mime_type: Option<String>,
+    text: Option<String>,
+    #[serde(default)]
+    params: Vec<Param>,
+    #[serde(rename = "fileName")]
+    file_name: Option<String>,
+}
+
+/// A base environment and the sub-environments that overlay it, as v5 nests them
+#[derive(Debug, Deserialize)]
+struct Environment {
+    name: Option<String>,
+    #[serde(default)]
+    data: Map<String, Value>,
+    #[serde(default, rename = "subEnvironments")]
+    sub_environments: Vec<Environment>,
+}
+
+/// Rewrites `{{ _.name }}` and `{{ name }}` as `{{name}}`. Template tags such as
+/// `{% uuid %}` have no knurl equivalent and are left as they are.
+fn template(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let reference = rest[start + 2..start + end].trim();
+        let name = reference.strip_prefix("_.").unwrap_or(reference);
+        let is_name = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '$'));
+        if is_name {
+            out.push_str("{{");
+            out.push_str(name);
+            out.push_str("}}");
+        } else {
+            out.push_str(&rest[start..start + end + 2]);
+        }
+        rest = &rest[start + end + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Flattens nested environment data into `a.b` names, as `{{ _.a.b }}` reads them
+fn flatten(data: &Map<String, Value>, prefix: &str, out: &mut Vec<(String, String)>) {
+    for (key, value) in data {
+        let name = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match value {
+            Value::Object(nested) => flatten(nested, &name, out),
+            Value::Array(_) => out.push((name, value.to_string())),
+            other => out.push((name, template(&text(Some(other))))),
+        }
+    }
+}
+
+/// Variables of `layers`, later layers overriding earlier ones
+fn variables(layers: &[&Map<String, Value>]) -> Vec<(String, String, bool)> {
+    let mut merged: Vec<(String, String)> = Vec::new();
+    for layer in layers {
+        let mut flat = Vec::new();
+        flatten(layer, "", &mut flat);
+        for (name, value) in flat {
+            match merged.iter_mut().find(|(existing, _)| *existing == name) {
+                Some(entry) => entry.1 = value,
+                None => merged.push((name, value)),
+            }
+        }
+    }
+    merged
+        .into_iter()
+        .map(|(name, value)| {
+            let secure = is_sensitive_header(&name);
+            (name, value, secure)
+        })
+        .collect()
+}
+
+fn add_environments(builder: &mut NativeCollectionBuilder, base: &Environment) {
+    let base_name = base.name.as_deref().unwrap_or("Base Environment");
+    if base.sub_environments.is_empty() {
+        if !base.data.is_empty() {
+            builder.add_environment(
+                base_name.to_string(),
+                "Imported from an Insomnia base environment.",
+                variables(&[&base.data]),
+            );
+        }
+        return;
+    }
+    for sub in &base.sub_environments {
+        builder.add_environment(
+            sub.name.clone().unwrap_or_else(|| base_name.to_string()),
+            "Imported from an Insomnia environment, with its base environment's variables.",
+            variables(&[&base.data, &sub.data]),
+        );
+    }
+}
+
+fn form_fields(entries: &[Param]) -> Value {
+    let fields: Map<String, Value> = entries
+        .iter()
+        .filter_map(|entry| {
+            let key = entry.name.as_deref()?;
+            let id = super::new_id();
+            let path = entry.file_name.as_deref().filter(|path| !path.is_empty());
+            let is_file = entry.kind.as_deref() == Some("file");
+            let mut field = json!({
+                "id": id,
+                "key": key,
+                "value": if is_file { String::new() } else { entry.value() },
+                "enabled": !entry.disabled,
+                "secure": false,
+                "kind": if is_file { "file" } else { "text" },
+            });
+            if let Some(path) = path.filter(|_| is_file) {
+                field["fileName"] = json!(file_name(path));
+                field["filePath"] = json!(path);
+            }
+            Some((id, field))
+        })
+        .collect();
+    Value::Object(fields)
+}
+
+fn convert_body(body: Option<&Body>) -> Value {
+    let Some(body) = body else {
+        return json!({"type": "none"});
+    };
+    let mime_type = body.mime_type.as_deref().unwrap_or_default();
+    match mime_type {
+        "application/x-www-form-urlencoded" => json!({
+            "type": "form",
+            "encoding": "url",
+            "formData": form_fields(&body.params),
+        }),
+        "multipart/form-data" => json!({
+            "type": "form",
+            "encoding": "multipart",
+            "formData": form_fields(&body.params),
+        }),
+        "application/graphql" => {
+            let graphql: Value = body
+                .text
+                .as_deref()
+                .and_then(|text| serde_json::from_str(text).ok())
+                .unwrap_or_default();
+            graphql_body(
+                template(&text(graphql.get("query"))),
+                graphql.get("variables"),
+            )
+        }
+        _ => {
+            if let Some(path) = body.file_name.as_deref().filter(|path| !path.is_empty()) {
+                let mut converted = json!({
+                    "type": "binary",
+                    "binaryPath": path,
+                    "binaryFileName": file_name(path),
+                });
+                if !mime_type.is_empty() {
+                    converted["binaryContentType"] = json!(mime_type);
+                }
+                return converted;
+            }
+            match body.text.as_deref() {
+                Some(content) => json!({
+                    "type": "text",
+                    "language": text_language(mime_type, content),
+                    "content": template(content),
+                }),
+                None => json!({"type": "none"}),
+            }
+        }
+    }
+}
+
+/// Converts an Insomnia auth block. `None` means the block inherits from its parent.
+fn convert_auth(auth: Option<&Value>) -> Option<Value> {
+    let auth = auth.filter(|auth| auth.as_object().is_some_and(|a| !a.is_empty()))?;
+    if auth.get("disabled").and_then(Value::as_bool) == Some(true) {
+        return Some(json!({"type": "none"}));
+    }
+    let param = |key: &str| Some(template(&text(auth.get(key)))).filter(|value| !value.is_empty());
+    let kind = auth.get("type").and_then(Value::as_str)?;
+    let converted = match kind {
+        "inherit" => return None,
+        "bearer" => json!({
+            "type": "bearer",
+            "bearer": {"token": param("token").unwrap_or_default(), "scheme": param("prefix")},
+        }),
+        "basic" => json!({
+            "type": "basic",
+            "basic": {"username": param("username"), "password": param("password")},
+        }),
+        "digest" => json!({
+            "type": "digest",
+            "digest": {"username": param("username"), "password": param("password")},
+        }),
+        "ntlm" => json!({
+            "type": "ntlm",
+            "ntlm": {"username": param("username"), "password": param("password")},
+        }),
+        "apikey" => {
+            let key = param("key").unwrap_or_else(|| "X-API-Key".to_string());
+            let placement = match param("addTo").as_deref() {
+                Some("queryParams") => "query",
+                Some("cookie") => "cookie",
+                _ => "header",
+            };
+            json!({
+                "type": "apiKey",
+                "apiKey": {
+                    "key": key,
+                    "value": param("value").unwrap_or_default(),
+                    "placement": {"type": placement, "name": key},
+                },
+            })
+        }
+        "oauth2" => {
+            let mut oauth2 = json!({
+                "clientId": param("clientId"),
+                "clientSecret": param("clientSecret"),
+                "scope": param("scope"),
+                "authUrl": param("authorizationUrl"),
+                "tokenUrl": param("accessTokenUrl"),
+                "redirectUri": param("redirectUrl"),
+            });
+            let grant_type = param("grantType");
+            if let Some(grant_type) = grant_type.as_deref().filter(|grant| {
+                [
+                    "client_credentials",
+                    "authorization_code",
+                    "password",
+                    "refresh_token",
+                ]
+                .contains(grant)
+            }) {
+                oauth2["grantType"] = json!(grant_type);
+            }
+            if let Some(use_pkce) = auth.get("usePkce").and_then(Value::as_bool) {
+                oauth2["usePkce"] = json!(use_pkce);
+            }
+            json!({"type": "oauth2", "oauth2": oauth2})
+        }
+        _ => json!({"type": "none"}),
+    };
+    Some(converted)
+}
+
+/// Query params or headers
+fn rows(entries: &[Param]) -> Value {
+    params(entries.iter().filter_map(|entry| {
+        let name = entry.name.as_deref().filter(|name| !name.is_empty())?;
+        Some((
+            name,
+            entry.value(),
+            !entry.disabled,
+            is_sensitive_header(name),
+        ))
+    }))
+}
+
+/// The request's URL with its enabled params appended, since Insomnia keeps them apart
+fn request_url(request: &RequestFields) -> String {
+    let mut url = template(request.url.as_deref().unwrap_or_default());
+    for param in request.parameters.iter().filter(|param| !param.disabled) {
+        let Some(name) = param.name.as_deref().filter(|name| !name.is_empty()) else {
+            continue;
+        };
+        url.push(if url.contains('?') { '&' } else { '?' });
+        url.push_str(name);
+        url.push('=');
+        url.push_str(&param.value());
+    }
+    url
+}
+
+/// Walks `items` under `folder_id`, with the auth of the folders above
+fn add_items(
+    builder: &mut NativeCollectionBuilder,
+    items: &[Item],
+    folder_id: &str,
+    inherited_auth: Option<&Value>,
+) {
+    for item in items {
+        let name = item.name.as_deref();
+        let request = &item.request;
+        let auth = convert_auth(request.authentication.as_ref());
+        if let Some(children) = &item.children {
+            let child_id = builder.add_folder(name, folder_id);
+            let folder_variables = item.environment.as_ref().filter(|vars| !vars.is_empty());
+            if let Some(folder_variables) = folder_variables {
+                builder.add_environment(
+                    format!("{} Folder Variables", name.unwrap_or("Folder")),
+                    "Imported from Insomnia folder variables.",
+                    variables(&[folder_variables]),
+                );
+            }
+            add_items(
+                builder,
+                children,
+                &child_id,
+                auth.as_ref().or(inherited_auth),
+            );
+            continue;
+        }
+        let method = http_method(request.method.as_deref());
+        let url = request_url(request);
+        let authentication = auth
+            .or_else(|| inherited_auth.cloned())
+            .unwrap_or_else(|| json!({"type": "inherit"}));
+        let mut fields = Map::new();
+        fields.insert(
+            "name".to_string(),
+            json!(name.map_or_else(
+                || format!("{method} {url}").trim().to_string(),
+                str::to_string
+            )),
+        );
+        fields.insert("method".to_string(), json!(method));
+        fields.insert("queryParams".to_string(), rows(&request.parameters));
+        fields.insert("headers".to_string(), rows(&request.headers));
+        fields.insert("body".to_string(), convert_body(request.body.as_ref()));
+        fields.insert("authentication".to_string(), authentication);
+        fields.insert("url".to_string(), json!(url));
+        builder.add_request(fields, folder_id);
+    }
+}
+
+/// The v4 resources under `parent_id` as a v5-style tree, in Insomnia's sort order
+fn resource_items(resources: &[Resource], parent_id: &str) -> Vec<Item> {
+    let mut children: Vec<&Resource> = resources
+        .iter()
+        .filter(|resource| resource.parent_id.as_deref() == Some(parent_id))
+        .filter(|resource| matches!(resource.kind.as_str(), "request" | "request_group"))
+        .collect();
+    children.sort_by(|a, b| {
+        let (a, b) = (a.sort_key.unwrap_or(0.0), b.sort_key.unwrap_or(0.0));
+        a.total_cmp(&b)
+    });
+    children
+        .into_iter()
+        .map(|resource| Item {
+            name: resource.name.clone(),
+            children: (resource.kind == "request_group")
+                .then(|| resource_items(resources, &resource.id)),
+            environment: resource.environment.clone(),
+            request: resource.request.clone(),
+        })
+        .collect()
+}
+
+/// The v4 base environments of `workspace_id`, each with its sub-environments
+fn resource_environments(resources: &[Resource], workspace_id: &str) -> Vec<Environment> {
+    let environment = |resource: &Resource| Environment {
+        name: resource.name.clone(),
+        data: resource.data.clone().unwrap_or_default(),
+        sub_environments: Vec::new(),
+    };
+    let is_environment_of = |resource: &&Resource, parent_id: &str| {
+        resource.kind == "environment" && resource.parent_id.as_deref() == Some(parent_id)
+    };
+    resources
+        .iter()
+        .filter(|resource| is_environment_of(resource, workspace_id))
+        .map(|base| {
+            let mut subs: Vec<&Resource> = resources
+                .iter()
+                .filter(|resource| is_environment_of(resource, &base.id))
+                .collect();
+            subs.sort_by(|a, b| {
+                let (a, b) = (a.sort_key.unwrap_or(0.0), b.sort_key.unwrap_or(0.0));
+                a.total_cmp(&b)
+            });
+            Environment {
+                sub_environments: subs.into_iter().map(environment).collect(),
+                ..environment(base)
+            }
+        })
+        .collect()
+}
+
+/// Converts the text of an Insomnia v4 or v5 export into a native collection export
+pub fn insomnia_to_native(source: &str) -> Result<Value, AppError> {
+    let export: Export = serde_json::from_str(source)
+        .map_err(|e| AppError::new(ErrorKind::JsonError, format!("Not an Insomnia export: {e}")))?;
+    let mut builder = NativeCollectionBuilder::new();
+
+    let is_v5 = export
+        .kind
+        .as_deref()
+        .is_some_and(|kind| kind.starts_with("collection.insomnia.rest/"));
+    let name = if is_v5 {
+        add_items(&mut builder, &export.collection, ROOT_FOLDER_ID, None);
+        if let Some(environments) = &export.environments {
+            add_environments(&mut builder, environments);
+        }
+        export
+            .name
+            .clone()
+            .unwrap_or_else(|| "Insomnia".to_string())
+    } else if export.export_type.as_deref() == Some("export") {
+        let workspaces: Vec<&Resource> = export
+            .resources
+            .iter()
+            .filter(|resource| resource.kind == "workspace")
+            .collect();
+        // A lone workspace is the collection; several each get a folder
+        let single = workspaces.len() == 1;
+        for workspace in &workspaces {
+            let folder_id = if single {
+                ROOT_FOLDER_ID.to_string()
+            } else {
+                builder.add_folder(workspace.name.as_deref(), ROOT_FOLDER_ID)
+            };
+            let items = resource_items(&export.resources, &workspace.id);
+            add_items(&mut builder, &items, &folder_id, None);
+            for environment in resource_environments(&export.resources, &workspace.id) {
+                add_environments(&mut builder, &environment);
+            }
+        }
+        match workspaces.as_slice() {
+            [workspace] => workspace
+                .name
+                .clone()
+                .unwrap_or_else(|| "Insomnia".to_string()),
+            _ => "Insomnia".to_string(),
+        }
+    } else {
+        return Err(AppError::new(
+            ErrorKind::JsonError,
+            "Not an Insomnia export: expected a v4 or v5 export",
+        ));
+    };
+
+    Ok(builder.build(&name, "", json!({"type": "none"})))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::insomnia_to_native;
+    use serde_json::{Value, json};
+
+    fn requests(doc: &Value) -> Vec<&Value> {
+        let mut requests: Vec<&Value> = doc["collection"]["requests"]
+            .as_object()
+            .unwrap()
+            .values()
+            .collect();
+        requests.sort_by_key(|request| request["name"].as_str().unwrap().to_string());
+        requests
+    }
+
+    fn environment<'a>(doc: &'a Value, name: &str) -> &'a Value {
+        doc["collection"]["environments"]
+            .as_object()
+            .unwrap()
+            .values()
+            .find(|environment| environment["name"] == name)
+            .unwrap()
+    }
+
+    fn variable<'a>(environment: &'a Value, name: &str) -> &'a str {
+        environment["variables"]
+            .as_object()
+            .unwrap()
+            .values()
+            .find(|variable| variable["name"] == name)
+            .and_then(|variable| variable["value"].as_str())
+            .unwrap()
+    }
+
+    #[test]
+    fn converts_v4_workspaces_groups_and_environments() {
+        let source = json!({
+            "_type": "export",
+            "__export_format": 4,
+            "resources": [
+                {"_id": "wrk_1", "_type": "workspace", "name": "Shop", "parentId": null},
+                {
+                    "_id": "fld_1",
+                    "_type": "request_group",
+                    "parentId": "wrk_1",
+                    "name": "Orders",
+                    "metaSortKey": -1,
+                    "authentication": {"type": "bearer", "token": "{{ _.token }}"},
+                },
+                {
+                    "_id": "req_1",
+                    "_type": "request",
+                    "parentId": "fld_1",
+                    "name": "List orders",
+                    "method": "GET",
+                    "url": "{{ _.base_url }}/orders",
+                    "parameters": [
+                        {"name": "page", "value": "1"},
+                        {"name": "draft", "value": "true", "disabled": true},
+                    ],
+                    "headers": [{"name": "Accept", "value": "application/json"}],
+                    "authentication": {},
+                    "body": {},
+                },
+                {
+                    "_id": "req_2",
+                    "_type": "request",
+                    "parentId": "wrk_1",
+                    "name": "Create order",
+                    "method": "POST",
+                    "url": "{{base_url}}/orders",
+                    "metaSortKey": 5,
+                    "body": {"mimeType": "application/json", "text": "{\"sku\": \"{{ _.sku }}\"}"},
+                    "authentication": {"type": "basic", "username": "ada", "password": "pw"},
+                },
+                {
+                    "_id": "req_3",
+                    "_type": "request",
+                    "parentId": "wrk_1",
+                    "name": "Upload",
+                    "method": "POST",
+                    "url": "https://example.com/upload",
+                    "body": {
+                        "mimeType": "multipart/form-data",
+                        "params": [
+                            {"name": "title", "value": "x"},
+                            {"name": "file", "type": "file", "fileName": "/tmp/a.png"},
+                        ],
+                    },
+                },
+                {
+                    "_id": "env_base",
+                    "_type": "environment",
+                    "parentId": "wrk_1",
+                    "name": "Base Environment",
+                    "data": {"base_url": "https://api.example.com", "auth": {"token": "abc"}},
+                },
+                {
+                    "_id": "env_dev",
+                    "_type": "environment",
+                    "parentId": "env_base",
+                    "name": "Dev",
+                    "data": {"base_url": "http://localhost:8080"},
+                },
+                {"_id": "jar_1", "_type": "cookie_jar", "parentId": "wrk_1"},
+            ],
+        })
+        .to_string();
+        let doc = insomnia_to_native(&source).unwrap();
+        let collection = &doc["collection"];
+        assert_eq!(collection["name"], "Shop");
+
+        let folder = collection["folders"]
+            .as_object()
+            .unwrap()
+            .values()
+            .find(|folder| folder["name"] == "Orders")
+            .unwrap();
+        assert_eq!(folder["parentId"], "root");
+        assert_eq!(folder["requestIds"].as_array().unwrap().len(), 1);
+
+        let requests = requests(&doc);
+        let [create, list, upload] = requests.as_slice() else {
+            panic!("expected three requests");
+        };
+        assert_eq!(list["url"], "{{base_url}}/orders?page=1");
+        assert_eq!(list["authentication"]["type"], "bearer");
+        assert_eq!(list["authentication"]["bearer"]["token"], "{{token}}");
+        assert_eq!(list["body"]["type"], "none");
+        assert_eq!(list["queryParams"].as_object().unwrap().len(), 2);
+
+        assert_eq!(create["authentication"]["basic"]["username"], "ada");
+        assert_eq!(create["body"]["language"], "json");
+        assert_eq!(create["body"]["content"], "{\"sku\": \"{{sku}}\"}");
+
+        assert_eq!(upload["authentication"]["type"], "inherit");
+        assert_eq!(upload["body"]["encoding"], "multipart");
+        let file = upload["body"]["formData"]
+            .as_object()
+            .unwrap()
+            .values()
+            .find(|field| field["key"] == "file")
+            .unwrap();
+        assert_eq!(file["kind"], "file");
+        assert_eq!(file["fileName"], "a.png");
+
+        let dev = environment(&doc, "Dev");
+        assert_eq!(variable(dev, "base_url"), "http://localhost:8080");
+        assert_eq!(variable(dev, "auth.token"), "abc");
+        assert_eq!(
+            collection["environments"].as_object().unwrap().len(),
+            1,
+            "the base environment only feeds its sub-environments"
+        );
+    }
+
+    #[test]
+    fn converts_v5_collections() {
+        let source = json!({
+            "type": "collection.insomnia.rest/5.0",
+            "name": "Weather",
+            "collection": [
+                {
+                    "name": "Forecasts",
+                    "authentication": {
+                        "type": "apikey",
+                        "key": "api_key",
+                        "value": "{{ _.key }}",
+                        "addTo": "queryParams",
+                    },
+                    "environment": {"units": "metric"},
+                    "children": [
+                        {
+                            "name": "Today",
+                            "url": "https://api.example.com/today",
+                            "method": "GET",
+                        },
+                    ],
+                },
+                {
+                    "name": "Query",
+                    "url": "https://api.example.com/graphql",
+                    "method": "POST",
+                    "body": {
+                        "mimeType": "application/graphql",
+                        "text": "{\"query\": \"{ today { temp } }\", \"variables\": {\"city\": \"Oslo\"}}",
+                    },
+                    "authentication": {"type": "none"},
+                },
+            ],
+            "environments": {
+                "name": "Base Environment",
+                "data": {"key": "secret-key"},
+            },
+        })
+        .to_string();
+        let doc = insomnia_to_native(&source).unwrap();
+        assert_eq!(doc["collection"]["name"], "Weather");
+
+        let requests = requests(&doc);
+        let [query, today] = requests.as_slice() else {
+            panic!("expected two requests");
+        };
+        let api_key = &today["authentication"]["apiKey"];
+        assert_eq!(api_key["value"], "{{key}}");
+        assert_eq!(api_key["placement"]["type"], "query");
+        assert_eq!(query["authentication"]["type"], "none");
+        assert_eq!(query["body"]["language"], "graphql");
+        assert!(
+            query["body"]["content"]
+                .as_str()
+                .unwrap()
+                .starts_with("{ today { temp } }\n\n# Variables\n")
+        );
+
+        let base = environment(&doc, "Base Environment");
+        assert_eq!(variable(base, "key"), "secret-key");
+        let folder = environment(&doc, "Forecasts Folder Variables");
+        assert_eq!(variable(folder, "units"), "metric");
+    }
+
+    #[test]
+    fn rejects_other_documents() {
+        assert!(insomnia_to_native("{\"info\": {}}").is_err());
+        assert!(insomnia_to_native("not json").is_err());
+    }
+}
@@ -0,0 +1,694 @@
+//! Postman Collection v2.0/v2.1 exports. Folders become folders, and auth set on a folder is
+//! copied to the requests that inherit it, since knurl folders carry no auth of their own.
+//! Postman scripts can't run in knurl, so they're kept as comments in each request's tests.
+
+use super::{
+    NativeCollectionBuilder, ROOT_FOLDER_ID, file_name, graphql_body, http_method,
+    is_sensitive_header, params, text,
+};
+use crate::errors::{AppError, ErrorKind};
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+
+#[derive(Debug, Deserialize)]
+struct Collection {
+    info: Info,
+    #[serde(default)]
+    item: Vec<Item>,
+    #[serde(default)]
+    variable: Vec<KeyValue>,
+    auth: Option<Value>,
+    #[serde(default)]
+    event: Vec<Event>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Info {
+    name: String,
+    description: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Item {
+    name: Option<String>,
+    /// Present on folders, which hold no request
+    item: Option<Vec<Item>>,
+    request: Option<RequestDef>,
+    auth: Option<Value>,
+    #[serde(default)]
+    event: Vec<Event>,
+}
+
+/// A request is either a bare URL or a full definition
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RequestDef {
+    Url(String),
+    Full(Box<Request>),
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    method: Option<String>,
+    #[serde(default)]
+    header: Vec<KeyValue>,
+    body: Option<Body>,
+    url: Option<Url>,
+    auth: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Url {
+    Raw(String),
+    Parts(Box<UrlParts>),
+}
+
+#[derive(Debug, Deserialize)]
+struct UrlParts {
+    raw: Option<String>,
+    protocol: Option<String>,
+    host: Option<Value>,
+    port: Option<Value>,
+    path: Option<Value>,
+    #[serde(default)]
+    query: Vec<KeyValue>,
+    #[serde(default)]
+    variable: Vec<KeyValue>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KeyValue {
+    key: Option<String>,
+    value: Option<Value>,
+    #[serde(default)]
+    disabled: bool,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    src: Option<Value>,
+    #[serde(rename = "contentType")]
+    content_type: Option<String>,
+}
+
+impl KeyValue {
+    fn value(&self) -> String {
+        text(self.value.as_ref())
+    }
+
+    fn is_secret(&self) -> bool {
+        self.kind.as_deref() == Some("secret")
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Body {
+    mode: Option<String>,
+    raw: Option<String>,
+    options: Option<Value>,
+    #[serde(default)]
+    urlencoded: Vec<KeyValue>,
+    #[serde(default)]
+    formdata: Vec<KeyValue>,
+    file: Option<Value>,
+    graphql: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Event {
+    listen: String,
+    script: Option<Script>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Script {
+    exec: Option<Value>,
+}
+
+/// The Postman scripts that run before and after a request, outermost first, each with where
+/// it came from
+#[derive(Default, Clone)]
+struct Scripts {
+    prerequest: Vec<(String, String)>,
+    test: Vec<(String, String)>,
+}
+
+impl Scripts {
+    /// These scripts followed by `events`, which belong to `source`
+    fn with(&self, source: &str, events: &[Event]) -> Self {
+        let mut scripts = self.clone();
+        for event in events {
+            let Some(code) = event
+                .script
+                .as_ref()
+                .map(|script| lines(script.exec.as_ref()))
+            else {
+                continue;
+            };
+            if code.trim().is_empty() {
+                continue;
+            }
+            let code = (source.to_string(), code);
+            match event.listen.as_str() {
+                "prerequest" => scripts.prerequest.push(code),
+                "test" => scripts.test.push(code),
+                _ => {}
+            }
+        }
+        scripts
+    }
+
+    /// The scripts as comments, since knurl doesn't run Postman scripts
+    fn to_tests(&self) -> Option<String> {
+        let blocks: Vec<String> = [
+            ("pre-request script", &self.prerequest),
+            ("test script", &self.test),
+        ]
+        .into_iter()
+        .flat_map(|(kind, scripts)| {
+            scripts.iter().map(move |(source, code)| {
+                let mut block = format!("// Postman {kind} from {source} (not run by knurl)\n");
+                for line in code.lines() {
+                    block.push_str("// ");
+                    block.push_str(line);
+                    block.push('\n');
+                }
+                block
+            })
+        })
+        .collect();
+        (!blocks.is_empty()).then(|| blocks.join("\n"))
+    }
+}
+
+/// A script or other text given as a string or an array of lines
+fn lines(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::Array(lines)) => lines
+            .iter()
+            .map(|line| line.as_str().unwrap_or_default())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        other => text(other),
+    }
+}
+
+/// A description given as a string or as `{ content }`
+fn description(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::Object(object)) => text(object.get("content")),
+        other => text(other),
+    }
+}
+
+/// The first path of a file `src`, which may be a list
+fn source_path(value: Option<&Value>) -> Option<String> {
+    match value {
+        Some(Value::Array(paths)) => paths.iter().find_map(|p| p.as_str()).map(str::to_string),
+        Some(Value::String(path)) => Some(path.clone()),
+        _ => None,
+    }
+}
+
+/// Host or path segments, given as a list or as one string
+fn segments(value: Option<&Value>, separator: &str) -> String {
+    match value {
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .map(|part| text(Some(part)))
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(separator),
+        other => text(other),
+    }
+}
+
+fn build_url(url: &UrlParts) -> String {
+    if let Some(raw) = url.raw.as_deref().filter(|raw| !raw.trim().is_empty()) {
+        return raw.to_string();
+    }
+    let mut out = String::new();
+    if let Some(protocol) = &url.protocol {
+        out.push_str(protocol);
+        out.push_str("://");
+    }
+    out.push_str(&segments(url.host.as_ref(), "."));
+    let port = text(url.port.as_ref());
+    if !port.is_empty() {
+        out.push(':');
+        out.push_str(&port);
+    }
+    let path = segments(url.path.as_ref(), "/");
+    if !path.is_empty() {
+        if !path.starts_with('/') && !out.ends_with('/') {
+            out.push('/');
+        }
+        out.push_str(&path);
+    }
+    let query: Vec<String> = url
+        .query
+        .iter()
+        .filter_map(|entry| Some(format!("{}={}", entry.key.as_deref()?, entry.value())))
+        .collect();
+    if !query.is_empty() {
+        out.push(if out.contains('?') { '&' } else { '?' });
+        out.push_str(&query.join("&"));
+    }
+    out
+}
+
+fn raw_language(options: Option<&Value>) -> Option<&'static str> {
+    let language = options?.pointer("/raw/language")?.as_str()?;
+    ["json", "xml", "graphql", "javascript", "html", "text"]
+        .into_iter()
+        .find(|known| *known == language)
+}
+
+fn form_fields(entries: &[KeyValue]) -> Value {
+    let fields: Map<String, Value> = entries
+        .iter()
+        .filter_map(|entry| {
+            let key = entry.key.as_deref()?;
+            let id = super::new_id();
+            let src = source_path(entry.src.as_ref());
+            let is_file = entry.kind.as_deref() == Some("file") || src.is_some();
+            let mut field = json!({
+                "id": id,
+                "key": key,
+                "value": if is_file { String::new() } else { entry.value() },
+                "enabled": !entry.disabled,
+                "secure": entry.is_secret(),
+                "kind": if is_file { "file" } else { "text" },
+            });
+            if let Some(path) = src.filter(|_| is_file) {
+                field["fileName"] = json!(file_name(&path));
+                field["filePath"] = json!(path);
+            }
+            if let Some(content_type) = &entry.content_type {
+                field["contentType"] = json!(content_type);
+            }
+            Some((id, field))
+        })
+        .collect();
+    Value::Object(fields)
+}
+
+fn convert_body(body: Option<&Body>) -> Value {
+    let Some(body) = body else {
+        return json!({"type": "none"});
+    };
+    let mode = body
+        .mode
+        .as_deref()
+        .or_else(|| body.raw.as_ref().map(|_| "raw"));
+    match mode {
+        Some("raw") => {
+            let mut converted = json!({
+                "type": "text",
+                "content": body.raw.clone().unwrap_or_default(),
+            });
+            if let Some(language) = raw_language(body.options.as_ref()) {
+                converted["language"] = json!(language);
+            }
+            converted
+        }
+        Some("urlencoded") => json!({
+            "type": "form",
+            "encoding": "url",
+            "formData": form_fields(&body.urlencoded),
+        }),
+        Some("formdata") => json!({
+            "type": "form",
+            "encoding": "multipart",
+            "formData": form_fields(&body.formdata),
+        }),
+        Some("file") | Some("binary") => {
+            let path = source_path(body.file.as_ref().and_then(|file| file.get("src")));
+            json!({
+                "type": "binary",
+                "binaryPath": path,
+                "binaryFileName": path.as_deref().map(file_name),
+            })
+        }
+        Some("graphql") => {
+            let graphql = body.graphql.as_ref();
+            graphql_body(
+                text(graphql.and_then(|g| g.get("query"))),
+                graphql.and_then(|g| g.get("variables")),
+            )
+        }
+        _ => json!({"type": "none"}),
+    }
+}
+
+/// The value of `key` among an auth type's params, which v2.1 lists as `{ key, value }` and
+/// v2.0 gives as an object
+fn auth_param(params: Option<&Value>, key: &str) -> Option<String> {
+    let value = match params? {
+        Value::Array(entries) => entries
+            .iter()
+            .find(|entry| entry.get("key").and_then(Value::as_str) == Some(key))?
+            .get("value"),
+        Value::Object(object) => object.get(key),
+        _ => None,
+    };
+    Some(text(value)).filter(|value| !value.is_empty())
+}
+
+/// Converts a Postman auth block. `None` means the block inherits from its parent.
+fn convert_auth(auth: Option<&Value>) -> Option<Value> {
+    let auth = auth.filter(|auth| !auth.is_null())?;
+    let kind = auth.get("type").and_then(Value::as_str)?;
+    let params = auth.get(kind);
+    let param = |key: &str| auth_param(params, key);
+    let converted = match kind {
+        "inherit" => return None,
+        "noauth" => json!({"type": "none"}),
+        "bearer" => json!({
+            "type": "bearer",
+            "bearer": {"token": param("token").unwrap_or_default(), "scheme": param("prefix")},
+        }),
+        "basic" => json!({
+            "type": "basic",
+            "basic": {"username": param("username"), "password": param("password")},
+        }),
+        "digest" => json!({
+            "type": "digest",
+            "digest": {"username": param("username"), "password": param("password")},
+        }),
+        "ntlm" => json!({
+            "type": "ntlm",
+            "ntlm": {
+                "username": param("username"),
+                "password": param("password"),
+                "domain": param("domain"),
+                "workstation": param("workstation"),
+            },
+        }),
+        "apikey" => {
+            let key = param("key").unwrap_or_else(|| "X-API-Key".to_string());
+            let placement = match param("in").as_deref() {
+                Some("query") => "query",
+                Some("cookie") => "cookie",
+                _ => "header",
+            };
+            json!({
+                "type": "apiKey",
+                "apiKey": {
+                    "key": key,
+                    "value": param("value").unwrap_or_default(),
+                    "placement": {"type": placement, "name": param("name").unwrap_or_else(|| key.clone())},
+                },
+            })
+        }
+        "oauth2" => json!({
+            "type": "oauth2",
+            "oauth2": {
+                "clientId": param("clientId"),
+                "clientSecret": param("clientSecret"),
+                "scope": param("scope"),
+                "authUrl": param("authUrl"),
+                "tokenUrl": param("accessTokenUrl").or_else(|| param("tokenUrl")),
+            },
+        }),
+        _ => json!({"type": "none"}),
+    };
+    Some(converted)
+}
+
+/// Query or path params
+fn rows(entries: &[KeyValue]) -> Value {
+    params(entries.iter().filter_map(|entry| {
+        let key = entry.key.as_deref()?;
+        Some((key, entry.value(), !entry.disabled, entry.is_secret()))
+    }))
+}
+
+/// Walks `items` under `folder_id`, with the auth and scripts of the folders above
+fn add_items(
+    builder: &mut NativeCollectionBuilder,
+    items: &[Item],
+    folder_id: &str,
+    inherited_auth: Option<&Value>,
+    scripts: &Scripts,
+) {
+    for item in items {
+        let name = item.name.as_deref();
+        if let Some(children) = &item.item {
+            let child_id = builder.add_folder(name, folder_id);
+            let auth = convert_auth(item.auth.as_ref());
+            let folder = format!("folder \"{}\"", name.unwrap_or("Folder"));
+            add_items(
+                builder,
+                children,
+                &child_id,
+                auth.as_ref().or(inherited_auth),
+                &scripts.with(&folder, &item.event),
+            );
+            continue;
+        }
+        let Some(request) = &item.request else {
+            continue;
+        };
+        let bare;
+        let request = match request {
+            RequestDef::Url(url) => {
+                bare = Request {
+                    method: None,
+                    header: Vec::new(),
+                    body: None,
+                    url: Some(Url::Raw(url.clone())),
+                    auth: None,
+                };
+                &bare
+            }
+            RequestDef::Full(request) => request.as_ref(),
+        };
+        let method = http_method(request.method.as_deref());
+        let parts = match &request.url {
+            Some(Url::Parts(parts)) => Some(parts.as_ref()),
+            _ => None,
+        };
+        let url = match &request.url {
+            Some(Url::Raw(url)) => url.clone(),
+            Some(Url::Parts(parts)) => build_url(parts),
+            None => String::new(),
+        };
+        let authentication = convert_auth(request.auth.as_ref())
+            .or_else(|| inherited_auth.cloned())
+            .unwrap_or_else(|| json!({"type": "inherit"}));
+        let headers = params(request.header.iter().filter_map(|header| {
+            let key = header.key.as_deref()?;
+            let secure = header.is_secret() || is_sensitive_header(key);
+            Some((key, header.value(), !header.disabled, secure))
+        }));
+        let mut fields = Map::new();
+        fields.insert(
+            "name".to_string(),
+            json!(name.map_or_else(
+                || format!("{method} {url}").trim().to_string(),
+                str::to_string
+            )),
+        );
+        fields.insert("method".to_string(), json!(method));
+        fields.insert(
+            "pathParams".to_string(),
+            rows(parts.map_or(&[][..], |p| p.variable.as_slice())),
+        );
+        fields.insert(
+            "queryParams".to_string(),
+            rows(parts.map_or(&[][..], |p| p.query.as_slice())),
+        );
+        fields.insert("headers".to_string(), headers);
+        fields.insert("body".to_string(), convert_body(request.body.as_ref()));
+        fields.insert("authentication".to_string(), authentication);
+        let request_name = format!("request \"{}\"", name.unwrap_or(&url));
+        if let Some(tests) = scripts.with(&request_name, &item.event).to_tests() {
+            fields.insert("tests".to_string(), json!(tests));
+        }
+        fields.insert("url".to_string(), json!(url));
+        builder.add_request(fields, folder_id);
+    }
+}
+
+/// Converts the text of a Postman collection export into a native collection export
+pub fn postman_to_native(source: &str) -> Result<Value, AppError> {
+    let collection: Collection = serde_json::from_str(source).map_err(|e| {
+        AppError::new(
+            ErrorKind::JsonError,
+            format!("Not a Postman collection: {e}"),
+        )
+    })?;
+    let mut builder = NativeCollectionBuilder::new();
+    let scripts = Scripts::default().with("the collection", &collection.event);
+    add_items(
+        &mut builder,
+        &collection.item,
+        ROOT_FOLDER_ID,
+        None,
+        &scripts,
+    );
+
+    let variables: Vec<_> = collection
+        .variable
+        .iter()
+        .filter_map(|variable| {
+            let key = variable.key.clone()?;
+            Some((key, variable.value(), variable.is_secret()))
+        })
+        .collect();
+    if !variables.is_empty() {
+        builder.add_environment(
+            format!("{} Variables", collection.info.name),
+            "Imported from Postman collection variables.",
+            variables,
+        );
+    }
+    let authentication =
+        convert_auth(collection.auth.as_ref()).unwrap_or_else(|| json!({"type": "none"}));
+    Ok(builder.build(
+        &collection.info.name,
+        &description(collection.info.description.as_ref()),
+        authentication,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::postman_to_native;
+    use serde_json::{Value, json};
+
+    fn requests(doc: &Value) -> Vec<&Value> {
+        doc["collection"]["requests"]
+            .as_object()
+            .unwrap()
+            .values()
+            .collect()
+    }
+
+    #[test]
+    fn converts_folders_requests_and_variables() {
+        let source = json!({
+            "info": {
+                "name": "Sample",
+                "description": {"content": "Sample API"},
+                "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json",
+            },
+            "variable": [{"key": "token", "value": "shhh", "type": "secret"}],
+            "item": [{
+                "name": "Users",
+                "item": [{
+                    "name": "List Users",
+                    "request": {
+                        "method": "get",
+                        "header": [{"key": "Authorization", "value": "Bearer {{token}}"}],
+                        "body": {
+                            "mode": "raw",
+                            "raw": "{\"sample\":true}",
+                            "options": {"raw": {"language": "json"}},
+                        },
+                        "url": {
+                            "protocol": "https",
+                            "host": ["api", "example", "com"],
+                            "path": ["users"],
+                            "query": [{"key": "limit", "value": 10}],
+                        },
+                    },
+                }],
+            }],
+        });
+        let doc = postman_to_native(&source.to_string()).unwrap();
+        let collection = &doc["collection"];
+        assert_eq!(doc["format"], "native");
+        assert_eq!(collection["name"], "Sample");
+        assert_eq!(collection["description"], "Sample API");
+
+        let folder_id = collection["folders"]["root"]["childFolderIds"][0]
+            .as_str()
+            .unwrap();
+        assert_eq!(collection["folders"][folder_id]["name"], "Users");
+
+        let request = requests(&doc)[0];
+        assert_eq!(request["folderId"], folder_id);
+        assert_eq!(request["method"], "GET");
+        assert_eq!(request["url"], "https://api.example.com/users?limit=10");
+        assert_eq!(request["body"]["language"], "json");
+        assert_eq!(request["authentication"]["type"], "inherit");
+        let header = request["headers"]
+            .as_object()
+            .unwrap()
+            .values()
+            .next()
+            .unwrap();
+        assert_eq!(header["secure"], true);
+        let query = request["queryParams"]
+            .as_object()
+            .unwrap()
+            .values()
+            .next()
+            .unwrap();
+        assert_eq!(query["value"], "10");
+
+        let environment = collection["environments"]
+            .as_object()
+            .unwrap()
+            .values()
+            .next()
+            .unwrap();
+        assert_eq!(environment["name"], "Sample Variables");
+        let variable = environment["variables"]
+            .as_object()
+            .unwrap()
+            .values()
+            .next()
+            .unwrap();
+        assert_eq!(variable["secure"], true);
+    }
+
+    #[test]
+    fn copies_folder_auth_and_keeps_scripts_as_comments() {
+        let source = json!({
+            "info": {"name": "Auth"},
+            "auth": {"type": "basic", "basic": [{"key": "username", "value": "root"}]},
+            "item": [{
+                "name": "Admin",
+                "auth": {"type": "bearer", "bearer": [{"key": "token", "value": "abc"}]},
+                "event": [{"listen": "prerequest", "script": {"exec": ["pm.variables.set('a', 1)"]}}],
+                "item": [
+                    {
+                        "name": "Inherits",
+                        "request": {"method": "DELETE", "url": "https://example.com/x"},
+                        "event": [{"listen": "test", "script": {"exec": "pm.response.to.be.ok"}}],
+                    },
+                    {
+                        "name": "Public",
+                        "request": {"method": "GET", "url": "https://example.com", "auth": {"type": "noauth"}},
+                    },
+                ],
+            }],
+        });
+        let doc = postman_to_native(&source.to_string()).unwrap();
+        assert_eq!(
+            doc["collection"]["authentication"]["basic"]["username"],
+            "root"
+        );
+
+        let requests = requests(&doc);
+        let inherits = requests.iter().find(|r| r["name"] == "Inherits").unwrap();
+        assert_eq!(inherits["authentication"]["bearer"]["token"], "abc");
+        assert_eq!(
+            inherits["tests"],
+            "// Postman pre-request script from folder \"Admin\" (not run by knurl)\n\
+             // pm.variables.set('a', 1)\n\
+             \n\
+             // Postman test script from request \"Inherits\" (not run by knurl)\n\
+             // pm.response.to.be.ok\n"
+        );
+        let public = requests.iter().find(|r| r["name"] == "Public").unwrap();
+        assert_eq!(public["authentication"]["type"], "none");
+    }
+
+    #[test]
+    fn rejects_other_documents() {
+        assert!(postman_to_native("{\"openapi\": \"3.1.0\"}").is_err());
+    }
+}
@@ -0,0 +1,275 @@
+use super::crypto::{decrypt_in_place, encrypt_in_place, get_or_create_key};
+use super::loader::app_data_file_path;
+use crate::errors::AppError;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+pub(super) const JOURNAL_FILE: &str = "journal.jsonl";
+
+/// Serializes appends and compactions so concurrent commands can't interleave lines.
+pub(super) static JOURNAL_LOCK: Mutex<()> = Mutex::new(());
+
+/// A single edit operation sent by the frontend (already debounced on its side)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEdit {
+    /// ID of the edited request
+    pub target_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collection_id: Option<String>,
+    /// JSON merge patch (RFC 7386) describing the change against the previous state
+    pub patch: Value,
+    /// Time of the edit, ISO 8601. Defaults to the time it was journaled.
+    #[serde(default)]
+    pub timestamp: String,
+}
+
+/// Unsaved changes for one request, folded from all of its journal records
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveredEdit {
+    pub target_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collection_id: Option<String>,
+    /// Merge patch to apply to the last saved version of the request
+    pub changes: Value,
+    /// Timestamp of the newest edit
+    pub updated: String,
+    /// Number of journal records folded into `changes`
+    pub edits: usize,
+}
+
+/// Combines two successive merge patches into one that has the same effect.
+/// Unlike applying a patch, `null` members are kept so deletions survive the fold.
+fn compose_patch(acc: &mut Value, patch: &Value) {
+    match (acc, patch) {
+        (Value::Object(acc_map), Value::Object(patch_map)) => {
+            for (key, value) in patch_map {
+                match acc_map.get_mut(key) {
+                    Some(existing) if existing.is_object() && value.is_object() => {
+                        compose_patch(existing, value)
+                    }
+                    _ => {
+                        acc_map.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (acc, patch) => *acc = patch.clone(),
+    }
+}
+
+/// Parses journal lines, skipping any that are incomplete or corrupt
+/// (e.g. a torn final write after a crash).
+fn parse_lines(contents: &str) -> Vec<JournalEdit> {
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(edit) => Some(edit),
+            Err(e) => {
+                log::warn!("Skipping unreadable journal record: {e}");
+                None
+            }
+        })
+        .collect()
+}
+
+fn fold_edits(edits: Vec<JournalEdit>) -> Vec<RecoveredEdit> {
+    let mut recovered: BTreeMap<String, RecoveredEdit> = BTreeMap::new();
+    for edit in edits {
+        let entry = recovered
+            .entry(edit.target_id.clone())
+            .or_insert_with(|| RecoveredEdit {
+                target_id: edit.target_id.clone(),
+                collection_id: None,
+                changes: Value::Object(Map::new()),
+                updated: String::new(),
+                edits: 0,
+            });
+        compose_patch(&mut entry.changes, &edit.patch);
+        if edit.collection_id.is_some() {
+            entry.collection_id = edit.collection_id;
+        }
+        entry.updated = edit.timestamp;
+        entry.edits += 1;
+    }
+    recovered.into_values().collect()
+}
+
+fn read_journal(app: &AppHandle) -> Result<Vec<JournalEdit>, AppError> {
+    let path = app_data_file_path(app, JOURNAL_FILE)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let key = get_or_create_key(app, "app_data")?;
+    let mut edits = parse_lines(&fs::read_to_string(path)?);
+    for edit in &mut edits {
+        decrypt_in_place(&mut edit.patch, &key)?;
+    }
+    Ok(edits)
+}
+
+fn encode_lines(app: &AppHandle, edits: Vec<JournalEdit>) -> Result<String, AppError> {
+    let key = get_or_create_key(app, "app_data")?;
+    let mut out = String::new();
+    for mut edit in edits {
+        encrypt_in_place(&mut edit.patch, &key);
+        out.push_str(&serde_json::to_string(&edit)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Appends edits to the journal and flushes them to disk before returning.
+/// Secure values inside patches are encrypted the same way as in app data files.
+pub fn append_journal(app: &AppHandle, edits: Vec<JournalEdit>) -> Result<(), AppError> {
+    if edits.is_empty() {
+        return Ok(());
+    }
+    let now = chrono::Utc::now().to_rfc3339();
+    let edits = edits
+        .into_iter()
+        .map(|mut edit| {
+            if edit.timestamp.is_empty() {
+                edit.timestamp = now.clone();
+            }
+            edit
+        })
+        .collect();
+    let lines = encode_lines(app, edits)?;
+
+    let path = app_data_file_path(app, JOURNAL_FILE)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let _guard = JOURNAL_LOCK.lock().unwrap();
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(lines.as_bytes())?;
+    file.sync_data()?;
+    Ok(())
+}
+
+/// Returns the unsaved changes recorded in the journal, one entry per request.
+pub fn recover_journal(app: &AppHandle) -> Result<Vec<RecoveredEdit>, AppError> {
+    let _guard = JOURNAL_LOCK.lock().unwrap();
+    Ok(fold_edits(read_journal(app)?))
+}
+
+/// Drops journal records once their changes have been saved (or discarded).
+/// With `target_ids`, only records for those requests are removed; otherwise the
+/// journal is cleared.
+pub fn checkpoint_journal(
+    app: &AppHandle,
+    target_ids: Option<Vec<String>>,
+) -> Result<(), AppError> {
+    let _guard = JOURNAL_LOCK.lock().unwrap();
+    let path = app_data_file_path(app, JOURNAL_FILE)?;
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let Some(target_ids) = target_ids else {
+        fs::remove_file(path)?;
+        return Ok(());
+    };
+    let drop: HashSet<String> = target_ids.into_iter().collect();
+    let remaining: Vec<JournalEdit> = read_journal(app)?
+        .into_iter()
+        .filter(|edit| !drop.contains(&edit.target_id))
+        .collect();
+    if remaining.is_empty() {
+        fs::remove_file(path)?;
+        return Ok(());
+    }
+
+    // Rewrite atomically so a crash mid-compaction leaves the old journal intact
+    let dir = path.parent().expect("journal path has a parent");
+    let mut temp = tempfile::Builder::new()
+        .prefix(".journal-")
+        .tempfile_in(dir)?;
+    temp.write_all(encode_lines(app, remaining)?.as_bytes())?;
+    temp.as_file().sync_data()?;
+    temp.persist(&path).map_err(|e| AppError::from(e.error))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JournalEdit, compose_patch, fold_edits, parse_lines};
+    use serde_json::{Map, Value, json};
+
+    /// Applies a JSON merge patch (RFC 7386) to `target`.
+    fn merge_patch(target: &mut Value, patch: &Value) {
+        let Value::Object(patch_map) = patch else {
+            *target = patch.clone();
+            return;
+        };
+        if !target.is_object() {
+            *target = Value::Object(Map::new());
+        }
+        let target_map = target.as_object_mut().expect("target is an object");
+        for (key, value) in patch_map {
+            if value.is_null() {
+                target_map.remove(key);
+            } else {
+                merge_patch(target_map.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+
+    fn edit(target: &str, patch: Value) -> JournalEdit {
+        JournalEdit {
+            target_id: target.to_string(),
+            collection_id: Some("c1".to_string()),
+            patch,
+            timestamp: "2025-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn folded_patches_match_sequential_application() {
+        let saved =
+            json!({"name": "Get user", "url": "https://a", "headers": {"a": "1", "b": "2"}});
+        let patches = [
+            json!({"url": "https://b", "headers": {"b": null}}),
+            json!({"headers": {"c": "3"}, "name": null}),
+            json!({"headers": {"c": "4"}}),
+        ];
+
+        let mut sequential = saved.clone();
+        let mut folded = json!({});
+        for patch in &patches {
+            merge_patch(&mut sequential, patch);
+            compose_patch(&mut folded, patch);
+        }
+        let mut restored = saved;
+        merge_patch(&mut restored, &folded);
+
+        assert_eq!(restored, sequential);
+        assert_eq!(
+            restored,
+            json!({"url": "https://b", "headers": {"a": "1", "c": "4"}})
+        );
+    }
+
+    #[test]
+    fn skips_torn_lines_and_groups_by_target() {
+        let first = serde_json::to_string(&edit("r1", json!({"url": "x"}))).unwrap();
+        let second = serde_json::to_string(&edit("r2", json!({"method": "POST"}))).unwrap();
+        let third = serde_json::to_string(&edit("r1", json!({"url": "y"}))).unwrap();
+        let contents = format!("{first}\n{second}\n{third}\n{{\"targetId\":\"r1\",\"pa");
+
+        let recovered = fold_edits(parse_lines(&contents));
+        assert_eq!(recovered.len(), 2);
+        assert_eq!(recovered[0].target_id, "r1");
+        assert_eq!(recovered[0].changes, json!({"url": "y"}));
+        assert_eq!(recovered[0].edits, 2);
+        assert_eq!(recovered[1].changes, json!({"method": "POST"}));
+    }
+}
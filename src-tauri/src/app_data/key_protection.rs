@@ -0,0 +1,394 @@
+use super::crypto::decode_key;
+use crate::app_error;
+use crate::errors::{AppError, ErrorKind};
+use base64::{DecodeError, Engine, engine::general_purpose as b64};
+use serde::{Deserialize, Serialize};
+
+/// Prefix of keyring entries holding a key wrapped by a hardware-resident key:
+/// `hw1.<provider>.<base64 wrapped key>`. Entries without it hold the raw key, base64 encoded.
+const WRAPPED_PREFIX: &str = "hw1.";
+
+/// How a data encryption key is stored in the keyring
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum KeyProtection {
+    /// Raw key bytes
+    Software,
+    /// Key wrapped by a TPM or Secure Enclave key that never leaves the device
+    Hardware,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyProtectionStatus {
+    pub protection: KeyProtection,
+    /// Whether a hardware key store can be used on this device
+    pub hardware_available: bool,
+    /// Name of the hardware key store, e.g. "tpm" or "secureEnclave"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<&'static str>,
+}
+
+/// Returns how a keyring entry is protected.
+pub fn protection_of(entry: &str) -> KeyProtection {
+    if entry.starts_with(WRAPPED_PREFIX) {
+        KeyProtection::Hardware
+    } else {
+        KeyProtection::Software
+    }
+}
+
+pub fn status(protection: KeyProtection) -> KeyProtectionStatus {
+    KeyProtectionStatus {
+        protection,
+        hardware_available: platform::is_available(),
+        provider: platform::PROVIDER,
+    }
+}
+
+/// Encodes a key for storage in the keyring. Hardware protection falls back to storing the
+/// raw key when no hardware key store is usable, so check the returned protection.
+pub fn encode_entry(
+    key: &[u8; 32],
+    protection: KeyProtection,
+) -> Result<(String, KeyProtection), AppError> {
+    if protection == KeyProtection::Hardware
+        && let Some(provider) = platform::PROVIDER
+    {
+        match platform::wrap(key) {
+            Ok(wrapped) => {
+                let encoded = b64::URL_SAFE_NO_PAD.encode(wrapped);
+                return Ok((
+                    format!("{WRAPPED_PREFIX}{provider}.{encoded}"),
+                    KeyProtection::Hardware,
+                ));
+            }
+            Err(e) => {
+                log::warn!("Hardware key protection unavailable, storing key in software: {e}")
+            }
+        }
+    }
+    Ok((b64::URL_SAFE_NO_PAD.encode(key), KeyProtection::Software))
+}
+
+/// Decodes a keyring entry written by [`encode_entry`] (or a raw key from older versions).
+pub fn decode_entry(entry: &str) -> Result<[u8; 32], AppError> {
+    let Some(rest) = entry.strip_prefix(WRAPPED_PREFIX) else {
+        return decode_key(entry);
+    };
+    let (provider, encoded) = rest
+        .split_once('.')
+        .ok_or_else(|| app_error!(ErrorKind::KeyringBadEncoding, "Malformed wrapped key entry"))?;
+    if platform::PROVIDER != Some(provider) {
+        return Err(app_error!(
+            ErrorKind::KeyringPlatformFailure,
+            format!("Key is protected by {provider}, which is not available on this device")
+        ));
+    }
+    let wrapped = b64::URL_SAFE_NO_PAD
+        .decode(encoded)
+        .map_err(|e: DecodeError| app_error!(ErrorKind::KeyringBadEncoding, e.to_string()))?;
+    let key = platform::unwrap(&wrapped).map_err(|e| {
+        app_error!(
+            ErrorKind::KeyringPlatformFailure,
+            format!("Unable to unwrap key with {provider}: {e}")
+        )
+    })?;
+
+    key.try_into().map_err(|v: Vec<u8>| {
+        app_error!(
+            ErrorKind::InvalidKeyLength,
+            format!("Expected 32-byte key, got {} bytes", v.len())
+        )
+    })
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::ptr;
+
+    use windows_sys::Win32::Foundation::NTE_BAD_KEYSET;
+    use windows_sys::Win32::Security::Cryptography::{
+        BCRYPT_OAEP_PADDING_INFO, BCRYPT_SHA256_ALGORITHM, MS_PLATFORM_CRYPTO_PROVIDER,
+        NCRYPT_KEY_HANDLE, NCRYPT_LENGTH_PROPERTY, NCRYPT_PAD_OAEP_FLAG, NCRYPT_PROV_HANDLE,
+        NCRYPT_RSA_ALGORITHM, NCRYPT_SILENT_FLAG, NCryptCreatePersistedKey, NCryptDecrypt,
+        NCryptEncrypt, NCryptFinalizeKey, NCryptFreeObject, NCryptOpenKey,
+        NCryptOpenStorageProvider, NCryptSetProperty,
+    };
+    use windows_sys::core::{PCWSTR, w};
+
+    pub(super) const PROVIDER: Option<&str> = Some("tpm");
+
+    /// Name of the persisted TPM key that wraps data encryption keys
+    const WRAP_KEY_NAME: PCWSTR = w!("Knurl Data Key Wrap");
+
+    /// Owned NCrypt provider or key handle.
+    struct Handle(usize);
+
+    impl Drop for Handle {
+        fn drop(&mut self) {
+            if self.0 != 0 {
+                unsafe {
+                    NCryptFreeObject(self.0);
+                }
+            }
+        }
+    }
+
+    fn check(status: i32, call: &str) -> Result<(), String> {
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(format!("{call} failed: 0x{status:08X}"))
+        }
+    }
+
+    fn open_provider() -> Result<Handle, String> {
+        let mut provider: NCRYPT_PROV_HANDLE = 0;
+        let status =
+            unsafe { NCryptOpenStorageProvider(&mut provider, MS_PLATFORM_CRYPTO_PROVIDER, 0) };
+        check(status, "NCryptOpenStorageProvider")?;
+        Ok(Handle(provider))
+    }
+
+    /// Opens the wrapping key, creating it on first use.
+    fn open_key(create: bool) -> Result<Handle, String> {
+        let provider = open_provider()?;
+        let mut key: NCRYPT_KEY_HANDLE = 0;
+        let status =
+            unsafe { NCryptOpenKey(provider.0, &mut key, WRAP_KEY_NAME, 0, NCRYPT_SILENT_FLAG) };
+        if status == 0 {
+            return Ok(Handle(key));
+        }
+        if status != NTE_BAD_KEYSET || !create {
+            return Err(format!("NCryptOpenKey failed: 0x{status:08X}"));
+        }
+
+        let status = unsafe {
+            NCryptCreatePersistedKey(
+                provider.0,
+                &mut key,
+                NCRYPT_RSA_ALGORITHM,
+                WRAP_KEY_NAME,
+                0,
+                0,
+            )
+        };
+        check(status, "NCryptCreatePersistedKey")?;
+        let key = Handle(key);
+        let length = 2048u32.to_le_bytes();
+        let status = unsafe {
+            NCryptSetProperty(
+                key.0,
+                NCRYPT_LENGTH_PROPERTY,
+                length.as_ptr(),
+                length.len() as u32,
+                0,
+            )
+        };
+        check(status, "NCryptSetProperty")?;
+        let status = unsafe { NCryptFinalizeKey(key.0, NCRYPT_SILENT_FLAG) };
+        check(status, "NCryptFinalizeKey")?;
+        Ok(key)
+    }
+
+    fn padding() -> BCRYPT_OAEP_PADDING_INFO {
+        BCRYPT_OAEP_PADDING_INFO {
+            pszAlgId: BCRYPT_SHA256_ALGORITHM,
+            pbLabel: ptr::null_mut(),
+            cbLabel: 0,
+        }
+    }
+
+    type CryptFn = unsafe extern "system" fn(
+        usize,
+        *const u8,
+        u32,
+        *const core::ffi::c_void,
+        *mut u8,
+        u32,
+        *mut u32,
+        u32,
+    ) -> i32;
+
+    /// Runs NCryptEncrypt/NCryptDecrypt, sizing the output buffer first.
+    fn run(key: &Handle, call: CryptFn, name: &str, input: &[u8]) -> Result<Vec<u8>, String> {
+        let padding = padding();
+        let padding_ptr = ptr::from_ref(&padding).cast();
+        let mut len = 0u32;
+        let status = unsafe {
+            call(
+                key.0,
+                input.as_ptr(),
+                input.len() as u32,
+                padding_ptr,
+                ptr::null_mut(),
+                0,
+                &mut len,
+                NCRYPT_PAD_OAEP_FLAG | NCRYPT_SILENT_FLAG,
+            )
+        };
+        check(status, name)?;
+        let mut out = vec![0u8; len as usize];
+        let status = unsafe {
+            call(
+                key.0,
+                input.as_ptr(),
+                input.len() as u32,
+                padding_ptr,
+                out.as_mut_ptr(),
+                out.len() as u32,
+                &mut len,
+                NCRYPT_PAD_OAEP_FLAG | NCRYPT_SILENT_FLAG,
+            )
+        };
+        check(status, name)?;
+        out.truncate(len as usize);
+        Ok(out)
+    }
+
+    pub(super) fn is_available() -> bool {
+        open_provider().is_ok()
+    }
+
+    pub(super) fn wrap(key: &[u8]) -> Result<Vec<u8>, String> {
+        run(&open_key(true)?, NCryptEncrypt, "NCryptEncrypt", key)
+    }
+
+    pub(super) fn unwrap(wrapped: &[u8]) -> Result<Vec<u8>, String> {
+        run(&open_key(false)?, NCryptDecrypt, "NCryptDecrypt", wrapped)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use security_framework::item::{
+        ItemClass, ItemSearchOptions, KeyClass, Limit, Location, Reference, SearchResult,
+    };
+    use security_framework::key::{Algorithm, GenerateKeyOptions, KeyType, SecKey, Token};
+    use std::ffi::{CStr, c_char, c_void};
+
+    pub(super) const PROVIDER: Option<&str> = Some("secureEnclave");
+
+    /// Keychain label of the Secure Enclave key that wraps data encryption keys
+    const WRAP_KEY_LABEL: &str = "Knurl Data Key Wrap";
+    const ALGORITHM: Algorithm = Algorithm::ECIESEncryptionCofactorVariableIVX963SHA256AESGCM;
+
+    /// I/O Registry class of the Secure Enclave's manager, present on Macs that have one
+    const SEP_MANAGER_CLASS: &CStr = c"AppleSEPManager";
+
+    #[link(name = "IOKit", kind = "framework")]
+    unsafe extern "C" {
+        fn IOServiceMatching(name: *const c_char) -> *mut c_void;
+        fn IOServiceGetMatchingService(main_port: u32, matching: *mut c_void) -> u32;
+        fn IOObjectRelease(object: u32) -> i32;
+    }
+
+    fn find_key() -> Option<SecKey> {
+        ItemSearchOptions::new()
+            .class(ItemClass::key())
+            .key_class(KeyClass::private())
+            .label(WRAP_KEY_LABEL)
+            .ignore_legacy_keychains()
+            .load_refs(true)
+            .limit(Limit::Max(1))
+            .search()
+            .ok()?
+            .into_iter()
+            .find_map(|result| match result {
+                SearchResult::Ref(Reference::Key(key)) => Some(key),
+                _ => None,
+            })
+    }
+
+    fn create_key() -> Result<SecKey, String> {
+        SecKey::new(
+            GenerateKeyOptions::default()
+                .set_key_type(KeyType::ec_sec_prime_random())
+                .set_size_in_bits(256)
+                .set_label(WRAP_KEY_LABEL)
+                .set_token(Token::SecureEnclave)
+                .set_location(Location::DataProtectionKeychain),
+        )
+        .map_err(|e| e.to_string())
+    }
+
+    /// Whether the Mac has a Secure Enclave. Only looks it up in the I/O Registry, so asking
+    /// neither creates the wrapping key nor touches the keychain; the key is created by the first
+    /// `wrap`, when hardware protection is turned on.
+    pub(super) fn is_available() -> bool {
+        // The matching dictionary is consumed by the lookup; port 0 is the default main port
+        let service = unsafe {
+            IOServiceGetMatchingService(0, IOServiceMatching(SEP_MANAGER_CLASS.as_ptr()))
+        };
+        if service == 0 {
+            return false;
+        }
+        unsafe { IOObjectRelease(service) };
+        true
+    }
+
+    pub(super) fn wrap(key: &[u8]) -> Result<Vec<u8>, String> {
+        let private = match find_key() {
+            Some(private) => private,
+            None => create_key()?,
+        };
+        let public = private
+            .public_key()
+            .ok_or_else(|| "Secure Enclave key has no public key".to_string())?;
+        public
+            .encrypt_data(ALGORITHM, key)
+            .map_err(|e| e.to_string())
+    }
+
+    pub(super) fn unwrap(wrapped: &[u8]) -> Result<Vec<u8>, String> {
+        let private = find_key().ok_or_else(|| "Secure Enclave key not found".to_string())?;
+        private
+            .decrypt_data(ALGORITHM, wrapped)
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    pub(super) const PROVIDER: Option<&str> = None;
+
+    pub(super) fn is_available() -> bool {
+        false
+    }
+
+    pub(super) fn wrap(_key: &[u8]) -> Result<Vec<u8>, String> {
+        Err("no hardware key store on this platform".to_string())
+    }
+
+    pub(super) fn unwrap(_wrapped: &[u8]) -> Result<Vec<u8>, String> {
+        Err("no hardware key store on this platform".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyProtection, decode_entry, encode_entry, protection_of};
+    use crate::errors::ErrorKind;
+
+    #[test]
+    fn software_entries_round_trip() {
+        let key = [7u8; 32];
+        let (entry, protection) = encode_entry(&key, KeyProtection::Software).unwrap();
+        assert_eq!(protection, KeyProtection::Software);
+        assert_eq!(protection_of(&entry), KeyProtection::Software);
+        assert_eq!(decode_entry(&entry).unwrap(), key);
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    #[test]
+    fn hardware_falls_back_where_unavailable() {
+        let key = [7u8; 32];
+        let (entry, protection) = encode_entry(&key, KeyProtection::Hardware).unwrap();
+        assert_eq!(protection, KeyProtection::Software);
+        assert_eq!(decode_entry(&entry).unwrap(), key);
+
+        let err = decode_entry("hw1.tpm.AAAA").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::KeyringPlatformFailure);
+    }
+}
@@ -0,0 +1,43 @@
+use super::crypto::keyring_entry;
+use crate::app_error;
+use crate::errors::{AppError, ErrorKind};
+use tauri::AppHandle;
+
+/// Keyring entries of named secrets are namespaced so they can't clash with data or signing keys
+fn entry_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(app_error!(ErrorKind::BadRequest, "Secrets need a name"));
+    }
+    Ok(format!("secret:{name}"))
+}
+
+/// Stores a named secret, e.g. an API key, in the OS keyring, replacing any secret with the same
+/// name. Requests refer to it as `{{secret:name}}`.
+pub fn store_secret(app: &AppHandle, name: &str, value: &str) -> Result<(), AppError> {
+    keyring_entry(app, &entry_name(name)?)?
+        .set_password(value)
+        .map_err(|e: keyring::Error| app_error!(ErrorKind::KeyringPlatformFailure, e.to_string()))
+}
+
+/// Reads a named secret from the keyring.
+pub fn load_secret(app: &AppHandle, name: &str) -> Result<String, AppError> {
+    keyring_entry(app, &entry_name(name)?)?
+        .get_password()
+        .map_err(|e: keyring::Error| match e {
+            keyring::Error::NoEntry => app_error!(
+                ErrorKind::FileNotFound,
+                format!("No secret named '{name}' is stored")
+            ),
+            e => app_error!(ErrorKind::KeyringPlatformFailure, e.to_string()),
+        })
+}
+
+/// Removes a named secret from the keyring. Returns whether it existed.
+pub fn delete_secret(app: &AppHandle, name: &str) -> Result<bool, AppError> {
+    match keyring_entry(app, &entry_name(name)?)?.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => Err(app_error!(ErrorKind::KeyringPlatformFailure, e.to_string())),
+    }
+}
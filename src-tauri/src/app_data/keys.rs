@@ -0,0 +1,349 @@
+use super::crypto::{decrypt, encrypt, get_or_create_key, key_id, split_blob};
+use super::journal::{JOURNAL_FILE, JOURNAL_LOCK};
+use super::loader::app_data_file_path;
+use crate::errors::AppError;
+use crate::operations::{Operation, OperationKind, ProgressSink};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+/// Encrypted values of one app data file, counted by the key they were encrypted with
+#[derive(Debug, Default, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyUsage {
+    /// Number of values per key id
+    pub key_ids: BTreeMap<String, usize>,
+    /// Values written before key ids were recorded
+    pub legacy: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileKeyUsage {
+    /// Path relative to the app data directory
+    pub file_name: String,
+    #[serde(flatten)]
+    pub usage: KeyUsage,
+}
+
+/// Which keys the encrypted values in app data are encrypted with
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyAudit {
+    pub active_key_id: String,
+    /// Totals across all files
+    #[serde(flatten)]
+    pub usage: KeyUsage,
+    /// Files holding at least one encrypted value
+    pub files: Vec<FileKeyUsage>,
+    /// Files that could not be read or parsed
+    pub unreadable: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RewrapReport {
+    /// Number of files rewritten
+    pub files: usize,
+    /// Number of values re-encrypted with the active key
+    pub values: usize,
+    /// `file:path` of values that could be decrypted with neither key
+    pub failed: Vec<String>,
+}
+
+fn for_each_secure_value(
+    value: &mut Value,
+    path: &mut String,
+    f: &mut impl FnMut(&str, &mut String),
+) {
+    match value {
+        Value::Object(map) => {
+            if map.get("secure").and_then(Value::as_bool) == Some(true) {
+                if let Some(Value::String(blob)) = map.get_mut("value") {
+                    f(path, blob);
+                }
+                return;
+            }
+            for (key, child) in map.iter_mut() {
+                let len = path.len();
+                path.push('/');
+                path.push_str(key);
+                for_each_secure_value(child, path, f);
+                path.truncate(len);
+            }
+        }
+        Value::Array(items) => {
+            for (i, child) in items.iter_mut().enumerate() {
+                let len = path.len();
+                path.push_str(&format!("/{i}"));
+                for_each_secure_value(child, path, f);
+                path.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn count_usage(value: &mut Value, usage: &mut KeyUsage) {
+    for_each_secure_value(
+        value,
+        &mut String::new(),
+        &mut |_, blob| match split_blob(blob).0 {
+            Some(id) => *usage.key_ids.entry(id.to_string()).or_insert(0) += 1,
+            None => usage.legacy += 1,
+        },
+    );
+}
+
+/// Re-encrypts values that aren't tagged with the active key. Tagged values are decrypted
+/// with the previous key; legacy values with the active key, then the previous one.
+/// Returns the number of values re-encrypted and the paths of those that failed.
+fn rewrap_value(
+    value: &mut Value,
+    active: &[u8; 32],
+    previous: Option<&[u8; 32]>,
+) -> (usize, Vec<String>) {
+    let active_id = key_id(active);
+    let previous_id = previous.map(|key| key_id(key));
+    let mut rewrapped = 0;
+    let mut failed = Vec::new();
+    for_each_secure_value(value, &mut String::new(), &mut |path, blob| {
+        let plain = match split_blob(blob).0 {
+            Some(id) if id == active_id => return,
+            Some(id) if Some(id) == previous_id.as_deref() => {
+                previous.and_then(|key| decrypt(blob, key).ok())
+            }
+            Some(_) => None,
+            None => decrypt(blob, active)
+                .ok()
+                .or_else(|| previous.and_then(|key| decrypt(blob, key).ok())),
+        };
+        match plain.map(|plain| encrypt(&plain, active)) {
+            Some(Ok(encrypted)) => {
+                *blob = encrypted;
+                rewrapped += 1;
+            }
+            _ => failed.push(path.to_string()),
+        }
+    });
+    (rewrapped, failed)
+}
+
+fn collect_files(dir: &Path, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, out)?;
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext == "json" || ext == "jsonl")
+        {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn app_data_files(app: &AppHandle) -> Result<(PathBuf, Vec<PathBuf>), AppError> {
+    let root = app_data_file_path(app, "")?;
+    let mut files = Vec::new();
+    if root.exists() {
+        collect_files(&root, &mut files)?;
+    }
+    files.sort();
+    Ok((root, files))
+}
+
+fn relative_name(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Journal files hold one JSON document per line; everything else is a single document.
+fn parse_documents(path: &Path, contents: &str) -> Result<Vec<Value>, serde_json::Error> {
+    if path.extension().is_some_and(|ext| ext == "jsonl") {
+        contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect()
+    } else {
+        serde_json::from_str(contents).map(|doc| vec![doc])
+    }
+}
+
+fn write_documents(path: &Path, docs: &[Value]) -> Result<(), AppError> {
+    let contents = if path.extension().is_some_and(|ext| ext == "jsonl") {
+        let mut out = String::new();
+        for doc in docs {
+            out.push_str(&serde_json::to_string(doc)?);
+            out.push('\n');
+        }
+        out
+    } else {
+        serde_json::to_string_pretty(&docs[0])?
+    };
+    let dir = path.parent().expect("app data file has a parent");
+    let mut temp = tempfile::Builder::new()
+        .prefix(".rewrap-")
+        .tempfile_in(dir)?;
+    temp.write_all(contents.as_bytes())?;
+    temp.as_file().sync_data()?;
+    temp.persist(path).map_err(|e| AppError::from(e.error))?;
+    Ok(())
+}
+
+/// Lists which keys the encrypted values in every app data file are encrypted with.
+pub fn audit_keys(app: &AppHandle) -> Result<KeyAudit, AppError> {
+    let active = get_or_create_key(app, "app_data")?;
+    let (root, paths) = app_data_files(app)?;
+    let mut audit = KeyAudit {
+        active_key_id: key_id(&active),
+        usage: KeyUsage::default(),
+        files: Vec::new(),
+        unreadable: Vec::new(),
+    };
+    for path in paths {
+        let file_name = relative_name(&root, &path);
+        let docs = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| parse_documents(&path, &contents).ok());
+        let Some(mut docs) = docs else {
+            audit.unreadable.push(file_name);
+            continue;
+        };
+        let mut usage = KeyUsage::default();
+        for doc in &mut docs {
+            count_usage(doc, &mut usage);
+        }
+        if usage.key_ids.is_empty() && usage.legacy == 0 {
+            continue;
+        }
+        for (id, count) in &usage.key_ids {
+            *audit.usage.key_ids.entry(id.clone()).or_insert(0) += count;
+        }
+        audit.usage.legacy += usage.legacy;
+        audit.files.push(FileKeyUsage { file_name, usage });
+    }
+    Ok(audit)
+}
+
+/// Re-encrypts every app data value that isn't encrypted with the active key, e.g. after
+/// the key was replaced. `previous` is the key the values were encrypted with before.
+///
+/// Reports progress per file. A cancelled re-wrap leaves the remaining files as they were;
+/// running it again finishes them.
+pub fn rewrap_app_data(
+    app: &AppHandle,
+    previous: Option<[u8; 32]>,
+    progress: ProgressSink,
+) -> Result<RewrapReport, AppError> {
+    let active = get_or_create_key(app, "app_data")?;
+    let (root, paths) = app_data_files(app)?;
+    let operation = Operation::start(
+        progress,
+        OperationKind::KeyRotation,
+        Some(paths.len() as u64),
+    );
+    let result = rewrap_files(&root, &paths, &active, previous.as_ref(), &operation);
+    operation.finish(result)
+}
+
+fn rewrap_files(
+    root: &Path,
+    paths: &[PathBuf],
+    active: &[u8; 32],
+    previous: Option<&[u8; 32]>,
+    operation: &Operation,
+) -> Result<RewrapReport, AppError> {
+    let mut report = RewrapReport::default();
+    for (done, path) in paths.iter().enumerate() {
+        operation.check_cancelled()?;
+        operation.advance(done as u64);
+        let file_name = relative_name(root, path);
+        let _guard = (file_name == JOURNAL_FILE).then(|| JOURNAL_LOCK.lock().unwrap());
+        let Ok(mut docs) = parse_documents(path, &fs::read_to_string(path)?) else {
+            log::warn!("Skipping unreadable app data file {file_name} during re-wrap");
+            continue;
+        };
+        let mut rewrapped = 0;
+        for doc in &mut docs {
+            let (count, failed) = rewrap_value(doc, active, previous);
+            rewrapped += count;
+            report
+                .failed
+                .extend(failed.into_iter().map(|p| format!("{file_name}:{p}")));
+        }
+        if rewrapped > 0 {
+            write_documents(path, &docs)?;
+            report.files += 1;
+            report.values += rewrapped;
+        }
+    }
+    log::info!(
+        "Re-wrapped {} value(s) in {} file(s); {} could not be decrypted",
+        report.values,
+        report.files,
+        report.failed.len()
+    );
+    operation.advance(paths.len() as u64);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyUsage, count_usage, rewrap_value};
+    use crate::app_data::crypto::{decrypt, encrypt, key_id, split_blob};
+    use serde_json::json;
+
+    const OLD: [u8; 32] = [1u8; 32];
+    const NEW: [u8; 32] = [2u8; 32];
+
+    #[test]
+    fn counts_values_per_key_id() {
+        let legacy = split_blob(&encrypt("x", &OLD).unwrap()).1.to_string();
+        let mut doc = json!({
+            "a": {"secure": true, "value": encrypt("1", &OLD).unwrap()},
+            "b": [{"secure": true, "value": encrypt("2", &NEW).unwrap()}],
+            "c": {"secure": true, "value": legacy},
+            "d": {"secure": false, "value": "plain"},
+        });
+        let mut usage = KeyUsage::default();
+        count_usage(&mut doc, &mut usage);
+        assert_eq!(usage.key_ids[&key_id(&OLD)], 1);
+        assert_eq!(usage.key_ids[&key_id(&NEW)], 1);
+        assert_eq!(usage.legacy, 1);
+    }
+
+    #[test]
+    fn rewraps_previous_and_legacy_values_to_active_key() {
+        let legacy = split_blob(&encrypt("legacy", &OLD).unwrap()).1.to_string();
+        let current = encrypt("current", &NEW).unwrap();
+        let mut doc = json!({
+            "old": {"secure": true, "value": encrypt("old", &OLD).unwrap()},
+            "legacy": {"secure": true, "value": legacy},
+            "current": {"secure": true, "value": current.clone()},
+            "foreign": {"secure": true, "value": encrypt("foreign", &[3u8; 32]).unwrap()},
+        });
+
+        let (count, failed) = rewrap_value(&mut doc, &NEW, Some(&OLD));
+        assert_eq!(count, 2);
+        assert_eq!(failed, vec!["/foreign"]);
+        assert_eq!(
+            decrypt(doc["old"]["value"].as_str().unwrap(), &NEW).unwrap(),
+            "old"
+        );
+        assert_eq!(
+            decrypt(doc["legacy"]["value"].as_str().unwrap(), &NEW).unwrap(),
+            "legacy"
+        );
+        // Values already under the active key are left untouched
+        assert_eq!(doc["current"]["value"], current);
+    }
+}
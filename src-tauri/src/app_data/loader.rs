@@ -29,7 +29,7 @@ pub fn load_app_data(app: &AppHandle, file_name: &str) -> Result<Value, AppError
     let key = get_or_create_key(app, "app_data")?;
     let contents = fs::read_to_string(&config_path)?;
     let mut json: Value = serde_json::from_str(&contents)?;
-    decrypt_in_place(&mut json, &key);
+    decrypt_in_place(&mut json, &key)?;
     Ok(json)
 }
 
@@ -55,7 +55,7 @@ pub fn delete_app_data(app: &AppHandle, file_name: &str) -> Result<(), AppError>
     Ok(())
 }
 
-fn app_data_file_path(app: &AppHandle, file_name: &str) -> Result<PathBuf, AppError> {
+pub(super) fn app_data_file_path(app: &AppHandle, file_name: &str) -> Result<PathBuf, AppError> {
     #[cfg(test)]
     if let Some(dir) = TEST_APPDATA_DIR.get() {
         return Ok(dir.join(file_name));
@@ -97,7 +97,7 @@ mod tests {
     fn read_and_decrypt(path: &PathBuf) -> Value {
         let s = fs::read_to_string(path).unwrap();
         let mut json: Value = serde_json::from_str(&s).unwrap();
-        decrypt_in_place(&mut json, &TEST_KEY);
+        decrypt_in_place(&mut json, &TEST_KEY).unwrap();
         json
     }
 
@@ -150,4 +150,34 @@ mod tests {
             "file should not exist after delete_app_data"
         );
     }
+
+    #[test]
+    fn load_skips_a_corrupt_value_but_refuses_another_key() {
+        let tmp = unique_temp_dir();
+        fs::create_dir_all(&tmp).unwrap();
+        let path = tmp.join("collection.json");
+
+        write_pretty_json(
+            &path,
+            json!({
+                "token": {"secure": true, "value": "kept"},
+                "password": {"secure": true, "value": "corrupted"}
+            }),
+        );
+        let mut stored: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let blob = stored["password"]["value"].as_str().unwrap().to_string();
+        let corrupt = format!("{}AAAA", &blob[..blob.len() - 4]);
+        stored["password"]["value"] = json!(corrupt);
+        fs::write(&path, serde_json::to_string_pretty(&stored).unwrap()).unwrap();
+
+        // The rest of the file still loads; the corrupt value is left as stored
+        let loaded = read_and_decrypt(&path);
+        assert_eq!(loaded["token"]["value"], "kept");
+        assert_eq!(loaded["password"]["value"], corrupt.as_str());
+
+        // A value from another key still fails the whole load
+        let mut json: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let err: AppError = decrypt_in_place(&mut json, &[7u8; 32]).expect_err("mismatch");
+        assert_eq!(err.kind, ErrorKind::KeyMismatch);
+    }
 }
@@ -1,3 +1,16 @@
+pub mod annotations;
 pub mod crypto;
+pub mod env_snapshots;
+pub mod graphql_schemas;
+pub mod history;
+pub mod import;
+pub mod journal;
+pub mod key_protection;
+pub mod keyring_secrets;
+pub mod keys;
 pub mod loader;
+pub mod search;
+pub mod secrets;
+pub mod signing_keys;
+pub mod trash;
 pub use loader::{delete_app_data, load_app_data, save_app_data};
@@ -0,0 +1,791 @@
+//! Full-text search over saved requests and history. The collection files and the history log are
+//! indexed into an SQLite FTS5 database in the app data directory, which is kept across launches
+//! and brought up to date before each search by re-indexing only the collection files that
+//! changed and the history entries added or pruned since. Hits are ranked with BM25 so rare terms
+//! and matches in names outrank common ones buried in bodies.
+
+use super::history::{self, ENTRIES_FILE, HistoryEntry};
+use super::loader::{app_data_file_path, load_app_data};
+use crate::errors::AppError;
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+use tauri::AppHandle;
+
+const COLLECTION_DIR: &str = "collections";
+
+/// The index database, in the app data directory
+const INDEX_FILE: &str = "search.db";
+
+/// Version of the index's schema; an index written with another version is rebuilt
+const SCHEMA_VERSION: i64 = 1;
+
+/// Most of a stored body that is indexed; the rest of a large body isn't searchable
+const MAX_BODY_INDEXED: usize = 256 * 1024;
+
+/// Hits returned when no limit is given
+const DEFAULT_LIMIT: usize = 50;
+
+/// Characters of context on each side of the match in a snippet
+const SNIPPET_CONTEXT: usize = 40;
+
+/// The searchable fields of a document and their weights; matches in heavier fields count for
+/// more. The weights are repeated, in this order, in the `bm25()` call in `SearchIndex::search`.
+const FIELDS: [(&str, f64); 4] = [("name", 3.0), ("url", 2.0), ("headers", 1.0), ("body", 1.0)];
+
+/// The files indexed, as they were when indexed; the documents each file's content became; and
+/// the text of those documents. A document's text has the same rowid as the document.
+const SCHEMA: &str = "
+CREATE TABLE sources (
+    path TEXT PRIMARY KEY,
+    modified INTEGER,
+    len INTEGER NOT NULL
+);
+CREATE TABLE documents (
+    document INTEGER PRIMARY KEY,
+    source TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    id TEXT NOT NULL,
+    collection_id TEXT,
+    request_id TEXT,
+    title TEXT NOT NULL,
+    method TEXT NOT NULL,
+    url TEXT NOT NULL,
+    recorded_at TEXT
+);
+CREATE INDEX documents_by_source ON documents (source);
+CREATE VIRTUAL TABLE document_text USING fts5 (name, url, headers, body);
+";
+
+/// The index, opened on the first search
+static SEARCH_INDEX: Mutex<Option<SearchIndex>> = Mutex::new(None);
+
+/// What a hit is
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchHitKind {
+    /// A request saved in a collection
+    Request,
+    /// A send recorded in history
+    History,
+}
+
+impl SearchHitKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Request => "request",
+            Self::History => "history",
+        }
+    }
+}
+
+/// A request or history entry matching a search
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub kind: SearchHitKind,
+    /// ID of the saved request, or of the history entry
+    pub id: String,
+    pub collection_id: Option<String>,
+    /// ID of the saved request a history entry was sent from
+    pub request_id: Option<String>,
+    /// The request's name, or its method and URL for history
+    pub title: String,
+    pub method: String,
+    pub url: String,
+    /// Time a history entry was recorded, ISO 8601
+    pub recorded_at: Option<String>,
+    /// Field the snippet comes from: name, url, headers or body
+    pub field: String,
+    /// Text around the first match
+    pub snippet: String,
+    pub score: f64,
+}
+
+struct Document {
+    /// The hit reported when the document matches, less its field, snippet and score
+    hit: SearchHit,
+    /// Text of each of `FIELDS`, empty where the document has none
+    fields: [String; 4],
+}
+
+/// Splits text into lowercase alphanumeric terms, as the index's tokenizer does
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// The text around the first match of a query term, from the field matching the most terms,
+/// or the heaviest of those that tie
+fn snippet(fields: &[String; 4], query_terms: &[String]) -> (&'static str, String) {
+    let best = FIELDS
+        .iter()
+        .zip(fields)
+        .filter_map(|(&(name, weight), text)| {
+            let lower = text.to_lowercase();
+            // Lowercasing can change byte lengths, so only use positions when they line up
+            if lower.len() != text.len() {
+                return None;
+            }
+            let found: Vec<usize> = query_terms
+                .iter()
+                .filter_map(|term| lower.find(term.as_str()))
+                .collect();
+            let start = *found.iter().min()?;
+            Some((name, weight, text, found.len(), start))
+        })
+        .max_by(|a, b| a.3.cmp(&b.3).then(a.1.total_cmp(&b.1)));
+    if let Some((name, _, text, _, start)) = best {
+        let longest = query_terms.iter().map(String::len).max().unwrap_or(0);
+        let from = text[..start]
+            .char_indices()
+            .rev()
+            .nth(SNIPPET_CONTEXT - 1)
+            .map_or(0, |(i, _)| i);
+        let to = text[start..]
+            .char_indices()
+            .nth(SNIPPET_CONTEXT + longest)
+            .map_or(text.len(), |(i, _)| start + i);
+        let mut snippet: String = text[from..to]
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if from > 0 {
+            snippet.insert(0, '…');
+        }
+        if to < text.len() {
+            snippet.push('…');
+        }
+        return (name, snippet);
+    }
+    let first = FIELDS
+        .iter()
+        .zip(fields)
+        .find(|(_, text)| !text.is_empty())
+        .map_or(FIELDS[0].0, |((name, _), _)| name);
+    (first, String::new())
+}
+
+fn header_text(headers: impl Iterator<Item = (String, String)>) -> String {
+    headers
+        .map(|(name, value)| format!("{name}: {value}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Documents for the requests saved in a collection file's content
+fn request_documents(content: &Value) -> Vec<Document> {
+    let collection_id = content["id"].as_str().map(str::to_string);
+    let Some(requests) = content["requests"].as_object() else {
+        return Vec::new();
+    };
+    requests
+        .values()
+        .filter_map(|request| {
+            let id = request["id"].as_str()?.to_string();
+            let name = request["name"].as_str().unwrap_or_default().to_string();
+            let url = request["url"].as_str().unwrap_or_default().to_string();
+            let headers = request["headers"]
+                .as_object()
+                .into_iter()
+                .flat_map(|headers| headers.values())
+                .map(|header| {
+                    let text = |key: &str| header[key].as_str().unwrap_or_default().to_string();
+                    (text("name"), text("value"))
+                });
+            let body = request["body"]["content"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string();
+            Some(Document {
+                fields: [name.clone(), url.clone(), header_text(headers), body],
+                hit: SearchHit {
+                    kind: SearchHitKind::Request,
+                    id,
+                    collection_id: collection_id.clone(),
+                    request_id: None,
+                    title: name,
+                    method: request["method"].as_str().unwrap_or("GET").to_string(),
+                    url,
+                    recorded_at: None,
+                    field: String::new(),
+                    snippet: String::new(),
+                    score: 0.0,
+                },
+            })
+        })
+        .collect()
+}
+
+/// Text of a stored body worth indexing; binary bodies aren't
+fn body_text(body: &[u8]) -> Option<String> {
+    let body = &body[..body.len().min(MAX_BODY_INDEXED)];
+    if body.contains(&0) {
+        return None;
+    }
+    Some(String::from_utf8_lossy(body).into_owned())
+}
+
+fn history_document(entry: HistoryEntry, body: Option<String>) -> Document {
+    Document {
+        fields: [
+            String::new(),
+            entry.url.clone(),
+            header_text(entry.headers.into_iter()),
+            body.unwrap_or_default(),
+        ],
+        hit: SearchHit {
+            kind: SearchHitKind::History,
+            title: format!("{} {}", entry.method, entry.url),
+            id: entry.id,
+            collection_id: entry.collection_id,
+            request_id: entry.request_id,
+            method: entry.method,
+            url: entry.url,
+            recorded_at: Some(entry.recorded_at),
+            field: String::new(),
+            snippet: String::new(),
+            score: 0.0,
+        },
+    }
+}
+
+/// A file the index is built from, as it is now
+struct FileState {
+    /// The file's path in the app data directory, which its documents are recorded under
+    source: String,
+    /// Modification time in nanoseconds since the epoch
+    modified: Option<i64>,
+    len: i64,
+}
+
+impl FileState {
+    fn read(source: String, path: &Path) -> Self {
+        let metadata = fs::metadata(path).ok();
+        Self {
+            modified: metadata
+                .as_ref()
+                .and_then(|m| m.modified().ok())
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|elapsed| elapsed.as_nanos() as i64),
+            len: metadata.map_or(0, |m| m.len() as i64),
+            source,
+        }
+    }
+}
+
+/// The collection files, which hold the saved requests
+fn collection_files(app: &AppHandle) -> Result<Vec<FileState>, AppError> {
+    let mut files = Vec::new();
+    let collection_dir = app_data_file_path(app, COLLECTION_DIR)?;
+    if collection_dir.exists() {
+        for file in fs::read_dir(&collection_dir)? {
+            let path = file?.path();
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            // Skips the collection index, which holds no requests
+            if !name.starts_with('.') && name.ends_with(".json") {
+                files.push(FileState::read(format!("{COLLECTION_DIR}/{name}"), &path));
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn collection_documents(app: &AppHandle, file: &FileState) -> Vec<Document> {
+    match load_app_data(app, &file.source) {
+        Ok(json) => request_documents(&json["content"]),
+        Err(e) => {
+            log::warn!(
+                "Not searching collection file {}: {}",
+                file.source,
+                e.message
+            );
+            Vec::new()
+        }
+    }
+}
+
+/// Whether `file` is indexed as it is now
+fn is_current(db: &Connection, file: &FileState) -> Result<bool, AppError> {
+    let indexed: Option<(Option<i64>, i64)> = db
+        .prepare_cached("SELECT modified, len FROM sources WHERE path = ?1")?
+        .query_row([&file.source], |row| Ok((row.get(0)?, row.get(1)?)))
+        .optional()?;
+    Ok(indexed == Some((file.modified, file.len)))
+}
+
+/// Records that `file` is indexed as it is now
+fn record(db: &Connection, file: &FileState) -> Result<(), AppError> {
+    db.prepare_cached("INSERT OR REPLACE INTO sources (path, modified, len) VALUES (?1, ?2, ?3)")?
+        .execute(params![file.source, file.modified, file.len])?;
+    Ok(())
+}
+
+fn add_document(db: &Connection, source: &str, doc: &Document) -> Result<(), AppError> {
+    let hit = &doc.hit;
+    db.prepare_cached(
+        "INSERT INTO documents
+            (source, kind, id, collection_id, request_id, title, method, url, recorded_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+    )?
+    .execute(params![
+        source,
+        hit.kind.as_str(),
+        hit.id,
+        hit.collection_id,
+        hit.request_id,
+        hit.title,
+        hit.method,
+        hit.url,
+        hit.recorded_at,
+    ])?;
+    let [name, url, headers, body] = &doc.fields;
+    db.prepare_cached(
+        "INSERT INTO document_text (rowid, name, url, headers, body) VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?
+    .execute(params![db.last_insert_rowid(), name, url, headers, body])?;
+    Ok(())
+}
+
+fn remove_document(db: &Connection, document: i64) -> Result<(), AppError> {
+    db.prepare_cached("DELETE FROM document_text WHERE rowid = ?1")?
+        .execute([document])?;
+    db.prepare_cached("DELETE FROM documents WHERE document = ?1")?
+        .execute([document])?;
+    Ok(())
+}
+
+/// Drops the documents from `source` and the record of it
+fn remove_source(db: &Connection, source: &str) -> Result<(), AppError> {
+    db.prepare_cached(
+        "DELETE FROM document_text
+            WHERE rowid IN (SELECT document FROM documents WHERE source = ?1)",
+    )?
+    .execute([source])?;
+    db.prepare_cached("DELETE FROM documents WHERE source = ?1")?
+        .execute([source])?;
+    db.prepare_cached("DELETE FROM sources WHERE path = ?1")?
+        .execute([source])?;
+    Ok(())
+}
+
+/// Replaces the documents from `file` with `documents`
+fn index_file(db: &Connection, file: &FileState, documents: &[Document]) -> Result<(), AppError> {
+    remove_source(db, &file.source)?;
+    for doc in documents {
+        add_document(db, &file.source, doc)?;
+    }
+    record(db, file)
+}
+
+/// Brings the history documents up to date with `entries`, the content of the history log
+/// `file`. The log is rewritten when pruned, so entries are matched by ID rather than position.
+/// Bodies are stored by digest, so they can't change without the log changing, and `read_body`
+/// is only called for entries that weren't indexed before.
+fn index_history(
+    db: &Connection,
+    file: &FileState,
+    entries: Vec<HistoryEntry>,
+    mut read_body: impl FnMut(&str) -> Option<String>,
+) -> Result<(), AppError> {
+    let indexed: HashMap<String, i64> = db
+        .prepare_cached("SELECT id, document FROM documents WHERE source = ?1")?
+        .query_map([&file.source], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+    let ids: HashSet<&str> = entries.iter().map(|entry| entry.id.as_str()).collect();
+    for (id, document) in &indexed {
+        if !ids.contains(id.as_str()) {
+            remove_document(db, *document)?;
+        }
+    }
+    let mut bodies: HashMap<String, Option<String>> = HashMap::new();
+    for entry in entries {
+        if indexed.contains_key(&entry.id) {
+            continue;
+        }
+        let body = match &entry.body_digest {
+            Some(digest) => bodies
+                .entry(digest.clone())
+                .or_insert_with(|| read_body(digest))
+                .clone(),
+            None => None,
+        };
+        add_document(db, &file.source, &history_document(entry, body))?;
+    }
+    record(db, file)
+}
+
+/// The on-disk index
+struct SearchIndex {
+    db: Connection,
+}
+
+impl SearchIndex {
+    /// Opens the index at `path`, starting over if it can't be read
+    fn open(path: &Path) -> Result<Self, AppError> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        match Connection::open(path)
+            .map_err(AppError::from)
+            .and_then(Self::prepare)
+        {
+            Ok(index) => Ok(index),
+            Err(e) => {
+                log::warn!("Rebuilding the search index: {}", e.message);
+                fs::remove_file(path)?;
+                Self::prepare(Connection::open(path)?)
+            }
+        }
+    }
+
+    #[cfg(test)]
+    fn open_in_memory() -> Self {
+        Self::prepare(Connection::open_in_memory().unwrap()).unwrap()
+    }
+
+    /// Creates the index's tables, dropping those of an index written with another schema
+    fn prepare(db: Connection) -> Result<Self, AppError> {
+        let version: i64 = db.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version != SCHEMA_VERSION {
+            db.execute_batch(&format!(
+                "BEGIN;
+                DROP TABLE IF EXISTS sources;
+                DROP TABLE IF EXISTS documents;
+                DROP TABLE IF EXISTS document_text;
+                {SCHEMA}
+                PRAGMA user_version = {SCHEMA_VERSION};
+                COMMIT;"
+            ))?;
+        }
+        Ok(Self { db })
+    }
+
+    /// Re-indexes the collection files that were added, changed or deleted since the last
+    /// refresh, then the history entries added or pruned since then.
+    fn refresh(&mut self, app: &AppHandle) -> Result<(), AppError> {
+        let tx = self.db.transaction()?;
+        let files = collection_files(app)?;
+        let sources: Vec<String> = tx
+            .prepare("SELECT path FROM sources WHERE path <> ?1")?
+            .query_map([ENTRIES_FILE], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        for source in sources {
+            if !files.iter().any(|file| file.source == source) {
+                remove_source(&tx, &source)?;
+            }
+        }
+        for file in &files {
+            if !is_current(&tx, file)? {
+                index_file(&tx, file, &collection_documents(app, file))?;
+            }
+        }
+
+        let history_file = FileState::read(
+            ENTRIES_FILE.to_string(),
+            &app_data_file_path(app, ENTRIES_FILE)?,
+        );
+        if !is_current(&tx, &history_file)? {
+            let entries = history::list_history(app, None, None)?;
+            index_history(
+                &tx,
+                &history_file,
+                entries,
+                |digest| match history::read_history_body(app, digest) {
+                    Ok(body) => body_text(&body),
+                    Err(e) => {
+                        log::warn!("Not searching history body {digest}: {}", e.message);
+                        None
+                    }
+                },
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Documents containing every term of `query`, best first. The last term also matches as a
+    /// prefix, so results keep up while a word is being typed.
+    fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchHit>, AppError> {
+        let query_terms: Vec<String> = tokenize(query).collect();
+        if query_terms.is_empty() {
+            return Ok(Vec::new());
+        }
+        // Terms are alphanumeric, so quoting them is enough to keep them from being read as
+        // FTS5 operators
+        let expression = query_terms
+            .iter()
+            .map(|term| format!("\"{term}\""))
+            .collect::<Vec<_>>()
+            .join(" ")
+            + "*";
+
+        let mut statement = self.db.prepare_cached(
+            "SELECT d.kind, d.id, d.collection_id, d.request_id, d.title, d.method, d.url,
+                d.recorded_at, document_text.name, document_text.url, document_text.headers,
+                document_text.body, bm25(document_text, 3.0, 2.0, 1.0, 1.0) AS rank
+            FROM document_text JOIN documents AS d ON d.document = document_text.rowid
+            WHERE document_text MATCH ?1
+            ORDER BY rank, d.document
+            LIMIT ?2",
+        )?;
+        let hits = statement.query_map(params![expression, limit as i64], |row| {
+            let kind: String = row.get(0)?;
+            let fields: [String; 4] = [
+                row.get::<_, Option<String>>(8)?.unwrap_or_default(),
+                row.get::<_, Option<String>>(9)?.unwrap_or_default(),
+                row.get::<_, Option<String>>(10)?.unwrap_or_default(),
+                row.get::<_, Option<String>>(11)?.unwrap_or_default(),
+            ];
+            let (field, snippet) = snippet(&fields, &query_terms);
+            Ok(SearchHit {
+                kind: if kind == SearchHitKind::History.as_str() {
+                    SearchHitKind::History
+                } else {
+                    SearchHitKind::Request
+                },
+                id: row.get(1)?,
+                collection_id: row.get(2)?,
+                request_id: row.get(3)?,
+                title: row.get(4)?,
+                method: row.get(5)?,
+                url: row.get(6)?,
+                recorded_at: row.get(7)?,
+                field: field.to_string(),
+                snippet,
+                // BM25 scores are negative in SQLite, lower being better
+                score: -row.get::<_, f64>(12)?,
+            })
+        })?;
+        Ok(hits.collect::<Result<_, _>>()?)
+    }
+}
+
+/// Saved requests and history entries matching `query`, best first. Only what changed since the
+/// index was last brought up to date is re-indexed.
+pub fn search_workspace(
+    app: &AppHandle,
+    query: &str,
+    limit: Option<usize>,
+) -> Result<Vec<SearchHit>, AppError> {
+    let mut index = SEARCH_INDEX.lock().unwrap();
+    let index = match &mut *index {
+        Some(index) => index,
+        None => index.insert(SearchIndex::open(&app_data_file_path(app, INDEX_FILE)?)?),
+    };
+    index.refresh(app)?;
+    index.search(query, limit.unwrap_or(DEFAULT_LIMIT))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        FileState, SearchHitKind, SearchIndex, body_text, index_file, index_history, is_current,
+        request_documents, tokenize,
+    };
+    use crate::app_data::history::HistoryEntry;
+    use serde_json::json;
+    use std::fs;
+
+    fn entry(id: &str, url: &str) -> HistoryEntry {
+        HistoryEntry {
+            id: id.to_string(),
+            recorded_at: "2025-01-01T00:00:00Z".to_string(),
+            request_id: None,
+            collection_id: None,
+            method: "GET".to_string(),
+            url: url.to_string(),
+            status: 200,
+            status_text: "OK".to_string(),
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            duration: 12,
+            body_digest: None,
+            body_size: 0,
+        }
+    }
+
+    fn file(source: &str, len: i64) -> FileState {
+        FileState {
+            source: source.to_string(),
+            modified: Some(1),
+            len,
+        }
+    }
+
+    fn collection() -> serde_json::Value {
+        json!({
+            "id": "c1",
+            "requests": {
+                "r1": {
+                    "id": "r1",
+                    "name": "List invoices",
+                    "method": "GET",
+                    "url": "https://billing.example.com/invoices",
+                    "headers": {"h1": {"name": "X-Tenant", "value": "acme"}}
+                },
+                "r2": {
+                    "id": "r2",
+                    "name": "Create customer",
+                    "method": "POST",
+                    "url": "https://billing.example.com/customers",
+                    "headers": {},
+                    "body": {"content": "{\"name\": \"Ada\", \"plan\": \"invoice-monthly\"}"}
+                }
+            }
+        })
+    }
+
+    fn index() -> SearchIndex {
+        let index = SearchIndex::open_in_memory();
+        index_file(
+            &index.db,
+            &file("collections/c1.json", 1),
+            &request_documents(&collection()),
+        )
+        .unwrap();
+        let mut h1 = entry("h1", "https://billing.example.com/customers/7");
+        h1.body_digest = Some("d1".to_string());
+        let mut h2 = entry("h2", "https://cdn.example.com/logo.png");
+        h2.body_digest = Some("d2".to_string());
+        index_history(
+            &index.db,
+            &file("history/entries.jsonl", 1),
+            vec![h1, h2],
+            |digest| match digest {
+                "d1" => body_text(
+                    br#"{"id": 7, "created": "2024-01-01T00:00:00Z", "name": "Grace Hopper", "email": "grace@example.com"}"#,
+                ),
+                _ => body_text(b"\x89PNG\0\0binary"),
+            },
+        )
+        .unwrap();
+        index
+    }
+
+    fn ids(index: &SearchIndex, query: &str) -> Vec<String> {
+        index
+            .search(query, 10)
+            .unwrap()
+            .into_iter()
+            .map(|hit| hit.id)
+            .collect()
+    }
+
+    #[test]
+    fn tokenizes_into_lowercase_terms() {
+        let terms: Vec<String> = tokenize("GET https://API.example.com/v2/users?id=7").collect();
+        assert_eq!(
+            terms,
+            [
+                "get", "https", "api", "example", "com", "v2", "users", "id", "7"
+            ]
+        );
+    }
+
+    #[test]
+    fn ranks_name_matches_above_body_matches() {
+        let hits = index().search("invoice", 10).unwrap();
+        let ids: Vec<&str> = hits.iter().map(|hit| hit.id.as_str()).collect();
+        // The last term matches as a prefix, so "invoice" finds "invoices" too
+        assert_eq!(ids, ["r1", "r2"]);
+        assert_eq!(hits[0].field, "name");
+        assert_eq!(hits[1].field, "body");
+        assert!(hits[0].score > hits[1].score);
+    }
+
+    #[test]
+    fn requires_every_term_and_searches_history_bodies() {
+        let index = index();
+        let hits = index.search("customers grace", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, "h1");
+        assert_eq!(hits[0].kind, SearchHitKind::History);
+        assert_eq!(hits[0].title, "GET https://billing.example.com/customers/7");
+        assert_eq!(hits[0].field, "url");
+
+        let hits = index.search("grace@example", 10).unwrap();
+        assert_eq!(hits[0].field, "body");
+        assert!(
+            hits[0]
+                .snippet
+                .contains("\"name\": \"Grace Hopper\", \"email\": \"grace@")
+        );
+        assert!(hits[0].snippet.starts_with('…'));
+
+        // Binary bodies aren't indexed, and a query of only operators matches nothing
+        assert!(ids(&index, "png binary").is_empty());
+        assert!(ids(&index, " ?! ").is_empty());
+        assert!(ids(&index, "\"NEAR(invoices\" OR *").is_empty());
+    }
+
+    #[test]
+    fn replaces_changed_sources() {
+        let index = index();
+        let mut content = collection();
+        content["requests"].as_object_mut().unwrap().remove("r1");
+        let changed = file("collections/c1.json", 2);
+        assert!(!is_current(&index.db, &changed).unwrap());
+        index_file(&index.db, &changed, &request_documents(&content)).unwrap();
+        assert!(is_current(&index.db, &changed).unwrap());
+        assert_eq!(ids(&index, "invoice"), ["r2"]);
+        assert!(ids(&index, "tenant").is_empty());
+
+        // Pruned history entries are dropped and only new ones have their bodies read
+        let mut read = Vec::new();
+        let mut h3 = entry("h3", "https://billing.example.com/invoices/9");
+        h3.body_digest = Some("d3".to_string());
+        index_history(
+            &index.db,
+            &file("history/entries.jsonl", 2),
+            vec![entry("h2", "https://cdn.example.com/logo.png"), h3],
+            |digest| {
+                read.push(digest.to_string());
+                None
+            },
+        )
+        .unwrap();
+        assert_eq!(read, ["d3"]);
+        assert!(ids(&index, "grace").is_empty());
+        assert_eq!(ids(&index, "invoices 9"), ["h3"]);
+        assert_eq!(ids(&index, "example").len(), 3);
+    }
+
+    #[test]
+    fn keeps_the_index_across_opens() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("search.db");
+        let source = file("collections/c1.json", 1);
+        {
+            let index = SearchIndex::open(&path).unwrap();
+            index_file(&index.db, &source, &request_documents(&collection())).unwrap();
+        }
+        let index = SearchIndex::open(&path).unwrap();
+        assert!(is_current(&index.db, &source).unwrap());
+        assert_eq!(ids(&index, "invoice"), ["r1", "r2"]);
+        drop(index);
+
+        // An unreadable index is started over
+        fs::write(
+            &path,
+            b"not a database, just some bytes that sqlite won't read",
+        )
+        .unwrap();
+        let index = SearchIndex::open(&path).unwrap();
+        assert!(!is_current(&index.db, &source).unwrap());
+        assert!(ids(&index, "invoice").is_empty());
+    }
+
+    #[test]
+    fn limits_hits() {
+        let index = index();
+        assert_eq!(index.search("example", 10).unwrap().len(), 4);
+        assert_eq!(index.search("example", 2).unwrap().len(), 2);
+        assert_eq!(index.search("tenant acme", 10).unwrap()[0].field, "headers");
+    }
+}
@@ -0,0 +1,46 @@
+use super::crypto::keyring_entry;
+use crate::app_error;
+use crate::errors::{AppError, ErrorKind};
+use tauri::AppHandle;
+
+/// Keyring entries of signing keys are namespaced so they can't clash with data keys
+fn entry_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(app_error!(
+            ErrorKind::BadRequest,
+            "Signing keys need a name"
+        ));
+    }
+    Ok(format!("signing-key:{name}"))
+}
+
+/// Stores the key material of an HTTP message signing key (a PEM private key, or a base64
+/// HMAC secret) in the OS keyring, replacing any key with the same name.
+pub fn store_signing_key(app: &AppHandle, name: &str, key: &str) -> Result<(), AppError> {
+    keyring_entry(app, &entry_name(name)?)?
+        .set_password(key.trim())
+        .map_err(|e: keyring::Error| app_error!(ErrorKind::KeyringPlatformFailure, e.to_string()))
+}
+
+/// Reads a signing key from the keyring.
+pub fn load_signing_key(app: &AppHandle, name: &str) -> Result<String, AppError> {
+    keyring_entry(app, &entry_name(name)?)?
+        .get_password()
+        .map_err(|e: keyring::Error| match e {
+            keyring::Error::NoEntry => app_error!(
+                ErrorKind::FileNotFound,
+                format!("No signing key named '{name}' is stored")
+            ),
+            e => app_error!(ErrorKind::KeyringPlatformFailure, e.to_string()),
+        })
+}
+
+/// Removes a signing key from the keyring. Returns whether it existed.
+pub fn delete_signing_key(app: &AppHandle, name: &str) -> Result<bool, AppError> {
+    match keyring_entry(app, &entry_name(name)?)?.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => Err(app_error!(ErrorKind::KeyringPlatformFailure, e.to_string())),
+    }
+}
@@ -0,0 +1,286 @@
+use super::loader::{app_data_file_path, load_app_data, save_app_data};
+use crate::errors::{AppError, ErrorKind};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+const TRASH_INDEX_FILE: &str = "trash.json";
+const TRASH_DIR: &str = "trash";
+
+/// Serializes changes to the trash index, so commands and the scheduled purge can't drop each
+/// other's entries or purge one being restored.
+static TRASH_LOCK: Mutex<()> = Mutex::new(());
+
+/// Trash entries older than this are purged by the background purge task.
+pub const DEFAULT_TRASH_RETENTION_DAYS: u32 = 30;
+
+/// What kind of data a trash entry holds
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TrashKind {
+    /// A whole app data file (e.g. a collection) removed with `delete_app_data`
+    File,
+    /// A JSON value handed over by the frontend (e.g. a request removed from a collection)
+    Item,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashEntry {
+    pub id: String,
+    pub kind: TrashKind,
+    /// Original app data file name, for file entries
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    /// Display label, e.g. the name of the deleted request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Optional context for restoring an item, e.g. the owning collection id
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+    /// Deletion timestamp, ISO 8601
+    pub deleted_at: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TrashIndex {
+    #[serde(default)]
+    entries: BTreeMap<String, TrashEntry>,
+}
+
+impl TrashIndex {
+    /// IDs of entries deleted before `cutoff`. Entries with unparsable timestamps are kept.
+    fn expired(&self, cutoff: DateTime<Utc>) -> Vec<String> {
+        self.entries
+            .values()
+            .filter(|entry| {
+                DateTime::parse_from_rfc3339(&entry.deleted_at)
+                    .map(|deleted| deleted.with_timezone(&Utc) < cutoff)
+                    .unwrap_or(false)
+            })
+            .map(|entry| entry.id.clone())
+            .collect()
+    }
+}
+
+fn trash_file_name(id: &str) -> String {
+    format!("{TRASH_DIR}/{id}.json")
+}
+
+fn load_index(app: &AppHandle) -> Result<TrashIndex, AppError> {
+    match load_app_data(app, TRASH_INDEX_FILE) {
+        Ok(value) => Ok(serde_json::from_value(value)?),
+        Err(e) if e.kind == ErrorKind::FileNotFound => Ok(TrashIndex::default()),
+        Err(e) => Err(e),
+    }
+}
+
+fn save_index(app: &AppHandle, index: &TrashIndex) -> Result<(), AppError> {
+    save_app_data(app, TRASH_INDEX_FILE, serde_json::to_value(index)?)
+}
+
+fn new_entry(kind: TrashKind) -> TrashEntry {
+    TrashEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        kind,
+        file_name: None,
+        label: None,
+        parent_id: None,
+        deleted_at: Utc::now().to_rfc3339(),
+    }
+}
+
+fn remove_payload(app: &AppHandle, id: &str) -> Result<(), AppError> {
+    let path = app_data_file_path(app, &trash_file_name(id))?;
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// The name stored in an app data file (e.g. a collection's), to label its trash entry
+fn file_label(contents: &[u8]) -> Option<String> {
+    let value: Value = serde_json::from_slice(contents).ok()?;
+    value
+        .pointer("/content/name")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+/// Moves an app data file into the trash instead of deleting it.
+/// The file is moved as-is, so encrypted values stay encrypted.
+pub fn trash_app_data(app: &AppHandle, file_name: &str) -> Result<TrashEntry, AppError> {
+    if file_name == TRASH_INDEX_FILE {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            "The trash index cannot be moved to the trash",
+        ));
+    }
+    let _guard = TRASH_LOCK.lock().unwrap();
+    let source = app_data_file_path(app, file_name)?;
+    if !source.exists() {
+        return Err(AppError::new(
+            ErrorKind::FileNotFound,
+            format!("File '{}' does not exist", source.display()),
+        ));
+    }
+
+    let mut entry = new_entry(TrashKind::File);
+    entry.file_name = Some(file_name.to_string());
+    entry.label = file_label(&fs::read(&source)?);
+    let target = app_data_file_path(app, &trash_file_name(&entry.id))?;
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(&source, &target)?;
+
+    let mut index = load_index(app)?;
+    index.entries.insert(entry.id.clone(), entry.clone());
+    save_index(app, &index)?;
+    Ok(entry)
+}
+
+/// Stores a JSON value (e.g. a deleted request) in the trash so it can be restored later.
+pub fn trash_item(
+    app: &AppHandle,
+    label: Option<String>,
+    parent_id: Option<String>,
+    data: Value,
+) -> Result<TrashEntry, AppError> {
+    let _guard = TRASH_LOCK.lock().unwrap();
+    let mut entry = new_entry(TrashKind::Item);
+    entry.label = label;
+    entry.parent_id = parent_id;
+    save_app_data(app, &trash_file_name(&entry.id), data)?;
+
+    let mut index = load_index(app)?;
+    index.entries.insert(entry.id.clone(), entry.clone());
+    save_index(app, &index)?;
+    Ok(entry)
+}
+
+/// Lists trash entries, most recently deleted first.
+pub fn list_trash(app: &AppHandle) -> Result<Vec<TrashEntry>, AppError> {
+    let _guard = TRASH_LOCK.lock().unwrap();
+    let mut entries: Vec<TrashEntry> = load_index(app)?.entries.into_values().collect();
+    entries.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    Ok(entries)
+}
+
+/// Restores a trash entry and removes it from the trash.
+///
+/// File entries are moved back to their original name and `None` is returned; an existing
+/// file is only replaced when `overwrite` is set. Item entries return their stored value
+/// for the frontend to re-insert.
+pub fn restore_trash_entry(
+    app: &AppHandle,
+    id: &str,
+    overwrite: bool,
+) -> Result<Option<Value>, AppError> {
+    let _guard = TRASH_LOCK.lock().unwrap();
+    let mut index = load_index(app)?;
+    let entry = index.entries.get(id).cloned().ok_or_else(|| {
+        AppError::new(
+            ErrorKind::FileNotFound,
+            format!("Trash entry '{id}' does not exist"),
+        )
+    })?;
+
+    let restored = match (entry.kind, entry.file_name.as_deref()) {
+        (TrashKind::File, Some(file_name)) => {
+            let target = app_data_file_path(app, file_name)?;
+            if target.exists() && !overwrite {
+                return Err(AppError::new(
+                    ErrorKind::FileAlreadyExists,
+                    format!("File '{file_name}' already exists"),
+                ));
+            }
+            let source = app_data_file_path(app, &trash_file_name(id))?;
+            fs::rename(source, target)?;
+            None
+        }
+        _ => {
+            let value = load_app_data(app, &trash_file_name(id))?;
+            remove_payload(app, id)?;
+            Some(value)
+        }
+    };
+
+    index.entries.remove(id);
+    save_index(app, &index)?;
+    Ok(restored)
+}
+
+/// Permanently deletes trash entries. With `older_than_days`, only entries deleted before
+/// that many days ago are removed; otherwise the whole trash is emptied.
+/// Returns the number of entries removed.
+pub fn purge_trash(app: &AppHandle, older_than_days: Option<u32>) -> Result<usize, AppError> {
+    let _guard = TRASH_LOCK.lock().unwrap();
+    let mut index = load_index(app)?;
+    let ids = match older_than_days {
+        Some(days) => index.expired(Utc::now() - Duration::days(i64::from(days))),
+        None => index.entries.keys().cloned().collect(),
+    };
+    if ids.is_empty() {
+        return Ok(0);
+    }
+
+    for id in &ids {
+        remove_payload(app, id)?;
+        index.entries.remove(id);
+    }
+    save_index(app, &index)?;
+    log::info!("Purged {} trash entries", ids.len());
+    Ok(ids.len())
+}
+
+/// Purges expired trash entries now and then once a day for the lifetime of the app.
+pub fn schedule_purge(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(24 * 60 * 60));
+        loop {
+            interval.tick().await;
+            if let Err(e) = purge_trash(&app, Some(DEFAULT_TRASH_RETENTION_DAYS)) {
+                log::warn!("Scheduled trash purge failed: {}", e.message);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TrashEntry, TrashIndex, TrashKind};
+    use chrono::{Duration, Utc};
+
+    fn entry(id: &str, deleted_at: String) -> TrashEntry {
+        TrashEntry {
+            id: id.to_string(),
+            kind: TrashKind::File,
+            file_name: Some(format!("{id}.json")),
+            label: None,
+            parent_id: None,
+            deleted_at,
+        }
+    }
+
+    #[test]
+    fn expired_selects_entries_before_cutoff() {
+        let now = Utc::now();
+        let mut index = TrashIndex::default();
+        for e in [
+            entry("old", (now - Duration::days(40)).to_rfc3339()),
+            entry("recent", (now - Duration::days(2)).to_rfc3339()),
+            entry("garbled", "yesterday".to_string()),
+        ] {
+            index.entries.insert(e.id.clone(), e);
+        }
+
+        assert_eq!(index.expired(now - Duration::days(30)), vec!["old"]);
+        assert_eq!(index.expired(now), vec!["old", "recent"]);
+    }
+}
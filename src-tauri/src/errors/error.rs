@@ -20,6 +20,7 @@ pub enum ErrorKind {
     InvalidKeyLength,
     DecryptionFailed,
     EncryptionFailed,
+    KeyMismatch,
 
     // Keyring errors
     KeyringPlatformFailure,
@@ -201,6 +202,13 @@ impl From<tauri::Error> for AppError {
     }
 }
 
+impl From<rusqlite::Error> for AppError {
+    #[track_caller]
+    fn from(err: rusqlite::Error) -> Self {
+        AppError::from_error(ErrorKind::IoError, err, None, Location::caller())
+    }
+}
+
 // Specific error struct for user cancellation
 pub struct UserCancelled;
 
@@ -0,0 +1,297 @@
+//! Declarative checks of a response, evaluated once it has arrived. Each assertion passes or
+//! fails on its own, and the results travel with the response into whatever reports it.
+
+use crate::http_client::charset;
+use crate::http_client::response::ResponseData;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_json_path::JsonPath;
+
+/// A check of a response
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum Assertion {
+    /// The status code is `equals`
+    Status { equals: u16 },
+    /// The header is present and, when given, a value of it equals `equals` or matches the
+    /// regular expression `matches`
+    Header {
+        name: String,
+        equals: Option<String>,
+        matches: Option<String>,
+    },
+    /// The JSONPath selects a node of the JSON body and, when given, one equal to `equals`
+    JsonPath { path: String, equals: Option<Value> },
+    /// The body, read as UTF-8, contains `text`
+    BodyContains { text: String },
+    /// The response took less than `ms` milliseconds
+    LatencyUnder { ms: u64 },
+}
+
+impl Assertion {
+    /// Whether the assertion reads the body, which then has to be at hand
+    pub fn reads_body(&self) -> bool {
+        matches!(self, Self::JsonPath { .. } | Self::BodyContains { .. })
+    }
+}
+
+/// Whether an assertion passed, and why
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AssertionResult {
+    pub assertion: Assertion,
+    pub passed: bool,
+    /// What the response held, e.g. the status or the header's values
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actual: Option<String>,
+    pub message: String,
+}
+
+impl AssertionResult {
+    fn new(assertion: &Assertion, passed: bool, actual: Option<String>, message: String) -> Self {
+        Self {
+            assertion: assertion.clone(),
+            passed,
+            actual,
+            message,
+        }
+    }
+}
+
+/// Evaluates `assertions` against `response`, whose body is `body` wherever it was kept
+pub fn evaluate(
+    assertions: &[Assertion],
+    response: &ResponseData,
+    body: &[u8],
+) -> Vec<AssertionResult> {
+    assertions
+        .iter()
+        .map(|assertion| evaluate_one(assertion, response, body))
+        .collect()
+}
+
+fn evaluate_one(assertion: &Assertion, response: &ResponseData, body: &[u8]) -> AssertionResult {
+    match assertion {
+        Assertion::Status { equals } => {
+            let status = response.status;
+            let message = if status == *equals {
+                format!("Status is {status}")
+            } else {
+                format!("Expected status {equals}, got {status}")
+            };
+            AssertionResult::new(
+                assertion,
+                status == *equals,
+                Some(status.to_string()),
+                message,
+            )
+        }
+        Assertion::Header {
+            name,
+            equals,
+            matches,
+        } => {
+            let values: Vec<&str> = response
+                .headers
+                .iter()
+                .filter(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.as_str())
+                .collect();
+            if values.is_empty() {
+                let message = format!("Header {name} is missing");
+                return AssertionResult::new(assertion, false, None, message);
+            }
+            let actual = Some(values.join(", "));
+            if let Some(expected) = equals {
+                let passed = values.contains(&expected.as_str());
+                let message = if passed {
+                    format!("Header {name} is {expected}")
+                } else {
+                    format!("Expected header {name} to be {expected}")
+                };
+                return AssertionResult::new(assertion, passed, actual, message);
+            }
+            if let Some(pattern) = matches {
+                let regex = match Regex::new(pattern) {
+                    Ok(regex) => regex,
+                    Err(e) => {
+                        let message = format!("Invalid pattern {pattern}: {e}");
+                        return AssertionResult::new(assertion, false, actual, message);
+                    }
+                };
+                let passed = values.iter().any(|value| regex.is_match(value));
+                let message = if passed {
+                    format!("Header {name} matches {pattern}")
+                } else {
+                    format!("Expected header {name} to match {pattern}")
+                };
+                return AssertionResult::new(assertion, passed, actual, message);
+            }
+            AssertionResult::new(assertion, true, actual, format!("Header {name} is present"))
+        }
+        Assertion::JsonPath { path, equals } => {
+            let query = match JsonPath::parse(path) {
+                Ok(query) => query,
+                Err(e) => {
+                    let message = format!("Invalid JSONPath {path}: {e}");
+                    return AssertionResult::new(assertion, false, None, message);
+                }
+            };
+            let document: Value = match serde_json::from_slice(body) {
+                Ok(document) => document,
+                Err(e) => {
+                    let message = format!("Body isn't JSON: {e}");
+                    return AssertionResult::new(assertion, false, None, message);
+                }
+            };
+            let nodes = query.query(&document).all();
+            let actual = match nodes.as_slice() {
+                [] => None,
+                [node] => Some(node.to_string()),
+                nodes => Some(Value::Array(nodes.iter().map(|&n| n.clone()).collect()).to_string()),
+            };
+            let (passed, message) = match equals {
+                Some(expected) => {
+                    let passed = nodes.contains(&expected);
+                    let message = if passed {
+                        format!("{path} equals {expected}")
+                    } else if nodes.is_empty() {
+                        format!("{path} matched nothing, expected {expected}")
+                    } else {
+                        format!("Expected {path} to equal {expected}")
+                    };
+                    (passed, message)
+                }
+                None if nodes.is_empty() => (false, format!("{path} matched nothing")),
+                None => (true, format!("{path} matched")),
+            };
+            AssertionResult::new(assertion, passed, actual, message)
+        }
+        Assertion::BodyContains { text } => {
+            let passed = charset::response_text(response, body).contains(text.as_str());
+            let message = if passed {
+                format!("Body contains \"{text}\"")
+            } else {
+                format!("Expected the body to contain \"{text}\"")
+            };
+            AssertionResult::new(assertion, passed, None, message)
+        }
+        Assertion::LatencyUnder { ms } => {
+            let duration = response.duration;
+            let passed = duration < *ms;
+            let message = if passed {
+                format!("Took {duration} ms, under {ms} ms")
+            } else {
+                format!("Took {duration} ms, expected under {ms} ms")
+            };
+            AssertionResult::new(assertion, passed, Some(duration.to_string()), message)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Assertion, evaluate};
+    use crate::http_client::response::{ResponseData, Timings};
+    use serde_json::json;
+
+    fn response() -> ResponseData {
+        ResponseData {
+            request_id: "r".to_string(),
+            status: 201,
+            status_text: "Created".to_string(),
+            headers: vec![
+                ("Content-Type".to_string(), "application/json".to_string()),
+                ("Set-Cookie".to_string(), "a=1".to_string()),
+                ("Set-Cookie".to_string(), "session=abc".to_string()),
+            ],
+            binary_headers: Vec::new(),
+            cookies: Vec::new(),
+            body: Vec::new(),
+            file_path: None,
+            file_compressed: false,
+            truncated: false,
+            cancelled: false,
+            saved_to: None,
+            resumed_from: None,
+            content_range: None,
+            cache: None,
+            size: 0,
+            duration: 120,
+            timings: Timings::default(),
+            connection: None,
+            revocation: None,
+            rate_limit: None,
+            redirect_chain: None,
+            retries: None,
+            log_summary: None,
+            multistatus: None,
+            soap_fault: None,
+            assertions: None,
+            variables: None,
+            timestamp: String::new(),
+        }
+    }
+
+    fn assertions(value: serde_json::Value) -> Vec<Assertion> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    const BODY: &[u8] = br#"{"id": 7, "tags": ["a", "b"], "owner": {"name": "Ada"}}"#;
+
+    #[test]
+    fn evaluates_each_kind() {
+        let checks = assertions(json!([
+            {"type": "status", "equals": 201},
+            {"type": "header", "name": "content-type", "matches": "^application/(.+\\+)?json"},
+            {"type": "header", "name": "Set-Cookie", "equals": "session=abc"},
+            {"type": "jsonPath", "path": "$.owner.name", "equals": "Ada"},
+            {"type": "jsonPath", "path": "$.tags[*]"},
+            {"type": "bodyContains", "text": "\"id\": 7"},
+            {"type": "latencyUnder", "ms": 500},
+        ]));
+        let results = evaluate(&checks, &response(), BODY);
+        let failed: Vec<&str> = results
+            .iter()
+            .filter(|result| !result.passed)
+            .map(|result| result.message.as_str())
+            .collect();
+        assert!(failed.is_empty(), "{failed:?}");
+        assert_eq!(results[4].actual.as_deref(), Some(r#"["a","b"]"#));
+    }
+
+    #[test]
+    fn reports_why_assertions_fail() {
+        let checks = assertions(json!([
+            {"type": "status", "equals": 200},
+            {"type": "header", "name": "ETag"},
+            {"type": "header", "name": "Content-Type", "matches": "("},
+            {"type": "jsonPath", "path": "$.id", "equals": 8},
+            {"type": "jsonPath", "path": "$.missing"},
+            {"type": "latencyUnder", "ms": 100},
+        ]));
+        let results = evaluate(&checks, &response(), BODY);
+        assert!(results.iter().all(|result| !result.passed));
+        let messages: Vec<&str> = results.iter().map(|r| r.message.as_str()).collect();
+        assert_eq!(messages[0], "Expected status 200, got 201");
+        assert_eq!(messages[1], "Header ETag is missing");
+        assert!(messages[2].starts_with("Invalid pattern ("));
+        assert_eq!(messages[3], "Expected $.id to equal 8");
+        assert_eq!(results[3].actual.as_deref(), Some("7"));
+        assert_eq!(messages[4], "$.missing matched nothing");
+        assert_eq!(messages[5], "Took 120 ms, expected under 100 ms");
+    }
+
+    #[test]
+    fn json_path_fails_on_non_json_bodies() {
+        let checks = assertions(json!([{"type": "jsonPath", "path": "$.id"}]));
+        let results = evaluate(&checks, &response(), b"<html></html>");
+        assert!(!results[0].passed);
+        assert!(results[0].message.starts_with("Body isn't JSON"));
+    }
+}
@@ -1,15 +1,39 @@
 use crate::errors::{AppError, ErrorKind};
 use crate::http_client::engine::{HttpEngine, LogEmitter, TauriLogEmitter};
 use crate::http_client::hyper_engine::HyperEngine;
-use crate::http_client::request::Request;
+use crate::http_client::manager;
+use crate::http_client::request::{
+    ChallengeCredentials, ChallengeScheme, NtlmCredentials, Request,
+};
 use crate::http_client::response::{LogEntry, LogLevel, ResponseData};
 use base64::{Engine as _, engine::general_purpose};
 use chrono::{SecondsFormat, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
 use tauri::AppHandle;
+use tauri_plugin_opener::OpenerExt;
 
-#[derive(Debug, Serialize, Deserialize)]
+mod authorization_code;
+mod client_assertion;
+mod device_code;
+mod id_token;
+mod revocation;
+mod token_cache;
+mod token_refresh;
+
+use self::client_assertion::AssertionSigner;
+use self::token_cache::Cached;
+
+pub use self::revocation::{RevocationResult, RevokeTokenRequest};
+
+/// How long the authorization code flow waits for the user to finish in the browser
+const AUTHORIZATION_TIMEOUT: Duration = Duration::from_secs(300);
+
+// Deserialized once per authentication, so the size of the OAuth2 variant doesn't matter
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(tag = "type", rename_all = "camelCase")]
 pub enum AuthConfig {
     None,
@@ -29,10 +53,23 @@ pub enum AuthConfig {
         value: Option<String>,
         placement: Option<AuthPlacement>,
     },
+    Ntlm {
+        username: Option<String>,
+        password: Option<String>,
+        domain: Option<String>,
+        workstation: Option<String>,
+    },
+    /// HTTP Digest (RFC 7616), answered by the engine when the server challenges for it
+    Digest {
+        username: Option<String>,
+        password: Option<String>,
+    },
     #[serde(rename_all = "camelCase")]
     Oauth2 {
         grant_type: String,
         auth_url: Option<String>,
+        /// Device authorization endpoint for `device_code` (RFC 8628 §3.1)
+        device_authorization_url: Option<String>,
         token_url: Option<String>,
         client_id: Option<String>,
         client_secret: Option<String>,
@@ -40,25 +77,82 @@ pub enum AuthConfig {
         refresh_token: Option<String>,
         token_caching: Option<TokenCachingPolicy>,
         client_auth: Option<ClientAuth>,
+        /// Keyring name of the private key that signs `private_key_jwt` client assertions
+        assertion_key_name: Option<String>,
+        /// Algorithm of `private_key_jwt` client assertions (default RS256)
+        assertion_algorithm: Option<AssertionAlgorithm>,
+        /// `kid` header of `private_key_jwt` client assertions
+        assertion_key_id: Option<String>,
         token_extra_params: Option<HashMap<String, String>>,
+        /// Loopback redirect URI for `authorization_code`; any free port when it has none
+        redirect_uri: Option<String>,
+        /// Send a PKCE challenge with `authorization_code` (default true)
+        use_pkce: Option<bool>,
+        /// Where the user signs in for `authorization_code` (default the system browser)
+        authorization_window: Option<AuthorizationWindow>,
+        /// Check the signature and claims of the ID token issued with the access token
+        validate_id_token: Option<bool>,
+        /// Expected `iss` of ID tokens, whose discovery document locates the JWKS
+        issuer: Option<String>,
+        /// The provider's JWKS, when it shouldn't be discovered from the issuer
+        jwks_url: Option<String>,
+        /// Token revocation endpoint (RFC 7009 §2)
+        revocation_url: Option<String>,
     },
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl AuthConfig {
+    /// The configuration in effect for an item configured with `self`, given its ancestors'
+    /// configurations from the outermost (the collection) in. `Inherit` takes the nearest
+    /// ancestor's that doesn't inherit in turn, and there's no authentication when none has one.
+    pub fn resolve(self, parents: Vec<AuthConfig>) -> AuthConfig {
+        if !matches!(self, AuthConfig::Inherit) {
+            return self;
+        }
+        parents
+            .into_iter()
+            .rev()
+            .find(|parent| !matches!(parent, AuthConfig::Inherit))
+            .unwrap_or(AuthConfig::None)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub enum TokenCachingPolicy {
     Always,
     Never,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum AuthorizationWindow {
+    /// The system browser, redirecting to a loopback listener
+    System,
+    /// A Knurl window that intercepts the redirect, so the redirect URI can be any URI the
+    /// client is registered with
+    Embedded,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub enum ClientAuth {
     Basic,
     Body,
+    /// A JWT signed with the client's private key (RFC 7523 §2.2)
+    PrivateKeyJwt,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// JWS algorithms a client assertion can be signed with (RFC 7518 §3.1)
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AssertionAlgorithm {
+    #[default]
+    RS256,
+    PS256,
+    ES256,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct AuthPlacement {
     pub r#type: String,
@@ -75,22 +169,40 @@ pub struct AuthResult {
     pub cookies: Option<HashMap<String, String>>,
     pub body: Option<HashMap<String, serde_json::Value>>,
     pub expires_at: Option<i64>,
+    /// Credentials the engine negotiates NTLM with; passed on as `Request::ntlm`
+    pub ntlm: Option<NtlmCredentials>,
+    /// Credentials the engine answers `WWW-Authenticate` challenges with; passed on as
+    /// `Request::challenge_auth`
+    pub challenge: Option<ChallengeCredentials>,
+    /// Claims of the ID token issued with the access token, checked against the provider's keys
+    /// when the configuration asks for it
+    pub id_token_claims: Option<serde_json::Value>,
+    /// Refresh token issued with the access token; kept in the token cache, never sent to the
+    /// frontend
+    #[serde(skip)]
+    pub refresh_token: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct OidcDiscovery {
+    pub issuer: Option<String>,
+    pub jwks_uri: Option<String>,
     pub authorization_endpoint: Option<String>,
     pub token_endpoint: Option<String>,
     pub device_authorization_endpoint: Option<String>,
+    pub revocation_endpoint: Option<String>,
 }
 
 // Wire format from remote OIDC server (snake_case per spec). Not sent to frontend.
 #[derive(Debug, Deserialize)]
 struct OidcDiscoveryWire {
+    issuer: Option<String>,
+    jwks_uri: Option<String>,
     authorization_endpoint: Option<String>,
     token_endpoint: Option<String>,
     device_authorization_endpoint: Option<String>,
+    revocation_endpoint: Option<String>,
 }
 
 // Wire format for OAuth2 token response per RFC (snake_case). Not sent to frontend.
@@ -99,6 +211,8 @@ struct TokenResponseWire {
     access_token: String,
     expires_in: Option<u64>,
     token_type: String,
+    refresh_token: Option<String>,
+    id_token: Option<String>,
 }
 
 fn parse_token_response_body(body: &[u8]) -> Result<TokenResponseWire, AppError> {
@@ -139,11 +253,21 @@ fn parse_token_response_body(body: &[u8]) -> Result<TokenResponseWire, AppError>
                 v.as_u64()
                     .or_else(|| v.as_str().and_then(|s| s.parse::<u64>().ok()))
             });
+        let rt = value
+            .get("refresh_token")
+            .or_else(|| value.get("refreshToken"))
+            .and_then(|v| v.as_str());
+        let it = value
+            .get("id_token")
+            .or_else(|| value.get("idToken"))
+            .and_then(|v| v.as_str());
         if let (Some(access_token), Some(token_type)) = (at, tt) {
             return Ok(TokenResponseWire {
                 access_token: access_token.to_string(),
                 token_type: token_type.to_string(),
                 expires_in: ei,
+                refresh_token: rt.map(str::to_string),
+                id_token: it.map(str::to_string),
             });
         }
         // If JSON parsed but required fields missing, fall through to urlencoded parser
@@ -165,11 +289,21 @@ fn parse_token_response_body(body: &[u8]) -> Result<TokenResponseWire, AppError>
             .get("expires_in")
             .or_else(|| form_map.get("expiresIn"))
             .and_then(|s| s.parse::<u64>().ok());
+        let rt = form_map
+            .get("refresh_token")
+            .or_else(|| form_map.get("refreshToken"))
+            .cloned();
+        let it = form_map
+            .get("id_token")
+            .or_else(|| form_map.get("idToken"))
+            .cloned();
         if let (Some(access_token), Some(token_type)) = (at, tt) {
             return Ok(TokenResponseWire {
                 access_token,
                 token_type,
                 expires_in: ei,
+                refresh_token: rt,
+                id_token: it,
             });
         }
     }
@@ -207,6 +341,112 @@ fn log_token_response_metadata(emitter: &dyn LogEmitter, request_id: &str, resp:
     );
 }
 
+/// Identifies a public or confidential client at the token endpoint: with a signed assertion
+/// when there is one, with Basic when configured and there's a secret, otherwise with
+/// `client_id` (and `client_secret`, if any) in the form.
+fn add_client_auth<'a>(
+    client_auth: Option<&ClientAuth>,
+    client_id: &'a str,
+    client_secret: Option<&'a str>,
+    assertion: Option<&'a str>,
+    params: &mut Vec<(&'a str, &'a str)>,
+    headers: &mut HashMap<String, String>,
+) {
+    if let Some(assertion) = assertion {
+        params.push(("client_id", client_id));
+        params.push(("client_assertion_type", client_assertion::ASSERTION_TYPE));
+        params.push(("client_assertion", assertion));
+        return;
+    }
+    match (client_auth, client_secret) {
+        (Some(ClientAuth::Basic), Some(secret)) => {
+            let b64 = general_purpose::STANDARD.encode(format!("{client_id}:{secret}"));
+            headers.insert("Authorization".to_string(), format!("Basic {b64}"));
+        }
+        (_, secret) => {
+            params.push(("client_id", client_id));
+            if let Some(secret) = secret {
+                params.push(("client_secret", secret));
+            }
+        }
+    }
+}
+
+/// POSTs a form-encoded body, as token and device authorization endpoints expect.
+async fn post_form(
+    emitter: Arc<TauriLogEmitter>,
+    request_id: &str,
+    url: &str,
+    params: &[(&str, &str)],
+    mut headers: HashMap<String, String>,
+) -> Result<ResponseData, AppError> {
+    let body = serde_urlencoded::to_string(params)
+        .map_err(|e| AppError::new(ErrorKind::BadRequest, e.to_string()))?
+        .into_bytes();
+    headers.insert(
+        "Content-Type".to_string(),
+        "application/x-www-form-urlencoded".to_string(),
+    );
+    let request = Request {
+        request_id: request_id.to_string(),
+        url: url.to_string(),
+        method: "POST".to_string(),
+        headers: Some(headers.into_iter().collect()),
+        body: Some(body),
+        ..Default::default()
+    };
+    preferred_engine()
+        .execute(request, emitter)
+        .await
+        .map_err(|e| AppError::new(ErrorKind::HttpError, e.to_string()))
+}
+
+/// Authorization header for a token response, logging its receipt and the end of the flow.
+fn token_auth_result(
+    emitter: &dyn LogEmitter,
+    request_id: &str,
+    token_response: TokenResponseWire,
+    id_token_claims: Option<serde_json::Value>,
+) -> AuthResult {
+    let mut headers = HashMap::new();
+    headers.insert(
+        "Authorization".to_string(),
+        format!(
+            "{} {}",
+            token_response.token_type, token_response.access_token
+        ),
+    );
+    emit_auth_log(
+        emitter,
+        request_id,
+        LogLevel::Info,
+        "received_token",
+        "Received authentication token",
+        Some(serde_json::json!({
+            "tokenType": token_response.token_type,
+            "expiresIn": token_response.expires_in,
+        })),
+    );
+    emit_auth_log(
+        emitter,
+        request_id,
+        LogLevel::Info,
+        "complete",
+        "Authentication complete",
+        None,
+    );
+    AuthResult {
+        headers: Some(headers),
+        expires_at: token_response.expires_in.map(|secs| {
+            let now = chrono::Utc::now().timestamp();
+            now + secs as i64 - 300
+        }),
+        refresh_token: token_response.refresh_token,
+        id_token_claims,
+        ..Default::default()
+    }
+}
+
 pub async fn discover_oidc(app: AppHandle, url: String) -> Result<OidcDiscovery, AppError> {
     let request_id = uuid::Uuid::new_v4().to_string();
     let emitter = std::sync::Arc::new(TauriLogEmitter::new(app.clone()));
@@ -241,15 +481,253 @@ pub async fn discover_oidc(app: AppHandle, url: String) -> Result<OidcDiscovery,
     })?;
 
     let discovery = OidcDiscovery {
+        issuer: wire.issuer,
+        jwks_uri: wire.jwks_uri,
         authorization_endpoint: wire.authorization_endpoint,
         token_endpoint: wire.token_endpoint,
         device_authorization_endpoint: wire.device_authorization_endpoint,
+        revocation_endpoint: wire.revocation_endpoint,
     };
 
     Ok(discovery)
 }
 
+/// Authenticates with `config`, resolved against `parents` when it inherits (see
+/// [`AuthConfig::resolve`]). OAuth2 results are cached: with the `always` policy an unexpired
+/// cached token is returned without contacting the server, and with `never` a new token is
+/// always fetched, but still cached. Cached tokens near expiry are refreshed in the background
+/// when the server issued a refresh token, and expired ones are refreshed before falling back
+/// to a new sign-in.
 pub async fn get_authentication_result(
+    app: AppHandle,
+    config: AuthConfig,
+    parents: Vec<AuthConfig>,
+    parent_request_id: Option<String>,
+) -> Result<AuthResult, AppError> {
+    let config = config.resolve(parents);
+    let AuthConfig::Oauth2 { token_caching, .. } = &config else {
+        return authenticate(app, config, parent_request_id).await;
+    };
+    let key = token_cache::cache_key(&config)?;
+    if !matches!(token_caching, Some(TokenCachingPolicy::Always)) {
+        let result = authenticate(app.clone(), config, parent_request_id).await?;
+        cache_token(&app, &key, &result);
+        return Ok(result);
+    }
+
+    match read_token_cache(&app, &key) {
+        Some(Cached::Fresh(cached)) => return Ok(use_cached(&app, parent_request_id, cached)),
+        Some(Cached::Expiring(cached)) => {
+            // Still good for this send; the next one gets the refreshed token
+            if let Some(refresh_token) = cached.refresh_token.clone() {
+                token_refresh::refresh_in_background(app.clone(), config, key, refresh_token);
+            }
+            return Ok(use_cached(&app, parent_request_id, cached));
+        }
+        _ => {}
+    }
+
+    let lock = token_refresh::lock(&key);
+    let _guard = lock.lock().await;
+    // A send that held the lock before this one may have just cached a token
+    match read_token_cache(&app, &key) {
+        Some(Cached::Fresh(cached) | Cached::Expiring(cached)) => {
+            return Ok(use_cached(&app, parent_request_id, cached));
+        }
+        Some(Cached::Expired { refresh_token }) => {
+            match token_refresh::refresh(
+                &app,
+                &config,
+                &key,
+                refresh_token,
+                parent_request_id.clone(),
+            )
+            .await
+            {
+                Ok(result) => return Ok(result),
+                Err(e) => {
+                    log::warn!("Failed to refresh the OAuth token, authenticating again: {e}")
+                }
+            }
+        }
+        None => {}
+    }
+
+    let result = authenticate(app.clone(), config, parent_request_id).await?;
+    cache_token(&app, &key, &result);
+    Ok(result)
+}
+
+/// An unreadable cache shouldn't stop authentication
+fn read_token_cache(app: &AppHandle, key: &str) -> Option<Cached> {
+    token_cache::get(app, key).unwrap_or_else(|e| {
+        log::warn!("Failed to read the OAuth token cache: {e}");
+        None
+    })
+}
+
+fn cache_token(app: &AppHandle, key: &str, result: &AuthResult) {
+    if let Err(e) = token_cache::put(app, key, result) {
+        log::warn!("Failed to cache the OAuth token: {e}");
+    }
+}
+
+fn use_cached(
+    app: &AppHandle,
+    parent_request_id: Option<String>,
+    cached: AuthResult,
+) -> AuthResult {
+    let emitter = TauriLogEmitter::new(app.clone());
+    let req_id = parent_request_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    emit_auth_log(
+        &emitter,
+        &req_id,
+        LogLevel::Info,
+        "cached",
+        "Using cached authentication token",
+        Some(serde_json::json!({ "expiresAt": cached.expires_at })),
+    );
+    cached
+}
+
+/// Revokes `request.token`, or else the tokens cached for `config` (resolved against `parents`),
+/// at the configuration's revocation endpoint (RFC 7009). Cached tokens are dropped from the
+/// cache once revoked.
+pub async fn revoke_token(
+    app: AppHandle,
+    config: AuthConfig,
+    parents: Vec<AuthConfig>,
+    request: RevokeTokenRequest,
+    parent_request_id: Option<String>,
+) -> Result<RevocationResult, AppError> {
+    let config = config.resolve(parents);
+    let AuthConfig::Oauth2 {
+        token_url,
+        client_id,
+        client_secret,
+        client_auth,
+        assertion_key_name,
+        assertion_algorithm,
+        assertion_key_id,
+        revocation_url,
+        ..
+    } = &config
+    else {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            "Only OAuth2 tokens can be revoked".to_string(),
+        ));
+    };
+    let revocation_url = revocation_url.as_deref().ok_or(AppError::new(
+        ErrorKind::BadRequest,
+        "Revocation URL is required".to_string(),
+    ))?;
+    let client_id = client_id.as_deref().ok_or(AppError::new(
+        ErrorKind::BadRequest,
+        "Client ID is required".to_string(),
+    ))?;
+    let signer = AssertionSigner::load(
+        &app,
+        client_auth.as_ref(),
+        assertion_key_name.as_deref(),
+        *assertion_algorithm,
+        assertion_key_id.clone(),
+    )?;
+
+    let emitter = Arc::new(TauriLogEmitter::new(app.clone()));
+    let req_id = parent_request_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    emit_auth_log(
+        &*emitter,
+        &req_id,
+        LogLevel::Info,
+        "revoke",
+        "Starting token revocation",
+        None,
+    );
+
+    let key = token_cache::cache_key(&config)?;
+    // Keep a sign-in or refresh from caching new tokens while the old ones are revoked
+    let lock = token_refresh::lock(&key);
+    let _guard = lock.lock().await;
+    let from_cache = request.token.is_none();
+    let tokens = match request.token {
+        Some(token) => vec![(token, request.token_type_hint)],
+        None => token_cache::tokens(&app, &key)?
+            .map(|cached| revocation::cached_tokens(&cached))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(token, hint)| (token, Some(hint)))
+            .collect(),
+    };
+    if tokens.is_empty() {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            "There are no cached tokens to revoke".to_string(),
+        ));
+    }
+
+    // Assertions are audienced to the authorization server, which the token endpoint stands for
+    let audience = token_url.as_deref().unwrap_or(revocation_url);
+    for (token, hint) in &tokens {
+        let mut params = vec![("token", token.as_str())];
+        if let Some(hint) = hint {
+            params.push(("token_type_hint", hint.as_str()));
+        }
+        let assertion = signer
+            .as_ref()
+            .map(|signer| signer.sign(client_id, audience))
+            .transpose()?;
+        let mut headers = HashMap::new();
+        add_client_auth(
+            client_auth.as_ref(),
+            client_id,
+            client_secret.as_deref(),
+            assertion.as_deref(),
+            &mut params,
+            &mut headers,
+        );
+        let response =
+            post_form(emitter.clone(), &req_id, revocation_url, &params, headers).await?;
+        revocation::check_response(response.status, &response.body)?;
+        emit_auth_log(
+            &*emitter,
+            &req_id,
+            LogLevel::Info,
+            "revoked",
+            "Token revoked",
+            Some(serde_json::json!({ "tokenTypeHint": hint })),
+        );
+    }
+
+    if from_cache {
+        token_cache::remove(&app, &key)?;
+    }
+    Ok(RevocationResult {
+        revoked: tokens.len(),
+        cache_cleared: from_cache,
+    })
+}
+
+/// Basic auth is sent preemptively, as curl's `-u` and other clients do, which saves the
+/// round trip a challenge would cost. The same credentials also answer a Digest challenge,
+/// for servers that reject Basic and ask for Digest. Basic isn't among the schemes answered,
+/// since that would only send the rejected header again.
+fn basic_auth(username: String, password: String) -> AuthResult {
+    let encoded = general_purpose::STANDARD.encode(format!("{username}:{password}"));
+    let mut headers = HashMap::new();
+    headers.insert("Authorization".to_string(), format!("Basic {encoded}"));
+    AuthResult {
+        headers: Some(headers),
+        challenge: Some(ChallengeCredentials {
+            username,
+            password,
+            schemes: vec![ChallengeScheme::Digest],
+        }),
+        ..Default::default()
+    }
+}
+
+async fn authenticate(
     app: AppHandle,
     config: AuthConfig,
     parent_request_id: Option<String>,
@@ -259,6 +737,7 @@ pub async fn get_authentication_result(
     let emitter = std::sync::Arc::new(TauriLogEmitter::new(app.clone()));
 
     match config {
+        AuthConfig::None => Ok(AuthResult::default()),
         AuthConfig::Basic { username, password } => {
             let req_id = parent_request_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
             emit_auth_log(
@@ -269,11 +748,7 @@ pub async fn get_authentication_result(
                 "Starting authentication (basic)",
                 None,
             );
-            let username = username.unwrap_or_default();
-            let password = password.unwrap_or_default();
-            let encoded = general_purpose::STANDARD.encode(format!("{username}:{password}"));
-            let mut headers = HashMap::new();
-            headers.insert("Authorization".to_string(), format!("Basic {encoded}"));
+            let result = basic_auth(username.unwrap_or_default(), password.unwrap_or_default());
             emit_auth_log(
                 &*emitter,
                 &req_id,
@@ -290,8 +765,49 @@ pub async fn get_authentication_result(
                 "Authentication complete",
                 None,
             );
+            Ok(result)
+        }
+        AuthConfig::Ntlm {
+            username,
+            password,
+            domain,
+            workstation,
+        } => {
+            let req_id = parent_request_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+            emit_auth_log(
+                &*emitter,
+                &req_id,
+                LogLevel::Info,
+                "prepared",
+                "NTLM is negotiated when the request is sent",
+                None,
+            );
             Ok(AuthResult {
-                headers: Some(headers),
+                ntlm: Some(NtlmCredentials {
+                    username: username.unwrap_or_default(),
+                    password: password.unwrap_or_default(),
+                    domain: domain.filter(|d| !d.is_empty()),
+                    workstation: workstation.filter(|w| !w.is_empty()),
+                }),
+                ..Default::default()
+            })
+        }
+        AuthConfig::Digest { username, password } => {
+            let req_id = parent_request_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+            emit_auth_log(
+                &*emitter,
+                &req_id,
+                LogLevel::Info,
+                "prepared",
+                "Digest is answered when the server challenges the request",
+                None,
+            );
+            Ok(AuthResult {
+                challenge: Some(ChallengeCredentials {
+                    username: username.unwrap_or_default(),
+                    password: password.unwrap_or_default(),
+                    schemes: vec![ChallengeScheme::Digest],
+                }),
                 ..Default::default()
             })
         }
@@ -558,6 +1074,8 @@ pub async fn get_authentication_result(
         }
         AuthConfig::Oauth2 {
             grant_type,
+            auth_url,
+            device_authorization_url,
             token_url,
             client_id,
             client_secret,
@@ -565,8 +1083,17 @@ pub async fn get_authentication_result(
             refresh_token,
             token_caching: _,
             client_auth,
+            assertion_key_name,
+            assertion_algorithm,
+            assertion_key_id,
             token_extra_params,
-            ..
+            redirect_uri,
+            use_pkce,
+            authorization_window,
+            validate_id_token,
+            issuer,
+            jwks_url,
+            revocation_url: _,
         } => match grant_type.as_str() {
             "client_credentials" => {
                 let req_id = parent_request_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
@@ -586,10 +1113,20 @@ pub async fn get_authentication_result(
                     ErrorKind::BadRequest,
                     "Client ID is required".to_string(),
                 ))?;
-                let client_secret = client_secret.ok_or(AppError::new(
-                    ErrorKind::BadRequest,
-                    "Client Secret is required".to_string(),
-                ))?;
+                let signer = AssertionSigner::load(
+                    &app,
+                    client_auth.as_ref(),
+                    assertion_key_name.as_deref(),
+                    assertion_algorithm,
+                    assertion_key_id,
+                )?;
+                // A signed assertion takes the place of the secret
+                let client_secret = client_secret
+                    .or_else(|| signer.is_some().then(String::new))
+                    .ok_or(AppError::new(
+                        ErrorKind::BadRequest,
+                        "Client Secret is required".to_string(),
+                    ))?;
 
                 let mut params = vec![("grant_type", "client_credentials")];
                 if let Some(s) = &scope {
@@ -597,6 +1134,9 @@ pub async fn get_authentication_result(
                 }
 
                 // client authentication placement (policy: Basic or body)
+                let assertion = signer
+                    .map(|signer| signer.sign(&client_id, &token_url))
+                    .transpose()?;
                 let chosen_auth = client_auth.unwrap_or(ClientAuth::Body);
                 let mut headers = HashMap::new();
                 match chosen_auth {
@@ -617,6 +1157,14 @@ pub async fn get_authentication_result(
                         params.push(("client_id", &client_id));
                         params.push(("client_secret", &client_secret));
                     }
+                    ClientAuth::PrivateKeyJwt => add_client_auth(
+                        None,
+                        &client_id,
+                        None,
+                        assertion.as_deref(),
+                        &mut params,
+                        &mut headers,
+                    ),
                 }
 
                 // extra provider params
@@ -641,7 +1189,7 @@ pub async fn get_authentication_result(
                     request_id: request_id.clone(),
                     url: token_url,
                     method: "POST".to_string(),
-                    headers: Some(addl_headers),
+                    headers: Some(addl_headers.into_iter().collect()),
                     body: Some(body),
                     ..Default::default()
                 };
@@ -699,6 +1247,7 @@ pub async fn get_authentication_result(
                         let now = chrono::Utc::now().timestamp();
                         now + secs as i64 - 300
                     }),
+                    refresh_token: token_response.refresh_token,
                     ..Default::default()
                 })
             }
@@ -724,10 +1273,22 @@ pub async fn get_authentication_result(
                     ErrorKind::BadRequest,
                     "Client ID is required".to_string(),
                 ))?;
-                let client_secret = client_secret.ok_or(AppError::new(
-                    ErrorKind::BadRequest,
-                    "Client Secret is required".to_string(),
-                ))?;
+                let validation = id_token::Validation::from_config(
+                    validate_id_token,
+                    issuer.as_deref(),
+                    jwks_url.as_deref(),
+                    &client_id,
+                )?;
+                let signer = AssertionSigner::load(
+                    &app,
+                    client_auth.as_ref(),
+                    assertion_key_name.as_deref(),
+                    assertion_algorithm,
+                    assertion_key_id,
+                )?;
+                // Public clients refresh with just their client ID (RFC 6749 §6), and a signed
+                // assertion takes the place of the secret
+                let client_secret = client_secret.unwrap_or_default();
                 let refresh_token = refresh_token.ok_or(AppError::new(
                     ErrorKind::BadRequest,
                     "Refresh token is required".to_string(),
@@ -741,6 +1302,9 @@ pub async fn get_authentication_result(
                     params.push(("scope", s));
                 }
 
+                let assertion = signer
+                    .map(|signer| signer.sign(&client_id, &token_url))
+                    .transpose()?;
                 let chosen_auth = client_auth.unwrap_or(ClientAuth::Body);
                 let mut headers = HashMap::new();
                 match chosen_auth {
@@ -759,8 +1323,18 @@ pub async fn get_authentication_result(
                     }
                     ClientAuth::Body => {
                         params.push(("client_id", &client_id));
-                        params.push(("client_secret", &client_secret));
+                        if !client_secret.is_empty() {
+                            params.push(("client_secret", &client_secret));
+                        }
                     }
+                    ClientAuth::PrivateKeyJwt => add_client_auth(
+                        None,
+                        &client_id,
+                        None,
+                        assertion.as_deref(),
+                        &mut params,
+                        &mut headers,
+                    ),
                 }
 
                 if let Some(extra) = &token_extra_params {
@@ -784,7 +1358,7 @@ pub async fn get_authentication_result(
                     request_id: request_id.clone(),
                     url: token_url,
                     method: "POST".to_string(),
-                    headers: Some(addl_headers),
+                    headers: Some(addl_headers.into_iter().collect()),
                     body: Some(body),
                     ..Default::default()
                 };
@@ -805,57 +1379,395 @@ pub async fn get_authentication_result(
                     .map_err(|e| AppError::new(ErrorKind::HttpError, e.to_string()))?;
                 log_token_response_metadata(&*emitter, &req_id, &response_data);
                 let token_response = parse_token_response_body(&response_data.body)?;
+                let id_token_claims = id_token::claims(
+                    emitter.clone(),
+                    &req_id,
+                    token_response.id_token.as_deref(),
+                    validation.as_ref(),
+                )
+                .await?;
+                Ok(token_auth_result(
+                    &*emitter,
+                    &req_id,
+                    token_response,
+                    id_token_claims,
+                ))
+            }
+            "authorization_code" => {
+                let req_id = parent_request_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+                emit_auth_log(
+                    &*emitter,
+                    &req_id,
+                    LogLevel::Info,
+                    "start",
+                    "Starting authentication (oauth2: authorization_code)",
+                    None,
+                );
+                let auth_url = auth_url.ok_or(AppError::new(
+                    ErrorKind::BadRequest,
+                    "Auth URL is required".to_string(),
+                ))?;
+                let token_url = token_url.ok_or(AppError::new(
+                    ErrorKind::BadRequest,
+                    "Token URL is required".to_string(),
+                ))?;
+                let client_id = client_id.ok_or(AppError::new(
+                    ErrorKind::BadRequest,
+                    "Client ID is required".to_string(),
+                ))?;
+                let validation = id_token::Validation::from_config(
+                    validate_id_token,
+                    issuer.as_deref(),
+                    jwks_url.as_deref(),
+                    &client_id,
+                )?;
+                // Public clients have no secret and rely on PKCE
+                let client_secret = client_secret.filter(|s| !s.is_empty());
+                let signer = AssertionSigner::load(
+                    &app,
+                    client_auth.as_ref(),
+                    assertion_key_name.as_deref(),
+                    assertion_algorithm,
+                    assertion_key_id,
+                )?;
 
-                let mut auth_headers = HashMap::new();
-                auth_headers.insert(
-                    "Authorization".to_string(),
-                    format!(
-                        "{} {}",
-                        token_response.token_type, token_response.access_token
-                    ),
+                let embedded = matches!(authorization_window, Some(AuthorizationWindow::Embedded));
+                // The embedded window intercepts the redirect, so nothing has to listen for it
+                let loopback = if embedded {
+                    None
+                } else {
+                    Some(authorization_code::Loopback::bind(redirect_uri.as_deref()).await?)
+                };
+                let redirect_uri = match &loopback {
+                    Some(loopback) => loopback.redirect_uri.clone(),
+                    None => authorization_code::window_redirect_uri(redirect_uri.as_deref()),
+                };
+                let pkce = use_pkce.unwrap_or(true).then(authorization_code::Pkce::new);
+                let state = authorization_code::new_state();
+                let url = authorization_code::authorization_url(
+                    &auth_url,
+                    &client_id,
+                    &redirect_uri,
+                    scope.as_deref(),
+                    &state,
+                    pkce.as_ref().map(|p| p.challenge.as_str()),
+                )?;
+
+                let authorize = async {
+                    let Some(loopback) = &loopback else {
+                        emit_auth_log(
+                            &*emitter,
+                            &req_id,
+                            LogLevel::Info,
+                            "window",
+                            format!(
+                                "Opening a sign-in window; intercepting the redirect to {redirect_uri}"
+                            ),
+                            Some(serde_json::json!({ "url": auth_url, "pkce": pkce.is_some() })),
+                        );
+                        return authorization_code::authorize_in_window(
+                            &app,
+                            &url,
+                            &redirect_uri,
+                            &state,
+                        )
+                        .await;
+                    };
+                    emit_auth_log(
+                        &*emitter,
+                        &req_id,
+                        LogLevel::Info,
+                        "listening",
+                        format!("Listening for the redirect on {redirect_uri}"),
+                        Some(serde_json::json!({ "pkce": pkce.is_some() })),
+                    );
+                    app.opener()
+                        .open_url(url.as_str(), None::<&str>)
+                        .map_err(|e| {
+                            AppError::new(
+                                ErrorKind::TauriError,
+                                format!("Failed to open the browser: {e}"),
+                            )
+                        })?;
+                    emit_auth_log(
+                        &*emitter,
+                        &req_id,
+                        LogLevel::Info,
+                        "browser",
+                        "Opened the system browser; waiting for the user to sign in",
+                        Some(serde_json::json!({ "url": auth_url })),
+                    );
+                    loopback.wait_for_code(&state).await
+                };
+
+                // Cancelling the request stops waiting for the user
+                let token = manager::register(&req_id);
+                let code = tokio::select! {
+                    _ = token.cancelled() => Err(AppError::new(
+                        ErrorKind::UserCancelled,
+                        "Authorization was cancelled",
+                    )),
+                    res = tokio::time::timeout(AUTHORIZATION_TIMEOUT, authorize) => {
+                        res.unwrap_or_else(|_| Err(AppError::new(
+                            ErrorKind::Timeout,
+                            "Timed out waiting for the authorization redirect",
+                        )))
+                    }
+                };
+                manager::remove(&req_id);
+                let code = code?;
+                emit_auth_log(
+                    &*emitter,
+                    &req_id,
+                    LogLevel::Info,
+                    "redirect",
+                    "Received authorization code",
+                    None,
                 );
 
+                let mut params = vec![
+                    ("grant_type", "authorization_code"),
+                    ("code", code.as_str()),
+                    ("redirect_uri", redirect_uri.as_str()),
+                ];
+                if let Some(pkce) = &pkce {
+                    params.push(("code_verifier", &pkce.verifier));
+                }
+                let assertion = signer
+                    .map(|signer| signer.sign(&client_id, &token_url))
+                    .transpose()?;
+                let mut headers = HashMap::new();
+                add_client_auth(
+                    client_auth.as_ref(),
+                    &client_id,
+                    client_secret.as_deref(),
+                    assertion.as_deref(),
+                    &mut params,
+                    &mut headers,
+                );
+                if let Some(extra) = &token_extra_params {
+                    for (k, v) in extra {
+                        params.push((k.as_str(), v.as_str()));
+                    }
+                }
+
                 emit_auth_log(
                     &*emitter,
                     &req_id,
                     LogLevel::Info,
-                    "received_token",
-                    "Received authentication token",
-                    Some(serde_json::json!({
-                        "tokenType": token_response.token_type,
-                        "expiresIn": token_response.expires_in,
-                    })),
+                    "token",
+                    "Exchanging authorization code for an access token via POST",
+                    None,
                 );
+                let response_data =
+                    post_form(emitter.clone(), &req_id, &token_url, &params, headers).await?;
+                log_token_response_metadata(&*emitter, &req_id, &response_data);
+                let token_response = parse_token_response_body(&response_data.body)?;
+                let id_token_claims = id_token::claims(
+                    emitter.clone(),
+                    &req_id,
+                    token_response.id_token.as_deref(),
+                    validation.as_ref(),
+                )
+                .await?;
+                Ok(token_auth_result(
+                    &*emitter,
+                    &req_id,
+                    token_response,
+                    id_token_claims,
+                ))
+            }
+            "device_code" => {
+                let req_id = parent_request_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
                 emit_auth_log(
                     &*emitter,
                     &req_id,
                     LogLevel::Info,
-                    "complete",
-                    "Authentication complete",
+                    "start",
+                    "Starting authentication (oauth2: device_code)",
                     None,
                 );
+                let device_authorization_url = device_authorization_url
+                    .filter(|url| !url.is_empty())
+                    .ok_or(AppError::new(
+                        ErrorKind::BadRequest,
+                        "Device authorization URL is required".to_string(),
+                    ))?;
+                let token_url = token_url.ok_or(AppError::new(
+                    ErrorKind::BadRequest,
+                    "Token URL is required".to_string(),
+                ))?;
+                let client_id = client_id.ok_or(AppError::new(
+                    ErrorKind::BadRequest,
+                    "Client ID is required".to_string(),
+                ))?;
+                let validation = id_token::Validation::from_config(
+                    validate_id_token,
+                    issuer.as_deref(),
+                    jwks_url.as_deref(),
+                    &client_id,
+                )?;
+                let client_secret = client_secret.filter(|s| !s.is_empty());
+                let signer = AssertionSigner::load(
+                    &app,
+                    client_auth.as_ref(),
+                    assertion_key_name.as_deref(),
+                    assertion_algorithm,
+                    assertion_key_id,
+                )?;
 
-                Ok(AuthResult {
-                    headers: Some(auth_headers),
-                    expires_at: token_response.expires_in.map(|secs| {
-                        let now = chrono::Utc::now().timestamp();
-                        now + secs as i64 - 300
-                    }),
-                    ..Default::default()
-                })
+                let assertion = signer
+                    .as_ref()
+                    .map(|signer| signer.sign(&client_id, &token_url))
+                    .transpose()?;
+                let mut params = Vec::new();
+                if let Some(s) = &scope {
+                    params.push(("scope", s.as_str()));
+                }
+                let mut headers = HashMap::new();
+                add_client_auth(
+                    client_auth.as_ref(),
+                    &client_id,
+                    client_secret.as_deref(),
+                    assertion.as_deref(),
+                    &mut params,
+                    &mut headers,
+                );
+                emit_auth_log(
+                    &*emitter,
+                    &req_id,
+                    LogLevel::Info,
+                    "device_authorization",
+                    "Requesting device and user codes via POST",
+                    None,
+                );
+                let response_data = post_form(
+                    emitter.clone(),
+                    &req_id,
+                    &device_authorization_url,
+                    &params,
+                    headers,
+                )
+                .await?;
+                let device = device_code::parse_device_authorization(
+                    response_data.status,
+                    &response_data.body,
+                )?;
+                // The frontend shows this entry so the user knows where to go and what to enter
+                emit_auth_log(
+                    &*emitter,
+                    &req_id,
+                    LogLevel::Info,
+                    "user_code",
+                    format!(
+                        "To sign in, visit {} and enter the code {}",
+                        device.verification_uri, device.user_code
+                    ),
+                    Some(serde_json::json!({
+                        "verificationUri": device.verification_uri,
+                        "verificationUriComplete": device.verification_uri_complete,
+                        "userCode": device.user_code,
+                        "expiresIn": device.expires_in,
+                    })),
+                );
+
+                let expires = Duration::from_secs(device.expires_in);
+                let mut interval = Duration::from_secs(
+                    device
+                        .interval
+                        .unwrap_or(device_code::DEFAULT_INTERVAL_SECS),
+                );
+                let poll = async {
+                    loop {
+                        tokio::time::sleep(interval).await;
+                        // Each poll signs a new assertion, since servers accept one only once
+                        let assertion = signer
+                            .as_ref()
+                            .map(|signer| signer.sign(&client_id, &token_url))
+                            .transpose()?;
+                        let mut params = vec![
+                            ("grant_type", device_code::GRANT_TYPE),
+                            ("device_code", device.device_code.as_str()),
+                        ];
+                        let mut headers = HashMap::new();
+                        add_client_auth(
+                            client_auth.as_ref(),
+                            &client_id,
+                            client_secret.as_deref(),
+                            assertion.as_deref(),
+                            &mut params,
+                            &mut headers,
+                        );
+                        if let Some(extra) = &token_extra_params {
+                            for (k, v) in extra {
+                                params.push((k.as_str(), v.as_str()));
+                            }
+                        }
+                        let response_data =
+                            post_form(emitter.clone(), &req_id, &token_url, &params, headers)
+                                .await?;
+                        match device_code::poll_outcome(response_data.status, &response_data.body)?
+                        {
+                            device_code::PollOutcome::Granted => return Ok(response_data),
+                            device_code::PollOutcome::Pending => {}
+                            device_code::PollOutcome::SlowDown => {
+                                interval += Duration::from_secs(device_code::SLOW_DOWN_SECS);
+                                emit_auth_log(
+                                    &*emitter,
+                                    &req_id,
+                                    LogLevel::Debug,
+                                    "slow_down",
+                                    format!(
+                                        "Server asked to slow down; polling every {}s",
+                                        interval.as_secs()
+                                    ),
+                                    None,
+                                );
+                            }
+                        }
+                    }
+                };
+
+                // Cancelling the request stops polling
+                let token = manager::register(&req_id);
+                let response_data = tokio::select! {
+                    _ = token.cancelled() => Err(AppError::new(
+                        ErrorKind::UserCancelled,
+                        "Authorization was cancelled",
+                    )),
+                    res = tokio::time::timeout(expires, poll) => {
+                        res.unwrap_or_else(|_| Err(AppError::new(
+                            ErrorKind::Timeout,
+                            "The device code expired before the user approved it",
+                        )))
+                    }
+                };
+                manager::remove(&req_id);
+                let response_data = response_data?;
+                log_token_response_metadata(&*emitter, &req_id, &response_data);
+                let token_response = parse_token_response_body(&response_data.body)?;
+                let id_token_claims = id_token::claims(
+                    emitter.clone(),
+                    &req_id,
+                    token_response.id_token.as_deref(),
+                    validation.as_ref(),
+                )
+                .await?;
+                Ok(token_auth_result(
+                    &*emitter,
+                    &req_id,
+                    token_response,
+                    id_token_claims,
+                ))
             }
-            "device_code" => Err(AppError::new(
-                ErrorKind::NotImplemented,
-                "Device code not yet implemented".to_string(),
-            )),
             _ => Err(AppError::new(
                 ErrorKind::BadRequest,
                 "Unsupported grant type".to_string(),
             )),
         },
-        _ => Err(AppError::new(
+        AuthConfig::Inherit => Err(AppError::new(
             ErrorKind::BadRequest,
-            "Unsupported authentication type".to_string(),
+            "Inherited authentication must be resolved against its parents".to_string(),
         )),
     }
 }
@@ -887,3 +1799,71 @@ fn emit_auth_log(
     };
     emitter.emit(entry);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{AuthConfig, basic_auth};
+    use crate::http_client::request::ChallengeScheme;
+
+    fn bearer(token: &str) -> AuthConfig {
+        AuthConfig::Bearer {
+            token: Some(token.to_string()),
+            scheme: None,
+            placement: None,
+        }
+    }
+
+    fn token(config: AuthConfig) -> Option<String> {
+        match config {
+            AuthConfig::Bearer { token, .. } => token,
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn inherit_resolves_to_the_nearest_parent_that_does_not_inherit() {
+        let chain = || vec![bearer("collection"), AuthConfig::Inherit, bearer("folder")];
+        assert_eq!(
+            token(AuthConfig::Inherit.resolve(chain())).as_deref(),
+            Some("folder")
+        );
+
+        let inheriting_folder = vec![bearer("collection"), AuthConfig::Inherit];
+        assert_eq!(
+            token(AuthConfig::Inherit.resolve(inheriting_folder)).as_deref(),
+            Some("collection")
+        );
+        // Items with their own configuration ignore their parents'
+        assert_eq!(
+            token(bearer("own").resolve(chain())).as_deref(),
+            Some("own")
+        );
+        assert!(matches!(
+            AuthConfig::Inherit.resolve(vec![AuthConfig::Inherit]),
+            AuthConfig::None
+        ));
+        assert!(matches!(
+            AuthConfig::Inherit.resolve(Vec::new()),
+            AuthConfig::None
+        ));
+    }
+
+    #[test]
+    fn basic_auth_is_preemptive_and_answers_only_digest_challenges() {
+        let result = basic_auth("u".to_string(), "p".to_string());
+        assert_eq!(
+            result
+                .headers
+                .unwrap()
+                .get("Authorization")
+                .map(String::as_str),
+            Some("Basic dTpw")
+        );
+        let challenge = result.challenge.unwrap();
+        assert_eq!(
+            (challenge.username.as_str(), challenge.password.as_str()),
+            ("u", "p")
+        );
+        assert_eq!(challenge.schemes, vec![ChallengeScheme::Digest]);
+    }
+}
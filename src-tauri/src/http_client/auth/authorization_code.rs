@@ -0,0 +1,423 @@
+//! Authorization Code grant (RFC 6749 §4.1) for a native app: PKCE (RFC 7636), and either a
+//! loopback redirect listener (RFC 8252 §7.3) or an embedded window that intercepts the redirect.
+
+use crate::errors::{AppError, ErrorKind};
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Url, WebviewUrl, WebviewWindow, WebviewWindowBuilder, WindowEvent};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+
+const DEFAULT_REDIRECT_URI: &str = "http://127.0.0.1/callback";
+
+/// Largest callback request read; the browser sends only a request line and headers
+const MAX_CALLBACK_BYTES: usize = 16 * 1024;
+
+const DONE_PAGE: &str = "<!doctype html><html><body style=\"font-family:sans-serif\">\
+<h3>Authentication complete</h3><p>You can close this window and return to Knurl.</p></body></html>";
+const FAILED_PAGE: &str = "<!doctype html><html><body style=\"font-family:sans-serif\">\
+<h3>Authentication failed</h3><p>Return to Knurl for details.</p></body></html>";
+
+/// A PKCE code verifier and its S256 challenge
+pub(super) struct Pkce {
+    pub verifier: String,
+    pub challenge: String,
+}
+
+impl Pkce {
+    pub fn new() -> Self {
+        let mut bytes = [0u8; 32];
+        rand::rng().fill_bytes(&mut bytes);
+        let verifier = URL_SAFE_NO_PAD.encode(bytes);
+        let challenge = s256_challenge(&verifier);
+        Self {
+            verifier,
+            challenge,
+        }
+    }
+}
+
+fn s256_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+/// Random value tying the redirect to this attempt (RFC 6749 §10.12)
+pub(super) fn new_state() -> String {
+    let mut bytes = [0u8; 16];
+    rand::rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Builds the URL the browser opens to start the flow.
+pub(super) fn authorization_url(
+    auth_url: &str,
+    client_id: &str,
+    redirect_uri: &str,
+    scope: Option<&str>,
+    state: &str,
+    challenge: Option<&str>,
+) -> Result<String, AppError> {
+    let mut url = Url::parse(auth_url).map_err(|e| {
+        AppError::new(
+            ErrorKind::BadRequest,
+            format!("Invalid authorization URL: {e}"),
+        )
+    })?;
+    {
+        let mut query = url.query_pairs_mut();
+        query
+            .append_pair("response_type", "code")
+            .append_pair("client_id", client_id)
+            .append_pair("redirect_uri", redirect_uri)
+            .append_pair("state", state);
+        if let Some(scope) = scope.filter(|s| !s.is_empty()) {
+            query.append_pair("scope", scope);
+        }
+        if let Some(challenge) = challenge {
+            query
+                .append_pair("code_challenge", challenge)
+                .append_pair("code_challenge_method", "S256");
+        }
+    }
+    Ok(url.into())
+}
+
+/// Listener for the authorization server's redirect back to the app
+pub(super) struct Loopback {
+    listener: TcpListener,
+    path: String,
+    /// Redirect URI with the port actually bound, sent in both the authorization and token
+    /// requests
+    pub redirect_uri: String,
+}
+
+impl Loopback {
+    /// Binds the host and port of `redirect_uri`, which must be a loopback `http` URI. A URI
+    /// without a port binds an ephemeral one.
+    pub async fn bind(redirect_uri: Option<&str>) -> Result<Self, AppError> {
+        let raw = redirect_uri
+            .filter(|uri| !uri.trim().is_empty())
+            .unwrap_or(DEFAULT_REDIRECT_URI);
+        let mut url = Url::parse(raw).map_err(|e| {
+            AppError::new(ErrorKind::BadRequest, format!("Invalid redirect URI: {e}"))
+        })?;
+        let host = url.host_str().unwrap_or_default().to_string();
+        let bind_host = match host.as_str() {
+            "127.0.0.1" | "localhost" => "127.0.0.1",
+            "[::1]" => "[::1]",
+            _ => {
+                return Err(AppError::new(
+                    ErrorKind::BadRequest,
+                    "Redirect URI must use a loopback host (127.0.0.1, [::1] or localhost)",
+                ));
+            }
+        };
+        if url.scheme() != "http" {
+            return Err(AppError::new(
+                ErrorKind::BadRequest,
+                "Redirect URI must use http; the redirect never leaves this machine",
+            ));
+        }
+
+        let listener = TcpListener::bind(format!("{bind_host}:{}", url.port().unwrap_or(0)))
+            .await
+            .map_err(|e| {
+                AppError::new(
+                    ErrorKind::IoError,
+                    format!("Failed to listen for the redirect on {host}: {e}"),
+                )
+            })?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| AppError::new(ErrorKind::IoError, e.to_string()))?
+            .port();
+        let _ = url.set_port(Some(port));
+        Ok(Self {
+            listener,
+            path: url.path().to_string(),
+            redirect_uri: url.into(),
+        })
+    }
+
+    /// Waits for the browser to arrive at the redirect URI and returns the authorization code.
+    /// Requests for other paths (e.g. `/favicon.ico`) are answered with 404 and ignored.
+    pub async fn wait_for_code(&self, state: &str) -> Result<String, AppError> {
+        loop {
+            let (mut stream, _) = self
+                .listener
+                .accept()
+                .await
+                .map_err(|e| AppError::new(ErrorKind::IoError, e.to_string()))?;
+
+            let mut buf = Vec::new();
+            let mut chunk = [0u8; 1024];
+            while !buf.windows(4).any(|w| w == b"\r\n\r\n") && buf.len() < MAX_CALLBACK_BYTES {
+                match stream.read(&mut chunk).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => buf.extend_from_slice(&chunk[..n]),
+                }
+            }
+            let request = String::from_utf8_lossy(&buf);
+            let target = request
+                .lines()
+                .next()
+                .and_then(|line| line.split_whitespace().nth(1))
+                .unwrap_or_default();
+
+            let Some(result) = parse_callback(target, &self.path, state) else {
+                let _ = stream
+                    .write_all(
+                        b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    )
+                    .await;
+                continue;
+            };
+            let page = if result.is_ok() {
+                DONE_PAGE
+            } else {
+                FAILED_PAGE
+            };
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{page}",
+                page.len()
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+            let _ = stream.shutdown().await;
+            return result;
+        }
+    }
+}
+
+/// Reads the code from the redirect's request target, checking `state`. Returns None when the
+/// target isn't the redirect path.
+fn parse_callback(target: &str, path: &str, state: &str) -> Option<Result<String, AppError>> {
+    let url = Url::parse("http://127.0.0.1").ok()?.join(target).ok()?;
+    (url.path() == path).then(|| code_from_redirect(&url, state))
+}
+
+/// Reads the code from the redirect URL, checking `state`.
+fn code_from_redirect(url: &Url, state: &str) -> Result<String, AppError> {
+    let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+
+    if let Some(error) = params.get("error") {
+        let description = params
+            .get("error_description")
+            .map(|d| format!(" – {d}"))
+            .unwrap_or_default();
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            format!("Authorization failed: {error}{description}"),
+        ));
+    }
+    if params.get("state").map(String::as_str) != Some(state) {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            "Authorization redirect state does not match; ignoring the code",
+        ));
+    }
+    params.get("code").cloned().ok_or_else(|| {
+        AppError::new(
+            ErrorKind::BadRequest,
+            "Authorization redirect has no code parameter",
+        )
+    })
+}
+
+/// Redirect URI for the embedded window: the configured one as is, since nothing listens on it
+pub(super) fn window_redirect_uri(redirect_uri: Option<&str>) -> String {
+    redirect_uri
+        .map(str::trim)
+        .filter(|uri| !uri.is_empty())
+        .unwrap_or(DEFAULT_REDIRECT_URI)
+        .to_string()
+}
+
+/// Whether a navigation is to the redirect URI, ignoring its query
+fn is_redirect(url: &Url, redirect: &Url) -> bool {
+    url.scheme() == redirect.scheme()
+        && url.host_str() == redirect.host_str()
+        && url.port_or_known_default() == redirect.port_or_known_default()
+        && url.path() == redirect.path()
+}
+
+type CodeSender = Arc<Mutex<Option<oneshot::Sender<Result<String, AppError>>>>>;
+
+fn finish(sender: &CodeSender, result: Result<String, AppError>) {
+    if let Some(sender) = sender.lock().unwrap().take() {
+        let _ = sender.send(result);
+    }
+}
+
+/// Closes the sign-in window when the flow ends, including when it's cancelled or times out
+struct CloseOnDrop(WebviewWindow);
+
+impl Drop for CloseOnDrop {
+    fn drop(&mut self) {
+        let _ = self.0.close();
+    }
+}
+
+/// Opens the authorization URL in a Knurl window and returns the code from the redirect, which
+/// the window intercepts instead of loading. For IdPs that don't allow loopback redirects on
+/// arbitrary ports.
+pub(super) async fn authorize_in_window(
+    app: &AppHandle,
+    url: &str,
+    redirect_uri: &str,
+    state: &str,
+) -> Result<String, AppError> {
+    let redirect = Url::parse(redirect_uri)
+        .map_err(|e| AppError::new(ErrorKind::BadRequest, format!("Invalid redirect URI: {e}")))?;
+    let start = Url::parse(url).map_err(|e| {
+        AppError::new(
+            ErrorKind::BadRequest,
+            format!("Invalid authorization URL: {e}"),
+        )
+    })?;
+
+    let (sender, receiver) = oneshot::channel();
+    let sender: CodeSender = Arc::new(Mutex::new(Some(sender)));
+    let state = state.to_string();
+    let window = WebviewWindowBuilder::new(
+        app,
+        format!("oauth-{}", uuid::Uuid::new_v4()),
+        WebviewUrl::External(start),
+    )
+    .title("Sign in")
+    .inner_size(520.0, 720.0)
+    .center()
+    .on_navigation({
+        let sender = sender.clone();
+        move |url| {
+            if !is_redirect(url, &redirect) {
+                return true;
+            }
+            finish(&sender, code_from_redirect(url, &state));
+            false
+        }
+    })
+    .build()
+    .map_err(|e| {
+        AppError::new(
+            ErrorKind::TauriError,
+            format!("Failed to open the sign-in window: {e}"),
+        )
+    })?;
+    window.on_window_event({
+        let sender = sender.clone();
+        move |event| {
+            if matches!(event, WindowEvent::Destroyed) {
+                finish(
+                    &sender,
+                    Err(AppError::new(
+                        ErrorKind::UserCancelled,
+                        "The sign-in window was closed",
+                    )),
+                );
+            }
+        }
+    });
+    let _window = CloseOnDrop(window);
+
+    receiver.await.unwrap_or_else(|_| {
+        Err(AppError::new(
+            ErrorKind::UserCancelled,
+            "The sign-in window was closed",
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Loopback, authorization_url, is_redirect, parse_callback, s256_challenge};
+    use tauri::Url;
+
+    #[test]
+    fn s256_challenge_matches_rfc7636_example() {
+        // RFC 7636 Appendix B
+        assert_eq!(
+            s256_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn builds_authorization_url() {
+        let url = authorization_url(
+            "https://auth.example.com/authorize?audience=api",
+            "app",
+            "http://127.0.0.1:8765/callback",
+            Some("openid profile"),
+            "xyz",
+            Some("challenge"),
+        )
+        .unwrap();
+        assert_eq!(
+            url,
+            "https://auth.example.com/authorize?audience=api&response_type=code&client_id=app\
+&redirect_uri=http%3A%2F%2F127.0.0.1%3A8765%2Fcallback&state=xyz&scope=openid+profile\
+&code_challenge=challenge&code_challenge_method=S256"
+        );
+    }
+
+    #[test]
+    fn parses_callback() {
+        let code = parse_callback("/callback?code=abc&state=xyz", "/callback", "xyz");
+        assert_eq!(code.unwrap().unwrap(), "abc");
+
+        assert!(parse_callback("/favicon.ico", "/callback", "xyz").is_none());
+        assert!(
+            parse_callback("/callback?code=abc&state=other", "/callback", "xyz")
+                .unwrap()
+                .is_err()
+        );
+        let denied = parse_callback(
+            "/callback?error=access_denied&error_description=User+declined&state=xyz",
+            "/callback",
+            "xyz",
+        )
+        .unwrap()
+        .unwrap_err();
+        assert!(denied.message.contains("access_denied – User declined"));
+    }
+
+    #[test]
+    fn matches_redirect_navigation() {
+        let redirect = Url::parse("https://app.example.com/oauth/callback").unwrap();
+        let matches = |url: &str| is_redirect(&Url::parse(url).unwrap(), &redirect);
+        assert!(matches(
+            "https://app.example.com/oauth/callback?code=abc&state=xyz"
+        ));
+        assert!(matches("https://app.example.com:443/oauth/callback"));
+        assert!(!matches(
+            "https://app.example.com/oauth/authorize?redirect_uri=x"
+        ));
+        assert!(!matches("http://app.example.com/oauth/callback"));
+        assert!(!matches("https://idp.example.com/oauth/callback"));
+    }
+
+    #[tokio::test]
+    async fn binds_ephemeral_port_and_rejects_remote_hosts() {
+        let loopback = Loopback::bind(None).await.unwrap();
+        let port = loopback.listener.local_addr().unwrap().port();
+        assert_eq!(
+            loopback.redirect_uri,
+            format!("http://127.0.0.1:{port}/callback")
+        );
+
+        assert!(
+            Loopback::bind(Some("http://example.com/callback"))
+                .await
+                .is_err()
+        );
+        assert!(
+            Loopback::bind(Some("https://127.0.0.1/callback"))
+                .await
+                .is_err()
+        );
+    }
+}
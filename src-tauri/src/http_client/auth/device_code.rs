@@ -0,0 +1,165 @@
+//! Device Authorization grant (RFC 8628): the user approves the request on another device
+//! while the app polls the token endpoint.
+
+use crate::errors::{AppError, ErrorKind};
+use serde::Deserialize;
+
+pub(super) const GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// Poll interval when the server doesn't give one (RFC 8628 §3.2)
+pub(super) const DEFAULT_INTERVAL_SECS: u64 = 5;
+
+/// Added to the interval each time the server answers `slow_down` (RFC 8628 §3.5)
+pub(super) const SLOW_DOWN_SECS: u64 = 5;
+
+/// Device authorization response (RFC 8628 §3.2)
+#[derive(Debug, Deserialize)]
+pub(super) struct DeviceAuthorization {
+    pub device_code: String,
+    pub user_code: String,
+    // Google still sends the draft's `verification_url`
+    #[serde(alias = "verification_url")]
+    pub verification_uri: String,
+    pub verification_uri_complete: Option<String>,
+    pub expires_in: u64,
+    pub interval: Option<u64>,
+}
+
+/// What a poll of the token endpoint means for the flow
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum PollOutcome {
+    /// The response carries the tokens
+    Granted,
+    /// The user hasn't finished yet
+    Pending,
+    /// Keep polling, less often
+    SlowDown,
+}
+
+fn oauth_error(body: &[u8]) -> Option<(String, Option<String>)> {
+    let value: serde_json::Value = serde_json::from_slice(body).ok()?;
+    let error = value.get("error")?.as_str()?.to_string();
+    let description = value
+        .get("error_description")
+        .and_then(|d| d.as_str())
+        .map(str::to_string);
+    Some((error, description))
+}
+
+fn describe(error: &str, description: Option<String>) -> String {
+    match description {
+        Some(description) if !description.is_empty() => format!("{error} – {description}"),
+        _ => error.to_string(),
+    }
+}
+
+pub(super) fn parse_device_authorization(
+    status: u16,
+    body: &[u8],
+) -> Result<DeviceAuthorization, AppError> {
+    if let Some((error, description)) = oauth_error(body) {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            format!(
+                "Device authorization failed: {}",
+                describe(&error, description)
+            ),
+        ));
+    }
+    serde_json::from_slice(body).map_err(|e| {
+        AppError::new(
+            ErrorKind::JsonError,
+            format!("Failed to parse device authorization response (status {status}): {e}"),
+        )
+    })
+}
+
+/// Classifies a token endpoint response while the user is approving the device.
+pub(super) fn poll_outcome(status: u16, body: &[u8]) -> Result<PollOutcome, AppError> {
+    if (200..300).contains(&status) {
+        return Ok(PollOutcome::Granted);
+    }
+    let Some((error, description)) = oauth_error(body) else {
+        return Err(AppError::new(
+            ErrorKind::HttpError,
+            format!("Token endpoint returned status {status} without an OAuth error"),
+        ));
+    };
+    match error.as_str() {
+        "authorization_pending" => Ok(PollOutcome::Pending),
+        "slow_down" => Ok(PollOutcome::SlowDown),
+        "access_denied" => Err(AppError::new(
+            ErrorKind::UserCancelled,
+            "The user denied the device authorization request",
+        )),
+        "expired_token" => Err(AppError::new(
+            ErrorKind::Timeout,
+            "The device code expired before the user approved it",
+        )),
+        _ => Err(AppError::new(
+            ErrorKind::BadRequest,
+            format!("OAuth token error: {}", describe(&error, description)),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PollOutcome, parse_device_authorization, poll_outcome};
+    use crate::errors::ErrorKind;
+
+    #[test]
+    fn parses_device_authorization_response() {
+        // RFC 8628 §3.2
+        let body = br#"{
+            "device_code": "GmRhmhcxhwAzkoEqiMEg_DnyEysNkuNhszIySk9eS",
+            "user_code": "WDJB-MJHT",
+            "verification_uri": "https://example.com/device",
+            "verification_uri_complete": "https://example.com/device?user_code=WDJB-MJHT",
+            "expires_in": 1800,
+            "interval": 5
+        }"#;
+        let device = parse_device_authorization(200, body).unwrap();
+        assert_eq!(device.user_code, "WDJB-MJHT");
+        assert_eq!(device.verification_uri, "https://example.com/device");
+        assert_eq!(device.interval, Some(5));
+
+        let google = br#"{"device_code":"d","user_code":"u","verification_url":"https://www.google.com/device","expires_in":1800}"#;
+        let device = parse_device_authorization(200, google).unwrap();
+        assert_eq!(device.verification_uri, "https://www.google.com/device");
+        assert_eq!(device.interval, None);
+
+        let error = parse_device_authorization(400, br#"{"error":"invalid_client"}"#).unwrap_err();
+        assert!(error.message.contains("invalid_client"));
+    }
+
+    #[test]
+    fn classifies_poll_responses() {
+        let outcome = |status, body: &str| poll_outcome(status, body.as_bytes());
+        assert_eq!(
+            outcome(200, r#"{"access_token":"x","token_type":"Bearer"}"#).unwrap(),
+            PollOutcome::Granted
+        );
+        assert_eq!(
+            outcome(400, r#"{"error":"authorization_pending"}"#).unwrap(),
+            PollOutcome::Pending
+        );
+        assert_eq!(
+            outcome(400, r#"{"error":"slow_down"}"#).unwrap(),
+            PollOutcome::SlowDown
+        );
+        assert_eq!(
+            outcome(400, r#"{"error":"access_denied"}"#)
+                .unwrap_err()
+                .kind,
+            ErrorKind::UserCancelled
+        );
+        assert_eq!(
+            outcome(400, r#"{"error":"expired_token"}"#)
+                .unwrap_err()
+                .kind,
+            ErrorKind::Timeout
+        );
+        assert!(outcome(500, "oops").is_err());
+    }
+}
@@ -0,0 +1,147 @@
+//! Token revocation (RFC 7009): tells the authorization server that a token is no longer
+//! needed, so it stops honouring it before it expires.
+
+use super::AuthResult;
+use crate::errors::{AppError, ErrorKind};
+use serde::{Deserialize, Serialize};
+
+/// What kind of token is being revoked, so the server needn't search for it (RFC 7009 §2.1)
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenTypeHint {
+    AccessToken,
+    RefreshToken,
+}
+
+impl TokenTypeHint {
+    pub(super) fn as_str(self) -> &'static str {
+        match self {
+            TokenTypeHint::AccessToken => "access_token",
+            TokenTypeHint::RefreshToken => "refresh_token",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RevokeTokenRequest {
+    /// The token to revoke; the tokens cached for the configuration when there's none
+    pub token: Option<String>,
+    pub token_type_hint: Option<TokenTypeHint>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RevocationResult {
+    /// How many tokens the server accepted for revocation
+    pub revoked: usize,
+    /// Whether the configuration's cached tokens were revoked and dropped from the cache
+    pub cache_cleared: bool,
+}
+
+/// The tokens of a cached result, refresh token first, since revoking it usually takes the
+/// access tokens issued with it along (RFC 7009 §2.1).
+pub(super) fn cached_tokens(result: &AuthResult) -> Vec<(String, TokenTypeHint)> {
+    let mut tokens = Vec::new();
+    if let Some(refresh_token) = &result.refresh_token {
+        tokens.push((refresh_token.clone(), TokenTypeHint::RefreshToken));
+    }
+    let access_token = result.headers.as_ref().and_then(|headers| {
+        headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("authorization"))
+            .and_then(|(_, value)| value.split_once(' '))
+            .map(|(_, token)| token.trim().to_string())
+    });
+    if let Some(access_token) = access_token.filter(|t| !t.is_empty()) {
+        tokens.push((access_token, TokenTypeHint::AccessToken));
+    }
+    tokens
+}
+
+/// Checks the revocation endpoint's answer. The server answers 200 whether or not it knew the
+/// token, since either way the client can no longer use it (RFC 7009 §2.2).
+pub(super) fn check_response(status: u16, body: &[u8]) -> Result<(), AppError> {
+    if (200..300).contains(&status) {
+        return Ok(());
+    }
+    if status == 503 {
+        return Err(AppError::new(
+            ErrorKind::HttpError,
+            "Token revocation failed: the server is unavailable, try again later".to_string(),
+        ));
+    }
+    let value: serde_json::Value = serde_json::from_slice(body).unwrap_or_default();
+    let message = match value.get("error").and_then(|e| e.as_str()) {
+        // The server can't revoke tokens of this kind (RFC 7009 §2.2.1)
+        Some("unsupported_token_type") => {
+            "the server doesn't support revoking this type of token".to_string()
+        }
+        Some(error) => match value.get("error_description").and_then(|d| d.as_str()) {
+            Some(description) if !description.is_empty() => format!("{error} – {description}"),
+            _ => error.to_string(),
+        },
+        None => format!("status {status}"),
+    };
+    Err(AppError::new(
+        ErrorKind::BadRequest,
+        format!("Token revocation failed: {message}"),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TokenTypeHint, cached_tokens, check_response};
+    use crate::http_client::auth::AuthResult;
+    use std::collections::HashMap;
+
+    #[test]
+    fn cached_results_revoke_the_refresh_token_first() {
+        let result = AuthResult {
+            headers: Some(HashMap::from([(
+                "Authorization".to_string(),
+                "Bearer abc".to_string(),
+            )])),
+            refresh_token: Some("r1".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            cached_tokens(&result),
+            vec![
+                ("r1".to_string(), TokenTypeHint::RefreshToken),
+                ("abc".to_string(), TokenTypeHint::AccessToken),
+            ]
+        );
+        assert!(cached_tokens(&AuthResult::default()).is_empty());
+    }
+
+    #[test]
+    fn responses_succeed_with_any_2xx() {
+        assert!(check_response(200, b"").is_ok());
+        assert!(check_response(204, b"").is_ok());
+
+        let unsupported = check_response(400, br#"{"error":"unsupported_token_type"}"#)
+            .unwrap_err()
+            .message;
+        assert!(unsupported.contains("doesn't support"));
+        let invalid = check_response(
+            401,
+            br#"{"error":"invalid_client","error_description":"bad secret"}"#,
+        )
+        .unwrap_err()
+        .message;
+        assert!(invalid.ends_with("invalid_client – bad secret"));
+        assert!(
+            check_response(503, b"")
+                .unwrap_err()
+                .message
+                .contains("try again")
+        );
+        assert!(
+            check_response(500, b"oops")
+                .unwrap_err()
+                .message
+                .contains("500")
+        );
+    }
+}
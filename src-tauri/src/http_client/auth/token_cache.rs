@@ -0,0 +1,261 @@
+//! Issued OAuth2 tokens, kept in an encrypted app data file so they outlive the session.
+
+use super::{AuthConfig, AuthResult};
+use crate::app_data::{load_app_data, save_app_data};
+use crate::errors::{AppError, ErrorKind};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use tauri::AppHandle;
+
+const TOKEN_CACHE_FILE: &str = "oauth_tokens.json";
+
+/// How long before expiry a token that can be refreshed is renewed in the background
+pub(super) const REFRESH_AHEAD_SECS: i64 = 120;
+
+/// A string encrypted at rest by the app data loader
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct Secure {
+    secure: bool,
+    value: String,
+}
+
+impl Secure {
+    fn new(value: String) -> Self {
+        Self {
+            secure: true,
+            value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct CachedToken {
+    /// The `AuthResult` as JSON, which carries the access token
+    result: Secure,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    refresh_token: Option<Secure>,
+    /// Unix seconds after which the access token is no longer used
+    expires_at: i64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenCacheFile {
+    #[serde(default)]
+    entries: BTreeMap<String, CachedToken>,
+}
+
+/// Identifies the tokens of one OAuth2 configuration: a SHA-256 of everything that affects
+/// which token is issued, i.e. all of it but the caching policy and revocation endpoint.
+pub(super) fn cache_key(config: &AuthConfig) -> Result<String, AppError> {
+    let mut value = serde_json::to_value(config)?;
+    if let Some(object) = value.as_object_mut() {
+        object.remove("tokenCaching");
+        object.remove("revocationUrl");
+    }
+    Ok(hex::encode(Sha256::digest(value.to_string())))
+}
+
+fn load(app: &AppHandle) -> Result<TokenCacheFile, AppError> {
+    match load_app_data(app, TOKEN_CACHE_FILE) {
+        Ok(value) => Ok(serde_json::from_value(value)?),
+        Err(e) if e.kind == ErrorKind::FileNotFound => Ok(TokenCacheFile::default()),
+        Err(e) => Err(e),
+    }
+}
+
+fn save(app: &AppHandle, file: &TokenCacheFile) -> Result<(), AppError> {
+    save_app_data(app, TOKEN_CACHE_FILE, serde_json::to_value(file)?)
+}
+
+/// A cache entry, by what it's still good for
+#[derive(Debug)]
+pub(super) enum Cached {
+    /// The access token can be used as is
+    Fresh(AuthResult),
+    /// The access token can still be used, but should be refreshed with `refresh_token` now
+    Expiring(AuthResult),
+    /// The access token is spent, though `refresh_token` may get another
+    Expired { refresh_token: String },
+}
+
+fn cached_result(entry: &CachedToken, now: i64) -> Option<Cached> {
+    if now >= entry.expires_at {
+        let refresh_token = entry.refresh_token.as_ref().map(|t| t.value.clone());
+        return refresh_token.map(|refresh_token| Cached::Expired { refresh_token });
+    }
+    let result = stored_result(entry).ok()?;
+    if result.refresh_token.is_some() && now >= entry.expires_at - REFRESH_AHEAD_SECS {
+        Some(Cached::Expiring(result))
+    } else {
+        Some(Cached::Fresh(result))
+    }
+}
+
+fn cache_entry(result: &AuthResult) -> Result<Option<CachedToken>, AppError> {
+    // Without an expiry there's no telling when a cached token goes stale
+    let Some(expires_at) = result.expires_at else {
+        return Ok(None);
+    };
+    Ok(Some(CachedToken {
+        result: Secure::new(serde_json::to_string(result)?),
+        refresh_token: result.refresh_token.clone().map(Secure::new),
+        expires_at,
+    }))
+}
+
+/// The cache entry for `key`, unless it's expired with no way to refresh it.
+pub(super) fn get(app: &AppHandle, key: &str) -> Result<Option<Cached>, AppError> {
+    let now = chrono::Utc::now().timestamp();
+    Ok(load(app)?
+        .entries
+        .get(key)
+        .and_then(|entry| cached_result(entry, now)))
+}
+
+/// The tokens cached under `key`, whether or not they've expired.
+pub(super) fn tokens(app: &AppHandle, key: &str) -> Result<Option<AuthResult>, AppError> {
+    load(app)?.entries.get(key).map(stored_result).transpose()
+}
+
+/// Drops the entry for `key`.
+pub(super) fn remove(app: &AppHandle, key: &str) -> Result<(), AppError> {
+    let mut file = load(app)?;
+    if file.entries.remove(key).is_some() {
+        save(app, &file)?;
+    }
+    Ok(())
+}
+
+fn stored_result(entry: &CachedToken) -> Result<AuthResult, AppError> {
+    let mut result: AuthResult = serde_json::from_str(&entry.result.value)?;
+    result.refresh_token = entry.refresh_token.as_ref().map(|t| t.value.clone());
+    Ok(result)
+}
+
+/// Caches `result` under `key`, dropping entries of other configurations that are expired and
+/// can't be refreshed. Results without an expiry aren't cached.
+pub(super) fn put(app: &AppHandle, key: &str, result: &AuthResult) -> Result<(), AppError> {
+    let Some(entry) = cache_entry(result)? else {
+        return Ok(());
+    };
+    let now = chrono::Utc::now().timestamp();
+    let mut file = load(app)?;
+    file.entries
+        .retain(|_, entry| entry.expires_at > now || entry.refresh_token.is_some());
+    file.entries.insert(key.to_string(), entry);
+    save(app, &file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cached, REFRESH_AHEAD_SECS, cache_entry, cache_key, cached_result, stored_result};
+    use crate::http_client::auth::{AuthConfig, AuthResult, TokenCachingPolicy};
+    use std::collections::HashMap;
+
+    fn config(scope: &str, caching: TokenCachingPolicy) -> AuthConfig {
+        AuthConfig::Oauth2 {
+            grant_type: "client_credentials".to_string(),
+            auth_url: None,
+            device_authorization_url: None,
+            token_url: Some("https://login.example.com/token".to_string()),
+            client_id: Some("client".to_string()),
+            client_secret: Some("secret".to_string()),
+            scope: Some(scope.to_string()),
+            refresh_token: None,
+            token_caching: Some(caching),
+            client_auth: None,
+            assertion_key_name: None,
+            assertion_algorithm: None,
+            assertion_key_id: None,
+            token_extra_params: None,
+            redirect_uri: None,
+            use_pkce: None,
+            authorization_window: None,
+            validate_id_token: None,
+            issuer: None,
+            jwks_url: None,
+            revocation_url: None,
+        }
+    }
+
+    #[test]
+    fn keys_ignore_the_caching_policy() {
+        let always = cache_key(&config("read", TokenCachingPolicy::Always)).unwrap();
+        let never = cache_key(&config("read", TokenCachingPolicy::Never)).unwrap();
+        let other = cache_key(&config("write", TokenCachingPolicy::Always)).unwrap();
+        let mut revocable = config("read", TokenCachingPolicy::Always);
+        assert_eq!(always, never);
+        assert_ne!(always, other);
+        assert_eq!(always.len(), 64);
+
+        let AuthConfig::Oauth2 { revocation_url, .. } = &mut revocable else {
+            unreachable!()
+        };
+        *revocation_url = Some("https://login.example.com/revoke".to_string());
+        assert_eq!(cache_key(&revocable).unwrap(), always);
+    }
+
+    #[test]
+    fn entries_hold_secure_tokens_until_expiry() {
+        let result = AuthResult {
+            headers: Some(HashMap::from([(
+                "Authorization".to_string(),
+                "Bearer abc".to_string(),
+            )])),
+            expires_at: Some(1_000),
+            refresh_token: Some("r1".to_string()),
+            ..Default::default()
+        };
+        let entry = cache_entry(&result).unwrap().unwrap();
+        assert!(entry.result.secure);
+        assert!(entry.result.value.contains("Bearer abc"));
+        // The refresh token is kept apart from the result sent to the frontend
+        assert!(!entry.result.value.contains("r1"));
+        assert_eq!(entry.refresh_token.as_ref().unwrap().value, "r1");
+
+        let Some(Cached::Fresh(cached)) = cached_result(&entry, 1_000 - REFRESH_AHEAD_SECS - 1)
+        else {
+            panic!("expected a fresh token");
+        };
+        assert_eq!(cached.headers, result.headers);
+        assert_eq!(cached.refresh_token.as_deref(), Some("r1"));
+
+        let no_expiry = AuthResult {
+            expires_at: None,
+            ..result
+        };
+        assert!(cache_entry(&no_expiry).unwrap().is_none());
+    }
+
+    #[test]
+    fn tokens_near_expiry_are_refreshed_if_they_can_be() {
+        let refreshable = AuthResult {
+            expires_at: Some(1_000),
+            refresh_token: Some("r1".to_string()),
+            ..Default::default()
+        };
+        let entry = cache_entry(&refreshable).unwrap().unwrap();
+        assert!(matches!(
+            cached_result(&entry, 1_000 - REFRESH_AHEAD_SECS),
+            Some(Cached::Expiring(_))
+        ));
+        assert!(matches!(
+            cached_result(&entry, 1_000),
+            Some(Cached::Expired { refresh_token }) if refresh_token == "r1"
+        ));
+
+        let entry = cache_entry(&AuthResult {
+            refresh_token: None,
+            ..refreshable
+        })
+        .unwrap()
+        .unwrap();
+        assert!(matches!(cached_result(&entry, 999), Some(Cached::Fresh(_))));
+        assert!(cached_result(&entry, 1_000).is_none());
+        // Revocation still gets at the tokens of expired entries
+        assert_eq!(stored_result(&entry).unwrap().expires_at, Some(1_000));
+    }
+}
@@ -0,0 +1,123 @@
+//! Renews cached OAuth2 tokens with their refresh tokens, one sign-in or refresh per
+//! configuration at a time.
+
+use super::token_cache::{self, Cached};
+use super::{AuthConfig, AuthResult, authenticate};
+use crate::errors::AppError;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+use tauri::AppHandle;
+
+static LOCKS: LazyLock<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// The lock held while the token cached under `key` is issued or refreshed, so concurrent
+/// sends wait for one token round-trip instead of each making their own.
+pub(super) fn lock(key: &str) -> Arc<tokio::sync::Mutex<()>> {
+    LOCKS
+        .lock()
+        .unwrap()
+        .entry(key.to_string())
+        .or_default()
+        .clone()
+}
+
+/// `config` changed to redeem `token` with the same client and token endpoint
+fn refresh_config(mut config: AuthConfig, token: String) -> AuthConfig {
+    if let AuthConfig::Oauth2 {
+        grant_type,
+        refresh_token,
+        ..
+    } = &mut config
+    {
+        *grant_type = "refresh_token".to_string();
+        *refresh_token = Some(token);
+    }
+    config
+}
+
+/// Redeems `refresh_token` for a new token and caches it under `key`. Callers hold the lock
+/// for `key`.
+pub(super) async fn refresh(
+    app: &AppHandle,
+    config: &AuthConfig,
+    key: &str,
+    refresh_token: String,
+    parent_request_id: Option<String>,
+) -> Result<AuthResult, AppError> {
+    let config = refresh_config(config.clone(), refresh_token.clone());
+    let mut result = authenticate(app.clone(), config, parent_request_id).await?;
+    // Servers that don't rotate refresh tokens leave the old one in use
+    result.refresh_token.get_or_insert(refresh_token);
+    if let Err(e) = token_cache::put(app, key, &result) {
+        log::warn!("Failed to cache the OAuth token: {e}");
+    }
+    Ok(result)
+}
+
+/// Refreshes the token cached under `key` without holding up the caller, unless a sign-in or
+/// refresh for it is already under way.
+pub(super) fn refresh_in_background(
+    app: AppHandle,
+    config: AuthConfig,
+    key: String,
+    refresh_token: String,
+) {
+    let Ok(guard) = lock(&key).try_lock_owned() else {
+        return;
+    };
+    tauri::async_runtime::spawn(async move {
+        let _guard = guard;
+        // Another refresh may have finished between reading the cache and taking the lock
+        if let Ok(Some(Cached::Fresh(_))) = token_cache::get(&app, &key) {
+            return;
+        }
+        if let Err(e) = refresh(&app, &config, &key, refresh_token, None).await {
+            log::warn!("Background OAuth token refresh failed: {e}");
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lock, refresh_config};
+    use crate::http_client::auth::AuthConfig;
+    use std::sync::Arc;
+
+    #[test]
+    fn refreshes_with_the_same_client() {
+        let config: AuthConfig = serde_json::from_value(serde_json::json!({
+            "type": "oauth2",
+            "grantType": "authorization_code",
+            "tokenUrl": "https://login.example.com/token",
+            "clientId": "client",
+            "scope": "read"
+        }))
+        .unwrap();
+        let AuthConfig::Oauth2 {
+            grant_type,
+            refresh_token,
+            client_id,
+            scope,
+            ..
+        } = refresh_config(config, "r1".to_string())
+        else {
+            panic!("expected an oauth2 config");
+        };
+        assert_eq!(grant_type, "refresh_token");
+        assert_eq!(refresh_token.as_deref(), Some("r1"));
+        assert_eq!(client_id.as_deref(), Some("client"));
+        assert_eq!(scope.as_deref(), Some("read"));
+    }
+
+    #[test]
+    fn one_lock_per_configuration() {
+        assert!(Arc::ptr_eq(&lock("a"), &lock("a")));
+        assert!(!Arc::ptr_eq(&lock("a"), &lock("b")));
+
+        let held = lock("a").try_lock_owned().unwrap();
+        assert!(lock("a").try_lock_owned().is_err());
+        drop(held);
+        assert!(lock("a").try_lock_owned().is_ok());
+    }
+}
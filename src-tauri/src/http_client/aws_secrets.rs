@@ -0,0 +1,570 @@
+//! Secrets kept in AWS Secrets Manager or SSM Parameter Store. Credentials come from the
+//! `AWS_ACCESS_KEY_ID` environment variables, then the profile's static keys or
+//! `credential_process` in the shared `credentials` and `config` files. Other sources of the AWS
+//! SDKs' chain (SSO, assumed roles, web identity, container and instance metadata credentials)
+//! aren't supported; a profile using one can wrap it with `credential_process`. Calls are signed
+//! with Signature Version 4 and left out of the request logs, since their responses are the
+//! secrets.
+
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::engine::{HttpEngine, LogEmitter};
+use crate::http_client::hyper_engine::HyperEngine;
+use crate::http_client::request::Request;
+use crate::http_client::response::LogEntry;
+use crate::http_client::spool;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Credentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+}
+
+/// What `credential_process` prints
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ProcessCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+}
+
+/// Profile settings of credential sources that aren't supported, with what they're called
+const UNSUPPORTED_PROFILE_SOURCES: [(&str, &str); 4] = [
+    ("sso_session", "SSO"),
+    ("sso_start_url", "SSO"),
+    ("role_arn", "an assumed role"),
+    ("web_identity_token_file", "web identity"),
+];
+
+/// Environment variables of credential sources that aren't supported, with what they're called
+const UNSUPPORTED_ENV_SOURCES: [(&str, &str); 3] = [
+    ("AWS_WEB_IDENTITY_TOKEN_FILE", "web identity"),
+    (
+        "AWS_CONTAINER_CREDENTIALS_RELATIVE_URI",
+        "container credentials",
+    ),
+    (
+        "AWS_CONTAINER_CREDENTIALS_FULL_URI",
+        "container credentials",
+    ),
+];
+
+/// Drops the log entries of calls to AWS
+struct Unlogged;
+
+impl LogEmitter for Unlogged {
+    fn emit(&self, _: LogEntry) {}
+}
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Text of a shared AWS file, `~/.aws/<name>` unless `variable` names another; empty if there's
+/// none
+fn shared_file(variable: &str, name: &str) -> String {
+    let path = env(variable).map(PathBuf::from).or_else(|| {
+        env("HOME")
+            .or_else(|| env("USERPROFILE"))
+            .map(|home| Path::new(&home).join(".aws").join(name))
+    });
+    path.and_then(|path| std::fs::read_to_string(path).ok())
+        .unwrap_or_default()
+}
+
+/// Sections of an INI file such as the shared AWS files, by name, with lowercase keys
+fn parse_ini(text: &str) -> HashMap<String, HashMap<String, String>> {
+    let mut sections: HashMap<String, HashMap<String, String>> = HashMap::new();
+    let mut current = None;
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current = Some(name.trim().to_string());
+        } else if let (Some(section), Some((key, value))) = (&current, line.split_once('=')) {
+            sections
+                .entry(section.clone())
+                .or_default()
+                .insert(key.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+    sections
+}
+
+/// Settings of a profile: its `config` section, overridden by its `credentials` section
+fn profile_settings(config: &str, credentials: &str, profile: &str) -> HashMap<String, String> {
+    let section = if profile == "default" {
+        profile.to_string()
+    } else {
+        format!("profile {profile}")
+    };
+    let mut settings = parse_ini(config).remove(&section).unwrap_or_default();
+    settings.extend(parse_ini(credentials).remove(profile).unwrap_or_default());
+    settings
+}
+
+fn shell(command: &str) -> Command {
+    #[cfg(windows)]
+    {
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", command]);
+        shell
+    }
+    #[cfg(not(windows))]
+    {
+        let mut shell = Command::new("sh");
+        shell.args(["-c", command]);
+        shell
+    }
+}
+
+/// Runs a profile's `credential_process` for its temporary credentials
+async fn process_credentials(command: String) -> Result<Credentials, AppError> {
+    let output = tokio::task::spawn_blocking(move || shell(&command).output())
+        .await
+        .map_err(|e| {
+            AppError::new(
+                ErrorKind::IoError,
+                format!("Failed to run credential_process: {e}"),
+            )
+        })??;
+    if !output.status.success() {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            format!(
+                "credential_process failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        ));
+    }
+    let printed: ProcessCredentials = serde_json::from_slice(&output.stdout).map_err(|e| {
+        AppError::new(
+            ErrorKind::BadRequest,
+            format!("credential_process printed no credentials: {e}"),
+        )
+    })?;
+    Ok(Credentials {
+        access_key_id: printed.access_key_id,
+        secret_access_key: printed.secret_access_key,
+        session_token: printed.session_token,
+    })
+}
+
+/// Credentials and region for a call, from the environment or the named profile. A profile
+/// given explicitly takes precedence over the environment's keys.
+async fn credentials_and_region(
+    region: Option<&str>,
+    profile: Option<&str>,
+) -> Result<(Credentials, String), AppError> {
+    let profile_name = profile
+        .map(str::to_string)
+        .or_else(|| env("AWS_PROFILE"))
+        .unwrap_or_else(|| "default".to_string());
+    let settings = profile_settings(
+        &shared_file("AWS_CONFIG_FILE", "config"),
+        &shared_file("AWS_SHARED_CREDENTIALS_FILE", "credentials"),
+        &profile_name,
+    );
+    let region = region
+        .map(str::to_string)
+        .or_else(|| env("AWS_REGION"))
+        .or_else(|| env("AWS_DEFAULT_REGION"))
+        .or_else(|| settings.get("region").cloned())
+        .ok_or_else(|| {
+            AppError::new(
+                ErrorKind::BadRequest,
+                "No AWS region is set: give one, set AWS_REGION or add it to the profile",
+            )
+        })?;
+
+    let from_env = env("AWS_ACCESS_KEY_ID")
+        .zip(env("AWS_SECRET_ACCESS_KEY"))
+        .filter(|_| profile.is_none());
+    let credentials = if let Some((access_key_id, secret_access_key)) = from_env {
+        Credentials {
+            access_key_id,
+            secret_access_key,
+            session_token: env("AWS_SESSION_TOKEN"),
+        }
+    } else if let (Some(access_key_id), Some(secret_access_key)) = (
+        settings.get("aws_access_key_id"),
+        settings.get("aws_secret_access_key"),
+    ) {
+        Credentials {
+            access_key_id: access_key_id.clone(),
+            secret_access_key: secret_access_key.clone(),
+            session_token: settings.get("aws_session_token").cloned(),
+        }
+    } else if let Some(command) = settings.get("credential_process") {
+        process_credentials(command.clone()).await?
+    } else {
+        return Err(no_credentials(&profile_name, &settings, env));
+    };
+    Ok((credentials, region))
+}
+
+/// The error for a profile without supported credentials, naming the unsupported source it or
+/// the environment uses, if any
+fn no_credentials(
+    profile_name: &str,
+    settings: &HashMap<String, String>,
+    env: impl Fn(&str) -> Option<String>,
+) -> AppError {
+    let from_profile = UNSUPPORTED_PROFILE_SOURCES
+        .iter()
+        .find(|(key, _)| settings.contains_key(*key))
+        .map(|(key, source)| format!("profile '{profile_name}' uses {source} ({key})"));
+    let from_env = UNSUPPORTED_ENV_SOURCES
+        .iter()
+        .find(|(variable, _)| env(variable).is_some())
+        .map(|(variable, source)| format!("the environment uses {source} ({variable})"));
+    let message = match from_profile.or(from_env) {
+        Some(unsupported) => format!(
+            "No supported AWS credentials: {unsupported}, which isn't supported. Only access keys \
+             in the environment or profile and credential_process are; e.g. set \
+             credential_process = aws configure export-credentials --profile {profile_name} \
+             --format process"
+        ),
+        None => format!(
+            "No AWS credentials: set AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY, or give profile \
+             '{profile_name}' access keys or a credential_process"
+        ),
+    };
+    AppError::new(ErrorKind::BadRequest, message)
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, key);
+    ring::hmac::sign(&key, data).as_ref().to_vec()
+}
+
+/// Headers signing a request for `/` with Signature Version 4: `X-Amz-Date`, the session token
+/// if there is one, and `Authorization`. `headers` are signed as well.
+#[allow(clippy::too_many_arguments)]
+fn sign(
+    credentials: &Credentials,
+    region: &str,
+    service: &str,
+    method: &str,
+    host: &str,
+    headers: &[(&str, &str)],
+    body: &[u8],
+    now: DateTime<Utc>,
+) -> Vec<(String, String)> {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = &amz_date[..8];
+    let mut signed: Vec<(String, String)> = headers
+        .iter()
+        .map(|(name, value)| (name.to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+    signed.push(("host".to_string(), host.to_string()));
+    signed.push(("x-amz-date".to_string(), amz_date.clone()));
+    if let Some(token) = &credentials.session_token {
+        signed.push(("x-amz-security-token".to_string(), token.clone()));
+    }
+    signed.sort();
+    let canonical_headers: String = signed
+        .iter()
+        .map(|(name, value)| format!("{name}:{value}\n"))
+        .collect();
+    let signed_headers = signed
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_request = format!(
+        "{method}\n/\n\n{canonical_headers}\n{signed_headers}\n{}",
+        hex::encode(Sha256::digest(body))
+    );
+    let scope = format!("{date}/{region}/{service}/aws4_request");
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+    let mut key = format!("AWS4{}", credentials.secret_access_key).into_bytes();
+    for part in [date, region, service, "aws4_request"] {
+        key = hmac_sha256(&key, part.as_bytes());
+    }
+    let signature = hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()));
+
+    let mut out = vec![("X-Amz-Date".to_string(), amz_date.clone())];
+    if let Some(token) = &credentials.session_token {
+        out.push(("X-Amz-Security-Token".to_string(), token.clone()));
+    }
+    out.push((
+        "Authorization".to_string(),
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+            credentials.access_key_id
+        ),
+    ));
+    out
+}
+
+/// Error for a call AWS refused, e.g. `ResourceNotFoundException: Secrets Manager can't find
+/// the specified secret.`
+fn service_error(status: u16, body: &[u8]) -> AppError {
+    let value: Value = serde_json::from_slice(body).unwrap_or_default();
+    let kind = value
+        .get("__type")
+        .and_then(Value::as_str)
+        .map_or("Error", |kind| kind.rsplit('#').next().unwrap_or(kind));
+    let message = value
+        .get("message")
+        .or_else(|| value.get("Message"))
+        .and_then(Value::as_str)
+        .unwrap_or("no details given");
+    AppError::new(
+        ErrorKind::HttpError,
+        format!("AWS answered HTTP {status}, {kind}: {message}"),
+    )
+}
+
+/// Calls an action of an AWS JSON 1.1 service such as `secretsmanager`
+async fn call(
+    service: &str,
+    target: &str,
+    input: Value,
+    region: Option<&str>,
+    profile: Option<&str>,
+) -> Result<Value, AppError> {
+    let (credentials, region) = credentials_and_region(region, profile).await?;
+    let suffix = if region.starts_with("cn-") {
+        "amazonaws.com.cn"
+    } else {
+        "amazonaws.com"
+    };
+    let host = format!("{service}.{region}.{suffix}");
+    let body = input.to_string().into_bytes();
+    let content_type = ("Content-Type", "application/x-amz-json-1.1");
+    let target = ("X-Amz-Target", target);
+    let mut headers: Vec<(String, String)> = [content_type, target]
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    headers.extend(sign(
+        &credentials,
+        &region,
+        service,
+        "POST",
+        &host,
+        &[content_type, target],
+        &body,
+        Utc::now(),
+    ));
+
+    let request = Request {
+        request_id: uuid::Uuid::new_v4().to_string(),
+        url: format!("https://{host}/"),
+        method: "POST".to_string(),
+        headers: Some(headers),
+        body: Some(body),
+        ..Default::default()
+    };
+    let response = HyperEngine::new()
+        .execute(request, Arc::new(Unlogged))
+        .await?;
+    let body = match &response.file_path {
+        Some(path) => {
+            let mut body = Vec::new();
+            spool::open(Path::new(path))?.read_to_end(&mut body)?;
+            let _ = std::fs::remove_file(path);
+            body
+        }
+        None => response.body,
+    };
+    if !(200..300).contains(&response.status) {
+        return Err(service_error(response.status, &body));
+    }
+    serde_json::from_slice(&body).map_err(|e| {
+        AppError::new(
+            ErrorKind::HttpError,
+            format!("Unexpected answer from AWS: {e}"),
+        )
+    })
+}
+
+/// Value of `key` in a secret holding a JSON object, as Secrets Manager keeps key/value secrets
+fn secret_field(secret: &str, key: &str) -> Result<String, AppError> {
+    let fields: serde_json::Map<String, Value> = serde_json::from_str(secret).map_err(|_| {
+        AppError::new(
+            ErrorKind::BadRequest,
+            format!("The secret isn't a JSON object, so has no key '{key}'"),
+        )
+    })?;
+    match fields.get(key) {
+        Some(Value::String(text)) => Ok(text.clone()),
+        Some(value) => Ok(value.to_string()),
+        None => Err(AppError::new(
+            ErrorKind::FileNotFound,
+            format!("The secret has no key '{key}'"),
+        )),
+    }
+}
+
+/// Reads the current version of a Secrets Manager secret, or one key of it when it's a JSON
+/// object. A binary secret is given in base64.
+pub async fn secrets_manager_secret(
+    secret_id: &str,
+    json_key: Option<&str>,
+    region: Option<&str>,
+    profile: Option<&str>,
+) -> Result<String, AppError> {
+    let response = call(
+        "secretsmanager",
+        "secretsmanager.GetSecretValue",
+        json!({ "SecretId": secret_id }),
+        region,
+        profile,
+    )
+    .await?;
+    let secret = response
+        .get("SecretString")
+        .or_else(|| response.get("SecretBinary"))
+        .and_then(Value::as_str)
+        .ok_or_else(|| {
+            AppError::new(
+                ErrorKind::HttpError,
+                format!("AWS returned no value for secret '{secret_id}'"),
+            )
+        })?;
+    match json_key {
+        Some(key) => secret_field(secret, key),
+        None => Ok(secret.to_string()),
+    }
+}
+
+/// Reads an SSM parameter, decrypting it if it's a `SecureString`
+pub async fn ssm_parameter(
+    name: &str,
+    region: Option<&str>,
+    profile: Option<&str>,
+) -> Result<String, AppError> {
+    let response = call(
+        "ssm",
+        "AmazonSSM.GetParameter",
+        json!({ "Name": name, "WithDecryption": true }),
+        region,
+        profile,
+    )
+    .await?;
+    response
+        .pointer("/Parameter/Value")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| {
+            AppError::new(
+                ErrorKind::HttpError,
+                format!("AWS returned no value for parameter '{name}'"),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Credentials, no_credentials, profile_settings, secret_field, service_error, sign};
+    use chrono::{TimeZone, Utc};
+    use std::collections::HashMap;
+
+    #[test]
+    fn signs_like_the_aws_test_suite() {
+        // The "get-vanilla" case of the Signature Version 4 test suite
+        let credentials = Credentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: None,
+        };
+        let now = Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap();
+        let headers = sign(
+            &credentials,
+            "us-east-1",
+            "service",
+            "GET",
+            "example.amazonaws.com",
+            &[],
+            b"",
+            now,
+        );
+        assert_eq!(
+            headers,
+            [
+                ("X-Amz-Date".to_string(), "20150830T123600Z".to_string()),
+                (
+                    "Authorization".to_string(),
+                    "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+                     SignedHeaders=host;x-amz-date, \
+                     Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_profiles_from_the_shared_files() {
+        let config = "[default]\nregion = us-east-1\n\n[profile dev]\nregion=eu-west-1\n\
+                      credential_process = aws-vault export dev\n";
+        let credentials = "# keys\n[dev]\naws_access_key_id = AKID\n\
+                           AWS_SECRET_ACCESS_KEY = secret\n";
+        let dev = profile_settings(config, credentials, "dev");
+        assert_eq!(dev["region"], "eu-west-1");
+        assert_eq!(dev["aws_access_key_id"], "AKID");
+        assert_eq!(dev["aws_secret_access_key"], "secret");
+        assert_eq!(dev["credential_process"], "aws-vault export dev");
+        let default = profile_settings(config, credentials, "default");
+        assert_eq!(default.len(), 1);
+        assert!(profile_settings(config, credentials, "prod").is_empty());
+    }
+
+    #[test]
+    fn names_unsupported_credential_sources() {
+        let config = "[profile sso]\nsso_session = corp\nregion = us-east-1\n";
+        let sso = profile_settings(config, "", "sso");
+        let err = no_credentials("sso", &sso, |_| None);
+        assert!(err.message.contains("profile 'sso' uses SSO (sso_session)"));
+        assert!(err.message.contains("export-credentials --profile sso"));
+
+        let err = no_credentials("default", &HashMap::new(), |name| {
+            (name == "AWS_CONTAINER_CREDENTIALS_RELATIVE_URI").then(|| "/v2/creds".to_string())
+        });
+        assert!(
+            err.message
+                .contains("the environment uses container credentials")
+        );
+
+        let err = no_credentials("default", &HashMap::new(), |_| None);
+        assert!(
+            err.message
+                .starts_with("No AWS credentials: set AWS_ACCESS_KEY_ID")
+        );
+    }
+
+    #[test]
+    fn reads_keys_of_json_secrets_and_explains_failures() {
+        let secret = r#"{"apiKey": "abc", "port": 5432}"#;
+        assert_eq!(secret_field(secret, "apiKey").unwrap(), "abc");
+        assert_eq!(secret_field(secret, "port").unwrap(), "5432");
+        assert!(secret_field(secret, "user").is_err());
+        assert!(secret_field("plain", "apiKey").is_err());
+
+        let error = service_error(
+            400,
+            br#"{"__type": "com.amazonaws.secretsmanager#ResourceNotFoundException", "message": "Not found"}"#,
+        );
+        assert_eq!(
+            error.message,
+            "AWS answered HTTP 400, ResourceNotFoundException: Not found"
+        );
+    }
+}
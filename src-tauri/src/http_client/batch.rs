@@ -0,0 +1,175 @@
+//! Several requests sent at once, a limited number at a time, e.g. to warm caches or compare
+//! endpoints.
+
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::request::Request;
+use crate::http_client::response::ResponseData;
+use futures_util::StreamExt;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+
+/// Requests in flight at once when no limit is given
+const DEFAULT_CONCURRENCY: usize = 6;
+
+/// Most requests a batch may have in flight at once
+const MAX_CONCURRENCY: usize = 64;
+
+/// Outcome of one request of a batch: its response, or why it has none
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<ResponseData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<AppError>,
+}
+
+/// Sends `requests` with `send`, at most `concurrency` at a time, and returns their results by
+/// request id. A request that fails doesn't stop the others.
+pub async fn send_all<F, Fut>(
+    requests: Vec<Request>,
+    concurrency: Option<usize>,
+    send: F,
+) -> Result<HashMap<String, BatchResult>, AppError>
+where
+    F: Fn(Request) -> Fut,
+    Fut: Future<Output = Result<ResponseData, AppError>>,
+{
+    let mut ids = HashSet::new();
+    if let Some(request) = requests
+        .iter()
+        .find(|request| !ids.insert(request.request_id.as_str()))
+    {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            format!(
+                "Request id '{}' appears more than once in the batch",
+                request.request_id
+            ),
+        ));
+    }
+
+    let limit = concurrency
+        .unwrap_or(DEFAULT_CONCURRENCY)
+        .clamp(1, MAX_CONCURRENCY);
+    let results = futures_util::stream::iter(requests)
+        .map(|request| {
+            let request_id = request.request_id.clone();
+            let sent = send(request);
+            async move {
+                let result = match sent.await {
+                    Ok(response) => BatchResult {
+                        response: Some(response),
+                        error: None,
+                    },
+                    Err(error) => BatchResult {
+                        response: None,
+                        error: Some(error),
+                    },
+                };
+                (request_id, result)
+            }
+        })
+        .buffer_unordered(limit)
+        .collect()
+        .await;
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::send_all;
+    use crate::errors::{AppError, ErrorKind};
+    use crate::http_client::request::Request;
+    use crate::http_client::response::{ResponseData, Timings};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    fn request(id: &str) -> Request {
+        Request {
+            request_id: id.to_string(),
+            url: format!("https://example.com/{id}"),
+            method: "GET".to_string(),
+            ..Request::default()
+        }
+    }
+
+    fn response(request: &Request) -> ResponseData {
+        ResponseData {
+            request_id: request.request_id.clone(),
+            status: 200,
+            status_text: "OK".to_string(),
+            headers: Vec::new(),
+            binary_headers: Vec::new(),
+            cookies: Vec::new(),
+            body: request.url.clone().into_bytes(),
+            file_path: None,
+            file_compressed: false,
+            truncated: false,
+            cancelled: false,
+            saved_to: None,
+            resumed_from: None,
+            content_range: None,
+            cache: None,
+            size: 0,
+            duration: 0,
+            timings: Timings::default(),
+            connection: None,
+            revocation: None,
+            rate_limit: None,
+            redirect_chain: None,
+            retries: None,
+            log_summary: None,
+            multistatus: None,
+            soap_fault: None,
+            assertions: None,
+            variables: None,
+            timestamp: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn keys_results_by_request_id_within_the_limit() {
+        let in_flight = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let requests = ["a", "b", "c", "d", "e"].map(request).to_vec();
+        let results = send_all(requests, Some(2), |request| {
+            let (in_flight, peak) = (&in_flight, &peak);
+            async move {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                if request.request_id == "c" {
+                    return Err(AppError::new(ErrorKind::HttpError, "refused"));
+                }
+                Ok(response(&request))
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(results.len(), 5);
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            results["a"].response.as_ref().unwrap().body,
+            b"https://example.com/a"
+        );
+        assert!(results["c"].response.is_none());
+        assert_eq!(results["c"].error.as_ref().unwrap().message, "refused");
+    }
+
+    #[tokio::test]
+    async fn rejects_repeated_request_ids() {
+        let requests = vec![request("a"), request("a")];
+        let error = send_all(
+            requests,
+            None,
+            |request| async move { Ok(response(&request)) },
+        )
+        .await
+        .unwrap_err();
+        assert!(error.message.contains("'a'"));
+    }
+}
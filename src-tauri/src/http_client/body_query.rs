@@ -0,0 +1,312 @@
+//! Queries over a response body, run in the backend so a large body needn't be loaded into
+//! the webview to filter it.
+
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::{charset, spool, xml};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_json_path::JsonPath;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+fn default_max_results() -> usize {
+    1_000
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum QueryLanguage {
+    /// RFC 9535 JSONPath, e.g. `$.items[?@.price > 10].name`
+    JsonPath,
+    /// JMESPath, e.g. ``items[?price > `10`].name``
+    JmesPath,
+    /// XPath 1.0 over an XML body, e.g. `//m:Price/@currency`
+    #[serde(rename = "xpath")]
+    XPath,
+}
+
+/// Expressions to run over a response body, given inline or as the file a large response was
+/// spooled to
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BodyQuery {
+    pub body: Option<String>,
+    pub file_path: Option<String>,
+    /// The response's `Content-Type`, whose `charset` a spooled XML body is decoded with
+    pub content_type: Option<String>,
+    pub language: QueryLanguage,
+    pub expressions: Vec<String>,
+    /// Prefixes bound to namespace URIs for XPath, besides those the body declares
+    #[serde(default)]
+    pub namespaces: HashMap<String, String>,
+    /// Values returned at most for each expression
+    #[serde(default = "default_max_results")]
+    pub max_results: usize,
+}
+
+/// What one expression matched
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryResult {
+    pub expression: String,
+    /// The matched values. JMESPath gives one value, or none when the result is null; an array
+    /// result is cut to `max_results` items. XPath gives each node as its kind, name and
+    /// string value, e.g. `{"node": "attribute", "name": "currency", "value": "EUR"}`.
+    pub values: Vec<Value>,
+    /// Locations of the values, e.g. `$['items'][0]` or `/order[1]/item[2]`. Not given by
+    /// JMESPath, nor for XPath results that aren't nodes.
+    pub paths: Vec<String>,
+    /// Whether there were more than `max_results` values
+    pub truncated: bool,
+    /// Why the expression couldn't be run, e.g. a syntax error
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl QueryResult {
+    fn failed(expression: &str, error: String) -> Self {
+        Self {
+            expression: expression.to_string(),
+            values: Vec::new(),
+            paths: Vec::new(),
+            truncated: false,
+            error: Some(error),
+        }
+    }
+}
+
+fn json_path(expression: &str, document: &Value, limit: usize) -> QueryResult {
+    let path = match JsonPath::parse(expression) {
+        Ok(path) => path,
+        Err(e) => return QueryResult::failed(expression, e.to_string()),
+    };
+    let nodes = path.query_located(document);
+    let truncated = nodes.len() > limit;
+    let (paths, values) = nodes
+        .into_iter()
+        .take(limit)
+        .map(|node| (node.location().to_string(), node.node().clone()))
+        .unzip();
+    QueryResult {
+        expression: expression.to_string(),
+        values,
+        paths,
+        truncated,
+        error: None,
+    }
+}
+
+fn jmes_path(expression: &str, document: &Value, limit: usize) -> QueryResult {
+    let result = jmespath::compile(expression)
+        .and_then(|compiled| compiled.search(document))
+        .map_err(|e| e.to_string())
+        .and_then(|result| serde_json::to_value(&*result).map_err(|e| e.to_string()));
+    let (values, truncated) = match result {
+        Ok(Value::Null) => (Vec::new(), false),
+        Ok(Value::Array(mut items)) => {
+            let truncated = items.len() > limit;
+            items.truncate(limit);
+            (vec![Value::Array(items)], truncated)
+        }
+        Ok(value) => (vec![value], false),
+        Err(e) => return QueryResult::failed(expression, e),
+    };
+    QueryResult {
+        expression: expression.to_string(),
+        values,
+        paths: Vec::new(),
+        truncated,
+        error: None,
+    }
+}
+
+fn x_path(
+    expression: &str,
+    document: &sxd_document::dom::Document,
+    namespaces: &HashMap<String, String>,
+    limit: usize,
+) -> QueryResult {
+    let value = match xml::xpath(document, expression, namespaces) {
+        Ok(value) => value,
+        Err(e) => return QueryResult::failed(expression, e),
+    };
+    let (values, paths, truncated) = match value {
+        sxd_xpath::Value::Nodeset(nodes) => {
+            let nodes = nodes.document_order();
+            let truncated = nodes.len() > limit;
+            let nodes = &nodes[..nodes.len().min(limit)];
+            (
+                nodes.iter().map(|node| xml::node_value(*node)).collect(),
+                nodes.iter().map(|node| xml::node_path(*node)).collect(),
+                truncated,
+            )
+        }
+        sxd_xpath::Value::Boolean(value) => (vec![Value::Bool(value)], Vec::new(), false),
+        sxd_xpath::Value::Number(value) => (vec![xml::number_value(value)], Vec::new(), false),
+        sxd_xpath::Value::String(value) => (vec![Value::String(value)], Vec::new(), false),
+    };
+    QueryResult {
+        expression: expression.to_string(),
+        values,
+        paths,
+        truncated,
+        error: None,
+    }
+}
+
+/// Text of the body, given inline or spooled to a file. A spooled body is decoded in the
+/// charset it declares, or `content_type` does.
+pub fn body_text(
+    body: Option<&str>,
+    file_path: Option<&str>,
+    content_type: Option<&str>,
+) -> Result<String, AppError> {
+    match (body, file_path) {
+        (Some(body), _) => Ok(body.to_string()),
+        (None, Some(path)) => {
+            let mut bytes = Vec::new();
+            spool::open(Path::new(path))?.read_to_end(&mut bytes)?;
+            Ok(charset::decode(&bytes, content_type).text)
+        }
+        (None, None) => Err(AppError::new(
+            ErrorKind::BadRequest,
+            "A response body or file path is required",
+        )),
+    }
+}
+
+/// Runs each of the query's expressions over a JSON or, for XPath, XML response body. An
+/// expression that can't be run gives a result with an error rather than failing the others.
+pub fn query(query: BodyQuery) -> Result<Vec<QueryResult>, AppError> {
+    let limit = query.max_results.max(1);
+    if query.language == QueryLanguage::XPath {
+        let text = body_text(
+            query.body.as_deref(),
+            query.file_path.as_deref(),
+            query.content_type.as_deref(),
+        )?;
+        let package = sxd_document::parser::parse(&text).map_err(xml::invalid_xml)?;
+        let document = package.as_document();
+        return Ok(query
+            .expressions
+            .iter()
+            .map(|expression| x_path(expression, &document, &query.namespaces, limit))
+            .collect());
+    }
+
+    let document: Value = match (&query.body, &query.file_path) {
+        (Some(body), _) => serde_json::from_str(body)?,
+        (None, Some(path)) => serde_json::from_reader(spool::open(Path::new(path))?)?,
+        (None, None) => {
+            return Err(AppError::new(
+                ErrorKind::BadRequest,
+                "A response body or file path is required",
+            ));
+        }
+    };
+    Ok(query
+        .expressions
+        .iter()
+        .map(|expression| match query.language {
+            QueryLanguage::JmesPath => jmes_path(expression, &document, limit),
+            _ => json_path(expression, &document, limit),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BodyQuery, QueryLanguage, query};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn body_query(language: QueryLanguage, expressions: &[&str], max_results: usize) -> BodyQuery {
+        BodyQuery {
+            body: Some(
+                json!({ "items": [
+                    { "name": "pen", "price": 3 },
+                    { "name": "ink", "price": 12 },
+                    { "name": "pad", "price": 15 }
+                ]})
+                .to_string(),
+            ),
+            file_path: None,
+            content_type: None,
+            language,
+            expressions: expressions.iter().map(|e| e.to_string()).collect(),
+            namespaces: HashMap::new(),
+            max_results,
+        }
+    }
+
+    #[test]
+    fn runs_json_path_expressions() {
+        let results = query(body_query(
+            QueryLanguage::JsonPath,
+            &["$.items[?@.price > 10].name", "$.items[*]", "$.items["],
+            2,
+        ))
+        .unwrap();
+        assert_eq!(results[0].values, [json!("ink"), json!("pad")]);
+        assert_eq!(
+            results[0].paths,
+            ["$['items'][1]['name']", "$['items'][2]['name']"]
+        );
+        assert!(!results[0].truncated);
+        assert_eq!(results[1].values.len(), 2);
+        assert!(results[1].truncated);
+        assert!(results[2].error.is_some());
+    }
+
+    #[test]
+    fn runs_jmes_path_expressions() {
+        let results = query(body_query(
+            QueryLanguage::JmesPath,
+            &[
+                "items[?price > `10`].name",
+                "items[0].price",
+                "missing",
+                "items[*].name",
+            ],
+            2,
+        ))
+        .unwrap();
+        assert_eq!(results[0].values, [json!(["ink", "pad"])]);
+        assert_eq!(results[1].values, [json!(3)]);
+        assert!(results[2].values.is_empty() && results[2].error.is_none());
+        assert_eq!(results[3].values, [json!(["pen", "ink"])]);
+        assert!(results[3].truncated);
+    }
+
+    #[test]
+    fn runs_xpath_expressions() {
+        let mut xml = body_query(
+            QueryLanguage::XPath,
+            &["//p:item/@sku", "count(//p:item)", "//p:item", "//q:item"],
+            1,
+        );
+        xml.body = Some(
+            r#"<order xmlns:p="urn:p"><p:item sku="a">Pen</p:item><p:item sku="b">Ink</p:item></order>"#
+                .to_string(),
+        );
+        let results = query(xml).unwrap();
+        assert_eq!(
+            results[0].values,
+            [json!({"node": "attribute", "name": "sku", "value": "a"})]
+        );
+        assert_eq!(results[0].paths, ["/order[1]/p:item[1]/@sku"]);
+        assert!(results[0].truncated);
+        assert_eq!(results[1].values, [json!(2)]);
+        assert!(results[1].paths.is_empty());
+        assert_eq!(results[2].values[0]["value"], "Pen");
+        assert!(results[3].error.is_some());
+    }
+
+    #[test]
+    fn needs_a_json_body() {
+        let mut not_json = body_query(QueryLanguage::JsonPath, &["$"], 10);
+        not_json.body = Some("<xml/>".to_string());
+        assert!(query(not_json).is_err());
+    }
+}
@@ -0,0 +1,26 @@
+//! Passes response bodies to the frontend as they arrive, rather than in one JSON array.
+
+use bytes::Bytes;
+use std::fmt;
+use std::sync::Arc;
+
+/// Receives the body of a response chunk by chunk; set by the command, never read from the
+/// frontend.
+#[derive(Clone)]
+pub struct BodySink(Arc<dyn Fn(Bytes) + Send + Sync>);
+
+impl BodySink {
+    pub fn new(send: impl Fn(Bytes) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(send))
+    }
+
+    pub fn send(&self, chunk: Bytes) {
+        (self.0)(chunk)
+    }
+}
+
+impl fmt::Debug for BodySink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BodySink")
+    }
+}
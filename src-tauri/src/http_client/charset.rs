@@ -0,0 +1,184 @@
+//! Text of response bodies sent in other charsets than UTF-8, e.g. ISO-8859-1, Shift_JIS or
+//! GBK, transcoded to UTF-8 rather than shown as mojibake.
+
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::response::ResponseData;
+use crate::http_client::spool;
+use encoding_rs::{Encoding, UTF_8};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::io::Read;
+use std::path::Path;
+
+/// A response body to decode, given inline or as the file a large response was spooled to
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TextRequest {
+    pub body: Option<Vec<u8>>,
+    pub file_path: Option<String>,
+    /// The response's `Content-Type`, whose `charset` the body is decoded with
+    pub content_type: Option<String>,
+}
+
+/// Where a body's charset was found
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CharsetSource {
+    /// A byte order mark, which wins over a declared charset
+    Bom,
+    /// The `charset` parameter of `Content-Type`
+    ContentType,
+    /// The `encoding` of an `<?xml ...?>` declaration
+    XmlDeclaration,
+    /// Nothing said, so UTF-8
+    Default,
+}
+
+/// A body as UTF-8 text
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedText {
+    pub text: String,
+    /// Name of the charset the body was decoded from, e.g. "Shift_JIS"
+    pub charset: String,
+    pub source: CharsetSource,
+    /// Whether bytes invalid in the charset were replaced with U+FFFD
+    pub had_errors: bool,
+}
+
+/// The `charset` parameter of a `Content-Type`, unquoted
+pub fn content_type_charset(content_type: &str) -> Option<&str> {
+    content_type.split(';').skip(1).find_map(|parameter| {
+        let (name, value) = parameter.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches('"'))
+            .filter(|value| !value.is_empty())
+    })
+}
+
+/// The `encoding` an XML body declares, e.g. `<?xml version="1.0" encoding="Shift_JIS"?>`
+fn xml_declaration_encoding(body: &[u8]) -> Option<&str> {
+    let rest = body.strip_prefix(b"<?xml")?;
+    let end = rest.iter().position(|&b| b == b'>')?;
+    let declaration = std::str::from_utf8(&rest[..end]).ok()?;
+    let (_, value) = declaration.split_once("encoding")?;
+    let value = value.trim_start().strip_prefix('=')?.trim_start();
+    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    value[1..].split(quote).next()
+}
+
+/// The charset a body is in: its byte order mark, else its declared charset, else UTF-8.
+/// Unknown labels are ignored.
+pub fn body_encoding(
+    body: &[u8],
+    content_type: Option<&str>,
+) -> (&'static Encoding, CharsetSource) {
+    if let Some((encoding, _)) = Encoding::for_bom(body) {
+        return (encoding, CharsetSource::Bom);
+    }
+    let declared = content_type
+        .and_then(content_type_charset)
+        .and_then(|label| Encoding::for_label(label.as_bytes()))
+        .map(|encoding| (encoding, CharsetSource::ContentType));
+    declared
+        .or_else(|| {
+            xml_declaration_encoding(body)
+                .and_then(|label| Encoding::for_label(label.as_bytes()))
+                .map(|encoding| (encoding, CharsetSource::XmlDeclaration))
+        })
+        .unwrap_or((UTF_8, CharsetSource::Default))
+}
+
+/// Decodes a body to UTF-8 in the charset it's in, dropping any byte order mark
+pub fn decode(body: &[u8], content_type: Option<&str>) -> DecodedText {
+    let (encoding, source) = body_encoding(body, content_type);
+    let (text, used, had_errors) = encoding.decode(body);
+    DecodedText {
+        text: text.into_owned(),
+        charset: used.name().to_string(),
+        source,
+        had_errors,
+    }
+}
+
+/// The response's `Content-Type`
+pub fn response_content_type(response: &ResponseData) -> Option<&str> {
+    response
+        .headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+        .map(|(_, value)| value.as_str())
+}
+
+/// The response's body as text, in the charset the response declares
+pub fn response_text<'a>(response: &ResponseData, body: &'a [u8]) -> Cow<'a, str> {
+    let (encoding, _) = body_encoding(body, response_content_type(response));
+    encoding.decode(body).0
+}
+
+/// Decodes a body given inline or spooled to a file
+pub fn decode_body(request: TextRequest) -> Result<DecodedText, AppError> {
+    let body = match (request.body, &request.file_path) {
+        (Some(body), _) => body,
+        (None, Some(path)) => {
+            let mut body = Vec::new();
+            spool::open(Path::new(path))?.read_to_end(&mut body)?;
+            body
+        }
+        (None, None) => {
+            return Err(AppError::new(
+                ErrorKind::BadRequest,
+                "A response body or file path is required",
+            ));
+        }
+    };
+    Ok(decode(&body, request.content_type.as_deref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CharsetSource, content_type_charset, decode};
+
+    #[test]
+    fn decodes_in_the_declared_charset() {
+        let latin1 = decode(b"caf\xe9", Some("text/plain; charset=ISO-8859-1"));
+        assert_eq!(latin1.text, "café");
+        // WHATWG maps the ISO-8859-1 label to its windows-1252 superset
+        assert_eq!(latin1.charset, "windows-1252");
+        assert_eq!(latin1.source, CharsetSource::ContentType);
+        assert!(!latin1.had_errors);
+
+        let sjis = decode(b"\x93\xfa\x96\x7b", Some("text/html;charset=\"Shift_JIS\""));
+        assert_eq!(sjis.text, "日本");
+        let gbk = decode(b"\xd6\xd0\xce\xc4", Some("text/plain; Charset=GBK"));
+        assert_eq!(gbk.text, "中文");
+
+        let xml = decode(
+            b"<?xml version='1.0' encoding='Shift_JIS'?><a>\x93\xfa</a>",
+            Some("application/xml"),
+        );
+        assert_eq!(
+            xml.text,
+            "<?xml version='1.0' encoding='Shift_JIS'?><a>日</a>"
+        );
+        assert_eq!(xml.source, CharsetSource::XmlDeclaration);
+
+        let utf8 = decode(b"caf\xe9", None);
+        assert_eq!(utf8.source, CharsetSource::Default);
+        assert!(utf8.had_errors);
+        assert_eq!(utf8.text, "caf\u{FFFD}");
+    }
+
+    #[test]
+    fn sniffs_byte_order_marks_first() {
+        let utf16 = decode(b"\xff\xfeh\0i\0", Some("text/plain; charset=iso-8859-1"));
+        assert_eq!(utf16.text, "hi");
+        assert_eq!(utf16.charset, "UTF-16LE");
+        assert_eq!(utf16.source, CharsetSource::Bom);
+
+        assert_eq!(decode(b"\xef\xbb\xbfok", None).text, "ok");
+        assert_eq!(content_type_charset("text/plain; charset="), None);
+        assert_eq!(content_type_charset("text/plain"), None);
+    }
+}
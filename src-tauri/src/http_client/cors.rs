@@ -0,0 +1,551 @@
+use crate::errors::AppError;
+use crate::http_client::engine::{HttpEngine, LogEmitter, TauriLogEmitter};
+use crate::http_client::hyper_engine::HyperEngine;
+use crate::http_client::request::Request;
+use crate::http_client::response::ResponseData;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::AppHandle;
+
+/// How the browser-side call would be made
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CorsOptions {
+    /// Origin of the page making the call, e.g. "https://app.example.com"
+    pub origin: String,
+    /// Whether the call sends credentials (`fetch(..., { credentials: "include" })`)
+    #[serde(default)]
+    pub with_credentials: bool,
+    /// Also send the request itself with an `Origin` header and check its response. Off by
+    /// default because the request may have side effects.
+    #[serde(default)]
+    pub send_request: bool,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CorsSeverity {
+    /// The browser blocks the call
+    Error,
+    /// Allowed, but likely not what was intended
+    Warning,
+    Info,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CorsStage {
+    Request,
+    Preflight,
+    Response,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CorsFinding {
+    pub severity: CorsSeverity,
+    pub stage: CorsStage,
+    pub message: String,
+}
+
+/// Status and headers of one exchange made during the analysis
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CorsExchange {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CorsReport {
+    pub origin: String,
+    /// Whether a browser would send a preflight, and why
+    pub preflight_required: bool,
+    pub preflight_reasons: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preflight: Option<CorsExchange>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<CorsExchange>,
+    /// False when any finding is an error
+    pub allowed: bool,
+    pub findings: Vec<CorsFinding>,
+}
+
+const SAFELISTED_METHODS: [&str; 3] = ["GET", "HEAD", "POST"];
+
+/// Headers the browser sets itself; scripts can't send them, so they never trigger a preflight
+const FORBIDDEN_HEADERS: [&str; 21] = [
+    "accept-charset",
+    "accept-encoding",
+    "access-control-request-headers",
+    "access-control-request-method",
+    "connection",
+    "content-length",
+    "cookie",
+    "cookie2",
+    "date",
+    "dnt",
+    "expect",
+    "host",
+    "keep-alive",
+    "origin",
+    "referer",
+    "set-cookie",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "via",
+];
+
+/// Response headers scripts can always read (CORS-safelisted response-header names)
+const SAFELISTED_RESPONSE_HEADERS: [&str; 7] = [
+    "cache-control",
+    "content-language",
+    "content-length",
+    "content-type",
+    "expires",
+    "last-modified",
+    "pragma",
+];
+
+fn is_forbidden_header(name: &str) -> bool {
+    FORBIDDEN_HEADERS.contains(&name) || name.starts_with("proxy-") || name.starts_with("sec-")
+}
+
+/// Whether a request header is CORS-safelisted (Fetch standard, §2.2.2), so sending it
+/// doesn't require a preflight.
+fn is_safelisted_header(name: &str, value: &str) -> bool {
+    match name {
+        "accept" | "accept-language" | "content-language" => value.len() <= 128,
+        "content-type" => {
+            let essence = value
+                .split(';')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_ascii_lowercase();
+            value.len() <= 128
+                && matches!(
+                    essence.as_str(),
+                    "application/x-www-form-urlencoded" | "multipart/form-data" | "text/plain"
+                )
+        }
+        _ => false,
+    }
+}
+
+/// Lowercased names of the request headers that take the call out of "simple" territory,
+/// sorted and deduplicated as `Access-Control-Request-Headers` sends them.
+fn unsafe_headers(headers: &[(String, String)]) -> Vec<String> {
+    let mut names: Vec<String> = headers
+        .iter()
+        .map(|(name, value)| (name.to_ascii_lowercase(), value))
+        .filter(|(name, value)| !is_forbidden_header(name) && !is_safelisted_header(name, value))
+        .map(|(name, _)| name)
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+fn preflight_reasons(method: &str, unsafe_headers: &[String]) -> Vec<String> {
+    let mut reasons = Vec::new();
+    if !SAFELISTED_METHODS.contains(&method) {
+        reasons.push(format!("Method {method} is not GET, HEAD or POST"));
+    }
+    for name in unsafe_headers {
+        reasons.push(format!("Header '{name}' is not CORS-safelisted"));
+    }
+    reasons
+}
+
+/// All values of a header, split on commas as list-valued headers allow
+fn header_values<'a>(headers: &'a [(String, String)], name: &str) -> Vec<&'a str> {
+    headers
+        .iter()
+        .filter(|(n, _)| n.eq_ignore_ascii_case(name))
+        .flat_map(|(_, v)| v.split(','))
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+fn single_header<'a>(headers: &'a [(String, String)], name: &str) -> Vec<&'a str> {
+    headers
+        .iter()
+        .filter(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.trim())
+        .collect()
+}
+
+/// The CORS check run on both the preflight and the actual response
+fn check_origin(
+    headers: &[(String, String)],
+    options: &CorsOptions,
+    stage: CorsStage,
+    findings: &mut Vec<CorsFinding>,
+) {
+    let mut error = |message: String| {
+        findings.push(CorsFinding {
+            severity: CorsSeverity::Error,
+            stage,
+            message,
+        })
+    };
+    match single_header(headers, "access-control-allow-origin").as_slice() {
+        [] => error("Access-Control-Allow-Origin is missing".to_string()),
+        ["*"] if options.with_credentials => error(
+            "Access-Control-Allow-Origin is '*', which is not allowed for credentialed requests; \
+             it must echo the origin"
+                .to_string(),
+        ),
+        ["*"] => {}
+        [value] if *value == options.origin => {}
+        [value] => error(format!(
+            "Access-Control-Allow-Origin is '{value}' but the origin is '{}' (the match is exact, \
+             including scheme, port and trailing slashes)",
+            options.origin
+        )),
+        values => error(format!(
+            "Access-Control-Allow-Origin has {} values; exactly one is allowed",
+            values.len()
+        )),
+    }
+    if options.with_credentials {
+        match single_header(headers, "access-control-allow-credentials").as_slice() {
+            ["true"] => {}
+            [] => error(
+                "Access-Control-Allow-Credentials is missing; credentialed requests need 'true'"
+                    .to_string(),
+            ),
+            [value, ..] => error(format!(
+                "Access-Control-Allow-Credentials is '{value}'; credentialed requests need exactly 'true'"
+            )),
+        }
+    }
+}
+
+fn check_preflight(
+    status: u16,
+    headers: &[(String, String)],
+    method: &str,
+    requested_headers: &[String],
+    options: &CorsOptions,
+) -> Vec<CorsFinding> {
+    let mut findings = Vec::new();
+    let mut push = |severity, message: String| {
+        findings.push(CorsFinding {
+            severity,
+            stage: CorsStage::Preflight,
+            message,
+        })
+    };
+    if !(200..300).contains(&status) {
+        push(
+            CorsSeverity::Error,
+            format!("Preflight returned {status}; browsers require a 2xx status"),
+        );
+    }
+
+    let wildcard_allowed = !options.with_credentials;
+    let methods = header_values(headers, "access-control-allow-methods");
+    let method_allowed = SAFELISTED_METHODS.contains(&method)
+        || methods.contains(&method)
+        || (wildcard_allowed && methods.contains(&"*"));
+    if !method_allowed {
+        let hint = if methods.contains(&"*") {
+            " ('*' is literal for credentialed requests)"
+        } else if methods.iter().any(|m| m.eq_ignore_ascii_case(method)) {
+            " (methods are case-sensitive)"
+        } else {
+            ""
+        };
+        push(
+            CorsSeverity::Error,
+            format!(
+                "Method {method} is not in Access-Control-Allow-Methods [{}]{hint}",
+                methods.join(", ")
+            ),
+        );
+    }
+
+    let allowed_headers: Vec<String> = header_values(headers, "access-control-allow-headers")
+        .into_iter()
+        .map(str::to_ascii_lowercase)
+        .collect();
+    let header_wildcard = wildcard_allowed && allowed_headers.iter().any(|h| h == "*");
+    for name in requested_headers {
+        // The wildcard never covers Authorization
+        let covered =
+            allowed_headers.contains(name) || (header_wildcard && name.as_str() != "authorization");
+        if !covered {
+            push(
+                CorsSeverity::Error,
+                format!(
+                    "Header '{name}' is not in Access-Control-Allow-Headers [{}]",
+                    allowed_headers.join(", ")
+                ),
+            );
+        }
+    }
+
+    match single_header(headers, "access-control-max-age").first() {
+        Some(age) => push(
+            CorsSeverity::Info,
+            format!("Preflight may be cached for {age}s (browsers cap this, Chrome at 7200s)"),
+        ),
+        None => push(
+            CorsSeverity::Info,
+            "No Access-Control-Max-Age; browsers cache the preflight for 5s".to_string(),
+        ),
+    }
+
+    check_origin(headers, options, CorsStage::Preflight, &mut findings);
+    findings
+}
+
+fn check_response(headers: &[(String, String)], options: &CorsOptions) -> Vec<CorsFinding> {
+    let mut findings = Vec::new();
+    check_origin(headers, options, CorsStage::Response, &mut findings);
+
+    let exposed: Vec<String> = header_values(headers, "access-control-expose-headers")
+        .into_iter()
+        .map(str::to_ascii_lowercase)
+        .collect();
+    let expose_all = !options.with_credentials && exposed.iter().any(|h| h == "*");
+    let mut hidden: Vec<String> = headers
+        .iter()
+        .map(|(name, _)| name.to_ascii_lowercase())
+        .filter(|name| {
+            !SAFELISTED_RESPONSE_HEADERS.contains(&name.as_str())
+                && !name.starts_with("access-control-")
+                && name != "set-cookie"
+                && !expose_all
+                && !exposed.contains(name)
+        })
+        .collect();
+    hidden.sort();
+    hidden.dedup();
+    if !hidden.is_empty() {
+        findings.push(CorsFinding {
+            severity: CorsSeverity::Warning,
+            stage: CorsStage::Response,
+            message: format!(
+                "Scripts can't read these headers; list them in Access-Control-Expose-Headers: {}",
+                hidden.join(", ")
+            ),
+        });
+    }
+    findings
+}
+
+fn exchange(response: &ResponseData) -> CorsExchange {
+    CorsExchange {
+        status: response.status,
+        headers: response.headers.clone(),
+    }
+}
+
+/// Checks whether a browser page on `options.origin` could make `request`, sending the
+/// preflight the browser would send and explaining each reason it would block the call.
+pub async fn analyze_cors(
+    app: AppHandle,
+    request: Request,
+    options: CorsOptions,
+) -> Result<CorsReport, AppError> {
+    let emitter: Arc<dyn LogEmitter> = Arc::new(TauriLogEmitter::new(app));
+    let engine = HyperEngine::new();
+
+    let method = request.method.to_ascii_uppercase();
+    let headers = request.headers.clone().unwrap_or_default();
+    let requested_headers = unsafe_headers(&headers);
+    let reasons = preflight_reasons(&method, &requested_headers);
+    let mut findings = Vec::new();
+    if options.with_credentials {
+        findings.push(CorsFinding {
+            severity: CorsSeverity::Info,
+            stage: CorsStage::Request,
+            message: "Credentialed call: wildcards in Access-Control-* headers don't apply"
+                .to_string(),
+        });
+    }
+
+    let preflight = if reasons.is_empty() {
+        None
+    } else {
+        let mut preflight_headers = vec![
+            ("Origin".to_string(), options.origin.clone()),
+            ("Access-Control-Request-Method".to_string(), method.clone()),
+        ];
+        if !requested_headers.is_empty() {
+            preflight_headers.push((
+                "Access-Control-Request-Headers".to_string(),
+                requested_headers.join(","),
+            ));
+        }
+        // Browsers don't send credentials or a body with the preflight, nor follow redirects
+        let response = engine
+            .execute(
+                Request {
+                    request_id: uuid::Uuid::new_v4().to_string(),
+                    method: "OPTIONS".to_string(),
+                    headers: Some(preflight_headers),
+                    body: None,
+                    body_file_path: None,
+                    multipart_parts: None,
+                    content_encoding: None,
+                    max_redirects: Some(0),
+                    retry: None,
+                    ..request.clone()
+                },
+                emitter.clone(),
+            )
+            .await?;
+        findings.extend(check_preflight(
+            response.status,
+            &response.headers,
+            &method,
+            &requested_headers,
+            &options,
+        ));
+        Some(exchange(&response))
+    };
+
+    let preflight_failed = findings.iter().any(|f| f.severity == CorsSeverity::Error);
+    let response = if options.send_request && !preflight_failed {
+        let mut headers = headers;
+        headers.retain(|(name, _)| !name.eq_ignore_ascii_case("origin"));
+        headers.push(("Origin".to_string(), options.origin.clone()));
+        let response = engine
+            .execute(
+                Request {
+                    request_id: uuid::Uuid::new_v4().to_string(),
+                    headers: Some(headers),
+                    ..request
+                },
+                emitter,
+            )
+            .await?;
+        findings.extend(check_response(&response.headers, &options));
+        Some(exchange(&response))
+    } else {
+        None
+    };
+
+    Ok(CorsReport {
+        origin: options.origin,
+        preflight_required: !reasons.is_empty(),
+        preflight_reasons: reasons,
+        preflight,
+        response,
+        allowed: !findings.iter().any(|f| f.severity == CorsSeverity::Error),
+        findings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CorsOptions, CorsSeverity, check_preflight, check_response, preflight_reasons,
+        unsafe_headers,
+    };
+
+    fn headers(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn options(with_credentials: bool) -> CorsOptions {
+        CorsOptions {
+            origin: "https://app.example.com".to_string(),
+            with_credentials,
+            send_request: false,
+        }
+    }
+
+    fn errors(findings: &[super::CorsFinding]) -> Vec<&str> {
+        findings
+            .iter()
+            .filter(|f| f.severity == CorsSeverity::Error)
+            .map(|f| f.message.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn detects_when_a_preflight_is_needed() {
+        let simple = headers(&[
+            ("Accept", "application/json"),
+            ("Content-Type", "text/plain; charset=utf-8"),
+            ("User-Agent", "x"),
+        ]);
+        assert_eq!(unsafe_headers(&simple), vec!["user-agent"]);
+
+        let json = headers(&[
+            ("Content-Type", "application/json"),
+            ("X-Api-Key", "k"),
+            ("Host", "example.com"),
+            ("X-Api-Key", "again"),
+        ]);
+        let names = unsafe_headers(&json);
+        assert_eq!(names, vec!["content-type", "x-api-key"]);
+        assert_eq!(preflight_reasons("PUT", &names).len(), 3);
+        assert!(preflight_reasons("GET", &[]).is_empty());
+    }
+
+    #[test]
+    fn explains_a_rejected_preflight() {
+        let requested = vec!["authorization".to_string(), "x-trace".to_string()];
+        let response = headers(&[
+            ("access-control-allow-origin", "*"),
+            ("access-control-allow-methods", "GET, post, *"),
+            ("access-control-allow-headers", "*"),
+        ]);
+
+        let anonymous = check_preflight(204, &response, "PUT", &requested, &options(false));
+        // The header wildcard covers everything but Authorization
+        assert_eq!(errors(&anonymous).len(), 1);
+        assert!(errors(&anonymous)[0].contains("'authorization'"));
+
+        let credentialed = check_preflight(204, &response, "PUT", &requested, &options(true));
+        let messages = errors(&credentialed);
+        assert!(messages.iter().any(|m| m.starts_with("Method PUT")));
+        assert!(messages.iter().any(|m| m.contains("'x-trace'")));
+        assert!(
+            messages
+                .iter()
+                .any(|m| m.contains("'*', which is not allowed"))
+        );
+        assert!(
+            messages
+                .iter()
+                .any(|m| m.contains("Allow-Credentials is missing"))
+        );
+
+        let wrong_origin = headers(&[("access-control-allow-origin", "https://app.example.com/")]);
+        let findings = check_preflight(403, &wrong_origin, "GET", &[], &options(false));
+        let messages = errors(&findings);
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].contains("403"));
+        assert!(messages[1].contains("exact"));
+    }
+
+    #[test]
+    fn accepts_a_matching_response_and_lists_hidden_headers() {
+        let response = headers(&[
+            ("Access-Control-Allow-Origin", "https://app.example.com"),
+            ("Access-Control-Allow-Credentials", "true"),
+            ("Access-Control-Expose-Headers", "X-Request-Id"),
+            ("Content-Type", "application/json"),
+            ("X-Request-Id", "1"),
+            ("X-RateLimit-Remaining", "9"),
+        ]);
+        let findings = check_response(&response, &options(true));
+        assert!(errors(&findings).is_empty());
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0].message.ends_with(": x-ratelimit-remaining"));
+    }
+}
@@ -0,0 +1,345 @@
+//! Data-driven runs: a set of requests sent once per row of a CSV or JSON data file, with the
+//! row's values substituted for `{{column}}` placeholders, and the results gathered per row.
+
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::engine::{HttpEngine, LogEmitter, TauriLogEmitter};
+use crate::http_client::hyper_engine::HyperEngine;
+use crate::http_client::request::Request;
+use crate::http_client::response::ResponseData;
+use crate::http_client::variables;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::AppHandle;
+
+/// Format of a data file
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DataFormat {
+    /// Comma-separated values with a header row naming the columns (RFC 4180)
+    Csv,
+    /// An array of objects, one per row
+    Json,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DataRunOptions {
+    /// Id the run is cancelled by
+    pub run_id: String,
+    /// Path of the data file
+    pub data_path: String,
+    /// Detected from the file's first character when unset: `[` is JSON, anything else CSV
+    pub format: Option<DataFormat>,
+    /// Delay between rows
+    #[serde(default)]
+    pub delay_ms: u64,
+    /// Stop after the first row that fails
+    #[serde(default)]
+    pub stop_on_failure: bool,
+}
+
+/// One request of a row
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataRunStep {
+    pub request_id: String,
+    /// URL after substitution
+    pub url: String,
+    /// Sent without error, and every assertion of the request passed
+    pub passed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<ResponseData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<AppError>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataRunRow {
+    /// Position of the row in the data file, from 0
+    pub index: usize,
+    /// Column name/value pairs substituted into the requests
+    pub values: Vec<(String, String)>,
+    pub steps: Vec<DataRunStep>,
+    /// Every step passed
+    pub passed: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataRunReport {
+    pub columns: Vec<String>,
+    pub rows: Vec<DataRunRow>,
+    /// Rows whose steps all passed
+    pub passed: usize,
+    pub failed: usize,
+    /// Rows left unsent because `stop_on_failure` ended the run
+    pub skipped: usize,
+}
+
+/// A data file's column names and rows of values, in column order
+type DataRows = (Vec<String>, Vec<Vec<(String, String)>>);
+
+/// Parses a data file, detecting its format when `format` isn't given.
+pub fn parse_data(source: &str, format: Option<DataFormat>) -> Result<DataRows, AppError> {
+    let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+    let format = format.unwrap_or(if source.trim_start().starts_with('[') {
+        DataFormat::Json
+    } else {
+        DataFormat::Csv
+    });
+    match format {
+        DataFormat::Csv => parse_csv(source),
+        DataFormat::Json => parse_json(source),
+    }
+}
+
+fn parse_csv(source: &str) -> Result<DataRows, AppError> {
+    let mut records = csv_records(source)?.into_iter();
+    let Some(columns) = records.next() else {
+        return Ok((Vec::new(), Vec::new()));
+    };
+    let rows = records
+        .map(|record| {
+            // Missing trailing fields are empty; extra ones have no column to go in
+            columns
+                .iter()
+                .enumerate()
+                .map(|(i, column)| (column.clone(), record.get(i).cloned().unwrap_or_default()))
+                .collect()
+        })
+        .collect();
+    Ok((columns, rows))
+}
+
+/// Splits CSV into records of fields. Quoted fields may hold commas, line breaks and doubled
+/// quotes; blank lines are skipped.
+fn csv_records(source: &str) -> Result<Vec<Vec<String>>, AppError> {
+    let is_blank = |record: &[String]| matches!(record, [field] if field.is_empty());
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            _ if quoted => field.push(c),
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                if is_blank(&record) {
+                    record.clear();
+                } else {
+                    records.push(std::mem::take(&mut record));
+                }
+            }
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            "Data file has an unterminated quoted field",
+        ));
+    }
+    record.push(field);
+    if !is_blank(&record) {
+        records.push(record);
+    }
+    Ok(records)
+}
+
+fn parse_json(source: &str) -> Result<DataRows, AppError> {
+    let data: Value = serde_json::from_str(source).map_err(|e| {
+        AppError::new(
+            ErrorKind::JsonError,
+            format!("Data file isn't valid JSON: {e}"),
+        )
+    })?;
+    let Value::Array(items) = data else {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            "A JSON data file must be an array of objects",
+        ));
+    };
+    let mut columns: Vec<String> = Vec::new();
+    let mut rows = Vec::with_capacity(items.len());
+    for (index, item) in items.into_iter().enumerate() {
+        let Value::Object(object) = item else {
+            return Err(AppError::new(
+                ErrorKind::BadRequest,
+                format!("Row {index} of the data file isn't an object"),
+            ));
+        };
+        let row: Vec<(String, String)> = object
+            .into_iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    Value::String(text) => text,
+                    Value::Null => String::new(),
+                    other => other.to_string(),
+                };
+                (key, value)
+            })
+            .collect();
+        for (key, _) in &row {
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+        rows.push(row);
+    }
+    Ok((columns, rows))
+}
+
+/// Puts the values `response` extracted at the front of `row`, where they're found before
+/// the columns and any earlier value of the same name
+fn chain_variables(row: &mut Vec<(String, String)>, response: &ResponseData) {
+    for variable in response.variables.iter().flatten() {
+        if let Some(value) = &variable.value {
+            row.insert(0, (variable.name.clone(), value.clone()));
+        }
+    }
+}
+
+/// Sends `requests` in order once for each row of the data file at `options.data_path`.
+/// A request that fails to send fails its row without ending the run. Variables a request
+/// extracts are substituted into the row's later requests, ahead of columns of the same name.
+pub async fn run(
+    app: AppHandle,
+    requests: Vec<Request>,
+    options: DataRunOptions,
+) -> Result<DataRunReport, AppError> {
+    let source = tokio::fs::read_to_string(&options.data_path)
+        .await
+        .map_err(|e| {
+            AppError::new(
+                ErrorKind::IoError,
+                format!("Failed to read the data file: {e}"),
+            )
+        })?;
+    let (columns, rows) = parse_data(&source, options.format)?;
+    let emitter: Arc<dyn LogEmitter> = Arc::new(TauriLogEmitter::new(app));
+    let engine = HyperEngine::new();
+
+    let mut report = DataRunReport {
+        columns,
+        rows: Vec::with_capacity(rows.len()),
+        passed: 0,
+        failed: 0,
+        skipped: 0,
+    };
+    let total = rows.len();
+    for (index, values) in rows.into_iter().enumerate() {
+        if index > 0 && options.delay_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(options.delay_ms)).await;
+        }
+        let mut steps = Vec::with_capacity(requests.len());
+        let mut row = values.clone();
+        for request in &requests {
+            let request = variables::apply(request, &row);
+            let request_id = request.request_id.clone();
+            let url = request.url.clone();
+            let step = match engine.execute(request, emitter.clone()).await {
+                Ok(response) => {
+                    chain_variables(&mut row, &response);
+                    DataRunStep {
+                        request_id,
+                        url,
+                        passed: response
+                            .assertions
+                            .iter()
+                            .flatten()
+                            .all(|result| result.passed),
+                        response: Some(response),
+                        error: None,
+                    }
+                }
+                Err(error) => DataRunStep {
+                    request_id,
+                    url,
+                    passed: false,
+                    response: None,
+                    error: Some(error),
+                },
+            };
+            steps.push(step);
+        }
+        let passed = steps.iter().all(|step| step.passed);
+        if passed {
+            report.passed += 1;
+        } else {
+            report.failed += 1;
+        }
+        report.rows.push(DataRunRow {
+            index,
+            values,
+            steps,
+            passed,
+        });
+        if !passed && options.stop_on_failure {
+            report.skipped = total - index - 1;
+            break;
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DataFormat, parse_data};
+
+    fn row(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_csv_with_quoted_fields() {
+        let source = "\u{feff}id,name,note\r\n1,Ada,\"likes \"\"maths\"\", chess\"\r\n\r\n2,Grace,\"line\nbreak\"\n3\n";
+        let (columns, rows) = parse_data(source, None).unwrap();
+        assert_eq!(columns, ["id", "name", "note"]);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(
+            rows[0],
+            row(&[
+                ("id", "1"),
+                ("name", "Ada"),
+                ("note", "likes \"maths\", chess")
+            ])
+        );
+        assert_eq!(rows[1][2].1, "line\nbreak");
+        assert_eq!(rows[2], row(&[("id", "3"), ("name", ""), ("note", "")]));
+
+        assert!(parse_data("id\n\"open", Some(DataFormat::Csv)).is_err());
+    }
+
+    #[test]
+    fn parses_json_arrays_of_objects() {
+        let source = r#" [{"id": 1, "name": "Ada", "tags": ["a"]}, {"active": true, "id": 2, "name": null}]"#;
+        let (columns, rows) = parse_data(source, None).unwrap();
+        assert_eq!(columns, ["id", "name", "tags", "active"]);
+        assert_eq!(
+            rows[0],
+            row(&[("id", "1"), ("name", "Ada"), ("tags", r#"["a"]"#)])
+        );
+        assert_eq!(
+            rows[1],
+            row(&[("active", "true"), ("id", "2"), ("name", "")])
+        );
+
+        assert!(parse_data(r#"{"id": 1}"#, Some(DataFormat::Json)).is_err());
+        assert!(parse_data("[1, 2]", None).is_err());
+    }
+}
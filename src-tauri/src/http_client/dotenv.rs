@@ -0,0 +1,143 @@
+//! `.env` files, read by the backend so local development credentials can back request
+//! variables without being copied into the app's storage.
+
+use crate::errors::{AppError, ErrorKind};
+use std::collections::HashMap;
+
+/// End of a double-quoted value: the first quote not escaped with a backslash
+fn closing_quote(text: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some(c @ ('"' | '\\')) => out.push(c),
+            Some(c) => {
+                out.push('\\');
+                out.push(c);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Parses a `.env` file: `NAME=value` lines, optionally starting with `export`, and `#`
+/// comments. Single-quoted values are taken as they are; double-quoted values may span lines
+/// and use `\n`, `\r`, `\t`, `\"` and `\\`. `${NAME}` isn't expanded. Later definitions win.
+pub fn parse(text: &str) -> Result<HashMap<String, String>, String> {
+    let mut values = HashMap::new();
+    let mut lines = text.lines().enumerate();
+    while let Some((index, line)) = lines.next() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").map_or(line, str::trim_start);
+        let (name, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("Line {}: expected NAME=value", index + 1))?;
+        let name = name.trim();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(format!("Line {}: invalid name '{name}'", index + 1));
+        }
+        let value = value.trim_start();
+        let value = if let Some(quoted) = value.strip_prefix('"') {
+            let mut raw = quoted.to_string();
+            loop {
+                if let Some(end) = closing_quote(&raw) {
+                    break unescape(&raw[..end]);
+                }
+                let (_, next) = lines
+                    .next()
+                    .ok_or_else(|| format!("Line {}: unclosed quote", index + 1))?;
+                raw.push('\n');
+                raw.push_str(next);
+            }
+        } else if let Some(quoted) = value.strip_prefix('\'') {
+            quoted
+                .find('\'')
+                .map(|end| quoted[..end].to_string())
+                .ok_or_else(|| format!("Line {}: unclosed quote", index + 1))?
+        } else {
+            let value = value.find(" #").map_or(value, |comment| &value[..comment]);
+            value.trim_end().to_string()
+        };
+        values.insert(name.to_string(), value);
+    }
+    Ok(values)
+}
+
+/// Reads and parses the `.env` file at `path`
+pub fn load(path: &str) -> Result<HashMap<String, String>, AppError> {
+    let text = std::fs::read_to_string(path).map_err(|e| {
+        AppError::new(
+            ErrorKind::FileNotFound,
+            format!("Failed to read '{path}': {e}"),
+        )
+    })?;
+    parse(&text)
+        .map_err(|message| AppError::new(ErrorKind::BadRequest, format!("{path}: {message}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    #[test]
+    fn parses_quoted_exported_and_commented_values() {
+        let values = parse(
+            "# local settings\n\
+             API_KEY=abc123 # from the dashboard\n\
+             export REGION = eu-west-1\n\
+             EMPTY=\n\
+             LITERAL='a \\n #b'\n\
+             ESCAPED=\"say \\\"hi\\\"\\tnow\"\n\
+             PEM=\"-----BEGIN KEY-----\n\
+             MIIB\n\
+             -----END KEY-----\"\n\
+             API_KEY=overridden\n",
+        )
+        .unwrap();
+        assert_eq!(values["API_KEY"], "overridden");
+        assert_eq!(values["REGION"], "eu-west-1");
+        assert_eq!(values["EMPTY"], "");
+        assert_eq!(values["LITERAL"], "a \\n #b");
+        assert_eq!(values["ESCAPED"], "say \"hi\"\tnow");
+        assert_eq!(
+            values["PEM"],
+            "-----BEGIN KEY-----\nMIIB\n-----END KEY-----"
+        );
+        assert_eq!(values.len(), 6);
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        assert_eq!(
+            parse("A=1\nnot a pair").unwrap_err(),
+            "Line 2: expected NAME=value"
+        );
+        assert!(parse("MY VAR=1").is_err());
+        assert!(parse("A=\"open\nB=2").is_err());
+        assert!(parse("A='open").is_err());
+    }
+}
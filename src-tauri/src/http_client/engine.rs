@@ -1,11 +1,13 @@
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use tauri::Emitter;
 
 use crate::errors::AppError;
-use crate::http_client::request::Request;
-use crate::http_client::response::{LogEntry, ResponseData};
+use crate::http_client::request::{LogCapture, Request};
+use crate::http_client::request_log;
+use crate::http_client::response::{LogEntry, LogLevel, LogSummary, ResponseData};
 
 pub type EngineFuture = Pin<Box<dyn Future<Output = Result<ResponseData, AppError>> + Send>>;
 
@@ -29,6 +31,165 @@ impl TauriLogEmitter {
 
 impl LogEmitter for TauriLogEmitter {
     fn emit(&self, entry: LogEntry) {
+        request_log::retain(&entry);
         let _ = self.app_handle.emit("http-request-log", entry);
     }
 }
+
+/// Counts a request's log entries and passes them on only when the request's log capture
+/// mode keeps them. A sampled request is kept or dropped as a whole.
+pub struct CountingEmitter {
+    inner: Option<Arc<dyn LogEmitter>>,
+    entries: AtomicU64,
+    warnings: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl CountingEmitter {
+    pub fn new(mode: LogCapture, inner: Arc<dyn LogEmitter>) -> Self {
+        Self::with_draw(mode, inner, rand::random())
+    }
+
+    /// `draw` is a uniform sample in [0, 1) deciding whether a sampled request is kept.
+    fn with_draw(mode: LogCapture, inner: Arc<dyn LogEmitter>, draw: f64) -> Self {
+        let captured = match mode {
+            LogCapture::Full => true,
+            LogCapture::Sampled { rate } => draw < rate,
+            LogCapture::Off => false,
+        };
+        Self {
+            inner: captured.then_some(inner),
+            entries: AtomicU64::new(0),
+            warnings: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+        }
+    }
+
+    pub fn summary(&self) -> LogSummary {
+        LogSummary {
+            captured: self.inner.is_some(),
+            entries: self.entries.load(Ordering::Relaxed),
+            warnings: self.warnings.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl LogEmitter for CountingEmitter {
+    fn emit(&self, entry: LogEntry) {
+        self.entries.fetch_add(1, Ordering::Relaxed);
+        match entry.level {
+            LogLevel::Warning => self.warnings.fetch_add(1, Ordering::Relaxed),
+            LogLevel::Error => self.errors.fetch_add(1, Ordering::Relaxed),
+            LogLevel::Info | LogLevel::Debug => 0,
+        };
+        if let Some(inner) = &self.inner {
+            inner.emit(entry);
+        }
+    }
+}
+
+/// Log emitters for tests
+#[cfg(test)]
+pub(crate) mod test_emitters {
+    use super::LogEmitter;
+    use crate::http_client::response::LogEntry;
+    use std::sync::Mutex;
+
+    /// Drops every entry
+    pub(crate) struct Discard;
+
+    impl LogEmitter for Discard {
+        fn emit(&self, _entry: LogEntry) {}
+    }
+
+    /// Keeps every entry
+    #[derive(Default)]
+    pub(crate) struct Collect(pub(crate) Mutex<Vec<LogEntry>>);
+
+    impl LogEmitter for Collect {
+        fn emit(&self, entry: LogEntry) {
+            self.0.lock().unwrap().push(entry);
+        }
+    }
+
+    impl Collect {
+        /// The phases of the entries kept so far, in order
+        pub(crate) fn phases(&self) -> Vec<String> {
+            self.0
+                .lock()
+                .unwrap()
+                .iter()
+                .filter_map(|entry| entry.phase.clone())
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_emitters::Collect;
+    use super::{CountingEmitter, LogEmitter};
+    use crate::http_client::request::LogCapture;
+    use crate::http_client::response::{LogEntry, LogLevel};
+    use std::sync::Arc;
+
+    fn entry(level: LogLevel) -> LogEntry {
+        LogEntry {
+            request_id: "r".to_string(),
+            timestamp: String::new(),
+            level,
+            info_type: None,
+            message: String::new(),
+            category: None,
+            phase: None,
+            elapsed_ms: None,
+            details: None,
+            bytes_logged: None,
+            truncated: None,
+        }
+    }
+
+    fn run(mode: LogCapture, draw: f64) -> (usize, super::LogSummary) {
+        let sink = Arc::new(Collect::default());
+        let emitter = CountingEmitter::with_draw(mode, sink.clone(), draw);
+        for level in [
+            LogLevel::Info,
+            LogLevel::Debug,
+            LogLevel::Warning,
+            LogLevel::Error,
+        ] {
+            emitter.emit(entry(level));
+        }
+        let sent = sink.0.lock().unwrap().len();
+        (sent, emitter.summary())
+    }
+
+    #[test]
+    fn counts_entries_whether_or_not_they_are_sent() {
+        let (sent, summary) = run(LogCapture::Full, 0.9);
+        assert_eq!(sent, 4);
+        assert!(summary.captured);
+        assert_eq!(
+            (summary.entries, summary.warnings, summary.errors),
+            (4, 1, 1)
+        );
+
+        let (sent, summary) = run(LogCapture::Off, 0.0);
+        assert_eq!(sent, 0);
+        assert!(!summary.captured);
+        assert_eq!(
+            (summary.entries, summary.warnings, summary.errors),
+            (4, 1, 1)
+        );
+    }
+
+    #[test]
+    fn samples_whole_requests() {
+        let sampled = LogCapture::Sampled { rate: 0.25 };
+        assert_eq!(run(sampled, 0.1).0, 4);
+        assert_eq!(run(sampled, 0.5).0, 0);
+        assert_eq!(run(LogCapture::Sampled { rate: 0.0 }, 0.0).0, 0);
+        assert_eq!(run(LogCapture::Sampled { rate: 1.0 }, 0.999).0, 4);
+    }
+}
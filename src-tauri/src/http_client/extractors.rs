@@ -0,0 +1,253 @@
+//! Values pulled out of a response into named variables once it has arrived, so requests can
+//! be chained on the backend without the body travelling through the webview.
+
+use crate::http_client::charset;
+use crate::http_client::response::ResponseData;
+use crate::http_client::xml;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_json_path::JsonPath;
+use std::collections::HashMap;
+
+/// Where a variable's value comes from
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum Extractor {
+    /// The first node the JSONPath selects in the JSON body. Strings are taken as they are,
+    /// other values as JSON.
+    JsonPath { name: String, path: String },
+    /// The XPath 1.0 expression's value in the XML body, with the prefixes the body declares.
+    /// A node set gives the text of its first node.
+    #[serde(rename = "xpath")]
+    XPath { name: String, path: String },
+    /// The header's values, joined with ", "
+    Header { name: String, header: String },
+    /// The first match of the regular expression in the body, read as UTF-8: capture `group`
+    /// when given, else the first group when the pattern has one, else the whole match
+    Regex {
+        name: String,
+        pattern: String,
+        group: Option<usize>,
+    },
+}
+
+impl Extractor {
+    /// Name of the variable the value is stored under
+    pub fn name(&self) -> &str {
+        match self {
+            Self::JsonPath { name, .. }
+            | Self::XPath { name, .. }
+            | Self::Header { name, .. }
+            | Self::Regex { name, .. } => name,
+        }
+    }
+
+    /// Whether the extractor reads the body, which then has to be at hand
+    pub fn reads_body(&self) -> bool {
+        !matches!(self, Self::Header { .. })
+    }
+}
+
+/// A variable pulled from the response, or why it couldn't be
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractedVariable {
+    pub name: String,
+    /// Unset when the response didn't hold the value
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Runs `extractors` against `response`, whose body is `body` wherever it was kept
+pub fn extract(
+    extractors: &[Extractor],
+    response: &ResponseData,
+    body: &[u8],
+) -> Vec<ExtractedVariable> {
+    extractors
+        .iter()
+        .map(|extractor| {
+            let (value, message) = match extract_one(extractor, response, body) {
+                Ok(value) => (Some(value), None),
+                Err(message) => (None, Some(message)),
+            };
+            ExtractedVariable {
+                name: extractor.name().to_string(),
+                value,
+                message,
+            }
+        })
+        .collect()
+}
+
+fn extract_one(
+    extractor: &Extractor,
+    response: &ResponseData,
+    body: &[u8],
+) -> Result<String, String> {
+    match extractor {
+        Extractor::JsonPath { path, .. } => {
+            let query =
+                JsonPath::parse(path).map_err(|e| format!("Invalid JSONPath {path}: {e}"))?;
+            let document: Value =
+                serde_json::from_slice(body).map_err(|e| format!("Body isn't JSON: {e}"))?;
+            match query.query(&document).first() {
+                Some(Value::String(text)) => Ok(text.clone()),
+                Some(node) => Ok(node.to_string()),
+                None => Err(format!("{path} matched nothing")),
+            }
+        }
+        Extractor::XPath { path, .. } => {
+            let text = charset::response_text(response, body);
+            let package =
+                sxd_document::parser::parse(&text).map_err(|e| format!("Body isn't XML: {e}"))?;
+            let document = package.as_document();
+            match xml::xpath(&document, path, &HashMap::new())? {
+                sxd_xpath::Value::Nodeset(nodes) => nodes
+                    .document_order_first()
+                    .map(|node| node.string_value())
+                    .ok_or_else(|| format!("{path} matched nothing")),
+                value => Ok(value.string()),
+            }
+        }
+        Extractor::Header { header, .. } => {
+            let values: Vec<&str> = response
+                .headers
+                .iter()
+                .filter(|(key, _)| key.eq_ignore_ascii_case(header))
+                .map(|(_, value)| value.as_str())
+                .collect();
+            if values.is_empty() {
+                return Err(format!("Header {header} is missing"));
+            }
+            Ok(values.join(", "))
+        }
+        Extractor::Regex { pattern, group, .. } => {
+            let regex =
+                Regex::new(pattern).map_err(|e| format!("Invalid pattern {pattern}: {e}"))?;
+            let text = charset::response_text(response, body);
+            let captures = regex
+                .captures(&text)
+                .ok_or_else(|| format!("{pattern} matched nothing"))?;
+            let group = group.unwrap_or(if regex.captures_len() > 1 { 1 } else { 0 });
+            captures
+                .get(group)
+                .map(|found| found.as_str().to_string())
+                .ok_or_else(|| format!("Group {group} of {pattern} didn't match"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Extractor, extract};
+    use crate::http_client::response::{ResponseData, Timings};
+    use serde_json::json;
+
+    fn response(headers: &[(&str, &str)]) -> ResponseData {
+        ResponseData {
+            request_id: "r".to_string(),
+            status: 200,
+            status_text: "OK".to_string(),
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            binary_headers: Vec::new(),
+            cookies: Vec::new(),
+            body: Vec::new(),
+            file_path: None,
+            file_compressed: false,
+            truncated: false,
+            cancelled: false,
+            saved_to: None,
+            resumed_from: None,
+            content_range: None,
+            cache: None,
+            size: 0,
+            duration: 0,
+            timings: Timings::default(),
+            connection: None,
+            revocation: None,
+            rate_limit: None,
+            redirect_chain: None,
+            retries: None,
+            log_summary: None,
+            multistatus: None,
+            soap_fault: None,
+            assertions: None,
+            variables: None,
+            timestamp: String::new(),
+        }
+    }
+
+    fn extractors(value: serde_json::Value) -> Vec<Extractor> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn extracts_from_json_headers_and_text() {
+        let body = br#"{"token": "abc", "user": {"id": 7}, "next": "/page?cursor=x9"}"#;
+        let results = extract(
+            &extractors(json!([
+                {"type": "jsonPath", "name": "token", "path": "$.token"},
+                {"type": "jsonPath", "name": "user", "path": "$.user"},
+                {"type": "jsonPath", "name": "missing", "path": "$.nope"},
+                {"type": "header", "name": "etag", "header": "ETag"},
+                {"type": "regex", "name": "cursor", "pattern": "cursor=(\\w+)"},
+                {"type": "regex", "name": "whole", "pattern": "cursor=\\w+", "group": 0},
+            ])),
+            &response(&[("etag", "\"v1\"")]),
+            body,
+        );
+        let values: Vec<Option<&str>> = results.iter().map(|r| r.value.as_deref()).collect();
+        assert_eq!(
+            values,
+            [
+                Some("abc"),
+                Some(r#"{"id":7}"#),
+                None,
+                Some("\"v1\""),
+                Some("x9"),
+                Some("cursor=x9")
+            ]
+        );
+        assert_eq!(
+            results[2].message.as_deref(),
+            Some("$.nope matched nothing")
+        );
+    }
+
+    #[test]
+    fn extracts_from_xml() {
+        let body = br#"<order id="42"><item sku="a">Pen</item><item sku="b">Ink</item></order>"#;
+        let results = extract(
+            &extractors(json!([
+                {"type": "xpath", "name": "id", "path": "/order/@id"},
+                {"type": "xpath", "name": "item", "path": "//item[@sku='b']"},
+                {"type": "xpath", "name": "count", "path": "count(//item)"},
+                {"type": "xpath", "name": "none", "path": "//missing"},
+            ])),
+            &response(&[]),
+            body,
+        );
+        let values: Vec<Option<&str>> = results.iter().map(|r| r.value.as_deref()).collect();
+        assert_eq!(values, [Some("42"), Some("Ink"), Some("2"), None]);
+
+        let not_xml = extractors(json!([{"type": "xpath", "name": "x", "path": "//a"}]));
+        let results = extract(&not_xml, &response(&[]), b"{}");
+        assert!(
+            results[0]
+                .message
+                .as_deref()
+                .unwrap()
+                .starts_with("Body isn't XML")
+        );
+    }
+}
@@ -0,0 +1,138 @@
+//! Built-in `{{generator args}}` placeholders, given a fresh value each time one is substituted:
+//!
+//! - `{{uuid}}`: a random (v4) UUID
+//! - `{{timestamp}}`, `{{timestampMs}}`: Unix time in seconds or milliseconds
+//! - `{{isoDate}}`: ISO 8601 date and time in UTC, e.g. `2024-05-01T12:00:00Z`
+//! - `{{randomInt min max}}`: a whole number from `min` to `max` inclusive, 0 to 1000 by default
+//!
+//! The times take an offset from now, e.g. `{{isoDate +1d}}` or `{{timestamp -30m}}`, in
+//! seconds (`s`), minutes (`m`), hours (`h`), days (`d`) or weeks (`w`).
+
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use rand::Rng;
+
+const DEFAULT_RANDOM_MIN: i64 = 0;
+const DEFAULT_RANDOM_MAX: i64 = 1000;
+
+/// Value of the generator `expression` names, e.g. `randomInt 1 100`. `None` when it isn't a
+/// generator, an error when its arguments aren't valid.
+pub fn generate(expression: &str, now: DateTime<Utc>) -> Option<Result<String, String>> {
+    let mut words = expression.split_whitespace();
+    let name = words.next()?;
+    let args: Vec<&str> = words.collect();
+    let value = match name {
+        "uuid" => no_args(name, &args).map(|_| uuid::Uuid::new_v4().to_string()),
+        "timestamp" => offset_time(name, &args, now).map(|time| time.timestamp().to_string()),
+        "timestampMs" => {
+            offset_time(name, &args, now).map(|time| time.timestamp_millis().to_string())
+        }
+        "isoDate" => offset_time(name, &args, now)
+            .map(|time| time.to_rfc3339_opts(SecondsFormat::Secs, true)),
+        "randomInt" => random_int(&args),
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn no_args(name: &str, args: &[&str]) -> Result<(), String> {
+    if args.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("{{{{{name}}}}} takes no arguments"))
+    }
+}
+
+fn offset_time(name: &str, args: &[&str], now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    match args {
+        [] => Ok(now),
+        [offset] => parse_offset(offset)
+            .and_then(|offset| now.checked_add_signed(offset))
+            .ok_or_else(|| format!("Invalid offset '{offset}', e.g. +1d or -30m")),
+        _ => Err(format!("{{{{{name}}}}} takes at most an offset")),
+    }
+}
+
+/// Parses an offset such as `+1d`, `-30m` or `45s`
+fn parse_offset(offset: &str) -> Option<Duration> {
+    let (sign, rest) = match offset.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, offset.strip_prefix('+').unwrap_or(offset)),
+    };
+    let unit = rest.chars().last()?;
+    let amount: i64 = rest[..rest.len() - unit.len_utf8()].parse().ok()?;
+    let seconds = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    amount
+        .checked_mul(seconds)
+        .and_then(|seconds| seconds.checked_mul(sign))
+        .and_then(Duration::try_seconds)
+}
+
+fn random_int(args: &[&str]) -> Result<String, String> {
+    let (min, max) = match args {
+        [] => (DEFAULT_RANDOM_MIN, DEFAULT_RANDOM_MAX),
+        [min, max] => match (min.parse::<i64>(), max.parse::<i64>()) {
+            (Ok(min), Ok(max)) => (min, max),
+            _ => {
+                return Err(format!(
+                    "Invalid bounds '{min} {max}' for {{{{randomInt}}}}"
+                ));
+            }
+        },
+        _ => return Err("{{randomInt}} takes a minimum and a maximum, or neither".to_string()),
+    };
+    if min > max {
+        return Err(format!(
+            "{{{{randomInt}}}}'s minimum {min} is above its maximum {max}"
+        ));
+    }
+    Ok(rand::rng().random_range(min..=max).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::generate;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn generates_times_and_random_values() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let value = |expression: &str| generate(expression, now).unwrap().unwrap();
+
+        assert_eq!(value("timestamp"), "1714564800");
+        assert_eq!(value("timestampMs -1s"), "1714564799000");
+        assert_eq!(value("isoDate +1d"), "2024-05-02T12:00:00Z");
+        assert_eq!(value(" isoDate  -2w "), "2024-04-17T12:00:00Z");
+        assert_eq!(
+            uuid::Uuid::parse_str(&value("uuid"))
+                .unwrap()
+                .get_version_num(),
+            4
+        );
+        assert_eq!(value("randomInt 7 7"), "7");
+        let random: i64 = value("randomInt -5 5").parse().unwrap();
+        assert!((-5..=5).contains(&random));
+    }
+
+    #[test]
+    fn rejects_bad_arguments_and_ignores_other_names() {
+        let now = Utc::now();
+        assert!(generate("token", now).is_none());
+        assert!(generate("", now).is_none());
+        for expression in [
+            "uuid 4",
+            "isoDate +1y",
+            "isoDate 1 2",
+            "randomInt 9 1",
+            "randomInt 5",
+        ] {
+            assert!(generate(expression, now).unwrap().is_err(), "{expression}");
+        }
+    }
+}
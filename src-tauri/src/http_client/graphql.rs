@@ -0,0 +1,233 @@
+//! GraphQL over HTTP: operation bodies and schema introspection.
+
+use crate::errors::{AppError, ErrorKind};
+use hyper::http::{HeaderMap, HeaderValue, header};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Preferred response type of the GraphQL over HTTP spec, with plain JSON for older servers
+const ACCEPT: &str = "application/graphql-response+json, application/json;q=0.9";
+
+/// The introspection query of the GraphQL reference implementation
+pub const INTROSPECTION_QUERY: &str = r#"query IntrospectionQuery {
+  __schema {
+    queryType { name }
+    mutationType { name }
+    subscriptionType { name }
+    types { ...FullType }
+    directives {
+      name
+      description
+      locations
+      args { ...InputValue }
+    }
+  }
+}
+
+fragment FullType on __Type {
+  kind
+  name
+  description
+  fields(includeDeprecated: true) {
+    name
+    description
+    args { ...InputValue }
+    type { ...TypeRef }
+    isDeprecated
+    deprecationReason
+  }
+  inputFields { ...InputValue }
+  interfaces { ...TypeRef }
+  enumValues(includeDeprecated: true) {
+    name
+    description
+    isDeprecated
+    deprecationReason
+  }
+  possibleTypes { ...TypeRef }
+}
+
+fragment InputValue on __InputValue {
+  name
+  description
+  type { ...TypeRef }
+  defaultValue
+}
+
+fragment TypeRef on __Type {
+  kind
+  name
+  ofType {
+    kind
+    name
+    ofType {
+      kind
+      name
+      ofType {
+        kind
+        name
+        ofType {
+          kind
+          name
+          ofType {
+            kind
+            name
+            ofType {
+              kind
+              name
+              ofType {
+                kind
+                name
+              }
+            }
+          }
+        }
+      }
+    }
+  }
+}"#;
+
+/// A GraphQL operation, sent as the JSON request body
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphqlOperation {
+    pub query: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variables: Option<Value>,
+    /// Which operation to run when the document defines several
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operation_name: Option<String>,
+}
+
+impl GraphqlOperation {
+    pub fn introspection() -> Self {
+        Self {
+            query: INTROSPECTION_QUERY.to_string(),
+            variables: None,
+            operation_name: Some("IntrospectionQuery".to_string()),
+        }
+    }
+
+    /// Serializes the operation, defaulting `Content-Type` and, with `default_accept`, `Accept`
+    /// to GraphQL's.
+    pub fn encode(
+        &self,
+        headers: &mut HeaderMap,
+        default_accept: bool,
+    ) -> Result<Vec<u8>, AppError> {
+        if let Some(variables) = &self.variables
+            && !variables.is_object()
+            && !variables.is_null()
+        {
+            return Err(AppError::new(
+                ErrorKind::BadRequest,
+                "GraphQL variables must be a JSON object",
+            ));
+        }
+        headers
+            .entry(header::CONTENT_TYPE)
+            .or_insert(HeaderValue::from_static("application/json"));
+        if default_accept {
+            headers
+                .entry(header::ACCEPT)
+                .or_insert(HeaderValue::from_static(ACCEPT));
+        }
+        serde_json::to_vec(self).map_err(|e| AppError::new(ErrorKind::JsonError, e.to_string()))
+    }
+}
+
+/// Extracts the schema from an introspection response body. Fails when the server answered
+/// with errors only, e.g. because introspection is disabled.
+pub fn parse_introspection(body: &[u8]) -> Result<Value, AppError> {
+    let mut response: Value = serde_json::from_slice(body).map_err(|e| {
+        AppError::new(
+            ErrorKind::JsonError,
+            format!("Introspection response isn't JSON: {e}"),
+        )
+    })?;
+    match response.pointer_mut("/data/__schema").map(Value::take) {
+        Some(schema) if schema.is_object() => Ok(schema),
+        _ => {
+            let messages: Vec<&str> = response["errors"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|error| error["message"].as_str())
+                .collect();
+            let reason = if messages.is_empty() {
+                "the response has no schema".to_string()
+            } else {
+                messages.join("; ")
+            };
+            Err(AppError::new(
+                ErrorKind::HttpError,
+                format!("Introspection failed: {reason}"),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GraphqlOperation, parse_introspection};
+    use crate::errors::ErrorKind;
+    use hyper::http::{HeaderMap, HeaderValue, header};
+    use serde_json::{Value, json};
+
+    #[test]
+    fn encodes_operation_with_default_headers() {
+        let operation = GraphqlOperation {
+            query: "query User($id: ID!) { user(id: $id) { name } }".to_string(),
+            variables: Some(json!({"id": "1"})),
+            operation_name: Some("User".to_string()),
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, HeaderValue::from_static("application/json"));
+        let body: Value =
+            serde_json::from_slice(&operation.encode(&mut headers, true).unwrap()).unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "query": "query User($id: ID!) { user(id: $id) { name } }",
+                "variables": {"id": "1"},
+                "operationName": "User"
+            })
+        );
+        assert_eq!(headers[header::CONTENT_TYPE], "application/json");
+        // The caller's own Accept is kept
+        assert_eq!(headers[header::ACCEPT], "application/json");
+
+        let bare = GraphqlOperation {
+            query: "{ me { id } }".to_string(),
+            variables: None,
+            operation_name: None,
+        };
+        let mut headers = HeaderMap::new();
+        let body = bare.encode(&mut headers, false).unwrap();
+        assert_eq!(body, br#"{"query":"{ me { id } }"}"#);
+        assert!(!headers.contains_key(header::ACCEPT));
+
+        let invalid = GraphqlOperation {
+            variables: Some(json!([1])),
+            ..bare
+        };
+        let err = invalid.encode(&mut HeaderMap::new(), true).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::BadRequest);
+    }
+
+    #[test]
+    fn parses_introspection_responses() {
+        let schema = parse_introspection(
+            br#"{"data":{"__schema":{"queryType":{"name":"Query"},"types":[]}}}"#,
+        )
+        .unwrap();
+        assert_eq!(schema["queryType"]["name"], "Query");
+
+        let err = parse_introspection(
+            br#"{"errors":[{"message":"Introspection is disabled"}],"data":null}"#,
+        )
+        .unwrap_err();
+        assert!(err.message.contains("Introspection is disabled"));
+        assert!(parse_introspection(b"<html>").is_err());
+    }
+}
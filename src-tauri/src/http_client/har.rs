@@ -0,0 +1,665 @@
+//! HAR 1.2 entries of recent requests, built from the request, its log and its response, so a
+//! session can be opened in browser devtools or shared.
+
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::charset;
+use crate::http_client::request::{MultipartPart, Request};
+use crate::http_client::request_log;
+use crate::http_client::response::{LogEntry, ResponseData};
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
+use chrono::{DateTime, Duration, Utc};
+use hyper::http::Uri;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, LazyLock, Mutex};
+
+/// Requests whose entries are kept; the oldest is dropped beyond this
+const MAX_REQUESTS: usize = 50;
+
+/// Largest response body an entry keeps; a larger one is only described
+pub const MAX_BODY_BYTES: usize = 256 * 1024;
+
+/// Hides secret values wherever they'd appear in an entry, as the request's log does
+pub type Mask = Arc<dyn Fn(&mut String) + Send + Sync>;
+
+static RETAINED: LazyLock<Mutex<VecDeque<HarEntry>>> =
+    LazyLock::new(|| Mutex::new(VecDeque::new()));
+
+#[derive(Debug, Serialize)]
+struct Har {
+    log: HarLog,
+}
+
+#[derive(Debug, Serialize)]
+struct HarLog {
+    version: &'static str,
+    creator: HarCreator,
+    entries: Vec<HarEntry>,
+}
+
+#[derive(Debug, Serialize)]
+struct HarCreator {
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct HarEntry {
+    #[serde(skip)]
+    request_id: String,
+    started_date_time: String,
+    time: u64,
+    request: HarRequest,
+    response: HarResponse,
+    cache: serde_json::Value,
+    timings: HarTimings,
+    #[serde(rename = "serverIPAddress", skip_serializing_if = "Option::is_none")]
+    server_ip_address: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct HarRequest {
+    method: String,
+    url: String,
+    http_version: String,
+    cookies: Vec<HarCookie>,
+    headers: Vec<HarPair>,
+    query_string: Vec<HarPair>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    post_data: Option<HarPostData>,
+    headers_size: i64,
+    body_size: i64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct HarResponse {
+    status: u16,
+    status_text: String,
+    http_version: String,
+    cookies: Vec<HarCookie>,
+    headers: Vec<HarPair>,
+    content: HarContent,
+    #[serde(rename = "redirectURL")]
+    redirect_url: String,
+    headers_size: i64,
+    body_size: i64,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+struct HarPair {
+    name: String,
+    value: String,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+struct HarCookie {
+    name: String,
+    value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    domain: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    http_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    secure: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct HarPostData {
+    mime_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    params: Vec<HarParam>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct HarParam {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_type: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct HarContent {
+    size: u64,
+    mime_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encoding: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+}
+
+/// Phase durations in milliseconds; `-1` marks a phase that didn't happen or wasn't measured
+#[derive(Debug, Serialize, Clone, PartialEq)]
+struct HarTimings {
+    blocked: i64,
+    dns: i64,
+    connect: i64,
+    ssl: i64,
+    send: i64,
+    wait: i64,
+    receive: i64,
+}
+
+/// What a request is known to send before it runs; completed into a HAR entry by
+/// [`PendingEntry::finish`] once its response arrives.
+pub struct PendingEntry {
+    mask: Mask,
+    request_id: String,
+    method: String,
+    url: String,
+    headers: Vec<HarPair>,
+    post_data: Option<HarPostData>,
+    body_size: i64,
+}
+
+impl PendingEntry {
+    /// The entry of `request` as it's sent: with its variables substituted and its hooks
+    /// applied. `mask` is applied to the response too, once it arrives.
+    pub fn new(request: &Request, mask: Mask) -> Self {
+        let masked = |text: &str| {
+            let mut text = text.to_string();
+            mask(&mut text);
+            text
+        };
+        let headers: Vec<HarPair> = request
+            .headers
+            .iter()
+            .flatten()
+            .map(|(name, value)| HarPair {
+                name: name.clone(),
+                value: masked(value),
+            })
+            .collect();
+        let mime_type = header_value(&headers, "content-type").unwrap_or_default();
+        let (post_data, body_size) = if let Some(parts) = &request.multipart_parts {
+            let params = parts
+                .iter()
+                .map(|part| match part {
+                    MultipartPart::Text { name, value } => HarParam {
+                        name: name.clone(),
+                        value: Some(masked(value)),
+                        file_name: None,
+                        content_type: None,
+                    },
+                    MultipartPart::File {
+                        name,
+                        file_path,
+                        file_name,
+                        content_type,
+                    } => HarParam {
+                        name: name.clone(),
+                        value: None,
+                        file_name: file_name.clone().or_else(|| Some(file_path.clone())),
+                        content_type: content_type.clone(),
+                    },
+                })
+                .collect();
+            let post_data = HarPostData {
+                mime_type: "multipart/form-data".to_string(),
+                text: None,
+                params,
+                comment: None,
+            };
+            (Some(post_data), -1)
+        } else if let Some(path) = &request.body_file_path {
+            let post_data = HarPostData {
+                mime_type,
+                text: None,
+                params: Vec::new(),
+                comment: Some(format!("Body streamed from {path}")),
+            };
+            (Some(post_data), -1)
+        } else if let Some(body) = request.body.as_deref().filter(|body| !body.is_empty()) {
+            let post_data = HarPostData {
+                mime_type,
+                text: Some(masked(&String::from_utf8_lossy(body))),
+                params: Vec::new(),
+                comment: None,
+            };
+            (Some(post_data), body.len() as i64)
+        } else {
+            (None, 0)
+        };
+        let url = masked(&request.url);
+        Self {
+            mask,
+            request_id: request.request_id.clone(),
+            method: request.method.to_ascii_uppercase(),
+            url,
+            headers,
+            post_data,
+            body_size,
+        }
+    }
+
+    /// Builds the entry from the response and the retained log, and keeps it for
+    /// [`export`]. `body` is the response body, which a streamed response doesn't carry, or
+    /// `None` when it's larger than [`MAX_BODY_BYTES`].
+    pub fn finish(self, response: &ResponseData, body: Option<&[u8]>) {
+        let log = request_log::entries(&self.request_id);
+        let entry = self.into_entry(response, body, &log);
+        let mut entries = RETAINED.lock().unwrap();
+        entries.retain(|kept| kept.request_id != entry.request_id);
+        if entries.len() == MAX_REQUESTS {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    fn into_entry(
+        self,
+        response: &ResponseData,
+        body: Option<&[u8]>,
+        log: &[LogEntry],
+    ) -> HarEntry {
+        let masked = |text: &str| {
+            let mut text = text.to_string();
+            (self.mask)(&mut text);
+            text
+        };
+        // The log describes the request as sent, after redirects and added headers
+        let last_request = log
+            .iter()
+            .rposition(|entry| entry.phase.as_deref() == Some("request"));
+        let url = last_request
+            .and_then(|index| log[index].details.as_ref()?["uri"].as_str())
+            .map(str::to_string)
+            .unwrap_or(self.url);
+        let sent_headers: Vec<HarPair> = last_request
+            .map(|index| {
+                log[index..]
+                    .iter()
+                    .filter(|entry| entry.phase.as_deref() == Some("request_header"))
+                    .filter_map(logged_header)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let headers = if sent_headers.is_empty() {
+            self.headers
+        } else {
+            sent_headers
+        };
+        let http_version = log
+            .iter()
+            .rev()
+            .find(|entry| entry.phase.as_deref() == Some("response"))
+            .and_then(|entry| entry.details.as_ref()?["version"].as_str())
+            .unwrap_or("HTTP/1.1")
+            .to_string();
+        let started_date_time = log
+            .first()
+            .map(|entry| entry.timestamp.clone())
+            .or_else(|| {
+                let finished = DateTime::parse_from_rfc3339(&response.timestamp).ok()?;
+                let started = finished - Duration::milliseconds(response.duration as i64);
+                Some(started.with_timezone(&Utc).to_rfc3339())
+            })
+            .unwrap_or_else(|| response.timestamp.clone());
+
+        let response_headers: Vec<HarPair> = response
+            .headers
+            .iter()
+            .map(|(name, value)| HarPair {
+                name: name.clone(),
+                value: masked(value),
+            })
+            .collect();
+        let mut content = content(response, body, &response_headers);
+        content.text = content.text.as_deref().map(masked);
+        let request = HarRequest {
+            method: self.method,
+            query_string: query_string(&url),
+            cookies: request_cookies(&headers),
+            url,
+            http_version: http_version.clone(),
+            headers,
+            post_data: self.post_data,
+            headers_size: -1,
+            body_size: self.body_size,
+        };
+        let har_response = HarResponse {
+            status: response.status,
+            status_text: response.status_text.clone(),
+            http_version,
+            cookies: response
+                .cookies
+                .iter()
+                .map(|cookie| HarCookie {
+                    name: cookie.name.clone(),
+                    value: masked(&cookie.value),
+                    path: cookie.path.clone(),
+                    domain: cookie.domain.clone(),
+                    expires: cookie.expires.clone(),
+                    http_only: cookie.http_only,
+                    secure: cookie.secure,
+                })
+                .collect(),
+            redirect_url: header_value(&response_headers, "location").unwrap_or_default(),
+            content,
+            headers: response_headers,
+            headers_size: -1,
+            body_size: response.size as i64,
+        };
+        HarEntry {
+            request_id: self.request_id,
+            started_date_time,
+            time: response.timings.total,
+            timings: timings(response),
+            server_ip_address: response
+                .connection
+                .as_ref()
+                .and_then(|info| info.remote_addr.parse::<std::net::SocketAddr>().ok())
+                .map(|addr| addr.ip().to_string()),
+            cache: serde_json::json!({}),
+            request,
+            response: har_response,
+        }
+    }
+}
+
+/// Receives a request's [`PendingEntry`] from the engine once the request is ready to send;
+/// set by the command, never read from the frontend.
+#[derive(Clone, Default)]
+pub struct EntrySlot(Arc<Mutex<Option<PendingEntry>>>);
+
+impl EntrySlot {
+    pub fn fill(&self, entry: PendingEntry) {
+        *self.0.lock().unwrap() = Some(entry);
+    }
+
+    pub fn take(&self) -> Option<PendingEntry> {
+        self.0.lock().unwrap().take()
+    }
+}
+
+impl fmt::Debug for EntrySlot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EntrySlot")
+    }
+}
+
+/// A copy of a streamed response body for its entry, which a streamed response doesn't carry.
+/// The copy is dropped once the body outgrows [`MAX_BODY_BYTES`].
+#[derive(Clone)]
+pub struct BodyCopy(Arc<Mutex<Option<Vec<u8>>>>);
+
+impl BodyCopy {
+    pub fn new() -> Self {
+        Self(Arc::new(Mutex::new(Some(Vec::new()))))
+    }
+
+    pub fn push(&self, chunk: &[u8]) {
+        let mut copy = self.0.lock().unwrap();
+        if copy
+            .as_ref()
+            .is_some_and(|body| body.len() + chunk.len() > MAX_BODY_BYTES)
+        {
+            *copy = None;
+        }
+        if let Some(body) = copy.as_mut() {
+            body.extend_from_slice(chunk);
+        }
+    }
+
+    /// The body, or `None` if it was too large to keep
+    pub fn take(&self) -> Option<Vec<u8>> {
+        self.0.lock().unwrap().take()
+    }
+}
+
+/// A header as logged, e.g. `> accept: */*`; redacted values stay redacted
+fn logged_header(entry: &LogEntry) -> Option<HarPair> {
+    let details = entry.details.as_ref()?;
+    let name = details["name"].as_str()?;
+    let display = details["display"].as_str()?;
+    let value = display.strip_prefix(name)?.strip_prefix(": ")?;
+    Some(HarPair {
+        name: name.to_string(),
+        value: value.to_string(),
+    })
+}
+
+fn header_value(headers: &[HarPair], name: &str) -> Option<String> {
+    headers
+        .iter()
+        .find(|header| header.name.eq_ignore_ascii_case(name))
+        .map(|header| header.value.clone())
+}
+
+fn query_string(url: &str) -> Vec<HarPair> {
+    url.parse::<Uri>()
+        .ok()
+        .and_then(|uri| uri.query().map(str::to_string))
+        .and_then(|query| serde_urlencoded::from_str::<Vec<(String, String)>>(&query).ok())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, value)| HarPair { name, value })
+        .collect()
+}
+
+fn request_cookies(headers: &[HarPair]) -> Vec<HarCookie> {
+    headers
+        .iter()
+        .filter(|header| header.name.eq_ignore_ascii_case("cookie"))
+        .flat_map(|header| header.value.split(';'))
+        .filter_map(|pair| {
+            let (name, value) = pair.trim().split_once('=')?;
+            Some(HarCookie {
+                name: name.to_string(),
+                value: value.to_string(),
+                ..HarCookie::default()
+            })
+        })
+        .collect()
+}
+
+fn content(response: &ResponseData, body: Option<&[u8]>, headers: &[HarPair]) -> HarContent {
+    let mime_type = header_value(headers, "content-type").unwrap_or_default();
+    let mut content = HarContent {
+        size: response.size,
+        mime_type,
+        text: None,
+        encoding: None,
+        comment: None,
+    };
+    if let Some(path) = response.saved_to.as_ref().or(response.file_path.as_ref()) {
+        content.comment = Some(format!("Body not included; it was written to {path}"));
+    } else if let Some(body) = body.filter(|body| body.len() <= MAX_BODY_BYTES) {
+        // Text in another charset is transcoded; bodies that don't decode cleanly are kept as
+        // base64
+        let decoded = charset::decode(body, Some(&content.mime_type));
+        if decoded.had_errors {
+            content.text = Some(STANDARD.encode(body));
+            content.encoding = Some("base64");
+        } else {
+            content.text = Some(decoded.text);
+        }
+    } else {
+        content.comment = Some(format!(
+            "Body not included; it's larger than {} KiB",
+            MAX_BODY_BYTES / 1024
+        ));
+    }
+    content
+}
+
+fn timings(response: &ResponseData) -> HarTimings {
+    let timings = &response.timings;
+    let phase = |value: Option<u64>| value.map_or(-1, |ms| ms as i64);
+    // HAR's connect includes the TLS handshake
+    let connect = match (timings.tcp_connect, timings.tls_handshake) {
+        (None, None) => -1,
+        (tcp, tls) => (tcp.unwrap_or(0) + tls.unwrap_or(0)) as i64,
+    };
+    HarTimings {
+        blocked: -1,
+        dns: phase(timings.dns),
+        connect,
+        ssl: phase(timings.tls_handshake),
+        // send, wait and receive are required
+        send: timings.request_write.unwrap_or(0) as i64,
+        wait: timings.time_to_first_byte.unwrap_or(0) as i64,
+        receive: timings.download.unwrap_or(0) as i64,
+    }
+}
+
+/// Renders the kept entries of `request_ids`, in that order, as a HAR 1.2 document.
+pub fn export(request_ids: &[String]) -> Result<String, AppError> {
+    let retained = RETAINED.lock().unwrap();
+    let entries = request_ids
+        .iter()
+        .map(|request_id| {
+            retained
+                .iter()
+                .find(|entry| &entry.request_id == request_id)
+                .cloned()
+                .ok_or_else(|| {
+                    AppError::new(
+                        ErrorKind::BadRequest,
+                        format!(
+                            "No HAR entry is kept for request {request_id}; turn on 'Keep for \
+                             HAR' in its options and send it again"
+                        ),
+                    )
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let har = Har {
+        log: HarLog {
+            version: "1.2",
+            creator: HarCreator {
+                name: "knurl",
+                version: env!("CARGO_PKG_VERSION"),
+            },
+            entries,
+        },
+    };
+    serde_json::to_string_pretty(&har)
+        .map_err(|e| AppError::new(ErrorKind::JsonError, e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        BodyCopy, HarPair, MAX_BODY_BYTES, PendingEntry, logged_header, query_string,
+        request_cookies,
+    };
+    use crate::http_client::request::{MultipartPart, Request};
+    use crate::http_client::response::{LogEntry, LogLevel};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn pair(name: &str, value: &str) -> HarPair {
+        HarPair {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn reads_sent_headers_from_the_log() {
+        let entry = LogEntry {
+            request_id: "har".to_string(),
+            timestamp: "2025-01-01T00:00:00.000Z".to_string(),
+            level: LogLevel::Debug,
+            info_type: None,
+            message: "> accept: */*".to_string(),
+            category: Some("http".to_string()),
+            phase: Some("request_header".to_string()),
+            elapsed_ms: Some(1),
+            details: Some(json!({"name": "accept", "display": "accept: */*"})),
+            bytes_logged: None,
+            truncated: None,
+        };
+        assert_eq!(logged_header(&entry), Some(pair("accept", "*/*")));
+    }
+
+    #[test]
+    fn splits_query_strings_and_cookies() {
+        assert_eq!(
+            query_string("https://example.com/search?q=a%20b&page=2"),
+            [pair("q", "a b"), pair("page", "2")]
+        );
+        assert!(query_string("https://example.com/").is_empty());
+
+        let cookies = request_cookies(&[pair("Cookie", "session=abc; theme=dark")]);
+        let names: Vec<_> = cookies
+            .iter()
+            .map(|c| (c.name.as_str(), c.value.as_str()))
+            .collect();
+        assert_eq!(names, [("session", "abc"), ("theme", "dark")]);
+    }
+
+    #[test]
+    fn describes_multipart_bodies_by_part() {
+        let pending = PendingEntry::new(
+            &Request {
+                request_id: "har-multipart".to_string(),
+                url: "https://example.com/upload".to_string(),
+                method: "post".to_string(),
+                multipart_parts: Some(vec![
+                    MultipartPart::Text {
+                        name: "title".to_string(),
+                        value: "Report".to_string(),
+                    },
+                    MultipartPart::File {
+                        name: "file".to_string(),
+                        file_path: "/tmp/report.pdf".to_string(),
+                        file_name: Some("report.pdf".to_string()),
+                        content_type: Some("application/pdf".to_string()),
+                    },
+                ]),
+                ..Request::default()
+            },
+            Arc::new(|_: &mut String| {}),
+        );
+        assert_eq!(pending.method, "POST");
+        let post_data = serde_json::to_value(pending.post_data.unwrap()).unwrap();
+        assert_eq!(
+            post_data,
+            json!({
+                "mimeType": "multipart/form-data",
+                "params": [
+                    {"name": "title", "value": "Report"},
+                    {"name": "file", "fileName": "report.pdf", "contentType": "application/pdf"},
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn drops_streamed_bodies_over_the_cap() {
+        let copy = BodyCopy::new();
+        copy.push(&[b'a'; MAX_BODY_BYTES - 1]);
+        copy.push(b"b");
+        assert_eq!(copy.take().unwrap().len(), MAX_BODY_BYTES);
+
+        let copy = BodyCopy::new();
+        copy.push(&[b'a'; MAX_BODY_BYTES]);
+        copy.push(b"b");
+        copy.push(b"c");
+        assert!(copy.take().is_none());
+    }
+}
@@ -1,40 +1,107 @@
 use std::convert::TryFrom;
-use std::sync::Arc;
+use std::io::{BufWriter, Write};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+use base64::Engine as _;
 use bytes::Bytes;
 use chrono::{SecondsFormat, Utc};
 use futures_util::StreamExt;
-use http_body_util::{BodyExt, Full};
+use http_body_util::BodyExt;
 use hyper::body::Incoming;
 use hyper::http::{HeaderMap, HeaderName, HeaderValue, Uri};
-use hyper::{Method, Request as HyperRequest, Response as HyperResponse, Version as HttpVersion};
+use hyper::{
+    Method, Request as HyperRequest, Response as HyperResponse, StatusCode, Version as HttpVersion,
+};
 use hyper_util::client::legacy::Client;
 use hyper_util::client::legacy::connect::HttpInfo;
 use hyper_util::rt::TokioExecutor;
 use serde_json::{Value, json};
-use std::panic::Location;
-use tempfile::Builder as TempFileBuilder;
 use tokio::time::timeout;
+use tokio_util::sync::CancellationToken;
 
+mod body;
+mod canonical;
+mod challenge;
+mod client_cert;
+mod compression;
 mod connector;
-
+mod digest;
+mod dns_cache;
+mod hooks;
+mod insecure;
+mod ntlm;
+mod pool;
+mod query;
+mod rate_limit;
+mod raw;
+mod response_cache;
+mod resume;
+mod retry;
+mod revocation;
+mod signing;
+mod timings;
+
+use self::body::{BodySource, RequestBody};
+pub use self::canonical::{CanonicalRequest, canonical_request};
+pub use self::connector::CertificateSummary;
+pub(crate) use self::connector::{
+    TlsSettings, build_tls_config, cipher_suite_name, summarize_certificate,
+};
+pub use self::connector::{install_crypto_provider, refresh_root_store, root_store};
+pub use self::dns_cache::{DnsCacheEntry, dns_cache_entries, flush_dns_cache};
+pub use self::pool::{clear_profile_clients, close_connection_group};
+pub(crate) use self::query::with_query_params;
+use self::raw::{RawSender, SendError};
+pub use self::response_cache::clear_response_cache;
+pub(crate) use self::retry::parse_retry_after;
+use self::retry::{RetryDecision, RetryState};
+use self::revocation::RevocationReport;
+pub(crate) use self::signing::private_key;
+use self::timings::{Mark, PhaseTimer, TimedBody};
 use crate::errors::{AppError, ErrorKind};
+use crate::http_client::assertions::{self, Assertion, AssertionResult};
+use crate::http_client::body_stream::BodySink;
 use crate::http_client::cookies::parse_set_cookie_header;
-use crate::http_client::engine::{EngineFuture, HttpEngine, LogEmitter};
+use crate::http_client::engine::{CountingEmitter, EngineFuture, HttpEngine, LogEmitter};
+use crate::http_client::extractors::{self, ExtractedVariable, Extractor};
+use crate::http_client::har;
+use crate::http_client::ndjson::{self, NdjsonParser, RecordSink};
 use crate::http_client::request::{HttpVersionPref, MultipartPart, Request};
-use crate::http_client::response::{Cookie, LogEntry, LogLevel, ResponseData};
+use crate::http_client::response::{
+    BinaryHeader, CacheStatus, ConnectionInfo, Cookie, LogEntry, LogLevel, RedirectHop,
+    ResponseData,
+};
+use crate::http_client::soap;
+use crate::http_client::spool::{self, SpoolFile};
+use crate::http_client::sse::{self, EventSink, SseParser};
+use crate::http_client::variables;
+use crate::http_client::webdav;
+use crate::operations::{Operation, OperationKind, ProgressSink};
 
 const DEFAULT_MAX_LOG_BYTES: usize = 128 * 1024;
+const BODY_READ_CHUNK: usize = 1024 * 1024;
+/// Bytes between progress logs while reading multipart file parts
+const BODY_PROGRESS_INTERVAL: u64 = 64 * 1024 * 1024;
 const DEFAULT_HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+/// Stands in for secret variable values in logs
+const SECRET_MASK: &str = "********";
 
 pub struct HyperEngine;
 
+tokio::task_local! {
+    /// Logger of the request being sent on this task
+    static CURRENT_LOGGER: RequestLogger;
+}
+
 #[derive(Clone)]
 pub(super) struct RequestLogger {
     emitter: Arc<dyn LogEmitter>,
     request_id: Arc<String>,
     start: Instant,
+    timer: PhaseTimer,
+    /// Secret variable values, masked wherever they'd appear in an entry
+    secrets: Arc<Vec<String>>,
 }
 
 impl RequestLogger {
@@ -43,6 +110,32 @@ impl RequestLogger {
             emitter,
             request_id: Arc::new(request_id),
             start,
+            timer: PhaseTimer::default(),
+            secrets: Arc::default(),
+        }
+    }
+
+    fn masking(self, secrets: Vec<String>) -> Self {
+        Self {
+            secrets: Arc::new(secrets),
+            ..self
+        }
+    }
+
+    fn mask(&self, text: &mut String) {
+        for secret in self.secrets.iter() {
+            if text.contains(secret.as_str()) {
+                *text = text.replace(secret.as_str(), SECRET_MASK);
+            }
+        }
+    }
+
+    fn mask_value(&self, value: &mut Value) {
+        match value {
+            Value::String(text) => self.mask(text),
+            Value::Array(items) => items.iter_mut().for_each(|item| self.mask_value(item)),
+            Value::Object(object) => object.values_mut().for_each(|item| self.mask_value(item)),
+            _ => {}
         }
     }
 
@@ -50,6 +143,18 @@ impl RequestLogger {
         self.request_id.as_ref()
     }
 
+    fn timer(&self) -> &PhaseTimer {
+        &self.timer
+    }
+
+    /// Returns the logger of the request currently being sent, falling back to this one.
+    /// Connectors of pooled clients outlive the request that created them.
+    fn for_current_request(&self) -> Self {
+        CURRENT_LOGGER
+            .try_with(Clone::clone)
+            .unwrap_or_else(|_| self.clone())
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn event(
         &self,
@@ -57,7 +162,7 @@ impl RequestLogger {
         category: &str,
         phase: Option<&str>,
         message: impl Into<String>,
-        details: Option<Value>,
+        mut details: Option<Value>,
         bytes_logged: Option<u64>,
         truncated: Option<bool>,
     ) {
@@ -65,13 +170,20 @@ impl RequestLogger {
         let info_type = phase
             .map(|p| p.to_string())
             .or_else(|| Some(category.to_string()));
+        let mut message = message.into();
+        if !self.secrets.is_empty() {
+            self.mask(&mut message);
+            if let Some(details) = details.as_mut() {
+                self.mask_value(details);
+            }
+        }
 
         self.emitter.emit(LogEntry {
             request_id: self.request_id().to_string(),
             timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
             level,
             info_type,
-            message: message.into(),
+            message,
             category: Some(category.to_string()),
             phase: phase.map(|p| p.to_string()),
             elapsed_ms: Some(elapsed_ms),
@@ -180,8 +292,11 @@ impl HyperEngine {
     }
 
     fn build_uri(req: &Request) -> Result<Uri, AppError> {
-        req.url
-            .parse::<Uri>()
+        let url = match &req.query_params {
+            Some(params) => query::with_query_params(&req.url, params),
+            None => req.url.clone(),
+        };
+        url.parse::<Uri>()
             .map_err(|e| AppError::new(ErrorKind::BadRequest, format!("Invalid URL: {e}")))
     }
 
@@ -210,7 +325,9 @@ impl HyperEngine {
                 headers.append(header_name, header_value);
             }
         }
-        if let Some(ua) = &req.user_agent {
+        if !req.sends_auto_header(|auto| auto.user_agent) {
+            // Only the listed headers are sent as the request's own
+        } else if let Some(ua) = &req.user_agent {
             headers.insert(
                 hyper::header::USER_AGENT,
                 HeaderValue::try_from(ua.as_str()).map_err(|e| {
@@ -293,7 +410,7 @@ impl HyperEngine {
     }
 
     fn header_to_string(name: &HeaderName, value: &HeaderValue) -> String {
-        match value.to_str() {
+        match std::str::from_utf8(value.as_bytes()) {
             Ok(v) => format!("{name}: {v}"),
             Err(_) => format!("{}: <binary:{} bytes>", name, value.as_bytes().len()),
         }
@@ -339,7 +456,116 @@ impl HyperEngine {
         );
     }
 
-    fn build_body(req: &Request, headers: &mut HeaderMap) -> Result<Bytes, AppError> {
+    /// Appends the contents of a multipart file part to `buf`, logging progress for large files.
+    fn read_part_file(
+        file_path: &str,
+        part_name: &str,
+        buf: &mut Vec<u8>,
+        logger: &RequestLogger,
+    ) -> Result<(), AppError> {
+        use std::io::Read;
+
+        let read_error = |e: std::io::Error| {
+            AppError::new(
+                ErrorKind::IoError,
+                format!("Failed to read file '{file_path}': {e}"),
+            )
+        };
+        let mut file = std::fs::File::open(file_path).map_err(read_error)?;
+        let total = file.metadata().map_err(read_error)?.len();
+        logger.debug(
+            "http",
+            Some("multipart_file"),
+            format!("Reading multipart part '{part_name}' from {file_path} ({total} bytes)"),
+            Some(json!({"part": part_name, "path": file_path, "size": total})),
+        );
+        buf.reserve(total as usize);
+
+        let mut chunk = vec![0u8; BODY_READ_CHUNK];
+        let mut read: u64 = 0;
+        let mut next_report = BODY_PROGRESS_INTERVAL;
+        loop {
+            let n = file.read(&mut chunk).map_err(read_error)?;
+            if n == 0 {
+                break;
+            }
+            buf.extend_from_slice(&chunk[..n]);
+            read += n as u64;
+            if read >= next_report {
+                next_report += BODY_PROGRESS_INTERVAL;
+                logger.debug(
+                    "http",
+                    Some("multipart_progress"),
+                    format!("Read {read} of {total} bytes of multipart part '{part_name}'"),
+                    Some(json!({"part": part_name, "read": read, "size": total})),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Builds the request body and applies the requested canonicalization, content encoding
+    /// and digest. Returns the body as written by the user (for logging) and the body to send.
+    fn prepare_body(
+        req: &Request,
+        headers: &mut HeaderMap,
+        logger: &RequestLogger,
+    ) -> Result<(BodySource, BodySource), AppError> {
+        let started = Instant::now();
+        let mut plain_body = Self::build_body(req, headers, logger)?;
+        if req.canonicalize_json.unwrap_or(false)
+            && req.multipart_parts.is_none()
+            && !plain_body.is_empty()
+        {
+            plain_body = BodySource::Bytes(digest::canonicalize_json(&plain_body.into_bytes()?)?);
+            logger.info(
+                "http",
+                Some("request_canonicalized"),
+                format!("Canonicalized JSON body (JCS): {} bytes", plain_body.len()),
+                Some(json!({ "size": plain_body.len() })),
+            );
+        }
+        if req.multipart_parts.is_some() {
+            logger.info(
+                "http",
+                Some("request_body_built"),
+                format!(
+                    "Built multipart body ({} bytes) in {} ms",
+                    plain_body.len(),
+                    started.elapsed().as_millis()
+                ),
+                Some(json!({
+                    "size": plain_body.len(),
+                    "elapsedMs": started.elapsed().as_millis() as u64,
+                })),
+            );
+        }
+        let body = match req.content_encoding {
+            // Compression needs the whole body, so file bodies are buffered in this case
+            Some(encoding) => BodySource::Bytes(compression::encode_body(
+                plain_body.clone().into_bytes()?,
+                encoding,
+                headers,
+            )?),
+            None => plain_body.clone(),
+        };
+        if let Some(body_digest) = req.body_digest {
+            let sha256 = digest::apply(&body, body_digest, headers)?;
+            logger.info(
+                "http",
+                Some("request_digest"),
+                format!("Body digest sha-256={sha256}"),
+                Some(json!({ "algorithm": "sha-256", "digest": sha256 })),
+            );
+        }
+        Ok((plain_body, body))
+    }
+
+    fn build_body(
+        req: &Request,
+        headers: &mut HeaderMap,
+        logger: &RequestLogger,
+    ) -> Result<BodySource, AppError> {
         if let Some(parts) = &req.multipart_parts {
             // Build multipart/form-data body with boundary
             let crlf = "\r\n";
@@ -459,20 +685,14 @@ impl HyperEngine {
                         );
                         let header = format!("{disposition}Content-Type: {ct}{crlf}{crlf}",);
                         buf.extend_from_slice(header.as_bytes());
-                        let file_bytes = std::fs::read(file_path).map_err(|e| {
-                            AppError::new(
-                                ErrorKind::IoError,
-                                format!("Failed to read file '{file_path}': {e}"),
-                            )
-                        })?;
-                        buf.extend_from_slice(&file_bytes);
+                        Self::read_part_file(file_path, name, &mut buf, logger)?;
                         buf.extend_from_slice(crlf.as_bytes());
                     }
                 }
             }
             buf.extend_from_slice(format!("--{}--{}", &boundary, crlf).as_bytes());
 
-            return Ok(Bytes::from(buf));
+            return Ok(BodySource::Bytes(Bytes::from(buf)));
         }
         if let Some(path) = &req.body_file_path {
             // If no Content-Type header is set, try to guess based on filename
@@ -485,22 +705,36 @@ impl HyperEngine {
                 })?;
                 headers.insert(ct_header, ct_val);
             }
-            let data = std::fs::read(path).map_err(|e| {
+            // Only the size is needed now; the file is streamed when the request is sent
+            let metadata = std::fs::metadata(path).map_err(|e| {
                 AppError::new(
                     ErrorKind::IoError,
                     format!("Failed to read body file '{path}': {e}"),
                 )
             })?;
-            return Ok(Bytes::from(data));
+            return Ok(BodySource::File {
+                path: path.into(),
+                len: metadata.len(),
+            });
         }
-        Ok(req.body.clone().map(Bytes::from).unwrap_or_default())
+        if let Some(operation) = &req.graphql {
+            return Ok(BodySource::Bytes(Bytes::from(
+                operation.encode(headers, req.sends_auto_header(|auto| auto.accept))?,
+            )));
+        }
+        if let Some(operation) = &req.soap {
+            return Ok(BodySource::Bytes(Bytes::from(operation.encode(headers)?)));
+        }
+        Ok(BodySource::Bytes(
+            req.body.clone().map(Bytes::from).unwrap_or_default(),
+        ))
     }
 
     fn cookies_from_headers(headers: &HeaderMap) -> Vec<Cookie> {
         headers
             .get_all(hyper::header::SET_COOKIE)
             .iter()
-            .filter_map(|value| value.to_str().ok())
+            .filter_map(|value| std::str::from_utf8(value.as_bytes()).ok())
             .filter_map(parse_set_cookie_header)
             .collect()
     }
@@ -521,39 +755,150 @@ fn format_http_version(version: HttpVersion) -> &'static str {
     }
 }
 
+/// Response headers as text, in the order received with repeated names kept apart, and the
+/// exact bytes of any value that isn't valid UTF-8
+fn response_headers(headers: &HeaderMap) -> (Vec<(String, String)>, Vec<BinaryHeader>) {
+    let mut binary = Vec::new();
+    let text = headers
+        .iter()
+        .enumerate()
+        .map(|(index, (name, value))| {
+            let bytes = value.as_bytes();
+            let text = match std::str::from_utf8(bytes) {
+                Ok(text) => text.to_string(),
+                Err(_) => {
+                    binary.push(BinaryHeader {
+                        index,
+                        base64: base64::engine::general_purpose::STANDARD.encode(bytes),
+                    });
+                    String::from_utf8_lossy(bytes).into_owned()
+                }
+            };
+            (name.to_string(), text)
+        })
+        .collect();
+    (text, binary)
+}
+
 impl HttpEngine for HyperEngine {
     fn execute(&self, request: Request, emitter: Arc<dyn LogEmitter>) -> EngineFuture {
         Box::pin(async move {
+            // Hyper adds Host and Content-Length to HTTP/1.1 requests, so requests that leave
+            // them out, or keep header case, write their own head
+            let raw_headers = request.raw_headers.unwrap_or(false)
+                || !request.sends_auto_header(|auto| auto.host)
+                || !request.sends_auto_header(|auto| auto.content_length);
+            let mut request = if raw_headers {
+                Request {
+                    http_version: Some(HttpVersionPref::Http1),
+                    ..request
+                }
+            } else {
+                request
+            };
+            // A streamed body never reaches the response, so keep a copy for assertions and
+            // extractors that read it
+            let reads_body = request
+                .assertions
+                .as_ref()
+                .is_some_and(|assertions| assertions.iter().any(Assertion::reads_body))
+                || request
+                    .extractors
+                    .as_ref()
+                    .is_some_and(|extractors| extractors.iter().any(Extractor::reads_body));
+            let streamed_body =
+                reads_body
+                    .then(|| request.body_sink.take())
+                    .flatten()
+                    .map(|sink| {
+                        let body = Arc::new(Mutex::new(Vec::<u8>::new()));
+                        let copy = body.clone();
+                        request.body_sink = Some(BodySink::new(move |chunk| {
+                            copy.lock().unwrap().extend_from_slice(&chunk);
+                            sink.send(chunk);
+                        }));
+                        body
+                    });
             let request_id = request.request_id.clone();
+            let capture = request
+                .log_capture
+                .map(|mode| Arc::new(CountingEmitter::new(mode, emitter.clone())));
+            let emitter: Arc<dyn LogEmitter> = match &capture {
+                Some(capture) => capture.clone(),
+                None => emitter,
+            };
+            let secrets = variables::apply_variables(&mut request)?;
+            let logger = RequestLogger::new(emitter.clone(), request_id.clone(), Instant::now())
+                .masking(secrets);
+
+            hooks::apply(&mut request, Utc::now(), &logger)?;
+            if let Some(slot) = &request.har_entry {
+                let mask = logger.clone();
+                slot.fill(har::PendingEntry::new(
+                    &request,
+                    Arc::new(move |text| mask.mask(text)),
+                ));
+            }
             let uri = Self::build_uri(&request)?;
             let method = Self::parse_method(&request)?;
             let mut headers = Self::build_headers(&request)?;
-            let body = Self::build_body(&request, &mut headers)?;
             let timeout_secs = request
                 .timeout_secs
                 .unwrap_or(DEFAULT_HTTP_TIMEOUT.as_secs());
             let max_log_bytes = Self::max_log_bytes(&request);
 
-            let logger = RequestLogger::new(emitter.clone(), request_id.clone(), Instant::now());
-
             logger.info(
                 "engine",
                 Some("init"),
                 "Using hyper engine",
                 Some(json!({"engine": "hyper"})),
             );
-            logger.info(
-                "connect",
-                Some("policy"),
-                "Connection reuse disabled (no pooling)",
-                Some(json!({"poolMaxIdlePerHost": 0})),
-            );
             logger.info(
                 "flow",
                 Some("request_start"),
                 format!("Starting request {method} {uri}"),
                 None,
             );
+            // Keep the uncompressed body around so the log shows what the user wrote.
+            // Multipart assembly, canonicalization, compression and digests read whole files,
+            // so they run on the blocking pool instead of stalling the runtime (and other
+            // requests' logs).
+            let (plain_body, body) = if request.multipart_parts.is_some()
+                || request.content_encoding.is_some()
+                || request.canonicalize_json.unwrap_or(false)
+                || request.body_digest.is_some()
+            {
+                let request = request.clone();
+                let logger = logger.clone();
+                let mut built_headers = headers.clone();
+                let (bodies, built_headers) = tokio::task::spawn_blocking(move || {
+                    Self::prepare_body(&request, &mut built_headers, &logger)
+                        .map(|bodies| (bodies, built_headers))
+                })
+                .await
+                .map_err(|e| {
+                    AppError::new(
+                        ErrorKind::IoError,
+                        format!("Failed to build request body: {e}"),
+                    )
+                })??;
+                headers = built_headers;
+                bodies
+            } else {
+                Self::prepare_body(&request, &mut headers, &logger)?
+            };
+            if let Some(range) = &request.range {
+                headers.insert(hyper::header::RANGE, resume::range_header(range)?);
+            }
+            // Downloads to a file bypass the cache, which would stand in for their body
+            let cache = (request.response_cache.unwrap_or(false)
+                && request.download_to_path.is_none())
+            .then(|| response_cache::lookup(&method, &uri, &mut headers))
+            .flatten();
+            let download = request.download_to_path.as_deref().map(|path| {
+                let resume = request.resume_download.unwrap_or(false);
+                resume::Download::prepare(path, resume, &mut headers, &logger)
+            });
             logger.info(
                 "http",
                 Some("request"),
@@ -576,14 +921,46 @@ impl HttpEngine for HyperEngine {
                 "request_header",
                 ">",
             );
-            if request.log_bodies.unwrap_or(true) {
-                Self::log_body(
+            let log_bodies = request.log_bodies.unwrap_or(true);
+            match &plain_body {
+                BodySource::Bytes(bytes) if log_bodies => Self::log_body(
                     &logger,
                     "request_body",
                     "body",
-                    &body,
+                    bytes,
                     max_log_bytes,
                     "> body:",
+                ),
+                BodySource::File { path, len } => logger.info(
+                    "http",
+                    Some("request_body_file"),
+                    format!(
+                        "Streaming request body from {} ({len} bytes)",
+                        path.display()
+                    ),
+                    Some(json!({"path": path.display().to_string(), "size": len})),
+                ),
+                _ => {}
+            }
+            // Streamed file bodies are logged chunk by chunk as they are sent
+            let chunk_log = log_bodies.then(|| (logger.clone(), max_log_bytes));
+            if let Some(encoding) = request.content_encoding
+                && !plain_body.is_empty()
+            {
+                let original_size = plain_body.len();
+                logger.info(
+                    "http",
+                    Some("request_encoding"),
+                    format!(
+                        "Compressed request body with {}: {original_size} -> {} bytes",
+                        encoding.as_str(),
+                        body.len()
+                    ),
+                    Some(json!({
+                        "encoding": encoding.as_str(),
+                        "originalSize": original_size,
+                        "encodedSize": body.len(),
+                    })),
                 );
             }
 
@@ -656,22 +1033,59 @@ impl HttpEngine for HyperEngine {
             }
             // (host_header log moved above to include injected flag)
 
-            let connector = connector::build_connector(&request, &uri, logger.clone())?;
-
-            let mut client_builder = Client::builder(TokioExecutor::new());
-            // Ensure no idle connection reuse between requests
-            client_builder.pool_max_idle_per_host(0);
-            client_builder.http2_adaptive_window(true);
-            let client: Client<_, Full<Bytes>> = client_builder.build(connector);
+            let client = match &request.ntlm {
+                Some(credentials) => {
+                    // NTLM authenticates the connection, so the handshake and the request
+                    // need the same HTTP/1.1 connection
+                    let ntlm_request = Request {
+                        http_version: Some(HttpVersionPref::Http1),
+                        ..request.clone()
+                    };
+                    let client = pool::dedicated_client(&ntlm_request, &uri, &logger)?;
+                    if let Some(authorization) = ntlm::handshake(
+                        &client,
+                        &method,
+                        &uri,
+                        &headers,
+                        credentials,
+                        &logger,
+                        timeout_secs,
+                    )
+                    .await?
+                    {
+                        headers.insert(hyper::header::AUTHORIZATION, authorization);
+                    }
+                    client
+                }
+                None => pool::client_for(&request, &uri, &logger)?,
+            };
+            let raw_sender = raw_headers
+                .then(|| RawSender::new(&request, &uri, &logger))
+                .transpose()?;
 
             let mut current_uri = uri.clone();
             let mut current_method = method.clone();
             let mut current_body = body.clone();
             let mut redirects_left = request.max_redirects.unwrap_or(0);
+            let mut retry = RetryState::new(request.retry.as_ref());
+            let mut challenge_answered = false;
+            // Set while a Negotiate exchange waits for the server's NTLM challenge
+            let mut negotiating = false;
+            let mut redirect_chain = Vec::new();
             let start = Instant::now();
 
             // Redirect-following loop
             let response = loop {
+                if let Some(signature) = &request.signature {
+                    signing::apply(
+                        signature,
+                        &current_method,
+                        &current_uri,
+                        &mut headers,
+                        Utc::now().timestamp(),
+                        &logger,
+                    )?;
+                }
                 let mut req_builder = HyperRequest::builder()
                     .method(current_method.clone())
                     .uri(current_uri.clone());
@@ -683,7 +1097,13 @@ impl HttpEngine for HyperEngine {
                         headers_mut.append(name.clone(), value.clone());
                     }
                 }
-                let req_body = Full::from(current_body.clone());
+                logger.timer().reset_exchange();
+                let sent_at = Instant::now();
+                let req_body = TimedBody::new(
+                    current_body.to_body(chunk_log.clone())?,
+                    logger.timer().clone(),
+                )
+                .boxed_unsync();
                 let hyper_req = req_builder.body(req_body).map_err(|e| {
                     AppError::new(
                         ErrorKind::BadRequest,
@@ -691,7 +1111,13 @@ impl HttpEngine for HyperEngine {
                     )
                 })?;
 
-                let call = client.request(hyper_req);
+                // Pooled connectors are shared between requests; this routes their logs here
+                let call = CURRENT_LOGGER.scope(logger.clone(), async {
+                    match &raw_sender {
+                        Some(sender) => sender.send(hyper_req, &logger).await,
+                        None => client.request(hyper_req).await.map_err(SendError::Client),
+                    }
+                });
 
                 let response = match timeout(Duration::from_secs(timeout_secs), call).await {
                     Ok(Ok(res)) => {
@@ -735,7 +1161,7 @@ impl HttpEngine for HyperEngine {
                             let mut fb_client_builder = Client::builder(TokioExecutor::new());
                             fb_client_builder.pool_max_idle_per_host(0);
                             fb_client_builder.http2_adaptive_window(true);
-                            let fb_client: Client<_, Full<Bytes>> =
+                            let fb_client: Client<_, RequestBody> =
                                 fb_client_builder.build(fb_connector);
 
                             // Rebuild request
@@ -767,8 +1193,15 @@ impl HttpEngine for HyperEngine {
                                     headers_mut.insert(hyper::header::HOST, host_value);
                                 }
                             }
-                            let fb_request =
-                                fb_builder.body(Full::from(body.clone())).map_err(|e| {
+                            let fb_request = fb_builder
+                                .body(
+                                    TimedBody::new(
+                                        body.to_body(chunk_log.clone())?,
+                                        logger.timer().clone(),
+                                    )
+                                    .boxed_unsync(),
+                                )
+                                .map_err(|e| {
                                     AppError::new(
                                         ErrorKind::BadRequest,
                                         format!("Failed to build request: {e}"),
@@ -931,9 +1364,108 @@ impl HttpEngine for HyperEngine {
                         ));
                     }
                 };
+                logger.timer().mark(Mark::ResponseHeaders);
+                if logger.timer().timings(0).connection_reused {
+                    logger.info(
+                        "connect",
+                        Some("reused"),
+                        "Re-used existing connection",
+                        None,
+                    );
+                }
 
-                // Check for redirect
                 let status = response.status();
+                match retry.next(status, response.headers(), Utc::now()) {
+                    RetryDecision::Done => {}
+                    RetryDecision::Retry {
+                        delay,
+                        attempt,
+                        source,
+                    } => {
+                        let source = source.as_str();
+                        logger.info(
+                            "http",
+                            Some("retry"),
+                            format!(
+                                "{status} received; retry {attempt} in {:.1}s ({source})",
+                                delay.as_secs_f64()
+                            ),
+                            Some(json!({
+                                "status": status.as_u16(),
+                                "attempt": attempt,
+                                "delayMs": delay.as_millis() as u64,
+                                "retryAfter": response
+                                    .headers()
+                                    .get(hyper::header::RETRY_AFTER)
+                                    .and_then(|v| v.to_str().ok()),
+                            })),
+                        );
+                        drop(response);
+                        tokio::time::sleep(delay).await;
+                        continue;
+                    }
+                    RetryDecision::GiveUp { reason } => {
+                        logger.warn(
+                            "http",
+                            Some("retry_exhausted"),
+                            format!("Not retrying {status}: {reason}"),
+                            Some(json!({"status": status.as_u16()})),
+                        );
+                        break response;
+                    }
+                }
+
+                // Answer one authentication challenge, then send again with the credentials
+                if status == StatusCode::UNAUTHORIZED
+                    && (!challenge_answered || negotiating)
+                    && let Some(credentials) = &request.challenge_auth
+                {
+                    challenge_answered = true;
+                    let continuing = std::mem::take(&mut negotiating);
+                    let offered: Vec<&str> = response
+                        .headers()
+                        .get_all(hyper::header::WWW_AUTHENTICATE)
+                        .iter()
+                        .filter_map(|v| v.to_str().ok())
+                        .collect();
+                    match challenge::respond(
+                        credentials,
+                        response.headers(),
+                        &current_method,
+                        &current_uri,
+                    )? {
+                        // A second leg that would open the exchange again means it failed
+                        Some(answer) if !(continuing && answer.continues) => {
+                            let scheme = answer.scheme;
+                            logger.info(
+                                "auth",
+                                Some("challenge"),
+                                format!("401 received; answering the {scheme} challenge"),
+                                Some(json!({
+                                    "status": status.as_u16(),
+                                    "wwwAuthenticate": offered,
+                                    "scheme": scheme,
+                                })),
+                            );
+                            negotiating = answer.continues;
+                            headers.insert(hyper::header::AUTHORIZATION, answer.authorization);
+                            // Drain the body so the connection can carry the second leg
+                            let _ = response.into_body().collect().await;
+                            continue;
+                        }
+                        _ => logger.warn(
+                            "auth",
+                            Some("challenge_unsupported"),
+                            "401 received without a challenge the credentials can answer",
+                            Some(json!({
+                                "status": status.as_u16(),
+                                "wwwAuthenticate": offered,
+                            })),
+                        ),
+                    }
+                }
+
+                // Check for redirect
                 if redirects_left == 0 || !(300..400).contains(&status.as_u16()) {
                     break response;
                 }
@@ -943,12 +1475,21 @@ impl HttpEngine for HyperEngine {
                     .and_then(|v| v.to_str().ok());
                 if let Some(loc) = location {
                     // Resolve relative to current_uri
-                    let next_uri = if let Ok(abs) = loc.parse::<Uri>() {
+                    let next_uri = if let Ok(abs) = loc.parse::<Uri>()
+                        && abs.scheme().is_some()
+                    {
                         abs
                     } else {
-                        // Build relative against current
+                        // Build relative against current: the origin for absolute paths, the
+                        // current directory otherwise
                         let base = current_uri.to_string();
-                        let join = if let Some(pos) = base.rfind('/') {
+                        let scheme = current_uri.scheme_str().unwrap_or("http");
+                        let authority = current_uri.authority().map_or("", |a| a.as_str());
+                        let join = if loc.starts_with("//") {
+                            format!("{scheme}:{loc}")
+                        } else if loc.starts_with('/') {
+                            format!("{scheme}://{authority}{loc}")
+                        } else if let Some(pos) = base.rfind('/') {
                             format!("{}{}", &base[..=pos], loc)
                         } else {
                             loc.to_string()
@@ -973,7 +1514,9 @@ impl HttpEngine for HyperEngine {
                     };
                     // Clear body on GET/HEAD
                     if next_method == Method::GET || next_method == Method::HEAD {
-                        current_body = Bytes::new();
+                        current_body = BodySource::empty();
+                        headers.remove(hyper::header::CONTENT_ENCODING);
+                        digest::remove(&mut headers);
                     }
                     // Conservative header policy on cross-origin redirects: strip sensitive headers
                     let origin_changed = current_uri.scheme_str() != next_uri.scheme_str()
@@ -1003,6 +1546,25 @@ impl HttpEngine for HyperEngine {
                         format!("{current_uri} -> {next_uri}"),
                         Some(json!({"status": status.as_u16(), "remaining": redirects_left - 1})),
                     );
+                    let duration = sent_at.elapsed().as_millis() as u64;
+                    // Hops go back to the webview, so secrets in their URLs are masked as in
+                    // the log
+                    let mut url = current_uri.to_string();
+                    logger.mask(&mut url);
+                    let mut hop_headers = response_headers(response.headers()).0;
+                    for (_, value) in hop_headers.iter_mut() {
+                        logger.mask(value);
+                    }
+                    redirect_chain.push(RedirectHop {
+                        url,
+                        method: current_method.to_string(),
+                        status: status.as_u16(),
+                        status_text: status.canonical_reason().unwrap_or("").to_string(),
+                        headers: hop_headers,
+                        cookies: Self::cookies_from_headers(response.headers()),
+                        duration,
+                        timings: logger.timer().timings(duration),
+                    });
                     current_uri = next_uri;
                     current_method = next_method;
                     redirects_left -= 1;
@@ -1012,22 +1574,139 @@ impl HttpEngine for HyperEngine {
                 }
             };
 
-            Self::handle_response(
+            let mut data = Self::handle_response(
                 response,
                 request.redact_sensitive.unwrap_or(false),
                 request.log_bodies.unwrap_or(true),
                 max_log_bytes,
-                logger,
+                logger.clone(),
                 uri.host().map(|h| h.to_string()),
                 request.preview_max_bytes,
+                request.max_response_bytes,
+                request.event_sink.as_ref(),
+                request.record_sink.as_ref(),
+                request.body_sink.as_ref(),
+                request.progress.as_ref(),
+                request.cancellation.as_ref(),
+                download.as_ref(),
+                cache.as_ref(),
                 start,
             )
-            .await
+            .await?;
+            data.redirect_chain =
+                (request.max_redirects.unwrap_or(0) > 0).then_some(redirect_chain);
+            data.retries = retry.report();
+            let body = if reads_body {
+                Self::kept_body(&data, streamed_body.as_deref(), &logger)
+            } else {
+                Vec::new()
+            };
+            if let Some(checks) = &request.assertions {
+                data.assertions = Some(Self::assert_response(checks, &data, &body, &logger));
+            }
+            if let Some(extractors) = &request.extractors {
+                data.variables = Some(Self::extract_variables(extractors, &data, &body, &logger));
+            }
+            // Faults come with an error status; spilled bodies are left unparsed, like multistatus
+            if request.soap.is_some() && data.status >= 400 {
+                data.soap_fault = soap::parse_fault(&data.body);
+                if let Some(fault) = &data.soap_fault {
+                    logger.warn(
+                        "soap",
+                        Some("fault"),
+                        format!("SOAP fault {}: {}", fault.code, fault.reason),
+                        None,
+                    );
+                }
+            }
+            data.log_summary = capture.map(|capture| capture.summary());
+            Ok(data)
         })
     }
 }
 
 impl HyperEngine {
+    /// Reads the response's body back from wherever it was kept, for the assertions and
+    /// extractors that need it
+    fn kept_body(
+        data: &ResponseData,
+        streamed_body: Option<&Mutex<Vec<u8>>>,
+        logger: &RequestLogger,
+    ) -> Vec<u8> {
+        use std::io::Read;
+
+        let body = if let Some(path) = &data.file_path {
+            spool::open(std::path::Path::new(path)).and_then(|mut file| {
+                let mut body = Vec::new();
+                file.read_to_end(&mut body)?;
+                Ok(body)
+            })
+        } else if let Some(path) = &data.saved_to {
+            std::fs::read(path).map_err(AppError::from)
+        } else if let Some(streamed) = streamed_body {
+            Ok(std::mem::take(&mut *streamed.lock().unwrap()))
+        } else {
+            Ok(data.body.clone())
+        };
+        body.unwrap_or_else(|e| {
+            logger.warn(
+                "assert",
+                Some("body"),
+                format!("Could not read the body back: {}", e.message),
+                None,
+            );
+            Vec::new()
+        })
+    }
+
+    /// Evaluates the request's assertions against its response, logging each result
+    fn assert_response(
+        checks: &[Assertion],
+        data: &ResponseData,
+        body: &[u8],
+        logger: &RequestLogger,
+    ) -> Vec<AssertionResult> {
+        let results = assertions::evaluate(checks, data, body);
+        for result in &results {
+            let details = Some(json!({"assertion": result.assertion, "actual": result.actual}));
+            if result.passed {
+                logger.info("assert", Some("pass"), &result.message, details);
+            } else {
+                logger.warn("assert", Some("fail"), &result.message, details);
+            }
+        }
+        results
+    }
+
+    /// Runs the request's extractors against its response, logging which found a value. The
+    /// values themselves aren't logged; they're often tokens.
+    fn extract_variables(
+        extractors: &[Extractor],
+        data: &ResponseData,
+        body: &[u8],
+        logger: &RequestLogger,
+    ) -> Vec<ExtractedVariable> {
+        let variables = extractors::extract(extractors, data, body);
+        for (variable, extractor) in variables.iter().zip(extractors) {
+            let details = Some(json!({"extractor": extractor}));
+            match &variable.message {
+                None => logger.info(
+                    "extract",
+                    Some("found"),
+                    format!("Extracted {}", variable.name),
+                    details,
+                ),
+                Some(message) => logger.warn(
+                    "extract",
+                    Some("missing"),
+                    format!("Could not extract {}: {message}", variable.name),
+                    details,
+                ),
+            }
+        }
+        variables
+    }
+
     #[allow(clippy::too_many_arguments)]
     async fn handle_response(
         response: HyperResponse<Incoming>,
@@ -1037,6 +1716,14 @@ impl HyperEngine {
         logger: RequestLogger,
         request_host: Option<String>,
         preview_max_bytes: Option<u64>,
+        max_response_bytes: Option<u64>,
+        event_sink: Option<&EventSink>,
+        record_sink: Option<&RecordSink>,
+        body_sink: Option<&BodySink>,
+        progress: Option<&ProgressSink>,
+        cancellation: Option<&CancellationToken>,
+        download_to: Option<&resume::Download>,
+        cache: Option<&response_cache::CacheLookup>,
         start: Instant,
     ) -> Result<ResponseData, AppError> {
         let (parts, body_stream) = response.into_parts();
@@ -1061,7 +1748,26 @@ impl HyperEngine {
 
         Self::log_headers(&logger, &parts.headers, redact, "response_header", "<");
 
-        if let Some(info) = parts.extensions.get::<HttpInfo>() {
+        let rate_limit = rate_limit::parse_rate_limit(&parts.headers, Utc::now());
+        if let Some(limit) = &rate_limit {
+            logger.info(
+                "http",
+                Some("rate_limit"),
+                format!(
+                    "Rate limit: {} of {} remaining, resets in {}s",
+                    limit.remaining.map_or("?".to_string(), |v| v.to_string()),
+                    limit.limit.map_or("?".to_string(), |v| v.to_string()),
+                    limit.reset_secs.map_or("?".to_string(), |v| v.to_string()),
+                ),
+                serde_json::to_value(limit).ok(),
+            );
+        }
+
+        let revocation = parts
+            .extensions
+            .get::<RevocationReport>()
+            .map(|report| report.0.clone());
+        let connection = parts.extensions.get::<HttpInfo>().map(|info| {
             logger.info(
                 "connect",
                 Some("established"),
@@ -1071,7 +1777,12 @@ impl HyperEngine {
                     "localAddr": info.local_addr().to_string(),
                 })),
             );
-        }
+            ConnectionInfo {
+                local_addr: info.local_addr().to_string(),
+                remote_addr: info.remote_addr().to_string(),
+                reused: logger.timer().timings(0).connection_reused,
+            }
+        });
 
         // Unified streaming: accumulate until threshold, then spill to temp file
         let content_length = parts
@@ -1083,12 +1794,127 @@ impl HyperEngine {
         let stream_to_file_threshold: u64 = preview_max_bytes.unwrap_or(20 * 1024 * 1024);
         let mut size: u64 = 0;
         let mut s = body_stream.into_data_stream();
-        let mut temp: Option<tempfile::NamedTempFile> = None;
+        let mut temp: Option<SpoolFile> = None;
         let mut body_buf: Vec<u8> = Vec::new();
         let mut write_to_file = content_length > stream_to_file_threshold;
-        while let Some(chunk) = s.next().await {
-            let bytes = chunk
+        // Bodies spooled to disk are reported as downloads
+        let mut download: Option<Operation> = None;
+        let event_sink = event_sink.filter(|_| {
+            parts
+                .headers
+                .get(hyper::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(sse::is_event_stream)
+        });
+        if let Some(sink) = event_sink {
+            logger.info(
+                "sse",
+                Some("open"),
+                "Event stream open; events are passed on as they arrive",
+                None,
+            );
+            let mut parser = SseParser::default();
+            let mut events: u64 = 0;
+            while let Some(chunk) = s.next().await {
+                let bytes = chunk
+                    .map_err(|e| AppError::new(ErrorKind::HttpError, format!("Body error: {e}")))?;
+                size += bytes.len() as u64;
+                for event in parser.feed(&bytes) {
+                    events += 1;
+                    if log_bodies {
+                        logger.debug(
+                            "sse",
+                            Some("event"),
+                            format!("< event: {} ({} bytes)", event.event, event.data.len()),
+                            Some(json!({"event": event.event, "id": event.id})),
+                        );
+                    }
+                    sink.send(event);
+                }
+            }
+            logger.info(
+                "sse",
+                Some("closed"),
+                format!("Event stream closed by the server after {events} events"),
+                Some(json!({"events": events, "bytes": size})),
+            );
+        }
+        // NDJSON records are passed on as their lines complete, and the body kept as usual
+        let mut ndjson = record_sink
+            .filter(|_| {
+                parts
+                    .headers
+                    .get(hyper::header::CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+                    .is_some_and(ndjson::is_ndjson)
+            })
+            .map(|sink| (sink, NdjsonParser::default()));
+        let mut records: u64 = 0;
+        // Multistatus bodies are parsed below, so they stay in the response
+        let body_sink = body_sink.filter(|_| status != StatusCode::MULTI_STATUS);
+        let start_download = || {
+            progress.map(|sink| {
+                Operation::for_request(
+                    sink.clone(),
+                    logger.request_id(),
+                    OperationKind::Download,
+                    (content_length > 0).then_some(content_length),
+                )
+            })
+        };
+        // Downloads go straight to the file, leaving the body empty
+        let mut resumed_from = None;
+        let mut saved = match download_to {
+            Some(target) => target
+                .open(status, &parts.headers, &logger)?
+                .map(|(file, offset)| {
+                    let path = target.path().display();
+                    logger.info(
+                        "http",
+                        Some("download"),
+                        format!("Saving the body to {path}"),
+                        Some(json!({"path": path.to_string(), "offset": offset})),
+                    );
+                    download = start_download();
+                    resumed_from = offset;
+                    BufWriter::new(file)
+                }),
+            None => None,
+        };
+        let saved_to = saved
+            .as_ref()
+            .and(download_to)
+            .map(|target| target.path().to_string_lossy().to_string());
+        let mut truncated = false;
+        let mut cancelled = false;
+        let cancel = async {
+            match cancellation {
+                Some(token) => token.cancelled().await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::pin!(cancel);
+        loop {
+            let chunk = tokio::select! {
+                biased;
+                _ = &mut cancel => {
+                    cancelled = true;
+                    break;
+                }
+                chunk = s.next() => chunk,
+            };
+            let Some(chunk) = chunk else {
+                break;
+            };
+            let mut bytes = chunk
                 .map_err(|e| AppError::new(ErrorKind::HttpError, format!("Body error: {e}")))?;
+            if let Some(limit) = max_response_bytes
+                && size + bytes.len() as u64 > limit
+            {
+                // Keep what fits and leave the rest unread; the connection is closed
+                bytes.truncate((limit - size) as usize);
+                truncated = true;
+            }
             if log_bodies {
                 Self::log_body(
                     &logger,
@@ -1100,38 +1926,86 @@ impl HyperEngine {
                 );
             }
             size += bytes.len() as u64;
-            if write_to_file || size > stream_to_file_threshold {
+            if let Some((sink, parser)) = ndjson.as_mut() {
+                for record in parser.feed(&bytes) {
+                    records += 1;
+                    sink.send(record);
+                }
+            }
+            if let Some(file) = saved.as_mut() {
+                file.write_all(&bytes)?;
+                if let Some(download) = &download {
+                    download.advance(size);
+                }
+            } else if write_to_file || size > stream_to_file_threshold {
                 if temp.is_none() {
                     // Initialize temp and flush any buffered bytes
-                    let mut t =
-                        TempFileBuilder::new()
-                            .prefix("knurl-")
-                            .tempfile()
-                            .map_err(|e| {
-                                AppError::from_error(
-                                    ErrorKind::IoError,
-                                    e,
-                                    None,
-                                    Location::caller(),
-                                )
-                            })?;
+                    let mut t = SpoolFile::create(spool::should_compress(&parts.headers))?;
                     if !body_buf.is_empty() {
-                        use std::io::Write;
-                        t.write_all(&body_buf).map_err(|e| {
-                            AppError::from_error(ErrorKind::IoError, e, None, Location::caller())
-                        })?;
+                        t.write_all(&body_buf)?;
                         body_buf.clear();
                     }
                     temp = Some(t);
                     write_to_file = true;
+                    download = start_download();
+                }
+                temp.as_mut().unwrap().write_all(&bytes)?;
+                if let Some(download) = &download {
+                    download.advance(size);
                 }
-                use std::io::Write;
-                temp.as_mut().unwrap().write_all(&bytes).map_err(|e| {
-                    AppError::from_error(ErrorKind::IoError, e, None, Location::caller())
-                })?;
             } else {
+                // Kept until the end in case the body spills, which then holds all of it
                 body_buf.extend_from_slice(&bytes);
+                if let Some(sink) = body_sink
+                    && !bytes.is_empty()
+                {
+                    sink.send(bytes);
+                }
+            }
+            if truncated {
+                logger.warn(
+                    "http",
+                    Some("truncated"),
+                    format!("Response body cut off at the {size} byte limit"),
+                    Some(json!({"maxResponseBytes": size})),
+                );
+                break;
+            }
+        }
+        if cancelled {
+            logger.warn(
+                "http",
+                Some("cancelled"),
+                format!("Request cancelled after {size} bytes of the body"),
+                Some(json!({"bytes": size})),
+            );
+        }
+        if let Some((sink, mut parser)) = ndjson {
+            // A cut-off last line is left out rather than passed on as invalid
+            if let Some(record) = parser.finish().filter(|_| !cancelled && !truncated) {
+                records += 1;
+                sink.send(record);
             }
+            logger.info(
+                "ndjson",
+                Some("records"),
+                format!("Passed on {records} NDJSON records"),
+                Some(json!({"records": records})),
+            );
+        }
+
+        if let Some(mut file) = saved.take() {
+            file.flush()?;
+            if let Some(target) = download_to
+                && !cancelled
+                && !truncated
+            {
+                target.complete();
+            }
+        }
+        logger.timer().mark(Mark::BodyEnd);
+        if let Some(download) = download {
+            download.finish(Ok(()))?;
         }
 
         // body already logged per chunk above when log_bodies is true
@@ -1187,37 +2061,875 @@ impl HyperEngine {
             format!("Request completed in {duration_ms} ms"),
             Some(json!({"durationMs": duration_ms})),
         );
+        let timings = logger.timer().timings(duration_ms);
+        logger.debug(
+            "metrics",
+            Some("timings"),
+            "Request phase timings",
+            serde_json::to_value(&timings).ok(),
+        );
         logger.debug(
             "connect",
             Some("shutdown"),
             "Shutting down connection",
             None,
         );
-        let headers_vec = parts
-            .headers
-            .iter()
-            .map(|(name, value)| (name.to_string(), value.to_str().unwrap_or("").to_string()))
-            .collect::<Vec<_>>();
+
+        // A `304` to the cache's validators returns the cached response in its place
+        let revalidated = cache
+            .and_then(|lookup| lookup.revalidating.clone())
+            .filter(|_| status == StatusCode::NOT_MODIFIED);
+        let mut cache_status = cache.map(|_| CacheStatus::Uncached);
+        let (status, headers) = match &revalidated {
+            Some(cached) => {
+                logger.info(
+                    "cache",
+                    Some("revalidated"),
+                    "Not modified; using the cached response",
+                    Some(json!({"bytes": cached.body.len()})),
+                );
+                cache_status = Some(CacheStatus::Revalidated);
+                size = cached.body.len() as u64;
+                match body_sink {
+                    Some(sink) if !cached.body.is_empty() => {
+                        sink.send(Bytes::from(cached.body.clone()))
+                    }
+                    Some(_) => {}
+                    None => body_buf = cached.body.clone(),
+                }
+                (
+                    StatusCode::from_u16(cached.status).unwrap_or(StatusCode::OK),
+                    cached.revalidated_headers(&parts.headers),
+                )
+            }
+            None => {
+                if let Some(lookup) = cache
+                    && temp.is_none()
+                    && saved_to.is_none()
+                    && !truncated
+                    && !cancelled
+                    && response_cache::store(lookup, status.as_u16(), &parts.headers, &body_buf)
+                {
+                    logger.debug(
+                        "cache",
+                        Some("stored"),
+                        "Response stored for revalidation",
+                        None,
+                    );
+                    cache_status = Some(CacheStatus::Stored);
+                }
+                (status, parts.headers.clone())
+            }
+        };
+        let (headers_vec, binary_headers) = response_headers(&headers);
+        let file_compressed = temp.as_ref().is_some_and(SpoolFile::is_compressed);
         let (body_vec, file_path, reported_size) = if let Some(t) = temp {
-            let (_file, path) = t.keep().map_err(|e| {
-                AppError::from_error(ErrorKind::IoError, e.error, None, Location::caller())
-            })?;
+            let path = t.keep()?;
             (Vec::new(), Some(path.to_string_lossy().to_string()), size)
+        } else if body_sink.is_some() {
+            (Vec::new(), None, size)
         } else {
             (body_buf, None, size)
         };
 
+        // Multistatus bodies are small enough to stay in memory; spilled ones are left unparsed
+        let multistatus =
+            if status == StatusCode::MULTI_STATUS && file_path.is_none() && saved_to.is_none() {
+                webdav::parse_multistatus(&body_vec)
+                    .inspect_err(|e| {
+                        logger.debug(
+                            "response",
+                            Some("multistatus"),
+                            format!("Could not parse the multistatus body: {}", e.message),
+                            None,
+                        )
+                    })
+                    .ok()
+            } else {
+                None
+            };
+
         Ok(ResponseData {
             request_id: logger.request_id().to_string(),
             status: status.as_u16(),
             status_text: status.canonical_reason().unwrap_or("").to_string(),
             headers: headers_vec,
+            binary_headers,
             cookies,
             body: body_vec,
             file_path,
+            file_compressed,
+            truncated,
+            cancelled,
+            saved_to,
+            resumed_from,
+            content_range: (status == StatusCode::PARTIAL_CONTENT)
+                .then(|| resume::content_range(&headers))
+                .flatten(),
+            cache: cache_status,
             size: reported_size,
             duration: duration_ms,
+            timings,
+            connection,
+            revocation,
+            rate_limit,
+            redirect_chain: None,
+            retries: None,
+            log_summary: None,
+            multistatus,
+            soap_fault: None,
+            assertions: None,
+            variables: None,
             timestamp: Utc::now().to_rfc3339(),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        BODY_PROGRESS_INTERVAL, HyperEngine, RequestLogger, close_connection_group,
+        response_headers,
+    };
+    use crate::http_client::body_stream::BodySink;
+    use crate::http_client::engine::HttpEngine;
+    use crate::http_client::engine::test_emitters::{Collect, Discard};
+    use crate::http_client::har::{self, EntrySlot};
+    use crate::http_client::ndjson::{NdjsonRecord, RecordSink};
+    use crate::http_client::request::{ContentEncoding, HookStep, MultipartPart, Request};
+    use crate::http_client::response::CacheStatus;
+    use crate::http_client::variables::VariableValue;
+    use hyper::http::{HeaderMap, HeaderValue, header};
+    use std::collections::HashMap;
+    use std::io::Read;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Instant;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio_util::sync::CancellationToken;
+
+    fn file_part(name: &str, path: &std::path::Path) -> MultipartPart {
+        MultipartPart::File {
+            name: name.to_string(),
+            file_path: path.to_string_lossy().to_string(),
+            file_name: Some("upload.bin".to_string()),
+            content_type: Some("application/octet-stream".to_string()),
+        }
+    }
+
+    #[test]
+    fn keeps_request_header_order_and_repeats() {
+        let request: Request = serde_json::from_value(serde_json::json!({
+            "requestId": "r1",
+            "url": "https://example.com/",
+            "method": "GET",
+            "headers": [["X-B", "1"], ["Accept", "*/*"], ["x-b", "2"], ["X-A", "3"]]
+        }))
+        .unwrap();
+        let headers = HyperEngine::build_headers(&request).unwrap();
+        let sent: Vec<_> = headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.to_str().unwrap()))
+            .collect();
+        // Followed by the default User-Agent
+        assert_eq!(
+            sent[..4],
+            [("x-b", "1"), ("x-b", "2"), ("accept", "*/*"), ("x-a", "3")]
+        );
+
+        // The older map form still deserializes
+        let request: Request = serde_json::from_value(serde_json::json!({
+            "requestId": "r2",
+            "url": "https://example.com/",
+            "method": "GET",
+            "headers": {"Accept": "*/*"}
+        }))
+        .unwrap();
+        assert_eq!(
+            request.headers,
+            Some(vec![("Accept".to_string(), "*/*".to_string())])
+        );
+    }
+
+    #[test]
+    fn keeps_repeated_and_non_utf8_header_values() {
+        let mut headers = HeaderMap::new();
+        headers.append(header::SET_COOKIE, HeaderValue::from_static("a=1"));
+        headers.append(header::SET_COOKIE, HeaderValue::from_static("b=2"));
+        headers.append(
+            header::CONTENT_DISPOSITION,
+            HeaderValue::from_bytes("attachment; filename=\"résumé.pdf\"".as_bytes()).unwrap(),
+        );
+        headers.append("x-latin1", HeaderValue::from_bytes(b"caf\xe9").unwrap());
+
+        let (text, binary) = response_headers(&headers);
+        assert_eq!(text[0], ("set-cookie".to_string(), "a=1".to_string()));
+        assert_eq!(text[1], ("set-cookie".to_string(), "b=2".to_string()));
+        assert_eq!(text[2].1, "attachment; filename=\"résumé.pdf\"");
+        assert_eq!(text[3].1, "caf\u{fffd}");
+        assert_eq!(binary.len(), 1);
+        assert_eq!(binary[0].index, 3);
+        assert_eq!(binary[0].base64, "Y2Fm6Q==");
+    }
+
+    #[tokio::test]
+    async fn captures_each_redirect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let mut received = Vec::new();
+                    let mut buf = [0u8; 1024];
+                    loop {
+                        let n = stream.read(&mut buf).await.unwrap_or(0);
+                        if n == 0 {
+                            return;
+                        }
+                        received.extend_from_slice(&buf[..n]);
+                        while let Some(end) = received.windows(4).position(|w| w == b"\r\n\r\n") {
+                            let head = String::from_utf8_lossy(&received[..end]).to_string();
+                            received.drain(..end + 4);
+                            let reply: &[u8] = if head.starts_with("GET /start") {
+                                b"HTTP/1.1 302 Found\r\nLocation: /end\r\n\
+                                  Set-Cookie: hop=1\r\nContent-Length: 0\r\n\r\n"
+                            } else {
+                                b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\ndone"
+                            };
+                            stream.write_all(reply).await.unwrap();
+                        }
+                    }
+                });
+            }
+        });
+
+        let request = Request {
+            request_id: "redirect-chain".to_string(),
+            url: format!("http://127.0.0.1:{port}/start"),
+            method: "GET".to_string(),
+            max_redirects: Some(3),
+            ..Request::default()
+        };
+        let response = HyperEngine::new()
+            .execute(request.clone(), Arc::new(Discard))
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"done");
+        let chain = response.redirect_chain.unwrap();
+        assert_eq!(chain.len(), 1);
+        assert_eq!(chain[0].url, format!("http://127.0.0.1:{port}/start"));
+        assert_eq!(chain[0].method, "GET");
+        assert_eq!(chain[0].status, 302);
+        assert_eq!(chain[0].status_text, "Found");
+        assert!(
+            chain[0]
+                .headers
+                .contains(&("location".to_string(), "/end".to_string()))
+        );
+        assert_eq!(chain[0].cookies[0].name, "hop");
+
+        // Hops go back to the webview with their secrets masked
+        let with_secret = Request {
+            url: format!("http://127.0.0.1:{port}/start?key={{{{token}}}}"),
+            variables: Some(HashMap::from([(
+                "token".to_string(),
+                VariableValue::Resolved("s3cret".to_string()),
+            )])),
+            ..request.clone()
+        };
+        let response = HyperEngine::new()
+            .execute(with_secret, Arc::new(Discard))
+            .await
+            .unwrap();
+        assert_eq!(
+            response.redirect_chain.unwrap()[0].url,
+            format!("http://127.0.0.1:{port}/start?key=********")
+        );
+
+        // Without redirects followed, there's no chain
+        let unfollowed = Request {
+            max_redirects: None,
+            ..request
+        };
+        let response = HyperEngine::new()
+            .execute(unfollowed, Arc::new(Discard))
+            .await
+            .unwrap();
+        assert_eq!(response.status, 302);
+        assert!(response.redirect_chain.is_none());
+    }
+
+    #[tokio::test]
+    async fn stops_reading_at_the_size_limit() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).await;
+            let _ = stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 26\r\n\r\n")
+                .await;
+            let _ = stream.write_all(b"abcdefghijklm").await;
+            let _ = stream.flush().await;
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            let _ = stream.write_all(b"nopqrstuvwxyz").await;
+        });
+
+        let request = Request {
+            request_id: "max-response-bytes".to_string(),
+            url: format!("http://127.0.0.1:{port}/large"),
+            method: "GET".to_string(),
+            max_response_bytes: Some(20),
+            ..Request::default()
+        };
+        let response = HyperEngine::new()
+            .execute(request, Arc::new(Discard))
+            .await
+            .unwrap();
+        assert!(response.truncated);
+        assert_eq!(response.body, b"abcdefghijklmnopqrst");
+        assert_eq!(response.size, 20);
+    }
+
+    #[tokio::test]
+    async fn passes_chunks_to_the_body_sink() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).await;
+            let _ = stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nhello")
+                .await;
+            let _ = stream.flush().await;
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            let _ = stream.write_all(b"world").await;
+        });
+
+        let chunks = Arc::new(Mutex::new(Vec::new()));
+        let received = chunks.clone();
+        let request = Request {
+            request_id: "body-sink".to_string(),
+            url: format!("http://127.0.0.1:{port}/"),
+            method: "GET".to_string(),
+            body_sink: Some(BodySink::new(move |chunk| {
+                received.lock().unwrap().push(chunk)
+            })),
+            ..Request::default()
+        };
+        let response = HyperEngine::new()
+            .execute(request, Arc::new(Discard))
+            .await
+            .unwrap();
+        assert!(response.body.is_empty());
+        assert_eq!(response.size, 10);
+        assert_eq!(chunks.lock().unwrap().concat(), b"helloworld");
+    }
+
+    #[tokio::test]
+    async fn fills_the_har_entry_with_the_request_as_sent_and_masks_the_response() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).await;
+            let _ = stream
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\nx-echo: s3cret\r\ncontent-length: 15\r\n\r\ntoken is s3cret",
+                )
+                .await;
+        });
+
+        let slot = EntrySlot::default();
+        let request = Request {
+            request_id: "har-as-sent".to_string(),
+            url: format!("http://127.0.0.1:{port}/{{{{user}}}}"),
+            method: "POST".to_string(),
+            body: Some(b"token={{token}}".to_vec()),
+            variables: Some(HashMap::from([
+                ("user".to_string(), VariableValue::Text("ann".to_string())),
+                (
+                    "token".to_string(),
+                    VariableValue::Resolved("s3cret".to_string()),
+                ),
+            ])),
+            pre_request: Some(vec![HookStep::SetHeader {
+                name: "x-user".to_string(),
+                value: "{{user}}".to_string(),
+            }]),
+            har_entry: Some(slot.clone()),
+            ..Request::default()
+        };
+        let response = HyperEngine::new()
+            .execute(request, Arc::new(Discard))
+            .await
+            .unwrap();
+        slot.take().unwrap().finish(&response, Some(&response.body));
+
+        let har: serde_json::Value =
+            serde_json::from_str(&har::export(&["har-as-sent".to_string()]).unwrap()).unwrap();
+        let sent = &har["log"]["entries"][0]["request"];
+        assert_eq!(sent["url"], format!("http://127.0.0.1:{port}/ann"));
+        assert_eq!(sent["postData"]["text"], "token=********");
+        assert!(
+            sent["headers"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|header| header["name"] == "x-user" && header["value"] == "ann")
+        );
+        // Secrets echoed back are masked before the entry is kept
+        let received = &har["log"]["entries"][0]["response"];
+        assert_eq!(received["content"]["text"], "token is ********");
+        assert!(
+            received["headers"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|header| header["name"] == "x-echo" && header["value"] == "********")
+        );
+    }
+
+    #[tokio::test]
+    async fn passes_ndjson_records_as_they_arrive() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).await;
+            let _ = stream
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Type: application/x-ndjson\r\nContent-Length: 21\r\n\r\n{\"id\":1}\n{\"i",
+                )
+                .await;
+            let _ = stream.flush().await;
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            let _ = stream.write_all(b"d\":2}\nnot").await;
+        });
+
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let received = records.clone();
+        let request = Request {
+            request_id: "ndjson".to_string(),
+            url: format!("http://127.0.0.1:{port}/"),
+            method: "GET".to_string(),
+            record_sink: Some(RecordSink::new(move |record| {
+                received.lock().unwrap().push(record)
+            })),
+            ..Request::default()
+        };
+        let response = HyperEngine::new()
+            .execute(request, Arc::new(Discard))
+            .await
+            .unwrap();
+        assert_eq!(response.body, b"{\"id\":1}\n{\"id\":2}\nnot");
+        let records = records.lock().unwrap();
+        assert_eq!(
+            records[..2],
+            [
+                NdjsonRecord::Record {
+                    line: 1,
+                    value: serde_json::json!({"id": 1})
+                },
+                NdjsonRecord::Record {
+                    line: 2,
+                    value: serde_json::json!({"id": 2})
+                },
+            ]
+        );
+        assert!(matches!(records[2], NdjsonRecord::Invalid { line: 3, .. }));
+    }
+
+    #[tokio::test]
+    async fn evaluates_assertions_against_a_streamed_body() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).await;
+            let _ = stream
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 9\r\n\r\n{\"id\": 7}",
+                )
+                .await;
+        });
+
+        let chunks = Arc::new(Mutex::new(Vec::new()));
+        let received = chunks.clone();
+        let request = Request {
+            request_id: "assertions".to_string(),
+            url: format!("http://127.0.0.1:{port}/"),
+            method: "GET".to_string(),
+            assertions: Some(
+                serde_json::from_value(serde_json::json!([
+                    {"type": "status", "equals": 200},
+                    {"type": "jsonPath", "path": "$.id", "equals": 7},
+                    {"type": "bodyContains", "text": "missing"},
+                ]))
+                .unwrap(),
+            ),
+            body_sink: Some(BodySink::new(move |chunk| {
+                received.lock().unwrap().push(chunk)
+            })),
+            ..Request::default()
+        };
+        let response = HyperEngine::new()
+            .execute(request, Arc::new(Discard))
+            .await
+            .unwrap();
+        assert_eq!(chunks.lock().unwrap().concat(), br#"{"id": 7}"#);
+        let passed: Vec<bool> = response
+            .assertions
+            .unwrap()
+            .iter()
+            .map(|result| result.passed)
+            .collect();
+        assert_eq!(passed, [true, true, false]);
+    }
+
+    #[tokio::test]
+    async fn keeps_the_partial_body_when_cancelled() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).await;
+            let _ = stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\npartial")
+                .await;
+            let _ = stream.flush().await;
+            // Hold the connection open without sending the rest
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        });
+
+        let token = CancellationToken::new();
+        let cancel = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            cancel.cancel();
+        });
+        let request = Request {
+            request_id: "cancelled-body".to_string(),
+            url: format!("http://127.0.0.1:{port}/"),
+            method: "GET".to_string(),
+            cancellation: Some(token),
+            ..Request::default()
+        };
+        let response = HyperEngine::new()
+            .execute(request, Arc::new(Discard))
+            .await
+            .unwrap();
+        assert!(response.cancelled);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"partial");
+    }
+
+    #[tokio::test]
+    async fn writes_downloads_to_the_chosen_path() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).await;
+            let _ = stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 8\r\n\r\nartifact")
+                .await;
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("artifact.bin");
+        let request = Request {
+            request_id: "download-to-path".to_string(),
+            url: format!("http://127.0.0.1:{port}/artifact"),
+            method: "GET".to_string(),
+            download_to_path: Some(path.to_string_lossy().to_string()),
+            ..Request::default()
+        };
+        let response = HyperEngine::new()
+            .execute(request, Arc::new(Discard))
+            .await
+            .unwrap();
+        assert!(response.body.is_empty());
+        assert!(response.file_path.is_none());
+        assert_eq!(response.size, 8);
+        assert_eq!(response.saved_to, Some(path.to_string_lossy().to_string()));
+        assert_eq!(std::fs::read(&path).unwrap(), b"artifact");
+    }
+
+    #[tokio::test]
+    async fn resumes_interrupted_downloads() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let n = stream.read(&mut buf).await.unwrap();
+            let head = String::from_utf8_lossy(&buf[..n]).to_ascii_lowercase();
+            let response: &[u8] = if head.contains("range: bytes=5-")
+                && head.contains("if-range: \"v1\"")
+            {
+                b"HTTP/1.1 206 Partial Content\r\nContent-Range: bytes 5-9/10\r\nContent-Length: 5\r\n\r\nworld"
+            } else {
+                b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n"
+            };
+            let _ = stream.write_all(response).await;
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("artifact.bin");
+        let validators = dir.path().join("artifact.bin.resume");
+        std::fs::write(&path, b"hello").unwrap();
+        std::fs::write(&validators, br#"{"etag":"\"v1\""}"#).unwrap();
+        let request = Request {
+            request_id: "resume-download".to_string(),
+            url: format!("http://127.0.0.1:{port}/artifact"),
+            method: "GET".to_string(),
+            download_to_path: Some(path.to_string_lossy().to_string()),
+            resume_download: Some(true),
+            ..Request::default()
+        };
+        let response = HyperEngine::new()
+            .execute(request, Arc::new(Discard))
+            .await
+            .unwrap();
+        assert_eq!(response.status, 206);
+        assert_eq!(response.resumed_from, Some(5));
+        assert_eq!(response.content_range.unwrap().total, Some(10));
+        assert_eq!(std::fs::read(&path).unwrap(), b"helloworld");
+        assert!(!validators.exists());
+    }
+
+    #[tokio::test]
+    async fn revalidates_cached_responses() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            for _ in 0..2 {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 1024];
+                let n = stream.read(&mut buf).await.unwrap();
+                let head = String::from_utf8_lossy(&buf[..n]).to_ascii_lowercase();
+                let response: &[u8] = if head.contains("if-none-match: \"v1\"") {
+                    b"HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\n\r\n"
+                } else {
+                    b"HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: 6\r\n\r\ncached"
+                };
+                let _ = stream.write_all(response).await;
+            }
+        });
+
+        let request = Request {
+            request_id: "response-cache".to_string(),
+            url: format!("http://127.0.0.1:{port}/resource"),
+            method: "GET".to_string(),
+            response_cache: Some(true),
+            ..Request::default()
+        };
+        let engine = HyperEngine::new();
+        let first = engine
+            .execute(request.clone(), Arc::new(Discard))
+            .await
+            .unwrap();
+        assert_eq!(first.cache, Some(CacheStatus::Stored));
+
+        let second = engine.execute(request, Arc::new(Discard)).await.unwrap();
+        assert_eq!(second.cache, Some(CacheStatus::Revalidated));
+        assert_eq!(second.status, 200);
+        assert_eq!(second.body, b"cached");
+        assert_eq!(second.size, 6);
+    }
+
+    #[test]
+    fn reads_large_multipart_files_with_progress_logs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("large.bin");
+        let contents = vec![7u8; BODY_PROGRESS_INTERVAL as usize + 1];
+        std::fs::write(&path, &contents).unwrap();
+
+        let sink = Arc::new(Collect::default());
+        let logger = RequestLogger::new(sink.clone(), "r".to_string(), Instant::now());
+        let mut buf = b"head".to_vec();
+        HyperEngine::read_part_file(path.to_str().unwrap(), "file", &mut buf, &logger).unwrap();
+        assert_eq!(buf.len(), 4 + contents.len());
+        assert!(buf[4..] == contents[..]);
+        assert_eq!(sink.phases(), ["multipart_file", "multipart_progress"]);
+
+        let missing = dir.path().join("missing.bin");
+        let err = HyperEngine::read_part_file(missing.to_str().unwrap(), "file", &mut buf, &logger)
+            .unwrap_err();
+        assert!(err.message.contains("missing.bin"));
+    }
+
+    #[test]
+    fn compresses_multipart_bodies_and_keeps_the_plain_body() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("part.txt");
+        std::fs::write(&path, "file contents").unwrap();
+        let request = Request {
+            request_id: "multipart-gzip".to_string(),
+            url: "https://example.com/upload".to_string(),
+            method: "POST".to_string(),
+            multipart_parts: Some(vec![
+                MultipartPart::Text {
+                    name: "title".to_string(),
+                    value: "report".to_string(),
+                },
+                file_part("file", &path),
+            ]),
+            content_encoding: Some(ContentEncoding::Gzip),
+            ..Request::default()
+        };
+
+        let sink = Arc::new(Collect::default());
+        let logger = RequestLogger::new(sink.clone(), "r".to_string(), Instant::now());
+        let mut headers = HeaderMap::new();
+        let (plain, body) = HyperEngine::prepare_body(&request, &mut headers, &logger).unwrap();
+        let plain = plain.into_bytes().unwrap();
+        let plain_text = String::from_utf8_lossy(&plain);
+        assert!(plain_text.contains("name=\"title\"\r\n\r\nreport\r\n"));
+        assert!(plain_text.contains("file contents"));
+        assert_eq!(headers[header::CONTENT_ENCODING], "gzip");
+        assert!(
+            headers[header::CONTENT_TYPE]
+                .to_str()
+                .unwrap()
+                .starts_with("multipart/form-data; boundary=")
+        );
+
+        let mut inflated = Vec::new();
+        flate2::read::GzDecoder::new(body.into_bytes().unwrap().as_ref())
+            .read_to_end(&mut inflated)
+            .unwrap();
+        assert_eq!(inflated, plain);
+        assert!(sink.phases().contains(&"request_body_built".to_string()));
+    }
+
+    #[tokio::test]
+    async fn sends_multipart_file_parts_built_off_the_runtime() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut received = Vec::new();
+            let mut buf = [0u8; 4096];
+            let length = loop {
+                let n = stream.read(&mut buf).await.unwrap();
+                received.extend_from_slice(&buf[..n]);
+                if let Some(end) = received.windows(4).position(|w| w == b"\r\n\r\n") {
+                    let head = String::from_utf8_lossy(&received[..end]).to_ascii_lowercase();
+                    let length: usize = head
+                        .lines()
+                        .find_map(|line| line.strip_prefix("content-length: "))
+                        .unwrap()
+                        .parse()
+                        .unwrap();
+                    break end + 4 + length;
+                }
+            };
+            while received.len() < length {
+                let n = stream.read(&mut buf).await.unwrap();
+                received.extend_from_slice(&buf[..n]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok")
+                .await
+                .unwrap();
+            received
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("upload.bin");
+        std::fs::write(&path, "uploaded bytes").unwrap();
+        let request = Request {
+            request_id: "multipart-upload".to_string(),
+            url: format!("http://127.0.0.1:{port}/upload"),
+            method: "POST".to_string(),
+            multipart_parts: Some(vec![file_part("file", &path)]),
+            ..Request::default()
+        };
+        let sink = Arc::new(Collect::default());
+        let response = HyperEngine::new()
+            .execute(request, sink.clone())
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+
+        let received = String::from_utf8(server.await.unwrap()).unwrap();
+        assert!(received.contains("filename=\"upload.bin\""));
+        assert!(received.contains("\r\n\r\nuploaded bytes\r\n"));
+        let phases = sink.phases();
+        let built = phases
+            .iter()
+            .position(|p| p == "request_body_built")
+            .unwrap();
+        let sent = phases.iter().position(|p| p == "request").unwrap();
+        assert!(built < sent);
+    }
+
+    #[tokio::test]
+    async fn shares_one_connection_across_a_connection_group() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = accepted.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut received = Vec::new();
+                    let mut buf = [0u8; 1024];
+                    loop {
+                        let n = stream.read(&mut buf).await.unwrap_or(0);
+                        if n == 0 {
+                            return;
+                        }
+                        received.extend_from_slice(&buf[..n]);
+                        while let Some(end) = received.windows(4).position(|w| w == b"\r\n\r\n") {
+                            received.drain(..end + 4);
+                            stream
+                                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok")
+                                .await
+                                .unwrap();
+                        }
+                    }
+                });
+            }
+        });
+
+        let step = |path: &str| Request {
+            request_id: format!("group-{path}"),
+            url: format!("http://127.0.0.1:{port}/{path}"),
+            method: "GET".to_string(),
+            connection_group: Some("two-step-sequence".to_string()),
+            ..Request::default()
+        };
+        let engine = HyperEngine::new();
+        let login = engine
+            .execute(step("login"), Arc::new(Discard))
+            .await
+            .unwrap();
+        let profile = engine
+            .execute(step("profile"), Arc::new(Discard))
+            .await
+            .unwrap();
+        let (login, profile) = (login.connection.unwrap(), profile.connection.unwrap());
+        assert!(!login.reused);
+        assert!(profile.reused);
+        assert_eq!(login.local_addr, profile.local_addr);
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+
+        assert!(close_connection_group("two-step-sequence"));
+        assert!(!close_connection_group("two-step-sequence"));
+    }
+}
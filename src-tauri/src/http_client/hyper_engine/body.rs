@@ -0,0 +1,192 @@
+use std::io;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+
+use bytes::Bytes;
+use http_body_util::combinators::UnsyncBoxBody;
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Body, Frame, SizeHint};
+use tokio::io::{AsyncRead, ReadBuf};
+
+use super::{HyperEngine, RequestLogger};
+use crate::errors::{AppError, ErrorKind};
+
+/// Size of the chunks read from a body file
+const FILE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Body type handed to the hyper client
+pub(super) type RequestBody = UnsyncBoxBody<Bytes, io::Error>;
+
+/// Where the outgoing request body comes from. File bodies are streamed from disk each
+/// time the request is sent (including redirects and fallbacks) instead of being buffered.
+#[derive(Debug, Clone)]
+pub(super) enum BodySource {
+    Bytes(Bytes),
+    File { path: PathBuf, len: u64 },
+}
+
+impl BodySource {
+    pub(super) fn empty() -> Self {
+        BodySource::Bytes(Bytes::new())
+    }
+
+    pub(super) fn len(&self) -> u64 {
+        match self {
+            BodySource::Bytes(bytes) => bytes.len() as u64,
+            BodySource::File { len, .. } => *len,
+        }
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reads the whole body into memory, for transformations that need all of it at once.
+    pub(super) fn into_bytes(self) -> Result<Bytes, AppError> {
+        match self {
+            BodySource::Bytes(bytes) => Ok(bytes),
+            BodySource::File { path, .. } => std::fs::read(&path).map(Bytes::from).map_err(|e| {
+                AppError::new(
+                    ErrorKind::IoError,
+                    format!("Failed to read body file '{}': {e}", path.display()),
+                )
+            }),
+        }
+    }
+
+    /// Creates a fresh body for one send attempt. With `log`, each chunk read from a body
+    /// file is logged as it is sent.
+    pub(super) fn to_body(
+        &self,
+        log: Option<(RequestLogger, usize)>,
+    ) -> Result<RequestBody, AppError> {
+        match self {
+            BodySource::Bytes(bytes) => Ok(Full::new(bytes.clone())
+                .map_err(|never| match never {})
+                .boxed_unsync()),
+            BodySource::File { path, len } => {
+                let file = std::fs::File::open(path).map_err(|e| {
+                    AppError::new(
+                        ErrorKind::IoError,
+                        format!("Failed to read body file '{}': {e}", path.display()),
+                    )
+                })?;
+                Ok(FileBody {
+                    file: tokio::fs::File::from_std(file),
+                    buf: Vec::new(),
+                    remaining: *len,
+                    log,
+                }
+                .boxed_unsync())
+            }
+        }
+    }
+}
+
+/// Streams a file in fixed-size chunks with an exact size hint, so hyper still sends a
+/// `Content-Length` instead of falling back to chunked encoding.
+struct FileBody {
+    file: tokio::fs::File,
+    buf: Vec<u8>,
+    remaining: u64,
+    log: Option<(RequestLogger, usize)>,
+}
+
+impl Body for FileBody {
+    type Data = Bytes;
+    type Error = io::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.get_mut();
+        if this.remaining == 0 {
+            return Poll::Ready(None);
+        }
+        let want = usize::try_from(this.remaining)
+            .unwrap_or(FILE_CHUNK_SIZE)
+            .min(FILE_CHUNK_SIZE);
+        this.buf.resize(want, 0);
+        let mut read_buf = ReadBuf::new(&mut this.buf);
+        ready!(Pin::new(&mut this.file).poll_read(cx, &mut read_buf))?;
+        let read = read_buf.filled().len();
+        if read == 0 {
+            // The size was announced up front, so a file that shrank can't be sent correctly
+            return Poll::Ready(Some(Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Body file ended before its expected size",
+            ))));
+        }
+        this.remaining -= read as u64;
+        let chunk = Bytes::copy_from_slice(&this.buf[..read]);
+        if let Some((logger, max_log_bytes)) = &this.log {
+            HyperEngine::log_body(
+                logger,
+                "request_body",
+                "body",
+                &chunk,
+                *max_log_bytes,
+                "> body:",
+            );
+        }
+        Poll::Ready(Some(Ok(Frame::data(chunk))))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.remaining == 0
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::with_exact(self.remaining)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BodySource, FILE_CHUNK_SIZE};
+    use http_body_util::BodyExt;
+    use hyper::body::Body;
+    use std::io::Write;
+
+    #[tokio::test]
+    async fn streams_file_in_chunks_with_exact_size() {
+        let data: Vec<u8> = (0..FILE_CHUNK_SIZE * 2 + 123)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&data).unwrap();
+
+        let source = BodySource::File {
+            path: file.path().to_path_buf(),
+            len: data.len() as u64,
+        };
+        let mut body = source.to_body(None).unwrap();
+        assert_eq!(body.size_hint().exact(), Some(data.len() as u64));
+
+        let mut frames = 0;
+        let mut received = Vec::new();
+        while let Some(frame) = body.frame().await {
+            received.extend_from_slice(&frame.unwrap().into_data().unwrap());
+            frames += 1;
+        }
+        assert_eq!(frames, 3);
+        assert_eq!(received, data);
+
+        // Each send attempt re-reads the file from the start
+        let again = source.to_body(None).unwrap().collect().await.unwrap();
+        assert_eq!(again.to_bytes().len(), data.len());
+    }
+
+    #[tokio::test]
+    async fn fails_when_file_is_shorter_than_announced() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"short").unwrap();
+        let source = BodySource::File {
+            path: file.path().to_path_buf(),
+            len: 10,
+        };
+        assert!(source.to_body(None).unwrap().collect().await.is_err());
+    }
+}
@@ -0,0 +1,197 @@
+use serde::Serialize;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncReadExt;
+
+use super::HyperEngine;
+use crate::errors::AppError;
+use crate::http_client::request::{
+    ClientCertificate, HttpVersionPref, IpFamily, MultipartPart, Request, TlsVersion,
+};
+
+/// A fully resolved request in a stable JSON form, with its SHA-256.
+///
+/// Two requests with the same hash send the same method, URL, headers and body over the same
+/// transport settings. Bodies and files are represented by their size and SHA-256, and local
+/// file paths are left out so the hash is portable between machines. Multipart boundaries are
+/// random per send, so multipart bodies are compared part by part.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CanonicalRequest {
+    /// Canonical JSON: object keys sorted, no insignificant whitespace
+    pub canonical: String,
+    /// Hex encoded SHA-256 of `canonical`
+    pub sha256: String,
+}
+
+fn digest(bytes: &[u8]) -> Value {
+    json!({"size": bytes.len(), "sha256": hex::encode(Sha256::digest(bytes))})
+}
+
+async fn digest_file(path: &str) -> Result<Value, AppError> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        size += n as u64;
+    }
+    Ok(json!({"size": size, "sha256": hex::encode(hasher.finalize())}))
+}
+
+async fn canonical_body(request: &Request) -> Result<Value, AppError> {
+    if let Some(parts) = &request.multipart_parts {
+        let mut out = Vec::with_capacity(parts.len());
+        for part in parts {
+            out.push(match part {
+                MultipartPart::Text { name, value } => json!({
+                    "type": "text",
+                    "name": name,
+                    "value": digest(value.as_bytes()),
+                }),
+                MultipartPart::File {
+                    name,
+                    file_path,
+                    file_name,
+                    content_type,
+                } => json!({
+                    "type": "file",
+                    "name": name,
+                    "fileName": file_name,
+                    "contentType": content_type,
+                    "content": digest_file(file_path).await?,
+                }),
+            });
+        }
+        return Ok(json!({"type": "multipart", "parts": out}));
+    }
+    if let Some(path) = &request.body_file_path {
+        return Ok(json!({"type": "bytes", "content": digest_file(path).await?}));
+    }
+    Ok(match &request.body {
+        Some(bytes) if !bytes.is_empty() => json!({"type": "bytes", "content": digest(bytes)}),
+        _ => Value::Null,
+    })
+}
+
+async fn canonical_transport(request: &Request) -> Result<Value, AppError> {
+    let ca = match &request.ca_path {
+        Some(path) => Some(digest_file(path).await?),
+        None => None,
+    };
+    let client_certificate = match &request.client_certificate {
+        Some(ClientCertificate::Pem { cert_path, .. }) => {
+            json!({"type": "pem", "certificate": digest_file(cert_path).await?})
+        }
+        Some(ClientCertificate::System {
+            thumbprint,
+            subject,
+        }) => json!({
+            "type": "system",
+            "thumbprint": thumbprint.as_ref().map(|t| t.to_ascii_lowercase()),
+            "subject": subject,
+        }),
+        None => Value::Null,
+    };
+    Ok(json!({
+        "httpVersion": match request.http_version {
+            Some(HttpVersionPref::Http1) => "http1",
+            Some(HttpVersionPref::Http2) => "http2",
+            Some(HttpVersionPref::Auto) | None => "auto",
+        },
+        "ipFamily": match request.ip_family.unwrap_or_default() {
+            IpFamily::Auto => "auto",
+            IpFamily::Ipv4 => "ipv4",
+            IpFamily::Ipv6 => "ipv6",
+        },
+        "localAddress": request.local_address,
+        "interface": request.interface,
+        "verifyTls": !request.disable_ssl.unwrap_or(false),
+        "caBundle": ca,
+        "insecureHosts": request.insecure_hosts,
+        "checkRevocation": request.check_revocation.unwrap_or(false),
+        "tlsMinVersion": request.tls_min_version.map(TlsVersion::as_str),
+        "tlsMaxVersion": request.tls_max_version.map(TlsVersion::as_str),
+        "cipherSuites": request.cipher_suites,
+        "clientCertificate": client_certificate,
+        "hostOverride": request.host_override,
+        "ipOverride": request.ip_override,
+        "dnsOverrides": request.dns_overrides,
+        "contentEncoding": request.content_encoding.map(|e| e.as_str()),
+        "maxRedirects": request.max_redirects.unwrap_or(0),
+    }))
+}
+
+/// Builds the canonical form of `request` as the engine would send it.
+pub async fn canonical_request(request: &Request) -> Result<CanonicalRequest, AppError> {
+    let method = HyperEngine::parse_method(request)?;
+    let uri = HyperEngine::build_uri(request)?;
+    let mut headers: Vec<(String, String)> = HyperEngine::build_headers(request)?
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.as_str().to_string(),
+                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            )
+        })
+        .collect();
+    headers.sort();
+
+    // serde_json maps are ordered by key, so serialization is already canonical
+    let value = json!({
+        "version": 1,
+        "method": method.as_str(),
+        "url": uri.to_string(),
+        "headers": headers,
+        "body": canonical_body(request).await?,
+        "transport": canonical_transport(request).await?,
+    });
+    let canonical = serde_json::to_string(&value)?;
+    let sha256 = hex::encode(Sha256::digest(canonical.as_bytes()));
+    Ok(CanonicalRequest { canonical, sha256 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::canonical_request;
+    use crate::http_client::request::Request;
+
+    fn request(headers: &[(&str, &str)]) -> Request {
+        Request {
+            request_id: "r1".into(),
+            url: "https://api.example.com/users?id=1".into(),
+            method: "POST".into(),
+            headers: Some(
+                headers
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            ),
+            body: Some(b"{\"name\":\"knurl\"}".to_vec()),
+            user_agent: Some("test".into()),
+            ..Request::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn hash_ignores_header_order_and_request_identity() {
+        let a = canonical_request(&request(&[("Accept", "*/*"), ("X-Trace", "1")]))
+            .await
+            .unwrap();
+        let mut b_request = request(&[("x-trace", "1"), ("accept", "*/*")]);
+        b_request.request_id = "r2".into();
+        b_request.log_bodies = Some(false);
+        let b = canonical_request(&b_request).await.unwrap();
+        assert_eq!(a, b);
+        assert!(a.canonical.contains("\"headers\":[[\"accept\",\"*/*\"],"));
+
+        let mut changed = request(&[("Accept", "*/*"), ("X-Trace", "1")]);
+        changed.body = Some(b"{\"name\":\"other\"}".to_vec());
+        assert_ne!(canonical_request(&changed).await.unwrap().sha256, a.sha256);
+    }
+}
@@ -0,0 +1,563 @@
+//! Answers to `WWW-Authenticate` challenges (RFC 9110 §11.6.1): Basic (RFC 7617), Digest
+//! (RFC 7616) and Negotiate (RFC 4559), with the username and password of the request's Basic
+//! or Digest auth. Like curl's `--anyauth`, a rejected request is retried once with an answer
+//! to the strongest scheme the server offers that the configured auth allows.
+//!
+//! Negotiate is answered with NTLM messages, which Windows servers accept in place of SPNEGO
+//! tokens; Kerberos isn't supported. NTLM takes two legs, so a Negotiate answer carries the
+//! negotiate message and the server's next challenge is answered with the authenticate message.
+
+use base64::{Engine as _, engine::general_purpose};
+use hyper::Method;
+use hyper::http::{HeaderMap, HeaderValue, Uri};
+use md5::Md5;
+use rand::RngCore;
+use sha2::{Digest as _, Sha256, Sha512_256};
+
+use super::ntlm;
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::request::{ChallengeCredentials, ChallengeScheme, NtlmCredentials};
+
+/// One challenge of a `WWW-Authenticate` header
+#[derive(Debug, PartialEq)]
+pub(super) struct Challenge {
+    pub scheme: String,
+    /// Auth parameters, names lower-cased
+    pub params: Vec<(String, String)>,
+    /// The token sent in place of parameters, e.g. the server's leg of a Negotiate exchange
+    pub token68: Option<String>,
+}
+
+impl Challenge {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Splits at commas outside quoted strings
+fn split_items(value: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let (mut start, mut quoted, mut escaped) = (0, false, false);
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                items.push(&value[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&value[start..]);
+    items
+}
+
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    let Some(inner) = value
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+    else {
+        return value.to_string();
+    };
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        out.push(if c == '\\' {
+            chars.next().unwrap_or(c)
+        } else {
+            c
+        });
+    }
+    out
+}
+
+/// `name=value`, unless the `=` only pads a token68
+fn auth_param(item: &str) -> Option<(String, String)> {
+    let (name, value) = item.split_once('=')?;
+    let name = name.trim();
+    if name.is_empty() || name.contains(' ') || value.chars().all(|c| c == '=') {
+        return None;
+    }
+    Some((name.to_ascii_lowercase(), unquote(value)))
+}
+
+/// Parses the challenges of `WWW-Authenticate` header values. A value can hold several
+/// challenges, and their parameters are separated by the same commas.
+pub(super) fn parse_challenges<'a>(values: impl Iterator<Item = &'a str>) -> Vec<Challenge> {
+    let mut challenges: Vec<Challenge> = Vec::new();
+    for value in values {
+        for item in split_items(value) {
+            let item = item.trim();
+            if item.is_empty() {
+                continue;
+            }
+            if let Some(param) = auth_param(item)
+                && let Some(challenge) = challenges.last_mut()
+            {
+                challenge.params.push(param);
+                continue;
+            }
+            // A new challenge: the scheme, then its first parameter or a token68
+            let (scheme, rest) = item.split_once(' ').unwrap_or((item, ""));
+            let rest = rest.trim();
+            let mut challenge = Challenge {
+                scheme: scheme.to_string(),
+                params: Vec::new(),
+                token68: None,
+            };
+            if let Some(param) = auth_param(rest) {
+                challenge.params.push(param);
+            } else if !rest.is_empty() {
+                challenge.token68 = Some(rest.to_string());
+            }
+            challenges.push(challenge);
+        }
+    }
+    challenges
+}
+
+/// Digest algorithms (RFC 7616 §6.1), weakest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Algorithm {
+    Md5,
+    Sha256,
+    Sha512_256,
+}
+
+impl Algorithm {
+    fn parse(name: &str) -> Option<(Self, bool)> {
+        let (base, session) = match name.len().checked_sub(5) {
+            Some(at) if name[at..].eq_ignore_ascii_case("-sess") => (&name[..at], true),
+            _ => (name, false),
+        };
+        let algorithm = match base.to_ascii_uppercase().as_str() {
+            "MD5" => Algorithm::Md5,
+            "SHA-256" => Algorithm::Sha256,
+            "SHA-512-256" => Algorithm::Sha512_256,
+            _ => return None,
+        };
+        Some((algorithm, session))
+    }
+
+    fn hash(self, input: &str) -> String {
+        match self {
+            Algorithm::Md5 => hex::encode(Md5::digest(input)),
+            Algorithm::Sha256 => hex::encode(Sha256::digest(input)),
+            Algorithm::Sha512_256 => hex::encode(Sha512_256::digest(input)),
+        }
+    }
+}
+
+/// A Digest challenge this engine can answer
+struct DigestChallenge<'a> {
+    challenge: &'a Challenge,
+    algorithm: Algorithm,
+    session: bool,
+    /// Whether the server accepts `qop=auth`; without `qop` the RFC 2069 form is used
+    qop_auth: bool,
+}
+
+impl<'a> DigestChallenge<'a> {
+    fn new(challenge: &'a Challenge) -> Option<Self> {
+        challenge.param("nonce")?;
+        let (algorithm, session) = Algorithm::parse(challenge.param("algorithm").unwrap_or("MD5"))?;
+        let qop_auth = match challenge.param("qop") {
+            Some(qop) => {
+                // Only `auth-int` would need the body hashed in, which isn't supported
+                if !qop
+                    .split(',')
+                    .any(|q| q.trim().eq_ignore_ascii_case("auth"))
+                {
+                    return None;
+                }
+                true
+            }
+            None => false,
+        };
+        Some(Self {
+            challenge,
+            algorithm,
+            session,
+            qop_auth,
+        })
+    }
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// The `Authorization` value answering a Digest challenge (RFC 7616 §3.4)
+fn digest_authorization(
+    digest: &DigestChallenge,
+    credentials: &ChallengeCredentials,
+    method: &Method,
+    uri: &str,
+    cnonce: &str,
+) -> String {
+    let h = |input: String| digest.algorithm.hash(&input);
+    let realm = digest.challenge.param("realm").unwrap_or_default();
+    let nonce = digest.challenge.param("nonce").unwrap_or_default();
+    let nc = "00000001";
+
+    let mut ha1 = h(format!(
+        "{}:{realm}:{}",
+        credentials.username, credentials.password
+    ));
+    if digest.session {
+        ha1 = h(format!("{ha1}:{nonce}:{cnonce}"));
+    }
+    let ha2 = h(format!("{method}:{uri}"));
+    let response = if digest.qop_auth {
+        h(format!("{ha1}:{nonce}:{nc}:{cnonce}:auth:{ha2}"))
+    } else {
+        h(format!("{ha1}:{nonce}:{ha2}"))
+    };
+
+    let userhash = digest
+        .challenge
+        .param("userhash")
+        .is_some_and(|v| v.eq_ignore_ascii_case("true"));
+    let username = if userhash {
+        h(format!("{}:{realm}", credentials.username))
+    } else {
+        credentials.username.clone()
+    };
+    let algorithm = digest
+        .challenge
+        .param("algorithm")
+        .unwrap_or("MD5")
+        .to_string();
+
+    let mut parts = vec![
+        format!("username={}", quote(&username)),
+        format!("realm={}", quote(realm)),
+        format!("nonce={}", quote(nonce)),
+        format!("uri={}", quote(uri)),
+        format!("algorithm={algorithm}"),
+        format!("response={}", quote(&response)),
+    ];
+    if digest.qop_auth {
+        parts.push("qop=auth".to_string());
+        parts.push(format!("nc={nc}"));
+        parts.push(format!("cnonce={}", quote(cnonce)));
+    }
+    if let Some(opaque) = digest.challenge.param("opaque") {
+        parts.push(format!("opaque={}", quote(opaque)));
+    }
+    if userhash {
+        parts.push("userhash=true".to_string());
+    }
+    format!("Digest {}", parts.join(", "))
+}
+
+fn new_cnonce() -> String {
+    let mut bytes = [0u8; 16];
+    rand::rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// The challenges for `scheme`, in the order the server sent them
+fn offered<'a>(
+    challenges: &'a [Challenge],
+    scheme: &'a str,
+) -> impl Iterator<Item = &'a Challenge> {
+    challenges
+        .iter()
+        .filter(move |challenge| challenge.scheme.eq_ignore_ascii_case(scheme))
+}
+
+/// An `Authorization` header answering a challenge
+#[derive(Debug)]
+pub(super) struct Answer {
+    pub authorization: HeaderValue,
+    pub scheme: &'static str,
+    /// Whether the answer only opens an exchange the server challenges again, as the NTLM
+    /// negotiate message does
+    pub continues: bool,
+}
+
+/// The NTLM message answering a Negotiate challenge: the negotiate message to open the
+/// exchange, or the authenticate message once the server sent its NTLM challenge.
+fn negotiate_authorization(
+    negotiate: &Challenge,
+    credentials: &ChallengeCredentials,
+) -> Result<(String, bool), AppError> {
+    let Some(token) = &negotiate.token68 else {
+        return Ok((format!("Negotiate {}", ntlm::negotiate_message()), true));
+    };
+    let challenge = ntlm::parse_challenge(token)?;
+    let credentials = NtlmCredentials {
+        username: credentials.username.clone(),
+        password: credentials.password.clone(),
+        domain: None,
+        workstation: None,
+    };
+    let message = ntlm::authenticate_message(&challenge, &credentials);
+    Ok((format!("Negotiate {message}"), false))
+}
+
+/// The `Authorization` header answering the strongest challenge in `headers` that
+/// `credentials` allow. `None` when there's none to answer.
+pub(super) fn respond(
+    credentials: &ChallengeCredentials,
+    headers: &HeaderMap,
+    method: &Method,
+    uri: &Uri,
+) -> Result<Option<Answer>, AppError> {
+    let challenges = parse_challenges(
+        headers
+            .get_all(hyper::header::WWW_AUTHENTICATE)
+            .iter()
+            .filter_map(|value| value.to_str().ok()),
+    );
+    let allows = |scheme| credentials.schemes.is_empty() || credentials.schemes.contains(&scheme);
+
+    let (authorization, scheme, continues) = if let Some(negotiate) =
+        offered(&challenges, "Negotiate")
+            .next()
+            .filter(|_| allows(ChallengeScheme::Negotiate))
+    {
+        let (value, continues) = negotiate_authorization(negotiate, credentials)?;
+        (value, "Negotiate", continues)
+    } else if let Some(digest) = offered(&challenges, "Digest")
+        .filter(|_| allows(ChallengeScheme::Digest))
+        .filter_map(DigestChallenge::new)
+        .max_by_key(|digest| digest.algorithm)
+    {
+        let target = uri.path_and_query().map_or("/", |pq| pq.as_str());
+        let value = digest_authorization(&digest, credentials, method, target, &new_cnonce());
+        (value, "Digest", false)
+    } else if offered(&challenges, "Basic").next().is_some() && allows(ChallengeScheme::Basic) {
+        let encoded = general_purpose::STANDARD
+            .encode(format!("{}:{}", credentials.username, credentials.password));
+        (format!("Basic {encoded}"), "Basic", false)
+    } else {
+        return Ok(None);
+    };
+    let authorization = HeaderValue::try_from(authorization).map_err(|e| {
+        AppError::new(
+            ErrorKind::BadRequest,
+            format!("Invalid credentials for the authentication challenge: {e}"),
+        )
+    })?;
+    Ok(Some(Answer {
+        authorization,
+        scheme,
+        continues,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Challenge, DigestChallenge, digest_authorization, parse_challenges, respond};
+    use crate::http_client::request::{ChallengeCredentials, ChallengeScheme};
+    use base64::{Engine as _, engine::general_purpose};
+    use hyper::Method;
+    use hyper::http::{HeaderMap, HeaderValue, Uri};
+
+    fn credentials(username: &str, password: &str) -> ChallengeCredentials {
+        ChallengeCredentials {
+            username: username.to_string(),
+            password: password.to_string(),
+            schemes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn parses_several_challenges_per_header() {
+        let challenges = parse_challenges(
+            [
+                r#"Digest realm="a, b", qop="auth,auth-int", nonce="n\"1", Basic realm="x""#,
+                "Negotiate abc==",
+            ]
+            .into_iter(),
+        );
+        assert_eq!(
+            challenges,
+            vec![
+                Challenge {
+                    scheme: "Digest".to_string(),
+                    params: vec![
+                        ("realm".to_string(), "a, b".to_string()),
+                        ("qop".to_string(), "auth,auth-int".to_string()),
+                        ("nonce".to_string(), "n\"1".to_string()),
+                    ],
+                    token68: None,
+                },
+                Challenge {
+                    scheme: "Basic".to_string(),
+                    params: vec![("realm".to_string(), "x".to_string())],
+                    token68: None,
+                },
+                Challenge {
+                    scheme: "Negotiate".to_string(),
+                    params: Vec::new(),
+                    token68: Some("abc==".to_string()),
+                },
+            ]
+        );
+    }
+
+    /// The SHA-256 example of RFC 7616 §3.9.1
+    #[test]
+    fn matches_rfc_7616_example() {
+        let challenge = &parse_challenges(
+            [concat!(
+                r#"Digest realm="http-auth@example.org", qop="auth, auth-int", "#,
+                r#"algorithm=SHA-256, nonce="7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v", "#,
+                r#"opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#
+            )]
+            .into_iter(),
+        )[0];
+        let digest = DigestChallenge::new(challenge).unwrap();
+        let authorization = digest_authorization(
+            &digest,
+            &credentials("Mufasa", "Circle of Life"),
+            &Method::GET,
+            "/dir/index.html",
+            "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ",
+        );
+        assert!(authorization.contains(
+            r#"response="753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1""#
+        ));
+        assert!(authorization.contains("qop=auth, nc=00000001"));
+        assert!(authorization.contains(r#"opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#));
+    }
+
+    #[test]
+    fn answers_the_strongest_allowed_challenge() {
+        let mut headers = HeaderMap::new();
+        for value in [
+            r#"Basic realm="api""#,
+            r#"Digest realm="api", nonce="abc", qop="auth""#,
+            r#"Digest realm="api", nonce="abc", qop="auth", algorithm=SHA-256"#,
+        ] {
+            headers.append("www-authenticate", HeaderValue::from_static(value));
+        }
+        let uri: Uri = "https://example.com/a?b=1".parse().unwrap();
+        let any = credentials("u", "p");
+
+        let answer = respond(&any, &headers, &Method::GET, &uri)
+            .unwrap()
+            .unwrap();
+        assert_eq!(answer.scheme, "Digest");
+        let value = answer.authorization.to_str().unwrap();
+        assert!(value.contains("algorithm=SHA-256"));
+        assert!(value.contains(r#"uri="/a?b=1""#));
+
+        let basic_only = ChallengeCredentials {
+            schemes: vec![ChallengeScheme::Basic],
+            ..any
+        };
+        let answer = respond(&basic_only, &headers, &Method::GET, &uri)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (answer.authorization.to_str().unwrap(), answer.scheme),
+            ("Basic dTpw", "Basic")
+        );
+
+        // Basic auth already sent its header, so it only answers Digest
+        let mut basic_challenge = HeaderMap::new();
+        basic_challenge.insert(
+            "www-authenticate",
+            HeaderValue::from_static(r#"Basic realm="api""#),
+        );
+        let from_basic_auth = ChallengeCredentials {
+            schemes: vec![ChallengeScheme::Digest],
+            ..credentials("u", "p")
+        };
+        assert!(
+            respond(&from_basic_auth, &basic_challenge, &Method::GET, &uri)
+                .unwrap()
+                .is_none()
+        );
+
+        // auth-int only needs the body, which isn't supported
+        let mut auth_int = HeaderMap::new();
+        auth_int.insert(
+            "www-authenticate",
+            HeaderValue::from_static(r#"Digest realm="api", nonce="abc", qop="auth-int""#),
+        );
+        assert!(
+            respond(&credentials("u", "p"), &auth_int, &Method::GET, &uri)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    /// A CHALLENGE_MESSAGE (MS-NLMP 2.2.1.2) without target info
+    fn ntlm_challenge() -> String {
+        let mut message = b"NTLMSSP\0".to_vec();
+        message.extend_from_slice(&2u32.to_le_bytes());
+        message.extend_from_slice(&[0; 8]);
+        message.extend_from_slice(&0x0008_8205u32.to_le_bytes());
+        message.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        message.extend_from_slice(&[0; 16]);
+        general_purpose::STANDARD.encode(message)
+    }
+
+    fn ntlm_message_type(authorization: &str) -> u8 {
+        let token = authorization.strip_prefix("Negotiate ").unwrap();
+        let message = general_purpose::STANDARD.decode(token).unwrap();
+        assert_eq!(&message[..8], b"NTLMSSP\0");
+        message[8]
+    }
+
+    #[test]
+    fn answers_negotiate_with_ntlm_in_two_legs() {
+        let uri: Uri = "https://example.com/a".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        for value in [r#"Digest realm="api", nonce="abc""#, "Negotiate", "NTLM"] {
+            headers.append("www-authenticate", HeaderValue::from_static(value));
+        }
+
+        // Negotiate is the strongest scheme, and the first leg only opens the exchange
+        let first = respond(&credentials("CORP\\u", "p"), &headers, &Method::GET, &uri)
+            .unwrap()
+            .unwrap();
+        assert_eq!((first.scheme, first.continues), ("Negotiate", true));
+        assert_eq!(ntlm_message_type(first.authorization.to_str().unwrap()), 1);
+
+        let mut challenged = HeaderMap::new();
+        challenged.insert(
+            "www-authenticate",
+            HeaderValue::try_from(format!("Negotiate {}", ntlm_challenge())).unwrap(),
+        );
+        let second = respond(
+            &credentials("CORP\\u", "p"),
+            &challenged,
+            &Method::GET,
+            &uri,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!((second.scheme, second.continues), ("Negotiate", false));
+        assert_eq!(ntlm_message_type(second.authorization.to_str().unwrap()), 3);
+
+        // Credentials limited to Digest skip it
+        let digest_only = ChallengeCredentials {
+            schemes: vec![ChallengeScheme::Digest],
+            ..credentials("u", "p")
+        };
+        let answer = respond(&digest_only, &headers, &Method::GET, &uri)
+            .unwrap()
+            .unwrap();
+        assert_eq!(answer.scheme, "Digest");
+
+        // A Kerberos token isn't an NTLM challenge
+        let mut kerberos = HeaderMap::new();
+        kerberos.insert(
+            "www-authenticate",
+            HeaderValue::from_static("Negotiate YIIBhgYGKwYBBQUCoIIBejCCAXY="),
+        );
+        assert!(respond(&credentials("u", "p"), &kerberos, &Method::GET, &uri).is_err());
+    }
+}
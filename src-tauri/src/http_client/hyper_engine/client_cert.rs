@@ -0,0 +1,746 @@
+// The OS store backends only exist on Windows and macOS; elsewhere the selection and
+// native signing helpers are only exercised by tests.
+#![cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+
+use std::fs;
+use std::sync::Arc;
+
+use rustls::sign::{CertifiedKey, Signer, SigningKey};
+use rustls::{SignatureAlgorithm, SignatureScheme};
+use rustls_pemfile::{certs, private_key};
+use serde_json::json;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use x509_parser::prelude::{FromDer, X509Certificate};
+use x509_parser::public_key::PublicKey;
+
+use super::RequestLogger;
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::request::ClientCertificate;
+
+/// Load the client certificate and signing key described by `source`.
+pub(super) fn load_client_certificate(
+    source: &ClientCertificate,
+    logger: &RequestLogger,
+) -> Result<Arc<CertifiedKey>, AppError> {
+    let (origin, certified) = match source {
+        ClientCertificate::Pem {
+            cert_path,
+            key_path,
+        } => ("pem", load_pem(cert_path, key_path)?),
+        ClientCertificate::System {
+            thumbprint,
+            subject,
+        } => {
+            let selector = CertificateSelector::new(thumbprint.as_deref(), subject.as_deref())?;
+            let (chain, key) = platform::find_identity(&selector)?;
+            ("system", CertifiedKey::new(chain, key))
+        }
+    };
+
+    if let Some(leaf) = certified.cert.first() {
+        let subject = X509Certificate::from_der(leaf.as_ref())
+            .map(|(_, cert)| cert.subject().to_string())
+            .unwrap_or_default();
+        logger.info(
+            "tls",
+            Some("client_cert"),
+            format!("Using client certificate {subject}"),
+            Some(json!({
+                "source": origin,
+                "subject": subject,
+                "sha1": hex::encode_upper(Sha1::digest(leaf.as_ref())),
+                "chainLength": certified.cert.len(),
+            })),
+        );
+    }
+
+    Ok(Arc::new(certified))
+}
+
+fn load_pem(cert_path: &str, key_path: &str) -> Result<CertifiedKey, AppError> {
+    let cert_data = fs::read(cert_path).map_err(|e| {
+        AppError::new(
+            ErrorKind::IoError,
+            format!("Failed to read client certificate: {e}"),
+        )
+    })?;
+    let chain = certs(&mut std::io::Cursor::new(cert_data))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            AppError::new(
+                ErrorKind::BadRequest,
+                format!("Invalid client certificate: {e}"),
+            )
+        })?;
+    if chain.is_empty() {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            "No certificates found in client certificate file",
+        ));
+    }
+
+    let key_data = fs::read(key_path).map_err(|e| {
+        AppError::new(
+            ErrorKind::IoError,
+            format!("Failed to read client key: {e}"),
+        )
+    })?;
+    let key = private_key(&mut std::io::Cursor::new(key_data))
+        .map_err(|e| AppError::new(ErrorKind::BadRequest, format!("Invalid client key: {e}")))?
+        .ok_or_else(|| {
+            AppError::new(
+                ErrorKind::BadRequest,
+                "No private key found in client key file",
+            )
+        })?;
+    let signing_key = rustls::crypto::ring::sign::any_supported_type(&key).map_err(|e| {
+        AppError::new(
+            ErrorKind::BadRequest,
+            format!("Unsupported client key: {e}"),
+        )
+    })?;
+
+    Ok(CertifiedKey::new(chain, signing_key))
+}
+
+/// Criteria for picking an identity out of the OS certificate store.
+#[derive(Debug)]
+pub(super) struct CertificateSelector {
+    /// SHA-1 (20 bytes) or SHA-256 (32 bytes) fingerprint of the DER certificate
+    thumbprint: Option<Vec<u8>>,
+    /// Lowercased substring of the subject distinguished name
+    subject: Option<String>,
+}
+
+impl CertificateSelector {
+    pub(super) fn new(thumbprint: Option<&str>, subject: Option<&str>) -> Result<Self, AppError> {
+        let thumbprint = thumbprint
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(parse_thumbprint)
+            .transpose()?;
+        let subject = subject
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_lowercase);
+        if thumbprint.is_none() && subject.is_none() {
+            return Err(AppError::new(
+                ErrorKind::BadRequest,
+                "A thumbprint or subject is required to select a system client certificate",
+            ));
+        }
+        Ok(Self {
+            thumbprint,
+            subject,
+        })
+    }
+
+    pub(super) fn matches(&self, der: &[u8]) -> bool {
+        if let Some(expected) = &self.thumbprint {
+            let actual = match expected.len() {
+                20 => Sha1::digest(der).to_vec(),
+                _ => Sha256::digest(der).to_vec(),
+            };
+            if &actual != expected {
+                return false;
+            }
+        }
+        if let Some(needle) = &self.subject {
+            let Ok((_, cert)) = X509Certificate::from_der(der) else {
+                return false;
+            };
+            if !cert.subject().to_string().to_lowercase().contains(needle) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Accepts the formats thumbprints are usually copied in: plain hex, colon or space
+/// separated, with or without the invisible marks the Windows certificate dialog adds.
+fn parse_thumbprint(value: &str) -> Result<Vec<u8>, AppError> {
+    let invalid = || {
+        AppError::new(
+            ErrorKind::BadRequest,
+            format!("Invalid certificate thumbprint: {value}"),
+        )
+    };
+    if value
+        .chars()
+        .any(|c| c.is_alphanumeric() && !c.is_ascii_hexdigit())
+    {
+        return Err(invalid());
+    }
+    let digits: String = value.chars().filter(char::is_ascii_hexdigit).collect();
+    let bytes = hex::decode(digits).map_err(|_| invalid())?;
+    match bytes.len() {
+        20 | 32 => Ok(bytes),
+        _ => Err(invalid()),
+    }
+}
+
+/// When several identities match, prefer one that is currently valid and expires last.
+fn select_candidate<T>(candidates: Vec<(Vec<u8>, T)>) -> Option<(Vec<u8>, T)> {
+    candidates.into_iter().max_by_key(|(der, _)| {
+        X509Certificate::from_der(der)
+            .map(|(_, cert)| {
+                (
+                    cert.validity().is_valid(),
+                    cert.validity().not_after.timestamp(),
+                )
+            })
+            .unwrap_or((false, i64::MIN))
+    })
+}
+
+/// Public key type of a certificate, which determines the schemes its key can sign with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum KeyKind {
+    Rsa,
+    EcP256,
+    EcP384,
+}
+
+impl KeyKind {
+    fn from_certificate(der: &[u8]) -> Option<Self> {
+        let (_, cert) = X509Certificate::from_der(der).ok()?;
+        match cert.public_key().parsed().ok()? {
+            PublicKey::RSA(_) => Some(Self::Rsa),
+            // Uncompressed points: 0x04 || X || Y
+            PublicKey::EC(point) => match point.data().len() {
+                65 => Some(Self::EcP256),
+                97 => Some(Self::EcP384),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn schemes(self) -> &'static [SignatureScheme] {
+        match self {
+            Self::Rsa => &[
+                SignatureScheme::RSA_PSS_SHA256,
+                SignatureScheme::RSA_PSS_SHA384,
+                SignatureScheme::RSA_PSS_SHA512,
+                SignatureScheme::RSA_PKCS1_SHA256,
+                SignatureScheme::RSA_PKCS1_SHA384,
+                SignatureScheme::RSA_PKCS1_SHA512,
+            ],
+            Self::EcP256 => &[SignatureScheme::ECDSA_NISTP256_SHA256],
+            Self::EcP384 => &[SignatureScheme::ECDSA_NISTP384_SHA384],
+        }
+    }
+
+    fn algorithm(self) -> SignatureAlgorithm {
+        match self {
+            Self::Rsa => SignatureAlgorithm::RSA,
+            Self::EcP256 | Self::EcP384 => SignatureAlgorithm::ECDSA,
+        }
+    }
+}
+
+/// A private key that never leaves the OS key store; signing is delegated to the platform.
+pub(super) trait NativeKey: std::fmt::Debug + Send + Sync + 'static {
+    /// Sign `message` (unhashed) using `scheme`, returning the TLS wire encoding.
+    fn sign(&self, scheme: SignatureScheme, message: &[u8]) -> Result<Vec<u8>, String>;
+}
+
+#[derive(Debug)]
+pub(super) struct NativeSigningKey<K: NativeKey> {
+    key: Arc<K>,
+    kind: KeyKind,
+}
+
+impl<K: NativeKey> NativeSigningKey<K> {
+    fn new(key: K, kind: KeyKind) -> Self {
+        Self {
+            key: Arc::new(key),
+            kind,
+        }
+    }
+}
+
+impl<K: NativeKey> SigningKey for NativeSigningKey<K> {
+    fn choose_scheme(&self, offered: &[SignatureScheme]) -> Option<Box<dyn Signer>> {
+        let scheme = self
+            .kind
+            .schemes()
+            .iter()
+            .find(|scheme| offered.contains(scheme))?;
+        Some(Box::new(NativeSigner {
+            key: self.key.clone(),
+            scheme: *scheme,
+        }))
+    }
+
+    fn algorithm(&self) -> SignatureAlgorithm {
+        self.kind.algorithm()
+    }
+}
+
+#[derive(Debug)]
+struct NativeSigner<K: NativeKey> {
+    key: Arc<K>,
+    scheme: SignatureScheme,
+}
+
+impl<K: NativeKey> Signer for NativeSigner<K> {
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, rustls::Error> {
+        self.key
+            .sign(self.scheme, message)
+            .map_err(|e| rustls::Error::General(format!("client certificate signing failed: {e}")))
+    }
+
+    fn scheme(&self) -> SignatureScheme {
+        self.scheme
+    }
+}
+
+/// Convert a raw `r || s` ECDSA signature into the DER `Ecdsa-Sig-Value` used by TLS.
+#[cfg(any(target_os = "windows", test))]
+fn ecdsa_raw_to_der(raw: &[u8]) -> Vec<u8> {
+    fn integer(bytes: &[u8]) -> Vec<u8> {
+        let trimmed = match bytes.iter().position(|b| *b != 0) {
+            Some(start) => &bytes[start..],
+            None => &[0u8][..],
+        };
+        let mut out = vec![0x02];
+        let pad = trimmed[0] & 0x80 != 0;
+        out.push((trimmed.len() + usize::from(pad)) as u8);
+        if pad {
+            out.push(0);
+        }
+        out.extend_from_slice(trimmed);
+        out
+    }
+
+    let (r, s) = raw.split_at(raw.len() / 2);
+    let mut body = integer(r);
+    body.extend(integer(s));
+    let mut out = vec![0x30];
+    if body.len() >= 0x80 {
+        out.push(0x81);
+    }
+    out.push(body.len() as u8);
+    out.extend(body);
+    out
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::sync::Arc;
+
+    use rustls::SignatureScheme;
+    use rustls::pki_types::CertificateDer;
+    use rustls::sign::SigningKey;
+    use security_framework::item::{ItemClass, ItemSearchOptions, Limit, Reference, SearchResult};
+    use security_framework::key::{Algorithm, SecKey};
+
+    use super::{CertificateSelector, KeyKind, NativeKey, NativeSigningKey, select_candidate};
+    use crate::errors::{AppError, ErrorKind};
+
+    #[derive(Debug)]
+    struct KeychainKey(SecKey);
+
+    impl NativeKey for KeychainKey {
+        fn sign(&self, scheme: SignatureScheme, message: &[u8]) -> Result<Vec<u8>, String> {
+            let algorithm = match scheme {
+                SignatureScheme::RSA_PSS_SHA256 => Algorithm::RSASignatureMessagePSSSHA256,
+                SignatureScheme::RSA_PSS_SHA384 => Algorithm::RSASignatureMessagePSSSHA384,
+                SignatureScheme::RSA_PSS_SHA512 => Algorithm::RSASignatureMessagePSSSHA512,
+                SignatureScheme::RSA_PKCS1_SHA256 => Algorithm::RSASignatureMessagePKCS1v15SHA256,
+                SignatureScheme::RSA_PKCS1_SHA384 => Algorithm::RSASignatureMessagePKCS1v15SHA384,
+                SignatureScheme::RSA_PKCS1_SHA512 => Algorithm::RSASignatureMessagePKCS1v15SHA512,
+                SignatureScheme::ECDSA_NISTP256_SHA256 => {
+                    Algorithm::ECDSASignatureMessageX962SHA256
+                }
+                SignatureScheme::ECDSA_NISTP384_SHA384 => {
+                    Algorithm::ECDSASignatureMessageX962SHA384
+                }
+                other => return Err(format!("unsupported scheme {other:?}")),
+            };
+            self.0
+                .create_signature(algorithm, message)
+                .map_err(|e| e.to_string())
+        }
+    }
+
+    pub(super) fn find_identity(
+        selector: &CertificateSelector,
+    ) -> Result<(Vec<CertificateDer<'static>>, Arc<dyn SigningKey>), AppError> {
+        // An empty keychain search is reported as an error; treat it as no matches.
+        let results = ItemSearchOptions::new()
+            .class(ItemClass::identity())
+            .load_refs(true)
+            .limit(Limit::All)
+            .search()
+            .unwrap_or_default();
+
+        let candidates = results
+            .into_iter()
+            .filter_map(|result| match result {
+                SearchResult::Ref(Reference::Identity(identity)) => {
+                    let der = identity.certificate().ok()?.to_der();
+                    selector.matches(&der).then_some((der, identity))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let (der, identity) = select_candidate(candidates).ok_or_else(|| {
+            AppError::new(
+                ErrorKind::BadRequest,
+                "No matching client certificate with a private key found in the keychain",
+            )
+        })?;
+        let kind = KeyKind::from_certificate(&der).ok_or_else(|| {
+            AppError::new(
+                ErrorKind::BadRequest,
+                "Client certificate key type is not supported",
+            )
+        })?;
+        let key = identity.private_key().map_err(|e| {
+            AppError::new(
+                ErrorKind::PermissionDenied,
+                format!("Unable to access client certificate private key: {e}"),
+            )
+        })?;
+
+        Ok((
+            vec![CertificateDer::from(der)],
+            Arc::new(NativeSigningKey::new(KeychainKey(key), kind)),
+        ))
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::ptr;
+    use std::sync::Arc;
+
+    use rustls::SignatureScheme;
+    use rustls::pki_types::CertificateDer;
+    use rustls::sign::SigningKey;
+    use sha2::{Digest, Sha256, Sha384, Sha512};
+    use windows_sys::Win32::Security::Cryptography::{
+        BCRYPT_PAD_PKCS1, BCRYPT_PAD_PSS, BCRYPT_PKCS1_PADDING_INFO, BCRYPT_PSS_PADDING_INFO,
+        BCRYPT_SHA256_ALGORITHM, BCRYPT_SHA384_ALGORITHM, BCRYPT_SHA512_ALGORITHM, CERT_CONTEXT,
+        CERT_NCRYPT_KEY_SPEC, CERT_STORE_OPEN_EXISTING_FLAG, CERT_STORE_PROV_SYSTEM_W,
+        CERT_STORE_READONLY_FLAG, CERT_SYSTEM_STORE_CURRENT_USER,
+        CRYPT_ACQUIRE_ONLY_NCRYPT_KEY_FLAG, CertCloseStore, CertDuplicateCertificateContext,
+        CertEnumCertificatesInStore, CertFreeCertificateContext, CertOpenStore,
+        CryptAcquireCertificatePrivateKey, NCRYPT_KEY_HANDLE, NCryptFreeObject, NCryptSignHash,
+    };
+
+    use super::{
+        CertificateSelector, KeyKind, NativeKey, NativeSigningKey, ecdsa_raw_to_der,
+        select_candidate,
+    };
+    use crate::errors::{AppError, ErrorKind};
+
+    /// Owned reference to a certificate in the store.
+    struct CertContext(*const CERT_CONTEXT);
+
+    impl Drop for CertContext {
+        fn drop(&mut self) {
+            unsafe {
+                CertFreeCertificateContext(self.0);
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    struct NcryptKey {
+        handle: NCRYPT_KEY_HANDLE,
+        owned: bool,
+    }
+
+    impl Drop for NcryptKey {
+        fn drop(&mut self) {
+            if self.owned {
+                unsafe {
+                    NCryptFreeObject(self.handle);
+                }
+            }
+        }
+    }
+
+    impl NativeKey for NcryptKey {
+        fn sign(&self, scheme: SignatureScheme, message: &[u8]) -> Result<Vec<u8>, String> {
+            let (hash, alg_id) = match scheme {
+                SignatureScheme::RSA_PSS_SHA256
+                | SignatureScheme::RSA_PKCS1_SHA256
+                | SignatureScheme::ECDSA_NISTP256_SHA256 => {
+                    (Sha256::digest(message).to_vec(), BCRYPT_SHA256_ALGORITHM)
+                }
+                SignatureScheme::RSA_PSS_SHA384
+                | SignatureScheme::RSA_PKCS1_SHA384
+                | SignatureScheme::ECDSA_NISTP384_SHA384 => {
+                    (Sha384::digest(message).to_vec(), BCRYPT_SHA384_ALGORITHM)
+                }
+                SignatureScheme::RSA_PSS_SHA512 | SignatureScheme::RSA_PKCS1_SHA512 => {
+                    (Sha512::digest(message).to_vec(), BCRYPT_SHA512_ALGORITHM)
+                }
+                other => return Err(format!("unsupported scheme {other:?}")),
+            };
+
+            let pkcs1 = BCRYPT_PKCS1_PADDING_INFO { pszAlgId: alg_id };
+            let pss = BCRYPT_PSS_PADDING_INFO {
+                pszAlgId: alg_id,
+                cbSalt: hash.len() as u32,
+            };
+            let (padding, flags): (*const core::ffi::c_void, u32) = match scheme {
+                SignatureScheme::RSA_PSS_SHA256
+                | SignatureScheme::RSA_PSS_SHA384
+                | SignatureScheme::RSA_PSS_SHA512 => (ptr::from_ref(&pss).cast(), BCRYPT_PAD_PSS),
+                SignatureScheme::RSA_PKCS1_SHA256
+                | SignatureScheme::RSA_PKCS1_SHA384
+                | SignatureScheme::RSA_PKCS1_SHA512 => {
+                    (ptr::from_ref(&pkcs1).cast(), BCRYPT_PAD_PKCS1)
+                }
+                _ => (ptr::null(), 0),
+            };
+
+            let mut len = 0u32;
+            let status = unsafe {
+                NCryptSignHash(
+                    self.handle,
+                    padding,
+                    hash.as_ptr(),
+                    hash.len() as u32,
+                    ptr::null_mut(),
+                    0,
+                    &mut len,
+                    flags,
+                )
+            };
+            if status != 0 {
+                return Err(format!("NCryptSignHash failed: 0x{status:08X}"));
+            }
+            let mut signature = vec![0u8; len as usize];
+            let status = unsafe {
+                NCryptSignHash(
+                    self.handle,
+                    padding,
+                    hash.as_ptr(),
+                    hash.len() as u32,
+                    signature.as_mut_ptr(),
+                    len,
+                    &mut len,
+                    flags,
+                )
+            };
+            if status != 0 {
+                return Err(format!("NCryptSignHash failed: 0x{status:08X}"));
+            }
+            signature.truncate(len as usize);
+
+            // CNG returns ECDSA signatures as raw r || s
+            if flags == 0 {
+                Ok(ecdsa_raw_to_der(&signature))
+            } else {
+                Ok(signature)
+            }
+        }
+    }
+
+    pub(super) fn find_identity(
+        selector: &CertificateSelector,
+    ) -> Result<(Vec<CertificateDer<'static>>, Arc<dyn SigningKey>), AppError> {
+        let store_name: Vec<u16> = "MY".encode_utf16().chain(std::iter::once(0)).collect();
+        let store = unsafe {
+            CertOpenStore(
+                CERT_STORE_PROV_SYSTEM_W,
+                0,
+                0,
+                CERT_SYSTEM_STORE_CURRENT_USER
+                    | CERT_STORE_OPEN_EXISTING_FLAG
+                    | CERT_STORE_READONLY_FLAG,
+                store_name.as_ptr().cast(),
+            )
+        };
+        if store.is_null() {
+            return Err(AppError::new(
+                ErrorKind::IoError,
+                "Unable to open the current user certificate store",
+            ));
+        }
+
+        let mut candidates = Vec::new();
+        let mut context: *const CERT_CONTEXT = ptr::null();
+        loop {
+            context = unsafe { CertEnumCertificatesInStore(store, context) }.cast_const();
+            if context.is_null() {
+                break;
+            }
+            let der = unsafe {
+                std::slice::from_raw_parts(
+                    (*context).pbCertEncoded,
+                    (*context).cbCertEncoded as usize,
+                )
+            }
+            .to_vec();
+            if selector.matches(&der) {
+                let owned = unsafe { CertDuplicateCertificateContext(context) };
+                candidates.push((der, CertContext(owned)));
+            }
+        }
+
+        let selected = select_candidate(candidates);
+        let result = match selected {
+            Some((der, context)) => acquire_key(&der, &context).map(|key| (der, key)),
+            None => Err(AppError::new(
+                ErrorKind::BadRequest,
+                "No matching client certificate found in the current user certificate store",
+            )),
+        };
+        unsafe {
+            CertCloseStore(store, 0);
+        }
+
+        let (der, key) = result?;
+        Ok((vec![CertificateDer::from(der)], key))
+    }
+
+    fn acquire_key(der: &[u8], context: &CertContext) -> Result<Arc<dyn SigningKey>, AppError> {
+        let kind = KeyKind::from_certificate(der).ok_or_else(|| {
+            AppError::new(
+                ErrorKind::BadRequest,
+                "Client certificate key type is not supported",
+            )
+        })?;
+
+        let mut handle = 0;
+        let mut key_spec = 0;
+        let mut owned = 0;
+        let ok = unsafe {
+            CryptAcquireCertificatePrivateKey(
+                context.0,
+                CRYPT_ACQUIRE_ONLY_NCRYPT_KEY_FLAG,
+                ptr::null(),
+                &mut handle,
+                &mut key_spec,
+                &mut owned,
+            )
+        };
+        if ok == 0 || key_spec != CERT_NCRYPT_KEY_SPEC {
+            return Err(AppError::new(
+                ErrorKind::PermissionDenied,
+                "Unable to access client certificate private key",
+            ));
+        }
+
+        Ok(Arc::new(NativeSigningKey::new(
+            NcryptKey {
+                handle,
+                owned: owned != 0,
+            },
+            kind,
+        )))
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use std::sync::Arc;
+
+    use rustls::pki_types::CertificateDer;
+    use rustls::sign::SigningKey;
+
+    use super::CertificateSelector;
+    use crate::errors::{AppError, ErrorKind};
+
+    pub(super) fn find_identity(
+        _selector: &CertificateSelector,
+    ) -> Result<(Vec<CertificateDer<'static>>, Arc<dyn SigningKey>), AppError> {
+        Err(AppError::new(
+            ErrorKind::NotImplemented,
+            "System certificate store client certificates are only supported on Windows and macOS",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CertificateSelector, KeyKind, ecdsa_raw_to_der, parse_thumbprint};
+    use rustls::SignatureScheme;
+    use rustls_pemfile::certs;
+
+    const TEST_CERT: &str = "-----BEGIN CERTIFICATE-----
+MIIBrjCCAVWgAwIBAgIUMxVypol6JJXr6L6am/jRtXw/7WwwCgYIKoZIzj0EAwIw
+LDEaMBgGA1UEAwwRa251cmwtdGVzdC1jbGllbnQxDjAMBgNVBAoMBUtudXJsMCAX
+DTI2MTAxNjE1NTgxM1oYDzIxMjYwOTIyMTU1ODEzWjAsMRowGAYDVQQDDBFrbnVy
+bC10ZXN0LWNsaWVudDEOMAwGA1UECgwFS251cmwwWTATBgcqhkjOPQIBBggqhkjO
+PQMBBwNCAARovisUzhfb0GKPqql+p4V0EBlyLboDg3KiZWGLvLDjboESsHX+jv2i
+32doM2NdA3Js1/zn4G0zBfq3gSvodqIBo1MwUTAdBgNVHQ4EFgQUHcNUh+s3z+1W
+m/0OOlKpcig3M8EwHwYDVR0jBBgwFoAUHcNUh+s3z+1Wm/0OOlKpcig3M8EwDwYD
+VR0TAQH/BAUwAwEB/zAKBggqhkjOPQQDAgNHADBEAiAtkhvxKYpDqLeCx6Q+bEFJ
+L39Dq6V0Lt5T6nPgE4VuEQIgDrsBEO4XY98Vye+o1ZBPZRPwx47ul2GGXD67G2DH
+E7E=
+-----END CERTIFICATE-----
+";
+
+    fn test_cert_der() -> Vec<u8> {
+        certs(&mut TEST_CERT.as_bytes())
+            .next()
+            .expect("certificate")
+            .expect("valid pem")
+            .to_vec()
+    }
+
+    #[test]
+    fn parses_thumbprint_formats() {
+        let sha1 = "56:9D:4E:81:B5:C9:4C:56:50:DA:65:E6:30:B2:44:DE:8E:1A:8B:91";
+        assert_eq!(parse_thumbprint(sha1).unwrap().len(), 20);
+        let spaced = "\u{200e}56 9d 4e 81 b5 c9 4c 56 50 da 65 e6 30 b2 44 de 8e 1a 8b 91";
+        assert_eq!(
+            parse_thumbprint(spaced).unwrap(),
+            parse_thumbprint(sha1).unwrap()
+        );
+        assert!(parse_thumbprint("abcd").is_err());
+        assert!(parse_thumbprint("zz9D4E81B5C94C5650DA65E630B244DE8E1A8B91").is_err());
+    }
+
+    #[test]
+    fn selector_matches_thumbprint_and_subject() {
+        let der = test_cert_der();
+        let sha1 = "569D4E81B5C94C5650DA65E630B244DE8E1A8B91";
+        let sha256 = "D53C82F26E88C4ECFE5B6A765A2FE9B6306C3204E8A19C6B78AF39FE1F5E4B41";
+
+        let by_sha1 = CertificateSelector::new(Some(sha1), None).unwrap();
+        assert!(by_sha1.matches(&der));
+        let by_sha256 = CertificateSelector::new(Some(sha256), None).unwrap();
+        assert!(by_sha256.matches(&der));
+        let by_subject = CertificateSelector::new(None, Some("KNURL-TEST")).unwrap();
+        assert!(by_subject.matches(&der));
+        let mismatch = CertificateSelector::new(Some(sha1), Some("other")).unwrap();
+        assert!(!mismatch.matches(&der));
+        assert!(CertificateSelector::new(None, Some("  ")).is_err());
+    }
+
+    #[test]
+    fn detects_key_kind_and_schemes() {
+        let kind = KeyKind::from_certificate(&test_cert_der());
+        assert_eq!(kind, Some(KeyKind::EcP256));
+        assert_eq!(
+            KeyKind::EcP256.schemes(),
+            &[SignatureScheme::ECDSA_NISTP256_SHA256]
+        );
+    }
+
+    #[test]
+    fn encodes_raw_ecdsa_signature_as_der() {
+        let mut raw = vec![0u8; 64];
+        raw[0] = 0x80;
+        raw[31] = 0x01;
+        raw[63] = 0x02;
+        let der = ecdsa_raw_to_der(&raw);
+        assert_eq!(der[0], 0x30);
+        assert_eq!(der[1] as usize, der.len() - 2);
+        // r has its high bit set and gains a leading zero; s trims to one byte
+        assert_eq!(&der[2..5], &[0x02, 33, 0x00]);
+        assert_eq!(&der[der.len() - 3..], &[0x02, 1, 0x02]);
+    }
+}
@@ -0,0 +1,118 @@
+use std::io::Write;
+
+use bytes::Bytes;
+use flate2::Compression;
+use flate2::write::{GzEncoder, ZlibEncoder};
+use hyper::http::{HeaderMap, HeaderValue};
+
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::request::ContentEncoding;
+
+const ZSTD_LEVEL: i32 = 3;
+
+impl ContentEncoding {
+    /// Token used in the `Content-Encoding` header
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentEncoding::Gzip => "gzip",
+            ContentEncoding::Deflate => "deflate",
+            ContentEncoding::Zstd => "zstd",
+        }
+    }
+}
+
+fn compress(body: &[u8], encoding: ContentEncoding) -> std::io::Result<Vec<u8>> {
+    match encoding {
+        ContentEncoding::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(body)?;
+            encoder.finish()
+        }
+        // HTTP "deflate" is the zlib format (RFC 9110 section 8.4.1.2), not raw deflate
+        ContentEncoding::Deflate => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(body)?;
+            encoder.finish()
+        }
+        ContentEncoding::Zstd => zstd::encode_all(body, ZSTD_LEVEL),
+    }
+}
+
+/// Compresses `body` and sets the matching `Content-Encoding` header, replacing any
+/// value set by the user. Empty bodies are sent as-is without the header.
+pub(super) fn encode_body(
+    body: Bytes,
+    encoding: ContentEncoding,
+    headers: &mut HeaderMap,
+) -> Result<Bytes, AppError> {
+    if body.is_empty() {
+        return Ok(body);
+    }
+    let compressed = compress(&body, encoding).map_err(|e| {
+        AppError::new(
+            ErrorKind::IoError,
+            format!("Failed to {} encode request body: {e}", encoding.as_str()),
+        )
+    })?;
+    headers.insert(
+        hyper::header::CONTENT_ENCODING,
+        HeaderValue::from_static(encoding.as_str()),
+    );
+    // Any user supplied length describes the uncompressed body; let hyper compute it
+    headers.remove(hyper::header::CONTENT_LENGTH);
+    Ok(Bytes::from(compressed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::encode_body;
+    use crate::http_client::request::ContentEncoding;
+    use bytes::Bytes;
+    use flate2::read::{GzDecoder, ZlibDecoder};
+    use hyper::http::{HeaderMap, HeaderValue};
+    use std::io::Read;
+
+    const BODY: &[u8] = br#"{"items":[1,2,3,4,5,6,7,8,9,10],"note":"compress me, compress me"}"#;
+
+    fn encode(encoding: ContentEncoding) -> (Vec<u8>, HeaderMap) {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            hyper::header::CONTENT_LENGTH,
+            HeaderValue::from_static("66"),
+        );
+        let encoded = encode_body(Bytes::from_static(BODY), encoding, &mut headers).unwrap();
+        (encoded.to_vec(), headers)
+    }
+
+    #[test]
+    fn round_trips_each_encoding() {
+        let (gzip, headers) = encode(ContentEncoding::Gzip);
+        let mut decoded = Vec::new();
+        GzDecoder::new(gzip.as_slice())
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, BODY);
+        assert_eq!(headers[hyper::header::CONTENT_ENCODING], "gzip");
+        assert!(!headers.contains_key(hyper::header::CONTENT_LENGTH));
+
+        let (deflate, headers) = encode(ContentEncoding::Deflate);
+        let mut decoded = Vec::new();
+        ZlibDecoder::new(deflate.as_slice())
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, BODY);
+        assert_eq!(headers[hyper::header::CONTENT_ENCODING], "deflate");
+
+        let (zstd, headers) = encode(ContentEncoding::Zstd);
+        assert_eq!(zstd::decode_all(zstd.as_slice()).unwrap(), BODY);
+        assert_eq!(headers[hyper::header::CONTENT_ENCODING], "zstd");
+    }
+
+    #[test]
+    fn leaves_empty_body_untouched() {
+        let mut headers = HeaderMap::new();
+        let encoded = encode_body(Bytes::new(), ContentEncoding::Gzip, &mut headers).unwrap();
+        assert!(encoded.is_empty());
+        assert!(headers.is_empty());
+    }
+}
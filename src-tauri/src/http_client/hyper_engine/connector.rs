@@ -1,24 +1,31 @@
+use std::collections::HashMap;
 use std::fs;
 use std::future::Future;
 use std::io;
 use std::net::{IpAddr, SocketAddr};
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, Once};
 use std::task::{Context, Poll};
+use std::time::SystemTime;
 use std::time::{Duration, Instant};
 
 use base64::Engine;
 use base64::engine::general_purpose::STANDARD as Base64;
 use hex::encode as hex_encode;
 use hyper::http::Uri;
+use hyper::rt::{Read, ReadBufCursor, Write};
 use hyper_rustls::HttpsConnectorBuilder;
-use hyper_util::client::legacy::connect::HttpConnector;
 use hyper_util::client::legacy::connect::dns::{GaiResolver, Name};
+use hyper_util::client::legacy::connect::{Connected, Connection, HttpConnector};
 use hyper_util::rt::TokioIo;
+use rustls::client::WebPkiServerVerifier;
 use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::CryptoProvider;
 use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::sign::{CertifiedKey, SingleCertAndKey};
 use rustls::{
     ClientConfig, ClientConnection, DigitallySignedStruct, RootCertStore, SignatureScheme,
+    SupportedCipherSuite, SupportedProtocolVersion,
 };
 use rustls_pemfile::certs;
 #[cfg(target_os = "windows")]
@@ -35,8 +42,17 @@ use x509_parser::public_key::PublicKey;
 use x509_parser::x509::SubjectPublicKeyInfo;
 
 use super::RequestLogger;
+use super::client_cert::load_client_certificate;
+use super::dns_cache;
+use super::insecure::AllowlistVerifier;
+use super::revocation::{self, RevocationPolicy, RevocationReport};
+use super::timings::{Mark, TimedConnector};
 use crate::errors::{AppError, ErrorKind};
-use crate::http_client::request::{HttpVersionPref, Request};
+use crate::http_client::request::{HttpVersionPref, InsecureHost, IpFamily, Request, TlsVersion};
+
+/// How long a connection attempt gets before the other address family joins the race, as
+/// RFC 8305 recommends
+const HAPPY_EYEBALLS_DELAY: Duration = Duration::from_millis(250);
 
 type HttpsStream = hyper_rustls::MaybeHttpsStream<TokioIo<TcpStream>>;
 
@@ -45,16 +61,66 @@ pub(super) fn build_connector(
     request: &Request,
     uri: &Uri,
     logger: RequestLogger,
-) -> Result<LoggingConnector<hyper_rustls::HttpsConnector<HttpConnector<OverrideResolver>>>, AppError>
-{
+) -> Result<
+    LoggingConnector<hyper_rustls::HttpsConnector<TimedConnector<HttpConnector<OverrideResolver>>>>,
+    AppError,
+> {
     if uri.host().is_none() {
         return Err(AppError::new(ErrorKind::BadRequest, "URL missing host"));
     }
 
-    let tls_config = build_tls_config(
-        request.disable_ssl.unwrap_or(false),
-        request.ca_path.as_deref(),
-    )?;
+    let client_cert = request
+        .client_certificate
+        .as_ref()
+        .map(|source| load_client_certificate(source, &logger))
+        .transpose()?;
+
+    let versions = tls_versions(request.tls_min_version, request.tls_max_version)?;
+    if request.tls_min_version.is_some() || request.tls_max_version.is_some() {
+        let names: Vec<&str> = versions
+            .iter()
+            .map(|version| version_name(version))
+            .collect();
+        logger.info(
+            "tls",
+            Some("versions"),
+            format!("TLS versions limited to {}", names.join(", ")),
+            Some(json!({"versions": names})),
+        );
+    }
+    let cipher_suites = request
+        .cipher_suites
+        .as_deref()
+        .filter(|names| !names.is_empty());
+    if let Some(names) = cipher_suites {
+        logger.info(
+            "tls",
+            Some("cipher_suites"),
+            format!("TLS cipher suites limited to {}", names.join(", ")),
+            Some(json!({"cipherSuites": names})),
+        );
+    }
+    let insecure_hosts = request.insecure_hosts.as_deref().unwrap_or_default();
+    if !insecure_hosts.is_empty() && !request.disable_ssl.unwrap_or(false) {
+        let hosts: Vec<&str> = insecure_hosts
+            .iter()
+            .map(|entry| entry.host.as_str())
+            .collect();
+        logger.warn(
+            "tls",
+            Some("insecure_hosts"),
+            format!("Certificate verification relaxed for {}", hosts.join(", ")),
+            Some(json!({"insecureHosts": insecure_hosts})),
+        );
+    }
+    let tls_config = build_tls_config(TlsSettings {
+        disable_verification: request.disable_ssl.unwrap_or(false),
+        custom_ca: request.ca_path.as_deref(),
+        client_cert,
+        versions: &versions,
+        cipher_suites,
+        insecure_hosts,
+    })?;
 
     // Preference handled below after building DNS connector
 
@@ -64,43 +130,56 @@ pub(super) fn build_connector(
         .unwrap_or(80);
     let host = uri.host().expect("host is checked above").to_string();
 
-    let override_ip = request
-        .ip_override
-        .as_ref()
-        .and_then(|value| {
-            if value.trim().is_empty() {
-                None
-            } else {
-                Some(value)
-            }
-        })
-        .map(|value| {
-            value.parse::<IpAddr>().map_err(|e| {
-                AppError::new(ErrorKind::BadRequest, format!("Invalid IP override: {e}"))
-            })
-        })
-        .transpose()?;
-
-    let override_socket = override_ip.map(|ip| SocketAddr::new(ip, port));
-
-    if let Some(socket) = override_socket {
+    let overrides = dns_overrides(request, &host)?;
+    for (name, ip) in &overrides {
         logger.info(
             "dns",
             Some("override"),
-            format!("Applying DNS override for {host}:{port} -> {socket}"),
+            format!("Applying DNS override for {name} -> {ip}"),
             Some(json!({
-                "host": host,
+                "host": name,
                 "port": port,
-                "ip": socket.ip().to_string(),
+                "ip": ip.to_string(),
             })),
         );
     }
 
-    let resolver = OverrideResolver::new(host.clone(), override_socket, logger.clone());
+    let local_address = local_address(request)?;
+    let family = match (request.ip_family.unwrap_or_default(), local_address) {
+        (IpFamily::Auto, Some(IpAddr::V4(_))) => IpFamily::Ipv4,
+        (IpFamily::Auto, Some(IpAddr::V6(_))) => IpFamily::Ipv6,
+        (family, _) => family,
+    };
+    let resolver = OverrideResolver::new(Arc::new(overrides), family, logger.clone());
 
     let mut http = HttpConnector::new_with_resolver(resolver);
     http.enforce_http(false);
+    http.set_happy_eyeballs_timeout(Some(HAPPY_EYEBALLS_DELAY));
+    if let Some(ip) = local_address {
+        logger.info(
+            "connect",
+            Some("bind"),
+            format!("Connecting from local address {ip}"),
+            Some(json!({"localAddress": ip.to_string()})),
+        );
+        http.set_local_address(Some(ip));
+    }
+    if let Some(interface) = request
+        .interface
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        bind_interface(&mut http, interface)?;
+        logger.info(
+            "connect",
+            Some("bind"),
+            format!("Connecting through interface {interface}"),
+            Some(json!({"interface": interface})),
+        );
+    }
     http.set_connect_timeout(Some(Duration::from_secs(10)));
+    let http = TimedConnector::new(http, logger.clone());
 
     // Configure ALPN and HTTP protocol enablement based on preference
     let preference = request
@@ -150,7 +229,88 @@ pub(super) fn build_connector(
         }
     };
 
-    Ok(LoggingConnector::new(connector, logger))
+    let revocation = match (request.check_revocation, request.disable_ssl) {
+        (Some(true), Some(true)) => RevocationPolicy::Report,
+        (Some(true), _) => RevocationPolicy::Enforce,
+        _ => RevocationPolicy::Off,
+    };
+    Ok(LoggingConnector::new(connector, logger, revocation))
+}
+
+/// The request's DNS overrides by lowercase host name. `ip_override` applies to `url_host`,
+/// unless the table lists that host too.
+pub(super) fn dns_overrides(
+    request: &Request,
+    url_host: &str,
+) -> Result<HashMap<String, IpAddr>, AppError> {
+    let parse = |host: &str, ip: &str| {
+        ip.trim().parse::<IpAddr>().map_err(|e| {
+            AppError::new(
+                ErrorKind::BadRequest,
+                format!("Invalid IP override for {host}: {e}"),
+            )
+        })
+    };
+    let mut overrides = HashMap::new();
+    if let Some(ip) = request.ip_override.as_deref()
+        && !ip.trim().is_empty()
+    {
+        overrides.insert(url_host.to_ascii_lowercase(), parse(url_host, ip)?);
+    }
+    for entry in request.dns_overrides.iter().flatten() {
+        let host = entry.host.trim().trim_end_matches('.');
+        if host.is_empty() {
+            continue;
+        }
+        overrides.insert(host.to_ascii_lowercase(), parse(host, &entry.ip)?);
+    }
+    Ok(overrides)
+}
+
+/// The local IP the request connects from, if it names one. It must match the address family
+/// the request asks for.
+fn local_address(request: &Request) -> Result<Option<IpAddr>, AppError> {
+    let Some(value) = request
+        .local_address
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+    else {
+        return Ok(None);
+    };
+    let ip = value.parse::<IpAddr>().map_err(|e| {
+        AppError::new(
+            ErrorKind::BadRequest,
+            format!("Invalid local address '{value}': {e}"),
+        )
+    })?;
+    match (request.ip_family.unwrap_or_default(), ip) {
+        (IpFamily::Ipv4, IpAddr::V6(_)) | (IpFamily::Ipv6, IpAddr::V4(_)) => Err(AppError::new(
+            ErrorKind::BadRequest,
+            format!("Local address {ip} doesn't match the requested IP family"),
+        )),
+        _ => Ok(Some(ip)),
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn bind_interface<R>(http: &mut HttpConnector<R>, interface: &str) -> Result<(), AppError> {
+    if interface.contains('\0') {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            "Invalid interface name",
+        ));
+    }
+    http.set_interface(interface);
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn bind_interface<R>(_http: &mut HttpConnector<R>, _interface: &str) -> Result<(), AppError> {
+    Err(AppError::new(
+        ErrorKind::BadRequest,
+        "Binding to a network interface isn't supported on Windows; use a local address",
+    ))
 }
 
 /// Extract a sanitized host header value from the override string, falling back to the URL host.
@@ -194,25 +354,70 @@ fn default_port_for_scheme(scheme: Option<&str>) -> Option<u16> {
 
 #[derive(Clone)]
 pub(super) struct OverrideResolver {
-    target_host: String,
-    override_socket: Option<SocketAddr>,
+    overrides: Arc<HashMap<String, IpAddr>>,
+    family: IpFamily,
     logger: RequestLogger,
 }
 
 impl OverrideResolver {
     fn new(
-        target_host: String,
-        override_socket: Option<SocketAddr>,
+        overrides: Arc<HashMap<String, IpAddr>>,
+        family: IpFamily,
         logger: RequestLogger,
     ) -> Self {
         Self {
-            target_host,
-            override_socket,
+            overrides,
+            family,
             logger,
         }
     }
 }
 
+/// Keeps the addresses of `family`. With both families in auto mode, the connector tries the
+/// first address's family and races the other one against it after a short delay.
+fn select_family(
+    addrs: Vec<SocketAddr>,
+    family: IpFamily,
+    host: &str,
+    logger: &RequestLogger,
+) -> io::Result<Vec<SocketAddr>> {
+    let wanted: fn(&SocketAddr) -> bool = match family {
+        IpFamily::Auto => {
+            if let Some(first) = addrs.first()
+                && let Some(other) = addrs.iter().find(|addr| addr.is_ipv4() != first.is_ipv4())
+            {
+                logger.debug(
+                    "connect",
+                    Some("happy_eyeballs"),
+                    format!("Racing {} against {}", first.ip(), other.ip()),
+                    Some(json!({
+                        "host": host,
+                        "preferred": first.ip().to_string(),
+                        "fallback": other.ip().to_string(),
+                        "fallbackDelayMs": HAPPY_EYEBALLS_DELAY.as_millis(),
+                    })),
+                );
+            }
+            return Ok(addrs);
+        }
+        IpFamily::Ipv4 => SocketAddr::is_ipv4,
+        IpFamily::Ipv6 => SocketAddr::is_ipv6,
+    };
+    let selected: Vec<SocketAddr> = addrs.into_iter().filter(wanted).collect();
+    if selected.is_empty() {
+        let name = if family == IpFamily::Ipv4 {
+            "IPv4"
+        } else {
+            "IPv6"
+        };
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{host} has no {name} address"),
+        ));
+    }
+    Ok(selected)
+}
+
 impl Service<Name> for OverrideResolver {
     type Response = std::vec::IntoIter<SocketAddr>;
     type Error = io::Error;
@@ -224,13 +429,16 @@ impl Service<Name> for OverrideResolver {
     }
 
     fn call(&mut self, name: Name) -> Self::Future {
-        let override_socket = self.override_socket;
-        let target_host = self.target_host.clone();
-        let logger = self.logger.clone();
+        let overrides = self.overrides.clone();
+        let family = self.family;
+        let logger = self.logger.for_current_request();
         let lookup = name.to_string();
+        let host = lookup.clone();
+        let family_logger = logger.clone();
 
-        Box::pin(async move {
+        let resolve = Box::pin(async move {
             let start = Instant::now();
+            logger.timer().mark(Mark::DnsStart);
             logger.debug(
                 "dns",
                 Some("lookup"),
@@ -238,27 +446,43 @@ impl Service<Name> for OverrideResolver {
                 Some(json!({"host": lookup})),
             );
 
-            if let Some(socket) = override_socket
-                && lookup.eq_ignore_ascii_case(&target_host)
-            {
+            if let Some(ip) = overrides.get(&lookup.to_ascii_lowercase()).copied() {
                 logger.info(
                     "dns",
                     Some("override_hit"),
-                    format!("DNS override hit: {lookup} -> {socket}"),
+                    format!("DNS override hit: {lookup} -> {ip}"),
+                    Some(json!({
+                        "host": lookup,
+                        "ip": ip.to_string(),
+                    })),
+                );
+                logger.timer().mark(Mark::DnsEnd);
+                // Port 0 has the connector fill in the destination's port
+                return Ok(vec![SocketAddr::new(ip, 0)].into_iter());
+            }
+
+            if let Some((results, left)) = dns_cache::lookup(&lookup) {
+                logger.timer().mark(Mark::DnsEnd);
+                logger.info(
+                    "dns",
+                    Some("cache_hit"),
+                    format!("DNS cache hit: {lookup}"),
                     Some(json!({
                         "host": lookup,
-                        "ip": socket.ip().to_string(),
-                        "port": socket.port(),
+                        "addresses": results.iter().map(|addr| addr.ip().to_string()).collect::<Vec<_>>(),
+                        "expiresInSecs": left.as_secs(),
                     })),
                 );
-                return Ok(vec![socket].into_iter());
+                return Ok(results.into_iter());
             }
 
             let mut resolver = GaiResolver::new();
 
             match resolver.call(name).await {
                 Ok(addrs) => {
+                    logger.timer().mark(Mark::DnsEnd);
                     let results: Vec<SocketAddr> = addrs.collect();
+                    dns_cache::store(&lookup, &results);
                     let elapsed = start.elapsed().as_millis();
                     let ipv4: Vec<String> = results
                         .iter()
@@ -335,27 +559,50 @@ impl Service<Name> for OverrideResolver {
                     Err(err)
                 }
             }
+        });
+
+        Box::pin(async move {
+            let addrs = resolve.await?.collect();
+            Ok(select_family(addrs, family, &host, &family_logger)?.into_iter())
         })
     }
 }
 
-fn build_tls_config(
-    disable_verification: bool,
-    custom_ca: Option<&str>,
-) -> Result<ClientConfig, AppError> {
-    // Load OS trust store first; fall back to webpki roots if unavailable or empty.
+/// Installs ring as the process's rustls crypto provider. Done once, on first use or by the
+/// startup preload, whichever comes first.
+pub fn install_crypto_provider() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        // Fails only if another provider was installed first, which is as good
+        let _ = rustls::crypto::ring::default_provider().install_default();
+    });
+}
+
+/// Identifies a custom CA bundle's contents without reading it: a bundle edited in place gets
+/// a new modification time, so its store is rebuilt.
+type BundleStamp = (String, Option<SystemTime>);
+
+/// Parsed root stores, built once instead of for every request. Reading the OS trust store is
+/// slow (notably on Windows), so the base store is built by the startup preload or else the
+/// first TLS request, and kept until [`refresh_root_store`]. Custom CA bundles are layered over
+/// the base store, one store per bundle.
+#[derive(Default)]
+struct RootStores {
+    base: Option<Arc<RootCertStore>>,
+    layered: HashMap<BundleStamp, Arc<RootCertStore>>,
+}
+
+static ROOT_STORES: Mutex<Option<RootStores>> = Mutex::new(None);
+
+/// Loads the OS trust store, falling back to the webpki roots if it's unavailable or empty.
+fn load_base_roots() -> RootCertStore {
     let mut roots = RootCertStore::empty();
     // rustls-native-certs 0.8 returns a CertificateResult with accessors
-    // Iterate certificates if available and add them to the root store.
     let native = rustls_native_certs::load_native_certs();
-    let (added_native, ignored_native) = roots.add_parsable_certificates(native.certs);
-
-    if !native.errors.is_empty() {
-        for error in native.errors {
-            log::debug!("tls-certstore: native certificate load error: {error}");
-        }
+    for error in native.errors {
+        log::debug!("tls-certstore: native certificate load error: {error}");
     }
-
+    let (added_native, ignored_native) = roots.add_parsable_certificates(native.certs);
     log::debug!("tls-certstore: added {added_native} native roots (ignored {ignored_native})");
 
     if added_native == 0 {
@@ -364,44 +611,212 @@ fn build_tls_config(
         );
         roots.extend(TLS_SERVER_ROOTS.iter().cloned());
     }
+    roots
+}
 
-    if let Some(path) = custom_ca {
-        let data = fs::read(path).map_err(|e| {
-            AppError::new(ErrorKind::IoError, format!("Failed to read CA bundle: {e}"))
-        })?;
-        let mut reader = std::io::Cursor::new(data);
-        let certificates = certs(&mut reader)
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| AppError::new(ErrorKind::BadRequest, format!("Invalid CA bundle: {e}")))?;
-        let (added, _) = roots.add_parsable_certificates(certificates);
-        if added == 0 {
-            return Err(AppError::new(
-                ErrorKind::BadRequest,
-                "No valid certificates found in custom CA bundle",
-            ));
+fn layer_bundle(base: &RootCertStore, path: &str) -> Result<RootCertStore, AppError> {
+    let data = fs::read(path)
+        .map_err(|e| AppError::new(ErrorKind::IoError, format!("Failed to read CA bundle: {e}")))?;
+    let mut reader = std::io::Cursor::new(data);
+    let certificates = certs(&mut reader)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::new(ErrorKind::BadRequest, format!("Invalid CA bundle: {e}")))?;
+    let mut roots = base.clone();
+    let (added, _) = roots.add_parsable_certificates(certificates);
+    if added == 0 {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            "No valid certificates found in custom CA bundle",
+        ));
+    }
+    log::debug!("tls-certstore: added {added} certificates from custom CA bundle");
+    Ok(roots)
+}
+
+/// The root store to verify servers with: the OS trust store, plus the certificates of
+/// `custom_ca` when set.
+pub fn root_store(custom_ca: Option<&str>) -> Result<Arc<RootCertStore>, AppError> {
+    // Held while loading, so concurrent first requests load the trust store once
+    let mut guard = ROOT_STORES.lock().unwrap();
+    let stores = guard.get_or_insert_with(RootStores::default);
+    let base = stores
+        .base
+        .get_or_insert_with(|| Arc::new(load_base_roots()))
+        .clone();
+    let Some(path) = custom_ca else {
+        return Ok(base);
+    };
+
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+    let stamp = (path.to_string(), modified);
+    if let Some(roots) = stores.layered.get(&stamp) {
+        return Ok(roots.clone());
+    }
+    let roots = Arc::new(layer_bundle(&base, path)?);
+    // Drop the store of the bundle's previous contents
+    stores.layered.retain(|(cached, _), _| cached != path);
+    stores.layered.insert(stamp, roots.clone());
+    Ok(roots)
+}
+
+/// Drops the cached root stores and reloads the OS trust store, e.g. after installing a
+/// certificate in it. Returns how many roots the reloaded store has.
+pub fn refresh_root_store() -> usize {
+    ROOT_STORES.lock().unwrap().take();
+    root_store(None).map_or(0, |roots| roots.len())
+}
+
+/// The TLS versions from `min` through `max`, both included.
+pub(crate) fn tls_versions(
+    min: Option<TlsVersion>,
+    max: Option<TlsVersion>,
+) -> Result<Vec<&'static SupportedProtocolVersion>, AppError> {
+    let min = min.unwrap_or(TlsVersion::Tls12);
+    let max = max.unwrap_or(TlsVersion::Tls13);
+    if min > max {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            format!(
+                "TLS minimum version {} is newer than the maximum {}",
+                min.as_str(),
+                max.as_str()
+            ),
+        ));
+    }
+    Ok([
+        (TlsVersion::Tls12, &rustls::version::TLS12),
+        (TlsVersion::Tls13, &rustls::version::TLS13),
+    ]
+    .into_iter()
+    .filter(|(version, _)| (min..=max).contains(version))
+    .map(|(_, supported)| supported)
+    .collect())
+}
+
+fn version_name(version: &SupportedProtocolVersion) -> &'static str {
+    match version.version {
+        rustls::ProtocolVersion::TLSv1_2 => "TLS 1.2",
+        rustls::ProtocolVersion::TLSv1_3 => "TLS 1.3",
+        _ => "TLS",
+    }
+}
+
+/// IANA name of a cipher suite; rustls names TLS 1.3 suites `TLS13_*`
+pub(crate) fn cipher_suite_name(suite: &SupportedCipherSuite) -> String {
+    let name = format!("{:?}", suite.suite());
+    match name.strip_prefix("TLS13_") {
+        Some(rest) => format!("TLS_{rest}"),
+        None => name,
+    }
+}
+
+/// The ring provider, offering only the `cipher_suites` named, in their order, when given.
+fn crypto_provider(cipher_suites: Option<&[String]>) -> Result<CryptoProvider, AppError> {
+    let mut provider = rustls::crypto::ring::default_provider();
+    let Some(names) = cipher_suites else {
+        return Ok(provider);
+    };
+    let supported = std::mem::take(&mut provider.cipher_suites);
+    for name in names {
+        let wanted = name.trim().to_ascii_uppercase().replace("TLS13_", "TLS_");
+        let suite = supported
+            .iter()
+            .find(|suite| cipher_suite_name(suite) == wanted)
+            .ok_or_else(|| {
+                let known: Vec<String> = supported.iter().map(cipher_suite_name).collect();
+                AppError::new(
+                    ErrorKind::BadRequest,
+                    format!(
+                        "Unsupported cipher suite '{name}'; supported are {}",
+                        known.join(", ")
+                    ),
+                )
+            })?;
+        if !provider.cipher_suites.contains(suite) {
+            provider.cipher_suites.push(*suite);
         }
-        log::debug!("tls-certstore: added {added} certificates from custom CA bundle");
     }
+    Ok(provider)
+}
 
-    let mut config = ClientConfig::builder()
-        .with_root_certificates(roots)
-        .with_no_client_auth();
+/// What a client TLS config is built for
+pub(crate) struct TlsSettings<'a> {
+    /// Skip certificate verification altogether
+    pub disable_verification: bool,
+    /// Path of a PEM bundle of roots trusted besides the OS's
+    pub custom_ca: Option<&'a str>,
+    pub client_cert: Option<Arc<CertifiedKey>>,
+    pub versions: &'a [&'static SupportedProtocolVersion],
+    /// Names of the only cipher suites to offer
+    pub cipher_suites: Option<&'a [String]>,
+    /// Hosts verified less strictly, unless verification is disabled anyway
+    pub insecure_hosts: &'a [InsecureHost],
+}
+
+impl Default for TlsSettings<'_> {
+    fn default() -> Self {
+        Self {
+            disable_verification: false,
+            custom_ca: None,
+            client_cert: None,
+            versions: rustls::DEFAULT_VERSIONS,
+            cipher_suites: None,
+            insecure_hosts: &[],
+        }
+    }
+}
 
-    if disable_verification {
+pub(crate) fn build_tls_config(settings: TlsSettings<'_>) -> Result<ClientConfig, AppError> {
+    install_crypto_provider();
+
+    let provider = Arc::new(crypto_provider(settings.cipher_suites)?);
+    let roots = root_store(settings.custom_ca)?;
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_protocol_versions(settings.versions)
+        .map_err(|e| AppError::new(ErrorKind::BadRequest, format!("Unusable TLS settings: {e}")))?
+        .with_root_certificates(roots.clone());
+    let mut config = match settings.client_cert {
+        Some(certified) => {
+            builder.with_client_cert_resolver(Arc::new(SingleCertAndKey::from(certified)))
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    if settings.disable_verification {
         config
             .dangerous()
             .set_certificate_verifier(Arc::new(NoVerifier));
-    } else {
-        #[cfg(target_os = "windows")]
-        {
-            if custom_ca.is_none() {
-                log::debug!("tls-certstore: enabling Windows platform verifier");
-                config
-                    .dangerous()
-                    .set_certificate_verifier(Arc::new(PlatformVerifier::new()));
-            }
+        return Ok(config);
+    }
+
+    let mut verifier: Option<Arc<dyn ServerCertVerifier>> = None;
+    #[cfg(target_os = "windows")]
+    {
+        if settings.custom_ca.is_none() {
+            log::debug!("tls-certstore: enabling Windows platform verifier");
+            verifier = Some(Arc::new(PlatformVerifier::new()));
         }
     }
+    if !settings.insecure_hosts.is_empty() {
+        let inner = match verifier {
+            Some(verifier) => verifier,
+            None => WebPkiServerVerifier::builder_with_provider(roots, provider)
+                .build()
+                .map_err(|e| {
+                    AppError::new(
+                        ErrorKind::BadRequest,
+                        format!("Failed to build certificate verifier: {e}"),
+                    )
+                })?,
+        };
+        verifier = Some(Arc::new(AllowlistVerifier::new(
+            inner,
+            settings.insecure_hosts.to_vec(),
+        )));
+    }
+    if let Some(verifier) = verifier {
+        config.dangerous().set_certificate_verifier(verifier);
+    }
 
     Ok(config)
 }
@@ -458,11 +873,73 @@ impl ServerCertVerifier for NoVerifier {
 pub(super) struct LoggingConnector<C> {
     inner: C,
     logger: RequestLogger,
+    revocation: RevocationPolicy,
 }
 
 impl<C> LoggingConnector<C> {
-    fn new(inner: C, logger: RequestLogger) -> Self {
-        Self { inner, logger }
+    fn new(inner: C, logger: RequestLogger, revocation: RevocationPolicy) -> Self {
+        Self {
+            inner,
+            logger,
+            revocation,
+        }
+    }
+}
+
+/// A connection made by `LoggingConnector`, which hands the revocation status of the
+/// server's chain to each response it carries
+pub(super) struct EngineStream {
+    inner: HttpsStream,
+    revocation: Option<RevocationReport>,
+}
+
+impl Read for EngineStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: ReadBufCursor<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl Write for EngineStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+impl Connection for EngineStream {
+    fn connected(&self) -> Connected {
+        let connected = self.inner.connected();
+        match &self.revocation {
+            Some(report) => connected.extra(report.clone()),
+            None => connected,
+        }
     }
 }
 
@@ -473,7 +950,7 @@ where
         + Send,
     C::Future: Send + 'static,
 {
-    type Response = C::Response;
+    type Response = EngineStream;
     type Error = C::Error;
     type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
 
@@ -483,14 +960,36 @@ where
 
     fn call(&mut self, req: Uri) -> Self::Future {
         let mut inner = self.inner.clone();
-        let logger = self.logger.clone();
+        let logger = self.logger.for_current_request();
+        let policy = self.revocation;
         let fut = inner.call(req);
 
         Box::pin(async move {
             match fut.await {
                 Ok(stream) => {
-                    log_connection_details(&logger, &stream);
-                    Ok(stream)
+                    let mut revocation = None;
+                    if let hyper_rustls::MaybeHttpsStream::Https(tls_io) = &stream {
+                        logger.timer().mark(Mark::TlsConnected);
+                        let chain = tls_io.inner().get_ref().1.peer_certificates();
+                        if policy != RevocationPolicy::Off
+                            && let Some(chain) = chain.map(<[_]>::to_vec)
+                        {
+                            revocation = Some(revocation::check_chain(&chain, &logger).await);
+                        }
+                    }
+                    log_connection_details(&logger, &stream, revocation.as_ref());
+                    if policy == RevocationPolicy::Enforce
+                        && let Some(revoked) =
+                            revocation.as_ref().and_then(RevocationReport::revoked)
+                    {
+                        return Err(
+                            format!("The certificate of {} was revoked", revoked.subject).into(),
+                        );
+                    }
+                    Ok(EngineStream {
+                        inner: stream,
+                        revocation,
+                    })
                 }
                 Err(err) => {
                     let mut causes = Vec::new();
@@ -517,7 +1016,25 @@ where
     }
 }
 
-fn log_connection_details(logger: &RequestLogger, stream: &HttpsStream) {
+fn log_connection_details(
+    logger: &RequestLogger,
+    stream: &HttpsStream,
+    revocation: Option<&RevocationReport>,
+) {
+    let tcp = match stream {
+        hyper_rustls::MaybeHttpsStream::Https(tls_io) => tls_io.inner().get_ref().0.inner().inner(),
+        hyper_rustls::MaybeHttpsStream::Http(tcp_io) => tcp_io.inner(),
+    };
+    // Which family won the Happy Eyeballs race, if there was one
+    if let Ok(addr) = tcp.peer_addr() {
+        let family = if addr.is_ipv4() { "IPv4" } else { "IPv6" };
+        logger.debug(
+            "connect",
+            Some("address_family"),
+            format!("Connected over {family} to {addr}"),
+            Some(json!({"family": family, "remoteAddr": addr.to_string()})),
+        );
+    }
     match stream {
         hyper_rustls::MaybeHttpsStream::Https(tls_io) => {
             let tls_stream = tls_io.inner();
@@ -525,7 +1042,7 @@ fn log_connection_details(logger: &RequestLogger, stream: &HttpsStream) {
             let tcp = io_wrapper.inner().inner();
             let remote_addr = tcp.peer_addr().ok();
             let local_addr = tcp.local_addr().ok();
-            log_tls_handshake(logger, conn, remote_addr, local_addr);
+            log_tls_handshake(logger, conn, remote_addr, local_addr, revocation);
         }
         hyper_rustls::MaybeHttpsStream::Http(tcp_io) => {
             let tcp = tcp_io.inner();
@@ -549,6 +1066,7 @@ fn log_tls_handshake(
     conn: &ClientConnection,
     remote_addr: Option<SocketAddr>,
     local_addr: Option<SocketAddr>,
+    revocation: Option<&RevocationReport>,
 ) {
     let alpn = conn
         .alpn_protocol()
@@ -651,6 +1169,9 @@ fn log_tls_handshake(
 
         details.insert("peerCertificates".to_string(), json!(summaries));
     }
+    if let Some(report) = revocation {
+        details.insert("revocation".to_string(), json!(report.0));
+    }
 
     logger.info(
         "tls",
@@ -660,8 +1181,9 @@ fn log_tls_handshake(
     );
 }
 
-#[derive(Serialize)]
-struct CertificateSummary {
+/// What the handshake log and `inspect_tls` show of a certificate
+#[derive(Debug, Serialize)]
+pub struct CertificateSummary {
     index: usize,
     sha256: String,
     subject: Option<String>,
@@ -681,7 +1203,7 @@ struct CertificateSummary {
     pem: String,
 }
 
-#[derive(Serialize)]
+#[derive(Debug, Serialize)]
 struct KeyDetails {
     kind: String,
     bits: Option<usize>,
@@ -694,7 +1216,13 @@ struct KeyDetails {
     curve_description: Option<String>,
 }
 
-fn summarize_certificate(index: usize, cert: &CertificateDer<'_>) -> CertificateSummary {
+impl CertificateSummary {
+    pub(crate) fn pem(&self) -> &str {
+        &self.pem
+    }
+}
+
+pub(crate) fn summarize_certificate(index: usize, cert: &CertificateDer<'_>) -> CertificateSummary {
     let der = cert.as_ref();
     let fingerprint = hex_encode(Sha256::digest(der));
     let pem = encode_pem_block("CERTIFICATE", der);
@@ -1028,3 +1556,165 @@ fn encode_pem_block(label: &str, der: &[u8]) -> String {
     pem.push_str(&format!("-----END {label}-----"));
     pem
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        OverrideResolver, TlsSettings, build_tls_config, cipher_suite_name, crypto_provider,
+        dns_overrides, local_address, refresh_root_store, root_store, select_family, tls_versions,
+    };
+    use crate::http_client::engine::test_emitters::Discard;
+    use crate::http_client::hyper_engine::RequestLogger;
+    use crate::http_client::request::{DnsOverride, IpFamily, Request, TlsVersion};
+    use hyper_util::client::legacy::connect::dns::Name;
+    use rustls::ProtocolVersion;
+    use std::net::{IpAddr, SocketAddr};
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use std::time::Instant;
+    use tower_service::Service;
+
+    #[tokio::test]
+    async fn resolves_every_overridden_host() {
+        let entry = |host: &str, ip: &str| DnsOverride {
+            host: host.to_string(),
+            ip: ip.to_string(),
+        };
+        let request = Request {
+            ip_override: Some("10.0.0.1".to_string()),
+            dns_overrides: Some(vec![
+                entry("Auth.Example.com.", "127.0.0.2"),
+                entry("cdn.example.com", "::1"),
+            ]),
+            ..Request::default()
+        };
+        let overrides = dns_overrides(&request, "api.example.com").unwrap();
+        assert_eq!(overrides.len(), 3);
+        assert_eq!(overrides["api.example.com"], IpAddr::from([10, 0, 0, 1]));
+
+        let logger = RequestLogger::new(Arc::new(Discard), "test".to_string(), Instant::now());
+        let mut resolver = OverrideResolver::new(Arc::new(overrides), IpFamily::Auto, logger);
+        let resolved: Vec<SocketAddr> = resolver
+            .call(Name::from_str("auth.example.com").unwrap())
+            .await
+            .unwrap()
+            .collect();
+        assert_eq!(resolved, ["127.0.0.2:0".parse().unwrap()]);
+        let resolved: Vec<SocketAddr> = resolver
+            .call(Name::from_str("CDN.example.com").unwrap())
+            .await
+            .unwrap()
+            .collect();
+        assert_eq!(resolved, ["[::1]:0".parse().unwrap()]);
+
+        let invalid = Request {
+            dns_overrides: Some(vec![entry("api.example.com", "localhost")]),
+            ..Request::default()
+        };
+        assert!(dns_overrides(&invalid, "api.example.com").is_err());
+    }
+
+    #[test]
+    fn limits_tls_versions() {
+        let versions = |min, max| {
+            tls_versions(min, max)
+                .map(|versions| versions.iter().map(|v| v.version).collect::<Vec<_>>())
+        };
+        assert_eq!(
+            versions(None, None).unwrap(),
+            [ProtocolVersion::TLSv1_2, ProtocolVersion::TLSv1_3]
+        );
+        assert_eq!(
+            versions(None, Some(TlsVersion::Tls12)).unwrap(),
+            [ProtocolVersion::TLSv1_2]
+        );
+        assert_eq!(
+            versions(Some(TlsVersion::Tls13), None).unwrap(),
+            [ProtocolVersion::TLSv1_3]
+        );
+        assert!(versions(Some(TlsVersion::Tls13), Some(TlsVersion::Tls12)).is_err());
+    }
+
+    #[test]
+    fn limits_cipher_suites() {
+        let names = |list: &[&str]| -> Vec<String> { list.iter().map(|s| s.to_string()).collect() };
+        let provider = crypto_provider(Some(&names(&[
+            "tls_chacha20_poly1305_sha256",
+            "TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256",
+        ])))
+        .unwrap();
+        let offered: Vec<String> = provider
+            .cipher_suites
+            .iter()
+            .map(cipher_suite_name)
+            .collect();
+        assert_eq!(
+            offered,
+            [
+                "TLS_CHACHA20_POLY1305_SHA256",
+                "TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256"
+            ]
+        );
+
+        let err = crypto_provider(Some(&names(&["TLS_RSA_WITH_RC4_128_MD5"]))).unwrap_err();
+        assert!(err.message.contains("TLS_AES_256_GCM_SHA384"));
+
+        // Only TLS 1.2 suites, but only TLS 1.3 offered
+        let tls13 = tls_versions(Some(TlsVersion::Tls13), None).unwrap();
+        let suites = names(&["TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256"]);
+        let settings = TlsSettings {
+            versions: &tls13,
+            cipher_suites: Some(&suites),
+            ..TlsSettings::default()
+        };
+        assert!(build_tls_config(settings).is_err());
+    }
+
+    #[test]
+    fn checks_the_local_address() {
+        let request = |local: &str, family| Request {
+            local_address: Some(local.to_string()),
+            ip_family: family,
+            ..Request::default()
+        };
+        assert_eq!(
+            local_address(&request("10.0.0.2", None)).unwrap(),
+            Some(IpAddr::from([10, 0, 0, 2]))
+        );
+        assert_eq!(local_address(&request(" ", None)).unwrap(), None);
+        assert!(local_address(&request("eth0", None)).is_err());
+        assert!(local_address(&request("::1", Some(IpFamily::Ipv4))).is_err());
+    }
+
+    #[test]
+    fn selects_the_address_family() {
+        let logger = RequestLogger::new(Arc::new(Discard), "test".to_string(), Instant::now());
+        let v6: SocketAddr = "[2001:db8::1]:0".parse().unwrap();
+        let v4: SocketAddr = "192.0.2.1:0".parse().unwrap();
+        let both = vec![v6, v4];
+        let select = |family| select_family(both.clone(), family, "api.example.com", &logger);
+        assert_eq!(select(IpFamily::Auto).unwrap(), [v6, v4]);
+        assert_eq!(select(IpFamily::Ipv4).unwrap(), [v4]);
+        assert_eq!(select(IpFamily::Ipv6).unwrap(), [v6]);
+
+        let err = select_family(vec![v4], IpFamily::Ipv6, "api.example.com", &logger).unwrap_err();
+        assert_eq!(err.to_string(), "api.example.com has no IPv6 address");
+    }
+
+    #[test]
+    fn root_stores_are_cached_until_refreshed() {
+        let base = root_store(None).unwrap();
+        assert!(!base.is_empty());
+        assert!(Arc::ptr_eq(&base, &root_store(None).unwrap()));
+
+        let bundle = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(bundle.path(), "not a certificate").unwrap();
+        let path = bundle.path().to_str().unwrap();
+        assert!(root_store(Some(path)).is_err());
+        // Failures aren't cached
+        assert!(root_store(Some(path)).is_err());
+
+        assert_eq!(refresh_root_store(), base.len());
+        assert!(!Arc::ptr_eq(&base, &root_store(None).unwrap()));
+    }
+}
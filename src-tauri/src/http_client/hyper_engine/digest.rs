@@ -0,0 +1,234 @@
+use std::fs::File;
+use std::io::Read;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use bytes::Bytes;
+use hyper::http::{HeaderMap, HeaderName, HeaderValue};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use super::body::BodySource;
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::request::BodyDigest;
+
+const CONTENT_DIGEST: HeaderName = HeaderName::from_static("content-digest");
+const DIGEST: HeaderName = HeaderName::from_static("digest");
+
+fn sha256(body: &BodySource) -> Result<[u8; 32], AppError> {
+    let mut hasher = Sha256::new();
+    match body {
+        BodySource::Bytes(bytes) => hasher.update(bytes),
+        BodySource::File { path, .. } => {
+            let io_error = |e: std::io::Error| {
+                AppError::new(
+                    ErrorKind::IoError,
+                    format!("Failed to digest body file '{}': {e}", path.display()),
+                )
+            };
+            let mut file = File::open(path).map_err(io_error)?;
+            let mut buf = vec![0u8; 64 * 1024];
+            loop {
+                let read = file.read(&mut buf).map_err(io_error)?;
+                if read == 0 {
+                    break;
+                }
+                hasher.update(&buf[..read]);
+            }
+        }
+    }
+    Ok(hasher.finalize().into())
+}
+
+/// Sets the digest headers for `body`, the bytes as sent (after any content encoding),
+/// replacing values set by the user. Returns the base64 SHA-256.
+pub(super) fn apply(
+    body: &BodySource,
+    digest: BodyDigest,
+    headers: &mut HeaderMap,
+) -> Result<String, AppError> {
+    let encoded = BASE64.encode(sha256(body)?);
+    let header = |value: String| {
+        HeaderValue::try_from(value).map_err(|e| {
+            AppError::new(
+                ErrorKind::BadRequest,
+                format!("Invalid digest header value: {e}"),
+            )
+        })
+    };
+    if matches!(digest, BodyDigest::ContentDigest | BodyDigest::Both) {
+        headers.insert(CONTENT_DIGEST, header(format!("sha-256=:{encoded}:"))?);
+    }
+    if matches!(digest, BodyDigest::Digest | BodyDigest::Both) {
+        headers.insert(DIGEST, header(format!("SHA-256={encoded}"))?);
+    }
+    Ok(encoded)
+}
+
+/// Drops digest headers once the body they describe is no longer sent
+pub(super) fn remove(headers: &mut HeaderMap) {
+    headers.remove(CONTENT_DIGEST);
+    headers.remove(DIGEST);
+}
+
+/// A number as ECMAScript's `Number.prototype.toString` writes it (RFC 8785 §3.2.2.3)
+fn write_number(out: &mut String, value: f64) {
+    if value == 0.0 {
+        out.push('0');
+        return;
+    }
+    if value < 0.0 {
+        out.push('-');
+    }
+    // Shortest round-trip digits, as `d.ddde±x`
+    let scientific = format!("{:e}", value.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let k = digits.len() as i32;
+    let n = exponent.parse::<i32>().unwrap_or(0) + 1;
+    if k <= n && n <= 21 {
+        out.push_str(&digits);
+        out.extend(std::iter::repeat_n('0', (n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        out.push_str(&digits[..n as usize]);
+        out.push('.');
+        out.push_str(&digits[n as usize..]);
+    } else if -6 < n && n <= 0 {
+        out.push_str("0.");
+        out.extend(std::iter::repeat_n('0', (-n) as usize));
+        out.push_str(&digits);
+    } else {
+        out.push_str(&digits[..1]);
+        if k > 1 {
+            out.push('.');
+            out.push_str(&digits[1..]);
+        }
+        out.push('e');
+        out.push(if n > 0 { '+' } else { '-' });
+        out.push_str(&(n - 1).abs().to_string());
+    }
+}
+
+fn write_canonical(out: &mut String, value: &Value) {
+    match value {
+        Value::Null | Value::Bool(_) => out.push_str(&value.to_string()),
+        Value::Number(number) => write_number(out, number.as_f64().unwrap_or_default()),
+        // serde_json escapes exactly what JCS does: quote, backslash and control characters
+        Value::String(string) => out.push_str(&Value::String(string.clone()).to_string()),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(out, item);
+            }
+            out.push(']');
+        }
+        Value::Object(object) => {
+            // Members are sorted by the UTF-16 code units of their names
+            let mut members: Vec<_> = object.iter().collect();
+            members.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            out.push('{');
+            for (i, (name, value)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(name.clone()).to_string());
+                out.push(':');
+                write_canonical(out, value);
+            }
+            out.push('}');
+        }
+    }
+}
+
+/// Rewrites a JSON body in its canonical form (RFC 8785 JCS), so the bytes that are digested
+/// and signed are the ones any other JCS implementation would produce.
+pub(super) fn canonicalize_json(body: &[u8]) -> Result<Bytes, AppError> {
+    let value: Value = serde_json::from_slice(body).map_err(|e| {
+        AppError::new(
+            ErrorKind::BadRequest,
+            format!("Can't canonicalize a body that isn't JSON: {e}"),
+        )
+    })?;
+    let mut out = String::with_capacity(body.len());
+    write_canonical(&mut out, &value);
+    Ok(Bytes::from(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply, canonicalize_json, write_number};
+    use crate::http_client::hyper_engine::body::BodySource;
+    use crate::http_client::request::BodyDigest;
+    use bytes::Bytes;
+    use hyper::http::HeaderMap;
+
+    #[test]
+    fn sets_content_digest_and_legacy_digest() {
+        // RFC 9530 §2
+        let body = BodySource::Bytes(Bytes::from_static(b"{\"hello\": \"world\"}"));
+        let mut headers = HeaderMap::new();
+        apply(&body, BodyDigest::Both, &mut headers).unwrap();
+        assert_eq!(
+            headers["content-digest"],
+            "sha-256=:X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=:"
+        );
+        assert_eq!(
+            headers["digest"],
+            "SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE="
+        );
+
+        let mut headers = HeaderMap::new();
+        apply(&body, BodyDigest::ContentDigest, &mut headers).unwrap();
+        assert!(!headers.contains_key("digest"));
+    }
+
+    #[test]
+    fn writes_numbers_like_ecmascript() {
+        // RFC 8785 Appendix B
+        for (value, expected) in [
+            (0.0, "0"),
+            (-0.0, "0"),
+            (1e21, "1e+21"),
+            (1e20, "100000000000000000000"),
+            (9007199254740992.0, "9007199254740992"),
+            (295147905179352830000.0, "295147905179352830000"),
+            (4.5, "4.5"),
+            (2e-3, "0.002"),
+            (1e-7, "1e-7"),
+            (0.000001, "0.000001"),
+            (-1.5e-7, "-1.5e-7"),
+            (333333333.3333333, "333333333.3333333"),
+            (5e-324, "5e-324"),
+            (1.7976931348623157e308, "1.7976931348623157e+308"),
+        ] {
+            let mut out = String::new();
+            write_number(&mut out, value);
+            assert_eq!(out, expected, "{value:e}");
+        }
+    }
+
+    #[test]
+    fn canonicalizes_json() {
+        // RFC 8785 §3.2.2 and §3.2.3
+        let body = r#"{
+            "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+            "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+            "literals": [null, true, false]
+        }"#;
+        assert_eq!(
+            canonicalize_json(body.as_bytes()).unwrap(),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+        );
+        // Sorted by UTF-16 code units, so U+1F600 (a surrogate pair) comes before U+FB33
+        let names =
+            r#"{"\u20ac":1,"\r":2,"\ufb33":3,"1":4,"\ud83d\ude00":5,"\u0080":6,"\u00f6":7}"#;
+        assert_eq!(
+            canonicalize_json(names.as_bytes()).unwrap(),
+            "{\"\\r\":2,\"1\":4,\"\u{80}\":6,\"\u{f6}\":7,\"\u{20ac}\":1,\"\u{1f600}\":5,\"\u{fb33}\":3}"
+        );
+        assert!(canonicalize_json(b"not json").is_err());
+    }
+}
@@ -0,0 +1,167 @@
+//! Lookups shared by every request, so new connections to a host skip DNS for a while.
+//! The system resolver doesn't report record TTLs, so entries live for a fixed time.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// How long a lookup is reused
+const DNS_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Cached hosts at most; expired entries go first, then the oldest
+const MAX_ENTRIES: usize = 512;
+
+struct Lookup {
+    addrs: Vec<SocketAddr>,
+    resolved_at: SystemTime,
+    expires: Instant,
+}
+
+#[derive(Default)]
+struct DnsCache {
+    entries: HashMap<String, Lookup>,
+}
+
+impl DnsCache {
+    fn get(&self, host: &str, now: Instant) -> Option<(Vec<SocketAddr>, Duration)> {
+        self.entries
+            .get(host)
+            .filter(|lookup| lookup.expires > now)
+            .map(|lookup| (lookup.addrs.clone(), lookup.expires - now))
+    }
+
+    fn insert(&mut self, host: String, addrs: Vec<SocketAddr>, now: Instant) {
+        if self.entries.len() >= MAX_ENTRIES && !self.entries.contains_key(&host) {
+            self.entries.retain(|_, lookup| lookup.expires > now);
+            if self.entries.len() >= MAX_ENTRIES
+                && let Some(oldest) = self
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, lookup)| lookup.expires)
+                    .map(|(host, _)| host.clone())
+            {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(
+            host,
+            Lookup {
+                addrs,
+                resolved_at: SystemTime::now(),
+                expires: now + DNS_CACHE_TTL,
+            },
+        );
+    }
+}
+
+static CACHE: LazyLock<Mutex<DnsCache>> = LazyLock::new(Mutex::default);
+
+/// A cached lookup, as listed by `dns_cache_entries`
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DnsCacheEntry {
+    pub host: String,
+    pub addresses: Vec<String>,
+    /// Time of the lookup, ISO 8601
+    pub resolved_at: String,
+    pub expires_in_secs: u64,
+}
+
+/// The cached addresses of `host` and how long they remain cached, unless expired.
+pub(super) fn lookup(host: &str) -> Option<(Vec<SocketAddr>, Duration)> {
+    CACHE
+        .lock()
+        .unwrap()
+        .get(&host.to_ascii_lowercase(), Instant::now())
+}
+
+pub(super) fn store(host: &str, addrs: &[SocketAddr]) {
+    if addrs.is_empty() {
+        return;
+    }
+    CACHE
+        .lock()
+        .unwrap()
+        .insert(host.to_ascii_lowercase(), addrs.to_vec(), Instant::now());
+}
+
+/// The unexpired lookups, by host.
+pub fn dns_cache_entries() -> Vec<DnsCacheEntry> {
+    let now = Instant::now();
+    let cache = CACHE.lock().unwrap();
+    let mut entries: Vec<DnsCacheEntry> = cache
+        .entries
+        .iter()
+        .filter(|(_, lookup)| lookup.expires > now)
+        .map(|(host, lookup)| DnsCacheEntry {
+            host: host.clone(),
+            addresses: lookup
+                .addrs
+                .iter()
+                .map(|addr| addr.ip().to_string())
+                .collect(),
+            resolved_at: DateTime::<Utc>::from(lookup.resolved_at).to_rfc3339(),
+            expires_in_secs: (lookup.expires - now).as_secs(),
+        })
+        .collect();
+    entries.sort_by(|a, b| a.host.cmp(&b.host));
+    entries
+}
+
+/// Drops the lookup of `host`, or every lookup. Returns how many were dropped.
+pub fn flush_dns_cache(host: Option<&str>) -> usize {
+    let mut cache = CACHE.lock().unwrap();
+    match host {
+        Some(host) => cache
+            .entries
+            .remove(&host.to_ascii_lowercase())
+            .map_or(0, |_| 1),
+        None => {
+            let flushed = cache.entries.len();
+            cache.entries.clear();
+            flushed
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DNS_CACHE_TTL, DnsCache, MAX_ENTRIES};
+    use std::net::SocketAddr;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn expires_and_evicts_lookups() {
+        let addr: SocketAddr = "192.0.2.1:0".parse().unwrap();
+        let start = Instant::now();
+        let mut cache = DnsCache::default();
+        cache.insert("api.example.com".to_string(), vec![addr], start);
+
+        let (addrs, left) = cache
+            .get("api.example.com", start + Duration::from_secs(10))
+            .unwrap();
+        assert_eq!(addrs, [addr]);
+        assert_eq!(left, DNS_CACHE_TTL - Duration::from_secs(10));
+        assert!(
+            cache
+                .get("api.example.com", start + DNS_CACHE_TTL)
+                .is_none()
+        );
+
+        for i in 1..MAX_ENTRIES {
+            let at = start + Duration::from_millis(i as u64);
+            cache.insert(format!("host{i}.example.com"), vec![addr], at);
+        }
+        assert_eq!(cache.entries.len(), MAX_ENTRIES);
+        let later = start + Duration::from_millis(MAX_ENTRIES as u64);
+        cache.insert("new.example.com".to_string(), vec![addr], later);
+        // The oldest went to make room
+        assert_eq!(cache.entries.len(), MAX_ENTRIES);
+        assert!(cache.get("api.example.com", later).is_none());
+        assert!(cache.get("new.example.com", later).is_some());
+    }
+}
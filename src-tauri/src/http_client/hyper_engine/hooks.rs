@@ -0,0 +1,355 @@
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD as BASE64, URL_SAFE_NO_PAD as BASE64_URL};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rand::RngCore;
+use ring::{digest, hmac};
+use serde_json::json;
+
+use super::{HyperEngine, RequestLogger};
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::request::{
+    HookAlgorithm, HookEncoding, HookStep, QueryParam, Request, TimestampFormat,
+};
+
+const DEFAULT_NONCE_BYTES: usize = 16;
+
+/// Longest nonce a step may ask for
+const MAX_NONCE_BYTES: usize = 1024;
+
+fn bad_request(message: impl Into<String>) -> AppError {
+    AppError::new(ErrorKind::BadRequest, message)
+}
+
+impl HookEncoding {
+    fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            HookEncoding::Hex => hex::encode(bytes),
+            HookEncoding::Base64 => BASE64.encode(bytes),
+            HookEncoding::Base64Url => BASE64_URL.encode(bytes),
+        }
+    }
+
+    fn decode(self, text: &str) -> Result<Vec<u8>, String> {
+        let text = text.trim();
+        match self {
+            HookEncoding::Hex => hex::decode(text).map_err(|e| e.to_string()),
+            HookEncoding::Base64 => BASE64.decode(text).map_err(|e| e.to_string()),
+            HookEncoding::Base64Url => BASE64_URL
+                .decode(text.trim_end_matches('='))
+                .map_err(|e| e.to_string()),
+        }
+    }
+}
+
+impl HookAlgorithm {
+    fn digest(self) -> &'static digest::Algorithm {
+        match self {
+            HookAlgorithm::Sha1 => &digest::SHA1_FOR_LEGACY_USE_ONLY,
+            HookAlgorithm::Sha256 => &digest::SHA256,
+            HookAlgorithm::Sha384 => &digest::SHA384,
+            HookAlgorithm::Sha512 => &digest::SHA512,
+        }
+    }
+
+    fn hmac(self) -> hmac::Algorithm {
+        match self {
+            HookAlgorithm::Sha1 => hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
+            HookAlgorithm::Sha256 => hmac::HMAC_SHA256,
+            HookAlgorithm::Sha384 => hmac::HMAC_SHA384,
+            HookAlgorithm::Sha512 => hmac::HMAC_SHA512,
+        }
+    }
+}
+
+fn timestamp(now: DateTime<Utc>, format: TimestampFormat) -> String {
+    match format {
+        TimestampFormat::UnixSeconds => now.timestamp().to_string(),
+        TimestampFormat::UnixMillis => now.timestamp_millis().to_string(),
+        TimestampFormat::Iso8601 => now.to_rfc3339_opts(SecondsFormat::Secs, true),
+        TimestampFormat::HttpDate => now.format("%a, %d %b %Y %H:%M:%S GMT").to_string(),
+    }
+}
+
+/// Value of a `{{request.…}}` placeholder, read from the request as it is now
+fn request_value(request: &Request, field: &str) -> Result<Option<String>, AppError> {
+    let value = match field {
+        "method" => request.method.clone(),
+        "url" => HyperEngine::build_uri(request)?.to_string(),
+        "host" => HyperEngine::build_uri(request)?
+            .host()
+            .unwrap_or_default()
+            .to_string(),
+        "path" => HyperEngine::build_uri(request)?.path().to_string(),
+        "query" => HyperEngine::build_uri(request)?
+            .query()
+            .unwrap_or_default()
+            .to_string(),
+        "body" => {
+            if request.multipart_parts.is_some()
+                || request.body_file_path.is_some()
+                || request.graphql.is_some()
+                || request.soap.is_some()
+            {
+                return Err(bad_request(
+                    "Hooks can only read bodies given inline, not multipart, file, GraphQL or SOAP ones",
+                ));
+            }
+            let body = request.body.as_deref().unwrap_or_default();
+            String::from_utf8(body.to_vec())
+                .map_err(|_| bad_request("Hooks can only read text bodies"))?
+        }
+        field => {
+            let Some(name) = field.strip_prefix("header.") else {
+                return Ok(None);
+            };
+            let values: Vec<&str> = request
+                .headers
+                .iter()
+                .flatten()
+                .filter(|(header, _)| header.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.as_str())
+                .collect();
+            values.join(", ")
+        }
+    };
+    Ok(Some(value))
+}
+
+/// Fills in a step's template. Placeholders that name nothing are an error, so a typo can't
+/// quietly sign the wrong text.
+fn render(
+    template: &str,
+    variables: &[(String, String)],
+    request: &Request,
+) -> Result<String, AppError> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let name = after[..end].trim();
+        let value = match variables
+            .iter()
+            .rev()
+            .find(|(variable, _)| variable == name)
+        {
+            Some((_, value)) => Some(value.clone()),
+            None => match name.strip_prefix("request.") {
+                Some(field) => request_value(request, field)?,
+                None => None,
+            },
+        };
+        let value =
+            value.ok_or_else(|| bad_request(format!("Unknown hook value '{{{{{name}}}}}'")))?;
+        out.push_str(&value);
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// What a step did, for the log; never the values, which may be secrets
+fn describe(step: &HookStep) -> String {
+    match step {
+        HookStep::Set { name, .. } => format!("Set '{name}'"),
+        HookStep::Timestamp { name, .. } => format!("Stored a timestamp as '{name}'"),
+        HookStep::Nonce { name, .. } => format!("Stored a nonce as '{name}'"),
+        HookStep::Hash { name, .. } => format!("Stored a hash as '{name}'"),
+        HookStep::Hmac { name, .. } => format!("Stored an HMAC as '{name}'"),
+        HookStep::SetHeader { name, .. } => format!("Set header '{name}'"),
+        HookStep::RemoveHeader { name } => format!("Removed header '{name}'"),
+        HookStep::SetQueryParam { name, .. } => format!("Set query parameter '{name}'"),
+    }
+}
+
+/// Runs the request's pre-request hook, changing the request as its steps say
+pub(super) fn apply(
+    request: &mut Request,
+    now: DateTime<Utc>,
+    logger: &RequestLogger,
+) -> Result<(), AppError> {
+    let Some(steps) = request.pre_request.take() else {
+        return Ok(());
+    };
+    let mut variables: Vec<(String, String)> = Vec::new();
+    for (index, step) in steps.iter().enumerate() {
+        run_step(step, request, &mut variables, now).map_err(|e| {
+            AppError::new(e.kind, format!("Hook step {}: {}", index + 1, e.message))
+        })?;
+        logger.debug(
+            "hook",
+            Some("pre_request"),
+            describe(step),
+            Some(json!({ "step": index + 1 })),
+        );
+    }
+    logger.info(
+        "hook",
+        Some("pre_request"),
+        format!("Ran {} pre-request hook steps", steps.len()),
+        Some(json!({ "steps": steps.len() })),
+    );
+    request.pre_request = Some(steps);
+    Ok(())
+}
+
+fn run_step(
+    step: &HookStep,
+    request: &mut Request,
+    variables: &mut Vec<(String, String)>,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    let (name, value) = match step {
+        HookStep::Set { name, value } => (name, render(value, variables, request)?),
+        HookStep::Timestamp { name, format } => (name, timestamp(now, *format)),
+        HookStep::Nonce {
+            name,
+            bytes,
+            encoding,
+        } => {
+            let len = bytes.unwrap_or(DEFAULT_NONCE_BYTES);
+            if !(1..=MAX_NONCE_BYTES).contains(&len) {
+                return Err(bad_request(format!(
+                    "Nonces must be 1 to {MAX_NONCE_BYTES} bytes"
+                )));
+            }
+            let mut nonce = vec![0u8; len];
+            rand::rng().fill_bytes(&mut nonce);
+            (name, encoding.encode(&nonce))
+        }
+        HookStep::Hash {
+            name,
+            algorithm,
+            input,
+            encoding,
+        } => {
+            let input = render(input, variables, request)?;
+            let hash = digest::digest(algorithm.digest(), input.as_bytes());
+            (name, encoding.encode(hash.as_ref()))
+        }
+        HookStep::Hmac {
+            name,
+            algorithm,
+            key,
+            key_encoding,
+            input,
+            encoding,
+        } => {
+            let key = render(key, variables, request)?;
+            let key = match key_encoding {
+                Some(key_encoding) => key_encoding
+                    .decode(&key)
+                    .map_err(|e| bad_request(format!("Invalid HMAC key: {e}")))?,
+                None => key.into_bytes(),
+            };
+            let input = render(input, variables, request)?;
+            let key = hmac::Key::new(algorithm.hmac(), &key);
+            let tag = hmac::sign(&key, input.as_bytes());
+            (name, encoding.encode(tag.as_ref()))
+        }
+        HookStep::SetHeader { name, value } => {
+            let value = render(value, variables, request)?;
+            let headers = request.headers.get_or_insert_with(Vec::new);
+            headers.retain(|(header, _)| !header.eq_ignore_ascii_case(name));
+            headers.push((name.clone(), value));
+            return Ok(());
+        }
+        HookStep::RemoveHeader { name } => {
+            if let Some(headers) = request.headers.as_mut() {
+                headers.retain(|(header, _)| !header.eq_ignore_ascii_case(name));
+            }
+            return Ok(());
+        }
+        HookStep::SetQueryParam { name, value } => {
+            let value = render(value, variables, request)?;
+            let params = request.query_params.get_or_insert_with(Vec::new);
+            params.retain(|param| param.name != *name);
+            params.push(QueryParam {
+                name: name.clone(),
+                value,
+                encode: true,
+            });
+            return Ok(());
+        }
+    };
+    variables.push((name.clone(), value));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply, render};
+    use crate::http_client::engine::test_emitters::Discard;
+    use crate::http_client::hyper_engine::RequestLogger;
+    use crate::http_client::request::{HookStep, Request};
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    fn run(request: &mut Request, steps: serde_json::Value) -> Result<(), String> {
+        request.pre_request = Some(serde_json::from_value(steps).unwrap());
+        let logger = RequestLogger::new(Arc::new(Discard), "test".to_string(), Instant::now());
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        apply(request, now, &logger).map_err(|e| e.message)
+    }
+
+    #[test]
+    fn signs_a_request_with_a_timestamp_and_hmac() {
+        let mut request = Request {
+            method: "POST".to_string(),
+            url: "https://api.example.com/v1/orders?b=2".to_string(),
+            headers: Some(vec![("X-Signature".to_string(), "stale".to_string())]),
+            body: Some(br#"{"id":1}"#.to_vec()),
+            ..Request::default()
+        };
+        run(
+            &mut request,
+            json!([
+                { "type": "timestamp", "name": "ts", "format": "unixSeconds" },
+                { "type": "set", "name": "base", "value": "{{ts}}.{{request.method}}.{{request.path}}.{{request.body}}" },
+                { "type": "hmac", "name": "sig", "algorithm": "sha256", "key": "secret", "input": "{{base}}" },
+                { "type": "setHeader", "name": "x-signature", "value": "t={{ts}},v1={{sig}}" },
+                { "type": "setQueryParam", "name": "ts", "value": "{{ts}}" },
+            ]),
+        )
+        .unwrap();
+
+        // HMAC-SHA256("secret", "1714564800.POST./v1/orders.{\"id\":1}")
+        let headers = request.headers.unwrap();
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers[0].0, "x-signature");
+        assert_eq!(
+            headers[0].1,
+            "t=1714564800,v1=3244ea0f87bb33e0bdd1018fdd555d23e80b656d694f83a9aae62e46603dde1e"
+        );
+        assert_eq!(request.query_params.unwrap()[0].value, "1714564800");
+        assert_eq!(request.pre_request.unwrap().len(), 5);
+    }
+
+    #[test]
+    fn rejects_unknown_placeholders() {
+        let request = Request::default();
+        assert_eq!(
+            render("{{ request.header.missing }}-{{a}", &[], &request).unwrap(),
+            "-{{a}"
+        );
+        let error = render("{{sig}}", &[], &request).unwrap_err();
+        assert_eq!(error.message, "Unknown hook value '{{sig}}'");
+
+        let mut request = Request::default();
+        let steps =
+            json!([{ "type": "hash", "name": "h", "algorithm": "sha256", "input": "{{nope}}" }]);
+        assert_eq!(
+            run(&mut request, steps).unwrap_err(),
+            "Hook step 1: Unknown hook value '{{nope}}'"
+        );
+        assert!(matches!(
+            serde_json::from_value::<HookStep>(json!({ "type": "nonce", "name": "n" })).unwrap(),
+            HookStep::Nonce { bytes: None, .. }
+        ));
+    }
+}
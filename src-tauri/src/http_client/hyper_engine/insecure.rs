@@ -0,0 +1,200 @@
+//! Certificate verification that lets chosen hosts through, for self-signed or misnamed
+//! development servers, while every other host is verified as usual.
+
+use std::sync::Arc;
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{CertificateError, DigitallySignedStruct, SignatureScheme};
+
+use crate::http_client::request::InsecureHost;
+
+#[derive(Debug)]
+pub(super) struct AllowlistVerifier {
+    inner: Arc<dyn ServerCertVerifier>,
+    hosts: Vec<InsecureHost>,
+}
+
+impl AllowlistVerifier {
+    pub(super) fn new(inner: Arc<dyn ServerCertVerifier>, hosts: Vec<InsecureHost>) -> Self {
+        Self { inner, hosts }
+    }
+}
+
+/// Whether `host` is `pattern`, or one of its subdomains for a `*.` pattern
+fn host_matches(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.trim().trim_end_matches('.').to_ascii_lowercase();
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host
+            .strip_suffix(domain)
+            .is_some_and(|sub| sub.len() > 1 && sub.ends_with('.')),
+        None => pattern == host,
+    }
+}
+
+impl ServerCertVerifier for AllowlistVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let host = server_name.to_str();
+        let entry = self
+            .hosts
+            .iter()
+            .find(|entry| host_matches(&entry.host, &host));
+        if entry.is_some_and(|entry| !entry.hostname_only) {
+            return Ok(ServerCertVerified::assertion());
+        }
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        );
+        match entry {
+            None => verified,
+            Some(_) => match verified {
+                Err(rustls::Error::InvalidCertificate(
+                    CertificateError::NotValidForName
+                    | CertificateError::NotValidForNameContext { .. },
+                )) => Ok(ServerCertVerified::assertion()),
+                other => other,
+            },
+        }
+    }
+
+    // Handshake signatures are checked against the certificate's own key, which holds for
+    // self-signed certificates too
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AllowlistVerifier, host_matches};
+    use crate::http_client::request::InsecureHost;
+    use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+    use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+    use rustls::{CertificateError, DigitallySignedStruct, SignatureScheme};
+    use std::sync::Arc;
+
+    /// Fails every certificate with the error it's given
+    #[derive(Debug)]
+    struct Failing(CertificateError);
+
+    impl ServerCertVerifier for Failing {
+        fn verify_server_cert(
+            &self,
+            _end_entity: &CertificateDer<'_>,
+            _intermediates: &[CertificateDer<'_>],
+            _server_name: &ServerName<'_>,
+            _ocsp_response: &[u8],
+            _now: UnixTime,
+        ) -> Result<ServerCertVerified, rustls::Error> {
+            Err(rustls::Error::InvalidCertificate(self.0.clone()))
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            _message: &[u8],
+            _cert: &CertificateDer<'_>,
+            _dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            Ok(HandshakeSignatureValid::assertion())
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            _message: &[u8],
+            _cert: &CertificateDer<'_>,
+            _dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            Ok(HandshakeSignatureValid::assertion())
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            Vec::new()
+        }
+    }
+
+    fn verify(inner: CertificateError, host: &str) -> bool {
+        let verifier = AllowlistVerifier::new(
+            Arc::new(Failing(inner)),
+            vec![
+                InsecureHost {
+                    host: "dev.local".to_string(),
+                    hostname_only: false,
+                },
+                InsecureHost {
+                    host: "*.staging.example.com".to_string(),
+                    hostname_only: true,
+                },
+            ],
+        );
+        let name = ServerName::try_from(host.to_string()).unwrap();
+        verifier
+            .verify_server_cert(
+                &CertificateDer::from(Vec::new()),
+                &[],
+                &name,
+                &[],
+                UnixTime::now(),
+            )
+            .is_ok()
+    }
+
+    #[test]
+    fn skips_verification_for_listed_hosts() {
+        assert!(verify(CertificateError::UnknownIssuer, "DEV.local"));
+        assert!(!verify(CertificateError::UnknownIssuer, "api.dev.local"));
+        assert!(!verify(CertificateError::UnknownIssuer, "example.com"));
+
+        // Only the name check is skipped
+        assert!(verify(
+            CertificateError::NotValidForName,
+            "api.staging.example.com"
+        ));
+        assert!(!verify(
+            CertificateError::UnknownIssuer,
+            "api.staging.example.com"
+        ));
+        assert!(!verify(
+            CertificateError::NotValidForName,
+            "staging.example.com"
+        ));
+    }
+
+    #[test]
+    fn matches_wildcard_subdomains() {
+        assert!(host_matches("*.example.com", "a.b.example.com"));
+        assert!(!host_matches("*.example.com", "badexample.com"));
+        assert!(host_matches("localhost.", "localhost"));
+        assert!(host_matches("127.0.0.1", "127.0.0.1"));
+    }
+}
@@ -0,0 +1,426 @@
+//! NTLMv2 messages (MS-NLMP) for HTTP authentication. Only authentication is supported;
+//! no session keys are exchanged since HTTP doesn't sign or seal messages.
+
+use std::time::Duration;
+
+use base64::{Engine as _, engine::general_purpose};
+use hmac::{Hmac, Mac};
+use http_body_util::BodyExt;
+use hyper::http::{HeaderMap, HeaderValue, Uri};
+use hyper::{Method, Request as HyperRequest, StatusCode};
+use md4::{Digest, Md4};
+use md5::Md5;
+use serde_json::json;
+use tokio::time::timeout;
+
+use super::body::BodySource;
+use super::pool::EngineClient;
+use super::{CURRENT_LOGGER, RequestLogger};
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::request::NtlmCredentials;
+
+const SIGNATURE: &[u8; 8] = b"NTLMSSP\0";
+
+const NEGOTIATE_UNICODE: u32 = 0x0000_0001;
+const NEGOTIATE_OEM: u32 = 0x0000_0002;
+const REQUEST_TARGET: u32 = 0x0000_0004;
+const NEGOTIATE_NTLM: u32 = 0x0000_0200;
+const NEGOTIATE_ALWAYS_SIGN: u32 = 0x0000_8000;
+const NEGOTIATE_EXTENDED_SESSIONSECURITY: u32 = 0x0008_0000;
+const NEGOTIATE_TARGET_INFO: u32 = 0x0080_0000;
+const NEGOTIATE_128: u32 = 0x2000_0000;
+const NEGOTIATE_56: u32 = 0x8000_0000;
+
+const NEGOTIATE_FLAGS: u32 = NEGOTIATE_UNICODE
+    | NEGOTIATE_OEM
+    | REQUEST_TARGET
+    | NEGOTIATE_NTLM
+    | NEGOTIATE_ALWAYS_SIGN
+    | NEGOTIATE_EXTENDED_SESSIONSECURITY
+    | NEGOTIATE_128
+    | NEGOTIATE_56;
+
+/// AV pair carrying the server's FILETIME (MS-NLMP 2.2.2.1)
+const MSV_AV_TIMESTAMP: u16 = 7;
+const MSV_AV_EOL: u16 = 0;
+
+/// Seconds between 1601-01-01 (FILETIME epoch) and 1970-01-01
+const FILETIME_UNIX_OFFSET_SECS: u64 = 11_644_473_600;
+
+type HmacMd5 = Hmac<Md5>;
+
+/// The server's CHALLENGE_MESSAGE
+#[derive(Debug, PartialEq)]
+pub(super) struct Challenge {
+    flags: u32,
+    server_challenge: [u8; 8],
+    target_info: Vec<u8>,
+}
+
+fn invalid(message: impl Into<String>) -> AppError {
+    AppError::new(ErrorKind::BadRequest, message)
+}
+
+fn utf16le(value: &str) -> Vec<u8> {
+    value.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
+
+fn hmac_md5(key: &[u8], parts: &[&[u8]]) -> [u8; 16] {
+    let mut mac = HmacMd5::new_from_slice(key).expect("HMAC accepts keys of any length");
+    for part in parts {
+        mac.update(part);
+    }
+    mac.finalize().into_bytes().into()
+}
+
+/// NTOWFv2: HMAC-MD5 of the upper-cased user and the domain, keyed with the MD4 password hash
+fn ntowf_v2(username: &str, password: &str, domain: &str) -> [u8; 16] {
+    let nt_hash = Md4::digest(utf16le(password));
+    hmac_md5(
+        &nt_hash,
+        &[&utf16le(&username.to_uppercase()), &utf16le(domain)],
+    )
+}
+
+/// Splits `DOMAIN\user` (or `user@domain`) when no domain was given separately.
+fn user_and_domain(credentials: &NtlmCredentials) -> (String, String) {
+    if let Some(domain) = credentials.domain.as_ref().filter(|d| !d.is_empty()) {
+        return (credentials.username.clone(), domain.clone());
+    }
+    if let Some((domain, user)) = credentials.username.split_once('\\') {
+        return (user.to_string(), domain.to_string());
+    }
+    if let Some((user, domain)) = credentials.username.split_once('@') {
+        return (user.to_string(), domain.to_string());
+    }
+    (credentials.username.clone(), String::new())
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// Payload referenced by the security buffer (length, max length, offset) at `at`
+fn security_buffer(bytes: &[u8], at: usize) -> Option<&[u8]> {
+    let len = read_u16(bytes, at)? as usize;
+    let offset = read_u32(bytes, at + 4)? as usize;
+    bytes.get(offset..offset.checked_add(len)?)
+}
+
+/// Server FILETIME from the target info, if the server sent one
+fn target_timestamp(target_info: &[u8]) -> Option<[u8; 8]> {
+    let mut at = 0;
+    loop {
+        let id = read_u16(target_info, at)?;
+        let len = read_u16(target_info, at + 2)? as usize;
+        if id == MSV_AV_EOL {
+            return None;
+        }
+        let value = target_info.get(at + 4..at + 4 + len)?;
+        if id == MSV_AV_TIMESTAMP {
+            return value.try_into().ok();
+        }
+        at += 4 + len;
+    }
+}
+
+fn filetime_now() -> [u8; 8] {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    let ticks = (now.as_secs() + FILETIME_UNIX_OFFSET_SECS) * 10_000_000
+        + u64::from(now.subsec_nanos() / 100);
+    ticks.to_le_bytes()
+}
+
+/// Base64 NEGOTIATE_MESSAGE sent with the first request
+pub(super) fn negotiate_message() -> String {
+    let mut message = Vec::with_capacity(32);
+    message.extend_from_slice(SIGNATURE);
+    message.extend_from_slice(&1u32.to_le_bytes());
+    message.extend_from_slice(&NEGOTIATE_FLAGS.to_le_bytes());
+    // Empty domain and workstation buffers
+    message.extend_from_slice(&[0u8; 16]);
+    general_purpose::STANDARD.encode(message)
+}
+
+/// Parses the base64 CHALLENGE_MESSAGE from a `WWW-Authenticate: NTLM ...` header.
+pub(super) fn parse_challenge(encoded: &str) -> Result<Challenge, AppError> {
+    let bytes = general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| invalid(format!("Invalid NTLM challenge encoding: {e}")))?;
+    if bytes.get(..8) != Some(SIGNATURE.as_slice()) || read_u32(&bytes, 8) != Some(2) {
+        return Err(invalid("Server did not send an NTLM challenge message"));
+    }
+    let flags = read_u32(&bytes, 20).ok_or_else(|| invalid("Truncated NTLM challenge"))?;
+    let server_challenge = bytes
+        .get(24..32)
+        .and_then(|c| c.try_into().ok())
+        .ok_or_else(|| invalid("Truncated NTLM challenge"))?;
+    let target_info = if flags & NEGOTIATE_TARGET_INFO != 0 {
+        security_buffer(&bytes, 40)
+            .ok_or_else(|| invalid("NTLM challenge target info is out of bounds"))?
+            .to_vec()
+    } else {
+        Vec::new()
+    };
+    Ok(Challenge {
+        flags,
+        server_challenge,
+        target_info,
+    })
+}
+
+/// NTLMv2 and LMv2 responses to `challenge`
+fn responses(
+    challenge: &Challenge,
+    response_key: &[u8; 16],
+    client_challenge: [u8; 8],
+    timestamp: [u8; 8],
+) -> (Vec<u8>, Vec<u8>) {
+    let mut blob = vec![1u8, 1, 0, 0, 0, 0, 0, 0];
+    blob.extend_from_slice(&timestamp);
+    blob.extend_from_slice(&client_challenge);
+    blob.extend_from_slice(&[0; 4]);
+    blob.extend_from_slice(&challenge.target_info);
+    blob.extend_from_slice(&[0; 4]);
+
+    let proof = hmac_md5(response_key, &[&challenge.server_challenge, &blob]);
+    let mut nt_response = proof.to_vec();
+    nt_response.extend_from_slice(&blob);
+
+    let mut lm_response = hmac_md5(
+        response_key,
+        &[&challenge.server_challenge, &client_challenge],
+    )
+    .to_vec();
+    lm_response.extend_from_slice(&client_challenge);
+    (nt_response, lm_response)
+}
+
+/// Base64 AUTHENTICATE_MESSAGE answering `challenge`
+pub(super) fn authenticate_message(challenge: &Challenge, credentials: &NtlmCredentials) -> String {
+    let (user, domain) = user_and_domain(credentials);
+    let response_key = ntowf_v2(&user, &credentials.password, &domain);
+    let server_time = target_timestamp(&challenge.target_info);
+    let (nt_response, lm_response) = responses(
+        challenge,
+        &response_key,
+        rand::random(),
+        server_time.unwrap_or_else(filetime_now),
+    );
+    // With a server timestamp the LMv2 response must be zeroed (MS-NLMP 3.1.5.1.2)
+    let lm_response = if server_time.is_some() {
+        vec![0; 24]
+    } else {
+        lm_response
+    };
+
+    let workstation = credentials.workstation.clone().unwrap_or_default();
+    let payloads = [
+        lm_response,
+        nt_response,
+        utf16le(&domain),
+        utf16le(&user),
+        utf16le(&workstation),
+        Vec::new(),
+    ];
+    let flags = (challenge.flags & NEGOTIATE_FLAGS & !NEGOTIATE_OEM) | NEGOTIATE_UNICODE;
+
+    const HEADER_LEN: usize = 64;
+    let mut message = Vec::with_capacity(HEADER_LEN + payloads.iter().map(Vec::len).sum::<usize>());
+    message.extend_from_slice(SIGNATURE);
+    message.extend_from_slice(&3u32.to_le_bytes());
+    let mut offset = HEADER_LEN;
+    for payload in &payloads {
+        let len = payload.len() as u16;
+        message.extend_from_slice(&len.to_le_bytes());
+        message.extend_from_slice(&len.to_le_bytes());
+        message.extend_from_slice(&(offset as u32).to_le_bytes());
+        offset += payload.len();
+    }
+    message.extend_from_slice(&flags.to_le_bytes());
+    for payload in &payloads {
+        message.extend_from_slice(payload);
+    }
+    general_purpose::STANDARD.encode(message)
+}
+
+/// Runs the negotiate/challenge exchange for `method uri` and returns the `Authorization`
+/// header that authenticates the connection. The connection must be kept alive for the
+/// request that carries it, so `client` must pool a single connection.
+///
+/// Returns `None` when the server doesn't ask for authentication.
+pub(super) async fn handshake(
+    client: &EngineClient,
+    method: &Method,
+    uri: &Uri,
+    headers: &HeaderMap,
+    credentials: &NtlmCredentials,
+    logger: &RequestLogger,
+    timeout_secs: u64,
+) -> Result<Option<HeaderValue>, AppError> {
+    let mut builder = HyperRequest::builder()
+        .method(method.clone())
+        .uri(uri.clone());
+    if let Some(headers_mut) = builder.headers_mut() {
+        for (name, value) in headers.iter() {
+            if name != hyper::header::CONTENT_ENCODING {
+                headers_mut.append(name.clone(), value.clone());
+            }
+        }
+        headers_mut.insert(
+            hyper::header::AUTHORIZATION,
+            HeaderValue::try_from(format!("NTLM {}", negotiate_message()))
+                .expect("base64 is a valid header value"),
+        );
+    }
+    // The body is only sent once the connection is authenticated
+    let negotiate = builder
+        .body(BodySource::empty().to_body(None)?)
+        .map_err(|e| invalid(format!("Failed to build request: {e}")))?;
+    logger.info(
+        "auth",
+        Some("ntlm_negotiate"),
+        "Sending NTLM negotiate message",
+        None,
+    );
+
+    let call = CURRENT_LOGGER.scope(logger.clone(), client.request(negotiate));
+    let response = match timeout(Duration::from_secs(timeout_secs), call).await {
+        Ok(Ok(response)) => response,
+        Ok(Err(e)) => {
+            return Err(AppError::new(
+                ErrorKind::HttpError,
+                format!("NTLM negotiation failed: {e}"),
+            ));
+        }
+        Err(_) => {
+            return Err(AppError::new(
+                ErrorKind::Timeout,
+                "NTLM negotiation timed out",
+            ));
+        }
+    };
+    let status = response.status();
+    let challenge = response
+        .headers()
+        .get_all(hyper::header::WWW_AUTHENTICATE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .find_map(|value| {
+            let (scheme, token) = value.trim().split_once(' ')?;
+            scheme
+                .eq_ignore_ascii_case("NTLM")
+                .then(|| token.to_string())
+        });
+    // Drain the body so the connection goes back to the pool for the next step
+    let _ = response.into_body().collect().await;
+
+    if status != StatusCode::UNAUTHORIZED {
+        logger.info(
+            "auth",
+            Some("ntlm_skipped"),
+            format!("Server answered the negotiate message with {status}; sending without NTLM"),
+            Some(json!({"status": status.as_u16()})),
+        );
+        return Ok(None);
+    }
+    let Some(challenge) = challenge else {
+        return Err(AppError::new(
+            ErrorKind::HttpError,
+            "Server did not offer NTLM authentication",
+        ));
+    };
+    let challenge = parse_challenge(&challenge)?;
+    logger.info(
+        "auth",
+        Some("ntlm_challenge"),
+        "Received NTLM challenge; sending authenticate message",
+        Some(json!({"flags": format!("{:#010x}", challenge.flags)})),
+    );
+    let authorization = format!("NTLM {}", authenticate_message(&challenge, credentials));
+    Ok(Some(
+        HeaderValue::try_from(authorization).expect("base64 is a valid header value"),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Challenge, authenticate_message, negotiate_message, ntowf_v2, parse_challenge, responses,
+    };
+    use crate::http_client::request::NtlmCredentials;
+    use base64::{Engine as _, engine::general_purpose};
+
+    /// Target info from the MS-NLMP 4.2.4 example: NetBIOS domain "Domain", server "Server"
+    const TARGET_INFO: &str =
+        "02000c0044006f006d00610069006e0001000c0053006500720076006500720000000000";
+
+    #[test]
+    fn matches_ms_nlmp_ntlmv2_example() {
+        let key = ntowf_v2("User", "Password", "Domain");
+        assert_eq!(hex::encode(key), "0c868a403bfd7a93a3001ef22ef02e3f");
+
+        let challenge = Challenge {
+            flags: 0,
+            server_challenge: [0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef],
+            target_info: hex::decode(TARGET_INFO).unwrap(),
+        };
+        let (nt, lm) = responses(&challenge, &key, [0xaa; 8], [0; 8]);
+        assert_eq!(hex::encode(&nt[..16]), "68cd0ab851e51c96aabc927bebef6a1c");
+        assert_eq!(
+            hex::encode(lm),
+            "86c35097ac9cec102554764a57cccc19aaaaaaaaaaaaaaaa"
+        );
+    }
+
+    #[test]
+    fn round_trips_messages() {
+        let negotiate = general_purpose::STANDARD
+            .decode(negotiate_message())
+            .unwrap();
+        assert_eq!(&negotiate[..12], b"NTLMSSP\0\x01\0\0\0");
+
+        // CHALLENGE_MESSAGE with target info at offset 48
+        let target_info = hex::decode(TARGET_INFO).unwrap();
+        let mut message = b"NTLMSSP\0".to_vec();
+        message.extend_from_slice(&2u32.to_le_bytes());
+        message.extend_from_slice(&[0; 8]);
+        message.extend_from_slice(&0x0088_8205u32.to_le_bytes());
+        message.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        message.extend_from_slice(&[0; 8]);
+        let len = target_info.len() as u16;
+        message.extend_from_slice(&len.to_le_bytes());
+        message.extend_from_slice(&len.to_le_bytes());
+        message.extend_from_slice(&48u32.to_le_bytes());
+        message.extend_from_slice(&target_info);
+        let challenge = parse_challenge(&general_purpose::STANDARD.encode(&message)).unwrap();
+        assert_eq!(challenge.server_challenge, [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(challenge.target_info, target_info);
+
+        let credentials = NtlmCredentials {
+            username: "CORP\\alice".into(),
+            password: "secret".into(),
+            domain: None,
+            workstation: Some("WS01".into()),
+        };
+        let authenticate = general_purpose::STANDARD
+            .decode(authenticate_message(&challenge, &credentials))
+            .unwrap();
+        assert_eq!(&authenticate[..12], b"NTLMSSP\0\x03\0\0\0");
+        // Domain buffer (at 28) holds "CORP" in UTF-16LE
+        let domain_len = u16::from_le_bytes([authenticate[28], authenticate[29]]) as usize;
+        let domain_at = u32::from_le_bytes(authenticate[32..36].try_into().unwrap()) as usize;
+        assert_eq!(
+            &authenticate[domain_at..domain_at + domain_len],
+            b"C\0O\0R\0P\0"
+        );
+
+        assert!(parse_challenge("bm90IG50bG0=").is_err());
+    }
+}
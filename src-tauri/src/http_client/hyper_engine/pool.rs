@@ -0,0 +1,362 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use hyper::http::Uri;
+use hyper_rustls::HttpsConnector;
+use hyper_util::client::legacy::Client;
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::rt::{TokioExecutor, TokioTimer};
+use serde_json::json;
+
+use super::RequestLogger;
+use super::body::RequestBody;
+use super::connector::{self, LoggingConnector, OverrideResolver};
+use super::timings::TimedConnector;
+use crate::errors::AppError;
+use crate::http_client::request::{ConnectionPool, Request, TlsVersion};
+
+pub(super) type EngineConnector =
+    LoggingConnector<HttpsConnector<TimedConnector<HttpConnector<OverrideResolver>>>>;
+
+pub(super) type EngineClient = Client<EngineConnector, RequestBody>;
+
+const DEFAULT_IDLE_TIMEOUT_SECS: u64 = 90;
+const DEFAULT_MAX_IDLE_PER_HOST: usize = 8;
+
+/// Most clients kept for transport profiles; the least recently used is dropped beyond this.
+/// Connection groups don't count: they're closed explicitly, or dropped once idle.
+const MAX_PROFILE_CLIENTS: usize = 16;
+
+/// Identifies a shared client: the connection group (if any) and the transport profile,
+/// everything that shapes its connections.
+type ClientKey = (Option<String>, String);
+
+struct SharedClient {
+    client: EngineClient,
+    last_used: Instant,
+    /// How long the pool keeps an idle connection open
+    idle_timeout: Duration,
+}
+
+/// Shared clients. Idle connections close after the pool's idle timeout; the client itself
+/// is kept until its connection group is closed or outlives its connection, or until evicted
+/// for newer profiles.
+static SHARED_CLIENTS: LazyLock<Mutex<HashMap<ClientKey, SharedClient>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// The transport profile of a request: requests may only share a client when it would have
+/// been built the same for them. The host only matters when `ip_override` applies to it;
+/// otherwise the client's pool keeps each origin's connections apart.
+fn profile_key(request: &Request, uri: &Uri, pool: Option<&ConnectionPool>) -> String {
+    // Invalid overrides fail when the client is built
+    let dns_overrides: BTreeMap<String, String> =
+        connector::dns_overrides(request, uri.host().unwrap_or_default())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(host, ip)| (host, ip.to_string()))
+            .collect();
+    json!({
+        "dnsOverrides": dns_overrides,
+        "disableSsl": request.disable_ssl,
+        "caPath": request.ca_path,
+        "insecureHosts": request.insecure_hosts,
+        "checkRevocation": request.check_revocation,
+        "tlsMinVersion": request.tls_min_version.map(TlsVersion::as_str),
+        "tlsMaxVersion": request.tls_max_version.map(TlsVersion::as_str),
+        "cipherSuites": request.cipher_suites,
+        "clientCertificate": format!("{:?}", request.client_certificate),
+        "httpVersion": format!("{:?}", request.http_version),
+        "ipFamily": format!("{:?}", request.ip_family),
+        "localAddress": request.local_address,
+        "interface": request.interface,
+        "pooled": pool.is_some(),
+        "idleTimeoutSecs": pool.and_then(|pool| pool.idle_timeout_secs),
+        "maxIdlePerHost": pool.and_then(|pool| pool.max_idle_per_host),
+    })
+    .to_string()
+}
+
+fn build_client(
+    request: &Request,
+    uri: &Uri,
+    logger: &RequestLogger,
+    pool: Option<&ConnectionPool>,
+) -> Result<EngineClient, AppError> {
+    let connector = connector::build_connector(request, uri, logger.clone())?;
+    let mut builder = Client::builder(TokioExecutor::new());
+    builder.http2_adaptive_window(true);
+    match pool {
+        Some(pool) => {
+            builder
+                .pool_timer(TokioTimer::new())
+                .pool_idle_timeout(idle_timeout(Some(pool)))
+                .pool_max_idle_per_host(
+                    pool.max_idle_per_host.unwrap_or(DEFAULT_MAX_IDLE_PER_HOST),
+                );
+        }
+        None => {
+            builder.pool_max_idle_per_host(0);
+        }
+    }
+    Ok(builder.build(connector))
+}
+
+fn idle_timeout(pool: Option<&ConnectionPool>) -> Duration {
+    Duration::from_secs(
+        pool.and_then(|pool| pool.idle_timeout_secs)
+            .unwrap_or(DEFAULT_IDLE_TIMEOUT_SECS),
+    )
+}
+
+fn shared_client(
+    key: ClientKey,
+    request: &Request,
+    uri: &Uri,
+    logger: &RequestLogger,
+    pool: Option<&ConnectionPool>,
+) -> Result<EngineClient, AppError> {
+    if let Some(shared) = SHARED_CLIENTS.lock().unwrap().get_mut(&key) {
+        shared.last_used = Instant::now();
+        logger.debug(
+            "connect",
+            Some("client_reused"),
+            "Reusing the client of this transport profile",
+            None,
+        );
+        return Ok(shared.client.clone());
+    }
+    let client = build_client(request, uri, logger, pool)?;
+    let mut clients = SHARED_CLIENTS.lock().unwrap();
+    let client = clients
+        .entry(key)
+        .or_insert(SharedClient {
+            client,
+            last_used: Instant::now(),
+            idle_timeout: idle_timeout(pool),
+        })
+        .client
+        .clone();
+    evict_clients(&mut clients, Instant::now());
+    Ok(client)
+}
+
+/// Drops connection groups left idle past their pool's idle timeout, e.g. by a sequence that
+/// never closed its group, as their connection has closed by then. Then drops the least
+/// recently used profile clients beyond [`MAX_PROFILE_CLIENTS`]. Open connections close once
+/// no request is using them.
+fn evict_clients(clients: &mut HashMap<ClientKey, SharedClient>, now: Instant) {
+    clients.retain(|(group, _), shared| {
+        group.is_none() || now.duration_since(shared.last_used) <= shared.idle_timeout
+    });
+    loop {
+        let profiles = clients.keys().filter(|(group, _)| group.is_none());
+        if profiles.clone().count() <= MAX_PROFILE_CLIENTS {
+            return;
+        }
+        let Some(oldest) = profiles.min_by_key(|key| clients[*key].last_used).cloned() else {
+            return;
+        };
+        clients.remove(&oldest);
+    }
+}
+
+/// Returns the client to send `request` with. Requests with the same transport profile share
+/// a client, and with pooling enabled its idle connections, unless the pool settings opt out
+/// of sharing. A connection group gets a client of its own that keeps a single idle
+/// connection per host.
+pub(super) fn client_for(
+    request: &Request,
+    uri: &Uri,
+    logger: &RequestLogger,
+) -> Result<EngineClient, AppError> {
+    if let Some(group) = request
+        .connection_group
+        .as_ref()
+        .filter(|group| !group.is_empty())
+    {
+        let pool = ConnectionPool {
+            enabled: true,
+            max_idle_per_host: Some(1),
+            ..request.connection_pool.clone().unwrap_or_default()
+        };
+        logger.info(
+            "connect",
+            Some("policy"),
+            format!("Sharing one connection with connection group '{group}'"),
+            Some(json!({
+                "connectionGroup": group,
+                "poolIdleTimeoutSecs": pool.idle_timeout_secs.unwrap_or(DEFAULT_IDLE_TIMEOUT_SECS),
+            })),
+        );
+        let key = (Some(group.clone()), profile_key(request, uri, Some(&pool)));
+        return shared_client(key, request, uri, logger, Some(&pool));
+    }
+
+    let share_client = request
+        .connection_pool
+        .as_ref()
+        .and_then(|pool| pool.share_client)
+        .unwrap_or(true);
+    let Some(pool) = request.connection_pool.as_ref().filter(|pool| pool.enabled) else {
+        logger.info(
+            "connect",
+            Some("policy"),
+            "Connection reuse disabled (no pooling)",
+            Some(json!({"poolMaxIdlePerHost": 0, "shareClient": share_client})),
+        );
+        if !share_client {
+            return build_client(request, uri, logger, None);
+        }
+        let key = (None, profile_key(request, uri, None));
+        return shared_client(key, request, uri, logger, None);
+    };
+
+    logger.info(
+        "connect",
+        Some("policy"),
+        "Connection reuse enabled (keep-alive pooling)",
+        Some(json!({
+            "poolMaxIdlePerHost": pool.max_idle_per_host.unwrap_or(DEFAULT_MAX_IDLE_PER_HOST),
+            "poolIdleTimeoutSecs": pool.idle_timeout_secs.unwrap_or(DEFAULT_IDLE_TIMEOUT_SECS),
+            "shareClient": share_client,
+        })),
+    );
+    if !share_client {
+        // Connections can only be reused within this request, e.g. across its redirects
+        return build_client(request, uri, logger, Some(pool));
+    }
+    shared_client(
+        (None, profile_key(request, uri, Some(pool))),
+        request,
+        uri,
+        logger,
+        Some(pool),
+    )
+}
+
+/// A client of its own that keeps one connection alive between the steps of a
+/// connection-oriented handshake (e.g. NTLM).
+pub(super) fn dedicated_client(
+    request: &Request,
+    uri: &Uri,
+    logger: &RequestLogger,
+) -> Result<EngineClient, AppError> {
+    let pool = ConnectionPool {
+        enabled: true,
+        max_idle_per_host: Some(1),
+        ..request.connection_pool.clone().unwrap_or_default()
+    };
+    build_client(request, uri, logger, Some(&pool))
+}
+
+/// Drops the clients of a connection group, closing its connections once no request is
+/// using them. Returns whether the group existed.
+pub fn close_connection_group(group: &str) -> bool {
+    let mut clients = SHARED_CLIENTS.lock().unwrap();
+    let before = clients.len();
+    clients.retain(|(key_group, _), _| key_group.as_deref() != Some(group));
+    clients.len() != before
+}
+
+/// Drops the clients of transport profiles, e.g. after the trust store they verify servers
+/// with was reloaded. Connection groups keep theirs.
+pub fn clear_profile_clients() {
+    SHARED_CLIENTS
+        .lock()
+        .unwrap()
+        .retain(|(group, _), _| group.is_some());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ClientKey, MAX_PROFILE_CLIENTS, SharedClient, build_client, evict_clients, profile_key,
+    };
+    use crate::http_client::engine::test_emitters::Discard;
+    use crate::http_client::hyper_engine::RequestLogger;
+    use crate::http_client::request::{ConnectionPool, Request};
+    use hyper::http::Uri;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn profile_key_separates_connection_settings() {
+        let pool = ConnectionPool {
+            enabled: true,
+            ..ConnectionPool::default()
+        };
+        let uri: Uri = "https://api.example.com/users".parse().unwrap();
+        let other_host: Uri = "https://example.com/orders?page=2".parse().unwrap();
+        let request = Request::default();
+
+        // Origins share a profile; the client's pool keeps their connections apart
+        assert_eq!(
+            profile_key(&request, &uri, Some(&pool)),
+            profile_key(&request, &other_host, Some(&pool))
+        );
+        assert_ne!(
+            profile_key(&request, &uri, Some(&pool)),
+            profile_key(&request, &uri, None)
+        );
+
+        let insecure = Request {
+            disable_ssl: Some(true),
+            ..Request::default()
+        };
+        assert_ne!(
+            profile_key(&request, &uri, Some(&pool)),
+            profile_key(&insecure, &uri, Some(&pool))
+        );
+
+        // A DNS override only applies to its host
+        let overridden = Request {
+            ip_override: Some("127.0.0.1".to_string()),
+            ..Request::default()
+        };
+        assert_ne!(
+            profile_key(&overridden, &uri, None),
+            profile_key(&overridden, &other_host, None)
+        );
+    }
+
+    #[tokio::test]
+    async fn eviction_drops_the_least_recently_used_profiles_and_idle_groups() {
+        let uri: Uri = "https://api.example.com/".parse().unwrap();
+        let logger = RequestLogger::new(Arc::new(Discard), "test".to_string(), Instant::now());
+        let client = build_client(&Request::default(), &uri, &logger, None).unwrap();
+        let start = Instant::now();
+        let mut clients: HashMap<ClientKey, SharedClient> = (0..=MAX_PROFILE_CLIENTS)
+            .map(|i| {
+                let key = (None, i.to_string());
+                let last_used = start + Duration::from_secs(i as u64);
+                let shared = SharedClient {
+                    client: client.clone(),
+                    last_used,
+                    idle_timeout: Duration::from_secs(1),
+                };
+                (key, shared)
+            })
+            .collect();
+        let now = start + Duration::from_secs(MAX_PROFILE_CLIENTS as u64);
+        for (group, last_used) in [("active", now), ("abandoned", start)] {
+            clients.insert(
+                (Some(group.to_string()), "0".to_string()),
+                SharedClient {
+                    client: client.clone(),
+                    last_used,
+                    idle_timeout: Duration::from_secs(10),
+                },
+            );
+        }
+
+        evict_clients(&mut clients, now);
+        assert_eq!(clients.len(), MAX_PROFILE_CLIENTS + 1);
+        assert!(!clients.contains_key(&(None, "0".to_string())));
+        // Profiles stay however long they're idle, until evicted for newer ones
+        assert!(clients.contains_key(&(None, "1".to_string())));
+        assert!(clients.contains_key(&(Some("active".to_string()), "0".to_string())));
+        assert!(!clients.contains_key(&(Some("abandoned".to_string()), "0".to_string())));
+    }
+}
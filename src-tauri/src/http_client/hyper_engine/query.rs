@@ -0,0 +1,81 @@
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+
+use crate::http_client::request::QueryParam;
+
+/// Everything but RFC 3986 unreserved characters, so names and values can hold `&`, `=`, `+`
+/// and the like
+const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Appends `params` to the query of `url`, ahead of any fragment.
+pub(crate) fn with_query_params(url: &str, params: &[QueryParam]) -> String {
+    if params.is_empty() {
+        return url.to_string();
+    }
+    let (base, fragment) = match url.find('#') {
+        Some(index) => url.split_at(index),
+        None => (url, ""),
+    };
+
+    let mut out = String::with_capacity(url.len() + params.len() * 16);
+    out.push_str(base);
+    let mut separator = match base.find('?') {
+        None => Some('?'),
+        Some(_) if base.ends_with('?') || base.ends_with('&') => None,
+        Some(_) => Some('&'),
+    };
+    for param in params {
+        if let Some(separator) = separator {
+            out.push(separator);
+        }
+        separator = Some('&');
+        if param.encode {
+            out.extend(utf8_percent_encode(&param.name, COMPONENT));
+            out.push('=');
+            out.extend(utf8_percent_encode(&param.value, COMPONENT));
+        } else {
+            out.push_str(&param.name);
+            out.push('=');
+            out.push_str(&param.value);
+        }
+    }
+    out.push_str(fragment);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::with_query_params;
+    use crate::http_client::request::QueryParam;
+
+    fn params(json: &str) -> Vec<QueryParam> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn appends_encoded_params() {
+        let list = params(
+            r#"[["q", "a b&c=d"], ["tag", "ü"], {"name": "range", "value": "1,5%2C", "encode": false}]"#,
+        );
+        assert_eq!(
+            with_query_params("https://example.com/search", &list),
+            "https://example.com/search?q=a%20b%26c%3Dd&tag=%C3%BC&range=1,5%2C"
+        );
+        // After the existing query, ahead of the fragment
+        assert_eq!(
+            with_query_params("https://example.com/?page=2#top", &list[1..2]),
+            "https://example.com/?page=2&tag=%C3%BC#top"
+        );
+        assert_eq!(
+            with_query_params("https://example.com/?", &list[1..2]),
+            "https://example.com/?tag=%C3%BC"
+        );
+        assert_eq!(
+            with_query_params("https://example.com/", &[]),
+            "https://example.com/"
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use chrono::{DateTime, Utc};
+use hyper::http::HeaderMap;
+
+use crate::http_client::response::RateLimit;
+
+/// Reset values above this are Unix timestamps rather than delta-seconds
+const EPOCH_THRESHOLD: u64 = 1_000_000_000;
+
+/// Leading integer of the first list member, e.g. `100` in `100, 100;w=60`
+fn leading_number(value: &str) -> Option<u64> {
+    let first = value.split(',').next()?.split(';').next()?.trim();
+    first.trim_matches('"').parse().ok()
+}
+
+/// Value of parameter `key` in the first member of a structured field,
+/// e.g. `r` in `"default";r=50;t=30`
+fn parameter(value: &str, key: &str) -> Option<u64> {
+    value
+        .split(',')
+        .next()?
+        .split(';')
+        .skip(1)
+        .filter_map(|param| param.split_once('='))
+        .find(|(k, _)| k.trim() == key)
+        .and_then(|(_, v)| v.trim().parse().ok())
+}
+
+fn header<'a>(headers: &'a HeaderMap, names: &[&str]) -> Option<&'a str> {
+    names
+        .iter()
+        .find_map(|name| headers.get(*name))
+        .and_then(|value| value.to_str().ok())
+}
+
+fn seconds_until(reset: u64, now: DateTime<Utc>) -> u64 {
+    if reset > EPOCH_THRESHOLD {
+        reset.saturating_sub(now.timestamp().max(0) as u64)
+    } else {
+        reset
+    }
+}
+
+/// Reads the quota a server advertises, from the IETF `RateLimit`/`RateLimit-Policy` fields
+/// (structured, `"name";r=..;t=..` and `q=..;w=..`), the earlier `RateLimit-Limit`/`-Remaining`/
+/// `-Reset` drafts, or the widespread `X-RateLimit-*` headers.
+pub(super) fn parse_rate_limit(headers: &HeaderMap, now: DateTime<Utc>) -> Option<RateLimit> {
+    let structured = header(headers, &["ratelimit"]).filter(|v| v.contains(';'));
+    let policy = header(headers, &["ratelimit-policy", "x-ratelimit-policy"]);
+
+    let limit = header(headers, &["ratelimit-limit", "x-ratelimit-limit"])
+        .and_then(leading_number)
+        .or_else(|| policy.and_then(|p| parameter(p, "q").or_else(|| leading_number(p))));
+    let remaining = structured.and_then(|v| parameter(v, "r")).or_else(|| {
+        header(headers, &["ratelimit-remaining", "x-ratelimit-remaining"]).and_then(leading_number)
+    });
+    let reset_secs = structured
+        .and_then(|v| parameter(v, "t"))
+        .or_else(|| {
+            header(headers, &["ratelimit-reset", "x-ratelimit-reset"]).and_then(leading_number)
+        })
+        .map(|reset| seconds_until(reset, now));
+
+    if limit.is_none() && remaining.is_none() && reset_secs.is_none() {
+        return None;
+    }
+    Some(RateLimit {
+        limit,
+        remaining,
+        reset_secs,
+        policy: policy.map(str::to_string),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_rate_limit;
+    use chrono::{DateTime, Utc};
+    use hyper::http::{HeaderMap, HeaderName, HeaderValue};
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(k, v)| (HeaderName::from_static(k), HeaderValue::from_static(v)))
+            .collect()
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000, 0).unwrap()
+    }
+
+    #[test]
+    fn reads_structured_and_legacy_headers() {
+        let structured = parse_rate_limit(
+            &headers(&[
+                ("ratelimit", "\"default\";r=0;t=30"),
+                ("ratelimit-policy", "\"default\";q=100;w=60"),
+            ]),
+            now(),
+        )
+        .unwrap();
+        assert_eq!(structured.limit, Some(100));
+        assert_eq!(structured.remaining, Some(0));
+        assert_eq!(structured.reset_secs, Some(30));
+
+        let draft = parse_rate_limit(
+            &headers(&[
+                ("ratelimit-limit", "100, 100;w=60"),
+                ("ratelimit-remaining", "7"),
+                ("ratelimit-reset", "12"),
+            ]),
+            now(),
+        )
+        .unwrap();
+        assert_eq!(
+            (draft.limit, draft.remaining, draft.reset_secs),
+            (Some(100), Some(7), Some(12))
+        );
+
+        // GitHub style: reset is a Unix timestamp
+        let legacy = parse_rate_limit(
+            &headers(&[
+                ("x-ratelimit-limit", "5000"),
+                ("x-ratelimit-remaining", "0"),
+                ("x-ratelimit-reset", "1700000090"),
+            ]),
+            now(),
+        )
+        .unwrap();
+        assert_eq!(legacy.reset_secs, Some(90));
+
+        assert!(parse_rate_limit(&headers(&[("content-type", "text/plain")]), now()).is_none());
+    }
+}
@@ -0,0 +1,436 @@
+use std::fmt;
+use std::future::poll_fn;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use hyper::body::Body;
+use hyper::body::Incoming;
+use hyper::http::{HeaderMap, HeaderName, Method, Uri, header};
+use hyper::rt::{Read, ReadBufCursor, Write};
+use hyper::{Request as HyperRequest, Response};
+use serde_json::json;
+use tower_service::Service;
+
+use super::RequestLogger;
+use super::body::RequestBody;
+use super::connector;
+use super::pool::EngineConnector;
+use crate::errors::AppError;
+use crate::http_client::request::{HttpVersionPref, Request};
+
+/// Why sending a request failed, from the shared client or a raw connection
+#[derive(Debug)]
+pub(super) enum SendError {
+    Client(hyper_util::client::legacy::Error),
+    Raw(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendError::Client(err) => err.fmt(f),
+            SendError::Raw(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for SendError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SendError::Client(err) => err.source(),
+            SendError::Raw(err) => err.source(),
+        }
+    }
+}
+
+/// Sends requests with a head of its own making, each over a new HTTP/1.1 connection: the
+/// listed headers exactly as written in raw header mode, and Host and Content-Length only
+/// when they aren't turned off.
+pub(super) struct RawSender {
+    connector: EngineConnector,
+    listed: Vec<(String, String)>,
+    host_override: Option<String>,
+    send_host: bool,
+    send_content_length: bool,
+}
+
+impl RawSender {
+    pub(super) fn new(
+        request: &Request,
+        uri: &Uri,
+        logger: &RequestLogger,
+    ) -> Result<Self, AppError> {
+        let request = Request {
+            http_version: Some(HttpVersionPref::Http1),
+            ..request.clone()
+        };
+        let listed = if request.raw_headers.unwrap_or(false) {
+            request.headers.clone().unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        Ok(Self {
+            connector: connector::build_connector(&request, uri, logger.clone())?,
+            listed,
+            host_override: request.host_override.clone(),
+            send_host: request.sends_auto_header(|auto| auto.host),
+            send_content_length: request.sends_auto_header(|auto| auto.content_length),
+        })
+    }
+
+    pub(super) async fn send(
+        &self,
+        request: HyperRequest<RequestBody>,
+        logger: &RequestLogger,
+    ) -> Result<Response<Incoming>, SendError> {
+        let automatic = Automatic {
+            host: self
+                .send_host
+                .then(|| host_header(self.host_override.as_deref(), request.uri()))
+                .flatten(),
+            content_length: self
+                .send_content_length
+                .then(|| request.body().size_hint().exact())
+                .flatten()
+                .filter(|len| *len > 0),
+        };
+        let head = raw_head(
+            request.method(),
+            request.uri(),
+            &self.listed,
+            request.headers(),
+            &automatic,
+        );
+        logger.debug(
+            "http",
+            Some("raw_head"),
+            "Sending the header block as written",
+            Some(json!({"head": String::from_utf8_lossy(&head)})),
+        );
+
+        let mut connector = self.connector.clone();
+        poll_fn(|cx| connector.poll_ready(cx))
+            .await
+            .map_err(SendError::Raw)?;
+        let stream = connector
+            .call(request.uri().clone())
+            .await
+            .map_err(SendError::Raw)?;
+        let (mut sender, connection) =
+            hyper::client::conn::http1::handshake(RawHeadIo::new(stream, head))
+                .await
+                .map_err(|e| SendError::Raw(e.into()))?;
+        tokio::spawn(async move {
+            let _ = connection.await;
+        });
+        sender
+            .send_request(request)
+            .await
+            .map_err(|e| SendError::Raw(e.into()))
+    }
+}
+
+/// Headers hyper would have added to the head
+#[derive(Default)]
+struct Automatic {
+    host: Option<String>,
+    content_length: Option<u64>,
+}
+
+/// The Host header hyper sends: the overridden or URL host, with any non-default port
+fn host_header(host_override: Option<&str>, uri: &Uri) -> Option<String> {
+    let host = connector::compute_host_header(host_override, uri.host())?;
+    let default_port = match uri.scheme_str() {
+        Some("https") => 443,
+        _ => 80,
+    };
+    Some(match uri.port_u16() {
+        Some(port) if port != default_port => format!("{host}:{port}"),
+        _ => host,
+    })
+}
+
+/// The request head: Host, then the listed headers as written, in order, then any other the
+/// request carries (auth, content type, signatures), then Content-Length.
+fn raw_head(
+    method: &Method,
+    uri: &Uri,
+    listed: &[(String, String)],
+    headers: &HeaderMap,
+    automatic: &Automatic,
+) -> Bytes {
+    let target = uri.path_and_query().map_or("/", |target| target.as_str());
+    let sets = |name: &HeaderName| {
+        headers.contains_key(name)
+            || listed
+                .iter()
+                .any(|(listed, _)| listed.eq_ignore_ascii_case(name.as_str()))
+    };
+    let mut head = BytesMut::new();
+    head.put_slice(format!("{method} {target} HTTP/1.1\r\n").as_bytes());
+    if let Some(host) = &automatic.host
+        && !sets(&header::HOST)
+    {
+        head.put_slice(format!("host: {host}\r\n").as_bytes());
+    }
+    for (name, value) in listed {
+        head.put_slice(format!("{name}: {value}\r\n").as_bytes());
+    }
+    for (name, value) in headers {
+        if listed
+            .iter()
+            .any(|(listed, _)| listed.eq_ignore_ascii_case(name.as_str()))
+        {
+            continue;
+        }
+        head.put_slice(name.as_str().as_bytes());
+        head.put_slice(b": ");
+        head.put_slice(value.as_bytes());
+        head.put_slice(b"\r\n");
+    }
+    if let Some(len) = automatic.content_length
+        && !sets(&header::CONTENT_LENGTH)
+    {
+        head.put_slice(format!("content-length: {len}\r\n").as_bytes());
+    }
+    head.put_slice(b"\r\n");
+    head.freeze()
+}
+
+/// Writes `head` in place of the head hyper writes, then passes the body and everything read
+/// through unchanged.
+struct RawHeadIo<T> {
+    inner: T,
+    head: Bytes,
+    /// Bytes of `head` written so far
+    written: usize,
+    /// Bytes of the blank line ending hyper's head seen so far; `None` once it's all seen
+    matched: Option<usize>,
+}
+
+impl<T> RawHeadIo<T> {
+    fn new(inner: T, head: Bytes) -> Self {
+        Self {
+            inner,
+            head,
+            written: 0,
+            matched: Some(0),
+        }
+    }
+}
+
+const HEAD_END: &[u8; 4] = b"\r\n\r\n";
+
+impl<T: Read + Unpin> Read for RawHeadIo<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: ReadBufCursor<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<T: Write + Unpin> Write for RawHeadIo<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        let Some(mut matched) = this.matched else {
+            return Pin::new(&mut this.inner).poll_write(cx, buf);
+        };
+        while this.written < this.head.len() {
+            let n = ready!(Pin::new(&mut this.inner).poll_write(cx, &this.head[this.written..]))?;
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            this.written += n;
+        }
+        // Swallow hyper's own head, which may share a buffer with the start of the body
+        for (index, &byte) in buf.iter().enumerate() {
+            matched = if byte == HEAD_END[matched] {
+                matched + 1
+            } else {
+                usize::from(byte == b'\r')
+            };
+            if matched == HEAD_END.len() {
+                this.matched = None;
+                return Poll::Ready(Ok(index + 1));
+            }
+        }
+        this.matched = Some(matched);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Automatic, RawHeadIo, host_header, raw_head};
+    use crate::http_client::engine::HttpEngine;
+    use crate::http_client::engine::test_emitters::Discard;
+    use crate::http_client::hyper_engine::HyperEngine;
+    use crate::http_client::request::Request;
+    use bytes::Bytes;
+    use hyper::http::{HeaderMap, HeaderValue, Method, header};
+    use hyper_util::rt::TokioIo;
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn sends_the_request_as_written() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut received = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !received.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                received.extend_from_slice(&buf[..n]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok")
+                .await
+                .unwrap();
+            String::from_utf8(received).unwrap()
+        });
+
+        let request = Request {
+            request_id: "raw-headers".to_string(),
+            url: format!("http://127.0.0.1:{port}/path"),
+            method: "GET".to_string(),
+            headers: Some(vec![
+                ("HOST".to_string(), "example.test".to_string()),
+                ("X-Mixed-Case".to_string(), "a".to_string()),
+                ("x-mixed-case".to_string(), "b".to_string()),
+            ]),
+            raw_headers: Some(true),
+            ..Request::default()
+        };
+        let response = HyperEngine::new()
+            .execute(request, Arc::new(Discard))
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"ok");
+        assert_eq!(
+            server.await.unwrap(),
+            "GET /path HTTP/1.1\r\n\
+             HOST: example.test\r\n\
+             X-Mixed-Case: a\r\n\
+             x-mixed-case: b\r\n\
+             \r\n"
+        );
+    }
+
+    #[test]
+    fn writes_listed_headers_as_given() {
+        let listed = vec![
+            ("X-CASE".to_string(), "1".to_string()),
+            ("host".to_string(), "example.com".to_string()),
+            ("x-case".to_string(), "2".to_string()),
+        ];
+        let mut headers = HeaderMap::new();
+        headers.insert("x-case", HeaderValue::from_static("1"));
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_static("Bearer t"));
+        let head = raw_head(
+            &Method::POST,
+            &"https://example.com/a?b=c".parse().unwrap(),
+            &listed,
+            &headers,
+            &Automatic::default(),
+        );
+        assert_eq!(
+            head,
+            "POST /a?b=c HTTP/1.1\r\n\
+             X-CASE: 1\r\n\
+             host: example.com\r\n\
+             x-case: 2\r\n\
+             authorization: Bearer t\r\n\
+             \r\n"
+        );
+    }
+
+    #[test]
+    fn adds_the_automatic_headers_left_on() {
+        let uri = "https://example.com:8443/a".parse().unwrap();
+        assert_eq!(host_header(None, &uri).as_deref(), Some("example.com:8443"));
+        assert_eq!(
+            host_header(
+                Some("other.test:443"),
+                &"https://example.com/".parse().unwrap()
+            )
+            .as_deref(),
+            Some("other.test")
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-a", HeaderValue::from_static("1"));
+        let automatic = Automatic {
+            host: host_header(None, &uri),
+            content_length: Some(2),
+        };
+        assert_eq!(
+            raw_head(&Method::PUT, &uri, &[], &headers, &automatic),
+            "PUT /a HTTP/1.1\r\n\
+             host: example.com:8443\r\n\
+             x-a: 1\r\n\
+             content-length: 2\r\n\
+             \r\n"
+        );
+        // Left out when the request sets them itself
+        headers.insert(header::HOST, HeaderValue::from_static("mine"));
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from_static("2"));
+        let head = raw_head(&Method::PUT, &uri, &[], &headers, &automatic);
+        assert_eq!(
+            head,
+            "PUT /a HTTP/1.1\r\nx-a: 1\r\nhost: mine\r\ncontent-length: 2\r\n\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn replaces_the_head_hyper_writes() {
+        let (client, mut server) = tokio::io::duplex(1024);
+        let mut io = RawHeadIo::new(TokioIo::new(client), Bytes::from_static(b"RAW\r\n\r\n"));
+        let written = std::future::poll_fn(|cx| {
+            hyper::rt::Write::poll_write(
+                std::pin::Pin::new(&mut io),
+                cx,
+                b"GET / HTTP/1.1\r\nhost: x\r\n\r",
+            )
+        })
+        .await
+        .unwrap();
+        assert_eq!(written, 26);
+        let written = std::future::poll_fn(|cx| {
+            hyper::rt::Write::poll_write(std::pin::Pin::new(&mut io), cx, b"\nbody")
+        })
+        .await
+        .unwrap();
+        // Only hyper's head is swallowed
+        assert_eq!(written, 1);
+        std::future::poll_fn(|cx| {
+            hyper::rt::Write::poll_write(std::pin::Pin::new(&mut io), cx, b"body")
+        })
+        .await
+        .unwrap();
+        drop(io);
+
+        let mut sent = Vec::new();
+        server.read_to_end(&mut sent).await.unwrap();
+        assert_eq!(sent, b"RAW\r\n\r\nbody");
+    }
+}
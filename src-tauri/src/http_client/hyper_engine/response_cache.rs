@@ -0,0 +1,169 @@
+//! Responses kept for revalidation, shared by every request that enables the cache. A request
+//! with a cached response sends its validators as `If-None-Match`/`If-Modified-Since`; a `304`
+//! answer then returns the cached response in its place.
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::SystemTime;
+
+use hyper::Method;
+use hyper::http::{HeaderMap, Uri, header};
+
+/// Cached responses at most; the oldest goes first
+const MAX_ENTRIES: usize = 256;
+
+/// Larger bodies aren't cached
+const MAX_BODY_BYTES: usize = 10 * 1024 * 1024;
+
+/// Request headers that differ between a request and its revalidation
+const CONDITIONAL_HEADERS: [header::HeaderName; 2] =
+    [header::IF_NONE_MATCH, header::IF_MODIFIED_SINCE];
+
+pub(super) struct CachedResponse {
+    pub(super) status: u16,
+    pub(super) headers: HeaderMap,
+    pub(super) body: Vec<u8>,
+    stored_at: SystemTime,
+}
+
+impl CachedResponse {
+    /// The cached headers, updated with those of the `304` that confirmed them
+    pub(super) fn revalidated_headers(&self, update: &HeaderMap) -> HeaderMap {
+        let mut headers = self.headers.clone();
+        for name in update.keys() {
+            if name == header::CONTENT_LENGTH {
+                continue;
+            }
+            headers.remove(name);
+            for value in update.get_all(name) {
+                headers.append(name.clone(), value.clone());
+            }
+        }
+        headers
+    }
+}
+
+/// A request's place in the cache, and the cached response it revalidates
+pub(super) struct CacheLookup {
+    key: String,
+    pub(super) revalidating: Option<Arc<CachedResponse>>,
+}
+
+static CACHE: LazyLock<Mutex<HashMap<String, Arc<CachedResponse>>>> = LazyLock::new(Mutex::default);
+
+/// Looks up a `GET` request by URL and headers, adding the validators of its cached response
+/// unless the request sets its own
+pub(super) fn lookup(method: &Method, uri: &Uri, headers: &mut HeaderMap) -> Option<CacheLookup> {
+    if method != Method::GET {
+        return None;
+    }
+    let mut lines: Vec<String> = headers
+        .iter()
+        .filter(|(name, _)| !CONDITIONAL_HEADERS.contains(name))
+        .map(|(name, value)| format!("{name}: {}", String::from_utf8_lossy(value.as_bytes())))
+        .collect();
+    lines.sort();
+    let key = format!("{uri}\n{}", lines.join("\n"));
+    let revalidating = CACHE.lock().unwrap().get(&key).cloned().filter(|_| {
+        !CONDITIONAL_HEADERS
+            .iter()
+            .any(|name| headers.contains_key(name))
+    });
+    if let Some(cached) = &revalidating {
+        if let Some(etag) = cached.headers.get(header::ETAG) {
+            headers.insert(header::IF_NONE_MATCH, etag.clone());
+        }
+        if let Some(modified) = cached.headers.get(header::LAST_MODIFIED) {
+            headers.insert(header::IF_MODIFIED_SINCE, modified.clone());
+        }
+    }
+    Some(CacheLookup { key, revalidating })
+}
+
+/// Whether a response with these headers may be stored and revalidated
+fn cacheable(headers: &HeaderMap) -> bool {
+    let no_store = headers
+        .get_all(header::CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|directive| directive.trim().eq_ignore_ascii_case("no-store"));
+    !no_store && (headers.contains_key(header::ETAG) || headers.contains_key(header::LAST_MODIFIED))
+}
+
+/// Keeps a `200` response for revalidation. Returns whether it was stored.
+pub(super) fn store(lookup: &CacheLookup, status: u16, headers: &HeaderMap, body: &[u8]) -> bool {
+    if status != 200 || body.len() > MAX_BODY_BYTES || !cacheable(headers) {
+        return false;
+    }
+    let mut cache = CACHE.lock().unwrap();
+    if cache.len() >= MAX_ENTRIES
+        && !cache.contains_key(&lookup.key)
+        && let Some(oldest) = cache
+            .iter()
+            .min_by_key(|(_, entry)| entry.stored_at)
+            .map(|(key, _)| key.clone())
+    {
+        cache.remove(&oldest);
+    }
+    cache.insert(
+        lookup.key.clone(),
+        Arc::new(CachedResponse {
+            status,
+            headers: headers.clone(),
+            body: body.to_vec(),
+            stored_at: SystemTime::now(),
+        }),
+    );
+    true
+}
+
+/// Drops every cached response, returning how many were dropped
+pub fn clear_response_cache() -> usize {
+    let mut cache = CACHE.lock().unwrap();
+    let cleared = cache.len();
+    cache.clear();
+    cleared
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CachedResponse, cacheable};
+    use hyper::http::{HeaderMap, HeaderValue, header};
+    use std::time::SystemTime;
+
+    #[test]
+    fn needs_validators_and_allows_storing() {
+        let mut headers = HeaderMap::new();
+        assert!(!cacheable(&headers));
+        headers.insert(header::ETAG, HeaderValue::from_static("\"v1\""));
+        assert!(cacheable(&headers));
+        headers.insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static("private, No-Store"),
+        );
+        assert!(!cacheable(&headers));
+    }
+
+    #[test]
+    fn updates_headers_from_the_revalidation() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ETAG, HeaderValue::from_static("\"v1\""));
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from_static("5"));
+        headers.insert(header::DATE, HeaderValue::from_static("yesterday"));
+        let cached = CachedResponse {
+            status: 200,
+            headers,
+            body: b"hello".to_vec(),
+            stored_at: SystemTime::now(),
+        };
+
+        let mut update = HeaderMap::new();
+        update.insert(header::DATE, HeaderValue::from_static("today"));
+        update.insert(header::CONTENT_LENGTH, HeaderValue::from_static("0"));
+        let merged = cached.revalidated_headers(&update);
+        assert_eq!(merged[header::DATE], "today");
+        assert_eq!(merged[header::CONTENT_LENGTH], "5");
+        assert_eq!(merged[header::ETAG], "\"v1\"");
+    }
+}
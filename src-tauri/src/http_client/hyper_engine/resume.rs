@@ -0,0 +1,254 @@
+//! Byte ranges, and downloads to a file that pick up where an interrupted one stopped. The
+//! validators of the resource are kept beside the file until the download completes, and sent
+//! back as `If-Range` so a resource that changed meanwhile is downloaded again from the start.
+
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+use hyper::StatusCode;
+use hyper::http::{HeaderMap, HeaderValue, header};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::RequestLogger;
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::response::ContentRange;
+
+/// What identifies the version of the resource being downloaded
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct Validators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl Validators {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let value = |name| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+        Self {
+            // Weak tags can't be sent in `If-Range`
+            etag: value(header::ETAG).filter(|etag| !etag.starts_with("W/")),
+            last_modified: value(header::LAST_MODIFIED),
+        }
+    }
+
+    fn if_range(&self) -> Option<&str> {
+        self.etag.as_deref().or(self.last_modified.as_deref())
+    }
+}
+
+/// A response body headed for a file
+pub(super) struct Download {
+    path: PathBuf,
+    /// Bytes already in the file that the request asked the server to continue from
+    offset: u64,
+}
+
+impl Download {
+    /// With `resume`, asks for the rest of a download an earlier request left unfinished at
+    /// `path`, provided it recorded how to validate it.
+    pub(super) fn prepare(
+        path: &str,
+        resume: bool,
+        headers: &mut HeaderMap,
+        logger: &RequestLogger,
+    ) -> Self {
+        let path = PathBuf::from(path);
+        let mut download = Self { path, offset: 0 };
+        if !resume {
+            return download;
+        }
+        let offset = fs::metadata(&download.path).map_or(0, |meta| meta.len());
+        let validators = download.read_validators();
+        let Some(if_range) = validators.as_ref().and_then(Validators::if_range) else {
+            logger.debug(
+                "http",
+                Some("resume"),
+                "No interrupted download to resume; starting from the beginning",
+                None,
+            );
+            return download;
+        };
+        if offset == 0 {
+            return download;
+        }
+        if let Ok(if_range) = HeaderValue::try_from(if_range) {
+            headers.insert(
+                header::RANGE,
+                HeaderValue::try_from(format!("bytes={offset}-")).unwrap(),
+            );
+            headers.insert(header::IF_RANGE, if_range);
+            download.offset = offset;
+            logger.info(
+                "http",
+                Some("resume"),
+                format!("Resuming the download from byte {offset}"),
+                Some(json!({"offset": offset})),
+            );
+        }
+        download
+    }
+
+    /// Opens the file for a response's body, appending when the response continues the
+    /// download and starting over otherwise. `None` when the server couldn't satisfy the
+    /// range, leaving the file alone and the body in the response. Also returns the offset
+    /// appended at.
+    pub(super) fn open(
+        &self,
+        status: StatusCode,
+        headers: &HeaderMap,
+        logger: &RequestLogger,
+    ) -> Result<Option<(File, Option<u64>)>, AppError> {
+        let display = self.path.display();
+        let failed = |e: std::io::Error| {
+            AppError::new(
+                AppError::from(e).kind,
+                format!("Failed to open download file '{display}'"),
+            )
+        };
+        if self.offset > 0 {
+            if status == StatusCode::RANGE_NOT_SATISFIABLE {
+                logger.warn(
+                    "http",
+                    Some("resume"),
+                    "The server can't continue the download; the file was left as it is",
+                    None,
+                );
+                return Ok(None);
+            }
+            let continues = status == StatusCode::PARTIAL_CONTENT
+                && content_range(headers).is_some_and(|range| range.start == self.offset);
+            if continues {
+                let file = OpenOptions::new()
+                    .append(true)
+                    .open(&self.path)
+                    .map_err(failed)?;
+                return Ok(Some((file, Some(self.offset))));
+            }
+            logger.warn(
+                "http",
+                Some("resume"),
+                "The resource changed since the download was interrupted; starting over",
+                Some(json!({"status": status.as_u16()})),
+            );
+        }
+        let file = File::create(&self.path).map_err(failed)?;
+        let validators = Validators::from_headers(headers);
+        if validators.if_range().is_some() {
+            let _ = fs::write(
+                self.validators_path(),
+                serde_json::to_vec(&validators).unwrap_or_default(),
+            );
+        } else {
+            let _ = fs::remove_file(self.validators_path());
+        }
+        Ok(Some((file, None)))
+    }
+
+    pub(super) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Forgets how to resume, once the whole body is in the file
+    pub(super) fn complete(&self) {
+        let _ = fs::remove_file(self.validators_path());
+    }
+
+    fn read_validators(&self) -> Option<Validators> {
+        let bytes = fs::read(self.validators_path()).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    fn validators_path(&self) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(".resume");
+        PathBuf::from(name)
+    }
+}
+
+/// The `Range` header for `range`, given as `start-end`, `start-` or `-suffix` with an
+/// optional `bytes=` prefix
+pub(super) fn range_header(range: &str) -> Result<HeaderValue, AppError> {
+    let invalid = || {
+        AppError::new(
+            ErrorKind::BadRequest,
+            format!("Invalid byte range '{range}'; expected start-end, start- or -suffix"),
+        )
+    };
+    let spec = range.trim();
+    let spec = spec.strip_prefix("bytes=").unwrap_or(spec).trim();
+    let (start, end) = spec.split_once('-').ok_or_else(invalid)?;
+    let bound = |value: &str| -> Result<Option<u64>, AppError> {
+        match value.trim() {
+            "" => Ok(None),
+            value => value.parse().map(Some).map_err(|_| invalid()),
+        }
+    };
+    match (bound(start)?, bound(end)?) {
+        (None, None) => return Err(invalid()),
+        (Some(start), Some(end)) if start > end => return Err(invalid()),
+        _ => {}
+    }
+    HeaderValue::try_from(format!("bytes={}-{}", start.trim(), end.trim())).map_err(|_| invalid())
+}
+
+/// The part of the resource a `206` response holds, from its `Content-Range`
+pub(super) fn content_range(headers: &HeaderMap) -> Option<ContentRange> {
+    let value = headers.get(header::CONTENT_RANGE)?.to_str().ok()?;
+    let (range, total) = value.trim().strip_prefix("bytes ")?.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    Some(ContentRange {
+        start: start.trim().parse().ok()?,
+        end: end.trim().parse().ok()?,
+        total: total.trim().parse().ok(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{content_range, range_header};
+    use crate::http_client::response::ContentRange;
+    use hyper::http::{HeaderMap, HeaderValue, header};
+
+    #[test]
+    fn builds_range_headers() {
+        assert_eq!(range_header("0-1023").unwrap(), "bytes=0-1023");
+        assert_eq!(range_header("bytes=500-").unwrap(), "bytes=500-");
+        assert_eq!(range_header(" -200 ").unwrap(), "bytes=-200");
+        assert!(range_header("-").is_err());
+        assert!(range_header("10-5").is_err());
+        assert!(range_header("a-b").is_err());
+    }
+
+    #[test]
+    fn parses_content_ranges() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_RANGE,
+            HeaderValue::from_static("bytes 100-199/1000"),
+        );
+        assert_eq!(
+            content_range(&headers),
+            Some(ContentRange {
+                start: 100,
+                end: 199,
+                total: Some(1000)
+            })
+        );
+        headers.insert(
+            header::CONTENT_RANGE,
+            HeaderValue::from_static("bytes 0-9/*"),
+        );
+        assert_eq!(content_range(&headers).unwrap().total, None);
+        headers.insert(
+            header::CONTENT_RANGE,
+            HeaderValue::from_static("bytes */1000"),
+        );
+        assert_eq!(content_range(&headers), None);
+    }
+}
@@ -0,0 +1,257 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use hyper::StatusCode;
+use hyper::http::HeaderMap;
+
+use super::rate_limit::parse_rate_limit;
+use crate::http_client::request::RetryPolicy;
+use crate::http_client::response::RetryReport;
+
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const DEFAULT_MAX_DELAY_SECS: u64 = 60;
+
+/// Wait before a retry when the response has no usable `Retry-After`; doubles per attempt.
+const FALLBACK_DELAY: Duration = Duration::from_secs(1);
+
+/// Where a retry delay came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum DelaySource {
+    RetryAfter,
+    /// The reset time of an exhausted rate limit quota
+    RateLimitReset,
+    Backoff,
+}
+
+impl DelaySource {
+    pub(super) fn as_str(&self) -> &'static str {
+        match self {
+            DelaySource::RetryAfter => "Retry-After",
+            DelaySource::RateLimitReset => "rate limit reset",
+            DelaySource::Backoff => "backoff",
+        }
+    }
+}
+
+/// What to do after a response, decided by [`RetryState::next`]
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum RetryDecision {
+    /// Not a retryable status, or retries are disabled
+    Done,
+    /// Wait this long, then send the request again
+    Retry {
+        delay: Duration,
+        attempt: u32,
+        source: DelaySource,
+    },
+    /// Retryable, but the attempts are used up or the server asked for too long a wait
+    GiveUp { reason: String },
+}
+
+pub(super) struct RetryState {
+    enabled: bool,
+    max_retries: u32,
+    max_delay: Duration,
+    attempts: u32,
+    waited: Duration,
+}
+
+impl RetryState {
+    pub(super) fn new(policy: Option<&RetryPolicy>) -> Self {
+        let policy = policy.filter(|policy| policy.enabled);
+        Self {
+            enabled: policy.is_some(),
+            max_retries: policy.map_or(0, |p| p.max_retries.unwrap_or(DEFAULT_MAX_RETRIES)),
+            max_delay: Duration::from_secs(
+                policy
+                    .and_then(|p| p.max_delay_secs)
+                    .unwrap_or(DEFAULT_MAX_DELAY_SECS),
+            ),
+            attempts: 0,
+            waited: Duration::ZERO,
+        }
+    }
+
+    /// Retries spent so far, or `None` when the policy is disabled
+    pub(super) fn report(&self) -> Option<RetryReport> {
+        self.enabled.then_some(RetryReport {
+            attempts: self.attempts,
+            max_retries: self.max_retries,
+            waited_ms: self.waited.as_millis() as u64,
+        })
+    }
+
+    pub(super) fn next(
+        &mut self,
+        status: StatusCode,
+        headers: &HeaderMap,
+        now: DateTime<Utc>,
+    ) -> RetryDecision {
+        if self.max_retries == 0
+            || !matches!(
+                status,
+                StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+            )
+        {
+            return RetryDecision::Done;
+        }
+        if self.attempts >= self.max_retries {
+            return RetryDecision::GiveUp {
+                reason: format!("gave up after {} retries", self.attempts),
+            };
+        }
+
+        let retry_after = headers
+            .get(hyper::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| parse_retry_after(value, now))
+            .map(|delay| (delay, DelaySource::RetryAfter));
+        // Without Retry-After, wait for an exhausted quota to reset
+        let quota_reset = || {
+            parse_rate_limit(headers, now)
+                .filter(|limit| limit.remaining.is_none_or(|remaining| remaining == 0))
+                .and_then(|limit| limit.reset_secs)
+                .map(|secs| (Duration::from_secs(secs), DelaySource::RateLimitReset))
+        };
+        let (delay, source) = match retry_after.or_else(quota_reset) {
+            Some((delay, source)) if delay > self.max_delay => {
+                return RetryDecision::GiveUp {
+                    reason: format!(
+                        "{} of {}s exceeds the {}s limit",
+                        source.as_str(),
+                        delay.as_secs(),
+                        self.max_delay.as_secs()
+                    ),
+                };
+            }
+            Some(found) => found,
+            None => (
+                (FALLBACK_DELAY * 2u32.saturating_pow(self.attempts)).min(self.max_delay),
+                DelaySource::Backoff,
+            ),
+        };
+        self.attempts += 1;
+        self.waited += delay;
+        RetryDecision::Retry {
+            delay,
+            attempt: self.attempts,
+            source,
+        }
+    }
+}
+
+/// Parses a `Retry-After` value: either delay-seconds or an HTTP-date (RFC 9110 §10.2.3).
+/// Dates in the past mean "retry now".
+pub(crate) fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        date.with_timezone(&Utc)
+            .signed_duration_since(now)
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DelaySource, RetryDecision, RetryState, parse_retry_after};
+    use crate::http_client::request::RetryPolicy;
+    use chrono::{DateTime, Utc};
+    use hyper::StatusCode;
+    use hyper::http::{HeaderMap, HeaderName, HeaderValue};
+    use std::time::Duration;
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn parses_seconds_and_http_dates() {
+        assert_eq!(
+            parse_retry_after("120", now()),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now()),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now()),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now()), None);
+    }
+
+    #[test]
+    fn retries_until_attempts_or_delay_limit_run_out() {
+        let policy = RetryPolicy {
+            enabled: true,
+            max_retries: Some(2),
+            max_delay_secs: Some(10),
+        };
+        let mut retry = RetryState::new(Some(&policy));
+        let mut headers = HeaderMap::new();
+
+        assert_eq!(
+            retry.next(StatusCode::OK, &headers, now()),
+            RetryDecision::Done
+        );
+        assert_eq!(
+            retry.next(StatusCode::SERVICE_UNAVAILABLE, &headers, now()),
+            RetryDecision::Retry {
+                delay: Duration::from_secs(1),
+                attempt: 1,
+                source: DelaySource::Backoff
+            }
+        );
+        headers.insert("retry-after", HeaderValue::from_static("5"));
+        assert_eq!(
+            retry.next(StatusCode::TOO_MANY_REQUESTS, &headers, now()),
+            RetryDecision::Retry {
+                delay: Duration::from_secs(5),
+                attempt: 2,
+                source: DelaySource::RetryAfter
+            }
+        );
+        assert!(matches!(
+            retry.next(StatusCode::TOO_MANY_REQUESTS, &headers, now()),
+            RetryDecision::GiveUp { .. }
+        ));
+        let report = retry.report().unwrap();
+        assert_eq!((report.attempts, report.waited_ms), (2, 6000));
+
+        let mut retry = RetryState::new(Some(&policy));
+        let quota = HeaderMap::from_iter([(
+            HeaderName::from_static("ratelimit"),
+            HeaderValue::from_static("\"default\";r=0;t=4"),
+        )]);
+        assert_eq!(
+            retry.next(StatusCode::TOO_MANY_REQUESTS, &quota, now()),
+            RetryDecision::Retry {
+                delay: Duration::from_secs(4),
+                attempt: 1,
+                source: DelaySource::RateLimitReset
+            }
+        );
+
+        let mut retry = RetryState::new(Some(&policy));
+        headers.insert("retry-after", HeaderValue::from_static("3600"));
+        assert!(matches!(
+            retry.next(StatusCode::TOO_MANY_REQUESTS, &headers, now()),
+            RetryDecision::GiveUp { .. }
+        ));
+
+        let mut disabled = RetryState::new(None);
+        assert_eq!(
+            disabled.next(StatusCode::TOO_MANY_REQUESTS, &headers, now()),
+            RetryDecision::Done
+        );
+        assert!(disabled.report().is_none());
+    }
+}
@@ -0,0 +1,597 @@
+//! Revocation checks of the server's certificate chain: OCSP first, at the responders a
+//! certificate names, then its CRLs. Answers count only when signed by the certificate's
+//! issuer, or by an OCSP responder the issuer delegated to.
+
+use std::time::Duration;
+
+use bytes::Bytes;
+use chrono::{DateTime, SecondsFormat, Utc};
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::http::{Method, Request as HttpRequest, Uri, header};
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::TokioExecutor;
+use rustls::pki_types::CertificateDer;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use x509_parser::asn1_rs::BitString;
+use x509_parser::extensions::{DistributionPointName, GeneralName, ParsedExtension};
+use x509_parser::oid_registry::OID_PKIX_ACCESS_DESCRIPTOR_OCSP;
+use x509_parser::prelude::{
+    ASN1Time, AlgorithmIdentifier, CertificateRevocationList, FromDer, ReasonCode, X509Certificate,
+};
+use x509_parser::verify::verify_signature;
+
+use super::RequestLogger;
+use crate::http_client::response::{CertificateRevocation, RevocationSource, RevocationStatus};
+
+/// How long a responder or CRL server gets to answer
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Largest OCSP response or CRL read
+const MAX_FETCH_BYTES: usize = 10 * 1024 * 1024;
+
+const SEQUENCE: u8 = 0x30;
+const OCTET_STRING: u8 = 0x04;
+const ENUMERATED: u8 = 0x0A;
+const OID: u8 = 0x06;
+const GENERALIZED_TIME: u8 = 0x18;
+const BIT_STRING: u8 = 0x03;
+
+/// AlgorithmIdentifier of SHA-1, the hash OCSP responders universally accept in a CertID
+const SHA1_ALGORITHM: &[u8] = &[
+    0x30, 0x09, 0x06, 0x05, 0x2B, 0x0E, 0x03, 0x02, 0x1A, 0x05, 0x00,
+];
+const SHA1_OID: &[u8] = &[0x2B, 0x0E, 0x03, 0x02, 0x1A];
+const SHA256_OID: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+/// id-pkix-ocsp-basic
+const OCSP_BASIC: &[u8] = &[0x2B, 0x06, 0x01, 0x05, 0x05, 0x07, 0x30, 0x01, 0x01];
+
+/// What a connection does with the revocation status of the server's chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum RevocationPolicy {
+    Off,
+    /// Check and report, but connect regardless
+    Report,
+    /// Fail connections to servers with a revoked certificate
+    Enforce,
+}
+
+/// The statuses of a connection's chain, carried to each response on it
+#[derive(Debug, Clone)]
+pub(super) struct RevocationReport(pub(super) Vec<CertificateRevocation>);
+
+impl RevocationReport {
+    pub(super) fn revoked(&self) -> Option<&CertificateRevocation> {
+        self.0
+            .iter()
+            .find(|entry| entry.status == RevocationStatus::Revoked)
+    }
+}
+
+/// A definite answer of a responder or CRL
+#[derive(Debug, PartialEq)]
+enum Answer {
+    Good,
+    Revoked {
+        at: Option<String>,
+        reason: Option<String>,
+    },
+}
+
+/// Checks every certificate of `chain` that's followed by its issuer, i.e. all but the last.
+pub(super) async fn check_chain(
+    chain: &[CertificateDer<'static>],
+    logger: &RequestLogger,
+) -> RevocationReport {
+    let mut entries = Vec::new();
+    for pair in chain.windows(2) {
+        let (Ok((_, cert)), Ok((_, issuer))) = (
+            X509Certificate::from_der(&pair[0]),
+            X509Certificate::from_der(&pair[1]),
+        ) else {
+            continue;
+        };
+        let entry = check_certificate(&cert, &issuer).await;
+        let details = serde_json::to_value(&entry).ok();
+        match entry.status {
+            RevocationStatus::Good => logger.info(
+                "tls",
+                Some("revocation"),
+                format!("Certificate of {} isn't revoked", entry.subject),
+                details,
+            ),
+            RevocationStatus::Revoked => logger.error(
+                "tls",
+                Some("revocation"),
+                format!("Certificate of {} was revoked", entry.subject),
+                details,
+            ),
+            RevocationStatus::Unknown => logger.warn(
+                "tls",
+                Some("revocation"),
+                format!(
+                    "Revocation status of {} is unknown: {}",
+                    entry.subject,
+                    entry.error.as_deref().unwrap_or_default()
+                ),
+                details,
+            ),
+        }
+        entries.push(entry);
+    }
+    RevocationReport(entries)
+}
+
+async fn check_certificate(
+    cert: &X509Certificate<'_>,
+    issuer: &X509Certificate<'_>,
+) -> CertificateRevocation {
+    let mut entry = CertificateRevocation {
+        subject: cert.subject().to_string(),
+        serial: hex::encode(cert.raw_serial()),
+        status: RevocationStatus::Unknown,
+        source: None,
+        revoked_at: None,
+        reason: None,
+        error: None,
+    };
+    let (ocsp_urls, crl_urls) = revocation_urls(cert);
+    let mut errors = Vec::new();
+    let request = ocsp_request(
+        issuer.subject().as_raw(),
+        &issuer_key(issuer),
+        cert.raw_serial(),
+    );
+    let now = Utc::now().timestamp();
+    for url in &ocsp_urls {
+        let answer = match fetch(url, Some(request.clone())).await {
+            Ok(body) => parse_ocsp_response(&body, cert, issuer, now),
+            Err(err) => Err(err),
+        };
+        match answer {
+            Ok(answer) => return answered(entry, answer, RevocationSource::Ocsp),
+            Err(err) => errors.push(format!("OCSP {url}: {err}")),
+        }
+    }
+    for url in &crl_urls {
+        let answer = match fetch(url, None).await {
+            Ok(body) => check_crl(&body, cert, issuer, now),
+            Err(err) => Err(err),
+        };
+        match answer {
+            Ok(answer) => return answered(entry, answer, RevocationSource::Crl),
+            Err(err) => errors.push(format!("CRL {url}: {err}")),
+        }
+    }
+    if errors.is_empty() {
+        errors.push("the certificate names no OCSP responder or CRL".to_string());
+    }
+    entry.error = Some(errors.join("; "));
+    entry
+}
+
+fn answered(
+    mut entry: CertificateRevocation,
+    answer: Answer,
+    source: RevocationSource,
+) -> CertificateRevocation {
+    entry.source = Some(source);
+    match answer {
+        Answer::Good => entry.status = RevocationStatus::Good,
+        Answer::Revoked { at, reason } => {
+            entry.status = RevocationStatus::Revoked;
+            entry.revoked_at = at;
+            entry.reason = reason;
+        }
+    }
+    entry
+}
+
+/// The certificate's OCSP responder and CRL URLs
+fn revocation_urls(cert: &X509Certificate<'_>) -> (Vec<String>, Vec<String>) {
+    let mut ocsp = Vec::new();
+    let mut crl = Vec::new();
+    for extension in cert.extensions() {
+        match extension.parsed_extension() {
+            ParsedExtension::AuthorityInfoAccess(access) => {
+                for description in access.iter() {
+                    if description.access_method == OID_PKIX_ACCESS_DESCRIPTOR_OCSP
+                        && let GeneralName::URI(url) = description.access_location
+                    {
+                        ocsp.push(url.to_string());
+                    }
+                }
+            }
+            ParsedExtension::CRLDistributionPoints(points) => {
+                for point in points.iter() {
+                    if let Some(DistributionPointName::FullName(names)) = &point.distribution_point
+                    {
+                        crl.extend(names.iter().filter_map(|name| match name {
+                            GeneralName::URI(url) => Some(url.to_string()),
+                            _ => None,
+                        }));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    (ocsp, crl)
+}
+
+/// The issuer's public key bits, which a CertID identifies the issuer by
+fn issuer_key(issuer: &X509Certificate<'_>) -> Vec<u8> {
+    issuer.public_key().subject_public_key.data.to_vec()
+}
+
+/// Fetches `url` over plain HTTP, as OCSP and CRL URLs are, POSTing `ocsp_request` if given.
+async fn fetch(url: &str, ocsp_request: Option<Vec<u8>>) -> Result<Bytes, String> {
+    let uri: Uri = url.parse().map_err(|_| "invalid URL".to_string())?;
+    if uri.scheme_str() != Some("http") {
+        return Err("only http URLs are fetched".to_string());
+    }
+    let builder = HttpRequest::builder().uri(uri);
+    let request = match ocsp_request {
+        Some(body) => builder
+            .method(Method::POST)
+            .header(header::CONTENT_TYPE, "application/ocsp-request")
+            .body(Full::new(Bytes::from(body))),
+        None => builder.body(Full::new(Bytes::new())),
+    }
+    .map_err(|e| e.to_string())?;
+
+    let client = Client::builder(TokioExecutor::new()).build_http::<Full<Bytes>>();
+    let fetched = async {
+        let response = client.request(request).await.map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("HTTP {}", response.status()));
+        }
+        Limited::new(response.into_body(), MAX_FETCH_BYTES)
+            .collect()
+            .await
+            .map(|body| body.to_bytes())
+            .map_err(|e| e.to_string())
+    };
+    tokio::time::timeout(FETCH_TIMEOUT, fetched)
+        .await
+        .map_err(|_| format!("no answer within {}s", FETCH_TIMEOUT.as_secs()))?
+}
+
+/// DER encodes a tag and its contents.
+fn der(tag: u8, contents: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = contents.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|&&byte| byte == 0).count();
+        out.push(0x80 | (bytes.len() - skip) as u8);
+        out.extend_from_slice(&bytes[skip..]);
+    }
+    out.extend_from_slice(contents);
+    out
+}
+
+/// An OCSP request (RFC 6960) for one certificate, identified by SHA-1 hashes of its
+/// issuer's name and key and its serial number.
+fn ocsp_request(issuer_name: &[u8], issuer_key: &[u8], serial: &[u8]) -> Vec<u8> {
+    let mut cert_id = SHA1_ALGORITHM.to_vec();
+    cert_id.extend(der(OCTET_STRING, &Sha1::digest(issuer_name)));
+    cert_id.extend(der(OCTET_STRING, &Sha1::digest(issuer_key)));
+    cert_id.extend(der(0x02, serial));
+    let request = der(SEQUENCE, &der(SEQUENCE, &cert_id));
+    let request_list = der(SEQUENCE, &request);
+    der(SEQUENCE, &der(SEQUENCE, &request_list))
+}
+
+/// A DER element: its tag, contents, and the whole encoding
+struct Element<'a> {
+    tag: u8,
+    contents: &'a [u8],
+    raw: &'a [u8],
+}
+
+/// Splits the next element off `input`.
+fn next<'a>(input: &mut &'a [u8]) -> Result<Element<'a>, String> {
+    let malformed = || "malformed response".to_string();
+    let data = *input;
+    let (&tag, rest) = data.split_first().ok_or_else(malformed)?;
+    let (&first, rest) = rest.split_first().ok_or_else(malformed)?;
+    let (len, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let count = (first & 0x7F) as usize;
+        if count == 0 || count > 4 || rest.len() < count {
+            return Err(malformed());
+        }
+        let len = rest[..count]
+            .iter()
+            .fold(0usize, |len, &byte| (len << 8) | byte as usize);
+        (len, &rest[count..])
+    };
+    if rest.len() < len {
+        return Err(malformed());
+    }
+    let header = data.len() - rest.len();
+    *input = &rest[len..];
+    Ok(Element {
+        tag,
+        contents: &rest[..len],
+        raw: &data[..header + len],
+    })
+}
+
+/// Splits the next element off `input`, which must have `tag`.
+fn expect<'a>(input: &mut &'a [u8], tag: u8) -> Result<Element<'a>, String> {
+    let element = next(input)?;
+    if element.tag != tag {
+        return Err("malformed response".to_string());
+    }
+    Ok(element)
+}
+
+/// Splits the next element off `input` if it has `tag`.
+fn optional<'a>(input: &mut &'a [u8], tag: u8) -> Result<Option<Element<'a>>, String> {
+    if input.first() == Some(&tag) {
+        next(input).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+fn timestamp(element: &Element<'_>) -> Result<i64, String> {
+    ASN1Time::from_der(element.raw)
+        .map(|(_, time)| time.timestamp())
+        .map_err(|_| "malformed time".to_string())
+}
+
+fn iso8601(timestamp: i64) -> Option<String> {
+    DateTime::<Utc>::from_timestamp(timestamp, 0)
+        .map(|time| time.to_rfc3339_opts(SecondsFormat::Secs, true))
+}
+
+/// Reads an OCSP response to the request for `cert`, as of `now` in Unix seconds.
+fn parse_ocsp_response(
+    body: &[u8],
+    cert: &X509Certificate<'_>,
+    issuer: &X509Certificate<'_>,
+    now: i64,
+) -> Result<Answer, String> {
+    let mut input = body;
+    let mut response = expect(&mut input, SEQUENCE)?.contents;
+    let status = expect(&mut response, ENUMERATED)?;
+    if status.contents != [0] {
+        let status = match status.contents {
+            [1] => "malformedRequest",
+            [2] => "internalError",
+            [3] => "tryLater",
+            [5] => "sigRequired",
+            [6] => "unauthorized",
+            _ => "an unknown status",
+        };
+        return Err(format!("the responder answered {status}"));
+    }
+    let mut bytes = expect(&mut response, 0xA0)?.contents;
+    let mut bytes = expect(&mut bytes, SEQUENCE)?.contents;
+    if expect(&mut bytes, OID)?.contents != OCSP_BASIC {
+        return Err("unsupported response type".to_string());
+    }
+    let mut basic = expect(&mut bytes, OCTET_STRING)?.contents;
+    let mut basic = expect(&mut basic, SEQUENCE)?.contents;
+    let data = expect(&mut basic, SEQUENCE)?;
+    let algorithm = expect(&mut basic, SEQUENCE)?;
+    let signature = expect(&mut basic, BIT_STRING)?;
+    let certs = optional(&mut basic, 0xA0)?;
+    verify_ocsp_signature(&data, &algorithm, &signature, certs, issuer)?;
+    read_response_data(data.contents, cert.raw_serial(), &issuer_key(issuer), now)
+}
+
+/// Accepts responses signed by the issuer itself, or by a responder certificate the issuer
+/// signed for OCSP signing and sent along.
+fn verify_ocsp_signature(
+    data: &Element<'_>,
+    algorithm: &Element<'_>,
+    signature: &Element<'_>,
+    certs: Option<Element<'_>>,
+    issuer: &X509Certificate<'_>,
+) -> Result<(), String> {
+    let malformed = || "malformed signature".to_string();
+    let (_, algorithm) = AlgorithmIdentifier::from_der(algorithm.raw).map_err(|_| malformed())?;
+    let (_, signature) = BitString::from_der(signature.raw).map_err(|_| malformed())?;
+    if verify_signature(issuer.public_key(), &algorithm, &signature, data.raw).is_ok() {
+        return Ok(());
+    }
+    if let Some(certs) = certs {
+        let mut list = certs.contents;
+        let mut list = expect(&mut list, SEQUENCE)?.contents;
+        while !list.is_empty() {
+            let Ok((_, responder)) = X509Certificate::from_der(next(&mut list)?.raw) else {
+                continue;
+            };
+            let delegated = responder
+                .verify_signature(Some(issuer.public_key()))
+                .is_ok()
+                && responder
+                    .extended_key_usage()
+                    .ok()
+                    .flatten()
+                    .is_some_and(|usage| usage.value.ocsp_signing);
+            if delegated
+                && verify_signature(responder.public_key(), &algorithm, &signature, data.raw)
+                    .is_ok()
+            {
+                return Ok(());
+            }
+        }
+    }
+    Err("the response isn't signed by the certificate's issuer".to_string())
+}
+
+/// Finds the answer about `serial` among the single responses of a ResponseData.
+fn read_response_data(
+    mut data: &[u8],
+    serial: &[u8],
+    issuer_key: &[u8],
+    now: i64,
+) -> Result<Answer, String> {
+    optional(&mut data, 0xA0)?; // version
+    next(&mut data)?; // responderID
+    expect(&mut data, GENERALIZED_TIME)?; // producedAt
+    let mut responses = expect(&mut data, SEQUENCE)?.contents;
+    while !responses.is_empty() {
+        let mut single = expect(&mut responses, SEQUENCE)?.contents;
+        let cert_id = expect(&mut single, SEQUENCE)?;
+        if !matches_cert_id(cert_id.contents, serial, issuer_key)? {
+            continue;
+        }
+        let status = next(&mut single)?;
+        expect(&mut single, GENERALIZED_TIME)?; // thisUpdate
+        if let Some(next_update) = optional(&mut single, 0xA0)? {
+            let mut time = next_update.contents;
+            if timestamp(&expect(&mut time, GENERALIZED_TIME)?)? < now {
+                return Err("the response is out of date".to_string());
+            }
+        }
+        return match status.tag {
+            0x80 => Ok(Answer::Good),
+            0xA1 => {
+                let mut revoked = status.contents;
+                let at = timestamp(&expect(&mut revoked, GENERALIZED_TIME)?)?;
+                let reason = match optional(&mut revoked, 0xA0)? {
+                    Some(reason) => {
+                        let mut reason = reason.contents;
+                        match expect(&mut reason, ENUMERATED)?.contents {
+                            [code] => Some(ReasonCode(*code).to_string()),
+                            _ => None,
+                        }
+                    }
+                    None => None,
+                };
+                Ok(Answer::Revoked {
+                    at: iso8601(at),
+                    reason,
+                })
+            }
+            0x82 => Err("the responder doesn't know the certificate".to_string()),
+            _ => Err("malformed response".to_string()),
+        };
+    }
+    Err("the response doesn't cover the certificate".to_string())
+}
+
+/// Whether a CertID names the certificate with `serial` of the issuer with `issuer_key`
+fn matches_cert_id(mut cert_id: &[u8], serial: &[u8], issuer_key: &[u8]) -> Result<bool, String> {
+    let mut algorithm = expect(&mut cert_id, SEQUENCE)?.contents;
+    let hash = expect(&mut algorithm, OID)?.contents;
+    expect(&mut cert_id, OCTET_STRING)?; // issuerNameHash
+    let key_hash = expect(&mut cert_id, OCTET_STRING)?.contents;
+    let same_key = match hash {
+        SHA1_OID => key_hash == Sha1::digest(issuer_key).as_slice(),
+        SHA256_OID => key_hash == Sha256::digest(issuer_key).as_slice(),
+        _ => true,
+    };
+    Ok(same_key && expect(&mut cert_id, 0x02)?.contents == serial)
+}
+
+/// Looks `cert` up in a DER encoded CRL signed by `issuer`.
+fn check_crl(
+    body: &[u8],
+    cert: &X509Certificate<'_>,
+    issuer: &X509Certificate<'_>,
+    now: i64,
+) -> Result<Answer, String> {
+    let (_, crl) =
+        CertificateRevocationList::from_der(body).map_err(|_| "malformed list".to_string())?;
+    crl.verify_signature(issuer.public_key())
+        .map_err(|_| "the list isn't signed by the certificate's issuer".to_string())?;
+    if crl
+        .next_update()
+        .is_some_and(|next_update| next_update.timestamp() < now)
+    {
+        return Err("the list is out of date".to_string());
+    }
+    Ok(
+        match crl
+            .iter_revoked_certificates()
+            .find(|revoked| revoked.raw_serial() == cert.raw_serial())
+        {
+            Some(revoked) => Answer::Revoked {
+                at: iso8601(revoked.revocation_date.timestamp()),
+                reason: revoked.reason_code().map(|(_, code)| code.to_string()),
+            },
+            None => Answer::Good,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Answer, der, next, ocsp_request, read_response_data};
+    use sha1::{Digest, Sha1};
+
+    const SERIAL: &[u8] = &[0x01, 0x02, 0x03];
+    const KEY: &[u8] = b"issuer key";
+
+    /// A ResponseData with one SingleResponse for `SERIAL` with the given status
+    fn response_data(status: &[u8], next_update: &str) -> Vec<u8> {
+        let mut cert_id = vec![0x30, 0x07, 0x06, 0x05, 0x2B, 0x0E, 0x03, 0x02, 0x1A];
+        cert_id.extend(der(0x04, &Sha1::digest(b"issuer name")));
+        cert_id.extend(der(0x04, &Sha1::digest(KEY)));
+        cert_id.extend(der(0x02, SERIAL));
+        let mut single = der(0x30, &cert_id);
+        single.extend(status);
+        single.extend(der(0x18, b"20250101000000Z"));
+        single.extend(der(0xA0, &der(0x18, next_update.as_bytes())));
+
+        let mut data = der(0xA2, &der(0x04, &[0; 20]));
+        data.extend(der(0x18, b"20250101000000Z"));
+        data.extend(der(0x30, &der(0x30, &single)));
+        data
+    }
+
+    #[test]
+    fn encodes_ocsp_requests() {
+        let request = ocsp_request(b"issuer name", KEY, SERIAL);
+        let mut input = request.as_slice();
+        let outer = next(&mut input).unwrap();
+        assert!(input.is_empty());
+        assert_eq!(outer.tag, 0x30);
+        assert_eq!(outer.raw.len(), request.len());
+        // The algorithm, two 20 byte hashes and the serial, in five nested sequences
+        assert_eq!(request.len(), 11 + 2 * 22 + 5 + 5 * 2);
+        assert!(request.ends_with(&[0x02, 0x03, 0x01, 0x02, 0x03]));
+
+        let long = der(0x04, &[0; 300]);
+        assert_eq!(&long[..4], &[0x04, 0x82, 0x01, 0x2C]);
+    }
+
+    #[test]
+    fn reads_single_responses() {
+        let now = 1_750_000_000; // June 2025
+        let good = response_data(&[0x80, 0x00], "20300101000000Z");
+        assert_eq!(
+            read_response_data(&good, SERIAL, KEY, now),
+            Ok(Answer::Good)
+        );
+        assert!(read_response_data(&good, &[0x09], KEY, now).is_err());
+        assert!(read_response_data(&good, SERIAL, b"other key", now).is_err());
+
+        let stale = response_data(&[0x80, 0x00], "20240101000000Z");
+        assert_eq!(
+            read_response_data(&stale, SERIAL, KEY, now),
+            Err("the response is out of date".to_string())
+        );
+
+        let mut revoked = der(0x18, b"20240301120000Z");
+        revoked.extend(der(0xA0, &[0x0A, 0x01, 0x01]));
+        let revoked = response_data(&der(0xA1, &revoked), "20300101000000Z");
+        assert_eq!(
+            read_response_data(&revoked, SERIAL, KEY, now),
+            Ok(Answer::Revoked {
+                at: Some("2024-03-01T12:00:00Z".to_string()),
+                reason: Some("KeyCompromise".to_string()),
+            })
+        );
+    }
+}
@@ -0,0 +1,256 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use hyper::body::{Body, Frame, SizeHint};
+use hyper::http::Uri;
+use tower_service::Service;
+
+use super::RequestLogger;
+use crate::http_client::response::Timings;
+
+/// Points in time reached while sending a request
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Mark {
+    ConnectStart,
+    DnsStart,
+    DnsEnd,
+    TcpConnected,
+    TlsConnected,
+    RequestWritten,
+    ResponseHeaders,
+    BodyEnd,
+}
+
+#[derive(Debug, Default)]
+struct Marks {
+    connect_start: Option<Instant>,
+    dns_start: Option<Instant>,
+    dns_end: Option<Instant>,
+    tcp_connected: Option<Instant>,
+    tls_connected: Option<Instant>,
+    request_written: Option<Instant>,
+    response_headers: Option<Instant>,
+    body_end: Option<Instant>,
+}
+
+fn between(from: Option<Instant>, to: Option<Instant>) -> Option<u64> {
+    to?.checked_duration_since(from?)
+        .map(|d| d.as_millis() as u64)
+}
+
+impl Marks {
+    fn timings(&self, total: u64) -> Timings {
+        let connection_ready = self.tls_connected.or(self.tcp_connected);
+        Timings {
+            dns: between(self.dns_start, self.dns_end),
+            tcp_connect: between(self.dns_end.or(self.connect_start), self.tcp_connected),
+            tls_handshake: between(self.tcp_connected, self.tls_connected),
+            request_write: between(connection_ready, self.request_written),
+            time_to_first_byte: between(self.request_written, self.response_headers),
+            download: between(self.response_headers, self.body_end),
+            connection_reused: self.connect_start.is_none(),
+            total,
+        }
+    }
+}
+
+/// Records when each phase of a request is reached. Clones share the same marks.
+///
+/// Marks are cleared before each request is sent (e.g. after a redirect), so the breakdown
+/// describes the last request sent. Without connection marks, the request reused a
+/// pooled connection.
+#[derive(Clone, Default)]
+pub(super) struct PhaseTimer {
+    marks: Arc<Mutex<Marks>>,
+}
+
+impl PhaseTimer {
+    pub(super) fn mark(&self, mark: Mark) {
+        let now = Some(Instant::now());
+        let mut marks = self.marks.lock().unwrap();
+        match mark {
+            Mark::ConnectStart => {
+                // Start of a new connection: forget the phases of the previous one
+                *marks = Marks {
+                    connect_start: now,
+                    ..Marks::default()
+                }
+            }
+            Mark::DnsStart => marks.dns_start = now,
+            Mark::DnsEnd => marks.dns_end = now,
+            Mark::TcpConnected => marks.tcp_connected = now,
+            Mark::TlsConnected => marks.tls_connected = now,
+            Mark::RequestWritten => marks.request_written = now,
+            Mark::ResponseHeaders => marks.response_headers = now,
+            Mark::BodyEnd => marks.body_end = now,
+        }
+    }
+
+    /// Records `mark` unless it was already reached for the current request.
+    fn mark_once(&self, mark: Mark) {
+        let reached = {
+            let marks = self.marks.lock().unwrap();
+            match mark {
+                Mark::RequestWritten => marks.request_written.is_some(),
+                _ => false,
+            }
+        };
+        if !reached {
+            self.mark(mark);
+        }
+    }
+
+    /// Clears all marks before another request is sent.
+    pub(super) fn reset_exchange(&self) {
+        *self.marks.lock().unwrap() = Marks::default();
+    }
+
+    pub(super) fn timings(&self, total: u64) -> Timings {
+        self.marks.lock().unwrap().timings(total)
+    }
+}
+
+/// Wraps the TCP connector to time connection establishment.
+#[derive(Clone)]
+pub(super) struct TimedConnector<C> {
+    inner: C,
+    logger: RequestLogger,
+}
+
+impl<C> TimedConnector<C> {
+    pub(super) fn new(inner: C, logger: RequestLogger) -> Self {
+        Self { inner, logger }
+    }
+}
+
+impl<C> Service<Uri> for TimedConnector<C>
+where
+    C: Service<Uri> + Send,
+    C::Future: Send + 'static,
+{
+    type Response = C::Response;
+    type Error = C::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let timer = self.logger.for_current_request().timer().clone();
+        timer.mark(Mark::ConnectStart);
+        let fut = self.inner.call(uri);
+        Box::pin(async move {
+            let stream = fut.await?;
+            timer.mark(Mark::TcpConnected);
+            Ok(stream)
+        })
+    }
+}
+
+/// Wraps a request body to record when it has been fully handed to the connection.
+pub(super) struct TimedBody<B> {
+    inner: B,
+    timer: PhaseTimer,
+}
+
+impl<B> TimedBody<B> {
+    pub(super) fn new(inner: B, timer: PhaseTimer) -> Self {
+        Self { inner, timer }
+    }
+}
+
+impl<B: Body + Unpin> Body for TimedBody<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let polled = Pin::new(&mut self.inner).poll_frame(cx);
+        if let Poll::Ready(None) = polled {
+            self.timer.mark_once(Mark::RequestWritten);
+        }
+        polled
+    }
+
+    fn is_end_stream(&self) -> bool {
+        // Empty bodies are never polled; hyper checks this while writing the request head
+        let end = self.inner.is_end_stream();
+        if end {
+            self.timer.mark_once(Mark::RequestWritten);
+        }
+        end
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Mark, PhaseTimer, TimedBody};
+    use bytes::Bytes;
+    use http_body_util::{BodyExt, Full};
+    use std::time::Duration;
+
+    #[test]
+    fn computes_phases_from_marks() {
+        let timer = PhaseTimer::default();
+        for mark in [
+            Mark::ConnectStart,
+            Mark::DnsStart,
+            Mark::DnsEnd,
+            Mark::TcpConnected,
+            Mark::TlsConnected,
+            Mark::RequestWritten,
+        ] {
+            timer.mark(mark);
+        }
+        std::thread::sleep(Duration::from_millis(20));
+        timer.mark(Mark::ResponseHeaders);
+        timer.mark(Mark::BodyEnd);
+
+        let timings = timer.timings(100);
+        assert_eq!(timings.total, 100);
+        assert!(!timings.connection_reused);
+        assert!(timings.dns.is_some());
+        assert!(timings.tcp_connect.is_some());
+        assert!(timings.tls_handshake.is_some());
+        assert!(timings.time_to_first_byte.unwrap() >= 20);
+        assert!(timings.download.unwrap() < 20);
+
+        // A new connection starts a fresh breakdown
+        timer.mark(Mark::ConnectStart);
+        timer.mark(Mark::TcpConnected);
+        let timings = timer.timings(5);
+        assert!(timings.dns.is_none());
+        assert!(timings.tls_handshake.is_none());
+        assert!(timings.time_to_first_byte.is_none());
+
+        // A request sent over a pooled connection has no connection phases
+        timer.reset_exchange();
+        timer.mark(Mark::RequestWritten);
+        timer.mark(Mark::ResponseHeaders);
+        let timings = timer.timings(5);
+        assert!(timings.connection_reused);
+        assert!(timings.tcp_connect.is_none());
+        assert!(timings.time_to_first_byte.is_some());
+    }
+
+    #[tokio::test]
+    async fn marks_request_written_when_body_ends() {
+        let timer = PhaseTimer::default();
+        timer.mark(Mark::ConnectStart);
+        timer.mark(Mark::TcpConnected);
+
+        let body = TimedBody::new(Full::new(Bytes::from_static(b"payload")), timer.clone());
+        body.collect().await.unwrap();
+        assert!(timer.timings(0).request_write.is_some());
+    }
+}
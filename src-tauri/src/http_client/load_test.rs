@@ -0,0 +1,370 @@
+//! Smoke-level load tests: one request sent by several concurrent workers for a number of
+//! iterations or a length of time, with latency percentiles, error rates and throughput
+//! reported while it runs.
+
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::engine::{HttpEngine, LogEmitter};
+use crate::http_client::request::{LogCapture, Request};
+use crate::http_client::response::ResponseData;
+use futures_util::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+/// Most concurrent workers a run may use
+const MAX_WORKERS: u32 = 256;
+
+fn default_report_interval_ms() -> u64 {
+    1000
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadTestOptions {
+    /// Id the run is stopped by; stopping keeps the results so far
+    pub run_id: String,
+    /// Concurrent workers, each sending the request again as soon as its last one is answered
+    pub workers: u32,
+    /// Requests to send in all
+    pub iterations: Option<u64>,
+    /// Longest the run may take. With `iterations` too, the run ends at whichever comes first.
+    pub duration_secs: Option<u64>,
+    /// Time between the statistics sent while the run is going
+    #[serde(default = "default_report_interval_ms")]
+    pub report_interval_ms: u64,
+}
+
+/// Latencies of the responses received, in milliseconds
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyStats {
+    pub min: u64,
+    pub mean: f64,
+    pub p50: u64,
+    pub p90: u64,
+    pub p95: u64,
+    pub p99: u64,
+    pub max: u64,
+}
+
+/// Statistics of a run so far, or of the whole run once `done` is set
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadTestStats {
+    pub elapsed_ms: u64,
+    /// Requests answered or failed
+    pub completed: u64,
+    /// Requests that failed to send or receive a response
+    pub errors: u64,
+    /// Responses with a status of 400 or above
+    pub http_errors: u64,
+    /// Responses with an assertion that failed
+    pub assertion_failures: u64,
+    /// Share of completed requests that errored, got an error status or failed an assertion
+    pub error_rate: f64,
+    /// Completed requests per second
+    pub throughput: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency: Option<LatencyStats>,
+    /// Responses by status code
+    pub status_counts: BTreeMap<u16, u64>,
+    /// Message of each error, with the number of times it occurred
+    pub error_messages: BTreeMap<String, u64>,
+    /// Set on the final statistics of the run
+    pub done: bool,
+    /// The run was stopped before it finished
+    pub cancelled: bool,
+}
+
+/// Hands out iterations to workers until the count or the time runs out
+struct Plan {
+    iterations: Option<u64>,
+    deadline: Option<Instant>,
+    claimed: AtomicU64,
+}
+
+impl Plan {
+    fn claim(&self) -> bool {
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return false;
+        }
+        match self.iterations {
+            Some(iterations) => self.claimed.fetch_add(1, Ordering::Relaxed) < iterations,
+            None => true,
+        }
+    }
+}
+
+/// Outcomes of the requests sent so far
+#[derive(Default)]
+struct Recorder {
+    latencies: Vec<u64>,
+    errors: u64,
+    http_errors: u64,
+    assertion_failures: u64,
+    failed: u64,
+    status_counts: BTreeMap<u16, u64>,
+    error_messages: BTreeMap<String, u64>,
+}
+
+impl Recorder {
+    fn record(&mut self, result: &Result<ResponseData, AppError>) {
+        match result {
+            Ok(response) => {
+                self.latencies.push(response.duration);
+                *self.status_counts.entry(response.status).or_default() += 1;
+                let http_error = response.status >= 400;
+                let assertion_failed = response
+                    .assertions
+                    .iter()
+                    .flatten()
+                    .any(|result| !result.passed);
+                self.http_errors += u64::from(http_error);
+                self.assertion_failures += u64::from(assertion_failed);
+                self.failed += u64::from(http_error || assertion_failed);
+            }
+            Err(error) => {
+                self.errors += 1;
+                self.failed += 1;
+                *self
+                    .error_messages
+                    .entry(error.message.clone())
+                    .or_default() += 1;
+            }
+        }
+    }
+
+    fn stats(&self, elapsed: Duration) -> LoadTestStats {
+        let completed = self.latencies.len() as u64 + self.errors;
+        let elapsed_secs = elapsed.as_secs_f64();
+        LoadTestStats {
+            elapsed_ms: elapsed.as_millis() as u64,
+            completed,
+            errors: self.errors,
+            http_errors: self.http_errors,
+            assertion_failures: self.assertion_failures,
+            error_rate: if completed > 0 {
+                self.failed as f64 / completed as f64
+            } else {
+                0.0
+            },
+            throughput: if elapsed_secs > 0.0 {
+                completed as f64 / elapsed_secs
+            } else {
+                0.0
+            },
+            latency: latency_stats(&self.latencies),
+            status_counts: self.status_counts.clone(),
+            error_messages: self.error_messages.clone(),
+            done: false,
+            cancelled: false,
+        }
+    }
+}
+
+fn latency_stats(latencies: &[u64]) -> Option<LatencyStats> {
+    let mut sorted = latencies.to_vec();
+    sorted.sort_unstable();
+    let (&min, &max) = (sorted.first()?, sorted.last()?);
+    // Nearest-rank percentile
+    let percentile = |p: f64| {
+        let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+        sorted[rank.clamp(1, sorted.len()) - 1]
+    };
+    Some(LatencyStats {
+        min,
+        mean: sorted.iter().sum::<u64>() as f64 / sorted.len() as f64,
+        p50: percentile(50.0),
+        p90: percentile(90.0),
+        p95: percentile(95.0),
+        p99: percentile(99.0),
+        max,
+    })
+}
+
+/// Sends `request` with `options.workers` concurrent workers until the iterations or the time
+/// run out, or `cancellation` stops the run, passing statistics to `on_stats` as it goes.
+/// Returns the statistics of the whole run.
+///
+/// Log entries of the requests are dropped unless the request sets its own log capture mode.
+pub async fn run(
+    engine: &dyn HttpEngine,
+    mut request: Request,
+    options: &LoadTestOptions,
+    emitter: Arc<dyn LogEmitter>,
+    cancellation: &CancellationToken,
+    on_stats: impl Fn(LoadTestStats),
+) -> Result<LoadTestStats, AppError> {
+    if options.workers == 0 || options.workers > MAX_WORKERS {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            format!("A load test needs between 1 and {MAX_WORKERS} workers"),
+        ));
+    }
+    if options.iterations.is_none() && options.duration_secs.is_none() {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            "A load test needs a number of iterations or a duration",
+        ));
+    }
+    request.log_capture.get_or_insert(LogCapture::Off);
+
+    let started = Instant::now();
+    let plan = Plan {
+        iterations: options.iterations,
+        deadline: options
+            .duration_secs
+            .map(|secs| started + Duration::from_secs(secs)),
+        claimed: AtomicU64::new(0),
+    };
+    let recorder = Mutex::new(Recorder::default());
+    let (plan, recorder, request, emitter) = (&plan, &recorder, &request, &emitter);
+    let worker = move || async move {
+        while !cancellation.is_cancelled() && plan.claim() {
+            let result = tokio::select! {
+                _ = cancellation.cancelled() => break,
+                result = engine.execute(request.clone(), emitter.clone()) => result,
+            };
+            recorder.lock().unwrap().record(&result);
+        }
+    };
+    let workers = join_all((0..options.workers).map(|_| worker()));
+    tokio::pin!(workers);
+
+    let mut ticker =
+        tokio::time::interval(Duration::from_millis(options.report_interval_ms.max(100)));
+    ticker.tick().await;
+    loop {
+        tokio::select! {
+            _ = &mut workers => break,
+            _ = ticker.tick() => on_stats(recorder.lock().unwrap().stats(started.elapsed())),
+        }
+    }
+
+    let stats = LoadTestStats {
+        done: true,
+        cancelled: cancellation.is_cancelled(),
+        ..recorder.lock().unwrap().stats(started.elapsed())
+    };
+    on_stats(stats.clone());
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LoadTestOptions, Plan, Recorder, latency_stats, run};
+    use crate::errors::{AppError, ErrorKind};
+    use crate::http_client::engine::test_emitters::Discard;
+    use crate::http_client::hyper_engine::HyperEngine;
+    use crate::http_client::request::Request;
+    use std::sync::atomic::AtomicU64;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio_util::sync::CancellationToken;
+
+    #[test]
+    fn computes_nearest_rank_percentiles() {
+        let latencies: Vec<u64> = (1..=100).rev().collect();
+        let stats = latency_stats(&latencies).unwrap();
+        assert_eq!((stats.min, stats.max), (1, 100));
+        assert_eq!(
+            (stats.p50, stats.p90, stats.p95, stats.p99),
+            (50, 90, 95, 99)
+        );
+        assert_eq!(stats.mean, 50.5);
+
+        let single = latency_stats(&[7]).unwrap();
+        assert_eq!((single.p50, single.p99), (7, 7));
+        assert!(latency_stats(&[]).is_none());
+    }
+
+    #[test]
+    fn counts_errors_toward_the_error_rate() {
+        let mut recorder = Recorder::default();
+        recorder.record(&Err(AppError::new(ErrorKind::HttpError, "refused")));
+        recorder.record(&Err(AppError::new(ErrorKind::HttpError, "refused")));
+        let stats = recorder.stats(Duration::from_secs(2));
+        assert_eq!(stats.completed, 2);
+        assert_eq!(stats.error_rate, 1.0);
+        assert_eq!(stats.throughput, 1.0);
+        assert_eq!(stats.error_messages.get("refused"), Some(&2));
+        assert!(stats.latency.is_none());
+    }
+
+    #[test]
+    fn plan_stops_at_the_iteration_count_or_deadline() {
+        let plan = Plan {
+            iterations: Some(2),
+            deadline: None,
+            claimed: AtomicU64::new(0),
+        };
+        assert!(plan.claim() && plan.claim());
+        assert!(!plan.claim());
+
+        let expired = Plan {
+            iterations: None,
+            deadline: Some(Instant::now()),
+            claimed: AtomicU64::new(0),
+        };
+        assert!(!expired.claim());
+    }
+
+    #[tokio::test]
+    async fn sends_the_requested_iterations() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 1024];
+                    while matches!(stream.read(&mut buf).await, Ok(n) if n > 0) {
+                        let _ = stream
+                            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok")
+                            .await;
+                    }
+                });
+            }
+        });
+
+        let request = Request {
+            request_id: "load".to_string(),
+            url: format!("http://127.0.0.1:{port}/"),
+            method: "GET".to_string(),
+            ..Request::default()
+        };
+        let options = LoadTestOptions {
+            run_id: "load".to_string(),
+            workers: 4,
+            iterations: Some(20),
+            duration_secs: None,
+            report_interval_ms: 100,
+        };
+        let updates = Mutex::new(Vec::new());
+        let stats = run(
+            &HyperEngine::new(),
+            request,
+            &options,
+            Arc::new(Discard),
+            &CancellationToken::new(),
+            |stats| updates.lock().unwrap().push(stats),
+        )
+        .await
+        .unwrap();
+        assert!(stats.done && !stats.cancelled);
+        assert_eq!(stats.completed, 20);
+        assert_eq!(stats.status_counts.get(&200), Some(&20));
+        assert_eq!(stats.error_rate, 0.0);
+        assert_eq!(updates.lock().unwrap().last(), Some(&stats));
+    }
+}
@@ -0,0 +1,413 @@
+//! Browsing for services on the local network with multicast DNS (RFC 6762) and DNS-SD
+//! (RFC 6763), so devices and dev servers can be found without knowing their addresses.
+//!
+//! Sends a one-shot query (RFC 6762 §5.1) from an ephemeral port, which responders answer by
+//! unicast, so no socket has to share port 5353 with the system's own responder.
+
+use crate::errors::{AppError, ErrorKind};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::time::Instant;
+
+const MDNS_GROUP: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(224, 0, 0, 251)), 5353);
+
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+const TYPE_TXT: u16 = 16;
+const TYPE_AAAA: u16 = 28;
+const TYPE_SRV: u16 = 33;
+const CLASS_IN: u16 = 1;
+/// Asks responders to answer by unicast (RFC 6762 §5.4)
+const UNICAST_RESPONSE: u16 = 0x8000;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(2000);
+const MAX_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A service instance that answered, e.g. `Office Printer._http._tcp.local`
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalService {
+    /// Instance name, e.g. `Office Printer`
+    pub name: String,
+    /// Full instance domain name
+    pub instance: String,
+    /// Host the service runs on, e.g. `printer.local`
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    /// Addresses of `host` that came with the answer
+    pub addresses: Vec<String>,
+    /// TXT record attributes (RFC 6763 §6); attributes without a value map to ""
+    pub txt: BTreeMap<String, String>,
+}
+
+/// `_http._tcp` as the fully qualified `_http._tcp.local`
+fn service_domain(service_type: &str) -> Result<String, AppError> {
+    let trimmed = service_type.trim().trim_end_matches('.');
+    let domain = if trimmed.ends_with(".local") {
+        trimmed.to_string()
+    } else {
+        format!("{trimmed}.local")
+    };
+    let labels: Vec<&str> = domain.split('.').collect();
+    if labels.len() < 3
+        || !labels[0].starts_with('_')
+        || !matches!(labels[labels.len() - 2], "_tcp" | "_udp")
+        || labels
+            .iter()
+            .any(|label| label.is_empty() || label.len() > 63)
+    {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            format!("'{service_type}' is not a service type like '_http._tcp'"),
+        ));
+    }
+    Ok(domain)
+}
+
+/// A PTR query for `domain`
+fn query(domain: &str) -> Vec<u8> {
+    // ID 0 and no flags (RFC 6762 §18), one question
+    let mut message = vec![0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0];
+    for label in domain.split('.') {
+        message.push(label.len() as u8);
+        message.extend_from_slice(label.as_bytes());
+    }
+    message.push(0);
+    message.extend_from_slice(&TYPE_PTR.to_be_bytes());
+    message.extend_from_slice(&(CLASS_IN | UNICAST_RESPONSE).to_be_bytes());
+    message
+}
+
+struct Reader<'a> {
+    message: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn bytes(&mut self, len: usize) -> Option<&[u8]> {
+        let bytes = self.message.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.bytes(2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.bytes(4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// A domain name's labels, following compression pointers (RFC 1035 §4.1.4)
+    fn name(&mut self) -> Option<Vec<String>> {
+        let mut labels = Vec::new();
+        let mut pos = self.pos;
+        let mut resume = None;
+        // Each pointer must go backwards, which bounds the walk
+        let mut limit = pos;
+        loop {
+            let len = *self.message.get(pos)? as usize;
+            match len {
+                0 => {
+                    pos += 1;
+                    break;
+                }
+                l if l & 0xC0 == 0xC0 => {
+                    let target = ((l & 0x3F) << 8) | *self.message.get(pos + 1)? as usize;
+                    if target >= limit {
+                        return None;
+                    }
+                    resume.get_or_insert(pos + 2);
+                    limit = target;
+                    pos = target;
+                }
+                l if l < 64 => {
+                    let label = self.message.get(pos + 1..pos + 1 + l)?;
+                    labels.push(String::from_utf8_lossy(label).into_owned());
+                    pos += 1 + l;
+                }
+                _ => return None,
+            }
+        }
+        self.pos = resume.unwrap_or(pos);
+        Some(labels)
+    }
+}
+
+/// Names compare case-insensitively (RFC 6762 §16)
+fn key(labels: &[String]) -> String {
+    labels.join(".").to_lowercase()
+}
+
+/// Records gathered from every response
+#[derive(Debug, Default)]
+struct Records {
+    /// Instance name by domain, from the PTR records of the browsed type
+    instances: BTreeMap<String, (String, String)>,
+    srv: HashMap<String, (String, u16)>,
+    txt: HashMap<String, BTreeMap<String, String>>,
+    addresses: HashMap<String, Vec<IpAddr>>,
+}
+
+impl Records {
+    /// Adds the records of one response; `None` when it's malformed, keeping what was read.
+    fn add_response(&mut self, message: &[u8], service: &str) -> Option<()> {
+        let mut reader = Reader { message, pos: 4 };
+        let questions = reader.u16()?;
+        let records = reader.u16()? as usize + reader.u16()? as usize + reader.u16()? as usize;
+        for _ in 0..questions {
+            reader.name()?;
+            reader.bytes(4)?;
+        }
+        for _ in 0..records {
+            let name = reader.name()?;
+            let record_type = reader.u16()?;
+            let _class = reader.u16()?;
+            let ttl = reader.u32()?;
+            let len = reader.u16()? as usize;
+            let end = reader.pos + len;
+            let data = message.get(reader.pos..end)?;
+            // A zero TTL says goodbye (RFC 6762 §10.1)
+            if ttl > 0 {
+                let mut rdata = Reader {
+                    message,
+                    pos: reader.pos,
+                };
+                match record_type {
+                    TYPE_PTR if key(&name) == service => {
+                        let target = rdata.name()?;
+                        let display = target.first().cloned().unwrap_or_default();
+                        self.instances
+                            .insert(key(&target), (display, target.join(".")));
+                    }
+                    TYPE_SRV if len > 6 => {
+                        rdata.bytes(4)?;
+                        let port = rdata.u16()?;
+                        let host = rdata.name()?.join(".");
+                        self.srv.insert(key(&name), (host, port));
+                    }
+                    TYPE_TXT => {
+                        let mut attributes = BTreeMap::new();
+                        let mut rest = data;
+                        while let Some((&n, tail)) = rest.split_first() {
+                            let entry = tail.get(..n as usize)?;
+                            rest = &tail[n as usize..];
+                            let entry = String::from_utf8_lossy(entry);
+                            let (k, v) = entry.split_once('=').unwrap_or((&entry, ""));
+                            // The first occurrence of an attribute wins (RFC 6763 §6.4)
+                            if !k.is_empty() {
+                                attributes
+                                    .entry(k.to_lowercase())
+                                    .or_insert_with(|| v.to_string());
+                            }
+                        }
+                        self.txt.insert(key(&name), attributes);
+                    }
+                    TYPE_A if len == 4 => {
+                        let ip = IpAddr::V4(Ipv4Addr::new(data[0], data[1], data[2], data[3]));
+                        self.add_address(&name, ip);
+                    }
+                    TYPE_AAAA if len == 16 => {
+                        let octets: [u8; 16] = data.try_into().ok()?;
+                        self.add_address(&name, IpAddr::V6(Ipv6Addr::from(octets)));
+                    }
+                    _ => {}
+                }
+            }
+            reader.pos = end;
+        }
+        Some(())
+    }
+
+    fn add_address(&mut self, host: &[String], ip: IpAddr) {
+        let addresses = self.addresses.entry(key(host)).or_default();
+        if !addresses.contains(&ip) {
+            addresses.push(ip);
+        }
+    }
+
+    fn services(mut self) -> Vec<LocalService> {
+        let instances = std::mem::take(&mut self.instances);
+        instances
+            .into_iter()
+            .map(|(instance_key, (name, instance))| {
+                let srv = self.srv.get(&instance_key).cloned();
+                let addresses = srv
+                    .as_ref()
+                    .and_then(|(host, _)| self.addresses.get(&host.to_lowercase()))
+                    .map(|ips| ips.iter().map(IpAddr::to_string).collect())
+                    .unwrap_or_default();
+                LocalService {
+                    name,
+                    instance,
+                    host: srv.as_ref().map(|(host, _)| host.clone()),
+                    port: srv.map(|(_, port)| port),
+                    addresses,
+                    txt: self.txt.remove(&instance_key).unwrap_or_default(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Browses the local network for instances of `service_type` (e.g. `_http._tcp`), collecting
+/// answers for `timeout_ms` (2 s by default, at most 10 s).
+pub async fn discover_local_services(
+    service_type: &str,
+    timeout_ms: Option<u64>,
+) -> Result<Vec<LocalService>, AppError> {
+    let domain = service_domain(service_type)?;
+    let timeout = timeout_ms
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_TIMEOUT)
+        .min(MAX_TIMEOUT);
+    let io_error =
+        |e: std::io::Error| AppError::new(ErrorKind::IoError, format!("mDNS query failed: {e}"));
+
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .await
+        .map_err(io_error)?;
+    // Multicast DNS packets are sent with a TTL of 255 (RFC 6762 §11)
+    socket.set_multicast_ttl_v4(255).map_err(io_error)?;
+    socket
+        .send_to(&query(&domain), MDNS_GROUP)
+        .await
+        .map_err(io_error)?;
+
+    let service = domain.to_lowercase();
+    let mut records = Records::default();
+    let deadline = Instant::now() + timeout;
+    let mut buf = vec![0u8; 9000];
+    while let Ok(received) = tokio::time::timeout_at(deadline, socket.recv_from(&mut buf)).await {
+        let (len, from) = received.map_err(io_error)?;
+        // Only responses, from the standard port (RFC 6762 §6)
+        if len < 12 || buf[2] & 0x80 == 0 || from.port() != 5353 {
+            continue;
+        }
+        if records.add_response(&buf[..len], &service).is_none() {
+            log::debug!("Ignoring malformed mDNS response from {from}");
+        }
+    }
+    Ok(records.services())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Records, query, service_domain};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn qualifies_service_types() {
+        assert_eq!(service_domain("_http._tcp").unwrap(), "_http._tcp.local");
+        assert_eq!(
+            service_domain("_ipp._tcp.local.").unwrap(),
+            "_ipp._tcp.local"
+        );
+        assert!(service_domain("http").is_err());
+        assert!(service_domain("_http._xyz").is_err());
+    }
+
+    #[test]
+    fn asks_for_unicast_ptr_answers() {
+        let message = query("_http._tcp.local");
+        assert_eq!(&message[..12], &[0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(&message[12..18], b"\x05_http");
+        assert_eq!(&message[message.len() - 5..], &[0, 0, 12, 0x80, 1]);
+    }
+
+    fn record(out: &mut Vec<u8>, name: &[u8], record_type: u16, ttl: u32, data: &[u8]) {
+        out.extend_from_slice(name);
+        out.extend_from_slice(&record_type.to_be_bytes());
+        out.extend_from_slice(&[0x80, 1]);
+        out.extend_from_slice(&ttl.to_be_bytes());
+        out.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        out.extend_from_slice(data);
+    }
+
+    #[test]
+    fn collects_services_from_compressed_responses() {
+        // A response with a PTR answer and SRV, TXT and A records in the additional section
+        let mut message = vec![0, 0, 0x84, 0, 0, 0, 0, 1, 0, 0, 0, 4];
+        // Offset 12: _http._tcp.local
+        record(
+            &mut message,
+            b"\x05_http\x04_tcp\x05local\x00",
+            12,
+            4500,
+            &[],
+        );
+        // PTR data: "Office Printer" + pointer to _http._tcp.local
+        let ptr_at = message.len() - 2;
+        let mut ptr = b"\x0eOffice Printer".to_vec();
+        ptr.extend_from_slice(&[0xC0, 12]);
+        message.truncate(ptr_at);
+        message.extend_from_slice(&(ptr.len() as u16).to_be_bytes());
+        let instance = message.len() as u8;
+        message.extend_from_slice(&ptr);
+
+        let mut srv = vec![0, 0, 0, 0, 0x1F, 0x90];
+        srv.extend_from_slice(b"\x07printer");
+        srv.extend_from_slice(&[0xC0, 12 + 11]);
+        record(&mut message, &[0xC0, instance], 33, 120, &srv);
+        record(
+            &mut message,
+            &[0xC0, instance],
+            16,
+            4500,
+            b"\x06path=/\x04Note\x07path=/x",
+        );
+        record(
+            &mut message,
+            b"\x07PRINTER\x05local\x00",
+            1,
+            120,
+            &[192, 168, 1, 20],
+        );
+        // Goodbye for an address that went away
+        record(
+            &mut message,
+            b"\x07printer\x05local\x00",
+            1,
+            0,
+            &[192, 168, 1, 21],
+        );
+
+        let mut records = Records::default();
+        records.add_response(&message, "_http._tcp.local").unwrap();
+        let services = records.services();
+        assert_eq!(services.len(), 1);
+        let service = &services[0];
+        assert_eq!(service.name, "Office Printer");
+        assert_eq!(service.instance, "Office Printer._http._tcp.local");
+        assert_eq!(service.host.as_deref(), Some("printer.local"));
+        assert_eq!(service.port, Some(8080));
+        assert_eq!(service.addresses, vec!["192.168.1.20"]);
+        assert_eq!(
+            service.txt,
+            BTreeMap::from([
+                ("note".to_string(), String::new()),
+                ("path".to_string(), "/".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn rejects_pointer_loops_and_truncation() {
+        let mut message = vec![0, 0, 0x84, 0, 0, 0, 0, 1, 0, 0, 0, 0];
+        message.extend_from_slice(&[0xC0, 12]);
+        assert!(
+            Records::default()
+                .add_response(&message, "_http._tcp.local")
+                .is_none()
+        );
+        assert!(
+            Records::default()
+                .add_response(&message[..10], "_http._tcp.local")
+                .is_none()
+        );
+    }
+}
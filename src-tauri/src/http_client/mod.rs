@@ -1,7 +1,41 @@
+pub mod assertions;
 pub mod auth;
+pub mod aws_secrets;
+pub mod batch;
+pub mod body_query;
+pub mod body_stream;
+pub mod charset;
 pub mod cookies;
+pub mod cors;
+pub mod data_run;
+pub mod dotenv;
 pub mod engine;
+pub mod extractors;
+pub mod generators;
+pub mod graphql;
+pub mod har;
 pub mod hyper_engine;
+pub mod load_test;
 pub mod manager;
+pub mod mdns;
+pub mod ndjson;
+pub mod pagination;
+pub mod payload_crypto;
+pub mod polling;
+pub mod protobuf;
+pub mod recording_proxy;
 pub mod request;
+pub mod request_log;
 pub mod response;
+pub mod self_signed;
+pub mod soap;
+pub mod socket;
+pub mod spool;
+pub mod sse;
+pub mod static_server;
+pub mod tls_probe;
+pub mod variables;
+pub mod visualize;
+pub mod webdav;
+pub mod webhook;
+pub mod xml;
@@ -0,0 +1,178 @@
+//! Newline-delimited JSON (NDJSON, JSON Lines): parses a streamed body into records as it
+//! arrives, e.g. from streaming APIs and bulk export endpoints.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use std::sync::Arc;
+
+/// One line of the body
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum NdjsonRecord {
+    /// A line holding a JSON value
+    Record { line: u64, value: Value },
+    /// A line that isn't JSON; the lines after it are still parsed
+    Invalid {
+        line: u64,
+        text: String,
+        error: String,
+    },
+}
+
+/// Receives the records of a streamed response; set by the command, never read from the
+/// frontend.
+#[derive(Clone)]
+pub struct RecordSink(Arc<dyn Fn(NdjsonRecord) + Send + Sync>);
+
+impl RecordSink {
+    pub fn new(send: impl Fn(NdjsonRecord) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(send))
+    }
+
+    pub fn send(&self, record: NdjsonRecord) {
+        (self.0)(record)
+    }
+}
+
+impl fmt::Debug for RecordSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RecordSink")
+    }
+}
+
+/// Whether a response with this `Content-Type` is newline-delimited JSON
+pub fn is_ndjson(content_type: &str) -> bool {
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+    [
+        "application/x-ndjson",
+        "application/ndjson",
+        "application/jsonl",
+        "application/x-jsonlines",
+        "application/jsonlines",
+    ]
+    .iter()
+    .any(|known| essence.eq_ignore_ascii_case(known))
+}
+
+/// Incremental NDJSON parser. Lines may be split across chunks at any byte, and end with LF
+/// or CRLF; blank lines are skipped.
+#[derive(Debug, Default)]
+pub struct NdjsonParser {
+    line: Vec<u8>,
+    /// Lines seen so far, blank ones included, so records can be found in the body
+    lines: u64,
+}
+
+impl NdjsonParser {
+    /// Parses `bytes`, returning the records they complete.
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<NdjsonRecord> {
+        let mut records = Vec::new();
+        for &byte in bytes {
+            if byte == b'\n' {
+                records.extend(self.end_line());
+            } else {
+                self.line.push(byte);
+            }
+        }
+        records
+    }
+
+    /// The record of a last line the body didn't end with a line break
+    pub fn finish(&mut self) -> Option<NdjsonRecord> {
+        if self.line.is_empty() {
+            None
+        } else {
+            self.end_line()
+        }
+    }
+
+    fn end_line(&mut self) -> Option<NdjsonRecord> {
+        let bytes = std::mem::take(&mut self.line);
+        self.lines += 1;
+        let mut line = bytes.strip_suffix(b"\r").unwrap_or(&bytes);
+        if self.lines == 1 {
+            line = line.strip_prefix("\u{FEFF}".as_bytes()).unwrap_or(line);
+        }
+        if line.iter().all(u8::is_ascii_whitespace) {
+            return None;
+        }
+        Some(match serde_json::from_slice(line) {
+            Ok(value) => NdjsonRecord::Record {
+                line: self.lines,
+                value,
+            },
+            Err(e) => NdjsonRecord::Invalid {
+                line: self.lines,
+                text: String::from_utf8_lossy(line).into_owned(),
+                error: e.to_string(),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NdjsonParser, NdjsonRecord, is_ndjson};
+    use serde_json::json;
+
+    #[test]
+    fn parses_records_split_across_chunks() {
+        let stream = "\u{FEFF}{\"id\":1,\"name\":\"caf\u{e9}\"}\r\n\
+                      \n\
+                      [1, 2]\n\
+                      {\"id\":\n\
+                      \"text\"\n\
+                      {\"id\":3}";
+        let mut parser = NdjsonParser::default();
+        // One byte at a time, so every split point is exercised, multi-byte characters included
+        let mut records: Vec<_> = stream
+            .as_bytes()
+            .chunks(1)
+            .flat_map(|chunk| parser.feed(chunk))
+            .collect();
+        assert_eq!(records.len(), 4);
+        records.extend(parser.finish());
+        assert!(parser.finish().is_none());
+
+        assert_eq!(
+            records[0],
+            NdjsonRecord::Record {
+                line: 1,
+                value: json!({"id": 1, "name": "caf\u{e9}"})
+            }
+        );
+        assert_eq!(
+            records[1],
+            NdjsonRecord::Record {
+                line: 3,
+                value: json!([1, 2])
+            }
+        );
+        assert!(matches!(
+            &records[2],
+            NdjsonRecord::Invalid { line: 4, text, .. } if text == "{\"id\":"
+        ));
+        assert_eq!(
+            records[3],
+            NdjsonRecord::Record {
+                line: 5,
+                value: json!("text")
+            }
+        );
+        assert_eq!(
+            records[4],
+            NdjsonRecord::Record {
+                line: 6,
+                value: json!({"id": 3})
+            }
+        );
+    }
+
+    #[test]
+    fn recognizes_ndjson_content_types() {
+        assert!(is_ndjson("application/x-ndjson"));
+        assert!(is_ndjson("Application/JSONL; charset=utf-8"));
+        assert!(!is_ndjson("application/json"));
+    }
+}
@@ -0,0 +1,311 @@
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::engine::{HttpEngine, LogEmitter, TauriLogEmitter};
+use crate::http_client::hyper_engine::{HyperEngine, with_query_params};
+use crate::http_client::request::Request;
+use crate::http_client::response::Timings;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_json_path::JsonPath;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tauri::{AppHandle, Url};
+
+fn default_max_pages() -> u32 {
+    10
+}
+
+/// Where the next page comes from when the API doesn't send `Link` headers
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PaginationCursor {
+    /// JSONPath to the cursor in each page, e.g. `$.meta.next_cursor`
+    pub json_path: String,
+    /// Query parameter the cursor is sent in, e.g. `cursor`. Without one the cursor is taken
+    /// to be the next page's URL, e.g. `$.links.next`.
+    pub param: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PaginationOptions {
+    #[serde(default = "default_max_pages")]
+    pub max_pages: u32,
+    /// Follow a cursor in the body instead of `Link: <...>; rel="next"` headers
+    pub cursor: Option<PaginationCursor>,
+    /// JSONPath to the results in each page, e.g. `$.data[*]`. Without one each page's whole
+    /// body is one result.
+    pub items_path: Option<String>,
+}
+
+/// One fetched page
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageSummary {
+    pub page: u32,
+    pub url: String,
+    pub status: u16,
+    pub size: u64,
+    pub duration: u64,
+    pub timings: Timings,
+    /// Results this page added to `items`
+    pub item_count: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaginationReport {
+    pub pages: Vec<PageSummary>,
+    /// Results of all pages, in order
+    pub items: Vec<Value>,
+    /// Next page not fetched because `max_pages` was reached
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_url: Option<String>,
+    /// Why paging stopped
+    pub reason: String,
+}
+
+fn parse_path(expression: &str) -> Result<JsonPath, AppError> {
+    JsonPath::parse(expression).map_err(|e| {
+        AppError::new(
+            ErrorKind::BadRequest,
+            format!("Invalid JSONPath '{expression}': {e}"),
+        )
+    })
+}
+
+/// Target of the `rel="next"` link in `Link` headers (RFC 8288), resolved against `base`.
+fn next_link(headers: &[(String, String)], base: &str) -> Option<String> {
+    headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("link"))
+        .flat_map(|(_, value)| value.split(','))
+        .find_map(|link| {
+            let (target, params) = link.trim().split_once('>')?;
+            let target = target.trim().strip_prefix('<')?;
+            let is_next = params.split(';').any(|param| {
+                let Some((name, value)) = param.split_once('=') else {
+                    return false;
+                };
+                name.trim().eq_ignore_ascii_case("rel")
+                    && value
+                        .trim()
+                        .trim_matches('"')
+                        .split_whitespace()
+                        .any(|rel| rel.eq_ignore_ascii_case("next"))
+            });
+            is_next.then(|| resolve(base, target)).flatten()
+        })
+}
+
+fn resolve(base: &str, target: &str) -> Option<String> {
+    Url::parse(base)
+        .and_then(|base| base.join(target))
+        .map(String::from)
+        .ok()
+}
+
+/// URL of the page after `url`, from the cursor in its body. None when the cursor is missing,
+/// null or empty, which marks the last page.
+fn next_from_cursor(
+    cursor: &PaginationCursor,
+    path: &JsonPath,
+    body: &Value,
+    url: &str,
+) -> Option<String> {
+    let value = match path.query(body).first()? {
+        Value::String(s) if !s.is_empty() => s.clone(),
+        Value::Number(n) => n.to_string(),
+        _ => return None,
+    };
+    match &cursor.param {
+        Some(param) => {
+            let mut next = Url::parse(url).ok()?;
+            let others: Vec<(String, String)> = next
+                .query_pairs()
+                .filter(|(name, _)| name != param.as_str())
+                .map(|(name, value)| (name.into_owned(), value.into_owned()))
+                .collect();
+            next.query_pairs_mut()
+                .clear()
+                .extend_pairs(others)
+                .append_pair(param, &value);
+            Some(next.into())
+        }
+        None => resolve(url, &value),
+    }
+}
+
+fn page_items(items_path: Option<&JsonPath>, body: Option<&Value>, raw: &[u8]) -> Vec<Value> {
+    match (items_path, body) {
+        (Some(path), Some(body)) => path.query(body).all().into_iter().cloned().collect(),
+        (Some(_), None) => Vec::new(),
+        (None, Some(body)) => vec![body.clone()],
+        (None, None) => vec![Value::String(String::from_utf8_lossy(raw).into_owned())],
+    }
+}
+
+/// Sends `request` and follows its next-page links, or cursor, for up to `options.max_pages`
+/// pages, collecting the results of each.
+pub async fn paginate(
+    app: AppHandle,
+    request: Request,
+    options: PaginationOptions,
+) -> Result<PaginationReport, AppError> {
+    let cursor_path = options
+        .cursor
+        .as_ref()
+        .map(|cursor| parse_path(&cursor.json_path))
+        .transpose()?;
+    let items_path = options.items_path.as_deref().map(parse_path).transpose()?;
+    let emitter: Arc<dyn LogEmitter> = Arc::new(TauriLogEmitter::new(app));
+    let engine = HyperEngine::new();
+
+    let mut report = PaginationReport {
+        pages: Vec::new(),
+        items: Vec::new(),
+        next_url: None,
+        reason: String::new(),
+    };
+    let mut visited = HashSet::new();
+    // Next-page links are complete URLs, so the query params only go on the first
+    let mut url = match &request.query_params {
+        Some(params) => with_query_params(&request.url, params),
+        None => request.url.clone(),
+    };
+
+    for page in 1..=options.max_pages.max(1) {
+        visited.insert(url.clone());
+        let response = engine
+            .execute(
+                Request {
+                    url: url.clone(),
+                    query_params: None,
+                    ..request.clone()
+                },
+                emitter.clone(),
+            )
+            .await?;
+
+        let succeeded = (200..300).contains(&response.status);
+        let body: Option<Value> = serde_json::from_slice(&response.body).ok();
+        // An error page's body isn't a page of results
+        let items = if succeeded {
+            page_items(items_path.as_ref(), body.as_ref(), &response.body)
+        } else {
+            Vec::new()
+        };
+        report.pages.push(PageSummary {
+            page,
+            url: url.clone(),
+            status: response.status,
+            size: response.size,
+            duration: response.duration,
+            timings: response.timings.clone(),
+            item_count: items.len(),
+        });
+        report.items.extend(items);
+
+        if !succeeded {
+            report.reason = format!("Page {page} returned status {}", response.status);
+            return Ok(report);
+        }
+        let next = match (&options.cursor, &cursor_path) {
+            (Some(cursor), Some(path)) => body
+                .as_ref()
+                .and_then(|body| next_from_cursor(cursor, path, body, &url)),
+            _ => next_link(&response.headers, &url),
+        };
+        let Some(next) = next else {
+            report.reason = format!("Page {page} is the last page");
+            return Ok(report);
+        };
+        if visited.contains(&next) {
+            report.reason = format!("Page {page} links back to {next}");
+            return Ok(report);
+        }
+        url = next;
+    }
+
+    report.reason = format!("Stopped after {} pages", report.pages.len());
+    report.next_url = Some(url);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PaginationCursor, next_from_cursor, next_link, page_items};
+    use serde_json::json;
+    use serde_json_path::JsonPath;
+
+    fn link(value: &str) -> Vec<(String, String)> {
+        vec![("Link".to_string(), value.to_string())]
+    }
+
+    #[test]
+    fn follows_link_rel_next() {
+        let base = "https://api.github.com/repos/o/r/issues?page=1";
+        let headers = link(
+            r#"<https://api.github.com/repos/o/r/issues?page=2>; rel="next", <https://api.github.com/repos/o/r/issues?page=9>; rel="last""#,
+        );
+        assert_eq!(
+            next_link(&headers, base).as_deref(),
+            Some("https://api.github.com/repos/o/r/issues?page=2")
+        );
+        assert_eq!(
+            next_link(&link(r#"</items?after=x>; rel="prev next""#), base).as_deref(),
+            Some("https://api.github.com/items?after=x")
+        );
+        assert_eq!(next_link(&link(r#"</items?page=1>; rel=prev"#), base), None);
+        assert_eq!(next_link(&[], base), None);
+    }
+
+    #[test]
+    fn follows_cursor_param_or_url() {
+        let body = json!({ "data": [1, 2], "meta": { "next": "abc" }, "links": { "next": "/v1/items?page=2" } });
+
+        let by_param = PaginationCursor {
+            json_path: "$.meta.next".to_string(),
+            param: Some("cursor".to_string()),
+        };
+        let path = JsonPath::parse("$.meta.next").unwrap();
+        assert_eq!(
+            next_from_cursor(
+                &by_param,
+                &path,
+                &body,
+                "https://x.test/v1/items?limit=2&cursor=old"
+            )
+            .as_deref(),
+            Some("https://x.test/v1/items?limit=2&cursor=abc")
+        );
+
+        let by_url = PaginationCursor {
+            json_path: "$.links.next".to_string(),
+            param: None,
+        };
+        let path = JsonPath::parse("$.links.next").unwrap();
+        assert_eq!(
+            next_from_cursor(&by_url, &path, &body, "https://x.test/v1/items").as_deref(),
+            Some("https://x.test/v1/items?page=2")
+        );
+
+        let last = json!({ "meta": { "next": null } });
+        let path = JsonPath::parse("$.meta.next").unwrap();
+        assert_eq!(
+            next_from_cursor(&by_param, &path, &last, "https://x.test/"),
+            None
+        );
+    }
+
+    #[test]
+    fn collects_page_items() {
+        let body = json!({ "data": [{ "id": 1 }, { "id": 2 }] });
+        let path = JsonPath::parse("$.data[*]").unwrap();
+        assert_eq!(
+            page_items(Some(&path), Some(&body), b""),
+            vec![json!({ "id": 1 }), json!({ "id": 2 })]
+        );
+        assert_eq!(page_items(None, Some(&body), b""), vec![body.clone()]);
+        assert_eq!(page_items(None, None, b"a,b"), vec![json!("a,b")]);
+    }
+}
@@ -0,0 +1,229 @@
+//! JWE compact serialization (RFC 7516 §7.1) with RSA key wrapping (RFC 7518 §4.3) and
+//! AES-GCM content encryption (RFC 7518 §5.3).
+
+use super::rsa::{RsaPrivateKey, RsaPublicKey};
+use super::{bad_key, decryption_failed, public_key_from_pem};
+use crate::errors::{AppError, ErrorKind};
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes128Gcm, Aes256Gcm, Key, KeyInit, Nonce};
+use base64::Engine as _;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use rand::RngCore;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use sha1::Sha1;
+use sha2::Sha256;
+
+const IV_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+/// An RSA public key as a JWK (RFC 7518 §6.3.1)
+#[derive(Debug, Deserialize)]
+struct Jwk {
+    kty: String,
+    n: String,
+    e: String,
+    kid: Option<String>,
+}
+
+fn decode(part: &str) -> Result<Vec<u8>, AppError> {
+    URL_SAFE_NO_PAD
+        .decode(part)
+        .map_err(|e| AppError::new(ErrorKind::Base64Error, format!("Invalid JWE part: {e}")))
+}
+
+/// The recipient's key and its `kid`, from a PEM or a JWK
+fn recipient(public_key: &str) -> Result<(RsaPublicKey, Option<String>), AppError> {
+    if !public_key.trim_start().starts_with('{') {
+        return Ok((public_key_from_pem(public_key)?, None));
+    }
+    let jwk: Jwk = serde_json::from_str(public_key)?;
+    if jwk.kty != "RSA" {
+        return Err(bad_key(format!(
+            "JWE keys must be RSA keys, not '{}'",
+            jwk.kty
+        )));
+    }
+    let key = RsaPublicKey::new(&decode(&jwk.n)?, &decode(&jwk.e)?)?;
+    Ok((key, jwk.kid))
+}
+
+/// Encrypts `plaintext` with a random A256GCM content key wrapped with RSA-OAEP-256.
+pub(super) fn encrypt(
+    plaintext: &[u8],
+    public_key: &str,
+    key_id: Option<&str>,
+    content_type: Option<&str>,
+) -> Result<String, AppError> {
+    let (key, jwk_key_id) = recipient(public_key)?;
+    let mut header = json!({ "alg": "RSA-OAEP-256", "enc": "A256GCM" });
+    if let Some(kid) = key_id.or(jwk_key_id.as_deref()) {
+        header["kid"] = json!(kid);
+    }
+    if let Some(cty) = content_type {
+        header["cty"] = json!(cty);
+    }
+    let protected = URL_SAFE_NO_PAD.encode(header.to_string());
+
+    let mut content_key = [0u8; 32];
+    let mut iv = [0u8; IV_LEN];
+    rand::rng().fill_bytes(&mut content_key);
+    rand::rng().fill_bytes(&mut iv);
+    let encrypted_key = key.encrypt_oaep::<Sha256>(&content_key)?;
+    let sealed = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&content_key))
+        .encrypt(
+            Nonce::from_slice(&iv),
+            Payload {
+                msg: plaintext,
+                aad: protected.as_bytes(),
+            },
+        )
+        .map_err(|_| AppError::new(ErrorKind::EncryptionFailed, "Encrypting the payload failed"))?;
+    let (ciphertext, tag) = sealed.split_at(sealed.len() - TAG_LEN);
+
+    Ok([
+        protected,
+        URL_SAFE_NO_PAD.encode(encrypted_key),
+        URL_SAFE_NO_PAD.encode(iv),
+        URL_SAFE_NO_PAD.encode(ciphertext),
+        URL_SAFE_NO_PAD.encode(tag),
+    ]
+    .join("."))
+}
+
+fn open<C: Aead + KeyInit>(
+    content_key: &[u8],
+    iv: &[u8],
+    payload: Payload,
+) -> Result<Vec<u8>, AppError> {
+    if iv.len() != IV_LEN {
+        return Err(decryption_failed());
+    }
+    C::new_from_slice(content_key)
+        .map_err(|_| decryption_failed())?
+        .decrypt(aes_gcm::aead::Nonce::<C>::from_slice(iv), payload)
+        .map_err(|_| decryption_failed())
+}
+
+/// Decrypts a JWE wrapped with RSA-OAEP or RSA-OAEP-256 and encrypted with A128GCM or A256GCM.
+pub(super) fn decrypt(token: &str, key: &RsaPrivateKey) -> Result<Vec<u8>, AppError> {
+    let parts: Vec<&str> = token.trim().split('.').collect();
+    let [protected, encrypted_key, iv, ciphertext, tag] = parts.as_slice() else {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            "A JWE compact serialization has five parts",
+        ));
+    };
+    let header: Value = serde_json::from_slice(&decode(protected)?)?;
+    let unsupported = |name: &str| {
+        AppError::new(
+            ErrorKind::NotImplemented,
+            format!("Unsupported JWE {name} '{}'", header[name]),
+        )
+    };
+    if header.get("zip").is_some() {
+        return Err(unsupported("zip"));
+    }
+    let unwrap: fn(&RsaPrivateKey, &[u8]) -> Result<Vec<u8>, AppError> =
+        match header["alg"].as_str() {
+            Some("RSA-OAEP-256") => RsaPrivateKey::decrypt_oaep::<Sha256>,
+            Some("RSA-OAEP") => RsaPrivateKey::decrypt_oaep::<Sha1>,
+            _ => return Err(unsupported("alg")),
+        };
+    let key_len = match header["enc"].as_str() {
+        Some("A256GCM") => 32,
+        Some("A128GCM") => 16,
+        _ => return Err(unsupported("enc")),
+    };
+    // A key that doesn't unwrap is swapped for a random one rather than reported, so it fails
+    // like any other tampering and says nothing about the padding (RFC 7516 §11.5)
+    let content_key = unwrap(key, &decode(encrypted_key)?)
+        .ok()
+        .filter(|content_key| content_key.len() == key_len)
+        .unwrap_or_else(|| {
+            let mut random = vec![0u8; key_len];
+            rand::rng().fill_bytes(&mut random);
+            random
+        });
+
+    let mut sealed = decode(ciphertext)?;
+    sealed.extend_from_slice(&decode(tag)?);
+    let payload = Payload {
+        msg: &sealed,
+        aad: protected.as_bytes(),
+    };
+    let iv = decode(iv)?;
+    match key_len {
+        32 => open::<Aes256Gcm>(&content_key, &iv, payload),
+        _ => open::<Aes128Gcm>(&content_key, &iv, payload),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, decrypt, encrypt};
+    use crate::http_client::payload_crypto::private_key_from_pem;
+    use crate::http_client::payload_crypto::tests::{RSA_KEY, public_components};
+    use base64::Engine as _;
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use serde_json::{Value, json};
+
+    #[test]
+    fn round_trips_through_a_jwk() {
+        let key = private_key_from_pem(RSA_KEY).unwrap();
+        let (n, e) = public_components();
+        let jwk = json!({
+            "kty": "RSA",
+            "kid": "partner-2025",
+            "n": URL_SAFE_NO_PAD.encode(n),
+            "e": URL_SAFE_NO_PAD.encode(e),
+        });
+
+        let token = encrypt(
+            br#"{"amount":10}"#,
+            &jwk.to_string(),
+            None,
+            Some("application/json"),
+        )
+        .unwrap();
+        let parts: Vec<&str> = token.split('.').collect();
+        assert_eq!(parts.len(), 5);
+        let header: Value = serde_json::from_slice(&decode(parts[0]).unwrap()).unwrap();
+        assert_eq!(
+            header,
+            json!({
+                "alg": "RSA-OAEP-256",
+                "enc": "A256GCM",
+                "kid": "partner-2025",
+                "cty": "application/json"
+            })
+        );
+        assert_eq!(decrypt(&token, &key).unwrap(), br#"{"amount":10}"#);
+
+        // The header is authenticated, so changing it breaks decryption
+        let tampered = token.replacen(
+            parts[0],
+            &URL_SAFE_NO_PAD.encode(r#"{"alg":"RSA-OAEP-256","enc":"A256GCM"}"#),
+            1,
+        );
+        assert!(decrypt(&tampered, &key).is_err());
+
+        // A key wrapped with other padding fails the same way as a modified payload
+        let wrong_padding = token.replacen(parts[1], &URL_SAFE_NO_PAD.encode([1u8; 256]), 1);
+        assert_eq!(
+            decrypt(&wrong_padding, &key).unwrap_err().message,
+            decrypt(&tampered, &key).unwrap_err().message
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_tokens() {
+        let key = private_key_from_pem(RSA_KEY).unwrap();
+        assert!(decrypt("a.b.c", &key).is_err());
+        let unsupported = format!(
+            "{}.a.b.c.d",
+            URL_SAFE_NO_PAD.encode(r#"{"alg":"dir","enc":"A256GCM"}"#)
+        );
+        assert!(decrypt(&unsupported, &key).is_err());
+    }
+}
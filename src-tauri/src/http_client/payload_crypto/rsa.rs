@@ -0,0 +1,299 @@
+//! RSA encryption with the paddings JWE and OpenPGP use (RFC 8017 §7), computed on
+//! `num-bigint`. ring only signs with RSA, and the `rsa` crate's `num-bigint-dig` turns on
+//! `lazy_static`'s `spin_no_std` feature, which `jmespath` doesn't build with.
+//!
+//! Private key operations use the CRT on a blinded ciphertext, so their timing is
+//! uncorrelated with the ciphertext, and each result is checked against the public key before
+//! it's unblinded. Padding is checked over every byte, and callers turn a bad padding into the
+//! same error as any other tampering.
+
+use super::{bad_key, decryption_failed};
+use crate::errors::AppError;
+use num_bigint::BigUint;
+use rand::RngCore;
+use sha2::Digest;
+
+const MIN_MODULUS_BITS: u64 = 2048;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct RsaPublicKey {
+    n: BigUint,
+    e: BigUint,
+}
+
+impl RsaPublicKey {
+    /// The key with modulus `n` and public exponent `e`, both big-endian
+    pub(super) fn new(n: &[u8], e: &[u8]) -> Result<Self, AppError> {
+        let n = BigUint::from_bytes_be(n);
+        let e = BigUint::from_bytes_be(e);
+        if n.bits() < MIN_MODULUS_BITS {
+            return Err(bad_key(format!(
+                "RSA keys must be at least {MIN_MODULUS_BITS} bits"
+            )));
+        }
+        if e < BigUint::from(3u8) || !e.bit(0) || e >= n {
+            return Err(bad_key("Invalid RSA public exponent"));
+        }
+        Ok(Self { n, e })
+    }
+
+    #[cfg(test)]
+    pub(super) fn n(&self) -> Vec<u8> {
+        self.n.to_bytes_be()
+    }
+
+    #[cfg(test)]
+    pub(super) fn e(&self) -> Vec<u8> {
+        self.e.to_bytes_be()
+    }
+
+    /// Modulus length in bytes, the length of every ciphertext
+    pub(super) fn size(&self) -> usize {
+        (self.n.bits() as usize).div_ceil(8)
+    }
+
+    fn encrypt_raw(&self, encoded: &[u8]) -> Vec<u8> {
+        let c = BigUint::from_bytes_be(encoded).modpow(&self.e, &self.n);
+        left_pad(&c.to_bytes_be(), self.size())
+    }
+
+    /// RSAES-OAEP with `D` as both the hash and the MGF1 hash, and an empty label
+    pub(super) fn encrypt_oaep<D: Digest>(&self, message: &[u8]) -> Result<Vec<u8>, AppError> {
+        let k = self.size();
+        let h_len = <D as Digest>::output_size();
+        if message.len() + 2 * h_len + 2 > k {
+            return Err(bad_key("The payload is too long for the RSA key"));
+        }
+        let mut db = D::digest([]).to_vec();
+        db.resize(k - message.len() - h_len - 2, 0);
+        db.push(0x01);
+        db.extend_from_slice(message);
+        let mut seed = vec![0u8; h_len];
+        rand::rng().fill_bytes(&mut seed);
+        xor(&mut db, &mgf1::<D>(&seed, k - h_len - 1));
+        xor(&mut seed, &mgf1::<D>(&db, h_len));
+
+        let mut encoded = Vec::with_capacity(k);
+        encoded.push(0x00);
+        encoded.extend_from_slice(&seed);
+        encoded.extend_from_slice(&db);
+        Ok(self.encrypt_raw(&encoded))
+    }
+
+    /// RSAES-PKCS1-v1_5, which OpenPGP wraps session keys with
+    pub(super) fn encrypt_pkcs1(&self, message: &[u8]) -> Result<Vec<u8>, AppError> {
+        let k = self.size();
+        if message.len() + 11 > k {
+            return Err(bad_key("The payload is too long for the RSA key"));
+        }
+        let mut padding = vec![0u8; k - message.len() - 3];
+        let mut rng = rand::rng();
+        rng.fill_bytes(&mut padding);
+        for byte in padding.iter_mut() {
+            while *byte == 0 {
+                *byte = (rng.next_u32() & 0xff) as u8;
+            }
+        }
+        let mut encoded = Vec::with_capacity(k);
+        encoded.extend_from_slice(&[0x00, 0x02]);
+        encoded.extend_from_slice(&padding);
+        encoded.push(0x00);
+        encoded.extend_from_slice(message);
+        Ok(self.encrypt_raw(&encoded))
+    }
+}
+
+pub(super) struct RsaPrivateKey {
+    public: RsaPublicKey,
+    d: BigUint,
+    p: BigUint,
+    q: BigUint,
+    /// `d mod (p - 1)`
+    dp: BigUint,
+    /// `d mod (q - 1)`
+    dq: BigUint,
+    /// `q⁻¹ mod p`
+    q_inverse: BigUint,
+}
+
+impl RsaPrivateKey {
+    /// The key with modulus `n`, exponents `e` and `d`, and primes `p` and `q`, all
+    /// big-endian, once they're checked to belong together
+    pub(super) fn from_components(
+        n: &[u8],
+        e: &[u8],
+        d: &[u8],
+        p: &[u8],
+        q: &[u8],
+    ) -> Result<Self, AppError> {
+        let public = RsaPublicKey::new(n, e)?;
+        let invalid = || bad_key("Invalid RSA private key");
+        let [d, p, q] = [d, p, q].map(BigUint::from_bytes_be);
+        let one = BigUint::from(1u8);
+        if p <= one || q <= one || &p * &q != public.n {
+            return Err(invalid());
+        }
+        let (p_1, q_1) = (&p - 1u8, &q - 1u8);
+        let (dp, dq) = (&d % &p_1, &d % &q_1);
+        if (&public.e * &dp) % &p_1 != one || (&public.e * &dq) % &q_1 != one {
+            return Err(invalid());
+        }
+        let q_inverse = q.modinv(&p).ok_or_else(invalid)?;
+        Ok(Self {
+            public,
+            d,
+            p,
+            q,
+            dp,
+            dq,
+            q_inverse,
+        })
+    }
+
+    pub(super) fn public(&self) -> &RsaPublicKey {
+        &self.public
+    }
+
+    /// The private exponent, big-endian
+    pub(super) fn d(&self) -> Vec<u8> {
+        self.d.to_bytes_be()
+    }
+
+    /// `c^d mod n` by the CRT, on `c` blinded with a random `r^e`
+    fn decrypt_raw(&self, ciphertext: &[u8]) -> Result<Vec<u8>, AppError> {
+        let RsaPublicKey { n, e } = &self.public;
+        let k = self.public.size();
+        let c = BigUint::from_bytes_be(ciphertext);
+        if ciphertext.len() != k || &c >= n {
+            return Err(decryption_failed());
+        }
+        let mut bytes = vec![0u8; k];
+        let (r, r_inverse) = loop {
+            rand::rng().fill_bytes(&mut bytes);
+            let r = BigUint::from_bytes_be(&bytes) % n;
+            if let Some(r_inverse) = r.modinv(n) {
+                break (r, r_inverse);
+            }
+        };
+        let blinded = (c * r.modpow(e, n)) % n;
+
+        let m_p = blinded.modpow(&self.dp, &self.p);
+        let m_q = blinded.modpow(&self.dq, &self.q);
+        let h = (&self.q_inverse * (m_p + &self.p - &m_q % &self.p)) % &self.p;
+        let m = m_q + h * &self.q;
+        // A faulty result would give away a factor of n, so it's never unblinded
+        if m.modpow(e, n) != blinded {
+            return Err(decryption_failed());
+        }
+        Ok(left_pad(&((m * r_inverse) % n).to_bytes_be(), k))
+    }
+
+    pub(super) fn decrypt_oaep<D: Digest>(&self, ciphertext: &[u8]) -> Result<Vec<u8>, AppError> {
+        let mut encoded = self.decrypt_raw(ciphertext)?;
+        let h_len = <D as Digest>::output_size();
+        if encoded.len() < 2 * h_len + 2 {
+            return Err(decryption_failed());
+        }
+        let (first, rest) = encoded.split_at_mut(1);
+        let (seed, db) = rest.split_at_mut(h_len);
+        xor(seed, &mgf1::<D>(db, h_len));
+        xor(db, &mgf1::<D>(seed, db.len()));
+
+        // The label hash, any zeros, a one, and the message
+        let (label_hash, rest) = db.split_at(h_len);
+        let mut valid = first[0] == 0;
+        for (byte, expected) in label_hash.iter().zip(D::digest([])) {
+            valid &= *byte == expected;
+        }
+        let mut start = None;
+        for (index, &byte) in rest.iter().enumerate() {
+            let first_nonzero = start.is_none() & (byte != 0);
+            valid &= !first_nonzero | (byte == 1);
+            start = if first_nonzero {
+                Some(index + 1)
+            } else {
+                start
+            };
+        }
+        match start {
+            Some(start) if valid => Ok(rest[start..].to_vec()),
+            _ => Err(decryption_failed()),
+        }
+    }
+
+    pub(super) fn decrypt_pkcs1(&self, ciphertext: &[u8]) -> Result<Vec<u8>, AppError> {
+        let encoded = self.decrypt_raw(ciphertext)?;
+        // Zero, two, at least eight nonzero bytes of padding, a zero, and the message
+        let mut valid = encoded[0] == 0 && encoded[1] == 2;
+        let mut end = None;
+        for (index, &byte) in encoded.iter().enumerate().skip(2) {
+            let first_zero = end.is_none() & (byte == 0);
+            valid &= !first_zero | (index >= 10);
+            end = if first_zero { Some(index + 1) } else { end };
+        }
+        match end {
+            Some(end) if valid => Ok(encoded[end..].to_vec()),
+            _ => Err(decryption_failed()),
+        }
+    }
+}
+
+/// Mask generation function MGF1 (RFC 8017 §B.2.1)
+fn mgf1<D: Digest>(seed: &[u8], len: usize) -> Vec<u8> {
+    let mut mask = Vec::with_capacity(len + <D as Digest>::output_size());
+    let mut counter: u32 = 0;
+    while mask.len() < len {
+        let mut hasher = D::new();
+        hasher.update(seed);
+        hasher.update(counter.to_be_bytes());
+        mask.extend_from_slice(&hasher.finalize());
+        counter += 1;
+    }
+    mask.truncate(len);
+    mask
+}
+
+fn xor(data: &mut [u8], mask: &[u8]) {
+    for (byte, mask) in data.iter_mut().zip(mask) {
+        *byte ^= mask;
+    }
+}
+
+fn left_pad(bytes: &[u8], len: usize) -> Vec<u8> {
+    let mut padded = vec![0u8; len.saturating_sub(bytes.len())];
+    padded.extend_from_slice(bytes);
+    padded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RsaPublicKey;
+    use crate::http_client::payload_crypto::private_key_from_pem;
+    use crate::http_client::payload_crypto::tests::RSA_KEY;
+    use sha1::Sha1;
+    use sha2::Sha256;
+
+    #[test]
+    fn oaep_and_pkcs1_round_trip() {
+        let key = private_key_from_pem(RSA_KEY).unwrap();
+        let public = key.public();
+
+        let sealed = public.encrypt_oaep::<Sha256>(b"content key").unwrap();
+        assert_eq!(sealed.len(), 256);
+        assert_eq!(key.decrypt_oaep::<Sha256>(&sealed).unwrap(), b"content key");
+        assert!(key.decrypt_oaep::<Sha1>(&sealed).is_err());
+
+        let sealed = public.encrypt_pkcs1(b"session key").unwrap();
+        assert_eq!(key.decrypt_pkcs1(&sealed).unwrap(), b"session key");
+        assert!(key.decrypt_oaep::<Sha256>(&sealed).is_err());
+        assert!(key.decrypt_pkcs1(&[1u8; 256]).is_err());
+    }
+
+    #[test]
+    fn rejects_even_or_tiny_exponents() {
+        let n = [0xffu8; 256];
+        assert!(RsaPublicKey::new(&n, &[1, 0, 1]).is_ok());
+        assert!(RsaPublicKey::new(&n, &[1, 0, 0]).is_err());
+        assert!(RsaPublicKey::new(&n, &[1]).is_err());
+    }
+}
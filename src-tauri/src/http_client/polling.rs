@@ -0,0 +1,328 @@
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::engine::{HttpEngine, LogEmitter, TauriLogEmitter};
+use crate::http_client::hyper_engine::{HyperEngine, parse_retry_after};
+use crate::http_client::request::Request;
+use crate::http_client::response::ResponseData;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_json_path::JsonPath;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Url};
+
+fn default_interval_ms() -> u64 {
+    1000
+}
+
+fn default_backoff() -> f64 {
+    1.0
+}
+
+fn default_max_interval_ms() -> u64 {
+    30_000
+}
+
+fn default_max_attempts() -> u32 {
+    30
+}
+
+/// When polling stops. With neither field set, any status other than 202 is terminal.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PollCondition {
+    /// Statuses that end polling, e.g. `[200, 303]`
+    #[serde(default)]
+    pub status_codes: Vec<u16>,
+    /// JSONPath evaluated against the status body, e.g. `$.status`. Without `equals` any match
+    /// ends polling, so a filter such as `$[?@.state == 'done']` works on its own.
+    pub json_path: Option<String>,
+    /// Value a node selected by `json_path` must equal
+    pub equals: Option<Value>,
+}
+
+/// How to poll the status URL of a long-running job (RFC 9110 §15.3.3)
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PollOptions {
+    /// Delay before the first poll
+    #[serde(default = "default_interval_ms")]
+    pub interval_ms: u64,
+    /// Factor applied to the delay after each poll; 1 polls at a fixed interval
+    #[serde(default = "default_backoff")]
+    pub backoff: f64,
+    /// Upper bound on the delay, including delays asked for by `Retry-After`
+    #[serde(default = "default_max_interval_ms")]
+    pub max_interval_ms: u64,
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    #[serde(default)]
+    pub until: PollCondition,
+}
+
+/// One poll of the status URL
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PollStep {
+    pub attempt: u32,
+    pub url: String,
+    /// Time waited before sending this poll
+    pub waited_ms: u64,
+    /// Time since the initial request was sent
+    pub elapsed_ms: u64,
+    pub matched: bool,
+    pub response: ResponseData,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PollReport {
+    pub initial: ResponseData,
+    pub polls: Vec<PollStep>,
+    /// True when a response met the terminal condition
+    pub completed: bool,
+    /// Why polling stopped
+    pub reason: String,
+}
+
+/// Returns why a response ends polling, or None to keep polling.
+fn terminal_reason(
+    condition: &PollCondition,
+    path: Option<&JsonPath>,
+    status: u16,
+    body: &[u8],
+) -> Option<String> {
+    if condition.status_codes.contains(&status) {
+        return Some(format!("Status {status} is terminal"));
+    }
+    if let Some(path) = path {
+        let body: Value = serde_json::from_slice(body).ok()?;
+        let nodes = path.query(&body).all();
+        let matched = match &condition.equals {
+            Some(expected) => nodes.contains(&expected),
+            None => !nodes.is_empty(),
+        };
+        let expression = condition.json_path.as_deref().unwrap_or_default();
+        return matched.then(|| match &condition.equals {
+            Some(expected) => format!("{expression} equals {expected}"),
+            None => format!("{expression} matched"),
+        });
+    }
+    if condition.status_codes.is_empty() && status != 202 {
+        return Some(format!("Status {status} ends the job"));
+    }
+    None
+}
+
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Resolves a `Location` header against the URL it was returned for.
+fn status_url(base: &str, location: &str) -> Result<String, AppError> {
+    Url::parse(base)
+        .and_then(|base| base.join(location))
+        .map(String::from)
+        .map_err(|e| {
+            AppError::new(
+                ErrorKind::BadRequest,
+                format!("Invalid Location header '{location}': {e}"),
+            )
+        })
+}
+
+/// Delay before the next poll: `Retry-After` when the server sends one, else `interval`,
+/// both capped at `max_interval`.
+fn next_delay(
+    headers: &[(String, String)],
+    interval: Duration,
+    max_interval: Duration,
+) -> Duration {
+    header(headers, "retry-after")
+        .and_then(|value| parse_retry_after(value, chrono::Utc::now()))
+        .unwrap_or(interval)
+        .min(max_interval)
+}
+
+/// Sends `request` and, while the job it starts is pending (202), polls the status URL from
+/// its `Location` header with GET until `options.until` is met or attempts run out.
+pub async fn poll_until(
+    app: AppHandle,
+    request: Request,
+    options: PollOptions,
+) -> Result<PollReport, AppError> {
+    let path = options
+        .until
+        .json_path
+        .as_deref()
+        .map(JsonPath::parse)
+        .transpose()
+        .map_err(|e| AppError::new(ErrorKind::BadRequest, format!("Invalid JSONPath: {e}")))?;
+    let emitter: Arc<dyn LogEmitter> = Arc::new(TauriLogEmitter::new(app));
+    let engine = HyperEngine::new();
+    let started = Instant::now();
+
+    let initial = engine.execute(request.clone(), emitter.clone()).await?;
+    let mut report = PollReport {
+        initial,
+        polls: Vec::new(),
+        completed: false,
+        reason: String::new(),
+    };
+    if report.initial.status != 202 {
+        report.completed = true;
+        report.reason = format!("Status {} is not 202 Accepted", report.initial.status);
+        return Ok(report);
+    }
+    let Some(location) = header(&report.initial.headers, "location") else {
+        report.reason = "202 response has no Location header to poll".to_string();
+        return Ok(report);
+    };
+    let mut url = status_url(&request.url, location)?;
+
+    // Polls carry the request's headers (e.g. auth), but not its body
+    let mut headers = request.headers.clone().unwrap_or_default();
+    headers.retain(|(name, _)| {
+        !name.eq_ignore_ascii_case("content-type") && !name.eq_ignore_ascii_case("content-length")
+    });
+    let max_interval = Duration::from_millis(options.max_interval_ms);
+    let mut interval = Duration::from_millis(options.interval_ms).min(max_interval);
+    let mut delay = next_delay(&report.initial.headers, interval, max_interval);
+
+    for attempt in 1..=options.max_attempts {
+        tokio::time::sleep(delay).await;
+        let response = engine
+            .execute(
+                Request {
+                    url: url.clone(),
+                    method: "GET".to_string(),
+                    headers: Some(headers.clone()),
+                    body: None,
+                    body_file_path: None,
+                    multipart_parts: None,
+                    content_encoding: None,
+                    ..request.clone()
+                },
+                emitter.clone(),
+            )
+            .await?;
+
+        let reason = terminal_reason(
+            &options.until,
+            path.as_ref(),
+            response.status,
+            &response.body,
+        );
+        let next_url = match header(&response.headers, "location") {
+            Some(location) if reason.is_none() => Some(status_url(&url, location)?),
+            _ => None,
+        };
+        let waited = delay;
+        interval = interval.mul_f64(options.backoff.max(1.0)).min(max_interval);
+        delay = next_delay(&response.headers, interval, max_interval);
+        report.polls.push(PollStep {
+            attempt,
+            url: url.clone(),
+            waited_ms: waited.as_millis() as u64,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+            matched: reason.is_some(),
+            response,
+        });
+        if let Some(reason) = reason {
+            report.completed = true;
+            report.reason = reason;
+            return Ok(report);
+        }
+        // A pending job may move its status resource
+        if let Some(next_url) = next_url {
+            url = next_url;
+        }
+    }
+
+    report.reason = format!("Job still pending after {} polls", options.max_attempts);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PollCondition, next_delay, status_url, terminal_reason};
+    use serde_json::json;
+    use serde_json_path::JsonPath;
+    use std::time::Duration;
+
+    fn headers(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn defaults_to_first_non_202_status() {
+        let until = PollCondition::default();
+        assert_eq!(terminal_reason(&until, None, 202, b""), None);
+        assert!(terminal_reason(&until, None, 200, b"").is_some());
+        assert!(terminal_reason(&until, None, 303, b"").is_some());
+    }
+
+    #[test]
+    fn matches_status_codes_and_json_path() {
+        let until = PollCondition {
+            status_codes: vec![303],
+            json_path: Some("$.status".to_string()),
+            equals: Some(json!("succeeded")),
+        };
+        let path = JsonPath::parse("$.status").unwrap();
+        let pending = br#"{"status":"running"}"#;
+        let done = br#"{"status":"succeeded"}"#;
+        assert_eq!(terminal_reason(&until, Some(&path), 200, pending), None);
+        assert_eq!(
+            terminal_reason(&until, Some(&path), 200, done).as_deref(),
+            Some("$.status equals \"succeeded\"")
+        );
+        assert!(terminal_reason(&until, Some(&path), 303, b"").is_some());
+        // Non-JSON bodies never match the path
+        assert_eq!(terminal_reason(&until, Some(&path), 200, b"<ok/>"), None);
+
+        let filter = JsonPath::parse("$[?@.progress >= 100]").unwrap();
+        let until = PollCondition {
+            json_path: Some("$[?@.progress >= 100]".to_string()),
+            ..Default::default()
+        };
+        let body = r#"{"job":{"progress":100}}"#;
+        assert!(terminal_reason(&until, Some(&filter), 200, body.as_bytes()).is_some());
+    }
+
+    #[test]
+    fn resolves_relative_locations() {
+        assert_eq!(
+            status_url("https://api.example.com/v1/jobs", "/v1/jobs/42/status").unwrap(),
+            "https://api.example.com/v1/jobs/42/status"
+        );
+        assert_eq!(
+            status_url("https://api.example.com/v1/jobs/", "42").unwrap(),
+            "https://api.example.com/v1/jobs/42"
+        );
+        assert_eq!(
+            status_url("https://api.example.com/", "https://jobs.example.com/7").unwrap(),
+            "https://jobs.example.com/7"
+        );
+    }
+
+    #[test]
+    fn retry_after_overrides_interval_up_to_the_cap() {
+        let interval = Duration::from_secs(1);
+        let cap = Duration::from_secs(10);
+        assert_eq!(next_delay(&[], interval, cap), interval);
+        assert_eq!(
+            next_delay(&headers(&[("Retry-After", "5")]), interval, cap),
+            Duration::from_secs(5)
+        );
+        assert_eq!(
+            next_delay(&headers(&[("Retry-After", "120")]), interval, cap),
+            cap
+        );
+    }
+}
@@ -0,0 +1,343 @@
+//! Protocol Buffers response bodies, e.g. `application/x-protobuf` or gRPC, decoded to JSON
+//! with the message types of a `.proto` file or a compiled descriptor set.
+
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::spool;
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor, SerializeOptions};
+use protobuf::Message as _;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::Read;
+use std::path::Path;
+
+/// Where the message types come from
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "type")]
+pub enum ProtoSchema {
+    /// A `.proto` file. Its imports are looked up in its own directory, then `include_paths`;
+    /// `google/protobuf/*.proto` are built in.
+    #[serde(rename = "proto", rename_all = "camelCase")]
+    Proto {
+        path: String,
+        #[serde(default)]
+        include_paths: Vec<String>,
+    },
+    /// A binary `FileDescriptorSet`, e.g. from `protoc --include_imports --descriptor_set_out`
+    #[serde(rename = "descriptorSet", rename_all = "camelCase")]
+    DescriptorSet { path: String },
+}
+
+/// A Protocol Buffers body to decode, given inline or as the file a large response was spooled to
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtobufRequest {
+    pub body: Option<Vec<u8>>,
+    pub file_path: Option<String>,
+    pub schema: ProtoSchema,
+    /// Fully qualified name of the body's message type, e.g. `shop.v1.PriceReply`
+    pub message_type: String,
+    /// The body is gRPC framed: each message prefixed with a compression flag and its length
+    #[serde(default)]
+    pub grpc: bool,
+    /// `grpc-encoding` of the response, which compressed messages are decoded with
+    pub grpc_encoding: Option<String>,
+}
+
+/// A decoded body
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedProtobuf {
+    pub message_type: String,
+    /// The messages in their JSON form (proto3 JSON mapping, default values included); a
+    /// body that isn't gRPC framed holds one
+    pub messages: Vec<Value>,
+}
+
+fn invalid_schema(path: &str, e: impl std::fmt::Display) -> AppError {
+    AppError::new(
+        ErrorKind::BadRequest,
+        format!("Invalid schema '{path}': {e}"),
+    )
+}
+
+/// The message types of a schema
+pub fn load_schema(schema: &ProtoSchema) -> Result<DescriptorPool, AppError> {
+    match schema {
+        ProtoSchema::DescriptorSet { path } => {
+            let bytes = std::fs::read(path).map_err(|e| {
+                AppError::new(
+                    ErrorKind::FileNotFound,
+                    format!("Failed to read '{path}': {e}"),
+                )
+            })?;
+            DescriptorPool::decode(bytes.as_slice()).map_err(|e| invalid_schema(path, e))
+        }
+        ProtoSchema::Proto {
+            path,
+            include_paths,
+        } => {
+            let directory = Path::new(path).parent().unwrap_or(Path::new("."));
+            // The pure Rust parser, so decoding doesn't need protoc installed
+            let parsed = protobuf_parse::Parser::new()
+                .pure()
+                .include(directory)
+                .includes(include_paths)
+                .input(path)
+                .parse_and_typecheck()
+                .map_err(|e| invalid_schema(path, format!("{e:#}")))?;
+            // Imported files are kept, since the pool needs every type the input refers to
+            let mut files = protobuf::descriptor::FileDescriptorSet::new();
+            files.file = parsed.file_descriptors;
+            let bytes = files
+                .write_to_bytes()
+                .map_err(|e| invalid_schema(path, e))?;
+            DescriptorPool::decode(bytes.as_slice()).map_err(|e| invalid_schema(path, e))
+        }
+    }
+}
+
+/// Names of the schema's message types, sorted
+pub fn message_types(schema: &ProtoSchema) -> Result<Vec<String>, AppError> {
+    let mut names: Vec<String> = load_schema(schema)?
+        .all_messages()
+        .filter(|message| !message.is_map_entry())
+        .map(|message| message.full_name().to_string())
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+fn decode_message(descriptor: &MessageDescriptor, bytes: &[u8]) -> Result<Value, AppError> {
+    let message = DynamicMessage::decode(descriptor.clone(), bytes).map_err(|e| {
+        AppError::new(
+            ErrorKind::BadRequest,
+            format!("Body isn't a {}: {e}", descriptor.full_name()),
+        )
+    })?;
+    let options = SerializeOptions::new().skip_default_fields(false);
+    message
+        .serialize_with_options(serde_json::value::Serializer, &options)
+        .map_err(|e| AppError::new(ErrorKind::JsonError, e.to_string()))
+}
+
+fn decompress(message: &[u8], encoding: Option<&str>) -> Result<Vec<u8>, AppError> {
+    let mut out = Vec::new();
+    let result = match encoding {
+        Some("gzip") => flate2::read::GzDecoder::new(message).read_to_end(&mut out),
+        // gRPC's "deflate" is zlib framed
+        Some("deflate") => flate2::read::ZlibDecoder::new(message).read_to_end(&mut out),
+        Some("zstd") => zstd::stream::read::Decoder::new(message)?.read_to_end(&mut out),
+        other => {
+            return Err(AppError::new(
+                ErrorKind::BadRequest,
+                format!(
+                    "Can't decompress a gRPC message encoded as '{}'",
+                    other.unwrap_or("identity")
+                ),
+            ));
+        }
+    };
+    result.map_err(|e| {
+        AppError::new(
+            ErrorKind::BadRequest,
+            format!("Failed to decompress a gRPC message: {e}"),
+        )
+    })?;
+    Ok(out)
+}
+
+/// The messages of a gRPC framed body: a compression flag byte and a 4-byte big-endian length
+/// before each
+fn grpc_messages(mut body: &[u8], encoding: Option<&str>) -> Result<Vec<Vec<u8>>, AppError> {
+    let mut messages = Vec::new();
+    while !body.is_empty() {
+        let truncated = || AppError::new(ErrorKind::BadRequest, "The gRPC body ends mid-message");
+        let header = body.get(..5).ok_or_else(truncated)?;
+        let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+        let message = body.get(5..5 + len).ok_or_else(truncated)?;
+        messages.push(match header[0] {
+            0 => message.to_vec(),
+            _ => decompress(message, encoding)?,
+        });
+        body = &body[5 + len..];
+    }
+    Ok(messages)
+}
+
+/// Decodes a Protocol Buffers body to JSON
+pub fn decode(request: ProtobufRequest) -> Result<DecodedProtobuf, AppError> {
+    let body = match (request.body, &request.file_path) {
+        (Some(body), _) => body,
+        (None, Some(path)) => {
+            let mut body = Vec::new();
+            spool::open(Path::new(path))?.read_to_end(&mut body)?;
+            body
+        }
+        (None, None) => {
+            return Err(AppError::new(
+                ErrorKind::BadRequest,
+                "A response body or file path is required",
+            ));
+        }
+    };
+    let pool = load_schema(&request.schema)?;
+    let descriptor = pool
+        .get_message_by_name(&request.message_type)
+        .ok_or_else(|| {
+            AppError::new(
+                ErrorKind::BadRequest,
+                format!("The schema has no message type '{}'", request.message_type),
+            )
+        })?;
+    let messages = if request.grpc {
+        grpc_messages(&body, request.grpc_encoding.as_deref())?
+            .iter()
+            .map(|message| decode_message(&descriptor, message))
+            .collect::<Result<_, _>>()?
+    } else {
+        vec![decode_message(&descriptor, &body)?]
+    };
+    Ok(DecodedProtobuf {
+        message_type: request.message_type,
+        messages,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProtoSchema, ProtobufRequest, decode, load_schema, message_types};
+    use prost_reflect::DynamicMessage;
+    use prost_reflect::prost::Message;
+    use serde_json::json;
+    use std::io::Write;
+
+    const SHOP_PROTO: &str = r#"
+        syntax = "proto3";
+        package shop.v1;
+        import "google/protobuf/timestamp.proto";
+        import "common.proto";
+
+        message PriceReply {
+          string item = 1;
+          int32 cents = 2;
+          repeated string tags = 3;
+          google.protobuf.Timestamp at = 4;
+          shop.common.Currency currency = 5;
+          map<string, int32> stock = 6;
+        }
+    "#;
+
+    const COMMON_PROTO: &str = r#"
+        syntax = "proto3";
+        package shop.common;
+        enum Currency { CURRENCY_UNSPECIFIED = 0; EUR = 1; }
+    "#;
+
+    fn schema(dir: &std::path::Path) -> ProtoSchema {
+        std::fs::create_dir(dir.join("common")).unwrap();
+        std::fs::write(dir.join("common/common.proto"), COMMON_PROTO).unwrap();
+        std::fs::write(dir.join("shop.proto"), SHOP_PROTO).unwrap();
+        ProtoSchema::Proto {
+            path: dir.join("shop.proto").to_string_lossy().to_string(),
+            include_paths: vec![dir.join("common").to_string_lossy().to_string()],
+        }
+    }
+
+    fn reply(schema: &ProtoSchema, item: &str) -> Vec<u8> {
+        let descriptor = load_schema(schema)
+            .unwrap()
+            .get_message_by_name("shop.v1.PriceReply")
+            .unwrap();
+        let json = json!({
+            "item": item,
+            "cents": 250,
+            "tags": ["a"],
+            "at": "2024-01-02T03:04:05Z",
+            "currency": "EUR"
+        });
+        DynamicMessage::deserialize(descriptor, json)
+            .unwrap()
+            .encode_to_vec()
+    }
+
+    #[test]
+    fn decodes_with_a_proto_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let schema = schema(dir.path());
+        assert_eq!(
+            message_types(&schema).unwrap(),
+            ["google.protobuf.Timestamp", "shop.v1.PriceReply"]
+        );
+
+        let decoded = decode(ProtobufRequest {
+            body: Some(reply(&schema, "pen")),
+            file_path: None,
+            schema: schema.clone(),
+            message_type: "shop.v1.PriceReply".to_string(),
+            grpc: false,
+            grpc_encoding: None,
+        })
+        .unwrap();
+        assert_eq!(
+            decoded.messages,
+            [json!({
+                "item": "pen",
+                "cents": 250,
+                "tags": ["a"],
+                "at": "2024-01-02T03:04:05Z",
+                "currency": "EUR",
+                "stock": {}
+            })]
+        );
+
+        let missing = ProtobufRequest {
+            body: Some(Vec::new()),
+            file_path: None,
+            schema,
+            message_type: "shop.v1.Missing".to_string(),
+            grpc: false,
+            grpc_encoding: None,
+        };
+        assert!(decode(missing).is_err());
+    }
+
+    #[test]
+    fn decodes_grpc_frames_with_a_descriptor_set() {
+        let dir = tempfile::tempdir().unwrap();
+        let proto = schema(dir.path());
+        let set_path = dir.path().join("shop.pb");
+        std::fs::write(&set_path, load_schema(&proto).unwrap().encode_to_vec()).unwrap();
+        let schema = ProtoSchema::DescriptorSet {
+            path: set_path.to_string_lossy().to_string(),
+        };
+
+        let mut body = Vec::new();
+        let plain = reply(&schema, "pen");
+        body.push(0);
+        body.extend((plain.len() as u32).to_be_bytes());
+        body.extend(&plain);
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(&reply(&schema, "ink")).unwrap();
+        let compressed = gzip.finish().unwrap();
+        body.push(1);
+        body.extend((compressed.len() as u32).to_be_bytes());
+        body.extend(&compressed);
+
+        let mut request = ProtobufRequest {
+            body: Some(body.clone()),
+            file_path: None,
+            schema,
+            message_type: "shop.v1.PriceReply".to_string(),
+            grpc: true,
+            grpc_encoding: Some("gzip".to_string()),
+        };
+        let decoded = decode(request.clone()).unwrap();
+        assert_eq!(decoded.messages.len(), 2);
+        assert_eq!(decoded.messages[0]["item"], "pen");
+        assert_eq!(decoded.messages[1]["item"], "ink");
+
+        body.truncate(body.len() - 1);
+        request.body = Some(body);
+        assert!(decode(request).is_err());
+    }
+}
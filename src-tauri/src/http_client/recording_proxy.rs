@@ -0,0 +1,514 @@
+//! A local forward proxy that records the traffic passing through it. With a browser or app
+//! configured to use it, each plain HTTP exchange is captured whole, ready to be kept in history
+//! or saved as a request and replayed. HTTPS arrives as CONNECT and is tunnelled untouched, so
+//! only its host and byte counts are seen.
+
+use crate::errors::{AppError, ErrorKind};
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Incoming;
+use hyper::http::{HeaderMap, HeaderName, Method, Request, Response, StatusCode};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper_util::client::legacy::Client;
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Instant;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::sync::CancellationToken;
+
+/// Most of each body kept in a captured exchange; the whole body is still forwarded
+const MAX_CAPTURED_BODY: usize = 4 * 1024 * 1024;
+
+/// Headers that describe one connection rather than the message, which a proxy must not pass
+/// on (RFC 9110 section 7.6.1)
+const HOP_BY_HOP: [&str; 9] = [
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+static PROXIES: LazyLock<Mutex<HashMap<String, CancellationToken>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyOptions {
+    /// Names the proxy for `stop`
+    pub proxy_id: String,
+    /// Port to listen on. Defaults to an ephemeral one.
+    pub port: Option<u16>,
+    /// Address to listen on. Defaults to 127.0.0.1; any other exposes the proxy to the network.
+    pub bind_address: Option<String>,
+    /// Record captured exchanges in history. Defaults to true.
+    pub record_history: Option<bool>,
+    /// Collection the recorded history entries are filed under
+    pub collection_id: Option<String>,
+}
+
+/// A request forwarded by the proxy and the response it got
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CapturedExchange {
+    pub method: String,
+    pub url: String,
+    pub request_headers: Vec<(String, String)>,
+    pub request_body: Vec<u8>,
+    pub status: u16,
+    pub status_text: String,
+    pub response_headers: Vec<(String, String)>,
+    /// The body as the server sent it, still in any content encoding
+    pub response_body: Vec<u8>,
+    /// Milliseconds from forwarding the request to receiving the whole response
+    pub duration: u64,
+    /// Whether either body was longer than what was kept
+    pub truncated: bool,
+    /// ID of the history entry the exchange was recorded as
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum ProxyEvent {
+    Exchange {
+        exchange: CapturedExchange,
+    },
+    /// A CONNECT tunnel, reported once it has closed
+    Tunnel {
+        /// The `host:port` tunnelled to
+        authority: String,
+        bytes_sent: u64,
+        bytes_received: u64,
+    },
+    /// Always the last event of a proxy
+    Stopped {
+        /// Why the proxy stopped, when it wasn't stopped by `stop`
+        reason: Option<String>,
+    },
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyInfo {
+    pub proxy_id: String,
+    /// The address listened on, for configuring clients
+    pub address: String,
+}
+
+type EventHandler = Arc<dyn Fn(ProxyEvent) + Send + Sync>;
+
+struct Shared {
+    client: Client<HttpConnector, Full<Bytes>>,
+    on_event: EventHandler,
+}
+
+/// Starts listening as `options` asks, passing each exchange and tunnel to `on_event` until
+/// the proxy is stopped.
+pub async fn start(
+    options: ProxyOptions,
+    on_event: impl Fn(ProxyEvent) + Send + Sync + 'static,
+) -> Result<ProxyInfo, AppError> {
+    let id = options.proxy_id.clone();
+    if PROXIES.lock().unwrap().contains_key(&id) {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            format!("Proxy '{id}' is already running"),
+        ));
+    }
+
+    let host = options.bind_address.as_deref().unwrap_or("127.0.0.1");
+    let listener = TcpListener::bind((host, options.port.unwrap_or(0)))
+        .await
+        .map_err(|e| {
+            let message = format!("Failed to start the proxy on {host}: {e}");
+            AppError::new(AppError::from(e).kind, message)
+        })?;
+    let info = ProxyInfo {
+        proxy_id: id.clone(),
+        address: listener.local_addr()?.to_string(),
+    };
+
+    let token = CancellationToken::new();
+    {
+        let mut proxies = PROXIES.lock().unwrap();
+        if proxies.contains_key(&id) {
+            return Err(AppError::new(
+                ErrorKind::BadRequest,
+                format!("Proxy '{id}' is already running"),
+            ));
+        }
+        proxies.insert(id.clone(), token.clone());
+    }
+    let shared = Arc::new(Shared {
+        client: Client::builder(TokioExecutor::new()).build_http(),
+        on_event: Arc::new(on_event),
+    });
+    tokio::spawn(accept_loop(id, listener, token, shared));
+    Ok(info)
+}
+
+async fn accept_loop(
+    id: String,
+    listener: TcpListener,
+    token: CancellationToken,
+    shared: Arc<Shared>,
+) {
+    let reason = loop {
+        tokio::select! {
+            _ = token.cancelled() => break None,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    tokio::spawn(serve_connection(stream, token.clone(), shared.clone()));
+                }
+                Err(e) => break Some(e.to_string()),
+            }
+        }
+    };
+    // A proxy stopped by `stop` is already gone, and its id may be in use again
+    if !token.is_cancelled() {
+        PROXIES.lock().unwrap().remove(&id);
+    }
+    (shared.on_event)(ProxyEvent::Stopped { reason });
+}
+
+async fn serve_connection(stream: TcpStream, token: CancellationToken, shared: Arc<Shared>) {
+    let service = service_fn(move |request| handle(request, shared.clone()));
+    let connection = http1::Builder::new()
+        .serve_connection(TokioIo::new(stream), service)
+        .with_upgrades();
+    tokio::select! {
+        _ = token.cancelled() => {}
+        result = connection => {
+            if let Err(e) = result {
+                log::debug!("Proxy connection ended: {e}");
+            }
+        }
+    }
+}
+
+fn plain_response(status: StatusCode, message: String) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::from(message)));
+    *response.status_mut() = status;
+    response
+}
+
+async fn handle(
+    request: Request<Incoming>,
+    shared: Arc<Shared>,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    if request.method() == Method::CONNECT {
+        return Ok(tunnel(request, shared).await);
+    }
+    Ok(forward(request, &shared)
+        .await
+        .unwrap_or_else(|(status, message)| plain_response(status, message)))
+}
+
+/// Connects to the CONNECT request's authority and, once the client has the 200, relays bytes
+/// both ways until either side closes.
+async fn tunnel(request: Request<Incoming>, shared: Arc<Shared>) -> Response<Full<Bytes>> {
+    let Some(authority) = request.uri().authority().map(|a| a.to_string()) else {
+        let message = "CONNECT needs a host:port target".to_string();
+        return plain_response(StatusCode::BAD_REQUEST, message);
+    };
+    let mut server = match TcpStream::connect(authority.as_str()).await {
+        Ok(server) => server,
+        Err(e) => {
+            let message = format!("Failed to connect to {authority}: {e}");
+            return plain_response(StatusCode::BAD_GATEWAY, message);
+        }
+    };
+    tokio::spawn(async move {
+        let upgraded = match hyper::upgrade::on(request).await {
+            Ok(upgraded) => upgraded,
+            Err(e) => {
+                log::debug!("Proxy tunnel to {authority} never opened: {e}");
+                return;
+            }
+        };
+        let mut client = TokioIo::new(upgraded);
+        let (bytes_sent, bytes_received) =
+            match tokio::io::copy_bidirectional(&mut client, &mut server).await {
+                Ok(counts) => counts,
+                Err(e) => {
+                    log::debug!("Proxy tunnel to {authority} failed: {e}");
+                    (0, 0)
+                }
+            };
+        (shared.on_event)(ProxyEvent::Tunnel {
+            authority,
+            bytes_sent,
+            bytes_received,
+        });
+    });
+    Response::new(Full::new(Bytes::new()))
+}
+
+/// Drops the hop-by-hop headers, including any the Connection header names
+fn strip_hop_by_hop(headers: &mut HeaderMap) {
+    let named: Vec<HeaderName> = headers
+        .get_all(hyper::header::CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|name| HeaderName::from_bytes(name.trim().as_bytes()).ok())
+        .collect();
+    for name in named.iter().chain(&HOP_BY_HOP.map(HeaderName::from_static)) {
+        headers.remove(name);
+    }
+}
+
+pub(crate) fn header_pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+            (name.as_str().to_string(), value)
+        })
+        .collect()
+}
+
+fn captured(body: &Bytes) -> (Vec<u8>, bool) {
+    let kept = body.len().min(MAX_CAPTURED_BODY);
+    (body[..kept].to_vec(), kept < body.len())
+}
+
+/// Sends an absolute-form request on to its server and captures the exchange. Fails with the
+/// status and message to answer the client with.
+async fn forward(
+    request: Request<Incoming>,
+    shared: &Shared,
+) -> Result<Response<Full<Bytes>>, (StatusCode, String)> {
+    let (mut parts, body) = request.into_parts();
+    if parts.uri.scheme_str() != Some("http") || parts.uri.authority().is_none() {
+        let message = format!(
+            "Not a proxy request: {}. Only absolute http:// URLs are forwarded; HTTPS goes through CONNECT",
+            parts.uri
+        );
+        return Err((StatusCode::BAD_REQUEST, message));
+    }
+    let request_body = body
+        .collect()
+        .await
+        .map_err(|e| {
+            let message = format!("Failed to read the request body: {e}");
+            (StatusCode::BAD_REQUEST, message)
+        })?
+        .to_bytes();
+    strip_hop_by_hop(&mut parts.headers);
+    let method = parts.method.to_string();
+    let url = parts.uri.to_string();
+    let request_headers = header_pairs(&parts.headers);
+
+    let mut upstream = Request::new(Full::new(request_body.clone()));
+    *upstream.method_mut() = parts.method;
+    *upstream.uri_mut() = parts.uri;
+    *upstream.headers_mut() = parts.headers;
+    let started = Instant::now();
+    let response = shared.client.request(upstream).await.map_err(|e| {
+        let message = format!("Failed to forward {method} {url}: {e}");
+        (StatusCode::BAD_GATEWAY, message)
+    })?;
+    let (mut parts, body) = response.into_parts();
+    let response_body = body
+        .collect()
+        .await
+        .map_err(|e| {
+            let message = format!("Failed to read the response to {method} {url}: {e}");
+            (StatusCode::BAD_GATEWAY, message)
+        })?
+        .to_bytes();
+    let duration = started.elapsed().as_millis() as u64;
+    strip_hop_by_hop(&mut parts.headers);
+
+    let (request_body_kept, request_truncated) = captured(&request_body);
+    let (response_body_kept, response_truncated) = captured(&response_body);
+    (shared.on_event)(ProxyEvent::Exchange {
+        exchange: CapturedExchange {
+            method,
+            url,
+            request_headers,
+            request_body: request_body_kept,
+            status: parts.status.as_u16(),
+            status_text: parts
+                .status
+                .canonical_reason()
+                .unwrap_or_default()
+                .to_string(),
+            response_headers: header_pairs(&parts.headers),
+            response_body: response_body_kept,
+            duration,
+            truncated: request_truncated || response_truncated,
+            history_id: None,
+        },
+    });
+    Ok(Response::from_parts(parts, Full::new(response_body)))
+}
+
+/// Stops the proxy `proxy_id`, closing its connections. Returns false when it wasn't running.
+pub fn stop(proxy_id: &str) -> bool {
+    let Some(token) = PROXIES.lock().unwrap().remove(proxy_id) else {
+        return false;
+    };
+    token.cancel();
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProxyEvent, ProxyOptions, start, stop};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    fn options(proxy_id: &str) -> ProxyOptions {
+        ProxyOptions {
+            proxy_id: proxy_id.to_string(),
+            port: None,
+            bind_address: None,
+            record_history: None,
+            collection_id: None,
+        }
+    }
+
+    async fn started(proxy_id: &str) -> (String, Arc<Mutex<Vec<ProxyEvent>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let info = start(options(proxy_id), move |event| {
+            sink.lock().unwrap().push(event);
+        })
+        .await
+        .unwrap();
+        (info.address, events)
+    }
+
+    async fn wait_for(events: &Mutex<Vec<ProxyEvent>>, count: usize) {
+        for _ in 0..200 {
+            if events.lock().unwrap().len() >= count {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("expected {count} events, got {:?}", events.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn forwards_and_captures_plain_http() {
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server_addr = server.local_addr().unwrap();
+        let received = tokio::spawn(async move {
+            let (mut stream, _) = server.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.ends_with(b"abc") {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 201 Created\r\nContent-Length: 5\r\nX-Id: 7\r\n\r\nhello")
+                .await
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let (proxy_addr, events) = started("plain").await;
+        let mut client = TcpStream::connect(&proxy_addr).await.unwrap();
+        let request = format!(
+            "POST http://{server_addr}/items?x=1 HTTP/1.1\r\nHost: {server_addr}\r\nProxy-Connection: keep-alive\r\nContent-Length: 3\r\nConnection: close\r\n\r\nabc"
+        );
+        client.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 201 Created"), "{response}");
+        assert!(response.ends_with("hello"));
+
+        let received = received.await.unwrap().to_lowercase();
+        assert!(
+            received.starts_with("post /items?x=1 http/1.1"),
+            "{received}"
+        );
+        assert!(!received.contains("proxy-connection"));
+
+        let ProxyEvent::Exchange { exchange } = events.lock().unwrap()[0].clone() else {
+            panic!("expected an exchange");
+        };
+        assert_eq!(exchange.url, format!("http://{server_addr}/items?x=1"));
+        assert_eq!(exchange.request_body, b"abc");
+        assert_eq!(exchange.status, 201);
+        assert_eq!(exchange.status_text, "Created");
+        assert!(
+            exchange
+                .response_headers
+                .contains(&("x-id".to_string(), "7".to_string()))
+        );
+        assert_eq!(exchange.response_body, b"hello");
+
+        assert!(stop("plain"));
+        assert!(!stop("plain"));
+        wait_for(&events, 2).await;
+        assert_eq!(
+            events.lock().unwrap()[1],
+            ProxyEvent::Stopped { reason: None }
+        );
+    }
+
+    #[tokio::test]
+    async fn tunnels_connect_and_rejects_origin_form_requests() {
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server_addr = server.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = server.accept().await.unwrap();
+            let (mut reader, mut writer) = stream.split();
+            tokio::io::copy(&mut reader, &mut writer).await.unwrap();
+        });
+
+        let (proxy_addr, events) = started("tunnel").await;
+        let mut client = TcpStream::connect(&proxy_addr).await.unwrap();
+        let connect = format!("CONNECT {server_addr} HTTP/1.1\r\nHost: {server_addr}\r\n\r\n");
+        client.write_all(connect.as_bytes()).await.unwrap();
+        let mut buf = [0u8; 1024];
+        let n = client.read(&mut buf).await.unwrap();
+        assert!(buf[..n].starts_with(b"HTTP/1.1 200 OK"));
+
+        client.write_all(b"ping").await.unwrap();
+        let n = client.read(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"ping");
+        client.shutdown().await.unwrap();
+        wait_for(&events, 1).await;
+        assert_eq!(
+            events.lock().unwrap()[0],
+            ProxyEvent::Tunnel {
+                authority: server_addr.to_string(),
+                bytes_sent: 4,
+                bytes_received: 4,
+            }
+        );
+
+        let mut client = TcpStream::connect(&proxy_addr).await.unwrap();
+        client
+            .write_all(b"GET /status HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request"));
+        assert!(response.contains("Not a proxy request: /status"));
+        stop("tunnel");
+    }
+}
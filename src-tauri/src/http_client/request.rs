@@ -1,5 +1,16 @@
-use serde::Deserialize;
+use crate::http_client::assertions::Assertion;
+use crate::http_client::body_stream::BodySink;
+use crate::http_client::extractors::Extractor;
+use crate::http_client::graphql::GraphqlOperation;
+use crate::http_client::har::EntrySlot;
+use crate::http_client::ndjson::RecordSink;
+use crate::http_client::soap::SoapOperation;
+use crate::http_client::sse::EventSink;
+use crate::http_client::variables::VariableValue;
+use crate::operations::ProgressSink;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use tokio_util::sync::CancellationToken;
 
 #[derive(Debug, Deserialize, Clone)]
 #[serde(tag = "type")]
@@ -15,6 +26,135 @@ pub enum MultipartPart {
     },
 }
 
+/// A query parameter appended to the URL, given as `[name, value]` or as
+/// `{ name, value, encode }` to control its encoding.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(from = "QueryParamRepr")]
+pub struct QueryParam {
+    pub name: String,
+    pub value: String,
+    /// Percent-encode the name and value. When false they're appended as given, e.g. because
+    /// they're already encoded.
+    pub encode: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum QueryParamRepr {
+    Pair(String, String),
+    Object {
+        name: String,
+        value: String,
+        encode: Option<bool>,
+    },
+}
+
+impl From<QueryParamRepr> for QueryParam {
+    fn from(repr: QueryParamRepr) -> Self {
+        match repr {
+            QueryParamRepr::Pair(name, value) => Self {
+                name,
+                value,
+                encode: true,
+            },
+            QueryParamRepr::Object {
+                name,
+                value,
+                encode,
+            } => Self {
+                name,
+                value,
+                encode: encode.unwrap_or(true),
+            },
+        }
+    }
+}
+
+/// Headers added unless the request sets them itself; each is sent unless set to false.
+/// Turning off Host or Content-Length forces HTTP/1.1 and a new connection.
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoHeaders {
+    pub user_agent: Option<bool>,
+    pub host: Option<bool>,
+    /// The `Accept` GraphQL requests default to
+    pub accept: Option<bool>,
+    pub content_length: Option<bool>,
+}
+
+/// Resolves `host` to `ip`, whatever DNS says
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DnsOverride {
+    pub host: String,
+    pub ip: String,
+}
+
+impl Request {
+    /// Whether the engine adds the header `pick` selects
+    pub fn sends_auto_header(&self, pick: fn(&AutoHeaders) -> Option<bool>) -> bool {
+        !self.raw_headers.unwrap_or(false)
+            && self.auto_headers.as_ref().and_then(pick).unwrap_or(true)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HeadersRepr {
+    List(Vec<(String, String)>),
+    Map(HashMap<String, String>),
+}
+
+fn ordered_headers<'de, D>(deserializer: D) -> Result<Option<Vec<(String, String)>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(
+        Option::<HeadersRepr>::deserialize(deserializer)?.map(|repr| match repr {
+            HeadersRepr::List(list) => list,
+            HeadersRepr::Map(map) => map.into_iter().collect(),
+        }),
+    )
+}
+
+/// A host whose certificate isn't fully verified, such as a self-signed development server
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct InsecureHost {
+    /// Host name or IP; `*.example.test` matches every subdomain of `example.test`
+    pub host: String,
+    /// Only skip checking that the certificate names the host; its chain is still verified
+    #[serde(default)]
+    pub hostname_only: bool,
+}
+
+/// TLS versions the engine can speak
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TlsVersion {
+    #[serde(rename = "1.2")]
+    Tls12,
+    #[serde(rename = "1.3")]
+    Tls13,
+}
+
+impl TlsVersion {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TlsVersion::Tls12 => "1.2",
+            TlsVersion::Tls13 => "1.3",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum IpFamily {
+    #[default]
+    Auto,
+    Ipv4,
+    Ipv6,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub enum HttpVersionPref {
@@ -26,6 +166,250 @@ pub enum HttpVersionPref {
     Http2,
 }
 
+/// Compression applied to the outgoing request body.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentEncoding {
+    Gzip,
+    Deflate,
+    Zstd,
+}
+
+/// Which header carries the SHA-256 digest of the request body.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum BodyDigest {
+    /// `Content-Digest: sha-256=:…:` (RFC 9530)
+    ContentDigest,
+    /// `Digest: SHA-256=…` (RFC 3230), for servers that predate RFC 9530
+    Digest,
+    /// Both headers
+    Both,
+}
+
+/// Where to load the TLS client certificate (mutual TLS) from.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "type")]
+pub enum ClientCertificate {
+    /// PEM encoded certificate chain and private key on disk
+    #[serde(rename = "pem", rename_all = "camelCase")]
+    Pem { cert_path: String, key_path: String },
+    /// Identity from the OS certificate store (Windows "My" store or macOS keychain),
+    /// selected by SHA-1/SHA-256 thumbprint and/or subject
+    #[serde(rename = "system", rename_all = "camelCase")]
+    System {
+        thumbprint: Option<String>,
+        subject: Option<String>,
+    },
+}
+
+/// Keep-alive connection pooling. Without it (or with `enabled: false`) every request
+/// opens, and closes, its own connection.
+#[derive(Debug, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionPool {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Seconds an idle connection is kept open. Defaults to 90.
+    pub idle_timeout_secs: Option<u64>,
+    /// Maximum idle connections kept per host. Defaults to 8.
+    pub max_idle_per_host: Option<usize>,
+    /// Share a client, and its TLS and DNS setup, with requests of the same transport
+    /// profile. Defaults to true; false builds a client for each request, pooled or not.
+    pub share_client: Option<bool>,
+}
+
+/// Automatic retry of rate-limited (429) and unavailable (503) responses
+#[derive(Debug, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryPolicy {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Retries after the first attempt. Defaults to 3.
+    pub max_retries: Option<u32>,
+    /// Longest wait before a retry. A `Retry-After` asking for more ends the retries.
+    /// Defaults to 60.
+    pub max_delay_secs: Option<u64>,
+}
+
+/// Which of a request's log entries are sent to the frontend
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(tag = "mode", rename_all = "camelCase")]
+pub enum LogCapture {
+    /// Every entry
+    Full,
+    /// Every entry of a random `rate` (0 to 1) share of requests, none of the others
+    Sampled { rate: f64 },
+    /// No entries
+    Off,
+}
+
+/// Credentials for NTLM authentication, negotiated by the engine over one connection
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NtlmCredentials {
+    /// User name, optionally as `DOMAIN\user` or `user@domain`
+    pub username: String,
+    pub password: String,
+    pub domain: Option<String>,
+    pub workstation: Option<String>,
+}
+
+/// Authentication schemes the engine can answer a `WWW-Authenticate` challenge with
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ChallengeScheme {
+    Basic,
+    Digest,
+    /// NTLM inside Negotiate (RFC 4559); Kerberos isn't supported
+    Negotiate,
+}
+
+/// Credentials the engine answers a 401's `WWW-Authenticate` challenge with, retrying the
+/// request once (like curl's `--anyauth`)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ChallengeCredentials {
+    pub username: String,
+    pub password: String,
+    /// Schemes that may be answered; the strongest one offered when empty
+    #[serde(default)]
+    pub schemes: Vec<ChallengeScheme>,
+}
+
+/// Signature algorithms of the HTTP Message Signatures registry (RFC 9421 §6.2)
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureAlgorithm {
+    #[serde(rename = "ed25519")]
+    Ed25519,
+    #[serde(rename = "rsa-pss-sha512")]
+    RsaPssSha512,
+    #[serde(rename = "ecdsa-p256-sha256")]
+    EcdsaP256Sha256,
+    #[serde(rename = "hmac-sha256")]
+    HmacSha256,
+}
+
+/// Signs the request with HTTP Message Signatures (RFC 9421), adding `Signature-Input` and
+/// `Signature` headers.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageSignature {
+    /// Name the key is stored under in the keyring (see `store_signing_key`)
+    pub key_name: String,
+    pub algorithm: SignatureAlgorithm,
+    /// `keyid` parameter sent to the server; omitted when unset
+    pub key_id: Option<String>,
+    /// Label of the signature, "sig1" by default
+    pub label: Option<String>,
+    /// Covered components in order, e.g. `["@method", "@authority", "@path", "content-digest"]`
+    pub components: Vec<String>,
+    /// Adds an `expires` parameter this many seconds after `created`
+    pub expires_in_secs: Option<u64>,
+    /// Adds a random `nonce` parameter
+    #[serde(default)]
+    pub nonce: bool,
+    /// `tag` parameter naming the application profile
+    pub tag: Option<String>,
+    /// Key loaded from the keyring before sending; never read from the frontend
+    #[serde(skip)]
+    pub key_material: Option<String>,
+}
+
+/// How a computed value is written out
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum HookEncoding {
+    #[default]
+    Hex,
+    Base64,
+    /// URL-safe base64 without padding
+    Base64Url,
+}
+
+/// Digest algorithms pre-request hooks can hash and HMAC with
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HookAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TimestampFormat {
+    UnixSeconds,
+    UnixMillis,
+    /// RFC 3339 in UTC, e.g. `2024-05-01T12:00:00Z`
+    Iso8601,
+    /// RFC 7231 HTTP date, e.g. `Wed, 01 May 2024 12:00:00 GMT`
+    HttpDate,
+}
+
+/// One step of a pre-request hook. Text fields are templates: `{{name}}` is the value an
+/// earlier step stored under `name`, and `{{request.method}}`, `{{request.url}}`,
+/// `{{request.host}}`, `{{request.path}}`, `{{request.query}}`, `{{request.body}}` and
+/// `{{request.header.<name>}}` read the request as the earlier steps left it.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum HookStep {
+    /// Stores a template's value
+    Set {
+        name: String,
+        value: String,
+    },
+    /// Stores the current time
+    Timestamp {
+        name: String,
+        format: TimestampFormat,
+    },
+    /// Stores `bytes` random bytes (16 by default)
+    Nonce {
+        name: String,
+        bytes: Option<usize>,
+        #[serde(default)]
+        encoding: HookEncoding,
+    },
+    /// Stores the digest of `input`
+    Hash {
+        name: String,
+        algorithm: HookAlgorithm,
+        input: String,
+        #[serde(default)]
+        encoding: HookEncoding,
+    },
+    /// Stores the HMAC of `input` under `key`, a UTF-8 secret unless `key_encoding` says
+    /// how it's encoded
+    Hmac {
+        name: String,
+        algorithm: HookAlgorithm,
+        key: String,
+        key_encoding: Option<HookEncoding>,
+        input: String,
+        #[serde(default)]
+        encoding: HookEncoding,
+    },
+    /// Sets a header, replacing any the request has by that name
+    SetHeader {
+        name: String,
+        value: String,
+    },
+    RemoveHeader {
+        name: String,
+    },
+    /// Sets a query parameter, replacing any `query_params` entry by that name
+    SetQueryParam {
+        name: String,
+        value: String,
+    },
+}
+
 /// Options for an HTTP request sent via CurlClient
 /// over the Tauri backend.
 #[derive(Debug, Deserialize, Default, Clone)]
@@ -35,20 +419,42 @@ pub struct Request {
     pub request_id: String,
     // Full request URL
     pub url: String,
+    /// Query parameters appended to `url`, after any it already has, in order
+    pub query_params: Option<Vec<QueryParam>>,
     // HTTP method, e.g. "GET" or "POST"
     pub method: String,
-    /// Optional map of header key/value pairs
-    pub headers: Option<HashMap<String, String>>,
+    /// Header name/value pairs, sent in this order. Names may repeat to send a header more
+    /// than once; repeats are sent together, where the name first appears. A name-to-value map
+    /// is accepted too.
+    #[serde(default, deserialize_with = "ordered_headers")]
+    pub headers: Option<Vec<(String, String)>>,
     /// Optional request body as raw bytes
     pub body: Option<Vec<u8>>,
     /// If true, disable SSL certificate verification
     pub disable_ssl: Option<bool>,
+    /// Hosts whose certificates aren't verified, or aren't checked for their name, while
+    /// other hosts are verified as usual
+    pub insecure_hosts: Option<Vec<InsecureHost>>,
+    /// Check the server's certificates for revocation over OCSP, or their CRLs, on each new
+    /// connection. A revoked certificate fails the connection unless `disable_ssl` is set.
+    pub check_revocation: Option<bool>,
     /// Path to a custom root CA bundle (PEM format)
     pub ca_path: Option<String>,
+    /// Oldest TLS version to offer. Defaults to 1.2.
+    pub tls_min_version: Option<TlsVersion>,
+    /// Newest TLS version to offer. Defaults to 1.3.
+    pub tls_max_version: Option<TlsVersion>,
+    /// Cipher suites to offer, in order of preference, by IANA name (e.g.
+    /// "TLS_AES_128_GCM_SHA256"). Defaults to every suite the engine supports.
+    pub cipher_suites: Option<Vec<String>>,
     /// Hostname part for custom DNS override (e.g., "api.example.com")
     pub host_override: Option<String>,
-    /// IP to resolve host_override to (e.g., "127.0.0.1")
+    /// IP to resolve the URL's host to (e.g., "127.0.0.1"); shorthand for a `dns_overrides`
+    /// entry
     pub ip_override: Option<String>,
+    /// Hosts to resolve to fixed IPs in place of a DNS lookup, like per-request /etc/hosts
+    /// entries. They apply to every host the request connects to, redirects included.
+    pub dns_overrides: Option<Vec<DnsOverride>>,
     /// Timeout in seconds for the request
     pub timeout_secs: Option<u64>,
     /// User agent string
@@ -62,19 +468,156 @@ pub struct Request {
     /// If false, suppress DATA (body) logs, keep headers/ssl/debug only. Default true.
     pub log_bodies: Option<bool>,
 
+    /// Send the header block exactly as `headers` lists it: names as cased, in order, and no
+    /// automatic Host, User-Agent or Content-Length. Forces HTTP/1.1 and a new connection.
+    pub raw_headers: Option<bool>,
+
+    /// Turns off headers the engine adds on its own. Raw header mode turns them all off.
+    pub auto_headers: Option<AutoHeaders>,
+
     /// Optional multipart parts for backend-side assembly.
     pub multipart_parts: Option<Vec<MultipartPart>>,
 
     /// Optional path to a file to use as the raw request body.
     pub body_file_path: Option<String>,
 
+    /// Send a GraphQL operation as the JSON body, in place of `body`. Ignored with multipart
+    /// parts or a body file.
+    pub graphql: Option<GraphqlOperation>,
+
+    /// Send a SOAP operation as the XML body, its envelope built from the operation's
+    /// parameters. Ignored with multipart parts, a body file or a GraphQL operation.
+    pub soap: Option<SoapOperation>,
+
     /// Preferred HTTP version negotiation. Defaults to auto (h2 preferred via ALPN).
     pub http_version: Option<HttpVersionPref>,
 
+    /// Address family to connect over. Defaults to auto, which races the host's first IPv4
+    /// and IPv6 addresses (Happy Eyeballs).
+    pub ip_family: Option<IpFamily>,
+
+    /// Local IP to connect from, on machines with several addresses. Limits connections to
+    /// its address family.
+    pub local_address: Option<String>,
+
+    /// Network interface to connect through, e.g. "eth1" or "utun3". Not supported on Windows.
+    pub interface: Option<String>,
+
     /// Maximum number of redirects to follow automatically. 0 disables.
     pub max_redirects: Option<u32>,
 
     /// Threshold in bytes before streaming response body to a temp file on disk.
     /// If not provided, defaults to 20MB.
     pub preview_max_bytes: Option<u64>,
+
+    /// Stop reading the response body after this many bytes, returning what was read with
+    /// `truncated` set. Unlimited if not provided.
+    pub max_response_bytes: Option<u64>,
+
+    /// Write the response body straight to this file rather than returning it. The response
+    /// then carries only metadata, with `saved_to` set.
+    pub download_to_path: Option<String>,
+
+    /// Continue the download an earlier request left unfinished at `download_to_path`, unless
+    /// the resource changed since
+    pub resume_download: Option<bool>,
+
+    /// Bytes of the resource to request, as `start-end`, `start-` or `-suffix`; sent as the
+    /// `Range` header
+    pub range: Option<String>,
+
+    /// Keep `GET` responses that carry an `ETag` or `Last-Modified`, and revalidate them with
+    /// `If-None-Match`/`If-Modified-Since` when the same request is sent again
+    pub response_cache: Option<bool>,
+
+    /// Optional client certificate presented during the TLS handshake.
+    pub client_certificate: Option<ClientCertificate>,
+
+    /// Compress the request body with this encoding and set `Content-Encoding` to match.
+    pub content_encoding: Option<ContentEncoding>,
+
+    /// Rewrite a JSON body in canonical form (RFC 8785 JCS) before it's digested, signed and
+    /// sent. Ignored for multipart bodies.
+    pub canonicalize_json: Option<bool>,
+
+    /// Add a SHA-256 digest of the body as sent, after any compression. Computed before
+    /// signing, so the signature can cover `content-digest`.
+    pub body_digest: Option<BodyDigest>,
+
+    /// Reuse connections across requests to the same host.
+    pub connection_pool: Option<ConnectionPool>,
+
+    /// Requests with the same group share one dedicated keep-alive connection, e.g. the steps
+    /// of a chained sequence. Implies pooling; send the steps one at a time.
+    pub connection_group: Option<String>,
+
+    /// Retry 429/503 responses, waiting as long as their `Retry-After` header asks.
+    pub retry: Option<RetryPolicy>,
+
+    /// Authenticate with NTLM. Forces HTTP/1.1, since NTLM authenticates the connection.
+    pub ntlm: Option<NtlmCredentials>,
+
+    /// Answer a 401's `WWW-Authenticate` challenge with these credentials, once
+    pub challenge_auth: Option<ChallengeCredentials>,
+
+    /// Sign the request (RFC 9421). Re-signed for each redirect and retry.
+    pub signature: Option<MessageSignature>,
+
+    /// Values substituted for `{{name}}` placeholders in the URL, query, headers, body and
+    /// pre-request hook just before the request is sent. Secrets are given as references and
+    /// resolved on the backend.
+    pub variables: Option<HashMap<String, VariableValue>>,
+
+    /// Steps run in order before the request is built, e.g. to add a timestamp and an HMAC
+    /// of the body for a vendor's signing scheme. Run once; redirects and retries reuse the
+    /// result.
+    pub pre_request: Option<Vec<HookStep>>,
+
+    /// Send fewer log entries, e.g. for runs of thousands of requests. Entries are still
+    /// counted in the response's `log_summary`.
+    pub log_capture: Option<LogCapture>,
+
+    /// Keep a HAR entry of the exchange for `export_har`, with response secrets masked and
+    /// bodies over `har::MAX_BODY_BYTES` left out. Off unless set, so a streamed body isn't
+    /// copied for nothing.
+    pub keep_har: Option<bool>,
+
+    /// Checks of the response, evaluated once it has arrived. Results are returned in the
+    /// response's `assertions` and logged, whether they pass or fail.
+    pub assertions: Option<Vec<Assertion>>,
+
+    /// Values pulled from the response into named variables once it has arrived, returned in
+    /// the response's `variables`. Data runs substitute them into the row's later requests.
+    pub extractors: Option<Vec<Extractor>>,
+
+    /// Passes each Server-Sent Event of a `text/event-stream` response on as it arrives,
+    /// keeping the connection open until the server closes it or the request is cancelled.
+    /// The response's body is left empty.
+    #[serde(skip)]
+    pub event_sink: Option<EventSink>,
+
+    /// Passes each record of a newline-delimited JSON response (`application/x-ndjson`, JSON
+    /// Lines) on as its line arrives. The body is kept as usual.
+    #[serde(skip)]
+    pub record_sink: Option<RecordSink>,
+
+    /// Passes the body on chunk by chunk as it arrives, leaving the response's `body` empty.
+    /// Bodies spooled to a file are only passed on until they spill, and multistatus bodies
+    /// aren't passed on at all.
+    #[serde(skip)]
+    pub body_sink: Option<BodySink>,
+
+    /// Reports the progress of bodies too large to keep in memory; set by the command
+    #[serde(skip)]
+    pub progress: Option<ProgressSink>,
+
+    /// Receives the request's HAR entry once its variables are substituted and its hooks
+    /// applied; set by the command
+    #[serde(skip)]
+    pub har_entry: Option<EntrySlot>,
+
+    /// Ends the body early when cancelled, returning what was read with `cancelled` set; set by
+    /// the command
+    #[serde(skip)]
+    pub cancellation: Option<CancellationToken>,
 }
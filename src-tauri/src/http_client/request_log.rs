@@ -0,0 +1,264 @@
+//! The log entries of recent requests, kept in the backend so a request's log can be exported
+//! after the fact, e.g. to attach to a ticket.
+
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::response::{LogEntry, LogLevel};
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::sync::{LazyLock, Mutex};
+
+/// Requests whose logs are kept; the oldest request's log is dropped beyond this
+const MAX_REQUESTS: usize = 50;
+
+/// Entries kept per request, so a runaway request can't take all the memory
+const MAX_ENTRIES: usize = 10_000;
+
+struct RetainedLog {
+    request_id: String,
+    entries: Vec<LogEntry>,
+    dropped: usize,
+}
+
+static RETAINED: LazyLock<Mutex<VecDeque<RetainedLog>>> =
+    LazyLock::new(|| Mutex::new(VecDeque::new()));
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum LogExportFormat {
+    /// One JSON log entry per line
+    Ndjson,
+    /// Text in the style of `curl --verbose`: `*` notes, `>` request and `<` response lines
+    Curl,
+}
+
+/// Keeps a copy of `entry` with the log of its request.
+pub fn retain(entry: &LogEntry) {
+    let mut logs = RETAINED.lock().unwrap();
+    let log = match logs
+        .iter()
+        .rposition(|log| log.request_id == entry.request_id)
+    {
+        Some(index) => &mut logs[index],
+        None => {
+            if logs.len() == MAX_REQUESTS {
+                logs.pop_front();
+            }
+            logs.push_back(RetainedLog {
+                request_id: entry.request_id.clone(),
+                entries: Vec::new(),
+                dropped: 0,
+            });
+            logs.back_mut().expect("just pushed")
+        }
+    };
+    if log.entries.len() < MAX_ENTRIES {
+        log.entries.push(entry.clone());
+    } else {
+        log.dropped += 1;
+    }
+}
+
+/// Renders the retained log of `request_id` in `format`.
+pub fn export(request_id: &str, format: LogExportFormat) -> Result<String, AppError> {
+    let logs = RETAINED.lock().unwrap();
+    let log = logs
+        .iter()
+        .rev()
+        .find(|log| log.request_id == request_id)
+        .ok_or_else(|| {
+            AppError::new(
+                ErrorKind::BadRequest,
+                format!("No log is kept for request {request_id}"),
+            )
+        })?;
+    let mut out = match format {
+        LogExportFormat::Ndjson => render_ndjson(&log.entries)?,
+        LogExportFormat::Curl => render_curl(&log.entries),
+    };
+    if log.dropped > 0 && format == LogExportFormat::Curl {
+        let _ = writeln!(out, "* [{} later log entries were not kept]", log.dropped);
+    }
+    Ok(out)
+}
+
+/// A copy of the retained log of `request_id`, empty when none is kept.
+pub fn entries(request_id: &str) -> Vec<LogEntry> {
+    let logs = RETAINED.lock().unwrap();
+    logs.iter()
+        .rev()
+        .find(|log| log.request_id == request_id)
+        .map(|log| log.entries.clone())
+        .unwrap_or_default()
+}
+
+/// The PEM certificates of the TLS handshake logged for `request_id`, leaf first. Requests
+/// sent over a reused connection logged no handshake.
+pub fn peer_certificates(request_id: &str) -> Result<Vec<String>, AppError> {
+    let logs = RETAINED.lock().unwrap();
+    let log = logs
+        .iter()
+        .rev()
+        .find(|log| log.request_id == request_id)
+        .ok_or_else(|| {
+            AppError::new(
+                ErrorKind::BadRequest,
+                format!("No log is kept for request {request_id}"),
+            )
+        })?;
+    log.entries
+        .iter()
+        .rev()
+        .filter(|entry| {
+            entry.category.as_deref() == Some("tls") && entry.phase.as_deref() == Some("handshake")
+        })
+        .find_map(|entry| {
+            entry.details.as_ref()?["peerCertificates"]
+                .as_array()
+                .cloned()
+        })
+        .map(|certificates| {
+            certificates
+                .iter()
+                .filter_map(|summary| summary["pem"].as_str().map(str::to_string))
+                .collect()
+        })
+        .ok_or_else(|| {
+            AppError::new(
+                ErrorKind::BadRequest,
+                format!("Request {request_id} made no TLS handshake of its own"),
+            )
+        })
+}
+
+fn render_ndjson(entries: &[LogEntry]) -> Result<String, AppError> {
+    let mut out = String::new();
+    for entry in entries {
+        let line = serde_json::to_string(entry)
+            .map_err(|e| AppError::new(ErrorKind::JsonError, e.to_string()))?;
+        out.push_str(&line);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// The size of a logged body, from its details
+fn body_size(entry: &LogEntry) -> u64 {
+    entry
+        .details
+        .as_ref()
+        .and_then(|details| details.get("size"))
+        .and_then(|size| size.as_u64())
+        .or(entry.bytes_logged)
+        .unwrap_or(0)
+}
+
+fn render_curl(entries: &[LogEntry]) -> String {
+    let mut out = String::new();
+    for entry in entries {
+        let category = entry.category.as_deref().unwrap_or_default();
+        let message = entry.message.as_str();
+        let line = match category {
+            // Bodies are summarized, as curl does
+            "request_body" => format!("}} [{} bytes data]", body_size(entry)),
+            "response_body" => format!("{{ [{} bytes data]", body_size(entry)),
+            // Request and response lines already carry their direction
+            _ if message.starts_with("> ") || message.starts_with("< ") => message.to_string(),
+            _ => {
+                let marker = match entry.level {
+                    LogLevel::Warning => "WARNING: ",
+                    LogLevel::Error => "ERROR: ",
+                    LogLevel::Info | LogLevel::Debug => "",
+                };
+                format!("* {marker}{message}")
+            }
+        };
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LogExportFormat, export, peer_certificates, retain};
+    use crate::http_client::response::{LogEntry, LogLevel};
+    use serde_json::json;
+
+    fn entry(request_id: &str, category: &str, level: LogLevel, message: &str) -> LogEntry {
+        LogEntry {
+            request_id: request_id.to_string(),
+            timestamp: "2025-01-01T00:00:00.000Z".to_string(),
+            level,
+            info_type: None,
+            message: message.to_string(),
+            category: Some(category.to_string()),
+            phase: None,
+            elapsed_ms: Some(1),
+            details: None,
+            bytes_logged: None,
+            truncated: None,
+        }
+    }
+
+    #[test]
+    fn exports_retained_entries() {
+        let id = "request-log-export";
+        retain(&entry(
+            id,
+            "dns",
+            LogLevel::Debug,
+            "Resolving host example.com",
+        ));
+        retain(&entry(id, "http", LogLevel::Debug, "> GET / HTTP/1.1"));
+        retain(&entry(id, "http", LogLevel::Debug, "> accept: */*"));
+        retain(&entry(id, "http", LogLevel::Info, "< HTTP/1.1 200 OK"));
+        let mut body = entry(id, "response_body", LogLevel::Debug, "< body: {}");
+        body.details = Some(json!({"size": 2}));
+        retain(&body);
+        retain(&entry(
+            id,
+            "tls",
+            LogLevel::Warning,
+            "TLS verification disabled",
+        ));
+        retain(&entry("other", "http", LogLevel::Info, "unrelated"));
+
+        assert_eq!(
+            export(id, LogExportFormat::Curl).unwrap(),
+            "* Resolving host example.com\n\
+             > GET / HTTP/1.1\n\
+             > accept: */*\n\
+             < HTTP/1.1 200 OK\n\
+             { [2 bytes data]\n\
+             * WARNING: TLS verification disabled\n"
+        );
+
+        let ndjson = export(id, LogExportFormat::Ndjson).unwrap();
+        let lines: Vec<serde_json::Value> = ndjson
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[1]["message"], "> GET / HTTP/1.1");
+        assert_eq!(lines[5]["level"], "warning");
+
+        assert!(export("never-sent", LogExportFormat::Ndjson).is_err());
+    }
+
+    #[test]
+    fn finds_logged_peer_certificates() {
+        let id = "request-log-certificates";
+        retain(&entry(id, "http", LogLevel::Info, "< HTTP/1.1 200 OK"));
+        assert!(peer_certificates(id).is_err());
+
+        let mut handshake = entry(id, "tls", LogLevel::Info, "TLS handshake complete");
+        handshake.phase = Some("handshake".to_string());
+        handshake.details = Some(json!({
+            "alpn": "h2",
+            "peerCertificates": [{"index": 0, "pem": "LEAF"}, {"index": 1, "pem": "CA"}],
+        }));
+        retain(&handshake);
+        assert_eq!(peer_certificates(id).unwrap(), ["LEAF", "CA"]);
+    }
+}
@@ -1,3 +1,7 @@
+use crate::http_client::assertions::AssertionResult;
+use crate::http_client::extractors::ExtractedVariable;
+use crate::http_client::soap::SoapFault;
+use crate::http_client::webdav::Multistatus;
 use serde::Serialize;
 use serde_json::Value;
 
@@ -10,8 +14,12 @@ pub struct ResponseData {
     pub status: u16,
     /// HTTP status message (e.g., "OK")
     pub status_text: String,
-    /// List of (header, value)
+    /// List of (header, value) in the order received; repeated headers such as `Set-Cookie`
+    /// appear once per value
     pub headers: Vec<(String, String)>,
+    /// Exact bytes of header values that aren't valid UTF-8, whose text in `headers` is lossy
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub binary_headers: Vec<BinaryHeader>,
     /// List of cookies parsed from the response. Each cookie contains
     /// structured fields like name, value, domain, path, expiration, max age,
     /// secure, http_only and same_site in accordance with the latest HTTP
@@ -22,14 +30,221 @@ pub struct ResponseData {
     /// Optional file path if the body was streamed to a temporary file instead of memory
     #[serde(skip_serializing_if = "Option::is_none")]
     pub file_path: Option<String>,
+    /// Whether `file_path` holds the body compressed with zstd; read it with `spool::open`
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub file_compressed: bool,
+    /// Whether the body stopped at the request's `max_response_bytes`, leaving the rest unread
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    /// Whether the request was cancelled while reading the body, which holds what arrived before
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub cancelled: bool,
+    /// The file the body was written to, for requests with `download_to_path`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub saved_to: Option<String>,
+    /// Where in `saved_to` the body was appended, when it resumed an interrupted download
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resumed_from: Option<u64>,
+    /// The part of the resource a `206 Partial Content` response holds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_range: Option<ContentRange>,
+    /// What the response cache did, for requests with `response_cache` set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CacheStatus>,
     /// Response size in bytes
     pub size: u64,
     /// Response duration in milliseconds
     pub duration: u64,
+    /// Breakdown of `duration` by phase
+    pub timings: Timings,
+    /// Addresses of the connection the response arrived on. Steps that share a connection
+    /// report the same local address.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connection: Option<ConnectionInfo>,
+    /// Revocation status of the server's certificates, when `check_revocation` is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revocation: Option<Vec<CertificateRevocation>>,
+    /// Quota advertised by the server's rate limit headers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimit>,
+    /// Redirect responses followed on the way to this one, in order, when `max_redirects` is
+    /// above zero
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redirect_chain: Option<Vec<RedirectHop>>,
+    /// Retries made before this response, when the retry policy is enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retries: Option<RetryReport>,
+    /// Log entries the request produced, when its log capture mode is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_summary: Option<LogSummary>,
+    /// Parsed WebDAV multistatus body of a 207 response
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub multistatus: Option<Multistatus>,
+    /// Fault of a SOAP request's error response
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub soap_fault: Option<SoapFault>,
+    /// Results of the request's assertions, when it has any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assertions: Option<Vec<AssertionResult>>,
+    /// Values the request's extractors pulled from the response, when it has any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variables: Option<Vec<ExtractedVariable>>,
     /// Response timestamp, ISO 8601
     pub timestamp: String,
 }
 
+/// A header value that isn't valid UTF-8
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BinaryHeader {
+    /// Position of the header in `ResponseData::headers`
+    pub index: usize,
+    /// The value's bytes, base64 encoded
+    pub base64: String,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionInfo {
+    pub local_addr: String,
+    pub remote_addr: String,
+    /// Whether the response arrived on a connection an earlier request opened
+    pub reused: bool,
+}
+
+/// A redirect response followed on the way to the final response
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RedirectHop {
+    /// URL of the request that was redirected
+    pub url: String,
+    pub method: String,
+    pub status: u16,
+    pub status_text: String,
+    pub headers: Vec<(String, String)>,
+    pub cookies: Vec<Cookie>,
+    /// Milliseconds from sending the request to the redirect's headers
+    pub duration: u64,
+    pub timings: Timings,
+}
+
+/// Whether a certificate of the server's chain was revoked, and who said so
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificateRevocation {
+    pub subject: String,
+    /// Serial number, hex
+    pub serial: String,
+    pub status: RevocationStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<RevocationSource>,
+    /// Time of the revocation, ISO 8601
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Why the status is unknown
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RevocationStatus {
+    Good,
+    Revoked,
+    /// No responder or list could be reached, or none gave an answer
+    Unknown,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RevocationSource {
+    Ocsp,
+    Crl,
+}
+
+/// Rate limit quota read from `RateLimit`, `RateLimit-*` or `X-RateLimit-*` headers
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimit {
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    /// Seconds until the quota resets
+    pub reset_secs: Option<u64>,
+    /// `RateLimit-Policy` as sent, e.g. `"default";q=100;w=60`
+    pub policy: Option<String>,
+}
+
+/// What the response cache did with a request
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CacheStatus {
+    /// The response was stored, to be revalidated by the next request like it
+    Stored,
+    /// The server answered `304 Not Modified`, and the cached response stands in for it
+    Revalidated,
+    /// The response couldn't be stored, e.g. for lack of an `ETag` or `Last-Modified`
+    Uncached,
+}
+
+/// Byte positions from a `Content-Range` header, inclusive
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentRange {
+    pub start: u64,
+    pub end: u64,
+    /// Size of the whole resource, when the server knows it
+    pub total: Option<u64>,
+}
+
+/// How much of the retry budget was spent
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryReport {
+    /// Retries made
+    pub attempts: u32,
+    /// Retries allowed by the policy
+    pub max_retries: u32,
+    /// Total time spent waiting between attempts
+    pub waited_ms: u64,
+}
+
+/// Counts of a request's log entries, whether or not they were sent
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LogSummary {
+    /// Whether the entries were sent to the frontend
+    pub captured: bool,
+    pub entries: u64,
+    pub warnings: u64,
+    pub errors: u64,
+}
+
+/// Per-phase timings of a request in milliseconds. Phases that did not happen (e.g. no
+/// TLS handshake for plain HTTP) are `None`. When redirects were followed, the phases
+/// describe the final request while `total` covers all of them.
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Timings {
+    /// Host name resolution
+    pub dns: Option<u64>,
+    /// TCP connection establishment
+    pub tcp_connect: Option<u64>,
+    /// TLS handshake
+    pub tls_handshake: Option<u64>,
+    /// Sending the request head and body
+    pub request_write: Option<u64>,
+    /// Waiting for the response head after the request was sent
+    pub time_to_first_byte: Option<u64>,
+    /// Receiving the response body
+    pub download: Option<u64>,
+    /// Whether the request was sent over a pooled connection, skipping DNS, connect and TLS
+    pub connection_reused: bool,
+    /// Whole request, same as `ResponseData::duration`
+    pub total: u64,
+}
+
 /// Representation of an HTTP cookie.  This structure contains the
 /// standard fields defined by modern cookie specifications.  Optional
 /// fields are represented using `Option<T>` so that missing attributes
@@ -0,0 +1,249 @@
+//! Self-signed certificates for serving HTTPS locally, e.g. from the webhook listener, and the
+//! server TLS setup that uses them.
+
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::hyper_engine::install_crypto_provider;
+use chrono::{Datelike, Duration, Utc};
+use rcgen::{
+    CertificateParams, DistinguishedName, DnType, ExtendedKeyUsagePurpose, KeyPair, date_time_ymd,
+};
+use rustls::ServerConfig;
+use rustls_pemfile::{certs, private_key};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Days a certificate is valid for when not given
+const DEFAULT_VALID_DAYS: u32 = 365;
+
+/// Most days a certificate may be valid for; clients reject longer lifetimes
+const MAX_VALID_DAYS: u32 = 825;
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfSignedOptions {
+    /// Host name the certificate is issued to, also its common name
+    pub hostname: String,
+    /// Further host names or IP addresses the certificate is valid for
+    #[serde(default)]
+    pub alt_names: Vec<String>,
+    /// Defaults to 365
+    pub valid_days: Option<u32>,
+}
+
+/// A generated certificate and its private key, PEM encoded
+#[derive(Debug, Clone)]
+pub struct SelfSignedCertificate {
+    pub cert_pem: String,
+    pub key_pem: String,
+    /// SHA-256 of the DER certificate, hex, for pinning or trusting it by hand
+    pub sha256: String,
+    /// Last day the certificate is valid, ISO 8601
+    pub not_after: String,
+}
+
+/// Where a certificate and its key were saved
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedCertificate {
+    pub cert_path: String,
+    pub key_path: String,
+    pub sha256: String,
+    pub not_after: String,
+}
+
+fn rcgen_error(e: rcgen::Error) -> AppError {
+    AppError::new(
+        ErrorKind::BadRequest,
+        format!("Failed to generate the certificate: {e}"),
+    )
+}
+
+/// Generates an ECDSA P-256 key and a certificate for it, signed by itself, valid for
+/// serving `options.hostname` and its alternative names from today.
+pub fn generate(options: &SelfSignedOptions) -> Result<SelfSignedCertificate, AppError> {
+    let hostname = options.hostname.trim();
+    if hostname.is_empty() {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            "A host name is required",
+        ));
+    }
+    let valid_days = options.valid_days.unwrap_or(DEFAULT_VALID_DAYS);
+    if !(1..=MAX_VALID_DAYS).contains(&valid_days) {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            format!("Validity must be between 1 and {MAX_VALID_DAYS} days"),
+        ));
+    }
+
+    let mut names = vec![hostname.to_string()];
+    for name in options.alt_names.iter().map(|name| name.trim()) {
+        if !name.is_empty() && !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    // IP addresses among the names become IP address entries rather than DNS names
+    let mut params = CertificateParams::new(names).map_err(rcgen_error)?;
+    params.distinguished_name = DistinguishedName::new();
+    params.distinguished_name.push(DnType::CommonName, hostname);
+    params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+    let today = Utc::now().date_naive();
+    let last_day = today + Duration::days(i64::from(valid_days));
+    params.not_before = date_time_ymd(today.year(), today.month() as u8, today.day() as u8);
+    params.not_after = date_time_ymd(
+        last_day.year(),
+        last_day.month() as u8,
+        last_day.day() as u8,
+    );
+
+    let key = KeyPair::generate().map_err(rcgen_error)?;
+    let cert = params.self_signed(&key).map_err(rcgen_error)?;
+    Ok(SelfSignedCertificate {
+        cert_pem: cert.pem(),
+        key_pem: key.serialize_pem(),
+        sha256: hex::encode(Sha256::digest(cert.der())),
+        not_after: last_day.to_string(),
+    })
+}
+
+/// The file the key is saved to next to a certificate saved to `cert_path`, e.g. `localhost.pem`
+/// keeps its key in `localhost-key.pem`
+fn key_path_for(cert_path: &Path) -> PathBuf {
+    let stem = cert_path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = cert_path
+        .extension()
+        .map_or("pem".into(), |extension| extension.to_string_lossy());
+    cert_path.with_file_name(format!("{stem}-key.{extension}"))
+}
+
+/// Writes the certificate to `cert_path` and its key next to it, readable only by the user
+/// where the platform allows.
+pub fn write_pair(
+    cert_path: &Path,
+    certificate: &SelfSignedCertificate,
+) -> Result<SavedCertificate, AppError> {
+    let key_path = key_path_for(cert_path);
+    fs::write(cert_path, &certificate.cert_pem)?;
+    fs::write(&key_path, &certificate.key_pem)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&key_path, fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(SavedCertificate {
+        cert_path: cert_path.to_string_lossy().to_string(),
+        key_path: key_path.to_string_lossy().to_string(),
+        sha256: certificate.sha256.clone(),
+        not_after: certificate.not_after.clone(),
+    })
+}
+
+/// TLS settings for serving with the certificate chain and key in the PEM files given
+pub fn server_config(cert_path: &str, key_path: &str) -> Result<ServerConfig, AppError> {
+    let read = |path: &str, what: &str| {
+        fs::read(path).map_err(|e| {
+            AppError::new(
+                ErrorKind::IoError,
+                format!("Failed to read the server {what} '{path}': {e}"),
+            )
+        })
+    };
+    let chain = certs(&mut read(cert_path, "certificate")?.as_slice())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            AppError::new(
+                ErrorKind::BadRequest,
+                format!("Invalid server certificate: {e}"),
+            )
+        })?;
+    if chain.is_empty() {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            "No certificates found in the server certificate file",
+        ));
+    }
+    let key = private_key(&mut read(key_path, "key")?.as_slice())
+        .map_err(|e| AppError::new(ErrorKind::BadRequest, format!("Invalid server key: {e}")))?
+        .ok_or_else(|| {
+            AppError::new(
+                ErrorKind::BadRequest,
+                "No private key found in the server key file",
+            )
+        })?;
+    install_crypto_provider();
+    let mut config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(chain, key)
+        .map_err(|e| {
+            AppError::new(
+                ErrorKind::BadRequest,
+                format!("Unusable server certificate or key: {e}"),
+            )
+        })?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SelfSignedOptions, generate, key_path_for, server_config, write_pair};
+    use std::path::Path;
+    use x509_parser::extensions::GeneralName;
+    use x509_parser::pem::parse_x509_pem;
+
+    fn options(hostname: &str, alt_names: &[&str]) -> SelfSignedOptions {
+        SelfSignedOptions {
+            hostname: hostname.to_string(),
+            alt_names: alt_names.iter().map(|name| name.to_string()).collect(),
+            valid_days: Some(30),
+        }
+    }
+
+    #[test]
+    fn issues_certificates_for_each_name() {
+        let generated = generate(&options("localhost", &["127.0.0.1", "hooks.test"])).unwrap();
+        let (_, pem) = parse_x509_pem(generated.cert_pem.as_bytes()).unwrap();
+        let cert = pem.parse_x509().unwrap();
+
+        assert_eq!(cert.subject(), cert.issuer());
+        let common_name = cert.subject().iter_common_name().next().unwrap();
+        assert_eq!(common_name.as_str().unwrap(), "localhost");
+        let names = &cert
+            .subject_alternative_name()
+            .unwrap()
+            .unwrap()
+            .value
+            .general_names;
+        assert!(names.contains(&GeneralName::DNSName("localhost")));
+        assert!(names.contains(&GeneralName::DNSName("hooks.test")));
+        assert!(names.contains(&GeneralName::IPAddress(&[127, 0, 0, 1])));
+        assert!(cert.validity().is_valid());
+        assert_eq!(generated.sha256.len(), 64);
+    }
+
+    #[test]
+    fn rejects_missing_host_names_and_long_lifetimes() {
+        assert!(generate(&options(" ", &[])).is_err());
+        let mut long = options("localhost", &[]);
+        long.valid_days = Some(3650);
+        assert!(generate(&long).unwrap_err().message.contains("825"));
+    }
+
+    #[test]
+    fn saves_a_pair_that_serves_tls() {
+        let dir = tempfile::tempdir().unwrap();
+        let cert_path = dir.path().join("localhost.pem");
+        let generated = generate(&options("localhost", &[])).unwrap();
+        let saved = write_pair(&cert_path, &generated).unwrap();
+
+        assert_eq!(
+            key_path_for(Path::new("/certs/localhost.crt")),
+            Path::new("/certs/localhost-key.crt")
+        );
+        assert!(saved.key_path.ends_with("localhost-key.pem"));
+        assert!(server_config(&saved.cert_path, &saved.key_path).is_ok());
+        assert!(server_config(&saved.key_path, &saved.key_path).is_err());
+    }
+}
@@ -0,0 +1,772 @@
+//! SOAP 1.1 and 1.2: envelopes built from an operation and its parameters, faults read from
+//! response bodies, and the operations a WSDL 1.1 document describes.
+
+use crate::errors::{AppError, ErrorKind};
+use hyper::http::{HeaderMap, HeaderName, HeaderValue, header};
+use roxmltree::{Document, Node};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+const SOAP11_ENVELOPE: &str = "http://schemas.xmlsoap.org/soap/envelope/";
+const SOAP12_ENVELOPE: &str = "http://www.w3.org/2003/05/soap-envelope";
+const WSDL: &str = "http://schemas.xmlsoap.org/wsdl/";
+const WSDL_SOAP11: &str = "http://schemas.xmlsoap.org/wsdl/soap/";
+const WSDL_SOAP12: &str = "http://schemas.xmlsoap.org/wsdl/soap12/";
+const XSD: &str = "http://www.w3.org/2001/XMLSchema";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SoapVersion {
+    #[default]
+    #[serde(rename = "1.1")]
+    Soap11,
+    #[serde(rename = "1.2")]
+    Soap12,
+}
+
+impl SoapVersion {
+    fn envelope_namespace(self) -> &'static str {
+        match self {
+            SoapVersion::Soap11 => SOAP11_ENVELOPE,
+            SoapVersion::Soap12 => SOAP12_ENVELOPE,
+        }
+    }
+}
+
+/// A SOAP operation, sent as the XML request body
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SoapOperation {
+    /// Name of the element wrapping the parameters in the body, usually the operation's
+    pub operation: String,
+    /// Namespace of the wrapping element, e.g. the service's target namespace
+    pub namespace: Option<String>,
+    /// Parameters as `[name, value]` pairs, sent as child elements in order. Objects become
+    /// nested elements, by key in sorted order; arrays repeat the element; null leaves it empty.
+    #[serde(default)]
+    pub parameters: Vec<(String, Value)>,
+    /// Put the parameters in `namespace` too, as schemas with `elementFormDefault="qualified"`
+    /// expect
+    #[serde(default)]
+    pub qualified: bool,
+    /// Sent as the `SOAPAction` header (1.1) or the `action` of the content type (1.2)
+    pub soap_action: Option<String>,
+    #[serde(default)]
+    pub version: SoapVersion,
+    /// XML blocks put in the envelope's `Header` as given, e.g. a WS-Security header
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<String>,
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Whether `name` can be used as an element name as it is, prefix aside
+fn is_element_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+fn check_name(name: &str) -> Result<(), AppError> {
+    if is_element_name(name) {
+        Ok(())
+    } else {
+        Err(AppError::new(
+            ErrorKind::BadRequest,
+            format!("'{name}' can't be used as a SOAP element name"),
+        ))
+    }
+}
+
+fn write_parameter(
+    out: &mut String,
+    prefix: &str,
+    name: &str,
+    value: &Value,
+    depth: usize,
+) -> Result<(), AppError> {
+    check_name(name)?;
+    let indent = "  ".repeat(depth);
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                write_parameter(out, prefix, name, item, depth)?;
+            }
+        }
+        Value::Null => out.push_str(&format!("{indent}<{prefix}{name}/>\n")),
+        Value::Object(fields) => {
+            out.push_str(&format!("{indent}<{prefix}{name}>\n"));
+            for (field, value) in fields {
+                write_parameter(out, prefix, field, value, depth + 1)?;
+            }
+            out.push_str(&format!("{indent}</{prefix}{name}>\n"));
+        }
+        Value::String(text) => {
+            out.push_str(&format!(
+                "{indent}<{prefix}{name}>{}</{prefix}{name}>\n",
+                escape(text)
+            ));
+        }
+        scalar => out.push_str(&format!(
+            "{indent}<{prefix}{name}>{scalar}</{prefix}{name}>\n"
+        )),
+    }
+    Ok(())
+}
+
+impl SoapOperation {
+    /// The operation's envelope
+    pub fn envelope(&self) -> Result<String, AppError> {
+        check_name(&self.operation)?;
+        let mut out = format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<soap:Envelope xmlns:soap=\"{}\">\n",
+            self.version.envelope_namespace()
+        );
+        if !self.headers.is_empty() {
+            out.push_str("  <soap:Header>\n");
+            for block in &self.headers {
+                out.push_str(&format!("    {}\n", block.trim()));
+            }
+            out.push_str("  </soap:Header>\n");
+        }
+        out.push_str("  <soap:Body>\n");
+        let (element, declaration) = match &self.namespace {
+            Some(namespace) => (
+                format!("m:{}", self.operation),
+                format!(" xmlns:m=\"{}\"", escape(namespace)),
+            ),
+            None => (self.operation.clone(), String::new()),
+        };
+        let prefix = if self.qualified && self.namespace.is_some() {
+            "m:"
+        } else {
+            ""
+        };
+        if self.parameters.is_empty() {
+            out.push_str(&format!("    <{element}{declaration}/>\n"));
+        } else {
+            out.push_str(&format!("    <{element}{declaration}>\n"));
+            for (name, value) in &self.parameters {
+                write_parameter(&mut out, prefix, name, value, 3)?;
+            }
+            out.push_str(&format!("    </{element}>\n"));
+        }
+        out.push_str("  </soap:Body>\n</soap:Envelope>\n");
+        Ok(out)
+    }
+
+    /// Builds the envelope, defaulting `Content-Type`, and for SOAP 1.1 `SOAPAction`, to the
+    /// operation's.
+    pub fn encode(&self, headers: &mut HeaderMap) -> Result<Vec<u8>, AppError> {
+        let envelope = self.envelope()?;
+        let action = self
+            .soap_action
+            .as_deref()
+            .map(|action| action.replace('"', ""));
+        let content_type = match (self.version, &action) {
+            (SoapVersion::Soap11, _) => {
+                let action = format!("\"{}\"", action.as_deref().unwrap_or_default());
+                headers
+                    .entry(HeaderName::from_static("soapaction"))
+                    .or_insert(header_value(&action)?);
+                "text/xml; charset=utf-8".to_string()
+            }
+            (SoapVersion::Soap12, Some(action)) => {
+                format!("application/soap+xml; charset=utf-8; action=\"{action}\"")
+            }
+            (SoapVersion::Soap12, None) => "application/soap+xml; charset=utf-8".to_string(),
+        };
+        headers
+            .entry(header::CONTENT_TYPE)
+            .or_insert(header_value(&content_type)?);
+        Ok(envelope.into_bytes())
+    }
+}
+
+fn header_value(value: &str) -> Result<HeaderValue, AppError> {
+    HeaderValue::try_from(value).map_err(|e| {
+        AppError::new(
+            ErrorKind::BadRequest,
+            format!("Invalid SOAP action '{value}': {e}"),
+        )
+    })
+}
+
+/// A fault a SOAP response body carries in place of a result
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SoapFault {
+    pub version: SoapVersion,
+    /// e.g. `soap:Server` (1.1) or `env:Receiver` (1.2), as the body gives it
+    pub code: String,
+    /// Subcodes of a SOAP 1.2 fault, outermost first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub subcodes: Vec<String>,
+    pub reason: String,
+    /// `faultactor` (1.1) or `Role` (1.2)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actor: Option<String>,
+    /// Contents of the fault's detail, as XML
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+fn child<'a, 'input>(
+    node: Node<'a, 'input>,
+    namespace: Option<&str>,
+    name: &str,
+) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| {
+        child.is_element()
+            && child.tag_name().name() == name
+            && child.tag_name().namespace() == namespace
+    })
+}
+
+fn text(node: Option<Node>) -> Option<String> {
+    node.and_then(|node| node.text())
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+/// Child elements of `node` as XML, or its text when it has none
+fn inner_xml(node: Node, source: &str) -> Option<String> {
+    let elements: Vec<Node> = node.children().filter(Node::is_element).collect();
+    match (elements.first(), elements.last()) {
+        (Some(first), Some(last)) => {
+            Some(source[first.range().start..last.range().end].to_string())
+        }
+        _ => text(Some(node)),
+    }
+}
+
+/// The fault in a SOAP response body, if it holds one
+pub fn parse_fault(body: &[u8]) -> Option<SoapFault> {
+    let source = std::str::from_utf8(body).ok()?;
+    let document = Document::parse(source).ok()?;
+    let envelope = document.root_element();
+    let version = match envelope.tag_name().namespace() {
+        Some(SOAP11_ENVELOPE) => SoapVersion::Soap11,
+        Some(SOAP12_ENVELOPE) => SoapVersion::Soap12,
+        _ => return None,
+    };
+    let namespace = Some(version.envelope_namespace());
+    if envelope.tag_name().name() != "Envelope" {
+        return None;
+    }
+    let fault = child(child(envelope, namespace, "Body")?, namespace, "Fault")?;
+    Some(match version {
+        SoapVersion::Soap11 => SoapFault {
+            version,
+            code: text(child(fault, None, "faultcode")).unwrap_or_default(),
+            subcodes: Vec::new(),
+            reason: text(child(fault, None, "faultstring")).unwrap_or_default(),
+            actor: text(child(fault, None, "faultactor")),
+            detail: child(fault, None, "detail").and_then(|detail| inner_xml(detail, source)),
+        },
+        SoapVersion::Soap12 => {
+            let code = child(fault, namespace, "Code");
+            let mut subcodes = Vec::new();
+            let mut subcode = code.and_then(|code| child(code, namespace, "Subcode"));
+            while let Some(node) = subcode {
+                subcodes.extend(text(child(node, namespace, "Value")));
+                subcode = child(node, namespace, "Subcode");
+            }
+            SoapFault {
+                version,
+                code: text(code.and_then(|code| child(code, namespace, "Value")))
+                    .unwrap_or_default(),
+                subcodes,
+                reason: text(
+                    child(fault, namespace, "Reason")
+                        .and_then(|reason| child(reason, namespace, "Text")),
+                )
+                .unwrap_or_default(),
+                actor: text(child(fault, namespace, "Role")),
+                detail: child(fault, namespace, "Detail")
+                    .and_then(|detail| inner_xml(detail, source)),
+            }
+        }
+    })
+}
+
+/// The operations of a WSDL 1.1 document
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Wsdl {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_namespace: Option<String>,
+    /// One per operation of each SOAP port; a service with SOAP 1.1 and 1.2 ports lists its
+    /// operations twice
+    pub operations: Vec<WsdlOperation>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WsdlOperation {
+    pub name: String,
+    pub service: String,
+    pub port: String,
+    /// Address of the port, where requests are sent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documentation: Option<String>,
+    /// What the input expects
+    pub parameters: Vec<WsdlParameter>,
+    /// The operation ready to send, with an empty value for each parameter
+    pub request: SoapOperation,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WsdlParameter {
+    pub name: String,
+    /// Schema type, e.g. `xs:string`, as the document gives it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub type_name: Option<String>,
+    /// `minOccurs="0"`
+    pub optional: bool,
+    /// `maxOccurs` above 1
+    pub repeated: bool,
+}
+
+fn invalid_wsdl(message: impl Into<String>) -> AppError {
+    AppError::new(ErrorKind::BadRequest, message)
+}
+
+fn is(node: &Node, namespace: &str, name: &str) -> bool {
+    node.is_element()
+        && node.tag_name().name() == name
+        && node.tag_name().namespace() == Some(namespace)
+}
+
+fn children<'a, 'input>(
+    node: Node<'a, 'input>,
+    namespace: &'static str,
+    name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| is(child, namespace, name))
+}
+
+/// Local part of a qualified name, e.g. `GetPrice` of `tns:GetPrice`
+fn local_name(qname: &str) -> &str {
+    qname.rsplit_once(':').map_or(qname, |(_, local)| local)
+}
+
+/// Namespace the prefix of a qualified name in an attribute of `node` is bound to
+fn qname_namespace<'a>(node: Node<'a, '_>, qname: &str) -> Option<&'a str> {
+    match qname.split_once(':') {
+        Some((prefix, _)) => node.lookup_namespace_uri(Some(prefix)),
+        None => node.lookup_namespace_uri(None),
+    }
+}
+
+/// Top-level elements and complex types of the document's schemas
+struct Schemas<'a, 'input> {
+    elements: Vec<(Option<&'a str>, Node<'a, 'input>)>,
+    types: Vec<(Option<&'a str>, Node<'a, 'input>)>,
+}
+
+impl<'a, 'input> Schemas<'a, 'input> {
+    fn new(definitions: Node<'a, 'input>) -> Self {
+        let mut schemas = Self {
+            elements: Vec::new(),
+            types: Vec::new(),
+        };
+        for schema in children(definitions, WSDL, "types").flat_map(|t| children(t, XSD, "schema"))
+        {
+            let namespace = schema.attribute("targetNamespace");
+            schemas
+                .elements
+                .extend(children(schema, XSD, "element").map(|e| (namespace, e)));
+            schemas
+                .types
+                .extend(children(schema, XSD, "complexType").map(|t| (namespace, t)));
+        }
+        schemas
+    }
+
+    /// The node `qname` in an attribute of `at` names, preferring one in its namespace
+    fn find(
+        nodes: &[(Option<&'a str>, Node<'a, 'input>)],
+        at: Node,
+        qname: &str,
+    ) -> Option<(Option<&'a str>, Node<'a, 'input>)> {
+        let namespace = qname_namespace(at, qname);
+        let local = local_name(qname);
+        let named = |(_, node): &&(Option<&'a str>, Node<'a, 'input>)| {
+            node.attribute("name") == Some(local)
+        };
+        nodes
+            .iter()
+            .filter(named)
+            .find(|(ns, _)| *ns == namespace)
+            .or_else(|| nodes.iter().find(named))
+            .copied()
+    }
+
+    /// The elements an element's complex type holds, in order
+    fn fields(&self, element: Node<'a, 'input>) -> Vec<WsdlParameter> {
+        let complex_type = children(element, XSD, "complexType").next().or_else(|| {
+            let type_name = element.attribute("type")?;
+            Self::find(&self.types, element, type_name).map(|(_, node)| node)
+        });
+        let Some(complex_type) = complex_type else {
+            return Vec::new();
+        };
+        complex_type
+            .descendants()
+            .filter(|node| is(node, XSD, "element"))
+            .filter(|node| {
+                // Only the type's own fields, not those of anonymous types nested in them
+                node.ancestors().skip(1).find(|ancestor| {
+                    is(ancestor, XSD, "element") || is(ancestor, XSD, "complexType")
+                }) == Some(complex_type)
+            })
+            .filter_map(|field| {
+                let name = field
+                    .attribute("name")
+                    .or_else(|| field.attribute("ref").map(local_name))?;
+                Some(WsdlParameter {
+                    name: name.to_string(),
+                    type_name: field.attribute("type").map(str::to_string),
+                    optional: field.attribute("minOccurs") == Some("0"),
+                    repeated: field.attribute("maxOccurs").is_some_and(|max| {
+                        max == "unbounded" || max.parse::<u32>().is_ok_and(|n| n > 1)
+                    }),
+                })
+            })
+            .collect()
+    }
+}
+
+/// The WSDL component `qname` names
+fn named<'a, 'input>(
+    mut nodes: impl Iterator<Item = Node<'a, 'input>>,
+    qname: &str,
+) -> Option<Node<'a, 'input>> {
+    let local = local_name(qname);
+    nodes.find(|node| node.attribute("name") == Some(local))
+}
+
+/// The SOAP version and extension namespace of a binding, `None` for other bindings (e.g. HTTP)
+fn binding_version(binding: Node) -> Option<(SoapVersion, &'static str)> {
+    [
+        (SoapVersion::Soap11, WSDL_SOAP11),
+        (SoapVersion::Soap12, WSDL_SOAP12),
+    ]
+    .into_iter()
+    .find(|(_, namespace)| children(binding, namespace, "binding").next().is_some())
+}
+
+/// Parses a WSDL 1.1 document into its SOAP operations. Imported documents and schemas aren't
+/// fetched, so types they define have no parameters listed.
+pub fn parse_wsdl(source: &str) -> Result<Wsdl, AppError> {
+    let document =
+        Document::parse(source).map_err(|e| invalid_wsdl(format!("Invalid WSDL: {e}")))?;
+    let definitions = document.root_element();
+    if !is(&definitions, WSDL, "definitions") {
+        return Err(invalid_wsdl("Not a WSDL 1.1 document"));
+    }
+    let target_namespace = definitions.attribute("targetNamespace");
+    let schemas = Schemas::new(definitions);
+
+    let mut operations = Vec::new();
+    for service in children(definitions, WSDL, "service") {
+        for port in children(service, WSDL, "port") {
+            let Some(binding) = port
+                .attribute("binding")
+                .and_then(|qname| named(children(definitions, WSDL, "binding"), qname))
+            else {
+                continue;
+            };
+            let Some((version, soap)) = binding_version(binding) else {
+                continue;
+            };
+            let default_style = children(binding, soap, "binding")
+                .next()
+                .and_then(|b| b.attribute("style"))
+                .unwrap_or("document");
+            let port_type = binding
+                .attribute("type")
+                .and_then(|qname| named(children(definitions, WSDL, "portType"), qname));
+            for bound in children(binding, WSDL, "operation") {
+                let Some(name) = bound.attribute("name") else {
+                    continue;
+                };
+                let soap_operation = children(bound, soap, "operation").next();
+                let style = soap_operation
+                    .and_then(|o| o.attribute("style"))
+                    .unwrap_or(default_style);
+                let abstract_operation = port_type
+                    .and_then(|port_type| named(children(port_type, WSDL, "operation"), name));
+                let parts: Vec<Node> = abstract_operation
+                    .and_then(|o| children(o, WSDL, "input").next())
+                    .and_then(|input| input.attribute("message"))
+                    .and_then(|qname| named(children(definitions, WSDL, "message"), qname))
+                    .map(|message| children(message, WSDL, "part").collect())
+                    .unwrap_or_default();
+
+                let (operation, namespace, qualified, parameters) = if style == "rpc" {
+                    let namespace = children(bound, WSDL, "input")
+                        .flat_map(|input| children(input, soap, "body"))
+                        .find_map(|body| body.attribute("namespace"))
+                        .or(target_namespace);
+                    let parameters = parts
+                        .iter()
+                        .filter_map(|part| {
+                            Some(WsdlParameter {
+                                name: part.attribute("name")?.to_string(),
+                                type_name: part.attribute("type").map(str::to_string),
+                                optional: false,
+                                repeated: false,
+                            })
+                        })
+                        .collect();
+                    (name.to_string(), namespace, false, parameters)
+                } else {
+                    let element = parts.first().and_then(|part| {
+                        let qname = part.attribute("element")?;
+                        Schemas::find(&schemas.elements, *part, qname)
+                    });
+                    match element {
+                        Some((namespace, element)) => {
+                            let schema = element.parent();
+                            let qualified = schema.and_then(|s| s.attribute("elementFormDefault"))
+                                == Some("qualified");
+                            (
+                                element.attribute("name").unwrap_or(name).to_string(),
+                                namespace,
+                                qualified,
+                                schemas.fields(element),
+                            )
+                        }
+                        None => (name.to_string(), target_namespace, false, Vec::new()),
+                    }
+                };
+                operations.push(WsdlOperation {
+                    name: name.to_string(),
+                    service: service.attribute("name").unwrap_or_default().to_string(),
+                    port: port.attribute("name").unwrap_or_default().to_string(),
+                    endpoint: children(port, soap, "address")
+                        .find_map(|address| address.attribute("location"))
+                        .map(str::to_string),
+                    documentation: text(
+                        abstract_operation.and_then(|o| children(o, WSDL, "documentation").next()),
+                    ),
+                    request: SoapOperation {
+                        operation,
+                        namespace: namespace.map(str::to_string),
+                        parameters: parameters
+                            .iter()
+                            .map(|parameter| (parameter.name.clone(), Value::String(String::new())))
+                            .collect(),
+                        qualified,
+                        soap_action: soap_operation
+                            .and_then(|o| o.attribute("soapAction"))
+                            .filter(|action| !action.is_empty())
+                            .map(str::to_string),
+                        version,
+                        headers: Vec::new(),
+                    },
+                    parameters,
+                });
+            }
+        }
+    }
+    Ok(Wsdl {
+        target_namespace: target_namespace.map(str::to_string),
+        operations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SoapOperation, SoapVersion, parse_fault, parse_wsdl};
+    use hyper::http::HeaderMap;
+    use serde_json::json;
+
+    #[test]
+    fn builds_envelopes() {
+        let mut operation = SoapOperation {
+            operation: "GetPrice".to_string(),
+            namespace: Some("urn:shop".to_string()),
+            parameters: vec![
+                ("item".to_string(), json!("Pen & <Ink>")),
+                ("sizes".to_string(), json!([1, 2])),
+                ("address".to_string(), json!({"zip": "1000", "city": null})),
+            ],
+            qualified: false,
+            soap_action: Some("urn:shop/GetPrice".to_string()),
+            version: SoapVersion::Soap11,
+            headers: vec!["<t:Token xmlns:t=\"urn:t\">abc</t:Token>".to_string()],
+        };
+        let mut headers = HeaderMap::new();
+        let body = String::from_utf8(operation.encode(&mut headers).unwrap()).unwrap();
+        assert_eq!(
+            body,
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+             <soap:Envelope xmlns:soap=\"http://schemas.xmlsoap.org/soap/envelope/\">\n  \
+             <soap:Header>\n    <t:Token xmlns:t=\"urn:t\">abc</t:Token>\n  </soap:Header>\n  \
+             <soap:Body>\n    <m:GetPrice xmlns:m=\"urn:shop\">\n      \
+             <item>Pen &amp; &lt;Ink&gt;</item>\n      <sizes>1</sizes>\n      <sizes>2</sizes>\n      \
+             <address>\n        <city/>\n        <zip>1000</zip>\n      </address>\n    \
+             </m:GetPrice>\n  </soap:Body>\n</soap:Envelope>\n"
+        );
+        assert_eq!(headers["soapaction"], "\"urn:shop/GetPrice\"");
+        assert_eq!(headers["content-type"], "text/xml; charset=utf-8");
+
+        operation.version = SoapVersion::Soap12;
+        operation.qualified = true;
+        operation.headers.clear();
+        operation.parameters.truncate(1);
+        let mut headers = HeaderMap::new();
+        let body = String::from_utf8(operation.encode(&mut headers).unwrap()).unwrap();
+        assert!(body.contains("<m:item>Pen &amp; &lt;Ink&gt;</m:item>"));
+        assert!(body.contains("http://www.w3.org/2003/05/soap-envelope"));
+        assert!(!headers.contains_key("soapaction"));
+        assert_eq!(
+            headers["content-type"],
+            "application/soap+xml; charset=utf-8; action=\"urn:shop/GetPrice\""
+        );
+
+        operation.parameters = vec![("bad name".to_string(), json!(1))];
+        assert!(operation.envelope().is_err());
+    }
+
+    #[test]
+    fn parses_faults() {
+        let fault = parse_fault(
+            br#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"><s:Body>
+                <s:Fault><faultcode>s:Client</faultcode><faultstring>Unknown item</faultstring>
+                <detail><e:Code xmlns:e="urn:e">42</e:Code></detail></s:Fault>
+            </s:Body></s:Envelope>"#,
+        )
+        .unwrap();
+        assert_eq!(fault.version, SoapVersion::Soap11);
+        assert_eq!(fault.code, "s:Client");
+        assert_eq!(fault.reason, "Unknown item");
+        assert_eq!(
+            fault.detail.as_deref(),
+            Some(r#"<e:Code xmlns:e="urn:e">42</e:Code>"#)
+        );
+
+        let fault = parse_fault(
+            br#"<env:Envelope xmlns:env="http://www.w3.org/2003/05/soap-envelope"><env:Body>
+                <env:Fault>
+                  <env:Code><env:Value>env:Sender</env:Value>
+                    <env:Subcode><env:Value>m:BadItem</env:Value></env:Subcode></env:Code>
+                  <env:Reason><env:Text xml:lang="en">Unknown item</env:Text></env:Reason>
+                  <env:Role>urn:shop</env:Role>
+                </env:Fault>
+            </env:Body></env:Envelope>"#,
+        )
+        .unwrap();
+        assert_eq!(fault.code, "env:Sender");
+        assert_eq!(fault.subcodes, ["m:BadItem"]);
+        assert_eq!(fault.reason, "Unknown item");
+        assert_eq!(fault.actor.as_deref(), Some("urn:shop"));
+        assert!(fault.detail.is_none());
+
+        assert!(
+            parse_fault(
+                br#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"><s:Body><Ok/></s:Body></s:Envelope>"#
+            )
+            .is_none()
+        );
+        assert!(parse_fault(b"not xml").is_none());
+    }
+
+    #[test]
+    fn parses_wsdl_operations() {
+        let wsdl = parse_wsdl(
+            r#"<definitions xmlns="http://schemas.xmlsoap.org/wsdl/"
+                  xmlns:soap="http://schemas.xmlsoap.org/wsdl/soap/"
+                  xmlns:soap12="http://schemas.xmlsoap.org/wsdl/soap12/"
+                  xmlns:xs="http://www.w3.org/2001/XMLSchema"
+                  xmlns:tns="urn:shop" targetNamespace="urn:shop">
+              <types>
+                <xs:schema targetNamespace="urn:shop" elementFormDefault="qualified">
+                  <xs:element name="GetPrice">
+                    <xs:complexType><xs:sequence>
+                      <xs:element name="item" type="xs:string"/>
+                      <xs:element name="address" minOccurs="0">
+                        <xs:complexType><xs:sequence>
+                          <xs:element name="zip" type="xs:string"/>
+                        </xs:sequence></xs:complexType>
+                      </xs:element>
+                      <xs:element name="tag" type="xs:string" maxOccurs="unbounded"/>
+                    </xs:sequence></xs:complexType>
+                  </xs:element>
+                </xs:schema>
+              </types>
+              <message name="GetPriceIn"><part name="parameters" element="tns:GetPrice"/></message>
+              <message name="PingIn"><part name="note" type="xs:string"/></message>
+              <portType name="ShopPort">
+                <operation name="GetPrice">
+                  <documentation>Price of an item</documentation>
+                  <input message="tns:GetPriceIn"/>
+                </operation>
+                <operation name="Ping"><input message="tns:PingIn"/></operation>
+              </portType>
+              <binding name="ShopSoap" type="tns:ShopPort">
+                <soap:binding transport="http://schemas.xmlsoap.org/soap/http"/>
+                <operation name="GetPrice"><soap:operation soapAction="urn:shop/GetPrice"/></operation>
+                <operation name="Ping">
+                  <soap:operation soapAction="" style="rpc"/>
+                  <input><soap:body use="literal" namespace="urn:ping"/></input>
+                </operation>
+              </binding>
+              <binding name="ShopSoap12" type="tns:ShopPort">
+                <soap12:binding transport="http://schemas.xmlsoap.org/soap/http"/>
+                <operation name="GetPrice"><soap12:operation soapAction="urn:shop/GetPrice"/></operation>
+              </binding>
+              <service name="Shop">
+                <port name="ShopSoap" binding="tns:ShopSoap"><soap:address location="https://shop.test/soap"/></port>
+                <port name="ShopSoap12" binding="tns:ShopSoap12"><soap12:address location="https://shop.test/soap12"/></port>
+              </service>
+            </definitions>"#,
+        )
+        .unwrap();
+        assert_eq!(wsdl.target_namespace.as_deref(), Some("urn:shop"));
+        assert_eq!(wsdl.operations.len(), 3);
+
+        let get_price = &wsdl.operations[0];
+        assert_eq!(
+            get_price.endpoint.as_deref(),
+            Some("https://shop.test/soap")
+        );
+        assert_eq!(get_price.documentation.as_deref(), Some("Price of an item"));
+        let names: Vec<&str> = get_price
+            .parameters
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(names, ["item", "address", "tag"]);
+        assert!(get_price.parameters[1].optional && get_price.parameters[2].repeated);
+        assert_eq!(get_price.request.operation, "GetPrice");
+        assert_eq!(get_price.request.namespace.as_deref(), Some("urn:shop"));
+        assert!(get_price.request.qualified);
+        assert_eq!(
+            get_price.request.soap_action.as_deref(),
+            Some("urn:shop/GetPrice")
+        );
+        assert_eq!(get_price.request.parameters.len(), 3);
+
+        let ping = &wsdl.operations[1];
+        assert_eq!(ping.request.namespace.as_deref(), Some("urn:ping"));
+        assert!(!ping.request.qualified && ping.request.soap_action.is_none());
+        assert_eq!(ping.parameters[0].type_name.as_deref(), Some("xs:string"));
+
+        assert_eq!(wsdl.operations[2].request.version, SoapVersion::Soap12);
+        assert_eq!(wsdl.operations[2].port, "ShopSoap12");
+
+        assert!(parse_wsdl("<definitions/>").is_err());
+    }
+}
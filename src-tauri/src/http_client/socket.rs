@@ -0,0 +1,291 @@
+//! Raw TCP connections, optionally over TLS, for trying out text protocols such as SMTP or
+//! Redis by hand. Bytes are sent as given and received bytes are passed on as they arrive.
+
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::hyper_engine::{TlsSettings, build_tls_config};
+use rustls::pki_types::ServerName;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use tokio_util::sync::CancellationToken;
+
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+const READ_BUFFER_SIZE: usize = 16 * 1024;
+
+type Writer = Arc<tokio::sync::Mutex<Box<dyn AsyncWrite + Send + Unpin>>>;
+
+struct OpenSocket {
+    writer: Writer,
+    token: CancellationToken,
+}
+
+static SOCKETS: LazyLock<Mutex<HashMap<String, OpenSocket>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SocketOptions {
+    /// Names the connection for `send` and `close`
+    pub connection_id: String,
+    pub host: String,
+    pub port: u16,
+    /// Wrap the connection in TLS, with `host` as the server name
+    pub tls: Option<bool>,
+    /// If true, skip certificate verification
+    pub disable_ssl: Option<bool>,
+    /// Path to a custom root CA bundle (PEM format)
+    pub ca_path: Option<String>,
+    /// Seconds to wait for the connection and handshake. Defaults to 10.
+    pub connect_timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SocketEvent {
+    Data {
+        bytes: Vec<u8>,
+    },
+    /// Always the last event of a connection
+    Closed {
+        /// Why the connection ended, when it wasn't closed cleanly by either side
+        reason: Option<String>,
+    },
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SocketInfo {
+    pub connection_id: String,
+    pub local_addr: String,
+    pub peer_addr: String,
+    /// Negotiated TLS version, for TLS connections
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_version: Option<String>,
+}
+
+/// Connects as `options` asks and passes received bytes to `on_event` until either side
+/// closes the connection.
+pub async fn open(
+    options: SocketOptions,
+    on_event: impl Fn(SocketEvent) + Send + Sync + 'static,
+) -> Result<SocketInfo, AppError> {
+    let id = options.connection_id.clone();
+    if SOCKETS.lock().unwrap().contains_key(&id) {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            format!("Socket '{id}' is already open"),
+        ));
+    }
+
+    let limit = options
+        .connect_timeout_secs
+        .map_or(DEFAULT_CONNECT_TIMEOUT, Duration::from_secs);
+    let (info, reader, writer) = tokio::time::timeout(limit, connect(&options))
+        .await
+        .map_err(|_| {
+            AppError::new(
+                ErrorKind::Timeout,
+                format!("Timed out connecting to {}:{}", options.host, options.port),
+            )
+        })??;
+
+    let token = CancellationToken::new();
+    let writer: Writer = Arc::new(tokio::sync::Mutex::new(writer));
+    {
+        let mut sockets = SOCKETS.lock().unwrap();
+        if sockets.contains_key(&id) {
+            return Err(AppError::new(
+                ErrorKind::BadRequest,
+                format!("Socket '{id}' is already open"),
+            ));
+        }
+        sockets.insert(
+            id.clone(),
+            OpenSocket {
+                writer,
+                token: token.clone(),
+            },
+        );
+    }
+    tokio::spawn(read_loop(id, reader, token, on_event));
+    Ok(info)
+}
+
+async fn connect(
+    options: &SocketOptions,
+) -> Result<
+    (
+        SocketInfo,
+        Box<dyn AsyncRead + Send + Unpin>,
+        Box<dyn AsyncWrite + Send + Unpin>,
+    ),
+    AppError,
+> {
+    let stream = TcpStream::connect((options.host.as_str(), options.port))
+        .await
+        .map_err(|e| {
+            let message = format!(
+                "Failed to connect to {}:{}: {e}",
+                options.host, options.port
+            );
+            AppError::new(AppError::from(e).kind, message)
+        })?;
+    let mut info = SocketInfo {
+        connection_id: options.connection_id.clone(),
+        local_addr: stream.local_addr()?.to_string(),
+        peer_addr: stream.peer_addr()?.to_string(),
+        tls_version: None,
+    };
+    if !options.tls.unwrap_or(false) {
+        let (reader, writer) = tokio::io::split(stream);
+        return Ok((info, Box::new(reader), Box::new(writer)));
+    }
+
+    let config = build_tls_config(TlsSettings {
+        disable_verification: options.disable_ssl.unwrap_or(false),
+        custom_ca: options.ca_path.as_deref(),
+        ..TlsSettings::default()
+    })?;
+    let server_name = ServerName::try_from(options.host.clone()).map_err(|e| {
+        AppError::new(
+            ErrorKind::BadRequest,
+            format!("Invalid server name '{}': {e}", options.host),
+        )
+    })?;
+    let stream = TlsConnector::from(Arc::new(config))
+        .connect(server_name, stream)
+        .await
+        .map_err(|e| AppError::new(ErrorKind::HttpError, format!("TLS handshake failed: {e}")))?;
+    info.tls_version = stream
+        .get_ref()
+        .1
+        .protocol_version()
+        .and_then(|version| version.as_str())
+        .map(str::to_string);
+    let (reader, writer) = tokio::io::split(stream);
+    Ok((info, Box::new(reader), Box::new(writer)))
+}
+
+async fn read_loop(
+    id: String,
+    mut reader: Box<dyn AsyncRead + Send + Unpin>,
+    token: CancellationToken,
+    on_event: impl Fn(SocketEvent),
+) {
+    let mut buf = vec![0u8; READ_BUFFER_SIZE];
+    let reason = loop {
+        tokio::select! {
+            _ = token.cancelled() => break None,
+            read = reader.read(&mut buf) => match read {
+                Ok(0) => break None,
+                Ok(n) => on_event(SocketEvent::Data { bytes: buf[..n].to_vec() }),
+                Err(e) => break Some(e.to_string()),
+            }
+        }
+    };
+    // A socket closed by `close` is already gone, and its id may be in use again
+    if !token.is_cancelled() {
+        SOCKETS.lock().unwrap().remove(&id);
+    }
+    on_event(SocketEvent::Closed { reason });
+}
+
+/// Writes `bytes` to the open socket `connection_id`.
+pub async fn send(connection_id: &str, bytes: &[u8]) -> Result<(), AppError> {
+    let writer = SOCKETS
+        .lock()
+        .unwrap()
+        .get(connection_id)
+        .map(|socket| socket.writer.clone())
+        .ok_or_else(|| {
+            AppError::new(
+                ErrorKind::BadRequest,
+                format!("Socket '{connection_id}' is not open"),
+            )
+        })?;
+    let mut writer = writer.lock().await;
+    writer.write_all(bytes).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Closes the socket `connection_id`. Returns false when it wasn't open.
+pub async fn close(connection_id: &str) -> bool {
+    let Some(socket) = SOCKETS.lock().unwrap().remove(connection_id) else {
+        return false;
+    };
+    socket.token.cancel();
+    // Lets the peer see the close, and sends TLS's close_notify
+    let _ = socket.writer.lock().await.shutdown().await;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SocketEvent, SocketOptions, close, open, send};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn exchanges_bytes_until_closed() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            stream.write_all(b"+READY\r\n").await.unwrap();
+            let mut buf = [0u8; 64];
+            let n = stream.read(&mut buf).await.unwrap();
+            stream.write_all(&buf[..n]).await.unwrap();
+            // Waits for the client to close
+            let _ = stream.read(&mut buf).await;
+        });
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let options = SocketOptions {
+            connection_id: "socket-test".to_string(),
+            host: "127.0.0.1".to_string(),
+            port,
+            tls: None,
+            disable_ssl: None,
+            ca_path: None,
+            connect_timeout_secs: None,
+        };
+        let info = open(options.clone(), move |event| {
+            let _ = tx.send(event);
+        })
+        .await
+        .unwrap();
+        assert_eq!(info.peer_addr, format!("127.0.0.1:{port}"));
+        assert!(open(options, |_| {}).await.is_err());
+
+        let mut received = Vec::new();
+        while received != b"+READY\r\n" {
+            match rx.recv().await.unwrap() {
+                SocketEvent::Data { bytes } => received.extend(bytes),
+                other => panic!("unexpected {other:?}"),
+            }
+        }
+        send("socket-test", b"PING\r\n").await.unwrap();
+        received.clear();
+        while received != b"PING\r\n" {
+            match rx.recv().await.unwrap() {
+                SocketEvent::Data { bytes } => received.extend(bytes),
+                other => panic!("unexpected {other:?}"),
+            }
+        }
+
+        assert!(close("socket-test").await);
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            SocketEvent::Closed { reason: None }
+        );
+        assert!(!close("socket-test").await);
+        assert!(send("socket-test", b"late").await.is_err());
+    }
+}
@@ -0,0 +1,616 @@
+//! Temp files that response bodies too large to keep in memory are spooled to. Textual bodies
+//! are compressed with zstd as they're written, which typically shrinks them several times
+//! over. They're written in the zstd seekable format: independent frames followed by a seek
+//! table, so a range read only decompresses the frames it covers. Compressed spools are named
+//! with [`COMPRESSED_SUFFIX`], which is how readers tell them apart.
+
+use crate::errors::{AppError, ErrorKind};
+use base64::{Engine as _, engine::general_purpose};
+use hyper::http::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use tempfile::{Builder as TempFileBuilder, NamedTempFile};
+
+/// Spooling is on the download path, so favour speed over ratio
+const ZSTD_LEVEL: i32 = 1;
+
+pub const COMPRESSED_SUFFIX: &str = ".zst";
+
+/// Body bytes per zstd frame. A range read decompresses at most this much it doesn't return
+/// at either end.
+const FRAME_LEN: usize = 1 << 20;
+
+/// Seek table framing of the zstd seekable format (`contrib/seekable_format` in the zstd
+/// repository): a skippable frame (RFC 8878 §3.1.2) of per-frame sizes, then a footer
+const SKIPPABLE_MAGIC: u32 = 0x184D_2A5E;
+const SEEKABLE_MAGIC: u32 = 0x8F92_EAB1;
+const SEEK_TABLE_FOOTER_LEN: u64 = 9;
+/// Set in the footer's descriptor when entries carry a checksum after their sizes
+const CHECKSUM_FLAG: u8 = 0x80;
+
+/// Largest chunk read at once, so a viewer paging through a body never holds all of it
+pub const MAX_CHUNK_LEN: usize = 4 << 20;
+
+const DEFAULT_BYTES_PER_ROW: usize = 16;
+
+fn io_error(e: io::Error) -> AppError {
+    AppError::new(ErrorKind::IoError, format!("Spool file error: {e}"))
+}
+
+/// Whether a media type is text that compresses well
+fn is_textual(content_type: &str) -> bool {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    essence.starts_with("text/")
+        || essence.ends_with("+json")
+        || essence.ends_with("+xml")
+        || matches!(
+            essence.as_str(),
+            "application/json"
+                | "application/xml"
+                | "application/javascript"
+                | "application/ecmascript"
+                | "application/x-ndjson"
+                | "application/jsonl"
+                | "application/yaml"
+                | "application/x-yaml"
+                | "application/csv"
+                | "application/sql"
+                | "application/graphql"
+        )
+}
+
+/// Whether a response with these headers is worth compressing when spooled: textual, and not
+/// already compressed by the server.
+pub fn should_compress(headers: &HeaderMap) -> bool {
+    let encoded = headers
+        .get(hyper::header::CONTENT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| !v.trim().eq_ignore_ascii_case("identity"));
+    let textual = headers
+        .get(hyper::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(is_textual);
+    textual && !encoded
+}
+
+/// A spool file being written
+pub enum SpoolFile {
+    Plain(NamedTempFile),
+    Zstd(SeekableWriter),
+}
+
+/// Writes a body as independent zstd frames of [`FRAME_LEN`] bytes, then their seek table
+pub struct SeekableWriter {
+    file: NamedTempFile,
+    pending: Vec<u8>,
+    /// Compressed and body size of each frame written
+    frames: Vec<(u32, u32)>,
+}
+
+impl SeekableWriter {
+    fn write_all(&mut self, mut bytes: &[u8]) -> io::Result<()> {
+        while !bytes.is_empty() {
+            let take = (FRAME_LEN - self.pending.len()).min(bytes.len());
+            self.pending.extend_from_slice(&bytes[..take]);
+            bytes = &bytes[take..];
+            if self.pending.len() == FRAME_LEN {
+                self.write_frame()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_frame(&mut self) -> io::Result<()> {
+        let frame = zstd::bulk::compress(&self.pending, ZSTD_LEVEL)?;
+        self.file.write_all(&frame)?;
+        self.frames
+            .push((frame.len() as u32, self.pending.len() as u32));
+        self.pending.clear();
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<NamedTempFile> {
+        if !self.pending.is_empty() {
+            self.write_frame()?;
+        }
+        let entries_len = self.frames.len() * 8;
+        let mut table = Vec::with_capacity(8 + entries_len + SEEK_TABLE_FOOTER_LEN as usize);
+        table.extend_from_slice(&SKIPPABLE_MAGIC.to_le_bytes());
+        table.extend_from_slice(
+            &((entries_len as u64 + SEEK_TABLE_FOOTER_LEN) as u32).to_le_bytes(),
+        );
+        for (compressed, decompressed) in &self.frames {
+            table.extend_from_slice(&compressed.to_le_bytes());
+            table.extend_from_slice(&decompressed.to_le_bytes());
+        }
+        table.extend_from_slice(&(self.frames.len() as u32).to_le_bytes());
+        table.push(0);
+        table.extend_from_slice(&SEEKABLE_MAGIC.to_le_bytes());
+        self.file.write_all(&table)?;
+        Ok(self.file)
+    }
+}
+
+impl SpoolFile {
+    pub fn create(compress: bool) -> Result<Self, AppError> {
+        let mut builder = TempFileBuilder::new();
+        builder.prefix("knurl-");
+        if compress {
+            let file = builder
+                .suffix(COMPRESSED_SUFFIX)
+                .tempfile()
+                .map_err(io_error)?;
+            Ok(SpoolFile::Zstd(SeekableWriter {
+                file,
+                pending: Vec::with_capacity(FRAME_LEN),
+                frames: Vec::new(),
+            }))
+        } else {
+            Ok(SpoolFile::Plain(builder.tempfile().map_err(io_error)?))
+        }
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        matches!(self, SpoolFile::Zstd(_))
+    }
+
+    pub fn write_all(&mut self, bytes: &[u8]) -> Result<(), AppError> {
+        match self {
+            SpoolFile::Plain(file) => file.write_all(bytes),
+            SpoolFile::Zstd(writer) => writer.write_all(bytes),
+        }
+        .map_err(io_error)
+    }
+
+    /// Finishes the file and keeps it past the temp file's lifetime, returning its path.
+    pub fn keep(self) -> Result<PathBuf, AppError> {
+        let file = match self {
+            SpoolFile::Plain(file) => file,
+            SpoolFile::Zstd(writer) => writer.finish().map_err(io_error)?,
+        };
+        let (_file, path) = file.keep().map_err(|e| io_error(e.error))?;
+        Ok(path)
+    }
+}
+
+/// Whether a spool file holds its body compressed. Only [`SpoolFile`] gives files the
+/// [`COMPRESSED_SUFFIX`], so a plain body that happens to start like a zstd frame is read as-is.
+pub fn is_compressed(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().ends_with(COMPRESSED_SUFFIX))
+}
+
+/// A frame of a compressed spool: where it starts in the file and in the body, and its sizes
+struct Frame {
+    file_offset: u64,
+    body_offset: u64,
+    compressed: u64,
+    decompressed: u64,
+}
+
+/// The frames of a compressed spool, from its seek table
+fn read_seek_table(file: &mut File) -> io::Result<Vec<Frame>> {
+    let invalid = |what: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid zstd seek table: {what}"),
+        )
+    };
+    let u32_at = |bytes: &[u8], at: usize| {
+        u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+    };
+
+    let len = file.metadata()?.len();
+    let footer_start = len
+        .checked_sub(SEEK_TABLE_FOOTER_LEN)
+        .ok_or_else(|| invalid("file too short"))?;
+    file.seek(SeekFrom::Start(footer_start))?;
+    let mut footer = [0u8; SEEK_TABLE_FOOTER_LEN as usize];
+    file.read_exact(&mut footer)?;
+    if u32_at(&footer, 5) != SEEKABLE_MAGIC {
+        return Err(invalid("missing footer"));
+    }
+    let entry_len = if footer[4] & CHECKSUM_FLAG != 0 {
+        12
+    } else {
+        8
+    };
+    let entries_len = u64::from(u32_at(&footer, 0)) * entry_len;
+    let table_start = footer_start
+        .checked_sub(entries_len + 8)
+        .ok_or_else(|| invalid("truncated"))?;
+
+    file.seek(SeekFrom::Start(table_start))?;
+    let mut table = vec![0u8; (entries_len + 8) as usize];
+    file.read_exact(&mut table)?;
+    if u32_at(&table, 0) != SKIPPABLE_MAGIC {
+        return Err(invalid("missing skippable frame"));
+    }
+    let mut frames = Vec::new();
+    let (mut file_offset, mut body_offset) = (0, 0);
+    for entry in table[8..].chunks_exact(entry_len as usize) {
+        let frame = Frame {
+            file_offset,
+            body_offset,
+            compressed: u64::from(u32_at(entry, 0)),
+            decompressed: u64::from(u32_at(entry, 4)),
+        };
+        // Larger frames aren't written here, and would be decompressed whole
+        if frame.decompressed > FRAME_LEN as u64 {
+            return Err(invalid("frame too large"));
+        }
+        file_offset += frame.compressed;
+        body_offset += frame.decompressed;
+        frames.push(frame);
+    }
+    if file_offset != table_start {
+        return Err(invalid("frame sizes don't add up to the file"));
+    }
+    Ok(frames)
+}
+
+/// Size of the body a spool file holds
+pub fn body_len(path: &Path) -> Result<u64, AppError> {
+    let mut file = File::open(path).map_err(io_error)?;
+    if !is_compressed(path) {
+        return Ok(file.metadata().map_err(io_error)?.len());
+    }
+    let frames = read_seek_table(&mut file).map_err(io_error)?;
+    Ok(frames
+        .last()
+        .map_or(0, |last| last.body_offset + last.decompressed))
+}
+
+/// Opens a spooled body for reading, decompressing it if it was compressed.
+pub fn open(path: &Path) -> Result<Box<dyn Read + Send>, AppError> {
+    let file = File::open(path).map_err(io_error)?;
+    if is_compressed(path) {
+        // The decoder reads the frames in turn and skips the seek table
+        let decoder = zstd::stream::read::Decoder::new(file).map_err(io_error)?;
+        Ok(Box::new(decoder))
+    } else {
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+/// Reads up to `length` bytes of the body starting at `offset`, in the body's own offsets
+/// whether or not the file is compressed.
+pub fn read_range(path: &Path, offset: u64, length: usize) -> Result<Vec<u8>, AppError> {
+    let mut file = File::open(path).map_err(io_error)?;
+    let mut out = Vec::with_capacity(length.min(1 << 20));
+    if is_compressed(path) {
+        let end = offset.saturating_add(length as u64);
+        let frames = read_seek_table(&mut file).map_err(io_error)?;
+        let covered = frames
+            .iter()
+            .skip_while(|frame| frame.body_offset + frame.decompressed <= offset)
+            .take_while(|frame| frame.body_offset < end);
+        for frame in covered {
+            file.seek(SeekFrom::Start(frame.file_offset))
+                .map_err(io_error)?;
+            let mut compressed = vec![0u8; frame.compressed as usize];
+            file.read_exact(&mut compressed).map_err(io_error)?;
+            let body = zstd::bulk::decompress(&compressed, frame.decompressed as usize)
+                .map_err(io_error)?;
+            let start = (offset.saturating_sub(frame.body_offset) as usize).min(body.len());
+            let stop = ((end - frame.body_offset) as usize).min(body.len());
+            out.extend_from_slice(&body[start..stop]);
+        }
+    } else {
+        file.seek(SeekFrom::Start(offset)).map_err(io_error)?;
+        file.take(length as u64)
+            .read_to_end(&mut out)
+            .map_err(io_error)?;
+    }
+    Ok(out)
+}
+
+/// How a chunk's bytes are returned
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ChunkFormat {
+    #[default]
+    Base64,
+    /// Rows of a hex dump
+    Hex,
+}
+
+/// A byte range of a spooled body to read
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkRequest {
+    pub path: String,
+    pub offset: u64,
+    /// Bytes to read, at most `MAX_CHUNK_LEN`
+    pub length: usize,
+    #[serde(default)]
+    pub format: ChunkFormat,
+    /// Bytes per hex dump row, 16 by default
+    pub bytes_per_row: Option<usize>,
+}
+
+/// One row of a hex dump
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HexRow {
+    /// Body offset of the row's first byte
+    pub offset: u64,
+    /// The bytes as space-separated hex pairs, e.g. "7b 22 69 64"
+    pub hex: String,
+    /// The bytes as ASCII, with `.` for anything unprintable
+    pub text: String,
+}
+
+/// The bytes of a chunk
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "format", rename_all = "camelCase")]
+pub enum ChunkContent {
+    Base64 { data: String },
+    Hex { rows: Vec<HexRow> },
+}
+
+/// A byte range of a spooled body
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BodyChunk {
+    pub offset: u64,
+    /// Bytes read; fewer than asked for at the end of the body
+    pub length: usize,
+    /// Whether the chunk reaches the end of the body
+    pub eof: bool,
+    /// Size of the whole body
+    pub total_length: u64,
+    pub content: ChunkContent,
+}
+
+/// Rows of a hex dump of `bytes`, which start at body offset `offset`
+pub fn hex_rows(bytes: &[u8], offset: u64, bytes_per_row: usize) -> Vec<HexRow> {
+    let bytes_per_row = bytes_per_row.max(1);
+    bytes
+        .chunks(bytes_per_row)
+        .enumerate()
+        .map(|(i, row)| HexRow {
+            offset: offset + (i * bytes_per_row) as u64,
+            hex: row
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect::<Vec<_>>()
+                .join(" "),
+            text: row
+                .iter()
+                .map(|&b| {
+                    if b.is_ascii_graphic() || b == b' ' {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Reads a chunk of a spooled body for paging through it, e.g. in a hex viewer
+pub fn read_chunk(request: ChunkRequest) -> Result<BodyChunk, AppError> {
+    let path = Path::new(&request.path);
+    let length = request.length.min(MAX_CHUNK_LEN);
+    // One byte past the chunk tells whether the body goes on
+    let mut bytes = read_range(path, request.offset, length + 1)?;
+    let eof = bytes.len() <= length;
+    bytes.truncate(length);
+    let total_length = body_len(path)?;
+    let content = match request.format {
+        ChunkFormat::Base64 => ChunkContent::Base64 {
+            data: general_purpose::STANDARD.encode(&bytes),
+        },
+        ChunkFormat::Hex => ChunkContent::Hex {
+            rows: hex_rows(
+                &bytes,
+                request.offset,
+                request.bytes_per_row.unwrap_or(DEFAULT_BYTES_PER_ROW),
+            ),
+        },
+    };
+    Ok(BodyChunk {
+        offset: request.offset,
+        length: bytes.len(),
+        eof,
+        total_length,
+        content,
+    })
+}
+
+/// The body as a plain file for other apps to open: the spool file itself when it isn't
+/// compressed, else a decompressed copy next to it, written once.
+pub fn materialize(path: &Path) -> Result<PathBuf, AppError> {
+    if !is_compressed(path) {
+        return Ok(path.to_path_buf());
+    }
+    let file = File::open(path).map_err(io_error)?;
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let plain = path.with_file_name(name.strip_suffix(COMPRESSED_SUFFIX).unwrap_or(&name));
+    if plain.exists() {
+        return Ok(plain);
+    }
+    // Write under a temp name so an interrupted copy is never mistaken for a complete one
+    let parent = path.parent().unwrap_or(Path::new("."));
+    let mut out = TempFileBuilder::new()
+        .prefix("knurl-")
+        .tempfile_in(parent)
+        .map_err(io_error)?;
+    zstd::stream::copy_decode(file, &mut out).map_err(io_error)?;
+    out.persist(&plain).map_err(|e| io_error(e.error))?;
+    Ok(plain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ChunkContent, ChunkFormat, ChunkRequest, FRAME_LEN, SpoolFile, body_len, hex_rows,
+        is_compressed, materialize, open, read_chunk, read_range, read_seek_table, should_compress,
+    };
+    use hyper::http::{HeaderMap, HeaderValue};
+    use std::fs::File;
+    use std::io::Read;
+
+    fn headers(content_type: &str, encoding: Option<&str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", HeaderValue::from_str(content_type).unwrap());
+        if let Some(encoding) = encoding {
+            headers.insert("content-encoding", HeaderValue::from_str(encoding).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn compresses_only_uncompressed_text() {
+        assert!(should_compress(&headers(
+            "application/json; charset=utf-8",
+            None
+        )));
+        assert!(should_compress(&headers("application/problem+json", None)));
+        assert!(should_compress(&headers("text/csv", Some("identity"))));
+        assert!(!should_compress(&headers("text/html", Some("gzip"))));
+        assert!(!should_compress(&headers("image/png", None)));
+        assert!(!should_compress(&HeaderMap::new()));
+    }
+
+    #[test]
+    fn reads_compressed_spools_transparently() {
+        let body: Vec<u8> = (0..50_000u32)
+            .flat_map(|i| format!("{{\"row\":{i}}}\n").into_bytes())
+            .collect();
+        for compress in [true, false] {
+            let mut spool = SpoolFile::create(compress).unwrap();
+            assert_eq!(spool.is_compressed(), compress);
+            for chunk in body.chunks(7_000) {
+                spool.write_all(chunk).unwrap();
+            }
+            let path = spool.keep().unwrap();
+            let on_disk = std::fs::metadata(&path).unwrap().len() as usize;
+            assert_eq!(on_disk < body.len() / 4, compress);
+
+            let mut all = Vec::new();
+            open(&path).unwrap().read_to_end(&mut all).unwrap();
+            assert_eq!(all, body);
+            assert_eq!(
+                read_range(&path, 100_000, 50).unwrap(),
+                &body[100_000..100_050]
+            );
+            assert_eq!(
+                read_range(&path, body.len() as u64 - 10, 50).unwrap(),
+                &body[body.len() - 10..]
+            );
+            assert!(
+                read_range(&path, body.len() as u64 + 1, 50)
+                    .unwrap()
+                    .is_empty()
+            );
+
+            let plain = materialize(&path).unwrap();
+            assert_eq!(plain == path, !compress);
+            assert_eq!(std::fs::read(&plain).unwrap(), body);
+            // Materializing again reuses the copy
+            assert_eq!(materialize(&path).unwrap(), plain);
+
+            std::fs::remove_file(&path).unwrap();
+            if plain != path {
+                std::fs::remove_file(&plain).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn reads_ranges_across_frames() {
+        let body: Vec<u8> = (0..700_000u32).flat_map(|i| i.to_le_bytes()).collect();
+        let mut spool = SpoolFile::create(true).unwrap();
+        spool.write_all(&body).unwrap();
+        let path = spool.keep().unwrap();
+
+        let mut file = File::open(&path).unwrap();
+        let frames = read_seek_table(&mut file).unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[1].body_offset, FRAME_LEN as u64);
+        assert_eq!(body_len(&path).unwrap(), body.len() as u64);
+
+        let start = FRAME_LEN - 10;
+        assert_eq!(
+            read_range(&path, start as u64, FRAME_LEN + 20).unwrap(),
+            &body[start..start + FRAME_LEN + 20]
+        );
+        let mut all = Vec::new();
+        open(&path).unwrap().read_to_end(&mut all).unwrap();
+        assert_eq!(all, body);
+
+        // Without its seek table a compressed spool can't be read by range
+        let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(file.metadata().unwrap().len() - 1).unwrap();
+        assert!(read_range(&path, 0, 10).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reads_plain_spools_that_look_compressed() {
+        let body = [0x28, 0xB5, 0x2F, 0xFD, 0x00, 0x01, 0x02, 0x03];
+        let mut spool = SpoolFile::create(false).unwrap();
+        spool.write_all(&body).unwrap();
+        let path = spool.keep().unwrap();
+
+        assert!(!is_compressed(&path));
+        assert_eq!(read_range(&path, 0, 64).unwrap(), body);
+        let mut all = Vec::new();
+        open(&path).unwrap().read_to_end(&mut all).unwrap();
+        assert_eq!(all, body);
+        assert_eq!(materialize(&path).unwrap(), path);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reads_chunks_as_hex_dumps() {
+        assert_eq!(hex_rows(b"{\"a\":\n", 32, 4)[1].offset, 36);
+        let rows = hex_rows(b"{\"a\":\x00\xff", 0, 16);
+        assert_eq!(rows[0].hex, "7b 22 61 22 3a 00 ff");
+        assert_eq!(rows[0].text, "{\"a\":..");
+
+        let body: Vec<u8> = (0..1_000u32).flat_map(|i| i.to_be_bytes()).collect();
+        for compress in [true, false] {
+            let mut spool = SpoolFile::create(compress).unwrap();
+            spool.write_all(&body).unwrap();
+            let path = spool.keep().unwrap();
+            let request = |offset, length, format| ChunkRequest {
+                path: path.to_string_lossy().to_string(),
+                offset,
+                length,
+                format,
+                bytes_per_row: Some(8),
+            };
+
+            let first = read_chunk(request(0, 64, ChunkFormat::Hex)).unwrap();
+            assert_eq!(first.length, 64);
+            assert!(!first.eof);
+            assert_eq!(first.total_length, 4_000);
+            let ChunkContent::Hex { rows } = first.content else {
+                panic!("expected hex rows");
+            };
+            assert_eq!(rows.len(), 8);
+            assert_eq!(rows[1].offset, 8);
+            assert_eq!(rows[1].hex, "00 00 00 02 00 00 00 03");
+
+            // A chunk ending right at the end of the body is the last one
+            let last = read_chunk(request(3_936, 64, ChunkFormat::Base64)).unwrap();
+            assert!(last.eof);
+            assert_eq!(last.total_length, 4_000);
+            let past = read_chunk(request(3_990, 64, ChunkFormat::Base64)).unwrap();
+            assert_eq!((past.length, past.eof), (10, true));
+
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! Server-Sent Events: parses a `text/event-stream` body into events as it arrives, following
+//! the WHATWG HTML "event stream interpretation" rules.
+
+use serde::Serialize;
+use std::fmt;
+use std::sync::Arc;
+
+/// One dispatched event
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SseEvent {
+    /// The event type, "message" unless the server named one
+    pub event: String,
+    pub data: String,
+    /// The last event ID seen on the stream, which a reconnecting client sends back as
+    /// `Last-Event-ID`
+    pub id: Option<String>,
+    /// Reconnection time the server asked for, in milliseconds
+    pub retry: Option<u64>,
+}
+
+/// Receives the events of a streamed response; set by the command, never read from the
+/// frontend.
+#[derive(Clone)]
+pub struct EventSink(Arc<dyn Fn(SseEvent) + Send + Sync>);
+
+impl EventSink {
+    pub fn new(send: impl Fn(SseEvent) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(send))
+    }
+
+    pub fn send(&self, event: SseEvent) {
+        (self.0)(event)
+    }
+}
+
+impl fmt::Debug for EventSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EventSink")
+    }
+}
+
+/// Whether a response with this `Content-Type` is an event stream
+pub fn is_event_stream(content_type: &str) -> bool {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .eq_ignore_ascii_case("text/event-stream")
+}
+
+/// Incremental event stream parser. Lines may be split across chunks at any byte.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    line: Vec<u8>,
+    /// A CR ended the last line, so a LF right after it belongs to the same line break
+    skip_lf: bool,
+    started: bool,
+    event: String,
+    data: String,
+    last_event_id: Option<String>,
+    retry: Option<u64>,
+}
+
+impl SseParser {
+    /// Parses `bytes`, returning the events they complete.
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+        for &byte in bytes {
+            if std::mem::take(&mut self.skip_lf) && byte == b'\n' {
+                continue;
+            }
+            match byte {
+                b'\r' => {
+                    self.skip_lf = true;
+                    self.end_line(&mut events);
+                }
+                b'\n' => self.end_line(&mut events),
+                _ => self.line.push(byte),
+            }
+        }
+        events
+    }
+
+    fn end_line(&mut self, events: &mut Vec<SseEvent>) {
+        let bytes = std::mem::take(&mut self.line);
+        let mut line = String::from_utf8_lossy(&bytes).into_owned();
+        if !self.started {
+            self.started = true;
+            if let Some(rest) = line.strip_prefix('\u{FEFF}') {
+                line = rest.to_string();
+            }
+        }
+
+        if line.is_empty() {
+            if let Some(event) = self.dispatch() {
+                events.push(event);
+            }
+            return;
+        }
+        if line.starts_with(':') {
+            // Comment, often sent as a keep-alive
+            return;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line.as_str(), ""),
+        };
+        match field {
+            "event" => self.event = value.to_string(),
+            "data" => {
+                self.data.push_str(value);
+                self.data.push('\n');
+            }
+            "id" if !value.contains('\0') => self.last_event_id = Some(value.to_string()),
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                self.retry = value.parse().ok();
+            }
+            _ => {}
+        }
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = std::mem::take(&mut self.event);
+        let mut data = std::mem::take(&mut self.data);
+        if data.is_empty() {
+            return None;
+        }
+        data.pop();
+        Some(SseEvent {
+            event: if event.is_empty() {
+                "message".to_string()
+            } else {
+                event
+            },
+            data,
+            id: self.last_event_id.clone(),
+            retry: self.retry,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SseParser, is_event_stream};
+
+    #[test]
+    fn parses_events_split_across_chunks() {
+        let stream = "\u{FEFF}: keep-alive\r\n\
+                      retry: 3000\r\n\
+                      id: 1\r\n\
+                      data: first\r\n\
+                      data:  second line\r\n\r\n\
+                      event: update\n\
+                      data\n\
+                      \n\
+                      event: ignored\n\
+                      \n\
+                      id: 2\rdata: last\r\r\
+                      data: incomplete";
+        let mut parser = SseParser::default();
+        // One byte at a time, so every split point is exercised
+        let events: Vec<_> = stream
+            .as_bytes()
+            .chunks(1)
+            .flat_map(|chunk| parser.feed(chunk))
+            .collect();
+
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].event, "message");
+        assert_eq!(events[0].data, "first\n second line");
+        assert_eq!(events[0].id.as_deref(), Some("1"));
+        assert_eq!(events[0].retry, Some(3000));
+        // A bare `data` field contributes an empty line
+        assert_eq!(events[1].event, "update");
+        assert_eq!(events[1].data, "");
+        assert_eq!(events[1].id.as_deref(), Some("1"));
+        // An event without data isn't dispatched, and its type doesn't carry over
+        assert_eq!(events[2].event, "message");
+        assert_eq!(events[2].data, "last");
+        assert_eq!(events[2].id.as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn recognizes_event_stream_content_types() {
+        assert!(is_event_stream("text/event-stream"));
+        assert!(is_event_stream("Text/Event-Stream; charset=utf-8"));
+        assert!(!is_event_stream("application/json"));
+    }
+}
@@ -0,0 +1,467 @@
+//! A small static file server for testing against fixture payloads or a built SPA. Each
+//! request it answers is logged like a sent request, under the server's id, so its access log
+//! shows up and exports alongside request logs.
+
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::engine::LogEmitter;
+use crate::http_client::response::{LogEntry, LogLevel};
+use bytes::Bytes;
+use chrono::{SecondsFormat, Utc};
+use http_body_util::Full;
+use hyper::body::Incoming;
+use hyper::http::{HeaderValue, Method, Request, Response, StatusCode, header};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper_util::rt::TokioIo;
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, percent_decode_str, utf8_percent_encode};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Instant;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::sync::CancellationToken;
+
+const INDEX_FILE: &str = "index.html";
+
+/// Characters escaped in the links of a directory listing
+const LINK_ESCAPES: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+static SERVERS: LazyLock<Mutex<HashMap<String, CancellationToken>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct StaticServerOptions {
+    /// Names the server for `stop`, and is the request id its access log is filed under
+    pub server_id: String,
+    /// Directory to serve
+    pub root: String,
+    /// Port to listen on. Defaults to an ephemeral one.
+    pub port: Option<u16>,
+    /// Address to listen on. Defaults to 127.0.0.1; any other exposes the server to the
+    /// network.
+    pub bind_address: Option<String>,
+    /// Answer paths that match no file with the root's index.html, as client-side routing
+    /// needs
+    pub spa_fallback: Option<bool>,
+    /// Allow any origin to read responses
+    pub cors: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StaticServerInfo {
+    pub server_id: String,
+    /// The address listened on
+    pub address: String,
+    pub url: String,
+    /// The directory served, resolved
+    pub root: String,
+}
+
+struct Shared {
+    id: String,
+    root: PathBuf,
+    spa_fallback: bool,
+    cors: bool,
+    emitter: Arc<dyn LogEmitter>,
+}
+
+/// Starts serving `options.root`, logging each request to `emitter` until the server is
+/// stopped.
+pub async fn start(
+    options: StaticServerOptions,
+    emitter: Arc<dyn LogEmitter>,
+) -> Result<StaticServerInfo, AppError> {
+    let id = options.server_id.clone();
+    if SERVERS.lock().unwrap().contains_key(&id) {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            format!("Server '{id}' is already running"),
+        ));
+    }
+    let root = Path::new(&options.root).canonicalize().map_err(|e| {
+        AppError::new(
+            ErrorKind::InvalidPath,
+            format!("Can't serve '{}': {e}", options.root),
+        )
+    })?;
+    if !root.is_dir() {
+        return Err(AppError::new(
+            ErrorKind::InvalidPath,
+            format!("Can't serve '{}': not a directory", options.root),
+        ));
+    }
+
+    let host = options.bind_address.as_deref().unwrap_or("127.0.0.1");
+    let listener = TcpListener::bind((host, options.port.unwrap_or(0)))
+        .await
+        .map_err(|e| {
+            let message = format!("Failed to listen on {host}: {e}");
+            AppError::new(AppError::from(e).kind, message)
+        })?;
+    let address = listener.local_addr()?;
+    let info = StaticServerInfo {
+        server_id: id.clone(),
+        address: address.to_string(),
+        url: format!("http://{address}/"),
+        root: root.display().to_string(),
+    };
+
+    let token = CancellationToken::new();
+    {
+        let mut servers = SERVERS.lock().unwrap();
+        if servers.contains_key(&id) {
+            return Err(AppError::new(
+                ErrorKind::BadRequest,
+                format!("Server '{id}' is already running"),
+            ));
+        }
+        servers.insert(id.clone(), token.clone());
+    }
+    let shared = Arc::new(Shared {
+        id,
+        root,
+        spa_fallback: options.spa_fallback.unwrap_or(false),
+        cors: options.cors.unwrap_or(false),
+        emitter,
+    });
+    tokio::spawn(accept_loop(listener, token, shared));
+    Ok(info)
+}
+
+fn emit(shared: &Shared, level: LogLevel, message: String, elapsed_ms: u64, details: Value) {
+    shared.emitter.emit(LogEntry {
+        request_id: shared.id.clone(),
+        timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        level,
+        info_type: Some("access".to_string()),
+        message,
+        category: Some("server".to_string()),
+        phase: Some("access".to_string()),
+        elapsed_ms: Some(elapsed_ms),
+        details: Some(details),
+        bytes_logged: None,
+        truncated: None,
+    });
+}
+
+async fn accept_loop(listener: TcpListener, token: CancellationToken, shared: Arc<Shared>) {
+    let error = loop {
+        tokio::select! {
+            _ = token.cancelled() => return,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, remote)) => {
+                    tokio::spawn(serve_connection(stream, remote, token.clone(), shared.clone()));
+                }
+                Err(e) => break e,
+            }
+        }
+    };
+    // A server stopped by `stop` is already gone, and its id may be in use again
+    SERVERS.lock().unwrap().remove(&shared.id);
+    let message = format!("Server stopped: {error}");
+    emit(&shared, LogLevel::Error, message, 0, json!({}));
+}
+
+async fn serve_connection(
+    stream: TcpStream,
+    remote: SocketAddr,
+    token: CancellationToken,
+    shared: Arc<Shared>,
+) {
+    let service = service_fn(move |request| serve(request, remote, shared.clone()));
+    let connection = http1::Builder::new().serve_connection(TokioIo::new(stream), service);
+    tokio::select! {
+        _ = token.cancelled() => {}
+        result = connection => {
+            if let Err(e) = result {
+                log::debug!("Static server connection ended: {e}");
+            }
+        }
+    }
+}
+
+/// The file under `root` a request path names. Paths that climb out of the root, or reach
+/// outside it through a link, name nothing.
+fn resolve(root: &Path, request_path: &str) -> Option<PathBuf> {
+    let decoded = percent_decode_str(request_path).decode_utf8().ok()?;
+    let mut path = root.to_path_buf();
+    for component in Path::new(decoded.trim_start_matches('/')).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    let path = path.canonicalize().ok()?;
+    path.starts_with(root).then_some(path)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A page linking to each entry of `dir`, directories first
+fn listing(dir: &Path, request_path: &str) -> std::io::Result<String> {
+    let mut entries: Vec<(bool, String)> = std::fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .map(|entry| {
+            let is_dir = entry.file_type().is_ok_and(|kind| kind.is_dir());
+            (!is_dir, entry.file_name().to_string_lossy().into_owned())
+        })
+        .collect();
+    entries.sort();
+    let base = request_path.trim_end_matches('/');
+    let title = escape_html(&format!("{base}/"));
+    let mut page = format!("<!doctype html><title>{title}</title><h1>{title}</h1><ul>");
+    for (is_file, name) in entries {
+        let slash = if is_file { "" } else { "/" };
+        let href = utf8_percent_encode(&name, LINK_ESCAPES);
+        let _ = write!(
+            page,
+            "<li><a href=\"{base}/{href}{slash}\">{}{slash}</a></li>",
+            escape_html(&name)
+        );
+    }
+    page.push_str("</ul>");
+    Ok(page)
+}
+
+/// What a request is answered with: a status, the body and its content type, and the file it
+/// came from
+struct Reply {
+    status: StatusCode,
+    content_type: String,
+    body: Bytes,
+    file: Option<PathBuf>,
+}
+
+impl Reply {
+    fn text(status: StatusCode, message: &str) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8".to_string(),
+            body: Bytes::from(format!("{message}\n")),
+            file: None,
+        }
+    }
+
+    async fn file(path: PathBuf) -> Self {
+        match tokio::fs::read(&path).await {
+            Ok(body) => Self {
+                status: StatusCode::OK,
+                content_type: mime_guess::from_path(&path)
+                    .first_or_octet_stream()
+                    .to_string(),
+                body: Bytes::from(body),
+                file: Some(path),
+            },
+            Err(e) => Self::text(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+        }
+    }
+}
+
+async fn reply(shared: &Shared, method: &Method, request_path: &str) -> Reply {
+    if method != Method::GET && method != Method::HEAD {
+        return Reply::text(StatusCode::METHOD_NOT_ALLOWED, "Method not allowed");
+    }
+    match resolve(&shared.root, request_path) {
+        Some(path) if path.is_file() => Reply::file(path).await,
+        Some(path) if path.is_dir() => {
+            let index = path.join(INDEX_FILE);
+            if index.is_file() {
+                return Reply::file(index).await;
+            }
+            match listing(&path, request_path) {
+                Ok(page) => Reply {
+                    status: StatusCode::OK,
+                    content_type: "text/html; charset=utf-8".to_string(),
+                    body: Bytes::from(page),
+                    file: Some(path),
+                },
+                Err(e) => Reply::text(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+            }
+        }
+        _ if shared.spa_fallback && shared.root.join(INDEX_FILE).is_file() => {
+            Reply::file(shared.root.join(INDEX_FILE)).await
+        }
+        _ => Reply::text(StatusCode::NOT_FOUND, "Not found"),
+    }
+}
+
+async fn serve(
+    request: Request<Incoming>,
+    remote: SocketAddr,
+    shared: Arc<Shared>,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let started = Instant::now();
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let reply = reply(&shared, &method, &path).await;
+
+    let size = reply.body.len();
+    let body = if method == Method::HEAD {
+        Bytes::new()
+    } else {
+        reply.body
+    };
+    let mut response = Response::new(Full::new(body));
+    *response.status_mut() = reply.status;
+    let headers = response.headers_mut();
+    if let Ok(content_type) = HeaderValue::from_str(&reply.content_type) {
+        headers.insert(header::CONTENT_TYPE, content_type);
+    }
+    headers.insert(header::CONTENT_LENGTH, HeaderValue::from(size));
+    // Fixtures change while testing; a cached copy would hide the change
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    if reply.status == StatusCode::METHOD_NOT_ALLOWED {
+        headers.insert(header::ALLOW, HeaderValue::from_static("GET, HEAD"));
+    }
+    if shared.cors {
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_ORIGIN,
+            HeaderValue::from_static("*"),
+        );
+    }
+
+    let status = reply.status.as_u16();
+    let level = if reply.status.is_success() {
+        LogLevel::Info
+    } else {
+        LogLevel::Warning
+    };
+    let message = format!("{remote} \"{method} {path}\" {status} {size}");
+    let details = json!({
+        "remoteAddr": remote.to_string(),
+        "method": method.as_str(),
+        "path": path,
+        "status": status,
+        "bytes": size,
+        "file": reply.file.map(|file| file.display().to_string()),
+    });
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    emit(&shared, level, message, elapsed_ms, details);
+    Ok(response)
+}
+
+/// Stops the server `server_id`. Returns false when it wasn't running.
+pub fn stop(server_id: &str) -> bool {
+    let Some(token) = SERVERS.lock().unwrap().remove(server_id) else {
+        return false;
+    };
+    token.cancel();
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StaticServerOptions, resolve, start, stop};
+    use crate::http_client::engine::LogEmitter;
+    use crate::http_client::response::LogEntry;
+    use std::fs;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    #[derive(Default)]
+    struct Collect(Mutex<Vec<LogEntry>>);
+
+    impl LogEmitter for Collect {
+        fn emit(&self, entry: LogEntry) {
+            self.0.lock().unwrap().push(entry);
+        }
+    }
+
+    async fn get(address: &str, request_line: &str) -> String {
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        let request =
+            format!("{request_line} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n");
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[test]
+    fn resolves_paths_inside_the_root_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        fs::create_dir(root.join("data")).unwrap();
+        fs::write(root.join("data/user one.json"), "{}").unwrap();
+
+        assert_eq!(
+            resolve(&root, "/data/user%20one.json"),
+            Some(root.join("data/user one.json"))
+        );
+        assert_eq!(resolve(&root, "/"), Some(root.clone()));
+        assert_eq!(resolve(&root, "/data/../../etc/passwd"), None);
+        assert_eq!(resolve(&root, "/%2e%2e/secret"), None);
+        assert_eq!(resolve(&root, "/missing.json"), None);
+    }
+
+    #[tokio::test]
+    async fn serves_files_and_logs_each_request() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("index.html"), "<h1>app</h1>").unwrap();
+        fs::create_dir(dir.path().join("fixtures")).unwrap();
+        fs::write(dir.path().join("fixtures/user.json"), r#"{"id":1}"#).unwrap();
+
+        let log = Arc::new(Collect::default());
+        let options = StaticServerOptions {
+            server_id: "fixtures".to_string(),
+            root: dir.path().display().to_string(),
+            spa_fallback: Some(true),
+            cors: Some(true),
+            ..StaticServerOptions::default()
+        };
+        let info = start(options, log.clone()).await.unwrap();
+
+        let response = get(&info.address, "GET /fixtures/user.json").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        assert!(response.contains("content-type: application/json"));
+        assert!(response.contains("access-control-allow-origin: *"));
+        assert!(response.ends_with(r#"{"id":1}"#));
+
+        let response = get(&info.address, "HEAD /fixtures/user.json").await;
+        assert!(response.contains("content-length: 8"));
+        assert!(response.ends_with("\r\n\r\n"));
+
+        let response = get(&info.address, "GET /fixtures/").await;
+        assert!(response.contains(r#"<a href="/fixtures/user.json">user.json</a>"#));
+
+        // Client-side routes get the app
+        let response = get(&info.address, "GET /users/7").await;
+        assert!(response.ends_with("<h1>app</h1>"));
+
+        let response = get(&info.address, "DELETE /fixtures/user.json").await;
+        assert!(response.starts_with("HTTP/1.1 405"));
+        assert!(response.contains("allow: GET, HEAD"));
+
+        let entries = log.0.lock().unwrap();
+        assert_eq!(entries.len(), 5);
+        assert!(entries.iter().all(|entry| entry.request_id == "fixtures"));
+        assert!(
+            entries[0]
+                .message
+                .ends_with("\"GET /fixtures/user.json\" 200 8")
+        );
+        assert_eq!(entries[4].details.as_ref().unwrap()["status"], 405);
+        drop(entries);
+
+        assert!(stop("fixtures"));
+        assert!(!stop("fixtures"));
+    }
+}
@@ -0,0 +1,277 @@
+//! TLS handshakes without HTTP, for inspecting the certificates a server presents.
+
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::hyper_engine::{
+    CertificateSummary, TlsSettings, build_tls_config, cipher_suite_name, summarize_certificate,
+};
+use crate::http_client::request_log;
+use rustls::pki_types::ServerName;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use tokio_rustls::client::TlsStream;
+
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TlsProbeOptions {
+    pub host: String,
+    /// Defaults to 443
+    pub port: Option<u16>,
+    /// Name sent as SNI and verified against the certificate, when not `host`
+    pub server_name: Option<String>,
+    /// Path to a custom root CA bundle (PEM format)
+    pub ca_path: Option<String>,
+    /// Seconds to wait for the connection and handshake. Defaults to 10.
+    pub connect_timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TlsProbe {
+    pub peer_addr: String,
+    pub tls_version: Option<String>,
+    /// IANA name of the negotiated cipher suite
+    pub cipher_suite: Option<String>,
+    /// Protocol the server picked of `h2` and `http/1.1`
+    pub alpn: Option<String>,
+    /// Why the chain failed verification. The certificates then come from a second handshake
+    /// that skipped it.
+    pub verification_error: Option<String>,
+    /// The server's chain, leaf first
+    pub certificates: Vec<CertificateSummary>,
+}
+
+/// Where the certificate chain to export comes from
+#[derive(Debug, Deserialize, Clone)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum ChainSource {
+    /// The chain logged for a recent request's handshake
+    Response { request_id: String },
+    /// The chain the server presents now
+    Host(TlsProbeOptions),
+}
+
+/// The PEM certificates of the chain, leaf first
+pub async fn certificate_chain(source: &ChainSource) -> Result<Vec<String>, AppError> {
+    let pems = match source {
+        ChainSource::Response { request_id } => request_log::peer_certificates(request_id)?,
+        ChainSource::Host(options) => inspect(options)
+            .await?
+            .certificates
+            .iter()
+            .map(|summary| summary.pem().to_string())
+            .collect(),
+    };
+    if pems.is_empty() {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            "The server presented no certificates",
+        ));
+    }
+    Ok(pems)
+}
+
+/// Writes the chain to `path` as one bundle, or with `separate_files`, each certificate to
+/// its own file named after `path` and its position in the chain. Returns the paths written.
+pub fn write_chain(
+    path: &Path,
+    pems: &[String],
+    separate_files: bool,
+) -> Result<Vec<PathBuf>, AppError> {
+    let files = if separate_files {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let extension = path
+            .extension()
+            .map_or("pem".into(), |extension| extension.to_string_lossy());
+        pems.iter()
+            .enumerate()
+            .map(|(index, pem)| {
+                let name = format!("{stem}-{index}.{extension}");
+                (path.with_file_name(name), pem.clone())
+            })
+            .collect()
+    } else {
+        let bundle: String = pems
+            .iter()
+            .map(|pem| format!("{}\n", pem.trim_end()))
+            .collect();
+        vec![(path.to_path_buf(), bundle)]
+    };
+    for (file, pem) in &files {
+        fs::write(file, pem)?;
+    }
+    Ok(files.into_iter().map(|(file, _)| file).collect())
+}
+
+/// Handshakes with the server as `options` asks and summarizes what it negotiated and
+/// presented. Chains that fail verification are still summarized.
+pub async fn inspect(options: &TlsProbeOptions) -> Result<TlsProbe, AppError> {
+    let limit = options
+        .connect_timeout_secs
+        .map_or(DEFAULT_CONNECT_TIMEOUT, Duration::from_secs);
+    let port = options.port.unwrap_or(443);
+    let name = options
+        .server_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .unwrap_or(&options.host)
+        .to_string();
+    let server_name = ServerName::try_from(name.clone()).map_err(|e| {
+        AppError::new(
+            ErrorKind::BadRequest,
+            format!("Invalid server name '{name}': {e}"),
+        )
+    })?;
+    let probe = async {
+        let stream = connect(options, port).await?;
+        match tls_connector(options, true)?
+            .connect(server_name.clone(), stream)
+            .await
+        {
+            Ok(stream) => Ok(summarize(&stream, None)),
+            Err(err) if untrusted(&err) => {
+                let stream = connect(options, port).await?;
+                let stream = tls_connector(options, false)?
+                    .connect(server_name, stream)
+                    .await
+                    .map_err(handshake_failed)?;
+                Ok(summarize(&stream, Some(err.to_string())))
+            }
+            Err(err) => Err(handshake_failed(err)),
+        }
+    };
+    tokio::time::timeout(limit, probe).await.map_err(|_| {
+        AppError::new(
+            ErrorKind::Timeout,
+            format!("Timed out connecting to {}:{port}", options.host),
+        )
+    })?
+}
+
+async fn connect(options: &TlsProbeOptions, port: u16) -> Result<TcpStream, AppError> {
+    TcpStream::connect((options.host.as_str(), port))
+        .await
+        .map_err(|e| {
+            let message = format!("Failed to connect to {}:{port}: {e}", options.host);
+            AppError::new(AppError::from(e).kind, message)
+        })
+}
+
+/// Offers `h2` and `http/1.1`, to learn which the server prefers
+fn tls_connector(options: &TlsProbeOptions, verify: bool) -> Result<TlsConnector, AppError> {
+    let mut config = build_tls_config(TlsSettings {
+        disable_verification: !verify,
+        custom_ca: options.ca_path.as_deref(),
+        ..TlsSettings::default()
+    })?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(TlsConnector::from(Arc::new(config)))
+}
+
+/// Whether the handshake failed on the server's certificate, rather than the connection
+fn untrusted(err: &io::Error) -> bool {
+    err.get_ref()
+        .and_then(|inner| inner.downcast_ref::<rustls::Error>())
+        .is_some_and(|inner| matches!(inner, rustls::Error::InvalidCertificate(_)))
+}
+
+fn handshake_failed(err: io::Error) -> AppError {
+    AppError::new(ErrorKind::HttpError, format!("TLS handshake failed: {err}"))
+}
+
+fn summarize(stream: &TlsStream<TcpStream>, verification_error: Option<String>) -> TlsProbe {
+    let (tcp, conn) = stream.get_ref();
+    TlsProbe {
+        peer_addr: tcp
+            .peer_addr()
+            .map(|addr| addr.to_string())
+            .unwrap_or_default(),
+        tls_version: conn
+            .protocol_version()
+            .and_then(|version| version.as_str())
+            .map(str::to_string),
+        cipher_suite: conn
+            .negotiated_cipher_suite()
+            .map(|suite| cipher_suite_name(&suite)),
+        alpn: conn
+            .alpn_protocol()
+            .map(|proto| String::from_utf8_lossy(proto).to_string()),
+        verification_error,
+        certificates: conn
+            .peer_certificates()
+            .unwrap_or_default()
+            .iter()
+            .enumerate()
+            .map(|(index, cert)| summarize_certificate(index, cert))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TlsProbeOptions, inspect, write_chain};
+    use crate::errors::ErrorKind;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn fails_against_plain_servers() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let _ = stream.write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n").await;
+        });
+
+        let err = inspect(&TlsProbeOptions {
+            host: "127.0.0.1".to_string(),
+            port: Some(port),
+            server_name: Some("localhost".to_string()),
+            ca_path: None,
+            connect_timeout_secs: Some(5),
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::HttpError);
+        assert!(err.message.starts_with("TLS handshake failed"));
+    }
+
+    #[test]
+    fn writes_bundles_or_separate_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let pems = ["-----LEAF-----\n".to_string(), "-----CA-----".to_string()];
+
+        let bundle = dir.path().join("chain.pem");
+        let written = write_chain(&bundle, &pems, false).unwrap();
+        assert_eq!(written, [bundle.as_path()]);
+        assert_eq!(
+            std::fs::read_to_string(&bundle).unwrap(),
+            "-----LEAF-----\n-----CA-----\n"
+        );
+
+        let written = write_chain(&dir.path().join("example.crt"), &pems, true).unwrap();
+        assert_eq!(
+            written,
+            [
+                dir.path().join("example-0.crt"),
+                dir.path().join("example-1.crt")
+            ]
+        );
+        assert_eq!(
+            std::fs::read_to_string(&written[1]).unwrap(),
+            "-----CA-----"
+        );
+    }
+}
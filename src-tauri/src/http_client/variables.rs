@@ -0,0 +1,606 @@
+//! `{{name}}` placeholders, substituted into a request by the engine just before it's sent.
+//! Secret values are sent from the frontend as references and resolved here, so they never
+//! pass through the webview in plaintext on their way into a request. A secure environment
+//! variable is read from its collection file, or from the value staged with
+//! [`stage_secure_value`] when it's been edited since. `{{secret:name}}` is the secret stored
+//! under `name` in the OS keyring, and placeholders naming no variable may name a
+//! [generator](crate::http_client::generators) instead.
+
+use crate::app_data::keyring_secrets;
+use crate::app_data::load_app_data;
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::request::{HookStep, MultipartPart, Request};
+use crate::http_client::{aws_secrets, dotenv, generators};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use tauri::AppHandle;
+
+/// A secure environment variable: its collection, environment and variable ids
+type SecureVariableKey = (String, String, String);
+
+/// Secure environment variable values as last edited, which the collection file may not hold
+/// yet
+static STAGED: LazyLock<Mutex<HashMap<SecureVariableKey, String>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Where a secret value is kept
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(
+    tag = "source",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum SecretRef {
+    /// A secure variable of a collection's environment: its staged value, or else the one
+    /// decrypted from the collection file
+    Environment {
+        collection_id: String,
+        environment_id: String,
+        variable_id: String,
+    },
+    /// A secret stored in the OS keyring under `name`
+    Keyring { name: String },
+    /// A secret in AWS Secrets Manager, or one key of it when it holds a JSON object. The
+    /// region and profile default to those of the AWS environment.
+    AwsSecretsManager {
+        secret_id: String,
+        json_key: Option<String>,
+        region: Option<String>,
+        profile: Option<String>,
+    },
+    /// A parameter in AWS SSM Parameter Store, decrypted if it's a `SecureString`
+    AwsSsm {
+        name: String,
+        region: Option<String>,
+        profile: Option<String>,
+    },
+    /// A variable of the app's process environment
+    ProcessEnv { name: String },
+    /// A variable defined in the `.env` file at `path`
+    Dotenv { path: String, name: String },
+}
+
+/// Prefix of placeholders naming a keyring secret, e.g. `{{secret:prod-api-key}}`
+const KEYRING_PREFIX: &str = "secret:";
+
+/// Value of a request variable: given as it is, or a reference to a secret
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum VariableValue {
+    Text(String),
+    Secret {
+        secret: SecretRef,
+    },
+    /// A secret's value, once resolved. Masked in the request's logs.
+    #[serde(skip_deserializing)]
+    Resolved(String),
+}
+
+/// Value of a placeholder's name, if it has one
+type Lookup<'a> = dyn FnMut(&str) -> Option<String> + 'a;
+
+/// Replaces `{{name}}` placeholders with the values `lookup` gives their names, leaving
+/// unknown names as they are
+fn render(text: &str, lookup: &mut Lookup) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let value = after
+            .find("}}")
+            .and_then(|end| lookup(&after[..end]).map(|value| (value, end)));
+        match value {
+            Some((value, end)) => {
+                out.push_str(&value);
+                rest = &after[end + 2..];
+            }
+            None => {
+                out.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Looks placeholder names up among a data row's columns
+fn row_lookup(row: &[(String, String)]) -> impl FnMut(&str) -> Option<String> + '_ {
+    |name| {
+        row.iter()
+            .find(|(column, _)| column == name)
+            .map(|(_, value)| value.clone())
+    }
+}
+
+fn render_value(value: &mut Value, lookup: &mut Lookup) {
+    match value {
+        Value::String(text) => *text = render(text, lookup),
+        Value::Array(items) => items.iter_mut().for_each(|item| render_value(item, lookup)),
+        Value::Object(object) => object
+            .values_mut()
+            .for_each(|item| render_value(item, lookup)),
+        _ => {}
+    }
+}
+
+/// The text fields of a pre-request hook step, which are templates
+fn step_templates(step: &mut HookStep) -> Vec<&mut String> {
+    match step {
+        HookStep::Set { value, .. }
+        | HookStep::SetHeader { value, .. }
+        | HookStep::SetQueryParam { value, .. } => vec![value],
+        HookStep::Hash { input, .. } => vec![input],
+        HookStep::Hmac { key, input, .. } => vec![key, input],
+        HookStep::Timestamp { .. } | HookStep::Nonce { .. } | HookStep::RemoveHeader { .. } => {
+            Vec::new()
+        }
+    }
+}
+
+/// Substitutes into the request's URL, query, headers, body and pre-request hook. Bodies that
+/// aren't UTF-8 are sent as they are.
+fn render_request(request: &mut Request, lookup: &mut Lookup) {
+    request.url = render(&request.url, lookup);
+    for param in request.query_params.iter_mut().flatten() {
+        param.name = render(&param.name, lookup);
+        param.value = render(&param.value, lookup);
+    }
+    for (name, value) in request.headers.iter_mut().flatten() {
+        *name = render(name, lookup);
+        *value = render(value, lookup);
+    }
+    if let Some(body) = request.body.as_mut()
+        && let Ok(text) = std::str::from_utf8(body)
+    {
+        *body = render(text, lookup).into_bytes();
+    }
+    for part in request.multipart_parts.iter_mut().flatten() {
+        if let MultipartPart::Text { value, .. } = part {
+            *value = render(value, lookup);
+        }
+    }
+    if let Some(graphql) = request.graphql.as_mut() {
+        graphql.query = render(&graphql.query, lookup);
+        if let Some(variables) = graphql.variables.as_mut() {
+            render_value(variables, lookup);
+        }
+    }
+    if let Some(soap) = request.soap.as_mut() {
+        for (_, value) in soap.parameters.iter_mut() {
+            render_value(value, lookup);
+        }
+        for template in soap.soap_action.iter_mut().chain(soap.headers.iter_mut()) {
+            *template = render(template, lookup);
+        }
+    }
+    for step in request.pre_request.iter_mut().flatten() {
+        for template in step_templates(step) {
+            *template = render(template, lookup);
+        }
+    }
+}
+
+/// Substitutes into the paths of the files the request reads and writes. Only a data run's
+/// rows choose them; variables don't, so an environment can't point a request at any file.
+fn render_paths(request: &mut Request, lookup: &mut Lookup) {
+    for part in request.multipart_parts.iter_mut().flatten() {
+        if let MultipartPart::File { file_path, .. } = part {
+            *file_path = render(file_path, lookup);
+        }
+    }
+    for path in [&mut request.body_file_path, &mut request.download_to_path]
+        .into_iter()
+        .flatten()
+    {
+        *path = render(path, lookup);
+    }
+}
+
+/// Returns `request` with the row's values substituted into it, file paths included
+pub fn apply(request: &Request, row: &[(String, String)]) -> Request {
+    let mut request = request.clone();
+    render_request(&mut request, &mut row_lookup(row));
+    render_paths(&mut request, &mut row_lookup(row));
+    request
+}
+
+/// Substitutes the request's variables and generated values into it. Returns the values of
+/// its secrets, which its logs mask.
+pub fn apply_variables(request: &mut Request) -> Result<Vec<String>, AppError> {
+    let variables = request.variables.take().unwrap_or_default();
+    let mut secrets = Vec::new();
+    for (name, value) in &variables {
+        match value {
+            VariableValue::Text(_) => {}
+            VariableValue::Resolved(secret) => secrets.push(secret.clone()),
+            VariableValue::Secret { .. } => {
+                return Err(AppError::new(
+                    ErrorKind::BadRequest,
+                    format!("Secret variable '{name}' wasn't resolved"),
+                ));
+            }
+        }
+    }
+
+    let now = Utc::now();
+    let mut failure = None;
+    render_request(request, &mut |name| match variables.get(name) {
+        Some(VariableValue::Text(value) | VariableValue::Resolved(value)) => Some(value.clone()),
+        _ => match generators::generate(name, now)? {
+            Ok(value) => Some(value),
+            Err(message) => {
+                failure.get_or_insert(message);
+                None
+            }
+        },
+    });
+    if let Some(message) = failure {
+        return Err(AppError::new(ErrorKind::BadRequest, message));
+    }
+
+    if !variables.is_empty() {
+        request.variables = Some(variables);
+    }
+    secrets.retain(|secret| !secret.is_empty());
+    Ok(secrets)
+}
+
+/// Keeps the value of a secure environment variable as it's edited, so requests referring to
+/// it use the edit before the collection is saved. `None` forgets it, e.g. once the variable
+/// isn't secure.
+pub fn stage_secure_value(
+    collection_id: String,
+    environment_id: String,
+    variable_id: String,
+    value: Option<String>,
+) {
+    let key = (collection_id, environment_id, variable_id);
+    let mut staged = STAGED.lock().unwrap();
+    match value {
+        Some(value) => staged.insert(key, value),
+        None => staged.remove(&key),
+    };
+}
+
+/// Reads the value of a secure environment variable from a collection file's data
+fn environment_secret(
+    collection: &Value,
+    environment_id: &str,
+    variable_id: &str,
+) -> Result<String, AppError> {
+    let escape = |id: &str| id.replace('~', "~0").replace('/', "~1");
+    let pointer = format!(
+        "/content/environments/{}/variables/{}/value",
+        escape(environment_id),
+        escape(variable_id)
+    );
+    collection
+        .pointer(&pointer)
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| {
+            AppError::new(
+                ErrorKind::FileNotFound,
+                format!("Environment variable '{variable_id}' of '{environment_id}' doesn't exist"),
+            )
+        })
+}
+
+/// Placeholders in the request naming a keyring secret, e.g. `secret:prod-api-key`, that aren't
+/// the names of its variables
+fn keyring_placeholders(request: &mut Request) -> Vec<String> {
+    let variables = request.variables.take();
+    let mut found = Vec::new();
+    // A lookup that knows no names leaves the request as it is
+    render_request(request, &mut |name| {
+        let known = variables
+            .as_ref()
+            .is_some_and(|variables| variables.contains_key(name));
+        if name.starts_with(KEYRING_PREFIX) && !known && !found.iter().any(|n| n == name) {
+            found.push(name.to_string());
+        }
+        None
+    });
+    request.variables = variables;
+    found
+}
+
+/// Resolves the request's secret variables, and its `{{secret:name}}` placeholders, from where
+/// they're kept
+pub async fn resolve_secrets(app: &AppHandle, request: &mut Request) -> Result<(), AppError> {
+    for placeholder in keyring_placeholders(request) {
+        let name = placeholder[KEYRING_PREFIX.len()..].trim().to_string();
+        request.variables.get_or_insert_default().insert(
+            placeholder,
+            VariableValue::Secret {
+                secret: SecretRef::Keyring { name },
+            },
+        );
+    }
+
+    let mut collections: HashMap<String, Value> = HashMap::new();
+    let mut dotenv_files: HashMap<String, HashMap<String, String>> = HashMap::new();
+    for value in request.variables.iter_mut().flat_map(HashMap::values_mut) {
+        let VariableValue::Secret { secret } = value else {
+            continue;
+        };
+        let resolved = match secret {
+            SecretRef::Environment {
+                collection_id,
+                environment_id,
+                variable_id,
+            } => {
+                let key = (
+                    collection_id.clone(),
+                    environment_id.clone(),
+                    variable_id.clone(),
+                );
+                let staged = STAGED.lock().unwrap().get(&key).cloned();
+                match staged {
+                    Some(staged) => staged,
+                    None => {
+                        if !collections.contains_key(collection_id.as_str()) {
+                            let data =
+                                load_app_data(app, &format!("collections/{collection_id}.json"))?;
+                            collections.insert(collection_id.clone(), data);
+                        }
+                        environment_secret(
+                            &collections[collection_id.as_str()],
+                            environment_id,
+                            variable_id,
+                        )?
+                    }
+                }
+            }
+            SecretRef::Keyring { name } => keyring_secrets::load_secret(app, name)?,
+            SecretRef::AwsSecretsManager {
+                secret_id,
+                json_key,
+                region,
+                profile,
+            } => {
+                aws_secrets::secrets_manager_secret(
+                    secret_id,
+                    json_key.as_deref(),
+                    region.as_deref(),
+                    profile.as_deref(),
+                )
+                .await?
+            }
+            SecretRef::AwsSsm {
+                name,
+                region,
+                profile,
+            } => aws_secrets::ssm_parameter(name, region.as_deref(), profile.as_deref()).await?,
+            SecretRef::ProcessEnv { name } => std::env::var(name.as_str()).map_err(|_| {
+                AppError::new(
+                    ErrorKind::BadRequest,
+                    format!("Environment variable '{name}' isn't set"),
+                )
+            })?,
+            SecretRef::Dotenv { path, name } => {
+                if !dotenv_files.contains_key(path.as_str()) {
+                    dotenv_files.insert(path.clone(), dotenv::load(path)?);
+                }
+                dotenv_files[path.as_str()]
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| {
+                        AppError::new(
+                            ErrorKind::BadRequest,
+                            format!("'{path}' doesn't define '{name}'"),
+                        )
+                    })?
+            }
+        };
+        *value = VariableValue::Resolved(resolved);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        STAGED, VariableValue, apply, apply_variables, environment_secret, keyring_placeholders,
+        render, row_lookup, stage_secure_value,
+    };
+    use crate::http_client::request::{HookStep, QueryParam, Request};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn row(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn substitutes_known_placeholders_only() {
+        let values = row(&[("id", "7"), ("name", "Ada")]);
+        let substitute =
+            |text: &str, values: &[(String, String)]| render(text, &mut row_lookup(values));
+        assert_eq!(
+            substitute("/users/{{id}}?q={{name}}&t={{token}}", &values),
+            "/users/7?q=Ada&t={{token}}"
+        );
+        assert_eq!(substitute("{{ {{id}} }}", &values), "{{ 7 }}");
+        assert_eq!(substitute("open {{id", &values), "open {{id");
+    }
+
+    #[test]
+    fn applies_a_row_to_each_part_of_the_request() {
+        let request = Request {
+            url: "https://api.example.com/users/{{id}}".to_string(),
+            query_params: Some(vec![QueryParam {
+                name: "q".to_string(),
+                value: "{{name}}".to_string(),
+                encode: true,
+            }]),
+            headers: Some(vec![("X-User".to_string(), "{{name}}".to_string())]),
+            body: Some(br#"{"id": {{id}}}"#.to_vec()),
+            download_to_path: Some("/tmp/{{id}}.json".to_string()),
+            ..Request::default()
+        };
+        let applied = apply(&request, &row(&[("id", "7"), ("name", "Ada")]));
+        assert_eq!(applied.url, "https://api.example.com/users/7");
+        assert_eq!(applied.query_params.unwrap()[0].value, "Ada");
+        assert_eq!(applied.headers.unwrap()[0].1, "Ada");
+        assert_eq!(applied.body.unwrap(), br#"{"id": 7}"#);
+        assert_eq!(applied.download_to_path.as_deref(), Some("/tmp/7.json"));
+    }
+
+    #[test]
+    fn substitutes_variables_and_reports_secrets() {
+        let mut request = Request {
+            url: "https://{{host}}/orders".to_string(),
+            headers: Some(vec![(
+                "Authorization".to_string(),
+                "Bearer {{token}}".to_string(),
+            )]),
+            pre_request: Some(
+                serde_json::from_value(json!([
+                    {"type": "hmac", "name": "sig", "algorithm": "sha256", "key": "{{token}}", "input": "{{ts}}"}
+                ]))
+                .unwrap(),
+            ),
+            variables: Some(HashMap::from([
+                ("host".to_string(), VariableValue::Text("api.example.com".to_string())),
+                ("token".to_string(), VariableValue::Resolved("s3cret".to_string())),
+            ])),
+            ..Request::default()
+        };
+        let secrets = apply_variables(&mut request).unwrap();
+        assert_eq!(secrets, ["s3cret"]);
+        assert_eq!(request.url, "https://api.example.com/orders");
+        assert_eq!(request.headers.unwrap()[0].1, "Bearer s3cret");
+        let Some(HookStep::Hmac { key, input, .. }) = request.pre_request.unwrap().pop() else {
+            panic!("expected the HMAC step");
+        };
+        assert_eq!((key.as_str(), input.as_str()), ("s3cret", "{{ts}}"));
+
+        let mut unresolved = Request {
+            variables: Some(
+                serde_json::from_value(json!({
+                    "token": {"secret": {"source": "keyring", "name": "api-token"}}
+                }))
+                .unwrap(),
+            ),
+            ..Request::default()
+        };
+        assert!(apply_variables(&mut unresolved).is_err());
+    }
+
+    #[test]
+    fn masks_resolved_secrets_and_leaves_file_paths_alone() {
+        let mut request = Request {
+            url: "https://api.example.com/{{id}}".to_string(),
+            body: Some(b"{{password}}".to_vec()),
+            body_file_path: Some("/tmp/{{id}}.json".to_string()),
+            download_to_path: Some("{{dir}}/out.json".to_string()),
+            variables: Some(HashMap::from([
+                ("id".to_string(), VariableValue::Text("7".to_string())),
+                ("dir".to_string(), VariableValue::Text("/etc".to_string())),
+                (
+                    "password".to_string(),
+                    VariableValue::Resolved("hunter2".to_string()),
+                ),
+            ])),
+            ..Request::default()
+        };
+        assert_eq!(apply_variables(&mut request).unwrap(), ["hunter2"]);
+        assert_eq!(request.url, "https://api.example.com/7");
+        assert_eq!(request.body.unwrap(), b"hunter2");
+        assert_eq!(request.body_file_path.as_deref(), Some("/tmp/{{id}}.json"));
+        assert_eq!(
+            request.download_to_path.as_deref(),
+            Some("{{dir}}/out.json")
+        );
+    }
+
+    #[test]
+    fn generates_values_for_unknown_names() {
+        let mut request = Request {
+            url: "https://api.example.com/{{uuid}}?n={{randomInt 3 3}}&t={{token}}".to_string(),
+            ..Request::default()
+        };
+        assert!(apply_variables(&mut request).unwrap().is_empty());
+        let (path, query) = request.url.split_once('?').unwrap();
+        assert_eq!(path.len(), "https://api.example.com/".len() + 36);
+        assert_eq!(query, "n=3&t={{token}}");
+
+        let mut invalid = Request {
+            url: "https://api.example.com/?n={{randomInt 9 1}}".to_string(),
+            ..Request::default()
+        };
+        assert!(apply_variables(&mut invalid).is_err());
+    }
+
+    #[test]
+    fn finds_keyring_placeholders() {
+        let mut request = Request {
+            url: "https://api.example.com/{{secret:tenant}}".to_string(),
+            headers: Some(vec![
+                ("X-Key".to_string(), "{{secret:prod-api-key}}".to_string()),
+                (
+                    "X-Other".to_string(),
+                    "{{secret:tenant}} {{secret:shadowed}}".to_string(),
+                ),
+            ]),
+            body: Some(b"{{secretive}}".to_vec()),
+            variables: Some(HashMap::from([(
+                "secret:shadowed".to_string(),
+                VariableValue::Text("plain".to_string()),
+            )])),
+            ..Request::default()
+        };
+        assert_eq!(
+            keyring_placeholders(&mut request),
+            ["secret:tenant", "secret:prod-api-key"]
+        );
+        assert_eq!(request.url, "https://api.example.com/{{secret:tenant}}");
+        assert!(request.variables.is_some());
+    }
+
+    #[test]
+    fn reads_secure_environment_variables() {
+        let collection = json!({"content": {"environments": {"dev": {"variables": {
+            "v1": {"id": "v1", "name": "token", "value": "s3cret", "secure": true}
+        }}}}});
+        assert_eq!(
+            environment_secret(&collection, "dev", "v1").unwrap(),
+            "s3cret"
+        );
+        assert!(environment_secret(&collection, "dev", "v2").is_err());
+    }
+
+    #[test]
+    fn stages_secure_values_until_forgotten() {
+        let ids = || {
+            (
+                "col-staged".to_string(),
+                "env".to_string(),
+                "v1".to_string(),
+            )
+        };
+        let (collection_id, environment_id, variable_id) = ids();
+        stage_secure_value(
+            collection_id,
+            environment_id,
+            variable_id,
+            Some("unsaved".to_string()),
+        );
+        assert_eq!(
+            STAGED.lock().unwrap().get(&ids()).map(String::as_str),
+            Some("unsaved")
+        );
+        let (collection_id, environment_id, variable_id) = ids();
+        stage_secure_value(collection_id, environment_id, variable_id, None);
+        assert!(!STAGED.lock().unwrap().contains_key(&ids()));
+    }
+}
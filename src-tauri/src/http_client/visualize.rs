@@ -0,0 +1,225 @@
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::spool;
+use jaq_core::load::{Arena, File, Loader};
+use jaq_core::{Compiler, Ctx, RcIter};
+use jaq_json::Val;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+
+fn default_max_rows() -> usize {
+    10_000
+}
+
+/// A jq transform over a response body, given inline or as the file a large response was
+/// spooled to
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct VisualizeRequest {
+    pub body: Option<String>,
+    pub file_path: Option<String>,
+    /// jq filter, e.g. `.items[] | {name, latency: .timings.total}`
+    pub transform: String,
+    #[serde(default = "default_max_rows")]
+    pub max_rows: usize,
+}
+
+/// Chart-ready result of a transform
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Visualization {
+    /// The transform produced numbers, e.g. `[.samples[].ms]`. Null is a gap.
+    #[serde(rename_all = "camelCase")]
+    Series {
+        values: Vec<Option<f64>>,
+        truncated: bool,
+    },
+    /// The transform produced objects, one row each, with a column per key of any of them,
+    /// sorted by name. Anything else is one `value` column.
+    #[serde(rename_all = "camelCase")]
+    Table {
+        columns: Vec<String>,
+        rows: Vec<Vec<Value>>,
+        truncated: bool,
+    },
+}
+
+/// Runs a jq filter over `input` and returns up to `limit` outputs, plus whether there were more.
+fn run_transform(
+    transform: &str,
+    input: Value,
+    limit: usize,
+) -> Result<(Vec<Value>, bool), AppError> {
+    let invalid = |detail: String| {
+        AppError::new(
+            ErrorKind::BadRequest,
+            format!("Invalid transform '{transform}': {detail}"),
+        )
+    };
+    let loader = Loader::new(jaq_std::defs().chain(jaq_json::defs()));
+    let arena = Arena::default();
+    let modules = loader
+        .load(
+            &arena,
+            File {
+                code: transform,
+                path: (),
+            },
+        )
+        .map_err(|errors| invalid(format!("{errors:?}")))?;
+    let filter = Compiler::default()
+        .with_funs(jaq_std::funs().chain(jaq_json::funs()))
+        .compile(modules)
+        .map_err(|errors| invalid(format!("{errors:?}")))?;
+
+    let inputs = RcIter::new(core::iter::empty());
+    let mut outputs = Vec::new();
+    for output in filter.run((Ctx::new([], &inputs), Val::from(input))) {
+        if outputs.len() == limit {
+            return Ok((outputs, true));
+        }
+        let value = output
+            .map_err(|e| AppError::new(ErrorKind::BadRequest, format!("Transform failed: {e}")))?;
+        outputs.push(Value::from(value));
+    }
+    Ok((outputs, false))
+}
+
+/// Shapes transform outputs into a series or table. A single array output is taken as the
+/// list of rows, so `[...]` and `.[]` transforms give the same result.
+fn shape(mut outputs: Vec<Value>, limit: usize, mut truncated: bool) -> Visualization {
+    if let [Value::Array(_)] = outputs.as_slice() {
+        let Some(Value::Array(items)) = outputs.pop() else {
+            unreachable!()
+        };
+        truncated |= items.len() > limit;
+        outputs = items.into_iter().take(limit).collect();
+    }
+
+    let numeric = outputs.iter().any(|value| value.is_number())
+        && outputs
+            .iter()
+            .all(|value| value.is_number() || value.is_null());
+    if numeric {
+        return Visualization::Series {
+            values: outputs.iter().map(Value::as_f64).collect(),
+            truncated,
+        };
+    }
+
+    if outputs.is_empty() || !outputs.iter().all(Value::is_object) {
+        return Visualization::Table {
+            columns: vec!["value".to_string()],
+            rows: outputs.into_iter().map(|value| vec![value]).collect(),
+            truncated,
+        };
+    }
+    let columns: Vec<String> = outputs
+        .iter()
+        .filter_map(Value::as_object)
+        .flat_map(|object| object.keys().cloned())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let rows = outputs
+        .into_iter()
+        .map(|value| {
+            let mut object = match value {
+                Value::Object(object) => object,
+                _ => Map::new(),
+            };
+            columns
+                .iter()
+                .map(|column| object.remove(column).unwrap_or(Value::Null))
+                .collect()
+        })
+        .collect();
+    Visualization::Table {
+        columns,
+        rows,
+        truncated,
+    }
+}
+
+/// Runs `request.transform` over a JSON response body and returns at most `max_rows` rows of it.
+pub fn visualize(request: VisualizeRequest) -> Result<Visualization, AppError> {
+    let input: Value = match (&request.body, &request.file_path) {
+        (Some(body), _) => serde_json::from_str(body)?,
+        (None, Some(path)) => serde_json::from_reader(spool::open(std::path::Path::new(path))?)?,
+        (None, None) => {
+            return Err(AppError::new(
+                ErrorKind::BadRequest,
+                "A response body or file path is required",
+            ));
+        }
+    };
+    let limit = request.max_rows.max(1);
+    let (outputs, truncated) = run_transform(&request.transform, input, limit)?;
+    Ok(shape(outputs, limit, truncated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Visualization, VisualizeRequest, visualize};
+    use serde_json::json;
+
+    fn run(body: serde_json::Value, transform: &str, max_rows: usize) -> Visualization {
+        visualize(VisualizeRequest {
+            body: Some(body.to_string()),
+            file_path: None,
+            transform: transform.to_string(),
+            max_rows,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn numbers_become_a_series() {
+        let body = json!({ "samples": [{ "ms": 12 }, { "ms": null }, { "ms": 7.5 }] });
+        let expected = Visualization::Series {
+            values: vec![Some(12.0), None, Some(7.5)],
+            truncated: false,
+        };
+        assert_eq!(run(body.clone(), ".samples[].ms", 100), expected);
+        assert_eq!(run(body, "[.samples[].ms]", 100), expected);
+    }
+
+    #[test]
+    fn objects_become_a_table() {
+        let body = json!([
+            { "name": "a", "latency": 10 },
+            { "name": "b", "status": 500 },
+            { "name": "c", "latency": 30 }
+        ]);
+        assert_eq!(
+            run(body, ".[] | {name, latency, status}", 2),
+            Visualization::Table {
+                columns: vec!["latency".into(), "name".into(), "status".into()],
+                rows: vec![
+                    vec![json!(10), json!("a"), json!(null)],
+                    vec![json!(null), json!("b"), json!(500)],
+                ],
+                truncated: true,
+            }
+        );
+        assert_eq!(
+            run(json!(["x", 1]), ".", 10),
+            Visualization::Table {
+                columns: vec!["value".into()],
+                rows: vec![vec![json!("x")], vec![json!(1)]],
+                truncated: false,
+            }
+        );
+    }
+
+    #[test]
+    fn reports_invalid_transforms() {
+        let request = VisualizeRequest {
+            body: Some("{}".to_string()),
+            file_path: None,
+            transform: ".[".to_string(),
+            max_rows: 10,
+        };
+        assert!(visualize(request).is_err());
+    }
+}
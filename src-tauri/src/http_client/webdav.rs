@@ -0,0 +1,191 @@
+use crate::errors::{AppError, ErrorKind};
+use roxmltree::{Document, Node};
+use serde::Serialize;
+
+const DAV: &str = "DAV:";
+
+/// A WebDAV `207 Multi-Status` body (RFC 4918 §13)
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Multistatus {
+    pub responses: Vec<DavResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Status of one resource
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DavResponse {
+    pub href: String,
+    /// Status of the whole resource (e.g. for a failed MOVE), when not reported per property
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    pub propstats: Vec<DavPropstat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Properties that share a status, e.g. all those found (200) or missing (404)
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DavPropstat {
+    pub status: Option<u16>,
+    pub properties: Vec<DavProperty>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DavProperty {
+    pub namespace: Option<String>,
+    pub name: String,
+    /// Text of the property, or its child elements as XML (e.g. `<D:collection/>`)
+    pub value: String,
+}
+
+fn is_dav(node: &Node, name: &str) -> bool {
+    node.is_element() && node.tag_name().name() == name && node.tag_name().namespace() == Some(DAV)
+}
+
+fn dav_child<'a, 'input>(node: &Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| is_dav(child, name))
+}
+
+fn child_text(node: &Node, name: &str) -> Option<String> {
+    dav_child(node, name)
+        .and_then(|child| child.text())
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+/// Status code of a `<D:status>HTTP/1.1 404 Not Found</D:status>` element
+fn status_code(node: &Node) -> Option<u16> {
+    child_text(node, "status")?
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()
+}
+
+fn property_value(node: &Node, source: &str) -> String {
+    let elements: Vec<Node> = node.children().filter(Node::is_element).collect();
+    match (elements.first(), elements.last()) {
+        (Some(first), Some(last)) => source[first.range().start..last.range().end].to_string(),
+        _ => node.text().unwrap_or_default().trim().to_string(),
+    }
+}
+
+fn parse_propstat(node: &Node, source: &str) -> DavPropstat {
+    let properties = dav_child(node, "prop")
+        .map(|prop| {
+            prop.children()
+                .filter(Node::is_element)
+                .map(|property| DavProperty {
+                    namespace: property.tag_name().namespace().map(str::to_string),
+                    name: property.tag_name().name().to_string(),
+                    value: property_value(&property, source),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    DavPropstat {
+        status: status_code(node),
+        properties,
+    }
+}
+
+/// Parses a multistatus response body.
+pub fn parse_multistatus(body: &[u8]) -> Result<Multistatus, AppError> {
+    let source = std::str::from_utf8(body).map_err(|e| {
+        AppError::new(
+            ErrorKind::BadRequest,
+            format!("Multistatus body is not UTF-8: {e}"),
+        )
+    })?;
+    let document = Document::parse(source).map_err(|e| {
+        AppError::new(
+            ErrorKind::BadRequest,
+            format!("Invalid multistatus XML: {e}"),
+        )
+    })?;
+    let root = document.root_element();
+    if !is_dav(&root, "multistatus") {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            "Body is not a DAV:multistatus document",
+        ));
+    }
+
+    let responses = root
+        .children()
+        .filter(|node| is_dav(node, "response"))
+        .map(|response| DavResponse {
+            href: child_text(&response, "href").unwrap_or_default(),
+            status: status_code(&response),
+            propstats: response
+                .children()
+                .filter(|node| is_dav(node, "propstat"))
+                .map(|propstat| parse_propstat(&propstat, source))
+                .collect(),
+            description: child_text(&response, "responsedescription"),
+        })
+        .collect();
+    Ok(Multistatus {
+        responses,
+        description: child_text(&root, "responsedescription"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_multistatus;
+
+    #[test]
+    fn parses_propfind_multistatus() {
+        let body = br#"<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:" xmlns:cs="http://calendarserver.org/ns/">
+  <d:response>
+    <d:href>/files/</d:href>
+    <d:propstat>
+      <d:prop>
+        <d:displayname>files</d:displayname>
+        <d:resourcetype><d:collection/></d:resourcetype>
+        <cs:getctag>42</cs:getctag>
+      </d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+    <d:propstat>
+      <d:prop><d:getcontentlength/></d:prop>
+      <d:status>HTTP/1.1 404 Not Found</d:status>
+    </d:propstat>
+  </d:response>
+  <d:response>
+    <d:href>/files/locked.txt</d:href>
+    <d:status>HTTP/1.1 423 Locked</d:status>
+    <d:responsedescription>Resource is locked</d:responsedescription>
+  </d:response>
+</d:multistatus>"#;
+
+        let multistatus = parse_multistatus(body).unwrap();
+        assert_eq!(multistatus.responses.len(), 2);
+
+        let files = &multistatus.responses[0];
+        assert_eq!(files.href, "/files/");
+        assert_eq!(files.propstats[0].status, Some(200));
+        let props = &files.propstats[0].properties;
+        assert_eq!(props[0].value, "files");
+        assert_eq!(props[1].value, "<d:collection/>");
+        assert_eq!(
+            props[2].namespace.as_deref(),
+            Some("http://calendarserver.org/ns/")
+        );
+        assert_eq!(files.propstats[1].status, Some(404));
+        assert_eq!(files.propstats[1].properties[0].name, "getcontentlength");
+
+        let locked = &multistatus.responses[1];
+        assert_eq!(locked.status, Some(423));
+        assert_eq!(locked.description.as_deref(), Some("Resource is locked"));
+
+        assert!(parse_multistatus(b"<html/>").is_err());
+    }
+}
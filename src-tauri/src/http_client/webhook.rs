@@ -0,0 +1,395 @@
+//! A temporary local listener that catches whatever is sent to it, for testing webhook
+//! integrations and OAuth callbacks. Every request is answered with the same configured
+//! response and passed on whole.
+
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::recording_proxy::header_pairs;
+use crate::http_client::self_signed;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Incoming;
+use hyper::http::{HeaderName, HeaderValue, Request, Response, StatusCode};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper_util::rt::TokioIo;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, LazyLock, Mutex};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::TlsAcceptor;
+use tokio_util::sync::CancellationToken;
+
+/// Most of a request body kept; the rest is read and dropped
+const MAX_CAPTURED_BODY: usize = 4 * 1024 * 1024;
+
+static LISTENERS: LazyLock<Mutex<HashMap<String, CancellationToken>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookOptions {
+    /// Names the listener for `stop`
+    pub listener_id: String,
+    /// Port to listen on. Defaults to an ephemeral one.
+    pub port: Option<u16>,
+    /// Address to listen on. Defaults to 127.0.0.1; any other exposes the listener to the
+    /// network.
+    pub bind_address: Option<String>,
+    /// Status every request is answered with. Defaults to 200.
+    pub response_status: Option<u16>,
+    #[serde(default)]
+    pub response_headers: Vec<(String, String)>,
+    pub response_body: Option<String>,
+    /// PEM certificate to serve HTTPS with, e.g. a generated self-signed one
+    pub cert_path: Option<String>,
+    /// PEM private key of `cert_path`
+    pub key_path: Option<String>,
+}
+
+/// A request the listener received
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CaughtRequest {
+    pub id: String,
+    /// Time the request arrived, ISO 8601
+    pub received_at: String,
+    /// Address of the client that sent it
+    pub remote_addr: String,
+    pub method: String,
+    pub path: String,
+    /// The query string's parameters, decoded
+    pub query: Vec<(String, String)>,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// Whether the body was longer than what was kept
+    pub truncated: bool,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum WebhookEvent {
+    Request {
+        request: CaughtRequest,
+    },
+    /// Always the last event of a listener
+    Stopped {
+        /// Why the listener stopped, when it wasn't stopped by `stop`
+        reason: Option<String>,
+    },
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookInfo {
+    pub listener_id: String,
+    /// The address listened on
+    pub address: String,
+    /// URL to give the sender, e.g. as a webhook or redirect URI. HTTPS when a certificate was
+    /// given.
+    pub url: String,
+}
+
+struct Shared {
+    tls: Option<TlsAcceptor>,
+    status: StatusCode,
+    headers: Vec<(HeaderName, HeaderValue)>,
+    body: Bytes,
+    on_event: Box<dyn Fn(WebhookEvent) + Send + Sync>,
+}
+
+/// Checks the configured certificate and response up front, so a bad header fails the start
+/// rather than every request
+fn prepare(
+    options: &WebhookOptions,
+    on_event: impl Fn(WebhookEvent) + Send + Sync + 'static,
+) -> Result<Shared, AppError> {
+    let tls = match (&options.cert_path, &options.key_path) {
+        (Some(cert_path), Some(key_path)) => Some(TlsAcceptor::from(Arc::new(
+            self_signed::server_config(cert_path, key_path)?,
+        ))),
+        (None, None) => None,
+        _ => {
+            return Err(AppError::new(
+                ErrorKind::BadRequest,
+                "HTTPS needs both a certificate and its key",
+            ));
+        }
+    };
+    let status = StatusCode::from_u16(options.response_status.unwrap_or(200)).map_err(|e| {
+        AppError::new(
+            ErrorKind::BadRequest,
+            format!("Invalid response status: {e}"),
+        )
+    })?;
+    let headers = options
+        .response_headers
+        .iter()
+        .map(|(name, value)| {
+            let invalid = |e: &dyn std::fmt::Display| {
+                AppError::new(
+                    ErrorKind::BadRequest,
+                    format!("Invalid response header '{name}': {e}"),
+                )
+            };
+            let name = HeaderName::from_bytes(name.trim().as_bytes()).map_err(|e| invalid(&e))?;
+            let value = HeaderValue::from_str(value).map_err(|e| invalid(&e))?;
+            Ok((name, value))
+        })
+        .collect::<Result<_, AppError>>()?;
+    Ok(Shared {
+        tls,
+        status,
+        headers,
+        body: Bytes::from(options.response_body.clone().unwrap_or_default()),
+        on_event: Box::new(on_event),
+    })
+}
+
+/// Starts listening as `options` asks, passing each request received to `on_event` until the
+/// listener is stopped.
+pub async fn start(
+    options: WebhookOptions,
+    on_event: impl Fn(WebhookEvent) + Send + Sync + 'static,
+) -> Result<WebhookInfo, AppError> {
+    let id = options.listener_id.clone();
+    if LISTENERS.lock().unwrap().contains_key(&id) {
+        return Err(AppError::new(
+            ErrorKind::BadRequest,
+            format!("Webhook listener '{id}' is already running"),
+        ));
+    }
+    let shared = Arc::new(prepare(&options, on_event)?);
+
+    let host = options.bind_address.as_deref().unwrap_or("127.0.0.1");
+    let listener = TcpListener::bind((host, options.port.unwrap_or(0)))
+        .await
+        .map_err(|e| {
+            let message = format!("Failed to listen on {host}: {e}");
+            AppError::new(AppError::from(e).kind, message)
+        })?;
+    let address = listener.local_addr()?;
+    let scheme = if shared.tls.is_some() {
+        "https"
+    } else {
+        "http"
+    };
+    let info = WebhookInfo {
+        listener_id: id.clone(),
+        address: address.to_string(),
+        url: format!("{scheme}://{address}/"),
+    };
+
+    let token = CancellationToken::new();
+    {
+        let mut listeners = LISTENERS.lock().unwrap();
+        if listeners.contains_key(&id) {
+            return Err(AppError::new(
+                ErrorKind::BadRequest,
+                format!("Webhook listener '{id}' is already running"),
+            ));
+        }
+        listeners.insert(id.clone(), token.clone());
+    }
+    tokio::spawn(accept_loop(id, listener, token, shared));
+    Ok(info)
+}
+
+async fn accept_loop(
+    id: String,
+    listener: TcpListener,
+    token: CancellationToken,
+    shared: Arc<Shared>,
+) {
+    let reason = loop {
+        tokio::select! {
+            _ = token.cancelled() => break None,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, remote)) => {
+                    tokio::spawn(serve_connection(stream, remote, token.clone(), shared.clone()));
+                }
+                Err(e) => break Some(e.to_string()),
+            }
+        }
+    };
+    // A listener stopped by `stop` is already gone, and its id may be in use again
+    if !token.is_cancelled() {
+        LISTENERS.lock().unwrap().remove(&id);
+    }
+    (shared.on_event)(WebhookEvent::Stopped { reason });
+}
+
+async fn serve_connection(
+    stream: TcpStream,
+    remote: SocketAddr,
+    token: CancellationToken,
+    shared: Arc<Shared>,
+) {
+    let Some(tls) = shared.tls.clone() else {
+        return serve_http(stream, remote, token, shared).await;
+    };
+    match tls.accept(stream).await {
+        Ok(stream) => serve_http(stream, remote, token, shared).await,
+        Err(e) => log::debug!("Webhook TLS handshake with {remote} failed: {e}"),
+    }
+}
+
+async fn serve_http(
+    stream: impl AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    remote: SocketAddr,
+    token: CancellationToken,
+    shared: Arc<Shared>,
+) {
+    let service = service_fn(move |request| catch(request, remote, shared.clone()));
+    let connection = http1::Builder::new().serve_connection(TokioIo::new(stream), service);
+    tokio::select! {
+        _ = token.cancelled() => {}
+        result = connection => {
+            if let Err(e) = result {
+                log::debug!("Webhook connection ended: {e}");
+            }
+        }
+    }
+}
+
+async fn catch(
+    request: Request<Incoming>,
+    remote: SocketAddr,
+    shared: Arc<Shared>,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let (parts, mut body) = request.into_parts();
+    let received_at = chrono::Utc::now().to_rfc3339();
+    let mut kept = Vec::new();
+    let mut truncated = false;
+    while let Some(frame) = body.frame().await {
+        let Ok(frame) = frame else {
+            // The sender gave up; there's no request to report
+            return Ok(Response::new(Full::default()));
+        };
+        if let Some(data) = frame.data_ref() {
+            let room = MAX_CAPTURED_BODY - kept.len();
+            truncated |= data.len() > room;
+            kept.extend_from_slice(&data[..data.len().min(room)]);
+        }
+    }
+    let query = parts
+        .uri
+        .query()
+        .and_then(|query| serde_urlencoded::from_str(query).ok())
+        .unwrap_or_default();
+    (shared.on_event)(WebhookEvent::Request {
+        request: CaughtRequest {
+            id: uuid::Uuid::new_v4().to_string(),
+            received_at,
+            remote_addr: remote.to_string(),
+            method: parts.method.to_string(),
+            path: parts.uri.path().to_string(),
+            query,
+            headers: header_pairs(&parts.headers),
+            body: kept,
+            truncated,
+        },
+    });
+
+    let mut response = Response::new(Full::new(shared.body.clone()));
+    *response.status_mut() = shared.status;
+    for (name, value) in &shared.headers {
+        response.headers_mut().append(name, value.clone());
+    }
+    Ok(response)
+}
+
+/// Stops the listener `listener_id`. Returns false when it wasn't running.
+pub fn stop(listener_id: &str) -> bool {
+    let Some(token) = LISTENERS.lock().unwrap().remove(listener_id) else {
+        return false;
+    };
+    token.cancel();
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{WebhookEvent, WebhookOptions, start, stop};
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    #[tokio::test]
+    async fn catches_requests_and_answers_as_configured() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let options = WebhookOptions {
+            listener_id: "hooks".to_string(),
+            response_status: Some(202),
+            response_headers: vec![("X-Caught".to_string(), "yes".to_string())],
+            response_body: Some("thanks".to_string()),
+            ..WebhookOptions::default()
+        };
+        let info = start(options, move |event| sink.lock().unwrap().push(event))
+            .await
+            .unwrap();
+        assert_eq!(info.url, format!("http://{}/", info.address));
+
+        let mut client = TcpStream::connect(&info.address).await.unwrap();
+        client
+            .write_all(
+                b"POST /hooks/github?event=push&tag=a%20b HTTP/1.1\r\nHost: localhost\r\nX-Hub-Signature: sha1=abc\r\nContent-Length: 11\r\nConnection: close\r\n\r\n{\"ref\":\"x\"}",
+            )
+            .await
+            .unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 202 Accepted"), "{response}");
+        assert!(response.to_lowercase().contains("x-caught: yes"));
+        assert!(response.ends_with("thanks"));
+
+        let WebhookEvent::Request { request } = events.lock().unwrap()[0].clone() else {
+            panic!("expected a request");
+        };
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/hooks/github");
+        assert_eq!(
+            request.query,
+            [
+                ("event".to_string(), "push".to_string()),
+                ("tag".to_string(), "a b".to_string())
+            ]
+        );
+        assert!(
+            request
+                .headers
+                .contains(&("x-hub-signature".to_string(), "sha1=abc".to_string()))
+        );
+        assert_eq!(request.body, br#"{"ref":"x"}"#);
+        assert!(!request.truncated);
+
+        assert!(stop("hooks"));
+        assert!(!stop("hooks"));
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_response_headers() {
+        let options = WebhookOptions {
+            listener_id: "bad".to_string(),
+            response_headers: vec![("Bad Name".to_string(), "x".to_string())],
+            ..WebhookOptions::default()
+        };
+        let error = start(options, |_| {}).await.unwrap_err();
+        assert!(
+            error
+                .message
+                .starts_with("Invalid response header 'Bad Name'")
+        );
+
+        let options = WebhookOptions {
+            listener_id: "half".to_string(),
+            cert_path: Some("localhost.pem".to_string()),
+            ..WebhookOptions::default()
+        };
+        let error = start(options, |_| {}).await.unwrap_err();
+        assert_eq!(error.message, "HTTPS needs both a certificate and its key");
+    }
+}
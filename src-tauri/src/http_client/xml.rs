@@ -0,0 +1,525 @@
+//! XML response bodies, e.g. `application/xml` or SOAP: pretty-printing, a node tree for the
+//! UI, and XPath 1.0 queries with the prefixes the body declares.
+
+use crate::errors::{AppError, ErrorKind};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fmt::Display;
+use sxd_document::dom::{ChildOfElement, ChildOfRoot, Document};
+use sxd_xpath::nodeset::Node;
+use sxd_xpath::{Context, Factory};
+
+const DEFAULT_INDENT: usize = 2;
+
+fn default_max_nodes() -> usize {
+    10_000
+}
+
+pub fn invalid_xml(e: impl Display) -> AppError {
+    AppError::new(ErrorKind::BadRequest, format!("Body isn't XML: {e}"))
+}
+
+/// An XML response body to parse, given inline or as the file a large response was spooled to
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct XmlRequest {
+    pub body: Option<String>,
+    pub file_path: Option<String>,
+    /// The response's `Content-Type`, whose `charset` a spooled body is decoded with
+    pub content_type: Option<String>,
+    /// Spaces per level of the pretty-printed body, 2 by default
+    pub indent: Option<usize>,
+    /// Nodes in the tree at most
+    #[serde(default = "default_max_nodes")]
+    pub max_nodes: usize,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct XmlAttribute {
+    /// Qualified name, e.g. `xsi:type`
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    pub value: String,
+}
+
+/// A node of the body. Text that's only whitespace is left out.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum XmlNode {
+    Element {
+        /// Qualified name, e.g. `soap:Envelope`
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        namespace: Option<String>,
+        attributes: Vec<XmlAttribute>,
+        children: Vec<XmlNode>,
+    },
+    Text {
+        text: String,
+    },
+    Comment {
+        text: String,
+    },
+    ProcessingInstruction {
+        target: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        value: Option<String>,
+    },
+}
+
+/// A parsed XML body
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct XmlDocument {
+    pub pretty: String,
+    /// The root element and any comments or processing instructions around it
+    pub nodes: Vec<XmlNode>,
+    /// Whether the tree was cut at `max_nodes`
+    pub truncated: bool,
+}
+
+enum Token<'a> {
+    Open(&'a str),
+    Close(&'a str),
+    /// A self-closing tag, comment, processing instruction or declaration
+    Markup(&'a str),
+    /// Character data, including CDATA sections
+    Text(&'a str),
+}
+
+/// Length of the markup `text` starts with, up to its closing `>`, skipping quoted values and
+/// bracketed declarations
+fn markup_len(text: &str) -> usize {
+    let mut quote = None;
+    let mut depth = 0usize;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '[') => depth += 1,
+            (None, ']') => depth = depth.saturating_sub(1),
+            (None, '>') if depth == 0 => return i + 1,
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+fn tokens(text: &str) -> Vec<Token<'_>> {
+    let mut out = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        if !rest.starts_with('<') {
+            let end = rest.find('<').unwrap_or(rest.len());
+            out.push(Token::Text(&rest[..end]));
+            rest = &rest[end..];
+            continue;
+        }
+        let delimited = |open: &str, close: &str| {
+            rest.strip_prefix(open)?
+                .find(close)
+                .map(|end| open.len() + end + close.len())
+        };
+        let len = delimited("<![CDATA[", "]]>")
+            .or_else(|| delimited("<!--", "-->"))
+            .or_else(|| delimited("<?", "?>"))
+            .unwrap_or_else(|| markup_len(rest));
+        let markup = &rest[..len];
+        out.push(if markup.starts_with("<![CDATA[") {
+            Token::Text(markup)
+        } else if markup.starts_with("</") {
+            Token::Close(markup)
+        } else if markup.starts_with("<!") || markup.starts_with("<?") || markup.ends_with("/>") {
+            Token::Markup(markup)
+        } else {
+            Token::Open(markup)
+        });
+        rest = &rest[len..];
+    }
+    out
+}
+
+/// Re-indents an XML body, one element per line. Elements holding only text stay on one line
+/// with their text as it is; other text is trimmed onto its own line.
+pub fn pretty_print(text: &str, indent: usize) -> Result<String, AppError> {
+    roxmltree::Document::parse(text).map_err(invalid_xml)?;
+    let tokens = tokens(text);
+    let mut out = String::with_capacity(text.len());
+    let mut depth = 0usize;
+    let line = |out: &mut String, depth: usize, text: &str| {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.extend(std::iter::repeat_n(' ', depth * indent));
+        out.push_str(text);
+    };
+    let mut i = 0;
+    while i < tokens.len() {
+        match tokens[i] {
+            Token::Text(text) if text.trim().is_empty() => {}
+            Token::Open(tag) => {
+                line(&mut out, depth, tag);
+                let texts = tokens[i + 1..]
+                    .iter()
+                    .take_while(|token| matches!(token, Token::Text(_)))
+                    .count();
+                if let Some(Token::Close(close)) = tokens.get(i + 1 + texts) {
+                    for token in &tokens[i + 1..i + 1 + texts] {
+                        if let Token::Text(text) = token {
+                            out.push_str(text);
+                        }
+                    }
+                    out.push_str(close);
+                    i += texts + 2;
+                    continue;
+                }
+                depth += 1;
+            }
+            Token::Close(tag) => {
+                depth = depth.saturating_sub(1);
+                line(&mut out, depth, tag);
+            }
+            Token::Markup(markup) => line(&mut out, depth, markup),
+            Token::Text(text) => line(&mut out, depth, text.trim()),
+        }
+        i += 1;
+    }
+    Ok(out)
+}
+
+fn qualified_name(node: roxmltree::Node, namespace: Option<&str>, local: &str) -> String {
+    match namespace.and_then(|uri| node.lookup_prefix(uri)) {
+        Some(prefix) if !prefix.is_empty() => format!("{prefix}:{local}"),
+        _ => local.to_string(),
+    }
+}
+
+/// Nodes a tree may still take
+struct Budget {
+    left: usize,
+    truncated: bool,
+}
+
+/// The node as a tree, within the budget. `None` when the budget is spent or the node is
+/// whitespace.
+fn tree_node(node: roxmltree::Node, budget: &mut Budget) -> Option<XmlNode> {
+    if node.is_text() && node.text().unwrap_or_default().trim().is_empty() {
+        return None;
+    }
+    if budget.left == 0 {
+        budget.truncated = true;
+        return None;
+    }
+    let tree = match node.node_type() {
+        roxmltree::NodeType::Element => {
+            let tag = node.tag_name();
+            budget.left -= 1;
+            XmlNode::Element {
+                name: qualified_name(node, tag.namespace(), tag.name()),
+                namespace: tag.namespace().map(str::to_string),
+                attributes: node
+                    .attributes()
+                    .map(|attribute| XmlAttribute {
+                        name: qualified_name(node, attribute.namespace(), attribute.name()),
+                        namespace: attribute.namespace().map(str::to_string),
+                        value: attribute.value().to_string(),
+                    })
+                    .collect(),
+                children: node
+                    .children()
+                    .filter_map(|child| tree_node(child, budget))
+                    .collect(),
+            }
+        }
+        roxmltree::NodeType::Text => {
+            budget.left -= 1;
+            XmlNode::Text {
+                text: node.text().unwrap_or_default().to_string(),
+            }
+        }
+        roxmltree::NodeType::Comment => {
+            budget.left -= 1;
+            XmlNode::Comment {
+                text: node.text().unwrap_or_default().to_string(),
+            }
+        }
+        roxmltree::NodeType::PI => {
+            let pi = node.pi()?;
+            budget.left -= 1;
+            XmlNode::ProcessingInstruction {
+                target: pi.target.to_string(),
+                value: pi.value.map(str::to_string),
+            }
+        }
+        roxmltree::NodeType::Root => return None,
+    };
+    Some(tree)
+}
+
+/// Parses an XML body into its pretty-printed text and node tree
+pub fn parse(text: &str, indent: Option<usize>, max_nodes: usize) -> Result<XmlDocument, AppError> {
+    let pretty = pretty_print(text, indent.unwrap_or(DEFAULT_INDENT))?;
+    let document = roxmltree::Document::parse(text).map_err(invalid_xml)?;
+    let mut budget = Budget {
+        left: max_nodes.max(1),
+        truncated: false,
+    };
+    let nodes = document
+        .root()
+        .children()
+        .filter_map(|node| tree_node(node, &mut budget))
+        .collect();
+    Ok(XmlDocument {
+        pretty,
+        nodes,
+        truncated: budget.truncated,
+    })
+}
+
+/// The prefixes declared anywhere in the document, the first declaration of each winning, so
+/// e.g. `//soap:Body` works as written in the body. `namespaces` adds to or overrides them.
+fn bindings(document: &Document, namespaces: &HashMap<String, String>) -> HashMap<String, String> {
+    let mut bindings: HashMap<String, String> = HashMap::new();
+    let mut elements: Vec<_> = document
+        .root()
+        .children()
+        .into_iter()
+        .filter_map(|child| match child {
+            ChildOfRoot::Element(element) => Some(element),
+            _ => None,
+        })
+        .collect();
+    while let Some(element) = elements.pop() {
+        for namespace in element.namespaces_in_scope() {
+            if !namespace.prefix().is_empty() {
+                bindings
+                    .entry(namespace.prefix().to_string())
+                    .or_insert_with(|| namespace.uri().to_string());
+            }
+        }
+        elements.extend(
+            element
+                .children()
+                .into_iter()
+                .filter_map(|child| match child {
+                    ChildOfElement::Element(element) => Some(element),
+                    _ => None,
+                }),
+        );
+    }
+    bindings.extend(namespaces.iter().map(|(p, uri)| (p.clone(), uri.clone())));
+    bindings
+}
+
+/// Prefixes of the qualified names in an XPath expression, e.g. `soap` in `//soap:Body`
+fn name_prefixes(expression: &str) -> Vec<&str> {
+    let is_name = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '.');
+    let mut prefixes = Vec::new();
+    let mut quote = None;
+    let mut start = None;
+    for (i, c) in expression.char_indices() {
+        if let Some(q) = quote {
+            if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' => quote = Some(c),
+            ':' => {
+                let rest = &expression[i + 1..];
+                let qualified =
+                    rest.starts_with(|c: char| c.is_alphabetic() || c == '_' || c == '*');
+                let axis = expression[..i].ends_with(':') || rest.starts_with(':');
+                if let Some(start) = start.filter(|_| qualified && !axis) {
+                    prefixes.push(&expression[start..i]);
+                }
+            }
+            _ => {}
+        }
+        start = match (is_name(c), start) {
+            (true, None) if !c.is_ascii_digit() => Some(i),
+            (true, start) => start,
+            (false, _) => None,
+        };
+    }
+    prefixes
+}
+
+/// Evaluates an XPath 1.0 expression against the document
+pub fn xpath<'d>(
+    document: &Document<'d>,
+    expression: &str,
+    namespaces: &HashMap<String, String>,
+) -> Result<sxd_xpath::Value<'d>, String> {
+    let compiled = Factory::new()
+        .build(expression)
+        .map_err(|e| format!("Invalid XPath {expression}: {e}"))?
+        .ok_or_else(|| "The XPath is empty".to_string())?;
+    let bindings = bindings(document, namespaces);
+    // Evaluating a name with an unbound prefix panics rather than failing
+    if let Some(prefix) = name_prefixes(expression)
+        .into_iter()
+        .find(|prefix| !bindings.contains_key(*prefix))
+    {
+        return Err(format!("Namespace prefix '{prefix}' isn't bound"));
+    }
+    let mut context = Context::new();
+    for (prefix, uri) in &bindings {
+        context.set_namespace(prefix, uri);
+    }
+    compiled
+        .evaluate(&context, document.root())
+        .map_err(|e| format!("XPath {expression} failed: {e}"))
+}
+
+/// A matched node as the UI shows it, e.g. `{"node": "element", "name": "m:Price", "value": "4"}`
+pub fn node_value(node: Node) -> Value {
+    let kind = match node {
+        Node::Root(_) => "root",
+        Node::Element(_) => "element",
+        Node::Attribute(_) => "attribute",
+        Node::Text(_) => "text",
+        Node::Comment(_) => "comment",
+        Node::Namespace(_) => "namespace",
+        Node::ProcessingInstruction(_) => "processingInstruction",
+    };
+    let mut value = json!({ "node": kind, "value": node.string_value() });
+    if let Some(name) = node.prefixed_name() {
+        value["name"] = Value::String(name);
+    }
+    value
+}
+
+/// Location of a matched node, e.g. `/soap:Envelope[1]/soap:Body[1]/@id`
+pub fn node_path(node: Node) -> String {
+    let mut steps = Vec::new();
+    let mut current = Some(node);
+    while let Some(node) = current {
+        let same_kind = |other: &Node| match (node, other) {
+            (Node::Element(_), Node::Element(_)) => other.prefixed_name() == node.prefixed_name(),
+            (Node::Text(_), Node::Text(_))
+            | (Node::Comment(_), Node::Comment(_))
+            | (Node::ProcessingInstruction(_), Node::ProcessingInstruction(_)) => true,
+            _ => false,
+        };
+        let position = node
+            .preceding_siblings()
+            .iter()
+            .filter(|n| same_kind(n))
+            .count()
+            + 1;
+        let step = match node {
+            Node::Root(_) => break,
+            Node::Element(_) => format!("{}[{position}]", node.prefixed_name().unwrap_or_default()),
+            Node::Attribute(_) => format!("@{}", node.prefixed_name().unwrap_or_default()),
+            Node::Namespace(_) => {
+                format!("namespace::{}", node.prefixed_name().unwrap_or_default())
+            }
+            Node::Text(_) => format!("text()[{position}]"),
+            Node::Comment(_) => format!("comment()[{position}]"),
+            Node::ProcessingInstruction(_) => format!("processing-instruction()[{position}]"),
+        };
+        steps.push(step);
+        current = node.parent();
+    }
+    steps.reverse();
+    format!("/{}", steps.join("/"))
+}
+
+/// An XPath number, as an integer when it's whole
+pub fn number_value(number: f64) -> Value {
+    if number.fract() == 0.0 && number.abs() < 9_007_199_254_740_992.0 {
+        json!(number as i64)
+    } else {
+        json!(number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{XmlNode, name_prefixes, node_path, parse, pretty_print, xpath};
+    use std::collections::HashMap;
+
+    const ENVELOPE: &str = r#"<?xml version="1.0"?>
+<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body>
+<m:GetPriceResponse xmlns:m="https://example.com/prices"><m:Price currency="EUR">4.50</m:Price><!-- cached --><m:Note><![CDATA[<b>sale</b>]]></m:Note></m:GetPriceResponse>
+</soap:Body></soap:Envelope>"#;
+
+    #[test]
+    fn pretty_prints_keeping_text_and_prefixes() {
+        assert_eq!(
+            pretty_print(ENVELOPE, 2).unwrap(),
+            r#"<?xml version="1.0"?>
+<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+  <soap:Body>
+    <m:GetPriceResponse xmlns:m="https://example.com/prices">
+      <m:Price currency="EUR">4.50</m:Price>
+      <!-- cached -->
+      <m:Note><![CDATA[<b>sale</b>]]></m:Note>
+    </m:GetPriceResponse>
+  </soap:Body>
+</soap:Envelope>"#
+        );
+        assert!(pretty_print("<a><b></a>", 2).is_err());
+    }
+
+    #[test]
+    fn builds_a_node_tree() {
+        let document = parse(ENVELOPE, None, 100).unwrap();
+        let [XmlNode::Element { name, children, .. }] = document.nodes.as_slice() else {
+            panic!("expected the envelope");
+        };
+        assert_eq!(name, "soap:Envelope");
+        assert_eq!(children.len(), 1);
+        assert!(!document.truncated);
+        assert!(parse(ENVELOPE, None, 3).unwrap().truncated);
+    }
+
+    #[test]
+    fn queries_with_the_body_s_prefixes() {
+        let package = sxd_document::parser::parse(ENVELOPE).unwrap();
+        let document = package.as_document();
+        let none = HashMap::new();
+        let sxd_xpath::Value::Nodeset(nodes) =
+            xpath(&document, "//m:Price/@currency", &none).unwrap()
+        else {
+            panic!("expected nodes");
+        };
+        let node = nodes.document_order_first().unwrap();
+        assert_eq!(node.string_value(), "EUR");
+        assert_eq!(
+            node_path(node),
+            "/soap:Envelope[1]/soap:Body[1]/m:GetPriceResponse[1]/m:Price[1]/@currency"
+        );
+
+        let bound = HashMap::from([("p".to_string(), "https://example.com/prices".to_string())]);
+        assert_eq!(
+            xpath(&document, "string(//p:Price)", &bound)
+                .unwrap()
+                .string(),
+            "4.50"
+        );
+        assert_eq!(
+            xpath(&document, "//x:Price", &none).unwrap_err(),
+            "Namespace prefix 'x' isn't bound"
+        );
+    }
+
+    #[test]
+    fn finds_the_prefixes_of_names() {
+        assert_eq!(
+            name_prefixes("/soap:Envelope/child::m:Price[@xsi:type = 'a:b'] | //ns-2:*"),
+            ["soap", "m", "xsi", "ns-2"]
+        );
+        assert!(name_prefixes("count(//item) > 1 and ancestor::x").is_empty());
+    }
+}
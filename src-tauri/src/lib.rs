@@ -1,16 +1,65 @@
 mod app_data;
 mod errors;
 mod http_client;
+mod monitors;
+mod operations;
 
+use crate::app_data::annotations::{self, Annotation, AnnotationQuery};
 use crate::app_data::crypto;
+use crate::app_data::env_snapshots::{self, SnapshotSummary};
+use crate::app_data::graphql_schemas::{self, CachedSchema};
+use crate::app_data::history::{self, HistoryEntry, HistoryRecord};
+use crate::app_data::import;
+use crate::app_data::journal::{self, JournalEdit, RecoveredEdit};
+use crate::app_data::key_protection::{KeyProtection, KeyProtectionStatus};
+use crate::app_data::keyring_secrets;
+use crate::app_data::keys::{self, KeyAudit, RewrapReport};
+use crate::app_data::search::{self, SearchHit};
+use crate::app_data::secrets::{self, SecretFinding};
+use crate::app_data::signing_keys;
+use crate::app_data::trash::{self, TrashEntry};
 use crate::errors::error::UserCancelled;
 use crate::errors::{AppError, ErrorKind};
-use crate::http_client::auth::{self, AuthConfig, AuthResult, OidcDiscovery};
+use crate::http_client::auth::{
+    self, AuthConfig, AuthResult, OidcDiscovery, RevocationResult, RevokeTokenRequest,
+};
+use crate::http_client::batch::{self, BatchResult};
+use crate::http_client::body_query::{self, BodyQuery, QueryResult};
+use crate::http_client::body_stream::BodySink;
+use crate::http_client::charset::{self, DecodedText, TextRequest};
+use crate::http_client::cors::{self, CorsOptions, CorsReport};
+use crate::http_client::data_run::{self, DataRunOptions, DataRunReport};
+use crate::http_client::dotenv;
+use crate::http_client::graphql::{self, GraphqlOperation};
+use crate::http_client::har;
+use crate::http_client::load_test::{self, LoadTestOptions, LoadTestStats};
+use crate::http_client::mdns::{self, LocalService};
+use crate::http_client::ndjson::{NdjsonRecord, RecordSink};
+use crate::http_client::pagination::{self, PaginationOptions, PaginationReport};
+use crate::http_client::payload_crypto::{self, DecryptPayloadRequest, EncryptPayloadRequest};
+use crate::http_client::polling::{self, PollOptions, PollReport};
+use crate::http_client::protobuf::{self, DecodedProtobuf, ProtoSchema, ProtobufRequest};
+use crate::http_client::recording_proxy::{self, ProxyEvent, ProxyInfo, ProxyOptions};
+use crate::http_client::request_log::{self, LogExportFormat};
+use crate::http_client::self_signed::{self, SavedCertificate, SelfSignedOptions};
+use crate::http_client::soap::{self, Wsdl};
+use crate::http_client::socket::{self, SocketEvent, SocketInfo, SocketOptions};
+use crate::http_client::spool;
+use crate::http_client::sse::{EventSink, SseEvent};
+use crate::http_client::static_server::{self, StaticServerInfo, StaticServerOptions};
+use crate::http_client::tls_probe::{self, ChainSource, TlsProbe, TlsProbeOptions};
+use crate::http_client::variables;
+use crate::http_client::visualize::{self, Visualization, VisualizeRequest};
+use crate::http_client::webhook::{self, WebhookEvent, WebhookInfo, WebhookOptions};
+use crate::http_client::xml::{self, XmlDocument, XmlRequest};
+use crate::monitors::metrics::{self, MonitorCheck};
+use crate::operations::ProgressSink;
 use base64::{Engine as _, engine::general_purpose};
 use chrono::Local;
+use futures_util::FutureExt;
 use http_client::{
     engine::{HttpEngine, TauriLogEmitter},
-    hyper_engine::HyperEngine,
+    hyper_engine::{self, CanonicalRequest, HyperEngine},
     manager,
     request::Request,
     response::ResponseData,
@@ -18,10 +67,12 @@ use http_client::{
 use log::LevelFilter;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 use std::panic::Location;
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 use tauri::Manager;
+use tauri::ipc::{Channel, InvokeResponseBody};
 use tauri::path::BaseDirectory;
 use tauri_plugin_dialog::DialogExt;
 
@@ -112,28 +163,445 @@ impl StartupProbe {
     }
 }
 
+/// Loads state that would otherwise be loaded on first use off the first window's critical path:
+/// the rustls crypto provider, the OS trust store and the data encryption key.
+fn spawn_preload(app: tauri::AppHandle, probe: StartupProbe) {
+    tauri::async_runtime::spawn_blocking(move || {
+        hyper_engine::install_crypto_provider();
+        probe.mark("preload_crypto_provider");
+
+        if let Ok(roots) = hyper_engine::root_store(None) {
+            log::debug!("startup: preloaded {} roots", roots.len());
+        }
+        probe.mark("preload_root_store");
+
+        if let Err(e) = crypto::get_or_create_key(&app, "app_data") {
+            log::warn!("startup: data encryption key preload failed: {}", e.message);
+        }
+        probe.mark("preload_data_key");
+    });
+}
+
 /// Sends an HTTP request and returns its response with live logging
 #[tauri::command(async)]
 async fn send_http_request(app: tauri::AppHandle, opts: Request) -> Result<ResponseData, AppError> {
+    execute_request(app, opts).await
+}
+
+/// Sends several HTTP requests concurrently, at most `concurrency` at a time (6 by default), and
+/// returns their results by request id. Each is cancelled like a single request, by its id.
+#[tauri::command(async)]
+async fn send_http_requests(
+    app: tauri::AppHandle,
+    requests: Vec<Request>,
+    concurrency: Option<usize>,
+) -> Result<HashMap<String, BatchResult>, AppError> {
+    batch::send_all(requests, concurrency, |request| {
+        execute_request(app.clone(), request)
+    })
+    .await
+}
+
+/// Sends an HTTP request and passes its response body to `on_chunk` as raw bytes while it
+/// arrives, rather than in the returned response. An empty chunk follows the last one once the
+/// response is complete.
+#[tauri::command(async)]
+async fn stream_http_request(
+    app: tauri::AppHandle,
+    mut opts: Request,
+    on_chunk: Channel<InvokeResponseBody>,
+) -> Result<ResponseData, AppError> {
+    let sink = on_chunk.clone();
+    opts.body_sink = Some(BodySink::new(move |chunk| {
+        let _ = sink.send(InvokeResponseBody::Raw(chunk.to_vec()));
+    }));
+    let response = execute_request(app, opts).await?;
+    let _ = on_chunk.send(InvokeResponseBody::Raw(Vec::new()));
+    Ok(response)
+}
+
+/// Sends an HTTP request after asking where to save its response, writing the body straight to
+/// that file. The returned response carries only metadata, with `saved_to` set.
+#[tauri::command(async)]
+async fn download_http_request(
+    app: tauri::AppHandle,
+    mut opts: Request,
+    options: SaveFileDialogOptions,
+) -> Result<ResponseData, AppError> {
+    let dialog_app = app.clone();
+    let path = tauri::async_runtime::spawn_blocking(move || -> Result<String, AppError> {
+        let mut dialog = dialog_app.dialog().file().set_title(&options.title);
+        for filter in options.filters.unwrap_or_default() {
+            let extensions: Vec<&str> = filter.extensions.iter().map(|s| s.as_str()).collect();
+            dialog = dialog.add_filter(&filter.name, &extensions);
+        }
+        let file_path = dialog
+            .set_file_name(&options.default_path)
+            .blocking_save_file()
+            .ok_or(UserCancelled)?;
+        let path = file_path.as_path().ok_or_else(|| {
+            AppError::new(
+                ErrorKind::InvalidPath,
+                "File path is not representable as a native path".to_string(),
+            )
+        })?;
+        Ok(path.to_string_lossy().to_string())
+    })
+    .await
+    .unwrap_or_else(|join_error| {
+        Err(AppError::new(
+            ErrorKind::IoError,
+            format!("Failed to execute save dialog: {join_error}"),
+        ))
+    })?;
+    opts.download_to_path = Some(path);
+    execute_request(app, opts).await
+}
+
+/// Sends a request whose `text/event-stream` response is read as Server-Sent Events, passing
+/// each one to `on_event` as it arrives. Returns once the server closes the stream; cancel it
+/// with `cancel_http_request`.
+#[tauri::command(async)]
+async fn send_sse_request(
+    app: tauri::AppHandle,
+    mut opts: Request,
+    on_event: Channel<SseEvent>,
+) -> Result<ResponseData, AppError> {
+    opts.event_sink = Some(EventSink::new(move |event| {
+        let _ = on_event.send(event);
+    }));
+    execute_request(app, opts).await
+}
+
+/// Sends a request whose newline-delimited JSON response (`application/x-ndjson`, JSON Lines) is
+/// passed to `on_record` a record at a time as it arrives, rather than only once it's complete
+#[tauri::command(async)]
+async fn send_ndjson_request(
+    app: tauri::AppHandle,
+    mut opts: Request,
+    on_record: Channel<NdjsonRecord>,
+) -> Result<ResponseData, AppError> {
+    opts.record_sink = Some(RecordSink::new(move |record| {
+        let _ = on_record.send(record);
+    }));
+    execute_request(app, opts).await
+}
+
+/// Runs the introspection query against the GraphQL endpoint of `opts` and caches the schema it
+/// returns. Headers and auth of `opts` are kept; its method and body are replaced.
+#[tauri::command(async)]
+async fn introspect_graphql_schema(
+    app: tauri::AppHandle,
+    mut opts: Request,
+) -> Result<CachedSchema, AppError> {
+    opts.method = "POST".to_string();
+    opts.body = None;
+    opts.body_file_path = None;
+    opts.multipart_parts = None;
+    opts.graphql = Some(GraphqlOperation::introspection());
+    let url = opts.url.clone();
+
+    let response = execute_request(app.clone(), opts).await?;
+    // GraphQL over HTTP answers a rejected query, e.g. with introspection disabled, with a 400
+    // whose body says why
+    if !(200..300).contains(&response.status) && response.status != 400 {
+        return Err(AppError::new(
+            ErrorKind::HttpError,
+            format!(
+                "Introspection failed: HTTP {} {}",
+                response.status, response.status_text
+            ),
+        ));
+    }
+    let body = match &response.file_path {
+        Some(path) => {
+            use std::io::Read;
+            let mut body = Vec::new();
+            spool::open(std::path::Path::new(path))?.read_to_end(&mut body)?;
+            body
+        }
+        None => response.body,
+    };
+    let schema = graphql::parse_introspection(&body)?;
+    graphql_schemas::save_schema(&app, &url, schema)
+}
+
+/// Returns the schema last introspected from a GraphQL endpoint, if any
+#[tauri::command(async)]
+async fn get_cached_graphql_schema(
+    app: tauri::AppHandle,
+    url: String,
+) -> Result<Option<CachedSchema>, AppError> {
+    graphql_schemas::load_schema(&app, &url)
+}
+
+/// Parses the text of a WSDL 1.1 document into the SOAP operations it describes, each ready to
+/// send as a request's `soap` operation
+#[tauri::command(async)]
+async fn parse_wsdl(source: String) -> Result<Wsdl, AppError> {
+    soap::parse_wsdl(&source)
+}
+
+/// Opens a raw TCP connection, optionally over TLS, passing received bytes to `on_event` as
+/// they arrive, followed by a final `closed` event
+#[tauri::command(async)]
+async fn open_socket(
+    opts: SocketOptions,
+    on_event: Channel<SocketEvent>,
+) -> Result<SocketInfo, AppError> {
+    socket::open(opts, move |event| {
+        let _ = on_event.send(event);
+    })
+    .await
+}
+
+/// Writes bytes to an open socket
+#[tauri::command(async)]
+async fn send_socket(connection_id: String, data: Vec<u8>) -> Result<(), AppError> {
+    socket::send(&connection_id, &data).await
+}
+
+/// Closes an open socket. Returns false when it wasn't open.
+#[tauri::command(async)]
+async fn close_socket(connection_id: String) -> Result<bool, AppError> {
+    Ok(socket::close(&connection_id).await)
+}
+
+/// Starts a local forward proxy that passes each exchange and CONNECT tunnel through it to
+/// `on_event`, recording exchanges in history unless asked not to, followed by a final
+/// `stopped` event
+#[tauri::command(async)]
+async fn start_recording_proxy(
+    app: tauri::AppHandle,
+    opts: ProxyOptions,
+    on_event: Channel<ProxyEvent>,
+) -> Result<ProxyInfo, AppError> {
+    let record_history = opts.record_history.unwrap_or(true);
+    let collection_id = opts.collection_id.clone();
+    recording_proxy::start(opts, move |mut event| {
+        if record_history && let ProxyEvent::Exchange { exchange } = &mut event {
+            let record = HistoryRecord {
+                request_id: None,
+                collection_id: collection_id.clone(),
+                method: exchange.method.clone(),
+                url: exchange.url.clone(),
+                status: exchange.status,
+                status_text: exchange.status_text.clone(),
+                headers: exchange.response_headers.clone(),
+                duration: exchange.duration,
+                body: exchange.response_body.clone(),
+                file_path: None,
+            };
+            match history::record_history_entry(&app, record) {
+                Ok(entry) => exchange.history_id = Some(entry.id),
+                Err(e) => log::warn!(
+                    "Failed to record {} {} in history: {}",
+                    exchange.method,
+                    exchange.url,
+                    e.message
+                ),
+            }
+        }
+        let _ = on_event.send(event);
+    })
+    .await
+}
+
+/// Stops a recording proxy. Returns false when it wasn't running.
+#[tauri::command(async)]
+async fn stop_recording_proxy(proxy_id: String) -> Result<bool, AppError> {
+    Ok(recording_proxy::stop(&proxy_id))
+}
+
+/// Starts a temporary local listener that answers every request with the configured response
+/// and passes it to `on_event`, followed by a final `stopped` event
+#[tauri::command(async)]
+async fn start_webhook_listener(
+    opts: WebhookOptions,
+    on_event: Channel<WebhookEvent>,
+) -> Result<WebhookInfo, AppError> {
+    webhook::start(opts, move |event| {
+        let _ = on_event.send(event);
+    })
+    .await
+}
+
+/// Stops a webhook listener. Returns false when it wasn't running.
+#[tauri::command(async)]
+async fn stop_webhook_listener(listener_id: String) -> Result<bool, AppError> {
+    Ok(webhook::stop(&listener_id))
+}
+
+/// Starts serving a directory over HTTP, logging each request it answers like a sent request,
+/// under the server's id
+#[tauri::command(async)]
+async fn start_static_server(
+    app: tauri::AppHandle,
+    opts: StaticServerOptions,
+) -> Result<StaticServerInfo, AppError> {
+    static_server::start(opts, Arc::new(TauriLogEmitter::new(app))).await
+}
+
+/// Stops a static file server. Returns false when it wasn't running.
+#[tauri::command(async)]
+async fn stop_static_server(server_id: String) -> Result<bool, AppError> {
+    Ok(static_server::stop(&server_id))
+}
+
+/// Performs only a TLS handshake with a server and summarizes the certificates it presents
+#[tauri::command(async)]
+async fn inspect_tls(opts: TlsProbeOptions) -> Result<TlsProbe, AppError> {
+    tls_probe::inspect(&opts).await
+}
+
+/// Saves a certificate chain as PEM where the user picks: one bundle, or with `separate_files`
+/// one file per certificate. Returns the paths written.
+#[tauri::command(async)]
+async fn export_certificate_chain(
+    app: tauri::AppHandle,
+    source: ChainSource,
+    separate_files: Option<bool>,
+    options: SaveFileDialogOptions,
+) -> Result<Vec<String>, AppError> {
+    let pems = tls_probe::certificate_chain(&source).await?;
+    let result = tauri::async_runtime::spawn_blocking(move || -> Result<Vec<String>, AppError> {
+        let mut dialog = app.dialog().file().set_title(&options.title);
+        for filter in options.filters.unwrap_or_default() {
+            let extensions: Vec<&str> = filter.extensions.iter().map(|s| s.as_str()).collect();
+            dialog = dialog.add_filter(&filter.name, &extensions);
+        }
+        let file_path = dialog
+            .set_file_name(&options.default_path)
+            .blocking_save_file()
+            .ok_or(UserCancelled)?;
+        let path = file_path.as_path().ok_or_else(|| {
+            AppError::new(
+                ErrorKind::InvalidPath,
+                "File path is not representable as a native path".to_string(),
+            )
+        })?;
+        let written = tls_probe::write_chain(path, &pems, separate_files.unwrap_or(false))?;
+        Ok(written
+            .iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect())
+    })
+    .await;
+
+    result.unwrap_or_else(|join_error| {
+        Err(AppError::new(
+            ErrorKind::IoError,
+            format!("Failed to execute save operation: {join_error}"),
+        ))
+    })
+}
+
+/// Generates a self-signed certificate for `opts.hostname` and saves it as PEM where the user
+/// picks, with its key next to it, e.g. for serving the webhook listener over HTTPS.
+#[tauri::command(async)]
+async fn generate_self_signed_certificate(
+    app: tauri::AppHandle,
+    opts: SelfSignedOptions,
+    options: SaveFileDialogOptions,
+) -> Result<SavedCertificate, AppError> {
+    let certificate = self_signed::generate(&opts)?;
+    let result =
+        tauri::async_runtime::spawn_blocking(move || -> Result<SavedCertificate, AppError> {
+            let mut dialog = app.dialog().file().set_title(&options.title);
+            for filter in options.filters.unwrap_or_default() {
+                let extensions: Vec<&str> = filter.extensions.iter().map(|s| s.as_str()).collect();
+                dialog = dialog.add_filter(&filter.name, &extensions);
+            }
+            let file_path = dialog
+                .set_file_name(&options.default_path)
+                .blocking_save_file()
+                .ok_or(UserCancelled)?;
+            let path = file_path.as_path().ok_or_else(|| {
+                AppError::new(
+                    ErrorKind::InvalidPath,
+                    "File path is not representable as a native path".to_string(),
+                )
+            })?;
+            self_signed::write_pair(path, &certificate)
+        })
+        .await;
+
+    result.unwrap_or_else(|join_error| {
+        Err(AppError::new(
+            ErrorKind::IoError,
+            format!("Failed to execute save operation: {join_error}"),
+        ))
+    })
+}
+
+/// Loads the request's signing key and secret variables, which the webview only refers to
+async fn load_request_secrets(
+    app: &tauri::AppHandle,
+    request: &mut Request,
+) -> Result<(), AppError> {
+    if let Some(signature) = request.signature.as_mut() {
+        signature.key_material = Some(signing_keys::load_signing_key(app, &signature.key_name)?);
+    }
+    variables::resolve_secrets(app, request).await
+}
+
+/// Runs a request, cancellable through the token registered under its id
+async fn execute_request(
+    app: tauri::AppHandle,
+    mut opts: Request,
+) -> Result<ResponseData, AppError> {
     use std::sync::Arc;
 
+    load_request_secrets(&app, &mut opts).await?;
+
     let emitter = Arc::new(TauriLogEmitter::new(app.clone()));
+    opts.progress = Some(ProgressSink::for_app(&app));
 
     // Backend uses Hyper exclusively now; ignore any engine preference.
     let engine: Box<dyn HttpEngine> = Box::new(HyperEngine::new());
 
     let request_id = opts.request_id.clone();
+    // Filled by the engine, so the entry shows the request as it was sent
+    let har_entry = opts.keep_har.unwrap_or(false).then(har::EntrySlot::default);
+    opts.har_entry = har_entry.clone();
+    // A streamed body never reaches the response, so keep a copy for the HAR entry
+    let streamed_body = har_entry
+        .is_some()
+        .then(|| opts.body_sink.take())
+        .flatten()
+        .map(|sink| {
+            let body = har::BodyCopy::new();
+            let copy = body.clone();
+            opts.body_sink = Some(BodySink::new(move |chunk| {
+                copy.push(&chunk);
+                sink.send(chunk);
+            }));
+            body
+        });
     // Register cancellation token for this request
     let token = manager::register(&request_id);
+    opts.cancellation = Some(token.clone());
     // Run the request and allow cancellation via token
+    let mut execution = engine.execute(opts, emitter);
     let result = tokio::select! {
         _ = token.cancelled() => {
-            Err(AppError::new(ErrorKind::UserCancelled, "Request was cancelled"))
+            // A body being read ends at once with what arrived; anything else is abandoned
+            execution.now_or_never().unwrap_or_else(|| {
+                Err(AppError::new(ErrorKind::UserCancelled, "Request was cancelled"))
+            })
         }
-        res = engine.execute(opts, emitter) => res
+        res = &mut execution => res
     };
     // Clean up token after completion
     manager::remove(&request_id);
+    if let Ok(response) = &result
+        && let Some(entry) = har_entry.and_then(|slot| slot.take())
+    {
+        match streamed_body {
+            Some(body) => entry.finish(response, body.take().as_deref()),
+            None => entry.finish(response, Some(&response.body)),
+        }
+    }
     result
 }
 
@@ -143,19 +611,238 @@ async fn load_app_data(app: tauri::AppHandle, file_name: String) -> Result<Value
     app_data::load_app_data(&app, &file_name)
 }
 
-/// Saves the application data file
+/// Saves the application data file.
+///
+/// Values at `secure_pointers` are marked secure before saving. Returns any remaining
+/// plaintext values that look like credentials so the UI can warn about them.
 #[tauri::command(async)]
 async fn save_app_data(
     app: tauri::AppHandle,
     file_name: String,
-    data: Value,
+    mut data: Value,
+    secure_pointers: Option<Vec<String>>,
+) -> Result<Vec<SecretFinding>, AppError> {
+    if let Some(pointers) = secure_pointers {
+        secrets::mark_secure(&mut data, &pointers);
+    }
+    let findings = secrets::scan_for_secrets(&data);
+    if !findings.is_empty() {
+        log::warn!(
+            "Saving {file_name} with {} plaintext value(s) that look like credentials",
+            findings.len()
+        );
+    }
+    app_data::save_app_data(&app, &file_name, data)?;
+    Ok(findings)
+}
+
+/// Deletes an application data file. Unless `permanent` is set, the file is moved to the trash.
+#[tauri::command(async)]
+async fn delete_app_data(
+    app: tauri::AppHandle,
+    file_name: String,
+    permanent: Option<bool>,
+) -> Result<(), AppError> {
+    if permanent.unwrap_or(false) {
+        app_data::delete_app_data(&app, &file_name)
+    } else {
+        trash::trash_app_data(&app, &file_name).map(|_| ())
+    }
+}
+
+/// Appends debounced request edits to the autosave journal
+#[tauri::command(async)]
+async fn append_journal(app: tauri::AppHandle, edits: Vec<JournalEdit>) -> Result<(), AppError> {
+    journal::append_journal(&app, edits)
+}
+
+/// Returns unsaved request edits left in the journal, e.g. after a crash
+#[tauri::command(async)]
+async fn recover_journal(app: tauri::AppHandle) -> Result<Vec<RecoveredEdit>, AppError> {
+    journal::recover_journal(&app)
+}
+
+/// Drops journal records for saved requests, or the whole journal when `target_ids` is omitted
+#[tauri::command(async)]
+async fn checkpoint_journal(
+    app: tauri::AppHandle,
+    target_ids: Option<Vec<String>>,
+) -> Result<(), AppError> {
+    journal::checkpoint_journal(&app, target_ids)
+}
+
+/// Records a changed environment in its snapshot history
+#[tauri::command(async)]
+async fn snapshot_environment(
+    app: tauri::AppHandle,
+    collection_id: String,
+    environment: Value,
+) -> Result<Option<SnapshotSummary>, AppError> {
+    env_snapshots::snapshot_environment(&app, &collection_id, environment)
+}
+
+/// Keeps the edited value of a secure environment variable for requests that refer to it
+/// before its collection is saved; no `value` forgets it
+#[tauri::command(async)]
+async fn stage_secure_variable(
+    collection_id: String,
+    environment_id: String,
+    variable_id: String,
+    value: Option<String>,
 ) -> Result<(), AppError> {
-    app_data::save_app_data(&app, &file_name, data)
+    variables::stage_secure_value(collection_id, environment_id, variable_id, value);
+    Ok(())
 }
 
+/// Lists the snapshots of an environment, newest first
 #[tauri::command(async)]
-async fn delete_app_data(app: tauri::AppHandle, file_name: String) -> Result<(), AppError> {
-    app_data::delete_app_data(&app, &file_name)
+async fn list_environment_snapshots(
+    app: tauri::AppHandle,
+    collection_id: String,
+    environment_id: String,
+) -> Result<Vec<SnapshotSummary>, AppError> {
+    env_snapshots::list_environment_snapshots(&app, &collection_id, &environment_id)
+}
+
+/// Returns an environment as it was in one of its snapshots
+#[tauri::command(async)]
+async fn restore_environment_snapshot(
+    app: tauri::AppHandle,
+    collection_id: String,
+    environment_id: String,
+    snapshot_id: String,
+) -> Result<Value, AppError> {
+    env_snapshots::restore_environment_snapshot(&app, &collection_id, &environment_id, &snapshot_id)
+}
+
+/// Records a sent request and its response in history, storing the body once per distinct
+/// content
+#[tauri::command(async)]
+async fn record_history_entry(
+    app: tauri::AppHandle,
+    record: HistoryRecord,
+) -> Result<HistoryEntry, AppError> {
+    tauri::async_runtime::spawn_blocking(move || history::record_history_entry(&app, record))
+        .await
+        .unwrap_or_else(|join_error| {
+            Err(AppError::new(
+                ErrorKind::IoError,
+                format!("Failed to record the history entry: {join_error}"),
+            ))
+        })
+}
+
+/// Lists history entries, newest first
+#[tauri::command(async)]
+async fn list_history(
+    app: tauri::AppHandle,
+    request_id: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<HistoryEntry>, AppError> {
+    history::list_history(&app, request_id.as_deref(), limit)
+}
+
+/// Returns a history entry's body by its digest
+#[tauri::command(async)]
+async fn read_history_body(app: tauri::AppHandle, digest: String) -> Result<Vec<u8>, AppError> {
+    history::read_history_body(&app, &digest)
+}
+
+/// Deletes all history and its stored bodies
+#[tauri::command(async)]
+async fn clear_history(app: tauri::AppHandle) -> Result<(), AppError> {
+    history::clear_history(&app)
+}
+
+/// Searches saved requests and history, including stored response bodies, returning the best
+/// hits first
+#[tauri::command(async)]
+async fn search_workspace(
+    app: tauri::AppHandle,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<SearchHit>, AppError> {
+    tauri::async_runtime::spawn_blocking(move || search::search_workspace(&app, &query, limit))
+        .await
+        .unwrap_or_else(|join_error| {
+            Err(AppError::new(
+                ErrorKind::IoError,
+                format!("Failed to search the workspace: {join_error}"),
+            ))
+        })
+}
+
+/// Moves a JSON value (e.g. a request removed from a collection) to the trash
+#[tauri::command(async)]
+async fn trash_app_item(
+    app: tauri::AppHandle,
+    label: Option<String>,
+    parent_id: Option<String>,
+    data: Value,
+) -> Result<TrashEntry, AppError> {
+    trash::trash_item(&app, label, parent_id, data)
+}
+
+#[tauri::command(async)]
+async fn list_trash(app: tauri::AppHandle) -> Result<Vec<TrashEntry>, AppError> {
+    trash::list_trash(&app)
+}
+
+/// Restores a trash entry. Returns the stored value for item entries.
+#[tauri::command(async)]
+async fn restore_trash_entry(
+    app: tauri::AppHandle,
+    id: String,
+    overwrite: Option<bool>,
+) -> Result<Option<Value>, AppError> {
+    trash::restore_trash_entry(&app, &id, overwrite.unwrap_or(false))
+}
+
+/// Permanently removes trash entries older than `older_than_days`, or all entries when omitted
+#[tauri::command(async)]
+async fn purge_trash(
+    app: tauri::AppHandle,
+    older_than_days: Option<u32>,
+) -> Result<usize, AppError> {
+    trash::purge_trash(&app, older_than_days)
+}
+
+#[tauri::command(async)]
+async fn get_annotation(
+    app: tauri::AppHandle,
+    target_id: String,
+) -> Result<Option<Annotation>, AppError> {
+    annotations::get_annotation(&app, &target_id)
+}
+
+/// Sets tags/notes for a saved request or history entry
+#[tauri::command(async)]
+async fn set_annotation(
+    app: tauri::AppHandle,
+    annotation: Annotation,
+) -> Result<Annotation, AppError> {
+    annotations::set_annotation(&app, annotation)
+}
+
+#[tauri::command(async)]
+async fn delete_annotation(app: tauri::AppHandle, target_id: String) -> Result<(), AppError> {
+    annotations::delete_annotation(&app, &target_id)
+}
+
+/// Finds annotations matching a query such as `tag:smoke -tag:flaky`
+#[tauri::command(async)]
+async fn query_annotations(
+    app: tauri::AppHandle,
+    filter: AnnotationQuery,
+) -> Result<Vec<Annotation>, AppError> {
+    annotations::query_annotations(&app, filter)
+}
+
+#[tauri::command(async)]
+async fn list_annotation_tags(
+    app: tauri::AppHandle,
+) -> Result<std::collections::BTreeMap<String, usize>, AppError> {
+    annotations::list_tags(&app)
 }
 
 #[tauri::command(async)]
@@ -168,6 +855,87 @@ async fn set_data_encryption_key(app: tauri::AppHandle, key_b64: String) -> Resu
     crypto::set_data_encryption_key(&app, &key_b64)
 }
 
+/// Reports whether the data encryption keys are wrapped by a hardware key store
+#[tauri::command(async)]
+async fn get_key_protection(app: tauri::AppHandle) -> Result<KeyProtectionStatus, AppError> {
+    crypto::get_key_protection(&app)
+}
+
+/// Moves the data encryption keys into (or out of) hardware protection where available
+#[tauri::command(async)]
+async fn set_key_protection(
+    app: tauri::AppHandle,
+    protection: KeyProtection,
+) -> Result<KeyProtectionStatus, AppError> {
+    crypto::set_key_protection(&app, protection)
+}
+
+/// Stores an HTTP message signing key (PEM private key or base64 HMAC secret) in the keyring
+#[tauri::command(async)]
+async fn store_signing_key(
+    app: tauri::AppHandle,
+    name: String,
+    key: String,
+) -> Result<(), AppError> {
+    signing_keys::store_signing_key(&app, &name, &key)
+}
+
+/// Removes an HTTP message signing key from the keyring
+#[tauri::command(async)]
+async fn delete_signing_key(app: tauri::AppHandle, name: String) -> Result<bool, AppError> {
+    signing_keys::delete_signing_key(&app, &name)
+}
+
+/// Stores a named secret in the keyring, for requests to refer to as `{{secret:name}}`
+#[tauri::command(async)]
+async fn store_keyring_secret(
+    app: tauri::AppHandle,
+    name: String,
+    value: String,
+) -> Result<(), AppError> {
+    keyring_secrets::store_secret(&app, &name, &value)
+}
+
+/// Reads a named secret from the keyring, e.g. to reveal it when editing
+#[tauri::command(async)]
+async fn load_keyring_secret(app: tauri::AppHandle, name: String) -> Result<String, AppError> {
+    keyring_secrets::load_secret(&app, &name)
+}
+
+/// Removes a named secret from the keyring
+#[tauri::command(async)]
+async fn delete_keyring_secret(app: tauri::AppHandle, name: String) -> Result<bool, AppError> {
+    keyring_secrets::delete_secret(&app, &name)
+}
+
+/// Lists the names a `.env` file defines, sorted, for variables to refer to. Values aren't
+/// returned; they're read when a request is sent.
+#[tauri::command(async)]
+async fn list_dotenv_variables(path: String) -> Result<Vec<String>, AppError> {
+    let mut names: Vec<String> = dotenv::load(&path)?.into_keys().collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Lists which encryption keys the secure values in app data are encrypted with
+#[tauri::command(async)]
+async fn audit_encryption_keys(app: tauri::AppHandle) -> Result<KeyAudit, AppError> {
+    keys::audit_keys(&app)
+}
+
+/// Re-encrypts app data values with the active key, decrypting with `previous_key_b64` where needed
+#[tauri::command(async)]
+async fn rewrap_app_data(
+    app: tauri::AppHandle,
+    previous_key_b64: Option<String>,
+) -> Result<RewrapReport, AppError> {
+    let previous = previous_key_b64
+        .as_deref()
+        .map(crypto::decode_key)
+        .transpose()?;
+    keys::rewrap_app_data(&app, previous, ProgressSink::for_app(&app))
+}
+
 #[tauri::command(async)]
 async fn get_app_data_dir(app: tauri::AppHandle) -> Result<String, AppError> {
     let path = app
@@ -194,6 +962,109 @@ async fn cancel_http_request(_app: tauri::AppHandle, request_id: String) -> Resu
     cancel_http_request_inner(&request_id)
 }
 
+/// Asks a long-running operation to stop, by the id of its `operation-progress` events.
+/// Returns whether the operation was still running.
+#[tauri::command(async)]
+async fn cancel_operation(operation_id: String) -> Result<bool, AppError> {
+    Ok(manager::cancel(&operation_id))
+}
+
+/// Returns the canonical form and hash of a request, for checking two requests are identical
+#[tauri::command(async)]
+async fn canonicalize_request(request: Request) -> Result<CanonicalRequest, AppError> {
+    http_client::hyper_engine::canonical_request(&request).await
+}
+
+/// Renders the log kept for a recent request as NDJSON or curl-verbose-style text
+#[tauri::command(async)]
+async fn export_request_log(
+    request_id: String,
+    format: LogExportFormat,
+) -> Result<String, AppError> {
+    request_log::export(&request_id, format)
+}
+
+/// Converts the text of a Postman collection export into a native collection export
+#[tauri::command(async)]
+async fn convert_postman_collection(source: String) -> Result<Value, AppError> {
+    tauri::async_runtime::spawn_blocking(move || import::postman_to_native(&source))
+        .await
+        .unwrap_or_else(|join_error| {
+            Err(AppError::new(
+                ErrorKind::IoError,
+                format!("Failed to convert the collection: {join_error}"),
+            ))
+        })
+}
+
+/// Converts the text of an Insomnia v4 or v5 export into a native collection export
+#[tauri::command(async)]
+async fn convert_insomnia_export(source: String) -> Result<Value, AppError> {
+    tauri::async_runtime::spawn_blocking(move || import::insomnia_to_native(&source))
+        .await
+        .unwrap_or_else(|join_error| {
+            Err(AppError::new(
+                ErrorKind::IoError,
+                format!("Failed to convert the collection: {join_error}"),
+            ))
+        })
+}
+
+/// Converts the text of a `.http` file into a native collection export
+#[tauri::command(async)]
+async fn convert_http_file(source: String) -> Result<Value, AppError> {
+    import::http_file_to_native(&source)
+}
+
+/// Writes a native collection export as a `.http` file
+#[tauri::command(async)]
+async fn export_http_file(collection: Value) -> Result<String, AppError> {
+    import::native_to_http_file(&collection)
+}
+
+/// Renders the recent requests `request_ids`, sent with `keep_har`, as a HAR 1.2 document,
+/// e.g. to open in browser devtools
+#[tauri::command(async)]
+async fn export_har(request_ids: Vec<String>) -> Result<String, AppError> {
+    har::export(&request_ids)
+}
+
+/// Closes the shared connection of a connection group (e.g. when a chained sequence ends)
+#[tauri::command(async)]
+async fn close_connection_group(group: String) -> Result<bool, AppError> {
+    Ok(http_client::hyper_engine::close_connection_group(&group))
+}
+
+/// Reloads the OS trust store (e.g. after a certificate was installed in it) and drops the cached
+/// root stores, returning how many roots the reloaded store has
+#[tauri::command(async)]
+async fn refresh_root_store() -> Result<usize, AppError> {
+    let roots = tauri::async_runtime::spawn_blocking(hyper_engine::refresh_root_store)
+        .await
+        .map_err(|e| AppError::new(ErrorKind::IoError, e.to_string()))?;
+    // Clients keep the TLS config they were built with
+    hyper_engine::clear_profile_clients();
+    Ok(roots)
+}
+
+/// Lists the DNS lookups cached for new connections
+#[tauri::command(async)]
+async fn list_dns_cache() -> Result<Vec<hyper_engine::DnsCacheEntry>, AppError> {
+    Ok(hyper_engine::dns_cache_entries())
+}
+
+/// Drops the cached DNS lookup of `host`, or all of them, returning how many were dropped
+#[tauri::command(async)]
+async fn flush_dns_cache(host: Option<String>) -> Result<usize, AppError> {
+    Ok(hyper_engine::flush_dns_cache(host.as_deref()))
+}
+
+/// Drops every response kept for revalidation, returning how many were dropped
+#[tauri::command(async)]
+async fn clear_response_cache() -> Result<usize, AppError> {
+    Ok(hyper_engine::clear_response_cache())
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct FileDialogFilter {
@@ -432,27 +1303,348 @@ async fn delete_file(_app: tauri::AppHandle, path: String) -> Result<(), AppErro
     }
 }
 
+/// The path of a spooled response body that other apps can open, decompressing it first if
+/// it was compressed
+#[tauri::command(async)]
+async fn materialize_response_file(path: String) -> Result<String, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        spool::materialize(std::path::Path::new(&path))
+            .map(|plain| plain.to_string_lossy().to_string())
+    })
+    .await
+    .unwrap_or_else(|join_error| {
+        Err(AppError::new(
+            ErrorKind::IoError,
+            format!("Failed to decompress the response file: {join_error}"),
+        ))
+    })
+}
+
+/// Reads `length` bytes of a spooled response body from `offset`, decompressing as needed.
+/// Returns them base64-encoded.
+#[tauri::command(async)]
+async fn read_response_file_range(
+    path: String,
+    offset: u64,
+    length: usize,
+) -> Result<String, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        spool::read_range(std::path::Path::new(&path), offset, length)
+            .map(|bytes| general_purpose::STANDARD.encode(bytes))
+    })
+    .await
+    .unwrap_or_else(|join_error| {
+        Err(AppError::new(
+            ErrorKind::IoError,
+            format!("Failed to read the response file: {join_error}"),
+        ))
+    })
+}
+
+/// Reads a chunk of a spooled response body, base64-encoded or as hex dump rows, for paging
+/// through it without loading the whole file
+#[tauri::command(async)]
+async fn read_response_file_chunk(
+    request: spool::ChunkRequest,
+) -> Result<spool::BodyChunk, AppError> {
+    tauri::async_runtime::spawn_blocking(move || spool::read_chunk(request))
+        .await
+        .unwrap_or_else(|join_error| {
+            Err(AppError::new(
+                ErrorKind::IoError,
+                format!("Failed to read the response file: {join_error}"),
+            ))
+        })
+}
+
 #[tauri::command(async)]
 async fn discover_oidc(app: tauri::AppHandle, url: String) -> Result<OidcDiscovery, AppError> {
     auth::discover_oidc(app, url).await
 }
 
+/// Authenticates with `config`, which may inherit from `parents`: the configurations of the
+/// collection and folders it sits in, outermost first
 #[tauri::command(async)]
 async fn get_authentication_result(
     app: tauri::AppHandle,
     config: AuthConfig,
+    parents: Option<Vec<AuthConfig>>,
     parent_request_id: Option<String>,
 ) -> Result<AuthResult, AppError> {
-    auth::get_authentication_result(app, config, parent_request_id).await
+    auth::get_authentication_result(app, config, parents.unwrap_or_default(), parent_request_id)
+        .await
+}
+
+/// Revokes an OAuth2 token, or the tokens cached for `config` (which may inherit from
+/// `parents`), at its revocation endpoint
+#[tauri::command(async)]
+async fn revoke_token(
+    app: tauri::AppHandle,
+    config: AuthConfig,
+    parents: Option<Vec<AuthConfig>>,
+    request: RevokeTokenRequest,
+    parent_request_id: Option<String>,
+) -> Result<RevocationResult, AppError> {
+    let parents = parents.unwrap_or_default();
+    auth::revoke_token(app, config, parents, request, parent_request_id).await
+}
+
+/// Sends the preflight a browser on `options.origin` would send and reports why it would
+/// block the request, if it would
+#[tauri::command(async)]
+async fn analyze_cors(
+    app: tauri::AppHandle,
+    request: Request,
+    options: CorsOptions,
+) -> Result<CorsReport, AppError> {
+    cors::analyze_cors(app, request, options).await
+}
+
+/// Browses the local network with mDNS for instances of a DNS-SD service type such as
+/// `_http._tcp`, returning their hosts, ports and addresses
+#[tauri::command(async)]
+async fn discover_local_services(
+    service_type: String,
+    timeout_ms: Option<u64>,
+) -> Result<Vec<LocalService>, AppError> {
+    mdns::discover_local_services(&service_type, timeout_ms).await
+}
+
+/// Sends a request that starts a long-running job and polls its status URL until the job
+/// finishes. Cancelled like any other request, by its request id.
+#[tauri::command(async)]
+async fn poll_until(
+    app: tauri::AppHandle,
+    mut request: Request,
+    options: PollOptions,
+) -> Result<PollReport, AppError> {
+    load_request_secrets(&app, &mut request).await?;
+
+    let request_id = request.request_id.clone();
+    let token = manager::register(&request_id);
+    let result = tokio::select! {
+        _ = token.cancelled() => {
+            Err(AppError::new(ErrorKind::UserCancelled, "Polling was cancelled"))
+        }
+        res = polling::poll_until(app, request, options) => res
+    };
+    manager::remove(&request_id);
+    result
+}
+
+/// Fetches a paginated resource page by page and returns the results of all pages. Cancelled
+/// like any other request, by its request id.
+#[tauri::command(async)]
+async fn paginate(
+    app: tauri::AppHandle,
+    mut request: Request,
+    options: PaginationOptions,
+) -> Result<PaginationReport, AppError> {
+    load_request_secrets(&app, &mut request).await?;
+
+    let request_id = request.request_id.clone();
+    let token = manager::register(&request_id);
+    let result = tokio::select! {
+        _ = token.cancelled() => {
+            Err(AppError::new(ErrorKind::UserCancelled, "Pagination was cancelled"))
+        }
+        res = pagination::paginate(app, request, options) => res
+    };
+    manager::remove(&request_id);
+    result
+}
+
+/// Sends requests once for each row of a CSV or JSON data file, substituting the row's values
+/// for `{{column}}` placeholders, and reports the results per row. Cancelled by the run id.
+#[tauri::command(async)]
+async fn run_data_driven(
+    app: tauri::AppHandle,
+    mut requests: Vec<Request>,
+    options: DataRunOptions,
+) -> Result<DataRunReport, AppError> {
+    for request in &mut requests {
+        load_request_secrets(&app, request).await?;
+    }
+
+    let run_id = options.run_id.clone();
+    let token = manager::register(&run_id);
+    let result = tokio::select! {
+        _ = token.cancelled() => {
+            Err(AppError::new(ErrorKind::UserCancelled, "Data-driven run was cancelled"))
+        }
+        res = data_run::run(app, requests, options) => res
+    };
+    manager::remove(&run_id);
+    result
+}
+
+/// Sends one request with several concurrent workers for a number of iterations or a length of
+/// time, passing latency, error and throughput statistics to `on_stats` while it runs. Stopped
+/// by the run id with `cancel_http_request`, which keeps the results so far.
+#[tauri::command(async)]
+async fn run_load_test(
+    app: tauri::AppHandle,
+    mut request: Request,
+    options: LoadTestOptions,
+    on_stats: Channel<LoadTestStats>,
+) -> Result<LoadTestStats, AppError> {
+    load_request_secrets(&app, &mut request).await?;
+
+    let emitter = Arc::new(TauriLogEmitter::new(app));
+    let token = manager::register(&options.run_id);
+    let result = load_test::run(
+        &HyperEngine::new(),
+        request,
+        &options,
+        emitter,
+        &token,
+        |stats| {
+            let _ = on_stats.send(stats);
+        },
+    )
+    .await;
+    manager::remove(&options.run_id);
+    result
+}
+
+/// Runs a jq transform over a JSON response body and returns the result as a chart-ready
+/// series or table, so large bodies don't have to cross IPC to be plotted
+#[tauri::command(async)]
+async fn visualize_response(request: VisualizeRequest) -> Result<Visualization, AppError> {
+    tauri::async_runtime::spawn_blocking(move || visualize::visualize(request))
+        .await
+        .unwrap_or_else(|join_error| {
+            Err(AppError::new(
+                ErrorKind::IoError,
+                format!("Failed to run the transform: {join_error}"),
+            ))
+        })
+}
+
+/// Runs JSONPath, JMESPath or XPath expressions over a response body, inline or spooled to a
+/// file, and returns what they matched, so large bodies can be filtered without crossing IPC
+#[tauri::command(async)]
+async fn query_response_body(query: BodyQuery) -> Result<Vec<QueryResult>, AppError> {
+    tauri::async_runtime::spawn_blocking(move || body_query::query(query))
+        .await
+        .unwrap_or_else(|join_error| {
+            Err(AppError::new(
+                ErrorKind::IoError,
+                format!("Failed to run the query: {join_error}"),
+            ))
+        })
+}
+
+/// Decodes a response body, inline or spooled to a file, to text in the charset its byte order
+/// mark or `Content-Type` gives, e.g. Shift_JIS or GBK, so it isn't shown as mojibake
+#[tauri::command(async)]
+async fn decode_body_text(request: TextRequest) -> Result<DecodedText, AppError> {
+    tauri::async_runtime::spawn_blocking(move || charset::decode_body(request))
+        .await
+        .unwrap_or_else(|join_error| {
+            Err(AppError::new(
+                ErrorKind::IoError,
+                format!("Failed to decode the body: {join_error}"),
+            ))
+        })
+}
+
+/// Parses an XML response body, inline or spooled to a file, into its pretty-printed text and
+/// node tree
+#[tauri::command(async)]
+async fn parse_xml_body(request: XmlRequest) -> Result<XmlDocument, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let text = body_query::body_text(
+            request.body.as_deref(),
+            request.file_path.as_deref(),
+            request.content_type.as_deref(),
+        )?;
+        xml::parse(&text, request.indent, request.max_nodes)
+    })
+    .await
+    .unwrap_or_else(|join_error| {
+        Err(AppError::new(
+            ErrorKind::IoError,
+            format!("Failed to parse the body: {join_error}"),
+        ))
+    })
+}
+
+/// Decodes a Protocol Buffers or gRPC response body, inline or spooled to a file, to JSON with
+/// the message types of a `.proto` file or descriptor set
+#[tauri::command(async)]
+async fn decode_protobuf_body(request: ProtobufRequest) -> Result<DecodedProtobuf, AppError> {
+    tauri::async_runtime::spawn_blocking(move || protobuf::decode(request))
+        .await
+        .unwrap_or_else(|join_error| {
+            Err(AppError::new(
+                ErrorKind::IoError,
+                format!("Failed to decode the body: {join_error}"),
+            ))
+        })
+}
+
+/// Lists the message types of a `.proto` file or descriptor set, to pick a body's type from
+#[tauri::command(async)]
+async fn list_protobuf_message_types(schema: ProtoSchema) -> Result<Vec<String>, AppError> {
+    tauri::async_runtime::spawn_blocking(move || protobuf::message_types(&schema))
+        .await
+        .unwrap_or_else(|join_error| {
+            Err(AppError::new(
+                ErrorKind::IoError,
+                format!("Failed to load the schema: {join_error}"),
+            ))
+        })
+}
+
+/// Encrypts a request body as a JWE or PGP message for a partner's public key
+#[tauri::command(async)]
+async fn encrypt_payload(request: EncryptPayloadRequest) -> Result<String, AppError> {
+    payload_crypto::encrypt_payload(&request)
+}
+
+/// Decrypts a JWE or PGP response body with a private key from the keyring
+#[tauri::command(async)]
+async fn decrypt_payload(
+    app: tauri::AppHandle,
+    request: DecryptPayloadRequest,
+) -> Result<String, AppError> {
+    let key = signing_keys::load_signing_key(&app, &request.key_name)?;
+    payload_crypto::decrypt_payload(&request, &key)
+}
+
+/// Records the result of a monitor check for metrics export
+#[tauri::command(async)]
+async fn record_monitor_check(check: MonitorCheck) -> Result<(), AppError> {
+    metrics::record_check(check);
+    Ok(())
+}
+
+#[tauri::command(async)]
+async fn remove_monitor_metrics(monitor_id: String) -> Result<(), AppError> {
+    metrics::remove_monitor(&monitor_id);
+    Ok(())
+}
+
+/// Starts the localhost-only Prometheus `/metrics` endpoint and returns its address
+#[tauri::command(async)]
+async fn start_monitor_metrics_endpoint(port: Option<u16>) -> Result<String, AppError> {
+    metrics::start_endpoint(port).await
+}
+
+#[tauri::command(async)]
+async fn stop_monitor_metrics_endpoint() -> Result<bool, AppError> {
+    Ok(metrics::stop_endpoint())
+}
+
+/// Writes monitor metrics to a textfile for the node_exporter textfile collector
+#[tauri::command(async)]
+async fn write_monitor_metrics_file(path: String) -> Result<(), AppError> {
+    metrics::write_textfile(&path)
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // Install ring crypto provider for rustls
-    rustls::crypto::ring::default_provider()
-        .install_default()
-        .expect("Failed to install default crypto provider");
-
     let probe = StartupProbe::new();
     probe.mark("rust_start");
 
@@ -493,19 +1685,106 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .invoke_handler(tauri::generate_handler![
             send_http_request,
+            send_http_requests,
+            stream_http_request,
+            download_http_request,
+            send_sse_request,
+            send_ndjson_request,
+            introspect_graphql_schema,
+            get_cached_graphql_schema,
+            parse_wsdl,
+            open_socket,
+            send_socket,
+            close_socket,
+            start_recording_proxy,
+            stop_recording_proxy,
+            start_webhook_listener,
+            stop_webhook_listener,
+            start_static_server,
+            stop_static_server,
+            inspect_tls,
+            export_certificate_chain,
+            generate_self_signed_certificate,
             load_app_data,
             save_app_data,
             delete_app_data,
+            append_journal,
+            recover_journal,
+            checkpoint_journal,
+            snapshot_environment,
+            stage_secure_variable,
+            list_environment_snapshots,
+            restore_environment_snapshot,
+            record_history_entry,
+            list_history,
+            read_history_body,
+            clear_history,
+            search_workspace,
+            trash_app_item,
+            list_trash,
+            restore_trash_entry,
+            purge_trash,
+            get_annotation,
+            set_annotation,
+            delete_annotation,
+            query_annotations,
+            list_annotation_tags,
             get_data_encryption_key,
             set_data_encryption_key,
+            get_key_protection,
+            set_key_protection,
+            store_signing_key,
+            delete_signing_key,
+            store_keyring_secret,
+            load_keyring_secret,
+            delete_keyring_secret,
+            list_dotenv_variables,
+            audit_encryption_keys,
+            rewrap_app_data,
             get_app_data_dir,
             save_file,
             save_binary,
             open_file,
             delete_file,
+            materialize_response_file,
+            read_response_file_range,
+            read_response_file_chunk,
             discover_oidc,
             get_authentication_result,
+            revoke_token,
             cancel_http_request,
+            cancel_operation,
+            close_connection_group,
+            export_request_log,
+            export_har,
+            convert_postman_collection,
+            convert_insomnia_export,
+            convert_http_file,
+            export_http_file,
+            refresh_root_store,
+            list_dns_cache,
+            flush_dns_cache,
+            clear_response_cache,
+            canonicalize_request,
+            analyze_cors,
+            discover_local_services,
+            poll_until,
+            paginate,
+            run_data_driven,
+            run_load_test,
+            visualize_response,
+            query_response_body,
+            decode_body_text,
+            parse_xml_body,
+            decode_protobuf_body,
+            list_protobuf_message_types,
+            encrypt_payload,
+            decrypt_payload,
+            record_monitor_check,
+            remove_monitor_metrics,
+            start_monitor_metrics_endpoint,
+            stop_monitor_metrics_endpoint,
+            write_monitor_metrics_file,
         ]);
 
     probe.mark("plugins_configured");
@@ -528,6 +1807,9 @@ pub fn run() {
             }
         }
 
+        trash::schedule_purge(app.handle().clone());
+        spawn_preload(app.handle().clone(), setup_probe.clone());
+
         setup_probe.mark("setup_complete");
         Ok(())
     });
@@ -0,0 +1,336 @@
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::body::Incoming;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request as HyperRequest, Response as HyperResponse, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde::Deserialize;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+use crate::errors::{AppError, ErrorKind};
+
+/// Default port for the metrics endpoint (same as the OpenTelemetry Prometheus exporter).
+pub const DEFAULT_METRICS_PORT: u16 = 9464;
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Pause after a failed accept, so errors that persist (e.g. running out of file descriptors)
+/// don't spin the accept loop
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(250);
+
+/// Result of a single monitor check, reported by the monitor scheduler.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MonitorCheck {
+    /// Stable identifier of the monitor
+    pub monitor_id: String,
+    /// Display name of the monitor
+    pub name: Option<String>,
+    /// URL that was checked
+    pub url: Option<String>,
+    /// HTTP status code, if a response was received
+    pub status: Option<u16>,
+    /// Whether the check passed
+    pub success: bool,
+    /// Check latency in milliseconds
+    pub latency_ms: u64,
+    /// Unix timestamp (seconds) of the check. Defaults to now.
+    pub timestamp: Option<i64>,
+}
+
+type SampleFn = fn(&MonitorSeries) -> Option<String>;
+
+#[derive(Debug, Clone)]
+struct MonitorSeries {
+    last: MonitorCheck,
+    checks_total: u64,
+    failures_total: u64,
+}
+
+/// In-memory store of the latest monitor results, rendered in Prometheus text format.
+#[derive(Debug, Default)]
+pub struct MetricsRegistry {
+    series: BTreeMap<String, MonitorSeries>,
+}
+
+impl MetricsRegistry {
+    pub fn record(&mut self, mut check: MonitorCheck) {
+        if check.timestamp.is_none() {
+            check.timestamp = Some(chrono::Utc::now().timestamp());
+        }
+        let failed = !check.success;
+        let entry = self
+            .series
+            .entry(check.monitor_id.clone())
+            .or_insert_with(|| MonitorSeries {
+                last: check.clone(),
+                checks_total: 0,
+                failures_total: 0,
+            });
+        entry.checks_total += 1;
+        if failed {
+            entry.failures_total += 1;
+        }
+        entry.last = check;
+    }
+
+    pub fn remove(&mut self, monitor_id: &str) {
+        self.series.remove(monitor_id);
+    }
+
+    /// Render all series using the Prometheus text exposition format (version 0.0.4).
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let metrics: [(&str, &str, &str, SampleFn); 6] = [
+            (
+                "knurl_monitor_up",
+                "gauge",
+                "Whether the last check succeeded (1) or failed (0).",
+                |s| Some(if s.last.success { "1" } else { "0" }.to_string()),
+            ),
+            (
+                "knurl_monitor_latency_milliseconds",
+                "gauge",
+                "Latency of the last check in milliseconds.",
+                |s| Some(s.last.latency_ms.to_string()),
+            ),
+            (
+                "knurl_monitor_status_code",
+                "gauge",
+                "HTTP status code returned by the last check.",
+                |s| s.last.status.map(|code| code.to_string()),
+            ),
+            (
+                "knurl_monitor_last_check_timestamp_seconds",
+                "gauge",
+                "Unix time of the last check.",
+                |s| s.last.timestamp.map(|ts| ts.to_string()),
+            ),
+            (
+                "knurl_monitor_checks_total",
+                "counter",
+                "Number of checks recorded since startup.",
+                |s| Some(s.checks_total.to_string()),
+            ),
+            (
+                "knurl_monitor_failures_total",
+                "counter",
+                "Number of failed checks recorded since startup.",
+                |s| Some(s.failures_total.to_string()),
+            ),
+        ];
+
+        for (name, kind, help, value) in metrics {
+            out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} {kind}\n"));
+            for series in self.series.values() {
+                if let Some(v) = value(series) {
+                    out.push_str(&format!("{name}{{{}}} {v}\n", format_labels(&series.last)));
+                }
+            }
+        }
+        out
+    }
+}
+
+fn format_labels(check: &MonitorCheck) -> String {
+    let mut labels = vec![format!("monitor=\"{}\"", escape_label(&check.monitor_id))];
+    if let Some(name) = &check.name {
+        labels.push(format!("name=\"{}\"", escape_label(name)));
+    }
+    if let Some(url) = &check.url {
+        labels.push(format!("url=\"{}\"", escape_label(url)));
+    }
+    labels.join(",")
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+static REGISTRY: OnceLock<Mutex<MetricsRegistry>> = OnceLock::new();
+static ENDPOINT: OnceLock<Mutex<Option<(SocketAddr, CancellationToken)>>> = OnceLock::new();
+
+fn registry() -> &'static Mutex<MetricsRegistry> {
+    REGISTRY.get_or_init(|| Mutex::new(MetricsRegistry::default()))
+}
+
+fn endpoint() -> &'static Mutex<Option<(SocketAddr, CancellationToken)>> {
+    ENDPOINT.get_or_init(|| Mutex::new(None))
+}
+
+pub fn record_check(check: MonitorCheck) {
+    registry().lock().unwrap().record(check);
+}
+
+pub fn remove_monitor(monitor_id: &str) {
+    registry().lock().unwrap().remove(monitor_id);
+}
+
+pub fn render() -> String {
+    registry().lock().unwrap().render()
+}
+
+/// Write the current metrics to `path` for the node_exporter textfile collector.
+/// The file is replaced atomically so a scrape never observes a partial write.
+pub fn write_textfile(path: &str) -> Result<(), AppError> {
+    let target = Path::new(path);
+    let dir = target
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let mut temp = tempfile::Builder::new()
+        .prefix(".knurl-metrics-")
+        .tempfile_in(dir)?;
+    {
+        use std::io::Write;
+        temp.write_all(render().as_bytes())?;
+    }
+    temp.persist(target).map_err(|e| AppError::from(e.error))?;
+    Ok(())
+}
+
+/// Start serving `GET /metrics` on 127.0.0.1. Returns the bound address.
+pub async fn start_endpoint(port: Option<u16>) -> Result<String, AppError> {
+    // Checked, bound and recorded under one lock so two starts can't both get past the check.
+    // The bind is the blocking std one, as the guard can't be held across an await.
+    let (listener, addr, token) = {
+        let mut endpoint = endpoint().lock().unwrap();
+        if let Some((addr, _)) = endpoint.as_ref() {
+            return Err(AppError::new(
+                ErrorKind::BadRequest,
+                format!("Metrics endpoint already running on {addr}"),
+            ));
+        }
+        let bind = SocketAddr::from((Ipv4Addr::LOCALHOST, port.unwrap_or(DEFAULT_METRICS_PORT)));
+        let listener = std::net::TcpListener::bind(bind)?;
+        listener.set_nonblocking(true)?;
+        let listener = TcpListener::from_std(listener)?;
+        let addr = listener.local_addr()?;
+        let token = CancellationToken::new();
+        *endpoint = Some((addr, token.clone()));
+        (listener, addr, token)
+    };
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => break,
+                accepted = listener.accept() => {
+                    let stream = match accepted {
+                        Ok((stream, _)) => stream,
+                        Err(e) => {
+                            log::warn!("Monitor metrics endpoint on {addr} failed to accept: {e}");
+                            tokio::select! {
+                                _ = token.cancelled() => break,
+                                _ = tokio::time::sleep(ACCEPT_BACKOFF) => continue,
+                            }
+                        }
+                    };
+                    tokio::spawn(async move {
+                        let _ = http1::Builder::new()
+                            .serve_connection(TokioIo::new(stream), service_fn(handle_scrape))
+                            .await;
+                    });
+                }
+            }
+        }
+        log::info!("Monitor metrics endpoint on {addr} stopped");
+    });
+
+    log::info!("Monitor metrics endpoint listening on http://{addr}/metrics");
+    Ok(addr.to_string())
+}
+
+pub fn stop_endpoint() -> bool {
+    match endpoint().lock().unwrap().take() {
+        Some((_, token)) => {
+            token.cancel();
+            true
+        }
+        None => false,
+    }
+}
+
+async fn handle_scrape(
+    req: HyperRequest<Incoming>,
+) -> Result<HyperResponse<Full<Bytes>>, Infallible> {
+    let response = if req.method() == Method::GET && req.uri().path() == "/metrics" {
+        HyperResponse::builder()
+            .status(StatusCode::OK)
+            .header(hyper::header::CONTENT_TYPE, CONTENT_TYPE)
+            .body(Full::from(render()))
+    } else {
+        HyperResponse::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Full::from("Not Found"))
+    };
+    Ok(response.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MetricsRegistry, MonitorCheck, escape_label, start_endpoint, stop_endpoint};
+
+    fn check(id: &str, success: bool, status: Option<u16>) -> MonitorCheck {
+        MonitorCheck {
+            monitor_id: id.to_string(),
+            name: Some("Health".to_string()),
+            url: Some("https://example.com/health".to_string()),
+            status,
+            success,
+            latency_ms: 42,
+            timestamp: Some(1_700_000_000),
+        }
+    }
+
+    #[test]
+    fn renders_gauges_and_counters_per_monitor() {
+        let mut reg = MetricsRegistry::default();
+        reg.record(check("m1", true, Some(200)));
+        reg.record(check("m1", false, Some(503)));
+        let text = reg.render();
+        let labels = r#"monitor="m1",name="Health",url="https://example.com/health""#;
+        assert!(text.contains("# TYPE knurl_monitor_up gauge"));
+        assert!(text.contains(&format!("knurl_monitor_up{{{labels}}} 0\n")));
+        assert!(text.contains(&format!("knurl_monitor_status_code{{{labels}}} 503\n")));
+        assert!(text.contains(&format!("knurl_monitor_checks_total{{{labels}}} 2\n")));
+        assert!(text.contains(&format!("knurl_monitor_failures_total{{{labels}}} 1\n")));
+        assert!(text.contains(&format!(
+            "knurl_monitor_latency_milliseconds{{{labels}}} 42\n"
+        )));
+    }
+
+    #[test]
+    fn omits_status_when_no_response_and_removes_series() {
+        let mut reg = MetricsRegistry::default();
+        reg.record(check("m2", false, None));
+        assert!(!reg.render().contains("knurl_monitor_status_code{"));
+        reg.remove("m2");
+        assert!(!reg.render().contains("m2"));
+    }
+
+    #[test]
+    fn escapes_label_values() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+
+    #[tokio::test]
+    async fn starts_one_endpoint_at_a_time() {
+        let (first, second) = tokio::join!(start_endpoint(Some(0)), start_endpoint(Some(0)));
+        assert!(first.is_ok() != second.is_ok());
+        assert!(stop_endpoint());
+        assert!(!stop_endpoint());
+    }
+}
@@ -0,0 +1,265 @@
+//! Progress and lifecycle events of long-running backend operations, such as large downloads
+//! and key rotation. All of them are emitted as `operation-progress` events, so the UI can show
+//! progress and offer cancellation the same way for each.
+
+use crate::errors::{AppError, ErrorKind};
+use crate::http_client::manager;
+use serde::Serialize;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio_util::sync::CancellationToken;
+
+pub const OPERATION_EVENT: &str = "operation-progress";
+
+/// Least time between progress events of one operation
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum OperationKind {
+    /// A response body too large to keep in memory, spooled to disk
+    Download,
+    /// Re-encrypting app data with the active key
+    KeyRotation,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum OperationState {
+    Started,
+    Progress,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationEvent {
+    /// Cancels the operation with `cancel_operation`; a download's is its request id
+    pub operation_id: String,
+    pub kind: OperationKind,
+    pub state: OperationState,
+    /// Units done so far: bytes for downloads, files for key rotation
+    pub processed: u64,
+    /// Units in all, when known
+    pub total: Option<u64>,
+    /// Share done, 0 to 100, when the total is known
+    pub percent: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Where operation events go
+#[derive(Clone)]
+pub struct ProgressSink(Arc<dyn Fn(OperationEvent) + Send + Sync>);
+
+impl ProgressSink {
+    pub fn new(send: impl Fn(OperationEvent) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(send))
+    }
+
+    /// Emits events to the app's windows
+    pub fn for_app(app: &AppHandle) -> Self {
+        let app = app.clone();
+        Self::new(move |event| {
+            let _ = app.emit(OPERATION_EVENT, event);
+        })
+    }
+}
+
+impl fmt::Debug for ProgressSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressSink")
+    }
+}
+
+/// A running operation. Emits `Started` when created, throttled `Progress` as it advances and
+/// one final state: `Completed`, `Failed` or, when dropped unfinished, `Cancelled`.
+pub struct Operation {
+    id: String,
+    kind: OperationKind,
+    sink: ProgressSink,
+    total: Option<u64>,
+    processed: AtomicU64,
+    last_progress: Mutex<Instant>,
+    /// Set when the operation registered its own cancellation token
+    token: Option<CancellationToken>,
+    finished: AtomicBool,
+}
+
+impl Operation {
+    /// Starts an operation cancellable with `cancel_operation`
+    pub fn start(sink: ProgressSink, kind: OperationKind, total: Option<u64>) -> Self {
+        let id = uuid::Uuid::new_v4().to_string();
+        let token = manager::register(&id);
+        Self::new(sink, id, kind, total, Some(token))
+    }
+
+    /// Starts an operation of an HTTP request, which is cancelled with the request
+    pub fn for_request(
+        sink: ProgressSink,
+        request_id: &str,
+        kind: OperationKind,
+        total: Option<u64>,
+    ) -> Self {
+        Self::new(sink, request_id.to_string(), kind, total, None)
+    }
+
+    fn new(
+        sink: ProgressSink,
+        id: String,
+        kind: OperationKind,
+        total: Option<u64>,
+        token: Option<CancellationToken>,
+    ) -> Self {
+        let operation = Self {
+            id,
+            kind,
+            sink,
+            total: total.filter(|total| *total > 0),
+            processed: AtomicU64::new(0),
+            last_progress: Mutex::new(Instant::now()),
+            token,
+            finished: AtomicBool::new(false),
+        };
+        operation.emit(OperationState::Started, None);
+        operation
+    }
+
+    /// Records that `processed` units are done, emitting progress at most every 100 ms.
+    pub fn advance(&self, processed: u64) {
+        self.processed.store(processed, Ordering::Relaxed);
+        {
+            let mut last = self.last_progress.lock().unwrap();
+            if last.elapsed() < PROGRESS_INTERVAL {
+                return;
+            }
+            *last = Instant::now();
+        }
+        self.emit(OperationState::Progress, None);
+    }
+
+    /// Fails with `UserCancelled` once the operation was asked to stop
+    pub fn check_cancelled(&self) -> Result<(), AppError> {
+        if self.token.as_ref().is_some_and(|t| t.is_cancelled()) {
+            return Err(AppError::new(
+                ErrorKind::UserCancelled,
+                "Operation was cancelled",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Emits the final state for `result` and passes it through.
+    pub fn finish<T>(self, result: Result<T, AppError>) -> Result<T, AppError> {
+        self.finished.store(true, Ordering::Relaxed);
+        match &result {
+            Ok(_) => self.emit(OperationState::Completed, None),
+            Err(e) if e.kind == ErrorKind::UserCancelled => {
+                self.emit(OperationState::Cancelled, Some(e.message.clone()))
+            }
+            Err(e) => self.emit(OperationState::Failed, Some(e.message.clone())),
+        }
+        result
+    }
+
+    fn emit(&self, state: OperationState, message: Option<String>) {
+        let processed = self.processed.load(Ordering::Relaxed);
+        let percent = self
+            .total
+            .map(|total| (processed as f64 / total as f64 * 100.0).min(100.0));
+        self.sink.0(OperationEvent {
+            operation_id: self.id.clone(),
+            kind: self.kind,
+            state,
+            processed,
+            total: self.total,
+            percent,
+            message,
+        });
+    }
+}
+
+impl Drop for Operation {
+    fn drop(&mut self) {
+        if self.token.is_some() {
+            manager::remove(&self.id);
+        }
+        // Dropped unfinished, e.g. a download whose request was cancelled
+        if !self.finished.load(Ordering::Relaxed) {
+            self.emit(OperationState::Cancelled, None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Operation, OperationEvent, OperationKind, OperationState, ProgressSink};
+    use crate::errors::ErrorKind;
+    use crate::http_client::manager;
+    use std::sync::{Arc, Mutex};
+
+    fn collecting_sink() -> (ProgressSink, Arc<Mutex<Vec<OperationEvent>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = {
+            let events = events.clone();
+            ProgressSink::new(move |event| events.lock().unwrap().push(event))
+        };
+        (sink, events)
+    }
+
+    fn states(events: &Mutex<Vec<OperationEvent>>) -> Vec<OperationState> {
+        events.lock().unwrap().iter().map(|e| e.state).collect()
+    }
+
+    #[test]
+    fn reports_lifecycle_and_percent() {
+        let (sink, events) = collecting_sink();
+        let operation = Operation::start(sink, OperationKind::KeyRotation, Some(4));
+        operation.advance(1);
+        assert!(operation.check_cancelled().is_ok());
+        operation.advance(4);
+        let _ = operation.finish(Ok(()));
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.first().unwrap().state, OperationState::Started);
+        let last = events.last().unwrap();
+        assert_eq!(last.state, OperationState::Completed);
+        assert_eq!(last.percent, Some(100.0));
+        assert_eq!(last.processed, 4);
+    }
+
+    #[test]
+    fn cancellation_is_reported_once() {
+        let (sink, events) = collecting_sink();
+        let operation = Operation::start(sink, OperationKind::KeyRotation, None);
+        let id = events.lock().unwrap()[0].operation_id.clone();
+        assert!(manager::cancel(&id));
+        let err = operation.check_cancelled().unwrap_err();
+        assert_eq!(err.kind, ErrorKind::UserCancelled);
+        let _ = operation.finish::<()>(Err(err));
+        assert_eq!(
+            states(&events),
+            vec![OperationState::Started, OperationState::Cancelled]
+        );
+        // Its token is gone with it
+        assert!(!manager::cancel(&id));
+
+        // A request's operation dropped unfinished was cancelled with its request
+        let (sink, events) = collecting_sink();
+        drop(Operation::for_request(
+            sink,
+            "req",
+            OperationKind::Download,
+            Some(10),
+        ));
+        assert_eq!(
+            states(&events),
+            vec![OperationState::Started, OperationState::Cancelled]
+        );
+    }
+}
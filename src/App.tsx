@@ -2,12 +2,15 @@ import { useEffect } from "react"
 
 import { MainWindow } from "@/components/windows/main"
 import { warmPrettier } from "@/lib/prettier"
+import { startMonitors } from "@/request/monitors"
 
 function App() {
   useEffect(() => {
     warmPrettier()
   }, [])
 
+  useEffect(() => startMonitors(), [])
+
   return <MainWindow />
 }
 
@@ -1,7 +1,7 @@
 import { describe, it, expect } from "vitest"
 import { mockIPC } from "@tauri-apps/api/mocks"
 
-import { isAppError, sendHttpRequest } from "./knurl"
+import { isAppError, sendHttpRequest, streamHttpRequest } from "./knurl"
 
 // Minimal request payload respecting the Request class shape in bindings
 const minimalRequest: any = {
@@ -122,6 +122,30 @@ describe("bindings contracts: sendHttpRequest Response shape", () => {
   })
 })
 
+describe("bindings contracts: streamHttpRequest", () => {
+  it("assembles the body from channel chunks and waits for the end marker", async () => {
+    const encoder = new TextEncoder()
+    mockIPC((cmd, payload) => {
+      if (cmd === "stream_http_request") {
+        const { onChunk } = payload as any
+        onChunk.onmessage(encoder.encode("pay").buffer)
+        onChunk.onmessage(encoder.encode("load").buffer)
+        // The end marker may arrive after the command returns
+        setTimeout(() => onChunk.onmessage(new ArrayBuffer(0)), 0)
+        return { requestId: "id-1", status: 200, statusText: "OK", headers: [], body: [], size: 7 }
+      }
+    })
+
+    const received: string[] = []
+    const request = { requestId: "id-1", url: "https://example.com", method: "GET" } as any
+    const res = await streamHttpRequest(request, (chunk) => received.push(new TextDecoder().decode(chunk)))
+
+    expect(received).toEqual(["pay", "load"])
+    expect(new TextDecoder().decode(res.body)).toBe("payload")
+    expect(res.size).toBe(7)
+  })
+})
+
 // ---------------------------------------------------------------------------
 // Consolidated tests from: knurl.appdata.contracts.test.ts
 // ---------------------------------------------------------------------------
@@ -168,10 +192,23 @@ describe("bindings contracts: app data commands", () => {
       }
     })
 
-    await saveAppData("collections/c1.json", { id: "c1", name: "C1" } as any)
+    await expect(saveAppData("collections/c1.json", { id: "c1", name: "C1" } as any)).resolves.toEqual([])
     expect(saved.id).toBe("c1")
     await deleteAppData("collections/c1.json")
   })
+
+  it("saveAppData passes securePointers and returns secret findings", async () => {
+    const finding = { pointer: "/content/headers/h1/value", kind: "jwt", preview: "Bear…sR8U", markable: true }
+    mockIPC((cmd, payload) => {
+      if (cmd === "save_app_data") {
+        const { securePointers } = payload as any
+        expect(securePointers).toEqual(["/content/auth/value"])
+        return [finding]
+      }
+    })
+
+    await expect(saveAppData("settings.json", {}, ["/content/auth/value"])).resolves.toEqual([finding])
+  })
 })
 
 // ---------------------------------------------------------------------------
@@ -1,4 +1,4 @@
-import { invoke } from "@tauri-apps/api/core"
+import { Channel, invoke } from "@tauri-apps/api/core"
 
 /**
  * Well-defined error kinds for the application.
@@ -16,6 +16,7 @@ export type ErrorKind =
   | "InvalidKeyLength"
   | "DecryptionFailed"
   | "EncryptionFailed"
+  | "KeyMismatch"
 
   // Keyring errors
   | "KeyringPlatformFailure"
@@ -89,16 +90,22 @@ export interface Request {
    */
   url: string
 
+  /**
+   * Query parameters the backend appends to `url`, after any it already has, in order. Names and values are
+   * percent-encoded unless `encode` is false.
+   */
+  queryParams?: QueryParam[]
+
   /**
    * HTTP method (e.g., "GET", "POST").
    */
   method: string
 
   /**
-   * Optional map of header key/value pairs.
-   * Keys are case-insensitive per HTTP, but original casing may be preserved by caller.
+   * Header name/value pairs, sent in this order. Names may repeat to send a header more than once; repeats are
+   * sent together, where the name first appears. A name-to-value map is accepted too.
    */
-  headers?: Record<string, string>
+  headers?: Array<[string, string]> | Record<string, string>
 
   /**
    * Optional request body as raw bytes.
@@ -110,21 +117,48 @@ export interface Request {
    */
   disableSsl: boolean | undefined
 
+  /**
+   * Hosts whose certificates aren't verified, or aren't checked for their name, while other hosts are verified
+   * as usual.
+   */
+  insecureHosts?: InsecureHost[]
+
+  /**
+   * Check the server's certificates for revocation over OCSP, or their CRLs, on each new connection. A revoked
+   * certificate fails the connection unless `disableSsl` is set.
+   */
+  checkRevocation?: boolean
+
   /**
    * Path to a custom root CA bundle (PEM format).
    */
   caPath: string | undefined
 
+  /** Oldest TLS version to offer. Defaults to "1.2". */
+  tlsMinVersion?: TlsVersion
+
+  /** Newest TLS version to offer. Defaults to "1.3". */
+  tlsMaxVersion?: TlsVersion
+
+  /** Cipher suites to offer, in order of preference, by IANA name (e.g. "TLS_AES_128_GCM_SHA256"). */
+  cipherSuites?: string[]
+
   /**
    * Hostname for custom DNS override (e.g., "api.example.com").
    */
   hostOverride: string | undefined
 
   /**
-   * IP address to resolve `hostOverride` to (e.g., "127.0.0.1").
+   * IP address to resolve the URL's host to (e.g., "127.0.0.1"); shorthand for a `dnsOverrides` entry.
    */
   ipOverride: string | undefined
 
+  /**
+   * Hosts to resolve to fixed IPs in place of a DNS lookup, like per-request /etc/hosts entries. They apply to
+   * every host the request connects to, redirects included.
+   */
+  dnsOverrides?: DnsOverride[]
+
   /**
    * Timeout in seconds for the request.
    */
@@ -143,6 +177,17 @@ export interface Request {
    */
   httpVersion?: "auto" | "http1" | "http2"
 
+  /**
+   * Address family to connect over. "auto" (the default) races the host's first IPv4 and IPv6 addresses.
+   */
+  ipFamily?: "auto" | "ipv4" | "ipv6"
+
+  /** Local IP to connect from, on machines with several addresses. Limits connections to its address family. */
+  localAddress?: string
+
+  /** Network interface to connect through, e.g. "eth1" or "utun3". Not supported on Windows. */
+  interface?: string
+
   // Backend uses a single HTTP engine (Hyper); deprecated engine selection removed.
 
   /**
@@ -175,6 +220,17 @@ export interface Request {
    */
   bodyFilePath?: string
 
+  /**
+   * Send a GraphQL operation as the JSON body, in place of `body`. Ignored with `multipartParts` or `bodyFilePath`.
+   */
+  graphql?: GraphqlOperation
+
+  /**
+   * Send a SOAP operation as the XML body, its envelope built in the backend from the operation's parameters. Ignored
+   * with `multipartParts`, `bodyFilePath` or `graphql`.
+   */
+  soap?: SoapOperation
+
   /**
    * Maximum number of redirects to follow automatically. 0 disables.
    */
@@ -185,8 +241,376 @@ export interface Request {
    * Used to keep memory bounded and align with UI preview limits.
    */
   previewMaxBytes?: number
+
+  /**
+   * Stop reading the response body after this many bytes, returning what was read with `truncated` set.
+   * Unlimited if not provided.
+   */
+  maxResponseBytes?: number
+
+  /**
+   * Write the response body straight to this file rather than returning it; the response then carries only
+   * metadata, with `savedTo` set.
+   */
+  downloadToPath?: string
+
+  /**
+   * Continue the download an earlier request left unfinished at `downloadToPath`, unless the resource changed since.
+   */
+  resumeDownload?: boolean
+
+  /**
+   * Bytes of the resource to request, as `start-end`, `start-` or `-suffix`; sent as the `Range` header.
+   */
+  range?: string
+
+  /**
+   * Keep `GET` responses that carry an `ETag` or `Last-Modified`, and revalidate them with
+   * `If-None-Match`/`If-Modified-Since` when the same request is sent again.
+   */
+  responseCache?: boolean
+
+  /**
+   * Client certificate presented during the TLS handshake (mutual TLS).
+   */
+  clientCertificate?: ClientCertificate
+
+  /**
+   * Compress the request body with this encoding and set the matching `Content-Encoding` header.
+   */
+  contentEncoding?: ContentEncoding
+
+  /**
+   * Rewrite a JSON body in canonical form (RFC 8785 JCS) before it's digested, signed and sent. Ignored for
+   * multipart bodies.
+   */
+  canonicalizeJson?: boolean
+
+  /**
+   * Send the header block exactly as `headers` lists it: names as cased, in order, and no automatic Host,
+   * User-Agent or Content-Length. Forces HTTP/1.1 and a new connection.
+   */
+  rawHeaders?: boolean
+
+  /** Turn off headers the engine adds on its own. Raw header mode turns them all off. */
+  autoHeaders?: AutoHeaders
+
+  /**
+   * Add a SHA-256 digest of the body as sent, after any compression. Computed before signing, so the
+   * signature can cover `content-digest`.
+   */
+  bodyDigest?: BodyDigest
+
+  /**
+   * Keep-alive connection pooling; without it each request opens its own connection.
+   */
+  connectionPool?: ConnectionPool
+
+  /**
+   * Requests with the same group share one dedicated keep-alive connection, e.g. the steps of a
+   * chained sequence. Implies pooling; send the steps one at a time.
+   */
+  connectionGroup?: string
+
+  /**
+   * Retry 429/503 responses, waiting as long as their `Retry-After` header asks.
+   */
+  retry?: RetryPolicy
+
+  /**
+   * Authenticate with NTLM over one HTTP/1.1 connection.
+   */
+  ntlm?: NtlmCredentials
+
+  /**
+   * Answer a 401's `WWW-Authenticate` challenge once with these credentials, like curl's `--anyauth`.
+   */
+  challengeAuth?: ChallengeCredentials
+
+  /**
+   * Sign the request (RFC 9421) with a key from the keyring. Re-signed for each redirect and retry.
+   */
+  signature?: MessageSignature
+
+  /**
+   * Values for `{{name}}` placeholders in the URL, query, headers, body, multipart parts and pre-request
+   * hook, substituted by the backend just before sending. Secrets are passed as references and read there;
+   * `{{secret:name}}` needs no variable and is the secret stored with `storeKeyringSecret`.
+   * Names no variable has may be generators, given a fresh value each time: `{{uuid}}`, `{{timestamp}}`,
+   * `{{timestampMs}}`, `{{isoDate}}` (the times take an offset such as `+1d` or `-30m`) and `{{randomInt 1 100}}`.
+   */
+  variables?: Record<string, VariableValue>
+
+  /**
+   * Steps run in order before the request is built, e.g. to add a timestamp and an HMAC of the body for a
+   * vendor's signing scheme. Run once; redirects and retries reuse the result.
+   */
+  preRequest?: HookStep[]
+
+  /**
+   * Send fewer log entries, e.g. for runs of thousands of requests. The response's `logSummary` still
+   * counts them.
+   */
+  logCapture?: LogCapture
+
+  /**
+   * Keep the exchange for `exportHar`, with secrets masked and response bodies over 256 KiB left out. Off unless
+   * set, so a streamed body isn't copied for nothing.
+   */
+  keepHar?: boolean
+
+  /**
+   * Checks of the response, evaluated once it has arrived. Results come back in the response's `assertions`
+   * and are logged, whether they pass or fail.
+   */
+  assertions?: Assertion[]
+
+  /**
+   * Values pulled from the response into named variables once it has arrived, returned in the response's
+   * `variables`. Data runs substitute them into the row's later requests.
+   */
+  extractors?: Extractor[]
+}
+
+/**
+ * A check of a response. Mirrors Rust `Assertion` in `http_client/assertions.rs`.
+ */
+export type Assertion =
+  | { type: "status"; equals: number }
+  /** Present and, when given, a value equals `equals` or matches the regular expression `matches` */
+  | { type: "header"; name: string; equals?: string; matches?: string }
+  /** Selects a node of the JSON body and, when given, one equal to `equals` */
+  | { type: "jsonPath"; path: string; equals?: unknown }
+  | { type: "bodyContains"; text: string }
+  | { type: "latencyUnder"; ms: number }
+
+/**
+ * Mirrors Rust `AssertionResult` in `http_client/assertions.rs`.
+ */
+export type AssertionResult = {
+  assertion: Assertion
+  passed: boolean
+  /** What the response held, e.g. the status or the header's values */
+  actual?: string
+  message: string
+}
+
+/**
+ * Where a variable's value comes from. Mirrors Rust `Extractor` in `http_client/extractors.rs`.
+ */
+export type Extractor =
+  /** The first node selected; strings as they are, other values as JSON */
+  | { type: "jsonPath"; name: string; path: string }
+  /** An XPath 1.0 expression; a node set gives the text of its first node */
+  | { type: "xpath"; name: string; path: string }
+  /** The header's values, joined with ", " */
+  | { type: "header"; name: string; header: string }
+  /** The first match in the body: `group` when given, else the first group if any, else the whole match */
+  | { type: "regex"; name: string; pattern: string; group?: number }
+
+/**
+ * Mirrors Rust `ExtractedVariable` in `http_client/extractors.rs`.
+ */
+export type ExtractedVariable = {
+  name: string
+  /** Null when the response didn't hold the value */
+  value: string | null
+  /** Why there's no value */
+  message?: string
+}
+
+/**
+ * Mirrors Rust `LogCapture` in `http_client/request.rs`.
+ */
+export type LogCapture =
+  | { mode: "full" }
+  /** Every entry of a random `rate` (0 to 1) share of requests, none of the others */
+  | { mode: "sampled"; rate: number }
+  | { mode: "off" }
+
+/**
+ * Mirrors Rust `BodyDigest` in `http_client/request.rs`.
+ * `contentDigest`: `Content-Digest: sha-256=:…:` (RFC 9530). `digest`: `Digest: SHA-256=…` (RFC 3230).
+ */
+export type BodyDigest = "contentDigest" | "digest" | "both"
+
+/**
+ * Mirrors Rust `MessageSignature` in `http_client/request.rs`.
+ */
+export type MessageSignature = {
+  /** Name the key is stored under with `storeSigningKey` */
+  keyName: string
+  algorithm: "ed25519" | "rsa-pss-sha512" | "ecdsa-p256-sha256" | "hmac-sha256"
+  keyId?: string
+  /** Signature label (default "sig1") */
+  label?: string
+  /** Covered components in order, e.g. `["@method", "@authority", "@path"]` */
+  components: string[]
+  expiresInSecs?: number
+  nonce?: boolean
+  tag?: string
+}
+
+/**
+ * Where a secret variable is kept. Mirrors Rust `SecretRef` in `http_client/variables.rs`.
+ * AWS secrets are read with credentials from the `AWS_ACCESS_KEY_ID` environment variables, then the profile's
+ * keys or `credential_process` in `~/.aws/credentials` and `~/.aws/config`. SSO, assumed-role, web identity,
+ * container and instance credentials aren't supported; wrap them with `credential_process`.
+ */
+export type SecretRef =
+  /** A secure variable of a collection's environment, as last staged with `stageSecureVariable` or else as saved */
+  | { source: "environment"; collectionId: string; environmentId: string; variableId: string }
+  | { source: "keyring"; name: string }
+  /** `jsonKey` picks one key of a secret holding a JSON object. Region and profile default to the AWS environment's. */
+  | { source: "awsSecretsManager"; secretId: string; jsonKey?: string; region?: string; profile?: string }
+  /** A `SecureString` parameter is decrypted */
+  | { source: "awsSsm"; name: string; region?: string; profile?: string }
+  /** A variable of the app's process environment */
+  | { source: "processEnv"; name: string }
+  /** A variable defined in the `.env` file at `path` */
+  | { source: "dotenv"; path: string; name: string }
+
+/**
+ * A request variable's value, or a reference to a secret the backend resolves. Mirrors Rust `VariableValue`
+ * in `http_client/variables.rs`.
+ */
+export type VariableValue = string | { secret: SecretRef }
+
+/**
+ * Mirrors Rust `HookEncoding` in `http_client/request.rs`. `base64Url` is URL-safe and unpadded.
+ */
+export type HookEncoding = "hex" | "base64" | "base64Url"
+
+/**
+ * Mirrors Rust `HookAlgorithm` in `http_client/request.rs`.
+ */
+export type HookAlgorithm = "sha1" | "sha256" | "sha384" | "sha512"
+
+/**
+ * One step of a pre-request hook. Mirrors Rust `HookStep` in `http_client/request.rs`.
+ * Text fields are templates: `{{name}}` is the value an earlier step stored under `name`, and
+ * `{{request.method}}`, `{{request.url}}`, `{{request.host}}`, `{{request.path}}`, `{{request.query}}`,
+ * `{{request.body}}` and `{{request.header.<name>}}` read the request as the earlier steps left it. Unknown
+ * names fail the request.
+ */
+export type HookStep =
+  | { type: "set"; name: string; value: string }
+  | { type: "timestamp"; name: string; format: "unixSeconds" | "unixMillis" | "iso8601" | "httpDate" }
+  /** `bytes` random bytes (default 16), hex unless `encoding` says otherwise */
+  | { type: "nonce"; name: string; bytes?: number; encoding?: HookEncoding }
+  | { type: "hash"; name: string; algorithm: HookAlgorithm; input: string; encoding?: HookEncoding }
+  /** `key` is a UTF-8 secret unless `keyEncoding` says how it's encoded */
+  | {
+      type: "hmac"
+      name: string
+      algorithm: HookAlgorithm
+      key: string
+      keyEncoding?: HookEncoding
+      input: string
+      encoding?: HookEncoding
+    }
+  /** Replaces any header of the same name */
+  | { type: "setHeader"; name: string; value: string }
+  | { type: "removeHeader"; name: string }
+  /** Replaces any `queryParams` entry of the same name */
+  | { type: "setQueryParam"; name: string; value: string }
+
+/**
+ * Mirrors Rust `NtlmCredentials` in `http_client/request.rs`.
+ */
+export type NtlmCredentials = {
+  /** May be `DOMAIN\\user` or `user@domain` when `domain` is not set */
+  username: string
+  password: string
+  domain?: string | null
+  workstation?: string | null
+}
+
+/**
+ * Mirrors Rust `ChallengeCredentials` in `http_client/request.rs`.
+ */
+export type ChallengeCredentials = {
+  username: string
+  password: string
+  /** Schemes the credentials may answer; any of them when empty */
+  schemes?: ("basic" | "digest" | "negotiate")[]
+}
+
+/**
+ * Mirrors Rust `RetryPolicy` in `http_client/request.rs`.
+ */
+export type RetryPolicy = {
+  enabled: boolean
+  /** Retries after the first attempt (default 3) */
+  maxRetries?: number
+  /** Longest wait before a retry; a longer `Retry-After` ends the retries (default 60) */
+  maxDelaySecs?: number
+}
+
+/**
+ * Mirrors Rust `ConnectionPool` in `http_client/request.rs`.
+ */
+export type ConnectionPool = {
+  enabled: boolean
+  /** Seconds an idle connection is kept open (default 90) */
+  idleTimeoutSecs?: number
+  /** Idle connections kept per host (default 8) */
+  maxIdlePerHost?: number
+  /** Share a client, and its TLS and DNS setup, with requests of the same connection settings (default true) */
+  shareClient?: boolean
+}
+
+/**
+ * Request body compression. "deflate" uses the zlib format, as HTTP expects.
+ */
+export type ContentEncoding = "gzip" | "deflate" | "zstd"
+
+/**
+ * Client certificate source.
+ * - "pem": certificate chain and private key files
+ * - "system": identity from the Windows certificate store or macOS keychain,
+ *   matched by SHA-1/SHA-256 thumbprint and/or subject (case-insensitive substring)
+ */
+export type ClientCertificate =
+  | { type: "pem"; certPath: string; keyPath: string }
+  | { type: "system"; thumbprint?: string; subject?: string }
+
+/**
+ * Mirrors Rust `InsecureHost` in `http_client/request.rs`. `*.example.test` matches every subdomain.
+ */
+export type InsecureHost = {
+  host: string
+  /** Only skip checking that the certificate names the host; its chain is still verified */
+  hostnameOnly?: boolean
+}
+
+/**
+ * Mirrors Rust `TlsVersion` in `http_client/request.rs`.
+ */
+export type TlsVersion = "1.2" | "1.3"
+
+/**
+ * Mirrors Rust `DnsOverride` in `http_client/request.rs`.
+ */
+export type DnsOverride = { host: string; ip: string }
+
+/**
+ * Mirrors Rust `AutoHeaders` in `http_client/request.rs`. Each header is added unless set to false; turning off
+ * Host or Content-Length forces HTTP/1.1 and a new connection.
+ */
+export type AutoHeaders = {
+  userAgent?: boolean
+  host?: boolean
+  /** The Accept GraphQL requests default to */
+  accept?: boolean
+  contentLength?: boolean
 }
 
+/**
+ * Mirrors Rust `QueryParam` in `http_client/request.rs`: `[name, value]`, or an object to control encoding.
+ */
+export type QueryParam = [name: string, value: string] | { name: string; value: string; encode?: boolean }
+
 export type MultipartPart =
   | { type: "text"; name: string; value: string }
   | { type: "file"; name: string; filePath: string; fileName?: string; contentType?: string }
@@ -237,6 +661,17 @@ export interface Cookie {
   sameSite?: SameSite
 }
 
+/**
+ * A response header value that isn't valid UTF-8.
+ * Mirrors Rust `BinaryHeader` in `http_client/response.rs`.
+ */
+export type BinaryHeader = {
+  /** Position of the header in `Response.headers` */
+  index: number
+  /** The value's bytes, base64 encoded */
+  base64: string
+}
+
 /**
  * Structured response returned to the frontend.
  */
@@ -252,6 +687,10 @@ export interface Response {
    * Mirrors Rust Vec<(String, String)>.
    */
   headers: Array<[string, string]>
+  /**
+   * Exact bytes of header values that aren't valid UTF-8, whose text in `headers` is lossy.
+   */
+  binaryHeaders?: BinaryHeader[]
   /**
    * Cookies parsed from the response with structured attributes.
    */
@@ -260,6 +699,39 @@ export interface Response {
    * Raw response body bytes.
    */
   body: Uint8Array
+  /**
+   * The file the body was spooled to when it was too large to keep in memory; `body` is then empty.
+   */
+  filePath?: string
+  /**
+   * Whether `filePath` holds the body compressed with zstd. Open it with `materializeResponseFile` and read it
+   * with `readResponseFileRange`.
+   */
+  fileCompressed?: boolean
+  /**
+   * Whether the body stopped at the request's `maxResponseBytes`, leaving the rest unread.
+   */
+  truncated?: boolean
+  /**
+   * Whether the request was cancelled while reading the body, which holds what arrived before.
+   */
+  cancelled?: boolean
+  /**
+   * The file the body was written to, for requests with `downloadToPath`.
+   */
+  savedTo?: string
+  /**
+   * Where in `savedTo` the body was appended, when it resumed an interrupted download.
+   */
+  resumedFrom?: number
+  /**
+   * The part of the resource a `206 Partial Content` response holds.
+   */
+  contentRange?: ContentRange
+  /**
+   * What the response cache did, for requests with `responseCache` set.
+   */
+  cache?: CacheStatus
   /**
    * Total response size in bytes.
    * Note: JavaScript numbers are IEEE-754 doubles; large 64-bit values may lose precision.
@@ -269,6 +741,50 @@ export interface Response {
    * Response duration in milliseconds.
    */
   duration: number
+  /**
+   * Breakdown of `duration` by phase.
+   */
+  timings: Timings
+  /**
+   * Addresses of the connection the response arrived on.
+   */
+  connection?: ConnectionInfo
+  /**
+   * Revocation status of the server's certificates, when `checkRevocation` is set.
+   */
+  revocation?: CertificateRevocation[]
+  /**
+   * Quota advertised by the server's rate limit headers.
+   */
+  rateLimit?: RateLimit
+  /**
+   * Redirect responses followed on the way to this one, in order, when `maxRedirects` is above zero.
+   */
+  redirectChain?: RedirectHop[]
+  /**
+   * Retries spent before this response, when the retry policy is enabled.
+   */
+  retries?: RetryReport
+  /**
+   * Log entries the request produced, when its log capture mode is set.
+   */
+  logSummary?: LogSummary
+  /**
+   * Parsed WebDAV multistatus body, for 207 responses held in memory.
+   */
+  multistatus?: Multistatus
+  /**
+   * Fault of a SOAP request's error response, when the body was held in memory.
+   */
+  soapFault?: SoapFault
+  /**
+   * Results of the request's assertions, when it has any.
+   */
+  assertions?: AssertionResult[]
+  /**
+   * Values the request's extractors pulled from the response, when it has any.
+   */
+  variables?: ExtractedVariable[]
   /**
    * Timestamp the response was recorded, ISO 8601 (RFC 3339) string.
    */
@@ -276,38 +792,170 @@ export interface Response {
 }
 
 /**
- * Log levels for categorizing different types of logs.
- * Serialized as lowercase strings to match Rust's `#[serde(rename_all = "lowercase")]`.
+ * A redirect response followed on the way to the final response.
+ * Mirrors Rust `RedirectHop` in `http_client/response.rs`.
  */
-export type LogLevel = "info" | "debug" | "error" | "warning"
+export type RedirectHop = {
+  /** URL of the request that was redirected */
+  url: string
+  method: string
+  status: number
+  statusText: string
+  headers: Array<[string, string]>
+  cookies: Cookie[]
+  /** Milliseconds from sending the request to the redirect's headers */
+  duration: number
+  timings: Timings
+}
 
 /**
- * Log entry for streaming to the frontend during request execution.
+ * Per-phase request timings in milliseconds; `null` for phases that did not happen.
+ * Mirrors Rust `Timings` in `http_client/response.rs`.
  */
-export interface LogEntry {
-  /** Unique ID for the related request. */
-  requestId: string
-  /** Timestamp of the log entry, ISO 8601 string. */
-  timestamp: string
-  /** Log level/category. */
-  level: LogLevel
-  /**
-   * Type of debug info (when applicable), often a sub-category or phase.
-   * Present as null if explicitly set to None on the Rust side.
-   */
-  infoType?: string | null
-  /** The actual log message. */
-  message: string
-  /** Structured category identifier (dns/connect/tls/http/etc). */
-  category?: string
-  /** Optional phase within the category (e.g., start/resolved). */
-  phase?: string
-  /** Milliseconds since the request started when emitted. */
-  elapsedMs?: number
-  /** Structured payload for the UI (mirrors serde_json::Value). */
-  details?: unknown
-  /** Number of payload bytes represented by this log entry. */
-  bytesLogged?: number
+export type Timings = {
+  dns: number | null
+  tcpConnect: number | null
+  tlsHandshake: number | null
+  requestWrite: number | null
+  timeToFirstByte: number | null
+  download: number | null
+  /** True when a pooled connection was reused, so DNS, connect and TLS were skipped */
+  connectionReused: boolean
+  total: number
+}
+
+/**
+ * Mirrors Rust `RateLimit` in `http_client/response.rs`.
+ * Read from `RateLimit`/`RateLimit-Policy`, `RateLimit-*` or `X-RateLimit-*` headers.
+ */
+export type RateLimit = {
+  limit: number | null
+  remaining: number | null
+  /** Seconds until the quota resets */
+  resetSecs: number | null
+  /** `RateLimit-Policy` as sent */
+  policy: string | null
+}
+
+/**
+ * Mirrors Rust `RetryReport` in `http_client/response.rs`.
+ */
+export type RetryReport = {
+  attempts: number
+  maxRetries: number
+  /** Total time spent waiting between attempts */
+  waitedMs: number
+}
+
+/**
+ * Mirrors Rust `LogSummary` in `http_client/response.rs`.
+ */
+export type LogSummary = {
+  /** Whether the entries were sent to the log panel */
+  captured: boolean
+  entries: number
+  warnings: number
+  errors: number
+}
+
+/**
+ * Mirrors Rust `Multistatus` in `http_client/webdav.rs`.
+ */
+export type Multistatus = {
+  responses: DavResponse[]
+  description?: string
+}
+
+export type DavResponse = {
+  href: string
+  /** Status of the whole resource, when not reported per property */
+  status?: number
+  propstats: DavPropstat[]
+  description?: string
+}
+
+export type DavPropstat = {
+  status: number | null
+  properties: DavProperty[]
+}
+
+export type DavProperty = {
+  namespace: string | null
+  name: string
+  /** Text of the property, or its child elements as XML */
+  value: string
+}
+
+/**
+ * Mirrors Rust `CanonicalRequest` in `http_client/hyper_engine/canonical.rs`.
+ */
+export type CanonicalRequest = {
+  /** Canonical JSON of the resolved request: sorted keys and headers, bodies as size + SHA-256 */
+  canonical: string
+  /** Hex SHA-256 of `canonical`; equal hashes mean byte-identical requests */
+  sha256: string
+}
+
+/**
+ * Mirrors Rust `ConnectionInfo` in `http_client/response.rs`.
+ * Steps that share a connection report the same `localAddr`.
+ */
+export type ConnectionInfo = {
+  localAddr: string
+  remoteAddr: string
+  /** Whether the response arrived on a connection an earlier request opened */
+  reused: boolean
+}
+
+/**
+ * Mirrors Rust `CertificateRevocation` in `http_client/response.rs`.
+ */
+export type CertificateRevocation = {
+  subject: string
+  /** Serial number, hex */
+  serial: string
+  status: "good" | "revoked" | "unknown"
+  source?: "ocsp" | "crl"
+  /** Time of the revocation, ISO 8601 */
+  revokedAt?: string
+  reason?: string
+  /** Why the status is unknown */
+  error?: string
+}
+
+/**
+ * Log levels for categorizing different types of logs.
+ * Serialized as lowercase strings to match Rust's `#[serde(rename_all = "lowercase")]`.
+ */
+export type LogLevel = "info" | "debug" | "error" | "warning"
+
+/**
+ * Log entry for streaming to the frontend during request execution.
+ */
+export interface LogEntry {
+  /** Unique ID for the related request. */
+  requestId: string
+  /** Timestamp of the log entry, ISO 8601 string. */
+  timestamp: string
+  /** Log level/category. */
+  level: LogLevel
+  /**
+   * Type of debug info (when applicable), often a sub-category or phase.
+   * Present as null if explicitly set to None on the Rust side.
+   */
+  infoType?: string | null
+  /** The actual log message. */
+  message: string
+  /** Structured category identifier (dns/connect/tls/http/etc). */
+  category?: string
+  /** Optional phase within the category (e.g., start/resolved). */
+  phase?: string
+  /** Milliseconds since the request started when emitted. */
+  elapsedMs?: number
+  /** Structured payload for the UI (mirrors serde_json::Value). */
+  details?: unknown
+  /** Number of payload bytes represented by this log entry. */
+  bytesLogged?: number
   /** Indicates that the payload was truncated due to log limits. */
   truncated?: boolean
 }
@@ -479,232 +1127,2853 @@ export async function sendHttpRequest(opts: Request): Promise<Response> {
 }
 
 /**
- * Load an application data file.
- * Mirrors `fn load_app_data(app, file_name) -> Result<Value, AppError>`.
+ * Outcome of one request of a batch: its response, or why it has none.
+ * Mirrors Rust `BatchResult` in `http_client/batch.rs`.
+ */
+export type BatchResult = {
+  response?: Response
+  error?: AppError
+}
+
+/**
+ * Send several HTTP requests concurrently, at most `concurrency` at a time (6 by default, up to 64), e.g. to warm
+ * caches or compare endpoints. A request that fails doesn't stop the others. Each is cancelled like a single
+ * request, with `cancelHttpRequest(requestId)`.
+ * Mirrors `send_http_requests` Tauri command.
  *
- * @param fileName Name of the file to load (no path traversal).
- * @returns Parsed JSON value from the file.
- * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ * @returns Results keyed by request id.
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`, e.g. when request ids repeat.
  */
-export async function loadAppData(fileName: string): Promise<JsonValue> {
+export async function sendHttpRequests(
+  requests: Request[],
+  concurrency?: number,
+): Promise<Record<string, BatchResult>> {
   try {
-    return await invoke<JsonValue>("load_app_data", { fileName })
+    return await invoke<Record<string, BatchResult>>("send_http_requests", { requests, concurrency })
   } catch (err) {
     normalizeInvokeError(err)
   }
 }
 
 /**
- * Save an application data file.
- * Mirrors `fn save_app_data(app, file_name, data) -> Result<(), AppError>`.
+ * Send an HTTP request through the Rust backend, receiving the response body over a channel as it arrives
+ * rather than in the command's JSON result. Each chunk is passed to `onChunk`; the resolved response carries the
+ * whole body. Bodies the backend spooled to `filePath` or kept in the response (multistatus) are left as they are.
+ * Mirrors `stream_http_request` Tauri command.
  *
- * @param fileName Target filename (no path traversal).
- * @param data JSON-serializable content to write.
  * @throws Error whose `.appError` (if present) contains the structured `AppError`.
  */
-export async function saveAppData(fileName: string, data: JsonValue): Promise<void> {
+export async function streamHttpRequest(opts: Request, onChunk?: (chunk: Uint8Array) => void): Promise<Response> {
+  const chunks: Uint8Array[] = []
+  let finish = () => {}
+  // The backend sends an empty chunk after the last one, which may arrive after the command returns
+  const finished = new Promise<void>((resolve) => {
+    finish = resolve
+  })
+  const channel = new Channel<ArrayBuffer>()
+  channel.onmessage = (data) => {
+    const chunk = new Uint8Array(data)
+    if (chunk.byteLength === 0) {
+      finish()
+      return
+    }
+    chunks.push(chunk)
+    onChunk?.(chunk)
+  }
   try {
-    await invoke<void>("save_app_data", { fileName, data })
+    const response = await invoke<Response>("stream_http_request", { opts, onChunk: channel })
+    await finished
+    if (response.filePath || chunks.length === 0) {
+      return response
+    }
+    const body = new Uint8Array(chunks.reduce((total, chunk) => total + chunk.byteLength, 0))
+    let offset = 0
+    for (const chunk of chunks) {
+      body.set(chunk, offset)
+      offset += chunk.byteLength
+    }
+    return { ...response, body }
   } catch (err) {
     normalizeInvokeError(err)
   }
 }
 
 /**
- * Delete an application data file.
- * Mirrors `fn delete_app_data(app, file_name) -> Result<(), AppError>`.
+ * Mirrors Rust `ContentRange` in `http_client/response.rs`. Byte positions are inclusive.
+ */
+export type ContentRange = {
+  start: number
+  end: number
+  /** Size of the whole resource, when the server knows it */
+  total?: number | null
+}
+
+/**
+ * Mirrors Rust `CacheStatus` in `http_client/response.rs`. `revalidated` means the server answered
+ * `304 Not Modified` and the cached response stands in for it.
+ */
+export type CacheStatus = "stored" | "revalidated" | "uncached"
+
+/**
+ * Send an HTTP request after asking where to save its response, with the backend writing the body straight to that
+ * file. The response carries only metadata, with `savedTo` set.
+ * Mirrors `download_http_request` Tauri command.
  *
- * @param fileName Name of the file to delete.
- * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`, e.g. `UserCancelled` when the
+ * dialog is dismissed.
  */
-export async function deleteAppData(fileName: string): Promise<void> {
+export async function downloadHttpRequest(opts: Request, options: SaveFileDialogOptions): Promise<Response> {
   try {
-    await invoke<void>("delete_app_data", { fileName })
+    return await invoke<Response>("download_http_request", { opts, options })
   } catch (err) {
     normalizeInvokeError(err)
   }
 }
 
 /**
- * Retrieves the data encryption key by invoking the "get_data_encryption_key" method.
- * Mirrors `fn get_data_encryption_key(app: tauri::AppHandle) -> Result<String, AppError>`
+ * Mirrors Rust `SseEvent` in `http_client/sse.rs`.
+ */
+export type SseEvent = {
+  /** Event type, "message" unless the server named one */
+  event: string
+  data: string
+  /** Last event ID seen on the stream */
+  id?: string | null
+  /** Reconnection time the server asked for, in milliseconds */
+  retry?: number | null
+}
+
+/**
+ * Send a request whose `text/event-stream` response is read as Server-Sent Events. Each event is passed to
+ * `onEvent` as it arrives; the promise resolves once the server closes the stream (with an empty body).
+ * Stop listening with `cancelHttpRequest(opts.requestId)`.
+ * Mirrors `send_sse_request` Tauri command.
  *
- * @return {Promise<string>} A promise that resolves to the encryption key as a string.
  * @throws Error whose `.appError` (if present) contains the structured `AppError`.
  */
-export async function getDataEncryptionKey(): Promise<string> {
+export async function sendSseRequest(opts: Request, onEvent: (event: SseEvent) => void): Promise<Response> {
+  const channel = new Channel<SseEvent>()
+  channel.onmessage = onEvent
   try {
-    return await invoke<string>("get_data_encryption_key")
+    return await invoke<Response>("send_sse_request", { opts, onEvent: channel })
   } catch (err) {
     normalizeInvokeError(err)
   }
 }
 
 /**
- * Sets the data encryption key by invoking the "set_data_encryption_key" method.
- * Mirrors `fn set_data_encryption_key(app: tauri::AppHandle, key_b64: String) -> Result<(), AppError>`
+ * One line of a newline-delimited JSON body; `line` counts from 1, blank lines included.
+ * Mirrors Rust `NdjsonRecord` in `http_client/ndjson.rs`.
+ */
+export type NdjsonRecord =
+  | { type: "record"; line: number; value: unknown }
+  | { type: "invalid"; line: number; text: string; error: string }
+
+/**
+ * Send a request whose newline-delimited JSON response (`application/x-ndjson`, JSON Lines) is parsed as it arrives.
+ * Each record is passed to `onRecord` once its line is complete; the body is still returned as usual. Stop reading
+ * with `cancelHttpRequest(opts.requestId)`.
+ * Mirrors `send_ndjson_request` Tauri command.
  *
  * @throws Error whose `.appError` (if present) contains the structured `AppError`.
  */
-export async function setDataEncryptionKey(key: string): Promise<void> {
+export async function sendNdjsonRequest(opts: Request, onRecord: (record: NdjsonRecord) => void): Promise<Response> {
+  const channel = new Channel<NdjsonRecord>()
+  channel.onmessage = onRecord
   try {
-    await invoke<void>("set_data_encryption_key", { keyB64: key })
+    return await invoke<Response>("send_ndjson_request", { opts, onRecord: channel })
   } catch (err) {
     normalizeInvokeError(err)
   }
 }
 
 /**
- * Retrieves the application's data directory path.
- * Mirrors `fn get_app_data_dir(app: tauri::AppHandle) -> Result<String, AppError>`.
+ * Mirrors Rust `GraphqlOperation` in `http_client/graphql.rs`.
+ */
+export type GraphqlOperation = {
+  query: string
+  /** Must be a JSON object */
+  variables?: Record<string, unknown>
+  /** Which operation to run when the document defines several */
+  operationName?: string
+}
+
+/**
+ * Mirrors Rust `CachedSchema` in `app_data/graphql_schemas.rs`.
+ */
+export type CachedGraphqlSchema = {
+  /** Endpoint the schema was introspected from */
+  url: string
+  /** Time of the introspection, ISO 8601 */
+  fetchedAt: string
+  /** The `__schema` object of the introspection result */
+  schema: Record<string, unknown>
+}
+
+/**
+ * Run the GraphQL introspection query against `opts.url` and cache the schema it returns. Headers and auth of
+ * `opts` are kept; its method and body are replaced.
+ * Mirrors `introspect_graphql_schema` Tauri command.
  *
- * @returns {Promise<string>} A promise that resolves to the data directory path.
  * @throws Error whose `.appError` (if present) contains the structured `AppError`.
  */
-export async function getAppDataDir(): Promise<string> {
+export async function introspectGraphqlSchema(opts: Request): Promise<CachedGraphqlSchema> {
   try {
-    return await invoke<string>("get_app_data_dir")
+    return await invoke<CachedGraphqlSchema>("introspect_graphql_schema", { opts })
   } catch (err) {
     normalizeInvokeError(err)
   }
 }
 
 /**
- * Prompts the user to select a location and saves content to a file.
- * Mirrors `async fn save_file(app: tauri::AppHandle, content: String, options: SaveFileDialogOptions) -> Result<(), AppError>`.
+ * Get the schema last introspected from a GraphQL endpoint, or null if there is none.
+ * Mirrors `get_cached_graphql_schema` Tauri command.
  *
- * @param content The string content to save.
- * @param options The dialog options.
- * @throws Error whose `.appError` will be `UserCancelled` if the user cancels.
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
  */
-export async function saveFile(content: string, options: SaveFileDialogOptions): Promise<string> {
+export async function getCachedGraphqlSchema(url: string): Promise<CachedGraphqlSchema | null> {
   try {
-    return await invoke<string>("save_file", { content, options })
+    return await invoke<CachedGraphqlSchema | null>("get_cached_graphql_schema", { url })
   } catch (err) {
     normalizeInvokeError(err)
   }
 }
 
+export type SoapVersion = "1.1" | "1.2"
+
 /**
- * Saves base64-encoded binary content to a user-chosen file.
- * Mirrors `async fn save_binary(app: tauri::AppHandle, content_base64: String, options: SaveFileDialogOptions)`.
+ * Mirrors Rust `SoapOperation` in `http_client/soap.rs`.
  */
-export async function saveBinary(contentBase64: string, options: SaveFileDialogOptions): Promise<string> {
-  try {
-    return await invoke<string>("save_binary", { contentBase64, options })
-  } catch (err) {
-    normalizeInvokeError(err)
-  }
+export type SoapOperation = {
+  /** Name of the element wrapping the parameters in the body, usually the operation's */
+  operation: string
+  /** Namespace of the wrapping element, e.g. the service's target namespace */
+  namespace?: string
+  /**
+   * Parameters sent as child elements in order. Objects become nested elements, by key in sorted order; arrays repeat
+   * the element; null leaves it empty.
+   */
+  parameters?: [string, unknown][]
+  /** Put the parameters in `namespace` too, as schemas with `elementFormDefault="qualified"` expect */
+  qualified?: boolean
+  /** Sent as the `SOAPAction` header (1.1) or the `action` of the content type (1.2) */
+  soapAction?: string
+  /** Defaults to "1.1" */
+  version?: SoapVersion
+  /** XML blocks put in the envelope's `Header` as given, e.g. a WS-Security header */
+  headers?: string[]
 }
 
 /**
- * Prompts the user to select a file and returns its content.
- * Mirrors `async fn open_file(app: tauri::AppHandle, options: OpenFileDialogOptions) -> Result<Option<String>, AppError>`.
+ * Mirrors Rust `SoapFault` in `http_client/soap.rs`.
+ */
+export type SoapFault = {
+  version: SoapVersion
+  /** e.g. `soap:Server` (1.1) or `env:Receiver` (1.2), as the body gives it */
+  code: string
+  /** Subcodes of a SOAP 1.2 fault, outermost first */
+  subcodes?: string[]
+  reason: string
+  /** `faultactor` (1.1) or `Role` (1.2) */
+  actor?: string
+  /** Contents of the fault's detail, as XML */
+  detail?: string
+}
+
+/**
+ * Mirrors Rust `Wsdl` in `http_client/soap.rs`.
+ */
+export type Wsdl = {
+  targetNamespace?: string
+  /** One per operation of each SOAP port; a service with SOAP 1.1 and 1.2 ports lists its operations twice */
+  operations: WsdlOperation[]
+}
+
+export type WsdlOperation = {
+  name: string
+  service: string
+  port: string
+  /** Address of the port, where requests are sent */
+  endpoint?: string
+  documentation?: string
+  /** What the input expects */
+  parameters: WsdlParameter[]
+  /** The operation ready to send as `Request.soap`, with an empty value for each parameter */
+  request: SoapOperation
+}
+
+export type WsdlParameter = {
+  name: string
+  /** Schema type, e.g. `xs:string`, as the document gives it */
+  typeName?: string
+  /** `minOccurs="0"` */
+  optional: boolean
+  /** `maxOccurs` above 1 */
+  repeated: boolean
+}
+
+/**
+ * Parse the text of a WSDL 1.1 document into its SOAP operations. Imported documents and schemas aren't fetched.
+ * Mirrors `fn parse_wsdl(source: String) -> Result<Wsdl, AppError>`.
  *
- * @param options The dialog options.
- * @returns The content of the selected file, or `null` if the user canceled.
- * @throws Error whose `.appError` will be `UserCancelled` if the user cancels.
+ * @throws Error whose `.appError` contains the structured AppError from Rust, e.g. when the text isn't a WSDL.
  */
-export async function openFile(options: OpenFileDialogOptions): Promise<OpenedFile | null> {
+export async function parseWsdl(source: string): Promise<Wsdl> {
   try {
-    return await invoke<OpenedFile | null>("open_file", { options })
+    return await invoke<Wsdl>("parse_wsdl", { source })
   } catch (err) {
     normalizeInvokeError(err)
   }
 }
 
-/** Delete a file from disk (best-effort; no error if missing). */
-export async function deleteFile(path: string): Promise<void> {
-  try {
-    await invoke<void>("delete_file", { path })
-  } catch {
-    // best effort
-  }
-}
-
-export interface AuthPlacement {
-  type: string
-  name?: string
-  fieldName?: string
-  contentType?: string
+/**
+ * Mirrors Rust `SocketOptions` in `http_client/socket.rs`.
+ */
+export type SocketOptions = {
+  /** Names the connection for `sendSocket` and `closeSocket` */
+  connectionId: string
+  host: string
+  port: number
+  /** Wrap the connection in TLS, with `host` as the server name */
+  tls?: boolean
+  /** If true, skip certificate verification */
+  disableSsl?: boolean
+  /** Path to a custom root CA bundle (PEM format) */
+  caPath?: string
+  /** Seconds to wait for the connection and handshake. Defaults to 10. */
+  connectTimeoutSecs?: number
 }
 
-export interface AuthConfig {
-  type: string
-  placement?: AuthPlacement
-  username?: string
-  password?: string
-  token?: string
-  // Optional scheme for Authorization header (e.g., "Bearer", "JWT", or custom)
-  scheme?: string
-  key?: string
-  value?: string
-  grantType?: "client_credentials" | "password" | "refresh_token" | "authorization_code" | "device_code"
-  authUrl?: string
-  tokenUrl?: string
-  clientId?: string
-  clientSecret?: string
-  scope?: string
-  refreshToken?: string
-  redirectUri?: string
-  usePkce?: boolean
-  tokenCaching?: "always" | "never"
-  clientAuth?: "basic" | "body"
-  tokenExtraParams?: Record<string, string>
-}
+/**
+ * Mirrors Rust `SocketEvent` in `http_client/socket.rs`. `closed` is always the last event of a connection.
+ */
+export type SocketEvent = { type: "data"; bytes: number[] } | { type: "closed"; reason?: string | null }
 
-export interface AuthResult {
-  headers?: Record<string, string>
-  query?: Record<string, string>
-  cookies?: Record<string, string>
-  body?: Record<string, unknown>
-  expiresAt?: number
+/**
+ * Mirrors Rust `SocketInfo` in `http_client/socket.rs`.
+ */
+export type SocketInfo = {
+  connectionId: string
+  localAddr: string
+  peerAddr: string
+  /** Negotiated TLS version, for TLS connections */
+  tlsVersion?: string
 }
 
-export interface OidcDiscovery {
-  authorizationEndpoint?: string
-  tokenEndpoint?: string
-  deviceAuthorizationEndpoint?: string
+/**
+ * Open a raw TCP connection, optionally over TLS. Received bytes are passed to `onEvent` as they arrive.
+ * Mirrors `open_socket` Tauri command.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function openSocket(opts: SocketOptions, onEvent: (event: SocketEvent) => void): Promise<SocketInfo> {
+  const channel = new Channel<SocketEvent>()
+  channel.onmessage = onEvent
+  try {
+    return await invoke<SocketInfo>("open_socket", { opts, onEvent: channel })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
 }
 
 /**
- * Retrieves the result of an authentication flow.
- * Mirrors `async fn get_authentication_result(config: AuthConfig) -> Result<AuthResult, String>`.
+ * Write bytes to an open socket.
+ * Mirrors `send_socket` Tauri command.
  *
- * @param config The authentication configuration.
- * @returns The result of the authentication flow.
  * @throws Error whose `.appError` (if present) contains the structured `AppError`.
  */
-export async function getAuthenticationResult(config: AuthConfig, parentRequestId?: string): Promise<AuthResult> {
+export async function sendSocket(connectionId: string, data: Uint8Array | number[]): Promise<void> {
   try {
-    return await invoke<AuthResult>("get_authentication_result", { config, parent_request_id: parentRequestId })
+    await invoke<void>("send_socket", { connectionId, data: Array.from(data) })
   } catch (err) {
     normalizeInvokeError(err)
   }
 }
 
 /**
- * Discovers OIDC endpoints.
- * Mirrors `async fn discover_oidc(app: tauri::AppHandle, url: String) -> Result<OidcDiscovery, String>`.
+ * Close an open socket. Resolves to false when it wasn't open.
+ * Mirrors `close_socket` Tauri command.
  *
- * @param url The base URL of the OIDC provider.
- * @returns The discovered endpoints.
  * @throws Error whose `.appError` (if present) contains the structured `AppError`.
  */
-export async function discoverOidc(url: string): Promise<OidcDiscovery> {
+export async function closeSocket(connectionId: string): Promise<boolean> {
   try {
-    return await invoke<OidcDiscovery>("discover_oidc", { url })
+    return await invoke<boolean>("close_socket", { connectionId })
   } catch (err) {
     normalizeInvokeError(err)
   }
 }
 
 /**
- * Cancel an in-flight HTTP request by its requestId/correlationId.
+ * Mirrors Rust `ProxyOptions` in `http_client/recording_proxy.rs`.
  */
-export async function cancelHttpRequest(requestId: string): Promise<void> {
-  try {
-    await invoke<void>("cancel_http_request", { requestId })
+export type ProxyOptions = {
+  /** Names the proxy for `stopRecordingProxy` */
+  proxyId: string
+  /** Port to listen on. Defaults to an ephemeral one. */
+  port?: number
+  /** Address to listen on. Defaults to 127.0.0.1; any other exposes the proxy to the network. */
+  bindAddress?: string
+  /** Record captured exchanges in history. Defaults to true. */
+  recordHistory?: boolean
+  /** Collection the recorded history entries are filed under */
+  collectionId?: string
+}
+
+/**
+ * Mirrors Rust `CapturedExchange` in `http_client/recording_proxy.rs`.
+ */
+export type CapturedExchange = {
+  method: string
+  url: string
+  requestHeaders: Array<[string, string]>
+  requestBody: number[]
+  status: number
+  statusText: string
+  responseHeaders: Array<[string, string]>
+  /** The body as the server sent it, still in any content encoding */
+  responseBody: number[]
+  /** Milliseconds from forwarding the request to receiving the whole response */
+  duration: number
+  /** Whether either body was longer than what was kept */
+  truncated: boolean
+  /** ID of the history entry the exchange was recorded as */
+  historyId?: string
+}
+
+/**
+ * Mirrors Rust `ProxyEvent` in `http_client/recording_proxy.rs`. `tunnel` reports a CONNECT (HTTPS) tunnel once it
+ * has closed; `stopped` is always the last event of a proxy.
+ */
+export type ProxyEvent =
+  | { type: "exchange"; exchange: CapturedExchange }
+  | { type: "tunnel"; authority: string; bytesSent: number; bytesReceived: number }
+  | { type: "stopped"; reason?: string | null }
+
+/**
+ * Mirrors Rust `ProxyInfo` in `http_client/recording_proxy.rs`.
+ */
+export type ProxyInfo = {
+  proxyId: string
+  /** The address listened on, for configuring clients */
+  address: string
+}
+
+/**
+ * Start a local forward proxy that records the plain HTTP traffic passing through it. Each exchange is passed to
+ * `onEvent` and, unless `recordHistory` is false, recorded in history; HTTPS is tunnelled without being decrypted.
+ * Mirrors `start_recording_proxy` Tauri command.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function startRecordingProxy(
+  opts: ProxyOptions,
+  onEvent: (event: ProxyEvent) => void,
+): Promise<ProxyInfo> {
+  const channel = new Channel<ProxyEvent>()
+  channel.onmessage = onEvent
+  try {
+    return await invoke<ProxyInfo>("start_recording_proxy", { opts, onEvent: channel })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Stop a recording proxy. Resolves to false when it wasn't running.
+ * Mirrors `stop_recording_proxy` Tauri command.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function stopRecordingProxy(proxyId: string): Promise<boolean> {
+  try {
+    return await invoke<boolean>("stop_recording_proxy", { proxyId })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Mirrors Rust `WebhookOptions` in `http_client/webhook.rs`.
+ */
+export type WebhookOptions = {
+  /** Names the listener for `stopWebhookListener` */
+  listenerId: string
+  /** Port to listen on. Defaults to an ephemeral one. */
+  port?: number
+  /** Address to listen on. Defaults to 127.0.0.1; any other exposes the listener to the network. */
+  bindAddress?: string
+  /** Status every request is answered with. Defaults to 200. */
+  responseStatus?: number
+  responseHeaders?: Array<[string, string]>
+  responseBody?: string
+  /** PEM certificate to serve HTTPS with, e.g. one from `generateSelfSignedCertificate` */
+  certPath?: string
+  /** PEM private key of `certPath` */
+  keyPath?: string
+}
+
+/**
+ * Mirrors Rust `CaughtRequest` in `http_client/webhook.rs`.
+ */
+export type CaughtRequest = {
+  id: string
+  /** ISO 8601 */
+  receivedAt: string
+  /** Address of the client that sent the request */
+  remoteAddr: string
+  method: string
+  path: string
+  /** The query string's parameters, decoded */
+  query: Array<[string, string]>
+  headers: Array<[string, string]>
+  body: number[]
+  /** Whether the body was longer than what was kept */
+  truncated: boolean
+}
+
+/**
+ * Mirrors Rust `WebhookEvent` in `http_client/webhook.rs`. `stopped` is always the last event of a listener.
+ */
+export type WebhookEvent = { type: "request"; request: CaughtRequest } | { type: "stopped"; reason?: string | null }
+
+/**
+ * Mirrors Rust `WebhookInfo` in `http_client/webhook.rs`.
+ */
+export type WebhookInfo = {
+  listenerId: string
+  /** The address listened on */
+  address: string
+  /** URL to give the sender, e.g. as a webhook or redirect URI. HTTPS when a certificate was given. */
+  url: string
+}
+
+/**
+ * Start a temporary local listener that catches every request sent to it, e.g. webhook deliveries or OAuth
+ * callbacks, answering each with the configured response. Caught requests are passed to `onEvent`.
+ * Mirrors `start_webhook_listener` Tauri command.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function startWebhookListener(
+  opts: WebhookOptions,
+  onEvent: (event: WebhookEvent) => void,
+): Promise<WebhookInfo> {
+  const channel = new Channel<WebhookEvent>()
+  channel.onmessage = onEvent
+  try {
+    return await invoke<WebhookInfo>("start_webhook_listener", { opts, onEvent: channel })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Stop a webhook listener. Resolves to false when it wasn't running.
+ * Mirrors `stop_webhook_listener` Tauri command.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function stopWebhookListener(listenerId: string): Promise<boolean> {
+  try {
+    return await invoke<boolean>("stop_webhook_listener", { listenerId })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Mirrors Rust `StaticServerOptions` in `http_client/static_server.rs`.
+ */
+export type StaticServerOptions = {
+  /** Names the server for `stopStaticServer`, and is the `requestId` of its access log entries */
+  serverId: string
+  /** Directory to serve */
+  root: string
+  /** Port to listen on. Defaults to an ephemeral one. */
+  port?: number
+  /** Address to listen on. Defaults to 127.0.0.1; any other exposes the server to the network. */
+  bindAddress?: string
+  /** Answer paths that match no file with the root's index.html, as client-side routing needs */
+  spaFallback?: boolean
+  /** Allow any origin to read responses */
+  cors?: boolean
+}
+
+/**
+ * Mirrors Rust `StaticServerInfo` in `http_client/static_server.rs`.
+ */
+export type StaticServerInfo = {
+  serverId: string
+  /** The address listened on */
+  address: string
+  url: string
+  /** The directory served, resolved */
+  root: string
+}
+
+/**
+ * Serve a directory over HTTP, e.g. fixture payloads or an SPA build. Each request answered is logged on the
+ * `http-request-log` event like a sent request, with the server's id as its `requestId`.
+ * Mirrors `start_static_server` Tauri command.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function startStaticServer(opts: StaticServerOptions): Promise<StaticServerInfo> {
+  try {
+    return await invoke<StaticServerInfo>("start_static_server", { opts })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Stop a static file server. Resolves to false when it wasn't running.
+ * Mirrors `stop_static_server` Tauri command.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function stopStaticServer(serverId: string): Promise<boolean> {
+  try {
+    return await invoke<boolean>("stop_static_server", { serverId })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Mirrors Rust `TlsProbeOptions` in `http_client/tls_probe.rs`.
+ */
+export type TlsProbeOptions = {
+  host: string
+  /** Defaults to 443 */
+  port?: number
+  /** Name sent as SNI and verified against the certificate, when not `host` */
+  serverName?: string
+  /** Path to a custom root CA bundle (PEM format) */
+  caPath?: string
+  /** Seconds to wait for the connection and handshake. Defaults to 10. */
+  connectTimeoutSecs?: number
+}
+
+/**
+ * Mirrors Rust `CertificateSummary` in `http_client/hyper_engine/connector.rs`, whose fields keep their snake_case
+ * names as in the handshake log.
+ */
+export type CertificateSummary = {
+  index: number
+  sha256: string
+  subject: string | null
+  issuer: string | null
+  version: string | null
+  serial: string | null
+  signature_algorithm: string | null
+  signature_algorithm_oid: string | null
+  signature_algorithm_description: string | null
+  not_before: string | null
+  not_after: string | null
+  public_key_algorithm: string | null
+  public_key_algorithm_oid: string | null
+  public_key_algorithm_description: string | null
+  public_key: {
+    kind: string
+    bits: number | null
+    modulus_lines: string[] | null
+    exponent_decimal: string | null
+    exponent_hex: string | null
+    data_lines: string[] | null
+    curve: string | null
+    curve_oid: string | null
+    curve_description: string | null
+  } | null
+  signature_lines: string[] | null
+  pem: string
+}
+
+/**
+ * Mirrors Rust `TlsProbe` in `http_client/tls_probe.rs`.
+ */
+export type TlsProbe = {
+  peerAddr: string
+  tlsVersion: string | null
+  /** IANA name of the negotiated cipher suite */
+  cipherSuite: string | null
+  /** Protocol the server picked of `h2` and `http/1.1` */
+  alpn: string | null
+  /** Why the chain failed verification; the certificates then come from a handshake that skipped it */
+  verificationError: string | null
+  /** The server's chain, leaf first */
+  certificates: CertificateSummary[]
+}
+
+/**
+ * Perform only a TLS handshake with a server and summarize the certificates it presents, even untrusted ones.
+ * Mirrors `fn inspect_tls(opts) -> Result<TlsProbe, AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function inspectTls(opts: TlsProbeOptions): Promise<TlsProbe> {
+  try {
+    return await invoke<TlsProbe>("inspect_tls", { opts })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Mirrors Rust `ChainSource` in `http_client/tls_probe.rs`: the chain logged for a recent request's handshake, or
+ * the chain a server presents now.
+ */
+export type ChainSource = { type: "response"; requestId: string } | ({ type: "host" } & TlsProbeOptions)
+
+/**
+ * Save a certificate chain as PEM where the user picks: one bundle, or one file per certificate.
+ * Mirrors `fn export_certificate_chain(app, source, separate_files, options) -> Result<Vec<String>, AppError>`.
+ *
+ * @returns The paths written.
+ * @throws Error whose `.appError` will be `UserCancelled` if the user cancels, or `BadRequest` when the request
+ * made no TLS handshake of its own.
+ */
+export async function exportCertificateChain(
+  source: ChainSource,
+  options: SaveFileDialogOptions,
+  separateFiles?: boolean,
+): Promise<string[]> {
+  try {
+    return await invoke<string[]>("export_certificate_chain", { source, separateFiles, options })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Mirrors Rust `SelfSignedOptions` in `http_client/self_signed.rs`.
+ */
+export type SelfSignedOptions = {
+  /** Host name the certificate is issued to, also its common name */
+  hostname: string
+  /** Further host names or IP addresses the certificate is valid for */
+  altNames?: string[]
+  /** Defaults to 365; at most 825 */
+  validDays?: number
+}
+
+/**
+ * Mirrors Rust `SavedCertificate` in `http_client/self_signed.rs`.
+ */
+export type SavedCertificate = {
+  certPath: string
+  /** Saved next to the certificate, e.g. `localhost-key.pem` for `localhost.pem` */
+  keyPath: string
+  /** SHA-256 of the DER certificate, hex */
+  sha256: string
+  /** Last day the certificate is valid, ISO 8601 */
+  notAfter: string
+}
+
+/**
+ * Generate a self-signed certificate and save it as PEM where the user picks, with its key next to it. Pass the
+ * paths to `startWebhookListener` to serve HTTPS.
+ * Mirrors `fn generate_self_signed_certificate(app, opts, options) -> Result<SavedCertificate, AppError>`.
+ *
+ * @throws Error whose `.appError` will be `UserCancelled` if the user cancels, or `BadRequest` for a missing host
+ * name or an invalid validity.
+ */
+export async function generateSelfSignedCertificate(
+  opts: SelfSignedOptions,
+  options: SaveFileDialogOptions,
+): Promise<SavedCertificate> {
+  try {
+    return await invoke<SavedCertificate>("generate_self_signed_certificate", { opts, options })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Load an application data file.
+ * Mirrors `fn load_app_data(app, file_name) -> Result<Value, AppError>`.
+ *
+ * @param fileName Name of the file to load (no path traversal).
+ * @returns Parsed JSON value from the file.
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function loadAppData(fileName: string): Promise<JsonValue> {
+  try {
+    return await invoke<JsonValue>("load_app_data", { fileName })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Kind of credential a plaintext value looks like.
+ */
+export type SecretKind = "awsAccessKey" | "jwt" | "privateKey" | "githubToken" | "slackToken" | "stripeKey"
+
+/**
+ * A plaintext value that looks like a credential, found while saving app data.
+ */
+export type SecretFinding = {
+  /** JSON pointer (RFC 6901) to the value */
+  pointer: string
+  kind: SecretKind
+  /** Masked excerpt of the value */
+  preview: string
+  /** True when the value can be encrypted by passing its pointer in `securePointers` */
+  markable: boolean
+}
+
+/**
+ * Save an application data file.
+ * Mirrors `fn save_app_data(app, file_name, data, secure_pointers) -> Result<Vec<SecretFinding>, AppError>`.
+ *
+ * @param fileName Target filename (no path traversal).
+ * @param data JSON-serializable content to write.
+ * @param securePointers JSON pointers of `{ value, secure }` entries to mark secure before saving.
+ * @returns Plaintext values that look like credentials and were saved unencrypted.
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function saveAppData(
+  fileName: string,
+  data: JsonValue,
+  securePointers?: string[],
+): Promise<SecretFinding[]> {
+  try {
+    return (await invoke<SecretFinding[] | null>("save_app_data", { fileName, data, securePointers })) ?? []
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Delete an application data file.
+ * Mirrors `fn delete_app_data(app, file_name, permanent) -> Result<(), AppError>`.
+ *
+ * @param fileName Name of the file to delete.
+ * @param permanent If true, delete the file instead of moving it to the trash.
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function deleteAppData(fileName: string, permanent?: boolean): Promise<void> {
+  try {
+    await invoke<void>("delete_app_data", { fileName, permanent })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Request edit recorded in the autosave journal.
+ * `patch` is a JSON merge patch (RFC 7386) against the previous state.
+ */
+export type JournalEdit = {
+  targetId: string
+  collectionId?: string
+  patch: JsonValue
+  timestamp?: string
+}
+
+/**
+ * Unsaved changes for one request, folded from its journal records.
+ */
+export type RecoveredEdit = {
+  targetId: string
+  collectionId?: string
+  changes: JsonValue
+  updated: string
+  edits: number
+}
+
+/**
+ * Append debounced request edits to the autosave journal.
+ * Mirrors `fn append_journal(app, edits) -> Result<(), AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function appendJournal(edits: JournalEdit[]): Promise<void> {
+  try {
+    await invoke<void>("append_journal", { edits })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Read unsaved request edits left in the journal (e.g. after a crash).
+ * Mirrors `fn recover_journal(app) -> Result<Vec<RecoveredEdit>, AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function recoverJournal(): Promise<RecoveredEdit[]> {
+  try {
+    return await invoke<RecoveredEdit[]>("recover_journal")
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Drop journal records for requests that were saved or discarded.
+ * Mirrors `fn checkpoint_journal(app, target_ids) -> Result<(), AppError>`.
+ *
+ * @param targetIds Requests to drop; omit to clear the journal.
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function checkpointJournal(targetIds?: string[]): Promise<void> {
+  try {
+    await invoke<void>("checkpoint_journal", { targetIds })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * An environment snapshot without its values.
+ * Mirrors Rust `SnapshotSummary` in `app_data/env_snapshots.rs`.
+ */
+export type SnapshotSummary = {
+  id: string
+  /** ISO 8601 */
+  takenAt: string
+  /** Environment name when the snapshot was taken */
+  name: string
+  variableCount: number
+}
+
+/**
+ * An environment as stored in a collection; snapshots keep it as-is.
+ */
+export type SnapshotEnvironment = { id: string; name: string } & Record<string, unknown>
+
+/**
+ * Record a changed environment in its append-only snapshot history. Secure variable values are
+ * encrypted like app data. Nothing is recorded when it's unchanged since the last snapshot.
+ * Mirrors `fn snapshot_environment(app, collection_id, environment) -> Result<Option<SnapshotSummary>, AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function snapshotEnvironment(
+  collectionId: string,
+  environment: SnapshotEnvironment,
+): Promise<SnapshotSummary | null> {
+  try {
+    return await invoke<SnapshotSummary | null>("snapshot_environment", { collectionId, environment })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Hand the backend the edited value of a secure environment variable, so requests referring to it use the edit
+ * before the collection is saved. A `null` value forgets it, e.g. once the variable isn't secure.
+ * Mirrors `fn stage_secure_variable(collection_id, environment_id, variable_id, value) -> Result<(), AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function stageSecureVariable(
+  collectionId: string,
+  environmentId: string,
+  variableId: string,
+  value: string | null,
+): Promise<void> {
+  try {
+    await invoke<void>("stage_secure_variable", { collectionId, environmentId, variableId, value })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * List the snapshots of an environment, newest first.
+ * Mirrors `fn list_environment_snapshots(app, collection_id, environment_id) -> Result<Vec<SnapshotSummary>, AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function listEnvironmentSnapshots(
+  collectionId: string,
+  environmentId: string,
+): Promise<SnapshotSummary[]> {
+  try {
+    return await invoke<SnapshotSummary[]>("list_environment_snapshots", { collectionId, environmentId })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Get an environment as it was in a snapshot. Put it back with `updateEnvironment`; that change is
+ * snapshotted in turn, so a restore can itself be undone.
+ * Mirrors `fn restore_environment_snapshot(app, collection_id, environment_id, snapshot_id) -> Result<Value, AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function restoreEnvironmentSnapshot(
+  collectionId: string,
+  environmentId: string,
+  snapshotId: string,
+): Promise<SnapshotEnvironment> {
+  try {
+    return await invoke<SnapshotEnvironment>("restore_environment_snapshot", {
+      collectionId,
+      environmentId,
+      snapshotId,
+    })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * A sent request and its response to record in history.
+ * Mirrors Rust `HistoryRecord` in `app_data/history.rs`.
+ */
+export type HistoryRecord = {
+  /** ID of the saved request that was sent, if any */
+  requestId?: string
+  collectionId?: string
+  method: string
+  url: string
+  status: number
+  statusText: string
+  headers?: Array<[string, string]>
+  /** Response duration in milliseconds */
+  duration: number
+  /** The body, when the response kept it in memory */
+  body?: Uint8Array
+  /** The file the body was spooled to (`Response.filePath`) */
+  filePath?: string
+}
+
+/**
+ * Mirrors Rust `HistoryEntry` in `app_data/history.rs`.
+ */
+export type HistoryEntry = Omit<HistoryRecord, "body" | "filePath"> & {
+  id: string
+  /** ISO 8601 */
+  recordedAt: string
+  headers: Array<[string, string]>
+  /** SHA-256 of the body, for `readHistoryBody`; absent for an empty body */
+  bodyDigest?: string
+  bodySize: number
+}
+
+/**
+ * Record a send in history. Bodies are content-addressed, so identical responses across runs share one stored
+ * copy; the oldest entries are dropped, with the bodies only they used, once history reaches its limit.
+ * Mirrors `fn record_history_entry(app, record: HistoryRecord) -> Result<HistoryEntry, AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function recordHistoryEntry(record: HistoryRecord): Promise<HistoryEntry> {
+  try {
+    return await invoke<HistoryEntry>("record_history_entry", { record })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * List history entries, newest first, optionally only those of one saved request.
+ * Mirrors `fn list_history(app, request_id, limit) -> Result<Vec<HistoryEntry>, AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function listHistory(requestId?: string, limit?: number): Promise<HistoryEntry[]> {
+  try {
+    return await invoke<HistoryEntry[]>("list_history", { requestId, limit })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Get the body of a history entry by its `bodyDigest`.
+ * Mirrors `fn read_history_body(app, digest) -> Result<Vec<u8>, AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function readHistoryBody(digest: string): Promise<Uint8Array> {
+  try {
+    return new Uint8Array(await invoke<number[]>("read_history_body", { digest }))
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Delete all history and its stored bodies.
+ * Mirrors `fn clear_history(app) -> Result<(), AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function clearHistory(): Promise<void> {
+  try {
+    await invoke("clear_history")
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Mirrors Rust `SearchHit` in `app_data/search.rs`.
+ */
+export type SearchHit = {
+  /** A request saved in a collection, or a send recorded in history */
+  kind: "request" | "history"
+  /** ID of the saved request, or of the history entry */
+  id: string
+  collectionId?: string
+  /** ID of the saved request a history entry was sent from */
+  requestId?: string
+  /** The request's name, or its method and URL for history */
+  title: string
+  method: string
+  url: string
+  /** ISO 8601; history only */
+  recordedAt?: string
+  /** Field the snippet comes from */
+  field: "name" | "url" | "headers" | "body"
+  /** Text around the first match */
+  snippet: string
+  score: number
+}
+
+/**
+ * Search saved requests and history, including stored response bodies, best hits first. Every term must match;
+ * the last also matches as a prefix. The index is rebuilt only after a collection or the history changes.
+ * Mirrors `fn search_workspace(app, query, limit) -> Result<Vec<SearchHit>, AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function searchWorkspace(query: string, limit?: number): Promise<SearchHit[]> {
+  try {
+    return await invoke<SearchHit[]>("search_workspace", { query, limit })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Entry in the trash area.
+ * - "file": an app data file removed with `deleteAppData`
+ * - "item": a value stored with `trashAppItem` (e.g. a deleted request)
+ */
+export type TrashEntry = {
+  id: string
+  kind: "file" | "item"
+  fileName?: string
+  label?: string
+  parentId?: string
+  deletedAt: string
+}
+
+/**
+ * Move a value (e.g. a request removed from a collection) to the trash.
+ * Mirrors `fn trash_app_item(app, label, parent_id, data) -> Result<TrashEntry, AppError>`.
+ *
+ * @param data JSON value to keep for restoring.
+ * @param label Display label for the trash view.
+ * @param parentId Optional context for restoring, e.g. the owning collection id.
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function trashAppItem(data: JsonValue, label?: string, parentId?: string): Promise<TrashEntry> {
+  try {
+    return await invoke<TrashEntry>("trash_app_item", { data, label, parentId })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * List trash entries, most recently deleted first.
+ * Mirrors `fn list_trash(app) -> Result<Vec<TrashEntry>, AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function listTrash(): Promise<TrashEntry[]> {
+  try {
+    return await invoke<TrashEntry[]>("list_trash")
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Restore a trash entry. File entries are moved back in place and resolve to `null`;
+ * item entries resolve to the stored value.
+ * Mirrors `fn restore_trash_entry(app, id, overwrite) -> Result<Option<Value>, AppError>`.
+ *
+ * @param id Trash entry id.
+ * @param overwrite Replace an existing file with the same name.
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function restoreTrashEntry(id: string, overwrite?: boolean): Promise<JsonValue | null> {
+  try {
+    return await invoke<JsonValue | null>("restore_trash_entry", { id, overwrite })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Permanently remove trash entries. Entries older than 30 days are also purged automatically.
+ * Mirrors `fn purge_trash(app, older_than_days) -> Result<usize, AppError>`.
+ *
+ * @param olderThanDays Only purge entries deleted more than this many days ago; omit to empty the trash.
+ * @returns Number of entries removed.
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function purgeTrash(olderThanDays?: number): Promise<number> {
+  try {
+    return await invoke<number>("purge_trash", { olderThanDays })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Tags and notes attached to a saved request or history entry. Mirrors Rust `Annotation` in
+ * `app_data/annotations.rs`.
+ */
+export type Annotation = {
+  /** ID of the request or history entry */
+  targetId: string
+  target: "request" | "history"
+  /** Owning collection, when the target is a saved request */
+  collectionId?: string
+  /** Lower-cased, without a leading `#` */
+  tags: string[]
+  notes?: string
+  /** Last update timestamp, ISO 8601; set by the backend */
+  updated?: string
+}
+
+/**
+ * Filter for `queryAnnotations`. Mirrors Rust `AnnotationQuery` in `app_data/annotations.rs`.
+ */
+export type AnnotationQuery = {
+  /** Query string, e.g. `tag:smoke -tag:flaky timeout`. Bare words match notes. */
+  query?: string
+  target?: Annotation["target"]
+  collectionId?: string
+}
+
+/**
+ * Get the annotation of a saved request or history entry.
+ * Mirrors `fn get_annotation(app, target_id) -> Result<Option<Annotation>, AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function getAnnotation(targetId: string): Promise<Annotation | null> {
+  try {
+    return await invoke<Annotation | null>("get_annotation", { targetId })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Create or replace the annotation of its target. An annotation without tags or notes is removed.
+ * Mirrors `fn set_annotation(app, annotation) -> Result<Annotation, AppError>`.
+ *
+ * @returns The annotation as stored, with its tags normalized.
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function setAnnotation(annotation: Annotation): Promise<Annotation> {
+  try {
+    return await invoke<Annotation>("set_annotation", { annotation })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Remove the annotation of a saved request or history entry.
+ * Mirrors `fn delete_annotation(app, target_id) -> Result<(), AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function deleteAnnotation(targetId: string): Promise<void> {
+  try {
+    await invoke<void>("delete_annotation", { targetId })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Find the annotations matching a filter.
+ * Mirrors `fn query_annotations(app, filter) -> Result<Vec<Annotation>, AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function queryAnnotations(filter: AnnotationQuery): Promise<Annotation[]> {
+  try {
+    return await invoke<Annotation[]>("query_annotations", { filter })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * List every tag in use with how many annotations have it.
+ * Mirrors `fn list_annotation_tags(app) -> Result<BTreeMap<String, usize>, AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function listAnnotationTags(): Promise<Record<string, number>> {
+  try {
+    return await invoke<Record<string, number>>("list_annotation_tags")
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Retrieves the data encryption key by invoking the "get_data_encryption_key" method.
+ * Mirrors `fn get_data_encryption_key(app: tauri::AppHandle) -> Result<String, AppError>`
+ *
+ * @return {Promise<string>} A promise that resolves to the encryption key as a string.
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function getDataEncryptionKey(): Promise<string> {
+  try {
+    return await invoke<string>("get_data_encryption_key")
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Sets the data encryption key by invoking the "set_data_encryption_key" method.
+ * Mirrors `fn set_data_encryption_key(app: tauri::AppHandle, key_b64: String) -> Result<(), AppError>`
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function setDataEncryptionKey(key: string): Promise<void> {
+  try {
+    await invoke<void>("set_data_encryption_key", { keyB64: key })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Encrypted values counted by the id of the key they were encrypted with.
+ * `legacy` counts values written before key ids were recorded.
+ */
+export type KeyUsage = {
+  keyIds: Record<string, number>
+  legacy: number
+}
+
+export type FileKeyUsage = KeyUsage & {
+  /** Path relative to the app data directory */
+  fileName: string
+}
+
+export type KeyAudit = KeyUsage & {
+  activeKeyId: string
+  files: FileKeyUsage[]
+  /** Files that could not be read or parsed */
+  unreadable: string[]
+}
+
+export type RewrapReport = {
+  files: number
+  values: number
+  /** `file:path` of values that could be decrypted with neither key */
+  failed: string[]
+}
+
+/** How data encryption keys are stored in the OS keyring */
+export type KeyProtection = "software" | "hardware"
+
+export type KeyProtectionStatus = {
+  protection: KeyProtection
+  /** Whether a TPM / Secure Enclave key store can be used on this device */
+  hardwareAvailable: boolean
+  /** Hardware key store name, e.g. "tpm" or "secureEnclave" */
+  provider?: string
+}
+
+/**
+ * Reports whether the data encryption keys are wrapped by a hardware key store.
+ * Mirrors `fn get_key_protection(app) -> Result<KeyProtectionStatus, AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function getKeyProtection(): Promise<KeyProtectionStatus> {
+  try {
+    return await invoke<KeyProtectionStatus>("get_key_protection")
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Stores an HTTP message signing key in the OS keyring, replacing any key with that name.
+ * `key` is a PEM private key, or a base64 secret for hmac-sha256.
+ * Mirrors `fn store_signing_key(app, name, key) -> Result<(), AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function storeSigningKey(name: string, key: string): Promise<void> {
+  try {
+    return await invoke<void>("store_signing_key", { name, key })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Removes an HTTP message signing key from the OS keyring. Returns whether it existed.
+ * Mirrors `fn delete_signing_key(app, name) -> Result<bool, AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function deleteSigningKey(name: string): Promise<boolean> {
+  try {
+    return await invoke<boolean>("delete_signing_key", { name })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Stores a named secret, e.g. an API key, in the OS keyring, replacing any secret with that name. Requests
+ * refer to it as `{{secret:name}}`, read by the backend when sending.
+ * Mirrors `fn store_keyring_secret(app, name, value) -> Result<(), AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function storeKeyringSecret(name: string, value: string): Promise<void> {
+  try {
+    return await invoke<void>("store_keyring_secret", { name, value })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Reads a named secret from the OS keyring.
+ * Mirrors `fn load_keyring_secret(app, name) -> Result<String, AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function loadKeyringSecret(name: string): Promise<string> {
+  try {
+    return await invoke<string>("load_keyring_secret", { name })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Removes a named secret from the OS keyring. Returns whether it existed.
+ * Mirrors `fn delete_keyring_secret(app, name) -> Result<bool, AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function deleteKeyringSecret(name: string): Promise<boolean> {
+  try {
+    return await invoke<boolean>("delete_keyring_secret", { name })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Lists the names a `.env` file defines, sorted, for `dotenv` secret references. Values stay in the backend.
+ * Mirrors `fn list_dotenv_variables(path) -> Result<Vec<String>, AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function listDotenvVariables(path: string): Promise<string[]> {
+  try {
+    return await invoke<string[]>("list_dotenv_variables", { path })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Re-stores the data encryption keys with the requested protection. Falls back to software
+ * where no hardware key store is available; check the returned status.
+ * Mirrors `fn set_key_protection(app, protection) -> Result<KeyProtectionStatus, AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function setKeyProtection(protection: KeyProtection): Promise<KeyProtectionStatus> {
+  try {
+    return await invoke<KeyProtectionStatus>("set_key_protection", { protection })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Lists which keys the encrypted values in app data are encrypted with.
+ * Mirrors `fn audit_encryption_keys(app) -> Result<KeyAudit, AppError>`.
+ *
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function auditEncryptionKeys(): Promise<KeyAudit> {
+  try {
+    return await invoke<KeyAudit>("audit_encryption_keys")
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Re-encrypts app data values with the active key, reporting progress as a `keyRotation` operation.
+ * Mirrors `fn rewrap_app_data(app, previous_key_b64) -> Result<RewrapReport, AppError>`.
+ *
+ * @param previousKey Base64 key the values were encrypted with before the key changed.
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function rewrapAppData(previousKey?: string): Promise<RewrapReport> {
+  try {
+    return await invoke<RewrapReport>("rewrap_app_data", { previousKeyB64: previousKey })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Retrieves the application's data directory path.
+ * Mirrors `fn get_app_data_dir(app: tauri::AppHandle) -> Result<String, AppError>`.
+ *
+ * @returns {Promise<string>} A promise that resolves to the data directory path.
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function getAppDataDir(): Promise<string> {
+  try {
+    return await invoke<string>("get_app_data_dir")
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Prompts the user to select a location and saves content to a file.
+ * Mirrors `async fn save_file(app: tauri::AppHandle, content: String, options: SaveFileDialogOptions) -> Result<(), AppError>`.
+ *
+ * @param content The string content to save.
+ * @param options The dialog options.
+ * @throws Error whose `.appError` will be `UserCancelled` if the user cancels.
+ */
+export async function saveFile(content: string, options: SaveFileDialogOptions): Promise<string> {
+  try {
+    return await invoke<string>("save_file", { content, options })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Saves base64-encoded binary content to a user-chosen file.
+ * Mirrors `async fn save_binary(app: tauri::AppHandle, content_base64: String, options: SaveFileDialogOptions)`.
+ */
+export async function saveBinary(contentBase64: string, options: SaveFileDialogOptions): Promise<string> {
+  try {
+    return await invoke<string>("save_binary", { contentBase64, options })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Prompts the user to select a file and returns its content.
+ * Mirrors `async fn open_file(app: tauri::AppHandle, options: OpenFileDialogOptions) -> Result<Option<String>, AppError>`.
+ *
+ * @param options The dialog options.
+ * @returns The content of the selected file, or `null` if the user canceled.
+ * @throws Error whose `.appError` will be `UserCancelled` if the user cancels.
+ */
+export async function openFile(options: OpenFileDialogOptions): Promise<OpenedFile | null> {
+  try {
+    return await invoke<OpenedFile | null>("open_file", { options })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/** Delete a file from disk (best-effort; no error if missing). */
+export async function deleteFile(path: string): Promise<void> {
+  try {
+    await invoke<void>("delete_file", { path })
+  } catch {
+    // best effort
+  }
+}
+
+/**
+ * The path of a spooled response body (`Response.filePath`) that other apps can open: the file itself, or a
+ * decompressed copy of it when `fileCompressed` is set.
+ * Mirrors `fn materialize_response_file(path: String) -> Result<String, AppError>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust.
+ */
+export async function materializeResponseFile(path: string): Promise<string> {
+  try {
+    return await invoke<string>("materialize_response_file", { path })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Read `length` bytes of a spooled response body from `offset`, in the body's own offsets whether or not
+ * the file is compressed. Fewer bytes are returned at the end of the body.
+ * Mirrors `fn read_response_file_range(path: String, offset: u64, length: usize) -> Result<String, AppError>`.
+ *
+ * @returns The bytes, base64-encoded.
+ * @throws Error whose `.appError` contains the structured AppError from Rust.
+ */
+export async function readResponseFileRange(path: string, offset: number, length: number): Promise<string> {
+  try {
+    return await invoke<string>("read_response_file_range", { path, offset, length })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * How a chunk's bytes are returned. Mirrors Rust `ChunkFormat` in `http_client/spool.rs`.
+ */
+export type ChunkFormat = "base64" | "hex"
+
+/**
+ * Mirrors Rust `ChunkRequest` in `http_client/spool.rs`.
+ */
+export type ChunkRequest = {
+  /** The file a large response was spooled to (`Response.filePath`) */
+  path: string
+  offset: number
+  /** Bytes to read, at most 4 MiB */
+  length: number
+  /** "base64" by default */
+  format?: ChunkFormat
+  /** Bytes per hex dump row, 16 by default */
+  bytesPerRow?: number
+}
+
+/**
+ * Mirrors Rust `HexRow` in `http_client/spool.rs`.
+ */
+export type HexRow = {
+  /** Body offset of the row's first byte */
+  offset: number
+  /** The bytes as space-separated hex pairs, e.g. "7b 22 69 64" */
+  hex: string
+  /** The bytes as ASCII, with `.` for anything unprintable */
+  text: string
+}
+
+/**
+ * Mirrors Rust `ChunkContent` in `http_client/spool.rs`.
+ */
+export type ChunkContent = { format: "base64"; data: string } | { format: "hex"; rows: HexRow[] }
+
+/**
+ * Mirrors Rust `BodyChunk` in `http_client/spool.rs`.
+ */
+export type BodyChunk = {
+  offset: number
+  /** Bytes read; fewer than asked for at the end of the body */
+  length: number
+  /** Whether the chunk reaches the end of the body */
+  eof: boolean
+  /** Size of the whole body */
+  totalLength: number
+  content: ChunkContent
+}
+
+/**
+ * Read a chunk of a spooled response body, base64-encoded or as hex dump rows, so a paged hex or text viewer
+ * never loads the whole file. Offsets are the body's own whether or not the file is compressed.
+ * Mirrors `fn read_response_file_chunk(request: ChunkRequest) -> Result<BodyChunk, AppError>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust, e.g. when the file is gone.
+ */
+export async function readResponseFileChunk(request: ChunkRequest): Promise<BodyChunk> {
+  try {
+    return await invoke<BodyChunk>("read_response_file_chunk", { request })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+export interface AuthPlacement {
+  type: string
+  name?: string
+  fieldName?: string
+  contentType?: string
+}
+
+export interface AuthConfig {
+  type: string
+  placement?: AuthPlacement
+  username?: string
+  password?: string
+  token?: string
+  // Optional scheme for Authorization header (e.g., "Bearer", "JWT", or custom)
+  scheme?: string
+  key?: string
+  value?: string
+  grantType?: "client_credentials" | "password" | "refresh_token" | "authorization_code" | "device_code"
+  authUrl?: string
+  /** Device authorization endpoint for the device_code grant */
+  deviceAuthorizationUrl?: string
+  tokenUrl?: string
+  clientId?: string
+  clientSecret?: string
+  scope?: string
+  refreshToken?: string
+  domain?: string
+  workstation?: string
+  redirectUri?: string
+  usePkce?: boolean
+  /** Sign in with the system browser (loopback redirect) or a Knurl window that intercepts the redirect */
+  authorizationWindow?: "system" | "embedded"
+  tokenCaching?: "always" | "never"
+  clientAuth?: "basic" | "body" | "privateKeyJwt"
+  /** Name of the keyring signing key (see `storeSigningKey`) that signs private_key_jwt assertions */
+  assertionKeyName?: string
+  /** Algorithm of private_key_jwt assertions (default RS256) */
+  assertionAlgorithm?: "RS256" | "PS256" | "ES256"
+  /** `kid` header of private_key_jwt assertions */
+  assertionKeyId?: string
+  tokenExtraParams?: Record<string, string>
+  /** Check the ID token's signature against the provider's JWKS and its `iss`, `aud` and `exp` claims */
+  validateIdToken?: boolean
+  /** Expected `iss` of ID tokens; its discovery document locates the JWKS unless `jwksUrl` is set */
+  issuer?: string
+  jwksUrl?: string
+  /** Token revocation endpoint (RFC 7009), used by `revokeToken` */
+  revocationUrl?: string
+}
+
+export interface AuthResult {
+  headers?: Record<string, string>
+  query?: Record<string, string>
+  cookies?: Record<string, string>
+  body?: Record<string, unknown>
+  expiresAt?: number
+  /** Credentials the backend negotiates NTLM with; pass on as `Request.ntlm` */
+  ntlm?: NtlmCredentials
+  /** Credentials the backend answers `WWW-Authenticate` challenges with; pass on as `Request.challengeAuth` */
+  challenge?: ChallengeCredentials
+  /** Claims of the ID token issued with the access token, validated when `validateIdToken` is set */
+  idTokenClaims?: Record<string, unknown>
+}
+
+export interface OidcDiscovery {
+  issuer?: string
+  jwksUri?: string
+  authorizationEndpoint?: string
+  tokenEndpoint?: string
+  deviceAuthorizationEndpoint?: string
+  revocationEndpoint?: string
+}
+
+/**
+ * Retrieves the result of an authentication flow. OAuth2 tokens are cached in an encrypted app data file:
+ * with `tokenCaching: "always"` an unexpired cached token is returned without contacting the server, and tokens
+ * near expiry are refreshed in the background when a refresh token was issued.
+ * Mirrors `async fn get_authentication_result(config: AuthConfig, parents: Option<Vec<AuthConfig>>) -> ...`.
+ *
+ * @param config The authentication configuration.
+ * @param parents Configurations of the collection and folders `config` sits in, outermost first; an `inherit`
+ *   config takes the nearest one that doesn't inherit in turn.
+ * @returns The result of the authentication flow.
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function getAuthenticationResult(
+  config: AuthConfig,
+  parentRequestId?: string,
+  parents?: AuthConfig[],
+): Promise<AuthResult> {
+  try {
+    return await invoke<AuthResult>("get_authentication_result", {
+      config,
+      parents,
+      parent_request_id: parentRequestId,
+    })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * A token to revoke; the tokens cached for the configuration when `token` is absent.
+ * Mirrors Rust `RevokeTokenRequest` in `http_client/auth/revocation.rs`.
+ */
+export type RevokeTokenRequest = {
+  token?: string
+  tokenTypeHint?: "access_token" | "refresh_token"
+}
+
+/**
+ * Mirrors Rust `RevocationResult` in `http_client/auth/revocation.rs`.
+ */
+export type RevocationResult = {
+  /** How many tokens the server accepted for revocation */
+  revoked: number
+  /** Whether the configuration's cached tokens were revoked and dropped from the cache */
+  cacheCleared: boolean
+}
+
+/**
+ * Revokes an OAuth2 token at the configuration's `revocationUrl` (RFC 7009). Without an explicit token the
+ * refresh and access tokens cached for `config` are revoked, then dropped from the token cache. `parents` resolve
+ * an inheriting `config` as for `getAuthenticationResult`.
+ * Mirrors `async fn revoke_token(config: AuthConfig, request: RevokeTokenRequest) -> Result<RevocationResult, _>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust.
+ */
+export async function revokeToken(
+  config: AuthConfig,
+  request: RevokeTokenRequest = {},
+  parentRequestId?: string,
+  parents?: AuthConfig[],
+): Promise<RevocationResult> {
+  try {
+    return await invoke<RevocationResult>("revoke_token", { config, parents, request, parentRequestId })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Discovers OIDC endpoints.
+ * Mirrors `async fn discover_oidc(app: tauri::AppHandle, url: String) -> Result<OidcDiscovery, String>`.
+ *
+ * @param url The base URL of the OIDC provider.
+ * @returns The discovered endpoints.
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function discoverOidc(url: string): Promise<OidcDiscovery> {
+  try {
+    return await invoke<OidcDiscovery>("discover_oidc", { url })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * How a browser page would make the call being analyzed.
+ * Mirrors Rust `CorsOptions` in `http_client/cors.rs`.
+ */
+export type CorsOptions = {
+  /** Origin of the calling page, e.g. "https://app.example.com" */
+  origin: string
+  /** The call sends credentials (`credentials: "include"`) */
+  withCredentials?: boolean
+  /** Also send the request with an `Origin` header and check its response (may have side effects) */
+  sendRequest?: boolean
+}
+
+export type CorsFinding = {
+  /** "error" findings are reasons the browser blocks the call */
+  severity: "error" | "warning" | "info"
+  stage: "request" | "preflight" | "response"
+  message: string
+}
+
+export type CorsExchange = {
+  status: number
+  headers: [string, string][]
+}
+
+/**
+ * Mirrors Rust `CorsReport` in `http_client/cors.rs`.
+ */
+export type CorsReport = {
+  origin: string
+  preflightRequired: boolean
+  preflightReasons: string[]
+  preflight?: CorsExchange
+  response?: CorsExchange
+  /** False when any finding is an error */
+  allowed: boolean
+  findings: CorsFinding[]
+}
+
+/**
+ * Send the preflight a browser on `options.origin` would send for `request` and explain why
+ * the browser would block the call, if it would.
+ * Mirrors `fn analyze_cors(request: Request, options: CorsOptions) -> Result<CorsReport, AppError>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust.
+ */
+export async function analyzeCors(request: Request, options: CorsOptions): Promise<CorsReport> {
+  try {
+    return await invoke<CorsReport>("analyze_cors", { request, options })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * A service instance found on the local network.
+ * Mirrors Rust `LocalService` in `http_client/mdns.rs`.
+ */
+export type LocalService = {
+  /** Instance name, e.g. "Office Printer" */
+  name: string
+  /** Full instance domain name, e.g. "Office Printer._http._tcp.local" */
+  instance: string
+  /** Host the service runs on, e.g. "printer.local" */
+  host?: string
+  port?: number
+  /** Addresses of `host` that came with the answer */
+  addresses: string[]
+  /** TXT record attributes; attributes without a value map to "" */
+  txt: Record<string, string>
+}
+
+/**
+ * Browse the local network with mDNS for instances of a DNS-SD service type such as `_http._tcp`.
+ * Answers are collected for `timeoutMs` (2 s by default, at most 10 s).
+ * Mirrors `fn discover_local_services(service_type: String, timeout_ms: Option<u64>) -> Result<Vec<LocalService>>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust.
+ */
+export async function discoverLocalServices(serviceType: string, timeoutMs?: number): Promise<LocalService[]> {
+  try {
+    return await invoke<LocalService[]>("discover_local_services", { serviceType, timeoutMs })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * When polling stops. With neither field set, any status other than 202 ends polling.
+ * Mirrors Rust `PollCondition` in `http_client/polling.rs`.
+ */
+export type PollCondition = {
+  /** Statuses that end polling, e.g. [200, 303] */
+  statusCodes?: number[]
+  /** JSONPath evaluated against the status body; without `equals` any match ends polling */
+  jsonPath?: string
+  /** Value a node selected by `jsonPath` must equal */
+  equals?: unknown
+}
+
+/**
+ * Mirrors Rust `PollOptions` in `http_client/polling.rs`.
+ */
+export type PollOptions = {
+  /** Delay before the first poll (default 1000) */
+  intervalMs?: number
+  /** Factor applied to the delay after each poll (default 1, a fixed interval) */
+  backoff?: number
+  /** Upper bound on the delay, including `Retry-After` delays (default 30000) */
+  maxIntervalMs?: number
+  /** Default 30 */
+  maxAttempts?: number
+  until?: PollCondition
+}
+
+export type PollStep = {
+  attempt: number
+  url: string
+  waitedMs: number
+  /** Time since the initial request was sent */
+  elapsedMs: number
+  matched: boolean
+  response: Response
+}
+
+/**
+ * Mirrors Rust `PollReport` in `http_client/polling.rs`.
+ */
+export type PollReport = {
+  initial: Response
+  polls: PollStep[]
+  /** True when a response met the terminal condition */
+  completed: boolean
+  /** Why polling stopped */
+  reason: string
+}
+
+/**
+ * Send a request that starts a long-running job and, while it answers 202 Accepted, poll the
+ * status URL from its `Location` header until `options.until` is met. Cancel with
+ * `cancelHttpRequest(request.requestId)`.
+ * Mirrors `fn poll_until(request: Request, options: PollOptions) -> Result<PollReport, AppError>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust.
+ */
+export async function pollUntil(request: Request, options: PollOptions): Promise<PollReport> {
+  try {
+    return await invoke<PollReport>("poll_until", { request, options })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Where the next page comes from when the API doesn't send `Link` headers.
+ * Mirrors Rust `PaginationCursor` in `http_client/pagination.rs`.
+ */
+export type PaginationCursor = {
+  /** JSONPath to the cursor in each page, e.g. `$.meta.next_cursor` */
+  jsonPath: string
+  /** Query parameter the cursor is sent in; without one the cursor is the next page's URL */
+  param?: string
+}
+
+/**
+ * Mirrors Rust `PaginationOptions` in `http_client/pagination.rs`.
+ */
+export type PaginationOptions = {
+  /** Default 10 */
+  maxPages?: number
+  /** Follow a cursor in the body instead of `Link: <...>; rel="next"` headers */
+  cursor?: PaginationCursor
+  /** JSONPath to the results in each page, e.g. `$.data[*]`; without one each body is one result */
+  itemsPath?: string
+}
+
+export type PageSummary = {
+  page: number
+  url: string
+  status: number
+  size: number
+  duration: number
+  timings: Timings
+  /** Results this page added to `items` */
+  itemCount: number
+}
+
+/**
+ * Mirrors Rust `PaginationReport` in `http_client/pagination.rs`.
+ */
+export type PaginationReport = {
+  pages: PageSummary[]
+  /** Results of all pages, in order */
+  items: unknown[]
+  /** Next page not fetched because `maxPages` was reached */
+  nextUrl?: string
+  /** Why paging stopped */
+  reason: string
+}
+
+/**
+ * Send `request` and follow its next-page links, or cursor, for up to `options.maxPages` pages,
+ * collecting the results of each. Cancel with `cancelHttpRequest(request.requestId)`.
+ * Mirrors `fn paginate(request: Request, options: PaginationOptions) -> Result<PaginationReport, AppError>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust.
+ */
+export async function paginate(request: Request, options: PaginationOptions): Promise<PaginationReport> {
+  try {
+    return await invoke<PaginationReport>("paginate", { request, options })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Mirrors Rust `DataRunOptions` in `http_client/data_run.rs`.
+ */
+export type DataRunOptions = {
+  /** Id the run is cancelled by, with `cancelHttpRequest(runId)` */
+  runId: string
+  /** Path of the CSV or JSON data file */
+  dataPath: string
+  /** Detected from the file's first character when unset: `[` is JSON, anything else CSV */
+  format?: "csv" | "json"
+  /** Delay between rows (default 0) */
+  delayMs?: number
+  /** Stop after the first row that fails */
+  stopOnFailure?: boolean
+}
+
+/**
+ * One request of a row. Mirrors Rust `DataRunStep` in `http_client/data_run.rs`.
+ */
+export type DataRunStep = {
+  requestId: string
+  /** URL after substitution */
+  url: string
+  /** Sent without error, and every assertion of the request passed */
+  passed: boolean
+  response?: Response
+  error?: AppError
+}
+
+/**
+ * Mirrors Rust `DataRunRow` in `http_client/data_run.rs`.
+ */
+export type DataRunRow = {
+  /** Position of the row in the data file, from 0 */
+  index: number
+  /** Column name/value pairs substituted into the requests */
+  values: [name: string, value: string][]
+  steps: DataRunStep[]
+  passed: boolean
+}
+
+/**
+ * Mirrors Rust `DataRunReport` in `http_client/data_run.rs`.
+ */
+export type DataRunReport = {
+  columns: string[]
+  rows: DataRunRow[]
+  /** Rows whose steps all passed */
+  passed: number
+  failed: number
+  /** Rows left unsent because `stopOnFailure` ended the run */
+  skipped: number
+}
+
+/**
+ * Send `requests` in order once for each row of a CSV (with a header row) or JSON (array of objects) data
+ * file, substituting the row's values for `{{column}}` placeholders in URLs, query parameters, headers and
+ * bodies. A request that fails to send fails its row without ending the run. Cancel with
+ * `cancelHttpRequest(options.runId)`.
+ * Mirrors `fn run_data_driven(requests: Vec<Request>, options: DataRunOptions) -> Result<DataRunReport, AppError>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust.
+ */
+export async function runDataDriven(requests: Request[], options: DataRunOptions): Promise<DataRunReport> {
+  try {
+    return await invoke<DataRunReport>("run_data_driven", { requests, options })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Mirrors Rust `LoadTestOptions` in `http_client/load_test.rs`.
+ */
+export type LoadTestOptions = {
+  /** Id the run is stopped by, with `cancelHttpRequest(runId)`; stopping keeps the results so far */
+  runId: string
+  /** Concurrent workers, 1 to 256 */
+  workers: number
+  /** Requests to send in all */
+  iterations?: number
+  /** Longest the run may take; with `iterations` too, the run ends at whichever comes first */
+  durationSecs?: number
+  /** Time between the statistics sent while the run is going (default 1000) */
+  reportIntervalMs?: number
+}
+
+/**
+ * Latencies of the responses received, in milliseconds.
+ * Mirrors Rust `LatencyStats` in `http_client/load_test.rs`.
+ */
+export type LatencyStats = {
+  min: number
+  mean: number
+  p50: number
+  p90: number
+  p95: number
+  p99: number
+  max: number
+}
+
+/**
+ * Statistics of a load test so far, or of the whole run once `done` is set.
+ * Mirrors Rust `LoadTestStats` in `http_client/load_test.rs`.
+ */
+export type LoadTestStats = {
+  elapsedMs: number
+  /** Requests answered or failed */
+  completed: number
+  /** Requests that failed to send or receive a response */
+  errors: number
+  /** Responses with a status of 400 or above */
+  httpErrors: number
+  /** Responses with an assertion that failed */
+  assertionFailures: number
+  /** Share (0 to 1) of completed requests that errored, got an error status or failed an assertion */
+  errorRate: number
+  /** Completed requests per second */
+  throughput: number
+  latency?: LatencyStats
+  /** Responses by status code */
+  statusCounts: Record<string, number>
+  /** Message of each error, with the number of times it occurred */
+  errorMessages: Record<string, number>
+  done: boolean
+  /** The run was stopped before it finished */
+  cancelled: boolean
+}
+
+/**
+ * Send `request` with `options.workers` concurrent workers for `options.iterations` requests or
+ * `options.durationSecs` seconds. Statistics are passed to `onStats` while the run goes, ending with the
+ * final ones, which the promise also resolves with. Log entries are dropped unless the request sets its own
+ * `logCapture`. Stop with `cancelHttpRequest(options.runId)`.
+ * Mirrors `fn run_load_test(request: Request, options: LoadTestOptions) -> Result<LoadTestStats, AppError>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust.
+ */
+export async function runLoadTest(
+  request: Request,
+  options: LoadTestOptions,
+  onStats: (stats: LoadTestStats) => void,
+): Promise<LoadTestStats> {
+  const channel = new Channel<LoadTestStats>()
+  channel.onmessage = onStats
+  try {
+    return await invoke<LoadTestStats>("run_load_test", { request, options, onStats: channel })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Mirrors Rust `VisualizeRequest` in `http_client/visualize.rs`.
+ */
+export type VisualizeRequest = {
+  /** JSON response body; or */
+  body?: string
+  /** the file a large response was spooled to (`ResponseData.filePath`) */
+  filePath?: string
+  /** jq filter, e.g. `.items[] | {name, latency: .timings.total}` */
+  transform: string
+  /** Rows returned at most (default 10000) */
+  maxRows?: number
+}
+
+/**
+ * Chart-ready result of a transform. Numbers make a series (null is a gap); objects make a table
+ * with a column per key, sorted by name; anything else makes a table with one `value` column.
+ * Mirrors Rust `Visualization` in `http_client/visualize.rs`.
+ */
+export type Visualization =
+  | { kind: "series"; values: (number | null)[]; truncated: boolean }
+  | { kind: "table"; columns: string[]; rows: unknown[][]; truncated: boolean }
+
+/**
+ * Run a jq transform over a JSON response body in the backend and return only the data to plot.
+ * Mirrors `fn visualize_response(request: VisualizeRequest) -> Result<Visualization, AppError>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust.
+ */
+export async function visualizeResponse(request: VisualizeRequest): Promise<Visualization> {
+  try {
+    return await invoke<Visualization>("visualize_response", { request })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Mirrors Rust `QueryLanguage` in `http_client/body_query.rs`.
+ */
+export type QueryLanguage = "jsonPath" | "jmesPath" | "xpath"
+
+/**
+ * Mirrors Rust `BodyQuery` in `http_client/body_query.rs`.
+ */
+export type BodyQuery = {
+  /** JSON response body; or */
+  body?: string
+  /** the file a large response was spooled to (`ResponseData.filePath`) */
+  filePath?: string
+  /** The response's `Content-Type`, whose `charset` a spooled XML body is decoded with */
+  contentType?: string
+  language: QueryLanguage
+  /** e.g. `$.items[?@.price > 10].name` (JSONPath), `items[*].name` (JMESPath) or `//m:Price` (XPath) */
+  expressions: string[]
+  /** Prefixes bound to namespace URIs for XPath, besides those the body declares */
+  namespaces?: Record<string, string>
+  /** Values returned at most for each expression (default 1000) */
+  maxResults?: number
+}
+
+/**
+ * What one expression matched. JMESPath gives one value, or none for a null result, and no paths. XPath gives
+ * each node as `{ node, name?, value }` with `value` its string value, or a lone boolean, number or string.
+ * Mirrors Rust `QueryResult` in `http_client/body_query.rs`.
+ */
+export type QueryResult = {
+  expression: string
+  values: unknown[]
+  /** Locations of the values, e.g. `$['items'][0]` or `/order[1]/item[2]` */
+  paths: string[]
+  truncated: boolean
+  /** Why the expression couldn't be run */
+  error?: string
+}
+
+/**
+ * Run JSONPath, JMESPath or XPath expressions over a response body in the backend and return only what they match.
+ * Mirrors `fn query_response_body(query: BodyQuery) -> Result<Vec<QueryResult>, AppError>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust.
+ */
+export async function queryResponseBody(query: BodyQuery): Promise<QueryResult[]> {
+  try {
+    return await invoke<QueryResult[]>("query_response_body", { query })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Mirrors Rust `TextRequest` in `http_client/charset.rs`.
+ */
+export type TextRequest = {
+  /** Response body bytes; or */
+  body?: Uint8Array | number[]
+  /** the file a large response was spooled to (`ResponseData.filePath`) */
+  filePath?: string
+  /** The response's `Content-Type`, whose `charset` the body is decoded with */
+  contentType?: string
+}
+
+/**
+ * Where a body's charset was found: a byte order mark (which wins), `Content-Type`, an `<?xml ...?>` declaration,
+ * or nowhere, meaning UTF-8. Mirrors Rust `CharsetSource` in `http_client/charset.rs`.
+ */
+export type CharsetSource = "bom" | "contentType" | "xmlDeclaration" | "default"
+
+/**
+ * Mirrors Rust `DecodedText` in `http_client/charset.rs`.
+ */
+export type DecodedText = {
+  text: string
+  /** Name of the charset the body was decoded from, e.g. "Shift_JIS"; ISO-8859-1 decodes as "windows-1252" */
+  charset: string
+  source: CharsetSource
+  /** Whether bytes invalid in the charset were replaced with U+FFFD */
+  hadErrors: boolean
+}
+
+/**
+ * Decode a response body to text in the backend, in the charset it declares (e.g. ISO-8859-1, Shift_JIS or GBK),
+ * rather than as UTF-8.
+ * Mirrors `fn decode_body_text(request: TextRequest) -> Result<DecodedText, AppError>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust, e.g. when the spooled file is gone.
+ */
+export async function decodeBodyText(request: TextRequest): Promise<DecodedText> {
+  try {
+    return await invoke<DecodedText>("decode_body_text", {
+      request: { ...request, body: request.body && Array.from(request.body) },
+    })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Mirrors Rust `XmlRequest` in `http_client/xml.rs`.
+ */
+export type XmlRequest = {
+  /** XML response body; or */
+  body?: string
+  /** the file a large response was spooled to (`ResponseData.filePath`) */
+  filePath?: string
+  /** The response's `Content-Type`, whose `charset` a spooled body is decoded with */
+  contentType?: string
+  /** Spaces per level of the pretty-printed body (default 2) */
+  indent?: number
+  /** Nodes in the tree at most (default 10000) */
+  maxNodes?: number
+}
+
+/**
+ * Mirrors Rust `XmlAttribute` in `http_client/xml.rs`. `name` is qualified, e.g. `xsi:type`.
+ */
+export type XmlAttribute = { name: string; namespace?: string; value: string }
+
+/**
+ * A node of an XML body; whitespace-only text is left out. Mirrors Rust `XmlNode` in `http_client/xml.rs`.
+ */
+export type XmlNode =
+  | { kind: "element"; name: string; namespace?: string; attributes: XmlAttribute[]; children: XmlNode[] }
+  | { kind: "text"; text: string }
+  | { kind: "comment"; text: string }
+  | { kind: "processingInstruction"; target: string; value?: string }
+
+/**
+ * Mirrors Rust `XmlDocument` in `http_client/xml.rs`.
+ */
+export type XmlDocument = {
+  pretty: string
+  /** The root element and any comments or processing instructions around it */
+  nodes: XmlNode[]
+  /** Whether the tree was cut at `maxNodes` */
+  truncated: boolean
+}
+
+/**
+ * Parse an XML or SOAP response body in the backend into its pretty-printed text and node tree.
+ * Mirrors `fn parse_xml_body(request: XmlRequest) -> Result<XmlDocument, AppError>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust.
+ */
+export async function parseXmlBody(request: XmlRequest): Promise<XmlDocument> {
+  try {
+    return await invoke<XmlDocument>("parse_xml_body", { request })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Where message types come from: a `.proto` file, whose imports are looked up in its own directory then
+ * `includePaths` (`google/protobuf/*.proto` are built in), or a binary `FileDescriptorSet` such as
+ * `protoc --include_imports --descriptor_set_out` writes. Mirrors Rust `ProtoSchema` in `http_client/protobuf.rs`.
+ */
+export type ProtoSchema =
+  | { type: "proto"; path: string; includePaths?: string[] }
+  | { type: "descriptorSet"; path: string }
+
+/**
+ * Mirrors Rust `ProtobufRequest` in `http_client/protobuf.rs`.
+ */
+export type ProtobufRequest = {
+  /** Response body bytes; or */
+  body?: Uint8Array | number[]
+  /** the file a large response was spooled to (`ResponseData.filePath`) */
+  filePath?: string
+  schema: ProtoSchema
+  /** Fully qualified name of the body's message type, e.g. `shop.v1.PriceReply` */
+  messageType: string
+  /** The body is gRPC framed: each message prefixed with a compression flag and its length */
+  grpc?: boolean
+  /** `grpc-encoding` of the response, which compressed messages are decoded with (gzip, deflate or zstd) */
+  grpcEncoding?: string
+}
+
+/**
+ * Mirrors Rust `DecodedProtobuf` in `http_client/protobuf.rs`.
+ */
+export type DecodedProtobuf = {
+  messageType: string
+  /** The messages in their JSON form (proto3 JSON mapping, default values included); one unless gRPC framed */
+  messages: unknown[]
+}
+
+/**
+ * Decode a Protocol Buffers or gRPC response body to JSON in the backend.
+ * Mirrors `fn decode_protobuf_body(request: ProtobufRequest) -> Result<DecodedProtobuf, AppError>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust, e.g. when the body isn't the type.
+ */
+export async function decodeProtobufBody(request: ProtobufRequest): Promise<DecodedProtobuf> {
+  try {
+    return await invoke<DecodedProtobuf>("decode_protobuf_body", {
+      request: { ...request, body: request.body && Array.from(request.body) },
+    })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * List the message types of a `.proto` file or descriptor set, sorted, to pick a body's type from.
+ * Mirrors `fn list_protobuf_message_types(schema: ProtoSchema) -> Result<Vec<String>, AppError>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust, e.g. when the schema doesn't parse.
+ */
+export async function listProtobufMessageTypes(schema: ProtoSchema): Promise<string[]> {
+  try {
+    return await invoke<string[]>("list_protobuf_message_types", { schema })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Mirrors Rust `PayloadFormat` in `http_client/payload_crypto.rs`.
+ * `jwe`: RSA-OAEP-256 key wrapping with A256GCM content encryption (compact serialization).
+ * `pgp`: an ASCII-armored OpenPGP message with an AES-256 session key for an RSA key.
+ */
+export type PayloadFormat = "jwe" | "pgp"
+
+/**
+ * Mirrors Rust `EncryptPayloadRequest` in `http_client/payload_crypto.rs`.
+ */
+export type EncryptPayloadRequest = {
+  format: PayloadFormat
+  plaintext: string
+  /** JWE: a `PUBLIC KEY` or `CERTIFICATE` PEM, or an RSA JWK. PGP: an ASCII-armored public key block */
+  publicKey: string
+  /** JWE `kid` header, defaulting to the `kid` of a JWK */
+  keyId?: string
+  /** JWE `cty` header, e.g. `application/json` */
+  contentType?: string
+}
+
+/**
+ * Mirrors Rust `DecryptPayloadRequest` in `http_client/payload_crypto.rs`.
+ */
+export type DecryptPayloadRequest = {
+  format: PayloadFormat
+  ciphertext: string
+  /**
+   * Name the private key was stored under with `storeSigningKey`: an RSA PEM, or for PGP also an
+   * unprotected armored secret key block
+   */
+  keyName: string
+}
+
+/**
+ * Encrypt a request body for a partner's RSA public key.
+ * Mirrors `fn encrypt_payload(request: EncryptPayloadRequest) -> Result<String, AppError>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust.
+ */
+export async function encryptPayload(request: EncryptPayloadRequest): Promise<string> {
+  try {
+    return await invoke<string>("encrypt_payload", { request })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Decrypt a JWE or PGP response body with a private key from the keyring. JWE may use RSA-OAEP or
+ * RSA-OAEP-256 with A128GCM or A256GCM.
+ * Mirrors `fn decrypt_payload(request: DecryptPayloadRequest) -> Result<String, AppError>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust; `KeyMismatch` when a
+ * PGP message isn't encrypted to the key.
+ */
+export async function decryptPayload(request: DecryptPayloadRequest): Promise<string> {
+  try {
+    return await invoke<string>("decrypt_payload", { request })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Result of one monitor check. Mirrors Rust `MonitorCheck` in `monitors/metrics.rs`.
+ */
+export type MonitorCheck = {
+  /** Stable identifier of the monitor */
+  monitorId: string
+  name?: string
+  url?: string
+  /** HTTP status code, if a response was received */
+  status?: number
+  success: boolean
+  latencyMs: number
+  /** Unix time (seconds) of the check; now when omitted */
+  timestamp?: number
+}
+
+/**
+ * Record a monitor check for the metrics endpoint and textfile.
+ * Mirrors `fn record_monitor_check(check: MonitorCheck) -> Result<(), AppError>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust.
+ */
+export async function recordMonitorCheck(check: MonitorCheck): Promise<void> {
+  try {
+    await invoke<void>("record_monitor_check", { check })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Drop the metrics of a monitor that no longer runs.
+ * Mirrors `fn remove_monitor_metrics(monitor_id: String) -> Result<(), AppError>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust.
+ */
+export async function removeMonitorMetrics(monitorId: string): Promise<void> {
+  try {
+    await invoke<void>("remove_monitor_metrics", { monitorId })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Serve monitor metrics in the Prometheus text format at `http://127.0.0.1:<port>/metrics`, port 9464 by default.
+ * Mirrors `fn start_monitor_metrics_endpoint(port: Option<u16>) -> Result<String, AppError>`.
+ *
+ * @returns The address the endpoint listens on.
+ * @throws Error whose `.appError` contains the structured AppError from Rust.
+ */
+export async function startMonitorMetricsEndpoint(port?: number): Promise<string> {
+  try {
+    return await invoke<string>("start_monitor_metrics_endpoint", { port })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Stop serving monitor metrics.
+ * Mirrors `fn stop_monitor_metrics_endpoint() -> Result<bool, AppError>`.
+ *
+ * @returns Whether the endpoint was running.
+ * @throws Error whose `.appError` contains the structured AppError from Rust.
+ */
+export async function stopMonitorMetricsEndpoint(): Promise<boolean> {
+  try {
+    return await invoke<boolean>("stop_monitor_metrics_endpoint")
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Write monitor metrics to `path` for the node_exporter textfile collector, replacing the file atomically.
+ * Mirrors `fn write_monitor_metrics_file(path: String) -> Result<(), AppError>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust.
+ */
+export async function writeMonitorMetricsFile(path: string): Promise<void> {
+  try {
+    await invoke<void>("write_monitor_metrics_file", { path })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Cancel an in-flight HTTP request by its requestId/correlationId.
+ */
+export async function cancelHttpRequest(requestId: string): Promise<void> {
+  try {
+    await invoke<void>("cancel_http_request", { requestId })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/** Event long-running backend operations report their progress with; see `OperationEvent` */
+export const OPERATION_PROGRESS_EVENT = "operation-progress"
+
+/**
+ * Mirrors Rust `OperationEvent` in `operations.rs`. Each operation emits `started`, throttled `progress`
+ * and one of `completed`, `failed` or `cancelled`.
+ */
+export type OperationEvent = {
+  /** Cancels the operation with `cancelOperation`; a download's is its request id */
+  operationId: string
+  kind: "download" | "keyRotation"
+  state: "started" | "progress" | "completed" | "failed" | "cancelled"
+  /** Units done so far: bytes for downloads, files for key rotation */
+  processed: number
+  total?: number | null
+  /** 0 to 100, when the total is known */
+  percent?: number | null
+  message?: string
+}
+
+/**
+ * Ask a long-running operation to stop.
+ * Mirrors `fn cancel_operation(operation_id: String) -> Result<bool, AppError>`.
+ *
+ * @returns Whether the operation was still running.
+ * @throws Error whose `.appError` (if present) contains the structured `AppError`.
+ */
+export async function cancelOperation(operationId: string): Promise<boolean> {
+  try {
+    return await invoke<boolean>("cancel_operation", { operationId })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Build the canonical form of a fully resolved request, e.g. to compare it with a teammate's.
+ * Mirrors `fn canonicalize_request(request: Request) -> Result<CanonicalRequest, AppError>`.
+ *
+ * @param request The same options that would be passed to `sendHttpRequest`.
+ * @throws Error whose `.appError` contains the structured AppError from Rust.
+ */
+export async function canonicalizeRequest(request: Request): Promise<CanonicalRequest> {
+  try {
+    return await invoke<CanonicalRequest>("canonicalize_request", { request })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Mirrors Rust `LogExportFormat` in `http_client/request_log.rs`.
+ * `ndjson`: one JSON log entry per line. `curl`: text in the style of `curl --verbose`.
+ */
+export type LogExportFormat = "ndjson" | "curl"
+
+/**
+ * Render the log the backend kept for a recent request, e.g. to attach to a ticket.
+ * Mirrors `fn export_request_log(request_id: String, format: LogExportFormat) -> Result<String, AppError>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust, e.g. when no log is kept for the request.
+ */
+export async function exportRequestLog(requestId: string, format: LogExportFormat): Promise<string> {
+  try {
+    return await invoke<string>("export_request_log", { requestId, format })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Convert the text of a Postman Collection v2.0/v2.1 export into a native collection export. Folder auth is copied
+ * to the requests that inherit it, and scripts are kept as comments in each request's tests. The result is unvalidated.
+ * Mirrors `fn convert_postman_collection(source: String) -> Result<Value, AppError>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust, e.g. when the text isn't a collection.
+ */
+export async function convertPostmanCollection(source: string): Promise<unknown> {
+  try {
+    return await invoke<unknown>("convert_postman_collection", { source })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Convert the text of an Insomnia v4 or v5 export into a native collection export. Request groups become folders,
+ * sub-environments are merged over their base environment, and `{{ _.name }}` references become `{{name}}`. YAML
+ * exports must be re-serialized as JSON first. The result is unvalidated.
+ * Mirrors `fn convert_insomnia_export(source: String) -> Result<Value, AppError>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust, e.g. when the text isn't an export.
+ */
+export async function convertInsomniaExport(source: string): Promise<unknown> {
+  try {
+    return await invoke<unknown>("convert_insomnia_export", { source })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Convert the text of a VS Code / JetBrains `.http` file into a native collection export. `###` lines separate and
+ * name requests, and `@name = value` file variables become an environment. The result is unvalidated.
+ * Mirrors `fn convert_http_file(source: String) -> Result<Value, AppError>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust, e.g. when the file has no requests.
+ */
+export async function convertHttpFile(source: string): Promise<unknown> {
+  try {
+    return await invoke<unknown>("convert_http_file", { source })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Write a native collection export as a `.http` file, with the active environment as file variables.
+ * Mirrors `fn export_http_file(collection: Value) -> Result<String, AppError>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust, e.g. when the export has no collection.
+ */
+export async function exportHttpFile(collection: unknown): Promise<string> {
+  try {
+    return await invoke<string>("export_http_file", { collection })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Render recent requests sent with `keepHar` as a HAR 1.2 document, to open in browser devtools or share.
+ * Mirrors `fn export_har(request_ids: Vec<String>) -> Result<String, AppError>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust, e.g. when a request isn't kept.
+ */
+export async function exportHar(requestIds: string[]): Promise<string> {
+  try {
+    return await invoke<string>("export_har", { requestIds })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Close the shared connection of a connection group, e.g. when a chained sequence ends.
+ * Mirrors `fn close_connection_group(group: String) -> Result<bool, AppError>`.
+ *
+ * @returns Whether the group had an open client.
+ * @throws Error whose `.appError` contains the structured AppError from Rust.
+ */
+export async function closeConnectionGroup(group: string): Promise<boolean> {
+  try {
+    return await invoke<boolean>("close_connection_group", { group })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Reload the OS trust store, e.g. after installing a certificate in it, and drop the cached root stores.
+ * Mirrors `fn refresh_root_store() -> Result<usize, AppError>`.
+ *
+ * @returns How many roots the reloaded store has.
+ * @throws Error whose `.appError` contains the structured AppError from Rust.
+ */
+export async function refreshRootStore(): Promise<number> {
+  try {
+    return await invoke<number>("refresh_root_store")
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * A DNS lookup cached for new connections. Mirrors Rust `DnsCacheEntry` in `http_client/hyper_engine/dns_cache.rs`.
+ */
+export type DnsCacheEntry = {
+  host: string
+  addresses: string[]
+  /** Time of the lookup, ISO 8601 */
+  resolvedAt: string
+  expiresInSecs: number
+}
+
+/**
+ * List the cached DNS lookups.
+ * Mirrors `fn list_dns_cache() -> Result<Vec<DnsCacheEntry>, AppError>`.
+ *
+ * @throws Error whose `.appError` contains the structured AppError from Rust.
+ */
+export async function listDnsCache(): Promise<DnsCacheEntry[]> {
+  try {
+    return await invoke<DnsCacheEntry[]>("list_dns_cache")
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Drop the cached DNS lookup of `host`, or all of them.
+ * Mirrors `fn flush_dns_cache(host: Option<String>) -> Result<usize, AppError>`.
+ *
+ * @returns How many lookups were dropped.
+ * @throws Error whose `.appError` contains the structured AppError from Rust.
+ */
+export async function flushDnsCache(host?: string): Promise<number> {
+  try {
+    return await invoke<number>("flush_dns_cache", { host })
+  } catch (err) {
+    normalizeInvokeError(err)
+  }
+}
+
+/**
+ * Drop every response kept for revalidation.
+ * Mirrors `fn clear_response_cache() -> Result<usize, AppError>`.
+ *
+ * @returns How many responses were dropped.
+ * @throws Error whose `.appError` contains the structured AppError from Rust.
+ */
+export async function clearResponseCache(): Promise<number> {
+  try {
+    return await invoke<number>("clear_response_cache")
   } catch (err) {
     normalizeInvokeError(err)
   }
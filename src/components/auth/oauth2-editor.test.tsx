@@ -26,5 +26,106 @@ describe("OAuth2Editor", () => {
 
     expect(onUpdate).toHaveBeenCalledWith({ grantType: "refresh_token" })
   })
-})
 
+  it("shows redirect and PKCE settings for the authorization code grant", async () => {
+    const user = userEvent.setup()
+    const onUpdate = vi.fn()
+    render(<OAuth2Editor auth={{ grantType: "authorization_code" }} onUpdate={onUpdate} onDiscover={() => {}} />)
+
+    expect(screen.getByLabelText(/token url/i)).toBeInTheDocument()
+    expect(screen.getByLabelText(/client id/i)).toBeInTheDocument()
+    await user.type(screen.getByLabelText(/redirect uri/i), "x")
+    expect(onUpdate).toHaveBeenCalledWith({ redirectUri: "x" })
+
+    const pkce = screen.getByRole("switch", { name: /pkce/i })
+    expect(pkce).toBeChecked()
+    await user.click(pkce)
+    expect(onUpdate).toHaveBeenCalledWith({ usePkce: false })
+
+    await user.click(screen.getByRole("combobox", { name: /sign in with/i }))
+    await user.click(await screen.findByRole("option", { name: /knurl window/i }))
+    expect(onUpdate).toHaveBeenCalledWith({ authorizationWindow: "embedded" })
+  })
+
+  it("asks for the issuer when ID token validation is on and shows the claims", async () => {
+    const user = userEvent.setup()
+    const onUpdate = vi.fn()
+    const { rerender } = render(
+      <OAuth2Editor auth={{ grantType: "authorization_code" }} onUpdate={onUpdate} onDiscover={() => {}} />,
+    )
+
+    expect(screen.queryByLabelText(/issuer/i)).not.toBeInTheDocument()
+    await user.click(screen.getByRole("switch", { name: /validate id token/i }))
+    expect(onUpdate).toHaveBeenCalledWith({ validateIdToken: true })
+
+    rerender(
+      <OAuth2Editor
+        auth={{ grantType: "authorization_code", validateIdToken: true }}
+        onUpdate={onUpdate}
+        onDiscover={() => {}}
+        token={{ value: "abc", idTokenClaims: { sub: "user-1" } }}
+      />,
+    )
+    await user.type(screen.getByLabelText(/issuer/i), "x")
+    expect(onUpdate).toHaveBeenCalledWith({ issuer: "x" })
+    expect(screen.getByLabelText(/id token claims/i)).toHaveValue(JSON.stringify({ sub: "user-1" }, null, 2))
+  })
+
+  it("asks for the device authorization endpoint for the device code grant", async () => {
+    const user = userEvent.setup()
+    const onUpdate = vi.fn()
+    render(<OAuth2Editor auth={{ grantType: "device_code" }} onUpdate={onUpdate} onDiscover={() => {}} />)
+
+    await user.type(screen.getByLabelText(/device url/i), "x")
+    expect(onUpdate).toHaveBeenCalledWith({ deviceAuthorizationUrl: "x" })
+    expect(screen.getByLabelText(/client id/i)).toBeInTheDocument()
+    expect(screen.queryByLabelText(/redirect uri/i)).not.toBeInTheDocument()
+  })
+
+  it("asks for a signing key when the client authenticates with private_key_jwt", async () => {
+    const user = userEvent.setup()
+    const onUpdate = vi.fn()
+    const { rerender } = render(
+      <OAuth2Editor auth={{ grantType: "client_credentials" }} onUpdate={onUpdate} onDiscover={() => {}} />,
+    )
+    expect(screen.queryByLabelText(/signing key/i)).not.toBeInTheDocument()
+
+    await user.click(screen.getByRole("combobox", { name: /client authentication/i }))
+    await user.click(await screen.findByRole("option", { name: /private_key_jwt/i }))
+    expect(onUpdate).toHaveBeenCalledWith({ clientAuth: "privateKeyJwt" })
+
+    rerender(
+      <OAuth2Editor
+        auth={{ grantType: "client_credentials", clientAuth: "privateKeyJwt" }}
+        onUpdate={onUpdate}
+        onDiscover={() => {}}
+      />,
+    )
+    await user.type(screen.getByLabelText(/signing key/i), "k")
+    expect(onUpdate).toHaveBeenCalledWith({ assertionKeyName: "k" })
+    expect(screen.getByRole("button", { name: /store key/i })).toBeDisabled()
+  })
+
+  it("revokes the token once there's a revocation endpoint", async () => {
+    const user = userEvent.setup()
+    const onUpdate = vi.fn()
+    const onRevoke = vi.fn()
+    const { rerender } = render(
+      <OAuth2Editor auth={{}} onUpdate={onUpdate} onDiscover={() => {}} token={{ value: "abc", onRevoke }} />,
+    )
+    expect(screen.getByRole("button", { name: /revoke/i })).toBeDisabled()
+    await user.type(screen.getByLabelText(/revocation url/i), "r")
+    expect(onUpdate).toHaveBeenCalledWith({ revocationUrl: "r" })
+
+    rerender(
+      <OAuth2Editor
+        auth={{ revocationUrl: "https://auth.example.com/revoke" }}
+        onUpdate={onUpdate}
+        onDiscover={() => {}}
+        token={{ value: "abc", onRevoke }}
+      />,
+    )
+    await user.click(screen.getByRole("button", { name: /revoke/i }))
+    expect(onRevoke).toHaveBeenCalled()
+  })
+})
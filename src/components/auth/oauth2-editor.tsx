@@ -1,9 +1,12 @@
-import React, { type FC } from "react"
+import React, { type FC, useState } from "react"
 
+import { storeSigningKey } from "@/bindings/knurl"
 import { Button } from "@/components/ui/button"
 import { Input } from "@/components/ui/knurl/input"
 import { Label } from "@/components/ui/label"
 import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from "@/components/ui/select"
+import { Switch } from "@/components/ui/switch"
+import { Textarea } from "@/components/ui/textarea"
 import type { OAuth2Auth } from "@/types/request"
 
 type FieldProps = {
@@ -31,13 +34,33 @@ export type OAuth2EditorProps = {
     value: string
     type?: string
     expiresAtSec?: number
+    idTokenClaims?: Record<string, unknown>
     onFetch?: () => void
     onDelete?: () => void
+    /** Revokes the cached tokens at the revocation endpoint */
+    onRevoke?: () => void
   }
 }
 
 export const OAuth2Editor: FC<OAuth2EditorProps> = ({ auth, onUpdate, onDiscover, token }) => {
   const grantType = auth.grantType ?? "client_credentials"
+  // Grants that authenticate the client at the token endpoint
+  const usesClient =
+    grantType === "client_credentials" || grantType === "authorization_code" || grantType === "device_code"
+  // Grants that can issue an OpenID Connect ID token
+  const issuesIdToken = grantType !== "client_credentials"
+  const [keyText, setKeyText] = useState("")
+  const [keyStatus, setKeyStatus] = useState<string>()
+
+  const handleStoreKey = async () => {
+    try {
+      await storeSigningKey(auth.assertionKeyName ?? "", keyText)
+      setKeyText("")
+      setKeyStatus(`Stored '${auth.assertionKeyName}' in the keyring`)
+    } catch (e) {
+      setKeyStatus((e as Error).message)
+    }
+  }
 
   const renderTokenMeta = () => {
     if (!token) {
@@ -95,6 +118,7 @@ export const OAuth2Editor: FC<OAuth2EditorProps> = ({ auth, onUpdate, onDiscover
             </SelectTrigger>
             <SelectContent>
               <SelectItem value="client_credentials">Client Credentials</SelectItem>
+              <SelectItem value="authorization_code">Authorization Code</SelectItem>
               <SelectItem value="refresh_token">Refresh Token</SelectItem>
               <SelectItem value="device_code">Device Code</SelectItem>
             </SelectContent>
@@ -114,7 +138,22 @@ export const OAuth2Editor: FC<OAuth2EditorProps> = ({ auth, onUpdate, onDiscover
         )}
       </Field>
 
-      {(grantType === "client_credentials" || !grantType) && (
+      {grantType === "device_code" && (
+        <Field label="Device URL">
+          {(id) => (
+            <Input
+              id={id}
+              type="text"
+              value={auth.deviceAuthorizationUrl ?? ""}
+              onChange={(e) => onUpdate({ deviceAuthorizationUrl: e.target.value })}
+              placeholder="Device authorization endpoint"
+              className="w-full font-mono"
+            />
+          )}
+        </Field>
+      )}
+
+      {usesClient && (
         <Field label="Token URL">
           {(id) => (
             <Input
@@ -128,7 +167,20 @@ export const OAuth2Editor: FC<OAuth2EditorProps> = ({ auth, onUpdate, onDiscover
         </Field>
       )}
 
-      {grantType === "client_credentials" && (
+      <Field label="Revocation URL">
+        {(id) => (
+          <Input
+            id={id}
+            type="text"
+            value={auth.revocationUrl ?? ""}
+            onChange={(e) => onUpdate({ revocationUrl: e.target.value || undefined })}
+            placeholder="Token revocation endpoint (optional)"
+            className="w-full font-mono"
+          />
+        )}
+      </Field>
+
+      {usesClient && (
         <div className="grid grid-cols-1 gap-4 md:grid-cols-2">
           <Field label="Client ID">
             {(id) => (
@@ -148,6 +200,13 @@ export const OAuth2Editor: FC<OAuth2EditorProps> = ({ auth, onUpdate, onDiscover
                 type="password"
                 value={auth.clientSecret ?? ""}
                 onChange={(e) => onUpdate({ clientSecret: e.target.value })}
+                placeholder={
+                  auth.clientAuth === "privateKeyJwt"
+                    ? "Not used with private_key_jwt"
+                    : grantType === "client_credentials"
+                      ? undefined
+                      : "Optional for public clients"
+                }
                 className="w-full font-mono"
               />
             )}
@@ -155,7 +214,7 @@ export const OAuth2Editor: FC<OAuth2EditorProps> = ({ auth, onUpdate, onDiscover
         </div>
       )}
 
-      {grantType === "client_credentials" && (
+      {usesClient && (
         <Field label="Scope">
           {(id) => (
             <Input
@@ -169,6 +228,93 @@ export const OAuth2Editor: FC<OAuth2EditorProps> = ({ auth, onUpdate, onDiscover
         </Field>
       )}
 
+      {grantType === "authorization_code" && (
+        <div className="grid grid-cols-1 gap-4 md:grid-cols-2">
+          <Field label="Sign In With">
+            {(id) => (
+              <Select
+                value={auth.authorizationWindow ?? "system"}
+                onValueChange={(value) => onUpdate({ authorizationWindow: value })}
+              >
+                <SelectTrigger id={id} className="w-full text-sm">
+                  <SelectValue placeholder="Select window" />
+                </SelectTrigger>
+                <SelectContent>
+                  <SelectItem value="system">System browser (loopback redirect)</SelectItem>
+                  <SelectItem value="embedded">Knurl window (intercepts redirect)</SelectItem>
+                </SelectContent>
+              </Select>
+            )}
+          </Field>
+          <Field label="Redirect URI">
+            {(id) => (
+              <Input
+                id={id}
+                type="text"
+                value={auth.redirectUri ?? ""}
+                onChange={(e) => onUpdate({ redirectUri: e.target.value })}
+                placeholder={
+                  auth.authorizationWindow === "embedded"
+                    ? "Registered redirect URI, e.g. https://app.example.com/callback"
+                    : "http://127.0.0.1/callback (any free port)"
+                }
+                className="w-full font-mono"
+              />
+            )}
+          </Field>
+          <Field label="PKCE (S256)">
+            {(id) => (
+              <Switch
+                id={id}
+                checked={auth.usePkce ?? true}
+                onCheckedChange={(checked) => onUpdate({ usePkce: !!checked })}
+              />
+            )}
+          </Field>
+        </div>
+      )}
+
+      {issuesIdToken && (
+        <Field label="Validate ID Token">
+          {(id) => (
+            <Switch
+              id={id}
+              checked={auth.validateIdToken ?? false}
+              onCheckedChange={(checked) => onUpdate({ validateIdToken: !!checked })}
+            />
+          )}
+        </Field>
+      )}
+
+      {issuesIdToken && auth.validateIdToken && (
+        <div className="grid grid-cols-1 gap-4 md:grid-cols-2">
+          <Field label="Issuer">
+            {(id) => (
+              <Input
+                id={id}
+                type="text"
+                value={auth.issuer ?? ""}
+                onChange={(e) => onUpdate({ issuer: e.target.value })}
+                placeholder="Expected iss, e.g. https://auth.example.com"
+                className="w-full font-mono"
+              />
+            )}
+          </Field>
+          <Field label="JWKS URL">
+            {(id) => (
+              <Input
+                id={id}
+                type="text"
+                value={auth.jwksUrl ?? ""}
+                onChange={(e) => onUpdate({ jwksUrl: e.target.value || undefined })}
+                placeholder="Discovered from the issuer if empty"
+                className="w-full font-mono"
+              />
+            )}
+          </Field>
+        </div>
+      )}
+
       <div className="grid grid-cols-1 gap-4 md:grid-cols-2">
         <Field label="Client Authentication">
           {(id) => (
@@ -179,6 +325,7 @@ export const OAuth2Editor: FC<OAuth2EditorProps> = ({ auth, onUpdate, onDiscover
               <SelectContent>
                 <SelectItem value="body">Request body (client_id/client_secret)</SelectItem>
                 <SelectItem value="basic">Authorization header (Basic)</SelectItem>
+                <SelectItem value="privateKeyJwt">Signed JWT (private_key_jwt)</SelectItem>
               </SelectContent>
             </Select>
           )}
@@ -198,6 +345,76 @@ export const OAuth2Editor: FC<OAuth2EditorProps> = ({ auth, onUpdate, onDiscover
         </Field>
       </div>
 
+      {auth.clientAuth === "privateKeyJwt" && (
+        <div className="grid grid-cols-1 gap-4 md:grid-cols-2">
+          <Field label="Signing Key">
+            {(id) => (
+              <Input
+                id={id}
+                type="text"
+                value={auth.assertionKeyName ?? ""}
+                onChange={(e) => onUpdate({ assertionKeyName: e.target.value })}
+                placeholder="Key name in the keyring"
+                className="w-full font-mono"
+              />
+            )}
+          </Field>
+          <Field label="JWT Algorithm">
+            {(id) => (
+              <Select
+                value={auth.assertionAlgorithm ?? "RS256"}
+                onValueChange={(value) => onUpdate({ assertionAlgorithm: value })}
+              >
+                <SelectTrigger id={id} className="w-full text-sm">
+                  <SelectValue />
+                </SelectTrigger>
+                <SelectContent>
+                  <SelectItem value="RS256">RS256</SelectItem>
+                  <SelectItem value="PS256">PS256</SelectItem>
+                  <SelectItem value="ES256">ES256</SelectItem>
+                </SelectContent>
+              </Select>
+            )}
+          </Field>
+          <Field label="Key ID">
+            {(id) => (
+              <Input
+                id={id}
+                type="text"
+                value={auth.assertionKeyId ?? ""}
+                onChange={(e) => onUpdate({ assertionKeyId: e.target.value || undefined })}
+                placeholder="kid header (optional)"
+                className="w-full font-mono"
+              />
+            )}
+          </Field>
+          <Field label="Private Key">
+            {(id) => (
+              <div className="space-y-2">
+                <Textarea
+                  id={id}
+                  value={keyText}
+                  placeholder="Paste a PEM private key to store it under the key name"
+                  onChange={(e) => setKeyText(e.target.value)}
+                  className="min-h-20 font-mono text-xs"
+                />
+                <div className="flex items-center gap-2">
+                  <Button
+                    variant="secondary"
+                    size="sm"
+                    disabled={!keyText.trim() || !auth.assertionKeyName}
+                    onClick={handleStoreKey}
+                  >
+                    Store Key
+                  </Button>
+                  {keyStatus && <span className="text-xs text-muted-foreground">{keyStatus}</span>}
+                </div>
+              </div>
+            )}
+          </Field>
+        </div>
+      )}
+
       {token && (
         <Field label="Access Token">
           {(id) => (
@@ -214,6 +431,16 @@ export const OAuth2Editor: FC<OAuth2EditorProps> = ({ auth, onUpdate, onDiscover
                     Delete
                   </Button>
                 )}
+                {token.onRevoke && (
+                  <Button
+                    variant="outline"
+                    size="sm"
+                    onClick={token.onRevoke}
+                    disabled={!token.value || !auth.revocationUrl}
+                  >
+                    Revoke
+                  </Button>
+                )}
               </div>
               {renderTokenMeta()}
             </div>
@@ -221,6 +448,19 @@ export const OAuth2Editor: FC<OAuth2EditorProps> = ({ auth, onUpdate, onDiscover
         </Field>
       )}
 
+      {token?.idTokenClaims && (
+        <Field label="ID Token Claims">
+          {(id) => (
+            <Textarea
+              id={id}
+              readOnly
+              value={JSON.stringify(token.idTokenClaims, null, 2)}
+              className="min-h-32 font-mono text-xs"
+            />
+          )}
+        </Field>
+      )}
+
       {grantType === "refresh_token" && (
         <Field label="Refresh Token">
           {(id) => (
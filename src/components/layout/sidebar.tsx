@@ -11,6 +11,7 @@ import {
   SearchIcon,
   SettingsIcon,
   SunIcon,
+  Trash2Icon,
   XIcon,
 } from "lucide-react"
 
@@ -88,6 +89,10 @@ export default function Sidebar() {
     sheetsApi.openSheet({ type: "settings" })
   }
 
+  const openTrashDialog = () => {
+    sheetsApi.openSheet({ type: "trash" })
+  }
+
   const openNewCollectionDialog = async () => {
     expandSidebar()
     setDialogProps({ action: "new" })
@@ -132,6 +137,19 @@ export default function Sidebar() {
                 </TooltipTrigger>
                 <TooltipContent>New Collection</TooltipContent>
               </Tooltip>
+              <Tooltip>
+                <TooltipTrigger asChild>
+                  <Button
+                    variant="ghost"
+                    size="sm"
+                    className="h-6 w-6 p-0 text-primary hover:text-primary"
+                    onClick={openTrashDialog}
+                  >
+                    <Trash2Icon className="h-5 w-5" />
+                  </Button>
+                </TooltipTrigger>
+                <TooltipContent>Trash</TooltipContent>
+              </Tooltip>
               <Tooltip>
                 <TooltipTrigger asChild>
                   <Button
@@ -12,7 +12,7 @@ import { OAuth2Editor } from "@/components/auth/oauth2-editor"
 import { SectionHeader } from "./section-header"
 import { credentialsCacheApi, useApplication } from "@/state/application"
 import { useCollections, useRequestTab } from "@/state"
-import type { ApiKeyAuth, BasicAuth, BearerAuth, OAuth2Auth } from "@/types/request"
+import type { ApiKeyAuth, BasicAuth, BearerAuth, NtlmAuth, OAuth2Auth } from "@/types/request"
 import { AuthTypes } from "@/types/request"
 
 export type RequestAuthPanelProps = {
@@ -80,6 +80,61 @@ const BasicAuthForm: FC<BasicAuthFormProps> = ({ auth, onUpdate }) => (
   </div>
 )
 
+type NtlmAuthFormProps = {
+  auth: Partial<NtlmAuth>
+  onUpdate: (updates: Record<string, unknown>) => void
+}
+
+const NtlmAuthForm: FC<NtlmAuthFormProps> = ({ auth, onUpdate }) => (
+  <div className="grid grid-cols-1 gap-4 md:grid-cols-2">
+    <AuthField label="Username">
+      {(id) => (
+        <Input
+          id={id}
+          type="text"
+          placeholder="DOMAIN\\user"
+          value={auth.username ?? ""}
+          onChange={(e) => onUpdate({ username: e.target.value })}
+          className="w-full font-mono"
+        />
+      )}
+    </AuthField>
+    <AuthField label="Password" placement="right">
+      {(id) => (
+        <Input
+          id={id}
+          type="password"
+          value={auth.password ?? ""}
+          onChange={(e) => onUpdate({ password: e.target.value })}
+          className="w-full font-mono"
+        />
+      )}
+    </AuthField>
+    <AuthField label="Domain">
+      {(id) => (
+        <Input
+          id={id}
+          type="text"
+          value={auth.domain ?? ""}
+          onChange={(e) => onUpdate({ domain: e.target.value })}
+          className="w-full font-mono"
+        />
+      )}
+    </AuthField>
+    <AuthField label="Workstation" placement="right">
+      {(id) => (
+        <Input
+          id={id}
+          type="text"
+          value={auth.workstation ?? ""}
+          onChange={(e) => onUpdate({ workstation: e.target.value })}
+          className="w-full font-mono"
+        />
+      )}
+    </AuthField>
+  </div>
+)
+
 type BearerAuthFormProps = {
   auth: Partial<BearerAuth>
   onUpdate: (updates: Record<string, unknown>) => void
@@ -319,9 +374,10 @@ type OAuth2AuthFormProps = {
   onUpdate: (updates: Record<string, unknown>) => void
   onDiscover: () => void
   onFetch: () => void
+  onRevoke: () => void
 }
 
-const OAuth2AuthForm: FC<OAuth2AuthFormProps> = ({ auth, onUpdate, onDiscover, onFetch }) => {
+const OAuth2AuthForm: FC<OAuth2AuthFormProps> = ({ auth, onUpdate, onDiscover, onFetch, onRevoke }) => {
   const { activeRequest } = useApplication(
     useShallow((state) => {
       const active = state.requestTabsState.activeTab
@@ -333,6 +389,7 @@ const OAuth2AuthForm: FC<OAuth2AuthFormProps> = ({ auth, onUpdate, onDiscover, o
   const [cachedToken, setCachedToken] = React.useState<string>("")
   const [tokenType, setTokenType] = React.useState<string>("")
   const [expiresAtSec, setExpiresAtSec] = React.useState<number | undefined>(undefined)
+  const [idTokenClaims, setIdTokenClaims] = React.useState<Record<string, unknown> | undefined>(undefined)
   const cacheKey = React.useMemo(
     () => (activeRequest ? `request-auth-${activeRequest.id}` : undefined),
     [activeRequest],
@@ -359,6 +416,7 @@ const OAuth2AuthForm: FC<OAuth2AuthFormProps> = ({ auth, onUpdate, onDiscover, o
         const headers = result.headers ?? {}
         const authHeader = headers.Authorization ?? headers.authorization
         setExpiresAtSec(typeof result.expiresAt === "number" ? result.expiresAt : undefined)
+        setIdTokenClaims(result.idTokenClaims)
         if (authHeader && typeof authHeader === "string") {
           const parts = authHeader.split(/\s+/)
           setTokenType(parts[0] ?? "")
@@ -380,6 +438,7 @@ const OAuth2AuthForm: FC<OAuth2AuthFormProps> = ({ auth, onUpdate, onDiscover, o
       setCachedToken("")
       setTokenType("")
       setExpiresAtSec(undefined)
+      setIdTokenClaims(undefined)
     }
   }, [cacheKey])
 
@@ -390,7 +449,15 @@ const OAuth2AuthForm: FC<OAuth2AuthFormProps> = ({ auth, onUpdate, onDiscover, o
       auth={auth}
       onUpdate={onUpdate}
       onDiscover={onDiscover}
-      token={{ value: cachedToken, type: tokenType, expiresAtSec, onFetch, onDelete: handleDeleteToken }}
+      token={{
+        value: cachedToken,
+        type: tokenType,
+        expiresAtSec,
+        idTokenClaims,
+        onFetch,
+        onDelete: handleDeleteToken,
+        onRevoke,
+      }}
     />
   )
 }
@@ -462,7 +529,11 @@ export function RequestAuthPanel({ tabId }: RequestAuthPanelProps) {
       const result = await discoverOidc(url)
       handleInputChange({
         authUrl: result.authorizationEndpoint,
+        deviceAuthorizationUrl: result.deviceAuthorizationEndpoint,
         tokenUrl: result.tokenEndpoint,
+        issuer: result.issuer,
+        jwksUrl: result.jwksUri,
+        revocationUrl: result.revocationEndpoint,
       })
     } catch (error) {
       console.error("OIDC Discovery failed:", error)
@@ -486,6 +557,11 @@ export function RequestAuthPanel({ tabId }: RequestAuthPanelProps) {
     switch (authentication.type) {
       case "basic":
         return <BasicAuthForm auth={authentication.basic ?? {}} onUpdate={handleInputChange} />
+      case "ntlm":
+        return <NtlmAuthForm auth={authentication.ntlm ?? {}} onUpdate={handleInputChange} />
+      case "digest":
+        // Same fields as Basic; the backend answers the server's challenge with them
+        return <BasicAuthForm auth={authentication.digest ?? {}} onUpdate={handleInputChange} />
       case "bearer":
         return (
           <BearerAuthForm
@@ -511,6 +587,7 @@ export function RequestAuthPanel({ tabId }: RequestAuthPanelProps) {
             onUpdate={handleInputChange}
             onDiscover={handleDiscover}
             onFetch={() => requestTabsApi.runAuthOnly(tabId)}
+            onRevoke={() => requestTabsApi.runAuthOnly(tabId, "revoke")}
           />
         )
       }
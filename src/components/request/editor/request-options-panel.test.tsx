@@ -37,6 +37,7 @@ describe("RequestOptionsPanel", () => {
         original: mockOriginal,
         autoSave: false,
         originalAutoSave: false,
+        identityProfiles: {},
       },
       actions: {
         updateClientOption: mockUpdateClientOption,
@@ -95,8 +96,51 @@ describe("RequestOptionsPanel", () => {
   it("updates DNS override when changed", () => {
     render(<RequestOptionsPanel tabId="1" />)
     const dnsInput = screen.getByLabelText("DNS Override")
-    fireEvent.change(dnsInput, { target: { value: "api.example.com:443:127.0.0.1" } })
-    expect(mockUpdateClientOption).toHaveBeenCalledWith({ hostOverride: "api.example.com:443:127.0.0.1" })
+    const hosts = "127.0.0.1 api.example.com auth.example.com\n::1 cdn # local\n10.0.0.1"
+    fireEvent.change(dnsInput, { target: { value: hosts } })
+    expect(mockUpdateClientOption).toHaveBeenCalledWith({
+      dnsOverrides: [
+        { host: "api.example.com", ip: "127.0.0.1" },
+        { host: "auth.example.com", ip: "127.0.0.1" },
+        { host: "cdn", ip: "::1" },
+      ],
+    })
+  })
+
+  it("updates insecure hosts when changed", () => {
+    render(<RequestOptionsPanel tabId="1" />)
+    const hostsInput = screen.getByLabelText("Insecure Hosts")
+    fireEvent.change(hostsInput, { target: { value: "dev.local\n\n*.staging.example.com name-only" } })
+    expect(mockUpdateClientOption).toHaveBeenCalledWith({
+      insecureHosts: [{ host: "dev.local" }, { host: "*.staging.example.com", hostnameOnly: true }],
+    })
+  })
+
+  it("updates assertions when changed", () => {
+    render(<RequestOptionsPanel tabId="1" />)
+    const assertionsInput = screen.getByLabelText("Assertions")
+    const text = [
+      "status 201",
+      "header Content-Type ~ ^application/json",
+      "header ETag",
+      'jsonpath $.owner = {"name": "Ada"}',
+      "jsonpath $.id = abc",
+      "contains ok",
+      "latency < 500",
+      "status nope",
+    ].join("\n")
+    fireEvent.change(assertionsInput, { target: { value: text } })
+    expect(mockUpdateClientOption).toHaveBeenCalledWith({
+      assertions: [
+        { type: "status", equals: 201 },
+        { type: "header", name: "Content-Type", matches: "^application/json" },
+        { type: "header", name: "ETag" },
+        { type: "jsonPath", path: "$.owner", equals: { name: "Ada" } },
+        { type: "jsonPath", path: "$.id", equals: "abc" },
+        { type: "bodyContains", text: "ok" },
+        { type: "latencyUnder", ms: 500 },
+      ],
+    })
   })
 
   it("updates autoSave when switch is clicked", async () => {
@@ -119,6 +163,16 @@ describe("RequestOptionsPanel", () => {
     expect(mockUpdateClientOption).toHaveBeenCalledWith({ disableSsl: true })
   })
 
+  it("updates canonicalizeJson when switch is clicked", async () => {
+    const user = userEvent.setup()
+    render(<RequestOptionsPanel tabId="1" />)
+
+    expect(screen.getByRole("combobox", { name: "Body Digest" })).toHaveTextContent("None")
+    await user.click(screen.getByRole("switch", { name: "Canonical JSON" }))
+
+    expect(mockUpdateClientOption).toHaveBeenCalledWith({ canonicalizeJson: true })
+  })
+
   it("switches between CA bundle path and text inputs", async () => {
     const user = userEvent.setup()
     render(<RequestOptionsPanel tabId="1" />)
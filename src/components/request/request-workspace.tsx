@@ -20,6 +20,7 @@ import {
 } from "@/components/ui/select"
 import { Toggle } from "@/components/ui/toggle"
 import { cn } from "@/lib"
+import { WebDavMethods, webdavDefaults } from "@/lib/webdav"
 import { ScratchCollectionId, useRequestTab } from "@/state"
 import type { HttpMethod } from "@/types"
 import { RequestEditor } from "./editor"
@@ -51,7 +52,7 @@ export default function RequestWorkspace({ tabId }: RequestWorkspaceProps) {
   const isDirty = requestTab.state.isDirty
 
   const handleMethodChange = (method: HttpMethod) => {
-    requestTabsApi.updateTabRequest(activeTab.tabId, { method })
+    requestTabsApi.updateTabRequest(activeTab.tabId, { method, ...webdavDefaults(request, method) })
     requestAnimationFrame(() => urlInputRef.current?.focus())
   }
 
@@ -146,6 +147,14 @@ export default function RequestWorkspace({ tabId }: RequestWorkspaceProps) {
                           </SelectItem>
                         ))}
                       </SelectGroup>
+                      <SelectGroup>
+                        <SelectLabel>WebDAV</SelectLabel>
+                        {WebDavMethods.map((method) => (
+                          <SelectItem key={method} value={method}>
+                            {method}
+                          </SelectItem>
+                        ))}
+                      </SelectGroup>
                     </SelectContent>
                   </Select>
 
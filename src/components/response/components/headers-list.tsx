@@ -3,7 +3,12 @@ import type React from "react"
 import { Badge } from "@/components/ui/badge"
 import { DataTable, DataTableCell, DataTableRow } from "@/components/shared/data-table"
 
-type HeadersMap = Record<string, string>
+type HeadersListProps = {
+  /** Headers in the order received; repeated names appear once per value */
+  headers: [string, string][]
+  /** Values that aren't valid UTF-8, by position in `headers` */
+  binaryHeaders?: { index: number; base64: string }[]
+}
 
 const Badge2 = ({ children }: { children: React.ReactNode }) => (
   <Badge variant="outline" className="px-1 py-0.5 text-[10px]">
@@ -32,17 +37,20 @@ const prettyValue = (name: string, value: string) => {
   return { text: value }
 }
 
-export const HeadersList = ({ headers }: { headers: HeadersMap }) => {
+export const HeadersList = ({ headers, binaryHeaders }: HeadersListProps) => {
+  const binary = new Map(binaryHeaders?.map((header) => [header.index, header.base64]))
   return (
     <DataTable columnTemplate="220px 1fr">
       <DataTableRow variant="header">
         <DataTableCell type="header">Header</DataTableCell>
         <DataTableCell type="header">Value</DataTableCell>
       </DataTableRow>
-      {Object.entries(headers).map(([name, value]) => {
-        const { text, sub, badge, tokens } = prettyValue(name, value)
+      {headers.map(([name, value], index) => {
+        const base64 = binary.get(index)
+        const { text, sub, badge, tokens } = base64 === undefined ? prettyValue(name, value) : { text: value }
         return (
-          <DataTableRow key={name}>
+          // biome-ignore lint/suspicious/noArrayIndexKey: repeated header names make the position the identity
+          <DataTableRow key={index}>
             <DataTableCell type="cell">{name}</DataTableCell>
             <DataTableCell type="cell">
               <div className="flex min-w-0 flex-wrap items-center gap-2">
@@ -50,6 +58,11 @@ export const HeadersList = ({ headers }: { headers: HeadersMap }) => {
                   {text}
                 </div>
                 {badge && <Badge2>{badge}</Badge2>}
+                {base64 !== undefined && (
+                  <span title={`Not valid UTF-8. Exact bytes (base64): ${base64}`}>
+                    <Badge2>binary</Badge2>
+                  </span>
+                )}
                 {tokens?.map((t) => (
                   <Badge2 key={t}>{t}</Badge2>
                 ))}
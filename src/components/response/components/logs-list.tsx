@@ -1,10 +1,11 @@
 import React from "react"
 
-import { CopyIcon, WrapTextIcon } from "lucide-react"
+import { CopyIcon, DownloadIcon, WrapTextIcon } from "lucide-react"
 
 import { DataTable, DataTableCell, DataTableRow } from "@/components/shared/data-table"
 import { Button } from "@/components/ui/button"
 import { Checkbox } from "@/components/ui/checkbox"
+import { DropdownMenu, DropdownMenuContent, DropdownMenuItem, DropdownMenuTrigger } from "@/components/ui/dropdown-menu"
 import { Popover, PopoverContent, PopoverTrigger } from "@/components/ui/popover"
 import { Tooltip, TooltipContent, TooltipTrigger } from "@/components/ui/tooltip"
 import { cn } from "@/lib/utils"
@@ -41,9 +42,20 @@ type LogsListProps = {
   sending?: boolean
   selectedLevels: LogLevel[]
   onSelectedLevelsChange?: (levels: LogLevel[]) => void
+  /** Exports the full log as kept by the backend, or the exchange as HAR; hidden when not given */
+  onExport?: (format: "ndjson" | "curl" | "har") => void
+  /** Offers HAR export, for requests sent with `keepHar` */
+  harExport?: boolean
 }
 
-export const LogsList = ({ logs, sending, selectedLevels, onSelectedLevelsChange }: LogsListProps) => {
+export const LogsList = ({
+  logs,
+  sending,
+  selectedLevels,
+  onSelectedLevelsChange,
+  onExport,
+  harExport,
+}: LogsListProps) => {
   const [selectedLevelSet, setSelectedLevelSet] = React.useState<Set<LogLevel>>(
     new Set(selectedLevels?.length ? selectedLevels : DEFAULT_LEVELS),
   )
@@ -228,10 +240,27 @@ export const LogsList = ({ logs, sending, selectedLevels, onSelectedLevelsChange
             </span>
           </div>
         </div>
-        <Button variant="ghost" size="sm" onClick={copyAllLogs} disabled={getFilteredLogs().length === 0}>
-          <CopyIcon className="mr-1 h-4 w-4" />
-          Copy Logs
-        </Button>
+        <div className="flex items-center gap-1">
+          {onExport && (
+            <DropdownMenu>
+              <DropdownMenuTrigger asChild>
+                <Button variant="ghost" size="sm" disabled={sending}>
+                  <DownloadIcon className="mr-1 h-4 w-4" />
+                  Export
+                </Button>
+              </DropdownMenuTrigger>
+              <DropdownMenuContent align="end">
+                <DropdownMenuItem onSelect={() => onExport("curl")}>curl verbose text</DropdownMenuItem>
+                <DropdownMenuItem onSelect={() => onExport("ndjson")}>NDJSON</DropdownMenuItem>
+                {harExport && <DropdownMenuItem onSelect={() => onExport("har")}>HAR</DropdownMenuItem>}
+              </DropdownMenuContent>
+            </DropdownMenu>
+          )}
+          <Button variant="ghost" size="sm" onClick={copyAllLogs} disabled={getFilteredLogs().length === 0}>
+            <CopyIcon className="mr-1 h-4 w-4" />
+            Copy Logs
+          </Button>
+        </div>
       </div>
       <div className="flex-1 overflow-auto font-mono text-sm">
         <DataTable columnTemplate="auto auto 1fr auto">
@@ -3,7 +3,7 @@ import React, { useState } from "react"
 import { openPath, revealItemInDir } from "@tauri-apps/plugin-opener"
 import { CodeIcon, CopyIcon, ExternalLinkIcon, FolderOpenIcon, ListRestartIcon } from "lucide-react"
 
-import { saveBinary, saveFile } from "@/bindings/knurl"
+import { exportHar, exportRequestLog, materializeResponseFile, saveBinary, saveFile } from "@/bindings/knurl"
 import { CodeViewer } from "@/components/editor/code-viewer"
 import { Badge } from "@/components/ui/badge"
 import { Button } from "@/components/ui/button"
@@ -13,7 +13,13 @@ import { useEvent } from "@/hooks/use-event"
 import { warmPrettier } from "@/lib/prettier"
 import { cn, isNotEmpty } from "@/lib/utils"
 import { useRequestTab } from "@/state"
-import { type CodeLanguage, CodeLanguages, DEFAULT_LOG_LEVELS, type ResponseState } from "@/types"
+import {
+  type CodeLanguage,
+  CodeLanguages,
+  DEFAULT_LOG_LEVELS,
+  type HttpResponseData,
+  type ResponseState,
+} from "@/types"
 import { CookieList, HeadersList, LogsList } from "./components"
 
 const detectLanguage = (contentType: string | undefined, body?: string): CodeLanguage => {
@@ -64,6 +70,37 @@ const detectLanguage = (contentType: string | undefined, body?: string): CodeLan
   return "text"
 }
 
+const formatTimings = (timings: NonNullable<HttpResponseData["timings"]>): string => {
+  const phases: Array<[string, number | null]> = [
+    ["DNS", timings.dns],
+    ["Connect", timings.tcpConnect],
+    ["TLS", timings.tlsHandshake],
+    ["Send", timings.requestWrite],
+    ["Wait", timings.timeToFirstByte],
+    ["Download", timings.download],
+  ]
+  const lines = phases.filter(([, ms]) => ms !== null).map(([label, ms]) => `${label}: ${ms}ms`)
+  return timings.connectionReused ? ["Reused connection", ...lines].join("\n") : lines.join("\n")
+}
+
+const formatRateLimit = (response: HttpResponseData): string | undefined => {
+  const { rateLimit, retries } = response
+  const lines = []
+  if (rateLimit) {
+    lines.push(`Remaining: ${rateLimit.remaining ?? "?"} of ${rateLimit.limit ?? "?"}`)
+    if (rateLimit.resetSecs !== null) {
+      lines.push(`Resets in: ${rateLimit.resetSecs}s`)
+    }
+  }
+  if (retries) {
+    lines.push(`Retries: ${retries.attempts} of ${retries.maxRetries} (waited ${retries.waitedMs}ms)`)
+  }
+  return lines.length ? lines.join("\n") : undefined
+}
+
+const formatAssertionResults = (results: NonNullable<HttpResponseData["assertions"]>): string =>
+  results.map(({ passed, message }) => `${passed ? "✓" : "✗"} ${message}`).join("\n")
+
 export type RequestTabsProps = {
   tabId: string
   className: string
@@ -179,6 +216,15 @@ export default function ResponseViewer({ tabId, className }: RequestTabsProps) {
     void navigator.clipboard.writeText(text)
   }
 
+  // Compressed spool files are decompressed to a copy before another app opens them
+  const handleOpenFile = async (path: string) => {
+    try {
+      await openPath(httpResponse?.fileCompressed ? await materializeResponseFile(path) : path)
+    } catch (error) {
+      console.error("Failed to open response file:", error)
+    }
+  }
+
   const handleLanguageChange = (lang: string) => {
     if (formattedView) {
       setFormattedView(false)
@@ -219,7 +265,7 @@ export default function ResponseViewer({ tabId, className }: RequestTabsProps) {
                       "ml-1 rounded px-1.5 py-0.5 text-xs",
                     )}
                   >
-                    {Object.keys(httpResponse?.headers ?? {}).length}
+                    {httpResponse?.headerList?.length ?? Object.keys(httpResponse?.headers ?? {}).length}
                   </Badge>
                 </TabsTrigger>
                 <TabsTrigger value="response-cookies" className="knurl-tab group/tab">
@@ -257,14 +303,59 @@ export default function ResponseViewer({ tabId, className }: RequestTabsProps) {
                   </div>
                   <div className="flex items-center gap-2">
                     <span className="text-muted-foreground">Time:</span>
-                    <span className="font-mono text-muted-foreground/75">{response.responseTime}ms</span>
+                    <span
+                      className="font-mono text-muted-foreground/75"
+                      title={httpResponse.timings ? formatTimings(httpResponse.timings) : undefined}
+                    >
+                      {response.responseTime}ms
+                    </span>
                   </div>
+                  {isNotEmpty(httpResponse.assertions) && (
+                    <div className="flex items-center gap-2">
+                      <span className="text-muted-foreground">Assertions:</span>
+                      <span
+                        className={cn(
+                          "font-mono font-medium",
+                          httpResponse.assertions.every(({ passed }) => passed) ? "text-green-500" : "text-red-500",
+                        )}
+                        title={formatAssertionResults(httpResponse.assertions)}
+                      >
+                        {httpResponse.assertions.filter(({ passed }) => passed).length}/{httpResponse.assertions.length}{" "}
+                        passed
+                      </span>
+                    </div>
+                  )}
+                  {(httpResponse.rateLimit || !!httpResponse.retries?.attempts) && (
+                    <div className="flex items-center gap-2">
+                      <span className="text-muted-foreground">Quota:</span>
+                      <span className="font-mono text-muted-foreground/75" title={formatRateLimit(httpResponse)}>
+                        {httpResponse.rateLimit?.remaining ?? "?"}
+                        {httpResponse.retries?.attempts ? ` (${httpResponse.retries.attempts} retries)` : ""}
+                      </span>
+                    </div>
+                  )}
                   <div className="flex items-center gap-2">
                     <span className="text-muted-foreground">Size:</span>
                     <span className="font-mono text-muted-foreground/75">
                       {formatBytes(response.responseSize ?? 0)}
+                      {httpResponse.truncated ? " (truncated)" : ""}
+                      {httpResponse.cancelled ? " (cancelled)" : ""}
+                      {httpResponse.cache === "revalidated" ? " (cached, not modified)" : ""}
+                      {httpResponse.contentRange
+                        ? ` (bytes ${httpResponse.contentRange.start}-${httpResponse.contentRange.end}` +
+                          `${httpResponse.contentRange.total != null ? ` of ${httpResponse.contentRange.total}` : ""})`
+                        : ""}
                     </span>
                   </div>
+                  {httpResponse.savedTo && (
+                    <div className="flex items-center gap-2">
+                      <span className="text-muted-foreground">Saved:</span>
+                      <span className="font-mono text-muted-foreground/75" title={httpResponse.savedTo}>
+                        {httpResponse.savedTo.split(/[\\/]/).pop()}
+                        {httpResponse.resumedFrom ? ` (resumed at ${formatBytes(httpResponse.resumedFrom)})` : ""}
+                      </span>
+                    </div>
+                  )}
                   <div className="flex items-center gap-2">
                     <Button
                       variant="ghost"
@@ -393,7 +484,7 @@ export default function ResponseViewer({ tabId, className }: RequestTabsProps) {
                             variant="ghost"
                             size="sm"
                             className="h-7 px-2"
-                            onClick={() => f && openPath(f)}
+                            onClick={() => f && handleOpenFile(f)}
                             title="Open saved response file"
                           >
                             <ExternalLinkIcon className="mr-1 h-3.5 w-3.5" /> Open
@@ -446,7 +537,7 @@ export default function ResponseViewer({ tabId, className }: RequestTabsProps) {
                             variant="ghost"
                             size="sm"
                             className="h-7 px-2"
-                            onClick={() => f && openPath(f)}
+                            onClick={() => f && handleOpenFile(f)}
                             title="Open saved response file"
                           >
                             <ExternalLinkIcon className="mr-1 h-3.5 w-3.5" /> Open
@@ -502,7 +593,10 @@ export default function ResponseViewer({ tabId, className }: RequestTabsProps) {
             )}
 
             <TabsContent value="response-headers" className="m-0 h-full p-4">
-              <HeadersList headers={httpResponse?.headers ?? {}} />
+              <HeadersList
+                headers={httpResponse?.headerList ?? Object.entries(httpResponse?.headers ?? {})}
+                binaryHeaders={httpResponse?.binaryHeaders}
+              />
             </TabsContent>
 
             <TabsContent value="response-cookies" className="m-0 h-full p-4">
@@ -527,6 +621,23 @@ export default function ResponseViewer({ tabId, className }: RequestTabsProps) {
                 sending={activeTab?.sending}
                 selectedLevels={response.logFilterLevels ?? DEFAULT_LOG_LEVELS}
                 onSelectedLevelsChange={(levels) => requestTabsApi.setResponseLogFilter(tabId, levels)}
+                harExport={request.options?.keepHar ?? false}
+                onExport={async (format) => {
+                  try {
+                    if (format === "har") {
+                      const har = await exportHar([response.requestId])
+                      await saveFile(har, { title: "Export HAR", defaultPath: "request.har" })
+                      return
+                    }
+                    const log = await exportRequestLog(response.requestId, format)
+                    await saveFile(log, {
+                      title: "Export Request Log",
+                      defaultPath: format === "ndjson" ? "request-log.ndjson" : "request-log.txt",
+                    })
+                  } catch (_e) {
+                    // ignore; user may have cancelled
+                  }
+                }}
               />
             </TabsContent>
           </div>
@@ -13,6 +13,14 @@ const HttpMethods: Record<HttpMethod, { abbr: string; color: string }> = {
   HEAD: { abbr: "HEAD", color: "bg-http-head text-http-head-foreground" },
   OPTIONS: { abbr: "OPT", color: "bg-http-options text-http-options-foreground" },
   TRACE: { abbr: "TRACE", color: "bg-http-trace text-http-trace-foreground" },
+  PROPFIND: { abbr: "PFIND", color: "bg-http-webdav text-http-webdav-foreground" },
+  PROPPATCH: { abbr: "PPATCH", color: "bg-http-webdav text-http-webdav-foreground" },
+  MKCOL: { abbr: "MKCOL", color: "bg-http-webdav text-http-webdav-foreground" },
+  COPY: { abbr: "COPY", color: "bg-http-webdav text-http-webdav-foreground" },
+  MOVE: { abbr: "MOVE", color: "bg-http-webdav text-http-webdav-foreground" },
+  LOCK: { abbr: "LOCK", color: "bg-http-webdav text-http-webdav-foreground" },
+  UNLOCK: { abbr: "UNLCK", color: "bg-http-webdav text-http-webdav-foreground" },
+  REPORT: { abbr: "RPT", color: "bg-http-webdav text-http-webdav-foreground" },
 }
 
 export type HttpBadgeProps = {
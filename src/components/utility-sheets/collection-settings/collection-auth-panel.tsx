@@ -1,7 +1,7 @@
 import React, { type FC, type ReactNode, useId } from "react"
 
 import type { AuthConfig as BindingAuthConfig } from "@/bindings/knurl"
-import { getAuthenticationResult, discoverOidc } from "@/bindings/knurl"
+import { getAuthenticationResult, discoverOidc, revokeToken } from "@/bindings/knurl"
 import { OAuth2Editor } from "@/components/auth/oauth2-editor"
 import { Alert, AlertDescription } from "@/components/ui/alert"
 import { InfoIcon } from "lucide-react"
@@ -19,7 +19,7 @@ import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from "@
 import { cn } from "@/lib/utils"
 import { credentialsCacheApi, useApplication, useCollection } from "@/state"
 import type { CollectionState } from "@/types"
-import type { ApiKeyAuth, AuthType, BasicAuth, BearerAuth, OAuth2Auth } from "@/types/request"
+import type { ApiKeyAuth, AuthType, BasicAuth, BearerAuth, NtlmAuth, OAuth2Auth } from "@/types/request"
 import { AuthTypes } from "@/types/request"
 
 type Props = {
@@ -81,6 +81,58 @@ const BasicAuthForm: FC<BasicAuthFormProps> = ({ auth, onUpdate }) => (
   </div>
 )
 
+// --- NTLM ---
+type NtlmAuthFormProps = { auth: Partial<NtlmAuth>; onUpdate: (updates: Record<string, unknown>) => void }
+const NtlmAuthForm: FC<NtlmAuthFormProps> = ({ auth, onUpdate }) => (
+  <div className="grid grid-cols-1 gap-4 md:grid-cols-2">
+    <AuthField label="Username">
+      {(id) => (
+        <Input
+          id={id}
+          type="text"
+          placeholder="DOMAIN\\user"
+          value={auth.username ?? ""}
+          onChange={(e) => onUpdate({ username: e.target.value })}
+          className="w-full font-mono"
+        />
+      )}
+    </AuthField>
+    <AuthField label="Password" placement="right">
+      {(id) => (
+        <Input
+          id={id}
+          type="password"
+          value={auth.password ?? ""}
+          onChange={(e) => onUpdate({ password: e.target.value })}
+          className="w-full font-mono"
+        />
+      )}
+    </AuthField>
+    <AuthField label="Domain">
+      {(id) => (
+        <Input
+          id={id}
+          type="text"
+          value={auth.domain ?? ""}
+          onChange={(e) => onUpdate({ domain: e.target.value })}
+          className="w-full font-mono"
+        />
+      )}
+    </AuthField>
+    <AuthField label="Workstation" placement="right">
+      {(id) => (
+        <Input
+          id={id}
+          type="text"
+          value={auth.workstation ?? ""}
+          onChange={(e) => onUpdate({ workstation: e.target.value })}
+          className="w-full font-mono"
+        />
+      )}
+    </AuthField>
+  </div>
+)
+
 // --- Bearer ---
 type BearerAuthFormProps = {
   auth: Partial<BearerAuth>
@@ -315,6 +367,7 @@ const OAuth2AuthFormShared: FC<OAuth2AuthFormProps> = ({ auth, onUpdate, onDisco
   const [cachedToken, setCachedToken] = React.useState<string>("")
   const [tokenType, setTokenType] = React.useState<string>("")
   const [expiresAtSec, setExpiresAtSec] = React.useState<number | undefined>(undefined)
+  const [idTokenClaims, setIdTokenClaims] = React.useState<Record<string, unknown> | undefined>(undefined)
 
   React.useEffect(() => {
     ;(async () => {
@@ -324,11 +377,13 @@ const OAuth2AuthFormShared: FC<OAuth2AuthFormProps> = ({ auth, onUpdate, onDisco
           setCachedToken("")
           setTokenType("")
           setExpiresAtSec(undefined)
+          setIdTokenClaims(undefined)
           return
         }
         const headers = result.headers ?? {}
         const authHeader = headers.Authorization ?? headers.authorization
         setExpiresAtSec(typeof result.expiresAt === "number" ? result.expiresAt : undefined)
+        setIdTokenClaims(result.idTokenClaims)
         if (authHeader && typeof authHeader === "string") {
           const parts = authHeader.split(/\s+/)
           setTokenType(parts[0] ?? "")
@@ -341,10 +396,44 @@ const OAuth2AuthFormShared: FC<OAuth2AuthFormProps> = ({ auth, onUpdate, onDisco
         setTokenType("")
         setCachedToken("")
         setExpiresAtSec(undefined)
+        setIdTokenClaims(undefined)
       }
     })()
   }, [cacheKey])
 
+  const clearToken = () => {
+    credentialsCacheApi().remove(cacheKey)
+    setCachedToken("")
+    setTokenType("")
+    setExpiresAtSec(undefined)
+    setIdTokenClaims(undefined)
+  }
+
+  const binding: BindingAuthConfig = {
+    type: "oauth2",
+    grantType: grantType,
+    authUrl: auth.authUrl,
+    deviceAuthorizationUrl: auth.deviceAuthorizationUrl,
+    tokenUrl: auth.tokenUrl,
+    clientId: auth.clientId,
+    clientSecret: auth.clientSecret,
+    scope: auth.scope,
+    refreshToken: auth.refreshToken,
+    tokenCaching: auth.tokenCaching ?? "always",
+    clientAuth: auth.clientAuth ?? "body",
+    assertionKeyName: auth.assertionKeyName,
+    assertionAlgorithm: auth.assertionAlgorithm,
+    assertionKeyId: auth.assertionKeyId,
+    tokenExtraParams: auth.tokenExtraParams,
+    redirectUri: auth.redirectUri,
+    usePkce: auth.usePkce,
+    authorizationWindow: auth.authorizationWindow,
+    validateIdToken: auth.validateIdToken,
+    issuer: auth.issuer,
+    jwksUrl: auth.jwksUrl,
+    revocationUrl: auth.revocationUrl,
+  }
+
   return (
     <OAuth2Editor
       auth={auth}
@@ -354,32 +443,23 @@ const OAuth2AuthFormShared: FC<OAuth2AuthFormProps> = ({ auth, onUpdate, onDisco
         value: cachedToken,
         type: tokenType,
         expiresAtSec,
+        idTokenClaims,
         onFetch: async () => {
           try {
-            const binding: BindingAuthConfig = {
-              type: "oauth2",
-              grantType: grantType,
-              authUrl: auth.authUrl,
-              tokenUrl: auth.tokenUrl,
-              clientId: auth.clientId,
-              clientSecret: auth.clientSecret,
-              scope: auth.scope,
-              refreshToken: auth.refreshToken,
-              tokenCaching: auth.tokenCaching ?? "always",
-              clientAuth: auth.clientAuth ?? "body",
-              tokenExtraParams: auth.tokenExtraParams,
-            }
             const result = await getAuthenticationResult(binding, `collection-auth-${collectionId}`)
             await credentialsCacheApi().set(cacheKey, result)
           } catch (e) {
             console.error("Collection OAuth2 fetch failed:", e)
           }
         },
-        onDelete: () => {
-          credentialsCacheApi().remove(cacheKey)
-          setCachedToken("")
-          setTokenType("")
-          setExpiresAtSec(undefined)
+        onDelete: clearToken,
+        onRevoke: async () => {
+          try {
+            await revokeToken(binding, {}, `collection-auth-${collectionId}`)
+            clearToken()
+          } catch (e) {
+            console.error("Collection OAuth2 revocation failed:", e)
+          }
         },
       }}
     />
@@ -465,7 +545,14 @@ export default function CollectionAuthPanel({ collectionId }: Props) {
       const normalized = discoveryBase.replace(/\/$/, "")
       const url = /\.well-known\//.test(normalized) ? normalized : `${normalized}/.well-known/openid-configuration`
       const result = await discoverOidc(url)
-      handleInputChange({ authUrl: result.authorizationEndpoint, tokenUrl: result.tokenEndpoint })
+      handleInputChange({
+        authUrl: result.authorizationEndpoint,
+        deviceAuthorizationUrl: result.deviceAuthorizationEndpoint,
+        tokenUrl: result.tokenEndpoint,
+        issuer: result.issuer,
+        jwksUrl: result.jwksUri,
+        revocationUrl: result.revocationEndpoint,
+      })
     } catch (err) {
       console.error("OIDC Discovery failed:", err)
     }
@@ -475,6 +562,11 @@ export default function CollectionAuthPanel({ collectionId }: Props) {
     switch (authentication.type) {
       case "basic":
         return <BasicAuthForm auth={authentication.basic ?? {}} onUpdate={handleInputChange} />
+      case "ntlm":
+        return <NtlmAuthForm auth={authentication.ntlm ?? {}} onUpdate={handleInputChange} />
+      case "digest":
+        // Same fields as Basic; the backend answers the server's challenge with them
+        return <BasicAuthForm auth={authentication.digest ?? {}} onUpdate={handleInputChange} />
       case "bearer":
         return (
           <BearerAuthForm
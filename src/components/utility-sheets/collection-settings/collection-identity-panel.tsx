@@ -0,0 +1,134 @@
+import { type ReactNode, useId } from "react"
+
+import { PlusIcon, Trash2Icon } from "lucide-react"
+
+import { Button } from "@/components/ui/button"
+import { Input } from "@/components/ui/knurl/input"
+import { Label } from "@/components/ui/label"
+import { Textarea } from "@/components/ui/textarea"
+import { useCollection } from "@/state"
+import type { IdentityProfile } from "@/types"
+
+type Props = {
+  collectionId: string
+}
+
+type ProfileFieldProps = {
+  label: ReactNode
+  children: (id: string) => ReactNode
+}
+
+function ProfileField({ label, children }: ProfileFieldProps) {
+  const id = useId()
+  return (
+    <div className="grid grid-cols-[9rem_auto] items-center gap-x-4">
+      <Label htmlFor={id} className="text-sm text-muted-foreground">
+        {label}
+      </Label>
+      <div className="min-w-0">{children(id)}</div>
+    </div>
+  )
+}
+
+const textFields: { key: keyof IdentityProfile; label: string; placeholder: string }[] = [
+  { key: "userAgent", label: "User-Agent", placeholder: "Mozilla/5.0 (Windows NT 10.0; Win64; x64) ..." },
+  { key: "acceptLanguage", label: "Accept-Language", placeholder: "en-US,en;q=0.9" },
+  { key: "secChUa", label: "sec-ch-ua", placeholder: '"Chromium";v="124", "Google Chrome";v="124"' },
+  { key: "secChUaMobile", label: "sec-ch-ua-mobile", placeholder: "?0" },
+  { key: "secChUaPlatform", label: "sec-ch-ua-platform", placeholder: '"Windows"' },
+]
+
+// Extra headers are edited as "Name: value" lines
+const formatHeaders = (headers: Record<string, string> | undefined) =>
+  Object.entries(headers ?? {})
+    .map(([name, value]) => `${name}: ${value}`)
+    .join("\n")
+
+const parseHeaders = (text: string): Record<string, string> => {
+  const headers: Record<string, string> = {}
+  for (const line of text.split("\n")) {
+    const separator = line.indexOf(":")
+    if (separator > 0) {
+      headers[line.slice(0, separator).trim()] = line.slice(separator + 1).trim()
+    }
+  }
+  return headers
+}
+
+export default function CollectionIdentityPanel({ collectionId }: Props) {
+  const {
+    state: { collection },
+    actions: { collectionsApi },
+  } = useCollection(collectionId)
+
+  if (!collection) {
+    return null
+  }
+
+  const profiles = Object.values(collection.identityProfiles ?? {})
+  const update = (id: string, changes: Partial<IdentityProfile>) =>
+    void collectionsApi().updateIdentityProfile(collection.id, id, changes)
+
+  return (
+    <div className="space-y-6 p-6">
+      <p className="text-sm text-muted-foreground">
+        Identity profiles are sets of client headers that requests in this collection can send by selecting one in
+        their Options. Headers a request sets itself take precedence.
+      </p>
+
+      {profiles.map((profile) => (
+        <section key={profile.id} className="space-y-3 rounded-md border p-4" aria-label={profile.name}>
+          <div className="flex items-center gap-2">
+            <Input
+              aria-label="Profile name"
+              value={profile.name}
+              placeholder="Profile name"
+              onChange={(e) => update(profile.id, { name: e.target.value })}
+              className="font-medium"
+            />
+            <Button
+              variant="ghost"
+              size="icon"
+              aria-label={`Delete ${profile.name}`}
+              onClick={() => void collectionsApi().deleteIdentityProfile(collection.id, profile.id)}
+            >
+              <Trash2Icon className="h-4 w-4" />
+            </Button>
+          </div>
+          {textFields.map(({ key, label, placeholder }) => (
+            <ProfileField key={key} label={label}>
+              {(id) => (
+                <Input
+                  id={id}
+                  value={(profile[key] as string | undefined) ?? ""}
+                  placeholder={placeholder}
+                  onChange={(e) => update(profile.id, { [key]: e.target.value || undefined })}
+                  className="w-full font-mono"
+                />
+              )}
+            </ProfileField>
+          ))}
+          <ProfileField label="Other headers">
+            {(id) => (
+              <Textarea
+                id={id}
+                defaultValue={formatHeaders(profile.headers)}
+                placeholder="Accept: text/html,application/xhtml+xml"
+                onBlur={(e) => update(profile.id, { headers: parseHeaders(e.target.value) })}
+                className="w-full font-mono"
+              />
+            )}
+          </ProfileField>
+        </section>
+      ))}
+
+      <Button
+        variant="outline"
+        size="sm"
+        onClick={() => void collectionsApi().createIdentityProfile(collection.id, "New profile")}
+      >
+        <PlusIcon className="mr-1 h-4 w-4" /> Add profile
+      </Button>
+    </div>
+  )
+}
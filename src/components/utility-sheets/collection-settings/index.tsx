@@ -1,16 +1,19 @@
 import { useState } from "react"
 
-import { GlobeIcon, LockIcon } from "lucide-react"
+import { FingerprintIcon, GlobeIcon, LockIcon } from "lucide-react"
 
 import { SheetDescription, SheetHeader, SheetTitle } from "@/components/ui/sheet"
 import EnvironmentManager from "@/components/utility-sheets/environment-manager"
 import { Button } from "@/components/ui/button"
 import { useCollection } from "@/state"
 import CollectionAuthPanel from "./collection-auth-panel"
+import CollectionIdentityPanel from "./collection-identity-panel"
+
+type CollectionSettingsTab = "environments" | "authentication" | "identities"
 
 type Props = {
   collectionId: string
-  tab?: "environments" | "authentication"
+  tab?: CollectionSettingsTab
   selectedEnvironmentId?: string
 }
 
@@ -18,7 +21,7 @@ export default function CollectionSettingsSheet({ collectionId, tab = "environme
   const {
     state: { collection },
   } = useCollection(collectionId)
-  const [activeTab, setActiveTab] = useState<"environments" | "authentication">(tab)
+  const [activeTab, setActiveTab] = useState<CollectionSettingsTab>(tab)
 
   return (
     <div className="flex h-full min-h-0 flex-col overflow-hidden">
@@ -27,7 +30,9 @@ export default function CollectionSettingsSheet({ collectionId, tab = "environme
           <LockIcon className="h-5 w-5" />
           Collection Settings
         </SheetTitle>
-        <SheetDescription>Manage environments and authentication defaults for {collection.name}.</SheetDescription>
+        <SheetDescription>
+          Manage environments, authentication defaults and identity profiles for {collection.name}.
+        </SheetDescription>
       </SheetHeader>
 
       <div className="flex flex-col overflow-hidden">
@@ -50,6 +55,15 @@ export default function CollectionSettingsSheet({ collectionId, tab = "environme
           >
             <LockIcon className="mr-1 h-4 w-4" /> Authentication
           </Button>
+          <Button
+            variant={activeTab === "identities" ? "secondary" : "ghost"}
+            className="justify-start"
+            onClick={() => setActiveTab("identities")}
+            aria-pressed={activeTab === "identities"}
+            size="sm"
+          >
+            <FingerprintIcon className="mr-1 h-4 w-4" /> Identities
+          </Button>
         </nav>
 
         <div className="min-h-0 flex-1 overflow-y-auto">
@@ -57,6 +71,7 @@ export default function CollectionSettingsSheet({ collectionId, tab = "environme
             <EnvironmentManager collectionId={collectionId} selectedEnvironmentId={selectedEnvironmentId} />
           )}
           {activeTab === "authentication" && <CollectionAuthPanel collectionId={collectionId} />}
+          {activeTab === "identities" && <CollectionIdentityPanel collectionId={collectionId} />}
         </div>
       </div>
     </div>
@@ -6,9 +6,10 @@ import ExportCollectionSheet from "./index"
 import { Sheet, SheetContent } from "@/components/ui/sheet"
 
 vi.mock("@/bindings/knurl", () => ({
+  exportHttpFile: vi.fn(),
   saveFile: vi.fn(),
 }))
-import { saveFile } from "@/bindings/knurl"
+import { exportHttpFile, saveFile } from "@/bindings/knurl"
 
 vi.mock("@/state", () => ({
   useCollection: vi.fn(),
@@ -76,6 +77,44 @@ describe("ExportCollectionSheet", () => {
     expect(await screen.findByText(/col_export.json/i)).toBeInTheDocument()
   })
 
+  it("exports a .http file when that format is chosen", async () => {
+    const user = userEvent.setup()
+    const collection = {
+      id: "col",
+      name: "My Collection",
+      updated: new Date().toISOString(),
+      encryption: { algorithm: "aes-gcm" },
+      environments: {},
+      requests: { r1: { id: "r1", name: "A", method: "GET", url: "/a" } },
+      authentication: { type: "none" },
+    }
+    vi.mocked(useCollection).mockReturnValue({ state: { collection }, actions: {} } as any)
+    const exportCollection = vi.fn(async () => exportedFixture())
+    vi.mocked(useCollections).mockReturnValue({ actions: { collectionsApi: () => ({ exportCollection }) } } as any)
+    vi.mocked(exportHttpFile).mockResolvedValue("### A\nGET /a\n")
+    vi.mocked(saveFile).mockResolvedValue("/tmp/My_Collection.http")
+
+    render(
+      <Sheet open onOpenChange={() => {}}>
+        <SheetContent side="right">
+          <ExportCollectionSheet collectionId="col" />
+        </SheetContent>
+      </Sheet>,
+    )
+
+    await user.click(screen.getByRole("button", { name: /choose export format/i }))
+    await user.click(await screen.findByRole("menuitem", { name: /http file/i }))
+    const exportBtns = await screen.findAllByRole("button", { name: /^export$/i })
+    await user.click(exportBtns[exportBtns.length - 1])
+
+    expect(exportHttpFile).toHaveBeenCalledWith(expect.objectContaining({ format: "native" }))
+    expect(saveFile).toHaveBeenCalledWith(
+      "### A\nGET /a\n",
+      expect.objectContaining({ defaultPath: "My_Collection.http" }),
+    )
+    expect(await screen.findByText(/My_Collection.http/i)).toBeInTheDocument()
+  })
+
   it("handles user cancellation with an error status", async () => {
     const user = userEvent.setup()
     const collection = {
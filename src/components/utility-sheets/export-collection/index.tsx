@@ -4,7 +4,7 @@ import { writeText } from "@tauri-apps/plugin-clipboard-manager"
 import { revealItemInDir } from "@tauri-apps/plugin-opener"
 import { ChevronDownIcon, ClipboardCopyIcon, FolderOpenIcon, RotateCcwIcon, UploadIcon, XIcon } from "lucide-react"
 
-import { saveFile } from "@/bindings/knurl"
+import { exportHttpFile, saveFile } from "@/bindings/knurl"
 import { Alert, AlertDescription, AlertTitle } from "@/components/ui/alert"
 import { Button } from "@/components/ui/button"
 import { Checkbox } from "@/components/ui/checkbox"
@@ -14,7 +14,7 @@ import { Input } from "@/components/ui/knurl/input"
 import { SheetDescription, SheetHeader, SheetTitle } from "@/components/ui/sheet"
 import { useCollection, useCollections } from "@/state"
 
-type ExportFormat = "native-json" | "openapi-json" | "openapi-yaml"
+type ExportFormat = "native-json" | "openapi-json" | "openapi-yaml" | "http-file"
 
 const ExportFileFilters = {
   json: [
@@ -29,6 +29,12 @@ const ExportFileFilters = {
       extensions: ["json"],
     },
   ],
+  http: [
+    {
+      name: "HTTP File",
+      extensions: ["http", "rest"],
+    },
+  ],
 }
 
 interface Props {
@@ -99,18 +105,24 @@ export default function ExportCollectionSheet({ collectionId }: Props) {
         e.id ? selectedEnvironmentIds.has(e.id) : false,
       )
 
-      const payload = JSON.stringify(exported, null, 2)
-
-      const path = await saveFile(payload, {
-        title: "Save exported collection",
-        defaultPath: `${collection.name.replace(/[^a-zA-Z0-9_-]/g, "_")}_export.json`,
-        filters: ExportFileFilters.json,
-      })
+      const baseName = collection.name.replace(/[^a-zA-Z0-9_-]/g, "_")
+      const path =
+        format === "http-file"
+          ? await saveFile(await exportHttpFile(exported), {
+              title: "Save exported collection",
+              defaultPath: `${baseName}.http`,
+              filters: ExportFileFilters.http,
+            })
+          : await saveFile(JSON.stringify(exported, null, 2), {
+              title: "Save exported collection",
+              defaultPath: `${baseName}_export.json`,
+              filters: ExportFileFilters.json,
+            })
       setStatus({ kind: "success", path })
     } catch (err) {
       setStatus({ kind: "error", message: (err as Error)?.message ?? String(err) })
     }
-  }, [collectionId, selectedRequests, selectedEnvironments, collection.name, collectionsApi])
+  }, [collectionId, selectedRequests, selectedEnvironments, collection.name, collectionsApi, format])
 
   return (
     <div className="flex h-full min-h-0 flex-col overflow-hidden">
@@ -309,6 +321,9 @@ export default function ExportCollectionSheet({ collectionId }: Props) {
                 <DropdownMenuItem onClick={() => setFormat("openapi-yaml")} inset>
                   {format === "openapi-yaml" ? "✓ " : ""}OpenAPI (YAML)
                 </DropdownMenuItem>
+                <DropdownMenuItem onClick={() => setFormat("http-file")} inset>
+                  {format === "http-file" ? "✓ " : ""}HTTP File (.http)
+                </DropdownMenuItem>
               </DropdownMenuContent>
             </DropdownMenu>
 
@@ -1,5 +1,5 @@
 import { ChevronDownIcon, ClipboardPasteIcon, UploadIcon } from "lucide-react"
-import type { ImportFormat } from "../types"
+import type { DetectedImportFormat, ImportFormat } from "../types"
 import { Button } from "@/components/ui/button"
 import {
   DropdownMenu,
@@ -13,7 +13,7 @@ import { Switch } from "@/components/ui/switch"
 
 interface ImportSourceStepProps {
   importFormat: ImportFormat
-  detectedFormat: "native" | "openapi" | "postman" | null
+  detectedFormat: DetectedImportFormat | null
   onChooseFile: () => void
   onPaste: () => void
   onFormatChange: (format: ImportFormat) => void
@@ -27,6 +27,10 @@ const ImportFormatName: { [K in ImportFormat]: string } = {
   native: "Native Knurl",
   openapi: "OpenAPI v3+",
   postman: "Postman Collection v2.1",
+  thunderclient: "Thunder Client",
+  hoppscotch: "Hoppscotch",
+  insomnia: "Insomnia v4/v5",
+  http: "HTTP File (.http)",
 }
 
 export function ImportSourceStep({
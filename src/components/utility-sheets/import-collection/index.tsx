@@ -17,9 +17,19 @@ import { useImportActions } from "./use-import-actions"
 import { ValidationErrorDisplay } from "./components/validation-error-display"
 import { ImportSourceStep } from "./components/import-source-step"
 import { ImportPreviewStep } from "./components/import-preview-step"
-import type { ImportFormat } from "./types"
+import type { DetectedImportFormat, ImportFormat } from "./types"
 
-type TabValue = "preview" | "native-source" | "openapi-source" | "postman-source"
+type TabValue = "preview" | "native-source" | "openapi-source" | "postman-source" | "converted-source"
+
+const ConvertedSourceName = {
+  thunderclient: "Thunder Client",
+  hoppscotch: "Hoppscotch",
+  insomnia: "Insomnia",
+  http: "HTTP File",
+} as const
+
+const hasConvertedSource = (format: DetectedImportFormat | null): format is keyof typeof ConvertedSourceName =>
+  !!format && format in ConvertedSourceName
 
 export default function ImportCollectionSheet() {
   // --- STATE MANAGEMENT ---
@@ -118,7 +128,7 @@ export default function ImportCollectionSheet() {
       setStatus(null)
       const file = await openFile({
         title: "Import Collection",
-        filters: [{ name: "Collection Files", extensions: ["json", "yaml", "yml"] }],
+        filters: [{ name: "Collection Files", extensions: ["json", "yaml", "yml", "http", "rest"] }],
       })
       if (file?.content) {
         setImportData(file.content)
@@ -207,6 +217,9 @@ export default function ImportCollectionSheet() {
                 </TabsTrigger>
                 {detectedFormat === "openapi" && <TabsTrigger value="openapi-source">OpenAPI Source</TabsTrigger>}
                 {detectedFormat === "postman" && <TabsTrigger value="postman-source">Postman Source</TabsTrigger>}
+                {hasConvertedSource(detectedFormat) && (
+                  <TabsTrigger value="converted-source">{ConvertedSourceName[detectedFormat]} Source</TabsTrigger>
+                )}
               </TabsList>
 
               <TabsContent value="preview" className="flex-1 min-h-0">
@@ -234,9 +247,7 @@ export default function ImportCollectionSheet() {
 
               <TabsContent value="native-source" className="relative flex-1 min-h-0">
                 <CodeEditor
-                  value={
-                    detectedFormat === "openapi" || detectedFormat === "postman" ? convertedData : formattedImportData
-                  }
+                  value={detectedFormat && detectedFormat !== "native" ? convertedData : formattedImportData}
                   onChange={setImportData}
                   className="absolute inset-0 h-full w-full rounded-sm border"
                   language="json"
@@ -264,6 +275,17 @@ export default function ImportCollectionSheet() {
                   />
                 </TabsContent>
               )}
+              {hasConvertedSource(detectedFormat) && (
+                <TabsContent value="converted-source" className="relative flex-1 min-h-0">
+                  <CodeEditor
+                    value={formattedImportData}
+                    onChange={setImportData}
+                    className="absolute h-full w-full rounded-sm border"
+                    language={detectedFormat === "http" ? "text" : importData.trim().startsWith("{") ? "json" : "yaml"}
+                    lineNumbers
+                  />
+                </TabsContent>
+              )}
             </Tabs>
             {validationIssues && <ValidationErrorDisplay issues={validationIssues} />}
           </div>
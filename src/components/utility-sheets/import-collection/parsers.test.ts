@@ -2,7 +2,18 @@ import { describe, expect, it } from "vitest"
 
 import { RootCollectionFolderId } from "@/types"
 
-import { isPostmanCollection, openApiToNative, postmanToNative, validatePostmanDocument } from "./parsers"
+import {
+  hoppscotchToNative,
+  isHoppscotchDocument,
+  isPostmanCollection,
+  isThunderClientDocument,
+  openApiToNative,
+  postmanToNative,
+  thunderClientToNative,
+  validateHoppscotchDocument,
+  validatePostmanDocument,
+  validateThunderClientDocument,
+} from "./parsers"
 
 describe("openApiToNative", () => {
   const baseSpec = {
@@ -190,3 +201,180 @@ describe("postmanToNative", () => {
     expect(envVariables[0]?.secure).toBe(true)
   })
 })
+
+describe("thunderClientToNative", () => {
+  const collection = {
+    clientName: "Thunder Client",
+    collectionName: "Sample Thunder",
+    dateExported: "2025-01-01T00:00:00.000Z",
+    version: "1.2",
+    folders: [
+      { _id: "f2", name: "Nested", containerId: "f1", sortNum: 20000 },
+      { _id: "f1", name: "Users", containerId: "", sortNum: 10000 },
+    ],
+    requests: [
+      {
+        _id: "r1",
+        containerId: "f2",
+        name: "Get User",
+        url: "{{baseUrl}}/users/{id}?verbose=true",
+        method: "GET",
+        sortNum: 10000,
+        headers: [
+          { name: "Accept", value: "application/json" },
+          { name: "X-Debug", value: "1", isDisabled: true },
+        ],
+        params: [
+          { name: "verbose", value: "true" },
+          { name: "id", value: "42", isPath: true },
+        ],
+        auth: { type: "bearer", bearer: "{{token}}" },
+      },
+      {
+        _id: "r2",
+        containerId: "",
+        name: "Login",
+        url: "{{baseUrl}}/login",
+        method: "post",
+        sortNum: 20000,
+        body: { type: "formencoded", form: [{ name: "user", value: "alice" }] },
+      },
+    ],
+  }
+
+  it("detects and converts Thunder Client collections", () => {
+    expect(isThunderClientDocument(collection)).toBe(true)
+    const validation = validateThunderClientDocument(collection)
+    expect(validation.success).toBe(true)
+
+    const result = thunderClientToNative(validation.success ? validation.data : (null as never))
+    expect(result.collection.name).toBe("Sample Thunder")
+
+    const folders = result.collection.folders ?? {}
+    const root = folders[RootCollectionFolderId]
+    expect(root.childFolderIds).toHaveLength(1)
+    const users = folders[root.childFolderIds[0]]
+    expect(users?.name).toBe("Users")
+    const nested = folders[users?.childFolderIds[0] ?? ""]
+    expect(nested?.name).toBe("Nested")
+
+    const requests = Object.values(result.collection.requests ?? {})
+    const getUser = requests.find((request) => request.name === "Get User")
+    expect(getUser?.folderId).toBe(nested?.id)
+    expect(Object.values(getUser?.pathParams ?? {}).map((param) => param.name)).toEqual(["id"])
+    expect(Object.values(getUser?.queryParams ?? {}).map((param) => param.name)).toEqual(["verbose"])
+    expect(Object.values(getUser?.headers ?? {}).map((header) => header.enabled)).toEqual([true, false])
+    expect(getUser?.authentication).toEqual({ type: "bearer", bearer: { token: "{{token}}", scheme: undefined } })
+
+    const login = requests.find((request) => request.name === "Login")
+    expect(login?.method).toBe("POST")
+    expect(login?.folderId).toBe(RootCollectionFolderId)
+    expect(login?.body.type).toBe("form")
+    expect(login?.body.encoding).toBe("url")
+    expect(Object.values(login?.body.formData ?? {})[0]?.key).toBe("user")
+  })
+
+  it("imports Thunder Client environments", () => {
+    const environment = {
+      clientName: "Thunder Client",
+      environmentName: "Local",
+      variables: [{ name: "baseUrl", value: "http://localhost:8080" }],
+    }
+    const validation = validateThunderClientDocument(environment)
+    expect(validation.success).toBe(true)
+
+    const result = thunderClientToNative(validation.success ? validation.data : (null as never))
+    const environments = Object.values(result.collection.environments ?? {})
+    expect(environments).toHaveLength(1)
+    expect(environments[0]?.name).toBe("Local")
+    expect(Object.values(environments[0]?.variables ?? {})[0]?.value).toBe("http://localhost:8080")
+  })
+})
+
+describe("hoppscotchToNative", () => {
+  const collection = {
+    v: 2,
+    name: "Sample Hoppscotch",
+    folders: [
+      {
+        v: 2,
+        name: "Orders",
+        folders: [],
+        requests: [
+          {
+            v: "1",
+            name: "Create Order",
+            method: "POST",
+            endpoint: "<<baseUrl>>/orders",
+            params: [],
+            headers: [{ key: "Authorization", value: "Bearer <<token>>", active: true }],
+            auth: { authType: "none", authActive: true },
+            body: { contentType: "application/json", body: '{"item": "<<sku>>"}' },
+          },
+        ],
+      },
+    ],
+    requests: [
+      {
+        v: "1",
+        name: "Search",
+        method: "GET",
+        endpoint: "<<baseUrl>>/search",
+        params: [
+          { key: "q", value: "shoes", active: true },
+          { key: "page", value: "2", active: false },
+        ],
+        headers: [],
+        auth: { authType: "api-key", authActive: true, key: "X-Api-Key", value: "<<apiKey>>", addTo: "QUERY_PARAMS" },
+        body: { contentType: "application/x-www-form-urlencoded", body: "a: 1\n#b: 2" },
+      },
+    ],
+  }
+
+  it("detects and converts Hoppscotch collections", () => {
+    expect(isThunderClientDocument(collection)).toBe(false)
+    expect(isHoppscotchDocument([collection])).toBe(true)
+    const validation = validateHoppscotchDocument(collection)
+    expect(validation.success).toBe(true)
+
+    const result = hoppscotchToNative(validation.success ? validation.data : (null as never))
+    expect(result.collection.name).toBe("Sample Hoppscotch")
+
+    const requests = Object.values(result.collection.requests ?? {})
+    const create = requests.find((request) => request.name === "Create Order")
+    expect(create?.url).toBe("{{baseUrl}}/orders")
+    expect(create?.body.content).toBe('{"item": "{{sku}}"}')
+    expect(create?.body.language).toBe("json")
+    expect(Object.values(create?.headers ?? {})[0]?.secure).toBe(true)
+
+    const search = requests.find((request) => request.name === "Search")
+    expect(search?.folderId).toBe(RootCollectionFolderId)
+    expect(Object.values(search?.queryParams ?? {}).map((param) => param.enabled)).toEqual([true, false])
+    expect(search?.authentication).toEqual({
+      type: "apiKey",
+      apiKey: { key: "X-Api-Key", value: "{{apiKey}}", placement: { type: "query", name: "X-Api-Key" } },
+    })
+    const fields = Object.values(search?.body.formData ?? {})
+    expect(fields.map((field) => [field.key, field.value, field.enabled])).toEqual([
+      ["a", "1", true],
+      ["b", "2", false],
+    ])
+  })
+
+  it("imports Hoppscotch environments", () => {
+    const environments = [
+      { name: "Dev", variables: [{ key: "baseUrl", value: "https://dev.example.com", secret: false }] },
+      { name: "Prod", variables: [{ key: "token", initialValue: "abc", secret: true }] },
+    ]
+    expect(isHoppscotchDocument(environments)).toBe(true)
+    const validation = validateHoppscotchDocument(environments)
+    expect(validation.success).toBe(true)
+
+    const result = hoppscotchToNative(validation.success ? validation.data : (null as never))
+    const imported = Object.values(result.collection.environments ?? {})
+    expect(imported.map((environment) => environment.name)).toEqual(["Dev", "Prod"])
+    const prodToken = Object.values(imported[1]?.variables ?? {})[0]
+    expect(prodToken?.value).toBe("abc")
+    expect(prodToken?.secure).toBe(true)
+  })
+})
@@ -549,6 +549,28 @@ const convertAuth = (auth?: PostmanAuth): RequestState["authentication"] => {
       },
     }
   }
+  if (kind === "ntlm") {
+    const params = getAuthParams(auth, "ntlm")
+    return {
+      type: "ntlm",
+      ntlm: {
+        username: findAuthValue(params, "username"),
+        password: findAuthValue(params, "password"),
+        domain: findAuthValue(params, "domain"),
+        workstation: findAuthValue(params, "workstation"),
+      },
+    }
+  }
+  if (kind === "digest") {
+    const params = getAuthParams(auth, "digest")
+    return {
+      type: "digest",
+      digest: {
+        username: findAuthValue(params, "username"),
+        password: findAuthValue(params, "password"),
+      },
+    }
+  }
   if (kind === "apikey" || kind === "apiKey") {
     const params = getAuthParams(auth, "apikey") ?? getAuthParams(auth, "apiKey")
     const key = findAuthValue(params, "key") ?? "X-API-Key"
@@ -1009,3 +1031,753 @@ export function openApiToNative(
     collection,
   }
 }
+
+type NativeRequestFields = Pick<
+  RequestState,
+  "name" | "method" | "url" | "pathParams" | "queryParams" | "headers" | "body" | "authentication"
+> &
+  Partial<Pick<RequestState, "tests">>
+
+/**
+ * Accumulates folders and requests for importers whose sources are trees of folders and requests.
+ */
+const createNativeCollectionBuilder = () => {
+  const collectionId = generateUniqueId()
+  const folders: Record<string, CollectionFolderNode> = {
+    [RootCollectionFolderId]: createFolderNode(RootCollectionFolderId, "Root", null),
+  }
+  const requests: Record<string, RequestState> = {}
+  const environments: Record<string, Environment> = {}
+
+  const addFolder = (name: string | undefined, parentId: string = RootCollectionFolderId): string => {
+    const parent = folders[parentId] ?? folders[RootCollectionFolderId]
+    const id = generateUniqueId()
+    folders[id] = createFolderNode(id, name?.trim() || "Folder", parent.id, parent.childFolderIds.length)
+    parent.childFolderIds.push(id)
+    return id
+  }
+
+  const addRequest = (fields: NativeRequestFields, folderId: string = RootCollectionFolderId) => {
+    const folder = folders[folderId] ?? folders[RootCollectionFolderId]
+    const id = generateUniqueId()
+    requests[id] = {
+      id,
+      order: folder.requestIds.length,
+      collectionId,
+      folderId: folder.id,
+      environmentId: undefined,
+      autoSave: false,
+      cookieParams: {},
+      options: undefined,
+      patch: {},
+      updated: 0,
+      ...fields,
+    }
+    folder.requestIds.push(id)
+  }
+
+  const addEnvironment = (name: string, variables: EnvironmentVariable[], description?: string) => {
+    const id = generateUniqueId()
+    environments[id] = {
+      id,
+      name,
+      description,
+      variables: Object.fromEntries(variables.map((variable) => [variable.id, variable])),
+    }
+  }
+
+  const build = (name: string, description = ""): ExportedCollection => {
+    const now = new Date().toISOString()
+    return {
+      format: "native",
+      version: "1.0.0",
+      exportedAt: now,
+      collection: {
+        id: collectionId,
+        name,
+        description,
+        updated: now,
+        requests,
+        folders,
+        environments,
+      },
+    }
+  }
+
+  return { addFolder, addRequest, addEnvironment, build }
+}
+
+const toHttpMethod = (method: string | undefined): RequestState["method"] => {
+  const parsed = zHttpMethod.safeParse(method?.toUpperCase())
+  return parsed.success ? parsed.data : "GET"
+}
+
+const toRecord = <T extends { id: string }>(items: T[]): Record<string, T> =>
+  Object.fromEntries(items.map((item) => [item.id, item]))
+
+// --- Thunder Client ---
+
+const zThunderKeyValue = z
+  .object({
+    name: z.string().optional(),
+    value: z.union([z.string(), z.number(), z.boolean(), z.null()]).optional(),
+    isDisabled: z.boolean().optional(),
+    isPath: z.boolean().optional(),
+  })
+  .passthrough()
+
+const zThunderBody = z
+  .object({
+    type: z.string().optional(),
+    raw: z.string().optional(),
+    form: z.array(zThunderKeyValue).optional(),
+    files: z.array(zThunderKeyValue).optional(),
+    graphql: z
+      .object({
+        query: z.string().optional(),
+        variables: z.string().optional(),
+      })
+      .passthrough()
+      .optional(),
+    binary: z.string().optional(),
+  })
+  .passthrough()
+
+const zThunderAuth = z
+  .object({
+    type: z.string().optional(),
+    bearer: z.string().optional(),
+    bearerPrefix: z.string().optional(),
+    basic: z
+      .object({
+        username: z.string().optional(),
+        password: z.string().optional(),
+      })
+      .optional(),
+    oauth2: z
+      .object({
+        grantType: z.string().optional(),
+        authUrl: z.string().optional(),
+        tokenUrl: z.string().optional(),
+        clientId: z.string().optional(),
+        clientSecret: z.string().optional(),
+        scope: z.string().optional(),
+      })
+      .passthrough()
+      .optional(),
+  })
+  .passthrough()
+
+const zThunderFolder = z
+  .object({
+    _id: z.string(),
+    name: z.string().optional(),
+    containerId: z.string().optional(),
+    sortNum: z.number().optional(),
+  })
+  .passthrough()
+
+const zThunderRequest = z
+  .object({
+    _id: z.string().optional(),
+    containerId: z.string().optional(),
+    name: z.string().optional(),
+    url: z.string().optional(),
+    method: z.string().optional(),
+    sortNum: z.number().optional(),
+    headers: z.array(zThunderKeyValue).optional(),
+    params: z.array(zThunderKeyValue).optional(),
+    body: zThunderBody.optional(),
+    auth: zThunderAuth.optional(),
+  })
+  .passthrough()
+
+const zThunderVariable = z
+  .object({
+    name: z.string(),
+    value: z.union([z.string(), z.number(), z.boolean(), z.null()]).optional(),
+    isSecret: z.boolean().optional(),
+  })
+  .passthrough()
+
+export const zThunderClientCollection = z
+  .object({
+    collectionName: z.string().optional(),
+    folders: z.array(zThunderFolder).optional(),
+    requests: z.array(zThunderRequest),
+  })
+  .passthrough()
+
+export const zThunderClientEnvironment = z
+  .object({
+    environmentName: z.string().optional(),
+    variables: z.array(zThunderVariable),
+  })
+  .passthrough()
+
+export type ThunderClientDocument =
+  | { kind: "collection"; collection: z.infer<typeof zThunderClientCollection> }
+  | { kind: "environment"; environment: z.infer<typeof zThunderClientEnvironment> }
+type ThunderKeyValue = z.infer<typeof zThunderKeyValue>
+type ThunderRequest = z.infer<typeof zThunderRequest>
+
+export type ThunderClientValidationResult =
+  | { success: true; data: ThunderClientDocument }
+  | { success: false; error: z.ZodError }
+
+/**
+ * Detects Thunder Client collection or environment exports by their `clientName` marker.
+ * @param doc Unknown document to test.
+ */
+export const isThunderClientDocument = (doc: unknown): boolean => {
+  if (doc === null || typeof doc !== "object" || Array.isArray(doc)) {
+    return false
+  }
+  const maybe = doc as Record<string, unknown>
+  const client = maybe.clientName ?? maybe.client
+  return typeof client === "string" && client.toLowerCase().includes("thunder")
+}
+
+/**
+ * Validates and parses a Thunder Client collection or environment export.
+ * @param doc The raw document to validate.
+ */
+export const validateThunderClientDocument = (doc: unknown): ThunderClientValidationResult => {
+  const isEnvironment =
+    doc !== null && typeof doc === "object" && Array.isArray((doc as { variables?: unknown }).variables)
+  if (isEnvironment) {
+    const result = zThunderClientEnvironment.safeParse(doc)
+    return result.success
+      ? { success: true, data: { kind: "environment", environment: result.data } }
+      : { success: false, error: result.error }
+  }
+  const result = zThunderClientCollection.safeParse(doc)
+  return result.success
+    ? { success: true, data: { kind: "collection", collection: result.data } }
+    : { success: false, error: result.error }
+}
+
+const convertThunderParams = (params: ThunderKeyValue[] | undefined, path: boolean) =>
+  toRecord(
+    (params ?? [])
+      .filter((param) => param.name && Boolean(param.isPath) === path)
+      .map((param) => ({
+        id: generateUniqueId(),
+        name: param.name ?? "",
+        value: toStringValue(param.value),
+        enabled: param.isDisabled !== true,
+        secure: false,
+      })),
+  )
+
+const convertThunderHeaders = (headers: ThunderKeyValue[] | undefined) =>
+  toRecord(
+    (headers ?? [])
+      .filter((header) => header.name)
+      .map((header) => ({
+        id: generateUniqueId(),
+        name: header.name ?? "",
+        value: toStringValue(header.value),
+        enabled: header.isDisabled !== true,
+        secure: isSensitiveHeader(header.name ?? ""),
+      })),
+  )
+
+const convertThunderBody = (body: ThunderRequest["body"]): RequestBodyData => {
+  switch (body?.type) {
+    case "json":
+    case "xml":
+    case "text":
+      return { type: "text", content: body.raw ?? "", language: mapRawLanguage(body.type) }
+    case "graphql": {
+      const query = body.graphql?.query ?? ""
+      const variables = body.graphql?.variables?.trim()
+      return {
+        type: "text",
+        language: "graphql",
+        content: variables ? `${query}\n\n# Variables\n${variables}` : query,
+      }
+    }
+    case "formencoded":
+    case "formdata": {
+      const text = (body.form ?? []).filter((field) => field.name)
+      const files = body.type === "formdata" ? (body.files ?? []).filter((field) => field.name) : []
+      const fields: FormField[] = [
+        ...text.map(
+          (field): FormField => ({
+            id: generateUniqueId(),
+            key: field.name ?? "",
+            value: toStringValue(field.value),
+            enabled: field.isDisabled !== true,
+            secure: false,
+            kind: "text",
+          }),
+        ),
+        ...files.map((field): FormField => {
+          const filePath = toStringValue(field.value) || undefined
+          return {
+            id: generateUniqueId(),
+            key: field.name ?? "",
+            value: "",
+            enabled: field.isDisabled !== true,
+            secure: false,
+            kind: "file",
+            filePath,
+            fileName: extractFileName(filePath),
+          }
+        }),
+      ]
+      return {
+        type: "form",
+        encoding: body.type === "formdata" ? "multipart" : "url",
+        formData: toRecord(fields),
+      }
+    }
+    case "binary":
+      return { type: "binary", binaryPath: body.binary, binaryFileName: extractFileName(body.binary) }
+    default:
+      return { type: "none" }
+  }
+}
+
+const convertThunderAuth = (auth: ThunderRequest["auth"]): RequestState["authentication"] => {
+  switch (auth?.type) {
+    case "bearer":
+      return { type: "bearer", bearer: { token: auth.bearer ?? "", scheme: auth.bearerPrefix } }
+    case "basic":
+      return { type: "basic", basic: { username: auth.basic?.username, password: auth.basic?.password } }
+    case "oauth2":
+      return {
+        type: "oauth2",
+        oauth2: {
+          grantType: "client_credentials",
+          clientId: auth.oauth2?.clientId,
+          clientSecret: auth.oauth2?.clientSecret,
+          scope: auth.oauth2?.scope,
+          authUrl: auth.oauth2?.authUrl,
+          tokenUrl: auth.oauth2?.tokenUrl,
+        },
+      }
+    case "inherit":
+      return { type: "inherit" }
+    default:
+      return { type: "none" }
+  }
+}
+
+const bySortNum = (a: { sortNum?: number }, b: { sortNum?: number }) => (a.sortNum ?? 0) - (b.sortNum ?? 0)
+
+/**
+ * Converts a Thunder Client collection or environment export into the native Knurl collection format.
+ * Environment exports become a collection that only carries the environment.
+ * @param doc The validated Thunder Client document.
+ */
+export function thunderClientToNative(doc: ThunderClientDocument): ExportedCollection {
+  const builder = createNativeCollectionBuilder()
+
+  if (doc.kind === "environment") {
+    const name = doc.environment.environmentName ?? "Thunder Client Environment"
+    builder.addEnvironment(
+      name,
+      doc.environment.variables.map((variable) => ({
+        id: generateUniqueId(),
+        name: variable.name,
+        value: toStringValue(variable.value),
+        secure: variable.isSecret === true,
+      })),
+      "Imported from Thunder Client.",
+    )
+    return builder.build(name)
+  }
+
+  const { collection } = doc
+  // Folders may reference parents that appear later in the export, so resolve them recursively
+  const sourceFolders = [...(collection.folders ?? [])].sort(bySortNum)
+  const folderIds = new Map<string, string>()
+  const resolveFolder = (sourceId: string | undefined, seen = new Set<string>()): string => {
+    if (!sourceId) {
+      return RootCollectionFolderId
+    }
+    const existing = folderIds.get(sourceId)
+    if (existing) {
+      return existing
+    }
+    const folder = sourceFolders.find((candidate) => candidate._id === sourceId)
+    if (!folder || seen.has(sourceId)) {
+      return RootCollectionFolderId
+    }
+    seen.add(sourceId)
+    const id = builder.addFolder(folder.name, resolveFolder(folder.containerId, seen))
+    folderIds.set(sourceId, id)
+    return id
+  }
+  for (const folder of sourceFolders) {
+    resolveFolder(folder._id)
+  }
+
+  for (const request of [...collection.requests].sort(bySortNum)) {
+    const method = toHttpMethod(request.method)
+    const url = request.url ?? ""
+    builder.addRequest(
+      {
+        name: request.name ?? `${method} ${url}`.trim(),
+        method,
+        url,
+        pathParams: convertThunderParams(request.params, true),
+        queryParams: convertThunderParams(request.params, false),
+        headers: convertThunderHeaders(request.headers),
+        body: convertThunderBody(request.body),
+        authentication: convertThunderAuth(request.auth),
+      },
+      resolveFolder(request.containerId),
+    )
+  }
+
+  return builder.build(collection.collectionName ?? "Thunder Client Collection")
+}
+
+// --- Hoppscotch ---
+
+const zHoppscotchKeyValue = z
+  .object({
+    key: z.string().optional(),
+    value: z.union([z.string(), z.number(), z.boolean(), z.null(), z.array(z.any())]).optional(),
+    active: z.boolean().optional(),
+    isFile: z.boolean().optional(),
+  })
+  .passthrough()
+
+const zHoppscotchAuth = z
+  .object({
+    authType: z.string().optional(),
+    authActive: z.boolean().optional(),
+    username: z.string().optional(),
+    password: z.string().optional(),
+    token: z.string().optional(),
+    key: z.string().optional(),
+    value: z.string().optional(),
+    addTo: z.string().optional(),
+    grantTypeInfo: z
+      .object({
+        grantType: z.string().optional(),
+        authEndpoint: z.string().optional(),
+        tokenEndpoint: z.string().optional(),
+        clientID: z.string().optional(),
+        clientSecret: z.string().optional(),
+        scopes: z.string().optional(),
+      })
+      .passthrough()
+      .optional(),
+  })
+  .passthrough()
+
+const zHoppscotchRequest = z
+  .object({
+    name: z.string().optional(),
+    method: z.string().optional(),
+    endpoint: z.string().optional(),
+    params: z.array(zHoppscotchKeyValue).optional(),
+    headers: z.array(zHoppscotchKeyValue).optional(),
+    auth: zHoppscotchAuth.optional(),
+    body: z
+      .object({
+        contentType: z.string().nullable().optional(),
+        body: z.union([z.string(), z.array(zHoppscotchKeyValue), z.null()]).optional(),
+      })
+      .passthrough()
+      .optional(),
+  })
+  .passthrough()
+
+type HoppscotchCollectionNode = {
+  name?: string
+  folders?: HoppscotchCollectionNode[]
+  requests?: z.infer<typeof zHoppscotchRequest>[]
+}
+
+const zHoppscotchCollection: z.ZodType<HoppscotchCollectionNode> = z.lazy(() =>
+  z
+    .object({
+      name: z.string().optional(),
+      folders: z.array(zHoppscotchCollection).optional(),
+      requests: z.array(zHoppscotchRequest).optional(),
+    })
+    .passthrough(),
+)
+
+const zHoppscotchEnvironment = z
+  .object({
+    name: z.string(),
+    variables: z.array(
+      z
+        .object({
+          key: z.string(),
+          value: z.string().optional(),
+          initialValue: z.string().optional(),
+          currentValue: z.string().optional(),
+          secret: z.boolean().optional(),
+        })
+        .passthrough(),
+    ),
+  })
+  .passthrough()
+
+export type HoppscotchDocument =
+  | { kind: "collections"; collections: HoppscotchCollectionNode[] }
+  | { kind: "environments"; environments: z.infer<typeof zHoppscotchEnvironment>[] }
+type HoppscotchRequest = z.infer<typeof zHoppscotchRequest>
+type HoppscotchKeyValue = z.infer<typeof zHoppscotchKeyValue>
+
+export type HoppscotchValidationResult =
+  | { success: true; data: HoppscotchDocument }
+  | { success: false; error: z.ZodError }
+
+const asArray = (doc: unknown): unknown[] => (Array.isArray(doc) ? doc : [doc])
+
+const isHoppscotchCollectionNode = (value: unknown): boolean =>
+  value !== null &&
+  typeof value === "object" &&
+  Array.isArray((value as { folders?: unknown }).folders) &&
+  Array.isArray((value as { requests?: unknown }).requests)
+
+const isHoppscotchEnvironmentNode = (value: unknown): boolean =>
+  value !== null &&
+  typeof value === "object" &&
+  typeof (value as { name?: unknown }).name === "string" &&
+  Array.isArray((value as { variables?: unknown }).variables) &&
+  !("clientName" in value)
+
+/**
+ * Detects Hoppscotch collection or environment exports (a single object or an array of them).
+ * @param doc Unknown document to test.
+ */
+export const isHoppscotchDocument = (doc: unknown): boolean => {
+  const items = asArray(doc)
+  return (
+    items.length > 0 && (items.every(isHoppscotchCollectionNode) || items.every(isHoppscotchEnvironmentNode))
+  )
+}
+
+/**
+ * Validates and parses a Hoppscotch collection or environment export.
+ * @param doc The raw document to validate.
+ */
+export const validateHoppscotchDocument = (doc: unknown): HoppscotchValidationResult => {
+  const items = asArray(doc)
+  if (items.length > 0 && items.every(isHoppscotchEnvironmentNode)) {
+    const result = z.array(zHoppscotchEnvironment).safeParse(items)
+    return result.success
+      ? { success: true, data: { kind: "environments", environments: result.data } }
+      : { success: false, error: result.error }
+  }
+  const result = z.array(zHoppscotchCollection).safeParse(items)
+  return result.success
+    ? { success: true, data: { kind: "collections", collections: result.data } }
+    : { success: false, error: result.error }
+}
+
+/** Hoppscotch uses `<<name>>` for variables; Knurl uses `{{name}}`. */
+const convertHoppscotchTemplate = (value: unknown): string =>
+  toStringValue(value).replace(/<<\s*([\w.-]+)\s*>>/g, "{{$1}}")
+
+const convertHoppscotchParams = (params: HoppscotchKeyValue[] | undefined) =>
+  toRecord(
+    (params ?? [])
+      .filter((param) => param.key)
+      .map((param) => ({
+        id: generateUniqueId(),
+        name: param.key ?? "",
+        value: convertHoppscotchTemplate(param.value),
+        enabled: param.active !== false,
+        secure: false,
+      })),
+  )
+
+const convertHoppscotchHeaders = (headers: HoppscotchKeyValue[] | undefined) =>
+  toRecord(
+    (headers ?? [])
+      .filter((header) => header.key)
+      .map((header) => ({
+        id: generateUniqueId(),
+        name: header.key ?? "",
+        value: convertHoppscotchTemplate(header.value),
+        enabled: header.active !== false,
+        secure: isSensitiveHeader(header.key ?? ""),
+      })),
+  )
+
+const hoppscotchLanguage = (contentType: string): RequestBodyData["language"] => {
+  if (contentType.includes("json")) {
+    return "json"
+  }
+  if (contentType.includes("xml")) {
+    return "xml"
+  }
+  if (contentType.includes("html")) {
+    return "html"
+  }
+  return "text"
+}
+
+const convertHoppscotchBody = (body: HoppscotchRequest["body"]): RequestBodyData => {
+  const contentType = body?.contentType?.toLowerCase()
+  if (!contentType) {
+    return { type: "none" }
+  }
+
+  if (contentType === "multipart/form-data") {
+    const entries = Array.isArray(body?.body) ? body.body : []
+    const fields = entries
+      .filter((entry) => entry.key)
+      .map(
+        (entry): FormField => ({
+          id: generateUniqueId(),
+          key: entry.key ?? "",
+          // File blobs are not part of the export; keep the field so the user can pick the file again
+          value: entry.isFile ? "" : convertHoppscotchTemplate(entry.value),
+          enabled: entry.active !== false,
+          secure: false,
+          kind: entry.isFile ? "file" : "text",
+        }),
+      )
+    return { type: "form", encoding: "multipart", formData: toRecord(fields) }
+  }
+
+  const raw = typeof body?.body === "string" ? body.body : ""
+  if (contentType === "application/x-www-form-urlencoded") {
+    // Stored as "key: value" lines; lines prefixed with "#" are disabled
+    const fields = raw
+      .split("\n")
+      .map((line) => line.trim())
+      .filter(Boolean)
+      .map((line): FormField => {
+        const enabled = !line.startsWith("#")
+        const entry = enabled ? line : line.slice(1).trim()
+        const separator = entry.indexOf(":")
+        const key = separator >= 0 ? entry.slice(0, separator).trim() : entry
+        const value = separator >= 0 ? entry.slice(separator + 1).trim() : ""
+        return {
+          id: generateUniqueId(),
+          key,
+          value: convertHoppscotchTemplate(value),
+          enabled,
+          secure: false,
+          kind: "text",
+        }
+      })
+    return { type: "form", encoding: "url", formData: toRecord(fields) }
+  }
+
+  if (contentType === "application/octet-stream") {
+    return { type: "binary", binaryContentType: contentType }
+  }
+
+  return { type: "text", content: convertHoppscotchTemplate(raw), language: hoppscotchLanguage(contentType) }
+}
+
+const convertHoppscotchAuth = (auth: HoppscotchRequest["auth"]): RequestState["authentication"] => {
+  if (!auth || auth.authActive === false) {
+    return { type: "none" }
+  }
+  switch (auth.authType) {
+    case "basic":
+      return {
+        type: "basic",
+        basic: {
+          username: convertHoppscotchTemplate(auth.username),
+          password: convertHoppscotchTemplate(auth.password),
+        },
+      }
+    case "bearer":
+      return { type: "bearer", bearer: { token: convertHoppscotchTemplate(auth.token) } }
+    case "api-key": {
+      const name = convertHoppscotchTemplate(auth.key)
+      const inQuery = (auth.addTo ?? "").toLowerCase().startsWith("query")
+      return {
+        type: "apiKey",
+        apiKey: {
+          key: name,
+          value: convertHoppscotchTemplate(auth.value),
+          placement: inQuery ? { type: "query", name } : { type: "header", name },
+        },
+      }
+    }
+    case "oauth-2": {
+      const info = auth.grantTypeInfo
+      return {
+        type: "oauth2",
+        oauth2: {
+          grantType: "client_credentials",
+          clientId: info?.clientID,
+          clientSecret: info?.clientSecret,
+          scope: info?.scopes,
+          authUrl: info?.authEndpoint,
+          tokenUrl: info?.tokenEndpoint,
+        },
+      }
+    }
+    case "inherit":
+      return { type: "inherit" }
+    default:
+      return { type: "none" }
+  }
+}
+
+/**
+ * Converts Hoppscotch collection or environment exports into the native Knurl collection format.
+ * Several collections are imported as top-level folders of one collection; environment exports
+ * become a collection that only carries the environments.
+ * @param doc The validated Hoppscotch document.
+ */
+export function hoppscotchToNative(doc: HoppscotchDocument): ExportedCollection {
+  const builder = createNativeCollectionBuilder()
+
+  if (doc.kind === "environments") {
+    for (const environment of doc.environments) {
+      builder.addEnvironment(
+        environment.name,
+        environment.variables.map((variable) => ({
+          id: generateUniqueId(),
+          name: variable.key,
+          value: variable.value ?? variable.initialValue ?? variable.currentValue ?? "",
+          secure: variable.secret === true,
+        })),
+        "Imported from Hoppscotch.",
+      )
+    }
+    const name = doc.environments.length === 1 ? doc.environments[0].name : "Hoppscotch Environments"
+    return builder.build(name)
+  }
+
+  const addNode = (node: HoppscotchCollectionNode, folderId: string) => {
+    for (const folder of node.folders ?? []) {
+      addNode(folder, builder.addFolder(folder.name, folderId))
+    }
+    for (const request of node.requests ?? []) {
+      const method = toHttpMethod(request.method)
+      const url = convertHoppscotchTemplate(request.endpoint)
+      builder.addRequest(
+        {
+          name: request.name ?? `${method} ${url}`.trim(),
+          method,
+          url,
+          pathParams: {},
+          queryParams: convertHoppscotchParams(request.params),
+          headers: convertHoppscotchHeaders(request.headers),
+          body: convertHoppscotchBody(request.body),
+          authentication: convertHoppscotchAuth(request.auth),
+        },
+        folderId,
+      )
+    }
+  }
+
+  if (doc.collections.length === 1) {
+    addNode(doc.collections[0], RootCollectionFolderId)
+    return builder.build(doc.collections[0].name ?? "Hoppscotch Collection")
+  }
+  for (const collection of doc.collections) {
+    addNode(collection, builder.addFolder(collection.name, RootCollectionFolderId))
+  }
+  return builder.build("Hoppscotch Collections")
+}
@@ -1 +1,11 @@
-export type ImportFormat = "auto" | "native" | "openapi" | "postman"
+export type ImportFormat =
+  | "auto"
+  | "native"
+  | "openapi"
+  | "postman"
+  | "thunderclient"
+  | "hoppscotch"
+  | "insomnia"
+  | "http"
+
+export type DetectedImportFormat = Exclude<ImportFormat, "auto">
@@ -1,11 +1,18 @@
 import { renderHook, waitFor } from "@testing-library/react"
 import { describe, expect, it, vi } from "vitest"
 
+import { convertHttpFile, convertInsomniaExport, convertPostmanCollection } from "@/bindings/knurl"
 import type { ExportedCollection } from "@/types"
 
 import * as parsers from "./parsers"
 import { useImportParser } from "./use-import-parser"
 
+vi.mock("@/bindings/knurl", () => ({
+  convertHttpFile: vi.fn(),
+  convertInsomniaExport: vi.fn(),
+  convertPostmanCollection: vi.fn(),
+}))
+
 describe("useImportParser", () => {
   it("passes OpenAPI conversion options through to the converter", async () => {
     const spec = {
@@ -72,7 +79,11 @@ describe("useImportParser with Postman collections", () => {
     ],
   }
 
-  it("auto-detects and converts Postman collections", async () => {
+  it("auto-detects Postman collections and converts them in the backend", async () => {
+    const validation = parsers.validatePostmanDocument(postmanDoc)
+    const converted = parsers.postmanToNative(validation.success ? validation.data : (null as never))
+    vi.mocked(convertPostmanCollection).mockResolvedValue(converted)
+
     const { result } = renderHook(() => useImportParser(JSON.stringify(postmanDoc), "auto", {}))
 
     await waitFor(() => {
@@ -81,5 +92,92 @@ describe("useImportParser with Postman collections", () => {
 
     expect(result.current.collection?.collection.name).toBe("API Suite")
     expect(result.current.convertedData).toContain('"format": "native"')
+    expect(convertPostmanCollection).toHaveBeenCalledWith(JSON.stringify(postmanDoc))
+  })
+
+  it("reports backend conversion errors as issues", async () => {
+    vi.mocked(convertPostmanCollection).mockRejectedValue(new Error("Not a Postman collection: missing field `info`"))
+
+    const { result } = renderHook(() => useImportParser(JSON.stringify(postmanDoc), "postman", {}))
+
+    await waitFor(() => {
+      expect(result.current.issues?.[0]?.message).toContain("missing field `info`")
+    })
+    expect(result.current.collection).toBeNull()
+  })
+})
+
+describe("useImportParser with .http files", () => {
+  const source = "@host = https://example.com\n\n### Ping\nGET {{host}}/ping\nAccept: application/json\n"
+
+  it("auto-detects .http files and converts them in the backend", async () => {
+    const converted: ExportedCollection = {
+      format: "native",
+      version: "1.0.0",
+      exportedAt: new Date().toISOString(),
+      collection: {
+        id: "col",
+        name: "HTTP File",
+        updated: new Date().toISOString(),
+        encryption: { algorithm: "aes-gcm" },
+        environments: {},
+        requests: {},
+        authentication: { type: "none" },
+      },
+    }
+    vi.mocked(convertHttpFile).mockResolvedValue(converted)
+
+    const { result } = renderHook(() => useImportParser(source, "auto", {}))
+
+    await waitFor(() => {
+      expect(result.current.detectedFormat).toBe("http")
+    })
+
+    expect(result.current.collection?.collection.name).toBe("HTTP File")
+    expect(convertHttpFile).toHaveBeenCalledWith(source)
+  })
+
+  it("leaves YAML documents to the YAML parser", async () => {
+    vi.mocked(convertHttpFile).mockClear()
+
+    const { result } = renderHook(() => useImportParser("openapi: 3.1.0\ninfo:\n  title: Spec\n", "auto", {}))
+
+    await waitFor(() => {
+      expect(result.current.detectedFormat).not.toBeNull()
+    })
+    expect(result.current.detectedFormat).not.toBe("http")
+    expect(convertHttpFile).not.toHaveBeenCalled()
+  })
+})
+
+describe("useImportParser with Insomnia exports", () => {
+  it("re-serializes YAML exports as JSON for the backend", async () => {
+    const source = "type: collection.insomnia.rest/5.0\nname: Weather\ncollection: []\n"
+    const converted: ExportedCollection = {
+      format: "native",
+      version: "1.0.0",
+      exportedAt: new Date().toISOString(),
+      collection: {
+        id: "col",
+        name: "Weather",
+        updated: new Date().toISOString(),
+        encryption: { algorithm: "aes-gcm" },
+        environments: {},
+        requests: {},
+        authentication: { type: "none" },
+      },
+    }
+    vi.mocked(convertInsomniaExport).mockResolvedValue(converted)
+
+    const { result } = renderHook(() => useImportParser(source, "auto", {}))
+
+    await waitFor(() => {
+      expect(result.current.detectedFormat).toBe("insomnia")
+    })
+
+    expect(result.current.collection?.collection.name).toBe("Weather")
+    expect(convertInsomniaExport).toHaveBeenCalledWith(
+      JSON.stringify({ type: "collection.insomnia.rest/5.0", name: "Weather", collection: [] }),
+    )
   })
 })
@@ -3,22 +3,27 @@ import { useEffect, useState } from "react"
 import yaml from "js-yaml"
 import type { core } from "zod"
 
+import { convertHttpFile, convertInsomniaExport, convertPostmanCollection } from "@/bindings/knurl"
 import type { ExportedCollection } from "@/types"
 import {
+  hoppscotchToNative,
+  isHoppscotchDocument,
   isOpenApiDocument,
   isPostmanCollection,
+  isThunderClientDocument,
   openApiToNative,
-  postmanToNative,
+  thunderClientToNative,
+  validateHoppscotchDocument,
   validateNativeDocument,
-  validatePostmanDocument,
+  validateThunderClientDocument,
   type OpenApiToNativeOptions,
 } from "./parsers"
-import type { ImportFormat } from "./types"
+import type { DetectedImportFormat, ImportFormat } from "./types"
 
 interface ParsingResult {
   collection: ExportedCollection | null
   issues: core.$ZodIssue[] | null
-  detectedFormat: "native" | "openapi" | "postman" | null
+  detectedFormat: DetectedImportFormat | null
   convertedData: string
 }
 
@@ -35,6 +40,24 @@ const customIssue = (message: string): core.$ZodIssue => ({
   message,
 })
 
+/**
+ * Cheap check for a Postman export, so large ones go straight to the backend without being parsed here first.
+ */
+const looksLikePostman = (text: string): boolean =>
+  /schema\.getpostman\.com\/json\/collection|"_postman_id"/.test(text.slice(0, 4096))
+
+/**
+ * Insomnia v4 exports start with `__export_format` and v5 exports with their `collection.insomnia.rest` type.
+ */
+const looksLikeInsomnia = (text: string): boolean =>
+  /__export_format|collection\.insomnia\.rest\//.test(text.slice(0, 4096))
+
+/**
+ * A `.http` file has a `###` separator or a request line, which neither JSON nor YAML documents start lines with.
+ */
+const looksLikeHttpFile = (text: string): boolean =>
+  !/^\s*[[{]/.test(text) && /^(###|(GET|POST|PUT|PATCH|DELETE|HEAD|OPTIONS)\s+\S)/m.test(text.slice(0, 4096))
+
 export function useImportParser(
   importData: string,
   importFormat: ImportFormat,
@@ -48,6 +71,74 @@ export function useImportParser(
       return
     }
 
+    const finish = (nativeDoc: ExportedCollection | null, format: DetectedImportFormat, converted: string) => {
+      if (!nativeDoc) {
+        setParsingResult(initialState)
+        return
+      }
+      const validation = validateNativeDocument(nativeDoc)
+      setParsingResult({
+        collection: validation.success ? validation.data : null,
+        issues: validation.success ? null : validation.error.issues,
+        detectedFormat: format,
+        convertedData: converted,
+      })
+    }
+
+    // Postman and Insomnia exports can be large, and .http files aren't JSON or YAML, so the backend converts them
+    const convertInBackend = (
+      convert: (source: string) => Promise<unknown>,
+      format: DetectedImportFormat,
+      source: string,
+      fallbackMessage: string,
+    ) => {
+      let cancelled = false
+      convert(source)
+        .then((nativeDoc) => {
+          if (!cancelled) {
+            finish(nativeDoc as ExportedCollection, format, JSON.stringify(nativeDoc, null, 2))
+          }
+        })
+        .catch((err) => {
+          if (!cancelled) {
+            setParsingResult({
+              ...initialState,
+              detectedFormat: format,
+              issues: [customIssue((err as Error)?.message ?? fallbackMessage)],
+            })
+          }
+        })
+      return () => {
+        cancelled = true
+      }
+    }
+    const convertPostman = (source: string) =>
+      convertInBackend(convertPostmanCollection, "postman", source, "Could not convert the Postman collection.")
+
+    if (importFormat === "postman" || (importFormat === "auto" && looksLikePostman(importData))) {
+      return convertPostman(importData)
+    }
+    if (importFormat === "insomnia" || (importFormat === "auto" && looksLikeInsomnia(importData))) {
+      // The backend reads JSON, so a YAML export is passed on re-serialized
+      let source = importData
+      if (!importData.trimStart().startsWith("{")) {
+        try {
+          source = JSON.stringify(yaml.load(importData))
+        } catch (_e) {
+          setParsingResult({
+            ...initialState,
+            detectedFormat: "insomnia",
+            issues: [customIssue("Invalid JSON or YAML syntax.")],
+          })
+          return
+        }
+      }
+      return convertInBackend(convertInsomniaExport, "insomnia", source, "Could not convert the Insomnia export.")
+    }
+    if (importFormat === "http" || (importFormat === "auto" && looksLikeHttpFile(importData))) {
+      return convertInBackend(convertHttpFile, "http", importData, "Could not convert the .http file.")
+    }
+
     let parsedSource: unknown
     try {
       parsedSource = yaml.load(importData) as unknown
@@ -59,13 +150,17 @@ export function useImportParser(
       return
     }
 
-    const format: ImportFormat =
+    const format: DetectedImportFormat =
       importFormat === "auto"
-        ? isPostmanCollection(parsedSource)
-          ? "postman"
-          : isOpenApiDocument(parsedSource)
-            ? "openapi"
-            : "native"
+        ? isThunderClientDocument(parsedSource)
+          ? "thunderclient"
+          : isHoppscotchDocument(parsedSource)
+            ? "hoppscotch"
+            : isPostmanCollection(parsedSource)
+              ? "postman"
+              : isOpenApiDocument(parsedSource)
+                ? "openapi"
+                : "native"
         : importFormat
     let nativeDoc: ExportedCollection | null = null
     let converted = ""
@@ -83,9 +178,12 @@ export function useImportParser(
         return
       }
     } else if (format === "postman") {
-      const validation = validatePostmanDocument(parsedSource)
+      // The backend reads JSON, so a YAML source is passed on re-serialized
+      return convertPostman(JSON.stringify(parsedSource))
+    } else if (format === "thunderclient") {
+      const validation = validateThunderClientDocument(parsedSource)
       if (validation.success) {
-        nativeDoc = postmanToNative(validation.data)
+        nativeDoc = thunderClientToNative(validation.data)
         converted = JSON.stringify(nativeDoc, null, 2)
       } else {
         setParsingResult({
@@ -95,30 +193,24 @@ export function useImportParser(
         })
         return
       }
-    } else {
-      nativeDoc = parsedSource as ExportedCollection
-    }
-
-    if (nativeDoc) {
-      const validation = validateNativeDocument(nativeDoc)
+    } else if (format === "hoppscotch") {
+      const validation = validateHoppscotchDocument(parsedSource)
       if (validation.success) {
-        setParsingResult({
-          collection: validation.data,
-          issues: null,
-          detectedFormat: format,
-          convertedData: converted,
-        })
+        nativeDoc = hoppscotchToNative(validation.data)
+        converted = JSON.stringify(nativeDoc, null, 2)
       } else {
         setParsingResult({
-          collection: null,
-          issues: validation.error.issues,
+          ...initialState,
           detectedFormat: format,
-          convertedData: converted,
+          issues: validation.error.issues,
         })
+        return
       }
     } else {
-      setParsingResult(initialState)
+      nativeDoc = parsedSource as ExportedCollection
     }
+
+    finish(nativeDoc, format, converted)
   }, [importData, importFormat, openApiOptions])
 
   return parsingResult
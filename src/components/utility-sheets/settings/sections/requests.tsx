@@ -24,6 +24,22 @@ export default function RequestsSection() {
     settingsApi().setProxyServer(v.length ? v : undefined)
   }, 400)
 
+  const handlePoolIdleTimeoutChange = useDebouncedCallback((value: number) => {
+    settingsApi().setConnectionPool({
+      enabled: settingsState.requests.connectionPool?.enabled ?? false,
+      ...settingsState.requests.connectionPool,
+      idleTimeoutSecs: value > 0 ? Math.floor(value) : undefined,
+    })
+  }, 300)
+
+  const handleMetricsPortChange = useDebouncedCallback((value: number) => {
+    settingsApi().setMetricsPort(value > 0 && value <= 65535 ? value : undefined)
+  }, 400)
+
+  const handleMetricsFileChange = useDebouncedCallback((value: string) => {
+    settingsApi().setMetricsFile(value.trim())
+  }, 400)
+
   const handlePreviewMaxChange = useDebouncedCallback((value: number) => {
     const bytes = Math.max(1, value) * 1024 * 1024
     settingsApi().setPreviewMaxBytes(bytes)
@@ -71,6 +87,48 @@ export default function RequestsSection() {
 
       <Separator className="mt-4 mb-4" />
 
+      <SettingRow
+        label="Keep-alive connections"
+        description="Reuse connections between requests to the same host instead of reconnecting each time"
+      >
+        <Switch
+          checked={settingsState.requests.connectionPool?.enabled ?? false}
+          onCheckedChange={(checked) =>
+            settingsApi().setConnectionPool({ ...settingsState.requests.connectionPool, enabled: checked })
+          }
+        />
+      </SettingRow>
+
+      <SettingRow label="Idle connection timeout" description="Seconds an unused pooled connection is kept open">
+        <Input
+          type="number"
+          min="1"
+          className="w-24"
+          placeholder="90"
+          disabled={!settingsState.requests.connectionPool?.enabled}
+          defaultValue={settingsState.requests.connectionPool?.idleTimeoutSecs ?? ""}
+          onChange={(e) => handlePoolIdleTimeoutChange(Number(e.target.value))}
+        />
+      </SettingRow>
+
+      <SettingRow
+        label="Share client setup"
+        description="Reuse TLS and DNS setup between requests with the same connection settings"
+      >
+        <Switch
+          checked={settingsState.requests.connectionPool?.shareClient ?? true}
+          onCheckedChange={(checked) =>
+            settingsApi().setConnectionPool({
+              enabled: settingsState.requests.connectionPool?.enabled ?? false,
+              ...settingsState.requests.connectionPool,
+              shareClient: checked,
+            })
+          }
+        />
+      </SettingRow>
+
+      <Separator className="mt-4 mb-4" />
+
       <SettingRow
         label="Proxy URL"
         description="Route requests through a proxy server (optional)"
@@ -99,6 +157,37 @@ export default function RequestsSection() {
           onChange={(e) => handlePreviewMaxChange(Number(e.target.value))}
         />
       </SettingRow>
+
+      <Separator className="mt-4 mb-4" />
+
+      <SettingRow
+        label="Monitor metrics port"
+        description="Serve monitor results for Prometheus at http://127.0.0.1:<port>/metrics (optional)"
+      >
+        <Input
+          type="number"
+          min="1"
+          max="65535"
+          className="w-24"
+          placeholder="9464"
+          defaultValue={settingsState.requests.metricsPort ?? ""}
+          onChange={(e) => handleMetricsPortChange(Number(e.target.value))}
+        />
+      </SettingRow>
+
+      <SettingRow
+        label="Monitor metrics file"
+        description="Write monitor results to this file for node_exporter's textfile collector (optional)"
+        className="flex flex-col items-stretch gap-2"
+      >
+        <div className="flex">
+          <Input
+            placeholder="/var/lib/node_exporter/knurl.prom"
+            defaultValue={settingsState.requests.metricsFile ?? ""}
+            onChange={(e) => handleMetricsFileChange(e.target.value)}
+          />
+        </div>
+      </SettingRow>
     </div>
   )
 }
@@ -0,0 +1,104 @@
+import { render, screen } from "@testing-library/react"
+import userEvent from "@testing-library/user-event"
+import { beforeEach, describe, expect, it, vi } from "vitest"
+
+import TrashSheet from "./index"
+import { Sheet, SheetContent } from "@/components/ui/sheet"
+
+vi.mock("@/bindings/knurl", () => ({
+  listTrash: vi.fn(),
+  purgeTrash: vi.fn(),
+  restoreTrashEntry: vi.fn(),
+}))
+import { listTrash, purgeTrash, restoreTrashEntry } from "@/bindings/knurl"
+
+vi.mock("@/state", () => ({
+  useCollections: vi.fn(),
+}))
+import { useCollections } from "@/state"
+
+const renderSheet = () =>
+  render(
+    <Sheet open>
+      <SheetContent>
+        <TrashSheet />
+      </SheetContent>
+    </Sheet>,
+  )
+
+describe("TrashSheet", () => {
+  const restoreRequest = vi.fn()
+  const restoreCollection = vi.fn()
+
+  beforeEach(() => {
+    vi.clearAllMocks()
+    vi.mocked(useCollections).mockReturnValue({
+      state: { collectionsIndex: [{ id: "col", name: "My Collection" }] },
+      actions: { collectionsApi: () => ({ restoreRequest, restoreCollection }) },
+    } as any)
+  })
+
+  it("restores a request into its collection", async () => {
+    const user = userEvent.setup()
+    const request = { id: "r1", name: "Get users", method: "GET", url: "/users" }
+    vi.mocked(listTrash)
+      .mockResolvedValueOnce([
+        { id: "t1", kind: "item", label: "Get users", parentId: "col", deletedAt: new Date().toISOString() },
+      ])
+      .mockResolvedValueOnce([])
+    vi.mocked(restoreTrashEntry).mockResolvedValue(request)
+
+    renderSheet()
+    expect(await screen.findByText("Get users")).toBeInTheDocument()
+    expect(screen.getByText(/Request in My Collection/)).toBeInTheDocument()
+
+    await user.click(screen.getByRole("button", { name: /Restore/ }))
+    expect(restoreTrashEntry).toHaveBeenCalledWith("t1")
+    expect(restoreRequest).toHaveBeenCalledWith("col", request)
+    expect(await screen.findByText("The trash is empty.")).toBeInTheDocument()
+  })
+
+  it("restores a collection file and re-indexes it", async () => {
+    const user = userEvent.setup()
+    vi.mocked(listTrash).mockResolvedValue([
+      {
+        id: "t2",
+        kind: "file",
+        fileName: "collections/abc.json",
+        label: "Old API",
+        deletedAt: new Date().toISOString(),
+      },
+    ])
+    vi.mocked(restoreTrashEntry).mockResolvedValue(null)
+
+    renderSheet()
+    await user.click(await screen.findByRole("button", { name: /Restore/ }))
+    expect(restoreTrashEntry).toHaveBeenCalledWith("t2")
+    expect(restoreCollection).toHaveBeenCalledWith("abc")
+  })
+
+  it("keeps requests whose collection is gone in the trash", async () => {
+    const user = userEvent.setup()
+    vi.mocked(listTrash).mockResolvedValue([
+      { id: "t3", kind: "item", label: "Orphan", parentId: "gone", deletedAt: new Date().toISOString() },
+    ])
+
+    renderSheet()
+    await user.click(await screen.findByRole("button", { name: /Restore/ }))
+    expect(restoreTrashEntry).not.toHaveBeenCalled()
+    expect(screen.getByRole("alert")).toHaveTextContent("no longer exists")
+  })
+
+  it("empties the trash", async () => {
+    const user = userEvent.setup()
+    vi.mocked(listTrash)
+      .mockResolvedValueOnce([{ id: "t4", kind: "item", label: "A", parentId: "col", deletedAt: "" }])
+      .mockResolvedValueOnce([])
+    vi.mocked(purgeTrash).mockResolvedValue(1)
+
+    renderSheet()
+    await user.click(await screen.findByRole("button", { name: "Empty Trash" }))
+    expect(purgeTrash).toHaveBeenCalledWith()
+    expect(await screen.findByText("The trash is empty.")).toBeInTheDocument()
+  })
+})
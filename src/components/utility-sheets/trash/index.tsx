@@ -0,0 +1,124 @@
+import { useCallback, useEffect, useState } from "react"
+
+import { ArchiveRestoreIcon, Trash2Icon } from "lucide-react"
+
+import { listTrash, purgeTrash, restoreTrashEntry, type TrashEntry } from "@/bindings/knurl"
+import { Alert, AlertDescription } from "@/components/ui/alert"
+import { Button } from "@/components/ui/button"
+import { SheetDescription, SheetFooter, SheetHeader, SheetTitle } from "@/components/ui/sheet"
+import { useCollections } from "@/state"
+import type { RequestState } from "@/types"
+
+/** The collection id of a trashed collection file, e.g. `collections/<id>.json` */
+const collectionIdFromFileName = (fileName: string): string | undefined =>
+  /^collections\/([^/]+)\.json$/.exec(fileName)?.[1]
+
+export default function TrashSheet() {
+  const {
+    state: { collectionsIndex },
+    actions: { collectionsApi },
+  } = useCollections()
+
+  const [entries, setEntries] = useState<TrashEntry[]>([])
+  const [error, setError] = useState<string | null>(null)
+
+  const refresh = useCallback(async () => {
+    try {
+      setEntries(await listTrash())
+    } catch (err) {
+      setError((err as Error)?.message ?? String(err))
+    }
+  }, [])
+
+  useEffect(() => {
+    void refresh()
+  }, [refresh])
+
+  const collectionName = useCallback(
+    (id: string) => collectionsIndex.find((e) => e.id === id)?.name,
+    [collectionsIndex],
+  )
+
+  const handleRestore = async (entry: TrashEntry) => {
+    setError(null)
+    try {
+      if (entry.kind === "item") {
+        // Check first, as restoring removes the entry from the trash
+        if (!entry.parentId || !collectionName(entry.parentId)) {
+          setError(`The collection "${entry.label ?? entry.id}" belonged to no longer exists`)
+          return
+        }
+        const request = await restoreTrashEntry(entry.id)
+        await collectionsApi().restoreRequest(entry.parentId, request as RequestState)
+      } else {
+        await restoreTrashEntry(entry.id)
+        const collectionId = entry.fileName ? collectionIdFromFileName(entry.fileName) : undefined
+        if (collectionId) {
+          await collectionsApi().restoreCollection(collectionId)
+        }
+      }
+    } catch (err) {
+      setError((err as Error)?.message ?? String(err))
+    }
+    await refresh()
+  }
+
+  const handleEmpty = async () => {
+    setError(null)
+    try {
+      await purgeTrash()
+    } catch (err) {
+      setError((err as Error)?.message ?? String(err))
+    }
+    await refresh()
+  }
+
+  return (
+    <div className="flex h-full min-h-0 flex-col overflow-hidden">
+      <SheetHeader className="border-b px-6 pt-6 pb-4">
+        <SheetTitle className="flex items-center gap-2 text-xl">
+          <Trash2Icon className="h-5 w-5 text-primary" />
+          Trash
+        </SheetTitle>
+        <SheetDescription>Deleted collections and requests are kept here for 30 days.</SheetDescription>
+      </SheetHeader>
+
+      <div className="flex-1 overflow-auto px-6 py-4">
+        {error && (
+          <Alert variant="destructive" className="mb-4">
+            <AlertDescription>{error}</AlertDescription>
+          </Alert>
+        )}
+        {entries.length === 0 ? (
+          <p className="text-sm text-muted-foreground">The trash is empty.</p>
+        ) : (
+          <ul className="divide-y rounded-sm border">
+            {entries.map((entry) => (
+              <li key={entry.id} className="flex items-center justify-between gap-4 px-3 py-2">
+                <div className="min-w-0">
+                  <div className="truncate text-sm font-medium">{entry.label ?? entry.fileName ?? entry.id}</div>
+                  <div className="truncate text-xs text-muted-foreground">
+                    {entry.kind === "item"
+                      ? `Request in ${(entry.parentId && collectionName(entry.parentId)) ?? "a deleted collection"}`
+                      : "Collection"}
+                    {" · "}Deleted {new Date(entry.deletedAt).toLocaleString()}
+                  </div>
+                </div>
+                <Button variant="outline" size="sm" onClick={() => handleRestore(entry)}>
+                  <ArchiveRestoreIcon className="h-4 w-4" />
+                  Restore
+                </Button>
+              </li>
+            ))}
+          </ul>
+        )}
+      </div>
+
+      <SheetFooter className="border-t px-6 py-4 flex-row justify-end gap-2">
+        <Button variant="destructive" onClick={handleEmpty} disabled={entries.length === 0}>
+          Empty Trash
+        </Button>
+      </SheetFooter>
+    </div>
+  )
+}
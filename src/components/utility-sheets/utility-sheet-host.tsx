@@ -6,6 +6,7 @@ import ExportCollectionSheet from "@/components/utility-sheets/export-collection
 import ImportCollectionSheet from "@/components/utility-sheets/import-collection"
 import SettingsSheet from "@/components/utility-sheets/settings"
 import ThemeEditorSheet from "@/components/utility-sheets/theme-editor"
+import TrashSheet from "@/components/utility-sheets/trash"
 import { useUtilitySheets } from "@/state"
 import type { UtilitySheet, UtilitySheetType } from "@/types"
 
@@ -18,6 +19,7 @@ const SHEET_CLASS_BY_TYPE: Record<UtilitySheetType, string> = {
   environment: "!w-[var(--utility-sheet-width)] !max-w-[100vw]",
   "collection-settings": "!w-[var(--utility-sheet-width)] !max-w-[100vw]",
   "theme-editor": "!w-[var(--utility-sheet-width)] !max-w-[100vw]",
+  trash: "!w-[var(--utility-sheet-width)] !max-w-[100vw]",
 }
 
 export function UtilitySheetHost() {
@@ -111,6 +113,9 @@ export function UtilitySheetHost() {
     case "theme-editor":
       content = <ThemeEditorSheet />
       break
+    case "trash":
+      content = <TrashSheet />
+      break
     default:
       content = null
   }
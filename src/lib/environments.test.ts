@@ -1,5 +1,6 @@
 import { describe, it, expect } from "vitest"
-import { resolveVariablesPhase, type RequestContext } from "@/request/pipeline"
+import { applyParamPlaceholders } from "@/lib/environments"
+import { variablesPhase, type RequestContext } from "@/request/pipeline"
 import type { Environment, RequestState } from "@/types"
 
 function makeCtx(vars: Partial<Environment["variables"]>): RequestContext {
@@ -23,15 +24,28 @@ function makeCtx(vars: Partial<Environment["variables"]>): RequestContext {
   }
 }
 
-describe("resolveVariablesPhase: disabled variables are ignored", () => {
-  it("does not substitute values for disabled variables and leaves placeholders intact", async () => {
+describe("variablesPhase: disabled variables are ignored", () => {
+  it("does not pass values for disabled variables and leaves placeholders intact", async () => {
     const ctx = makeCtx({
       host: { id: "v1", name: "host", value: "api.example.com", secure: false, enabled: false },
       id: { id: "v2", name: "id", value: "123", secure: false, enabled: true },
     })
 
-    const out = await resolveVariablesPhase(ctx)
-    expect(out.request.url).toBe("https://{{host}}/users/123")
+    const out = await variablesPhase(ctx)
+    expect(out.request.url).toBe("https://{{host}}/users/{{id}}")
+    expect(out.variables).toEqual({ id: "123" })
   })
 })
 
+describe("applyParamPlaceholders", () => {
+  it("fills params whose names contain regex metacharacters literally", () => {
+    const request = {
+      url: "https://api.example.com/{{id(1)}}/{{a.b}}?q={{q+}}&x={{axb}}",
+      pathParams: { p1: { name: "id(1)", value: "42" }, p2: { name: "a.b", value: "$&" } },
+      queryParams: { q: { name: "q+", value: "plus" } },
+    }
+
+    const out = applyParamPlaceholders(request, undefined)
+    expect(out.url).toBe("https://api.example.com/42/$&?q=plus&x={{axb}}")
+  })
+})
@@ -1,5 +1,10 @@
+import type { VariableValue } from "@/bindings/knurl"
 import type { Environment, EnvironmentVariable } from "@/types"
 
+function escapeRegExp(string: string): string {
+  return string.replace(/[.*+?^${}()|[\\]/g, "\\$&")
+}
+
 /**
  * Creates a map of variable names to their values for quick lookups.
  * @param environment The environment to extract variables from.
@@ -48,94 +53,61 @@ export function substituteVariables(input: string, variables: Record<string, str
 }
 
 /**
- * Deeply substitutes variables in a request object.
- * Creates a new request object with all variables in the URL, params, and headers replaced.
+ * The environment's enabled variables by name, for the backend to substitute into a request just before sending
+ * it. Secure variables are passed as references, so their values never travel with the request; the backend reads
+ * them as last staged by the collections store, unsaved edits included, or else from the collection file.
  *
- * @param request The original request state.
  * @param environment The environment to apply.
- * @returns A new RequestState object with all variables substituted.
+ * @param collectionId The collection the environment belongs to.
+ * @returns A Record mapping variable names to their values or secret references.
  */
-export function resolveRequestVariables<T extends object>(request: T, environment: Environment | undefined): T {
-  const variables = getVariableMap(environment)
-  if (Object.keys(variables).length === 0) {
-    return request
-  }
-
-  // Deep clone the request object to avoid mutating the original state
-  const resolvedRequest = structuredClone(request)
-
-  // Substitute in URL with environment variables first
-  if ("url" in resolvedRequest && typeof resolvedRequest.url === "string") {
-    resolvedRequest.url = substituteVariables(resolvedRequest.url, variables)
-  }
-
-  // Substitute in Path Params values using environment variables
-  if ("pathParams" in resolvedRequest && resolvedRequest.pathParams) {
-    for (const param of Object.values(resolvedRequest.pathParams as Record<string, { value: string; name?: string }>)) {
-      param.value = substituteVariables(param.value, variables)
-    }
-  }
-
-  // After resolving path param values, also inject path params into URL when placeholders are present
-  if (
-    "url" in resolvedRequest &&
-    typeof resolvedRequest.url === "string" &&
-    "pathParams" in resolvedRequest &&
-    resolvedRequest.pathParams
-  ) {
-    let url = resolvedRequest.url as unknown as string
-    const params = resolvedRequest.pathParams as Record<string, { value: string; name?: string }>
-    for (const [key, param] of Object.entries(params)) {
-      const name = param.name ?? key
-      const resolvedValue = param.value
-      // Only inject when the resolved value itself no longer contains variable placeholders
-      if (/\{\{/.test(resolvedValue)) {
-        continue
-      }
-      const pat = `{{${name}}}`
-      const escaped = pat.replace(/[.*+?^${}()|[\\]/g, "\\$&")
-      url = url.replace(new RegExp(escaped, "g"), resolvedValue)
-    }
-    ;(resolvedRequest as { url?: string }).url = url
+export function requestVariables(
+  environment: Environment | undefined,
+  collectionId: string,
+): Record<string, VariableValue> {
+  if (!environment) {
+    return {}
   }
-
-  // Substitute in Query Params
-  if ("queryParams" in resolvedRequest && resolvedRequest.queryParams) {
-    for (const param of Object.values(
-      resolvedRequest.queryParams as Record<string, { value: string; name?: string }>,
-    )) {
-      param.value = substituteVariables(param.value, variables)
+  const variables: Record<string, VariableValue> = {}
+  const entries = Object.entries(environment.variables) as Array<[string, EnvironmentVariable & { enabled?: boolean }]>
+  for (const [variableId, variable] of entries) {
+    if (!variable.name || variable.enabled === false) {
+      continue
     }
+    // The variable is stored under its key, which is how the backend finds its staged or saved value
+    variables[variable.name] = variable.secure
+      ? { secret: { source: "environment", collectionId, environmentId: environment.id, variableId } }
+      : variable.value
   }
+  return variables
+}
 
-  // After resolving query param values, also replace any placeholders in the URL matching the param names
-  if (
-    "url" in resolvedRequest &&
-    typeof resolvedRequest.url === "string" &&
-    "queryParams" in resolvedRequest &&
-    resolvedRequest.queryParams
-  ) {
-    let url = resolvedRequest.url as unknown as string
-    const qps = resolvedRequest.queryParams as Record<string, { value: string; name?: string }>
-    for (const [key, qp] of Object.entries(qps)) {
-      const name = qp.name ?? key
-      const resolvedValue = qp.value
-      if (/\{\{/.test(resolvedValue)) {
-        continue
-      }
-      const pat = `{{${name}}}`
-      const escaped = pat.replace(/[.*+?^${}()|[\\]/g, "\\$&")
-      url = url.replace(new RegExp(escaped, "g"), resolvedValue)
-    }
-    ;(resolvedRequest as { url?: string }).url = url
+/**
+ * Fills `{{name}}` placeholders in the request URL that name one of its path or query params, and no variable of
+ * the environment, with the param's value. Values are copied as they are, `{{variables}}` included, for the backend
+ * to substitute.
+ *
+ * @param request The original request state.
+ * @param environment The environment to apply, whose variables take precedence over params of the same name.
+ * @returns A new request object with its param placeholders filled.
+ */
+export function applyParamPlaceholders<T extends object>(request: T, environment: Environment | undefined): T {
+  if (!("url" in request) || typeof request.url !== "string") {
+    return request
   }
-
-  // Substitute in Headers
-  if ("headers" in resolvedRequest && resolvedRequest.headers) {
-    for (const header of Object.values(resolvedRequest.headers as Record<string, { value: string }>)) {
-      header.value = substituteVariables(header.value, variables)
+  const variables = getVariableMap(environment)
+  const resolvedRequest = structuredClone(request) as T & { url: string }
+  const params = [
+    ...Object.entries(("pathParams" in request && request.pathParams) || {}),
+    ...Object.entries(("queryParams" in request && request.queryParams) || {}),
+  ] as Array<[string, { value: string; name?: string }]>
+  for (const [key, param] of params) {
+    const name = param.name ?? key
+    if (variables[name] !== undefined) {
+      continue
     }
+    const pattern = escapeRegExp(`{{${name}}}`)
+    resolvedRequest.url = resolvedRequest.url.replace(new RegExp(pattern, "g"), () => param.value)
   }
-
   return resolvedRequest
 }
@@ -0,0 +1,58 @@
+import { describe, expect, it } from "vitest"
+
+import { applyIdentityProfile, identityHeaders } from "@/lib/identity"
+import type { IdentityProfile, RequestState } from "@/types"
+
+const chrome: IdentityProfile = {
+  id: "p1",
+  name: "Chrome on Windows",
+  userAgent: "Mozilla/5.0 (Windows NT 10.0; Win64; x64) Chrome/124.0",
+  acceptLanguage: "en-US,en;q=0.9",
+  secChUa: '"Chromium";v="124", "Google Chrome";v="124"',
+  secChUaMobile: "?0",
+  secChUaPlatform: '"Windows"',
+  headers: { Accept: "text/html" },
+}
+
+function makeRequest(patch: Partial<RequestState> = {}): RequestState {
+  return {
+    id: "r1",
+    collectionId: "c1",
+    name: "Req",
+    method: "GET",
+    url: "https://example.com",
+    headers: {},
+    queryParams: {},
+    body: { type: "none" },
+    ...patch,
+  } as RequestState
+}
+
+describe("identityHeaders", () => {
+  it("lists the profile's headers and skips unset fields", () => {
+    expect(identityHeaders({ id: "p", name: "p", acceptLanguage: "de", headers: { DNT: "1" } })).toEqual([
+      ["Accept-Language", "de"],
+      ["DNT", "1"],
+    ])
+  })
+})
+
+describe("applyIdentityProfile", () => {
+  it("sends the user agent as an option and adds the other headers", () => {
+    const applied = applyIdentityProfile(makeRequest(), chrome)
+    expect(applied.options?.userAgent).toBe(chrome.userAgent)
+    const names = Object.values(applied.headers).map((h) => h.name)
+    expect(names).toEqual(["Accept-Language", "sec-ch-ua", "sec-ch-ua-mobile", "sec-ch-ua-platform", "Accept"])
+  })
+
+  it("lets the request's own headers and user agent win", () => {
+    const request = makeRequest({
+      headers: { h1: { id: "h1", name: "accept-language", value: "fr", enabled: true, secure: false } },
+      options: { userAgent: "curl/8.0" },
+    })
+    const applied = applyIdentityProfile(request, chrome)
+    expect(applied.options?.userAgent).toBe("curl/8.0")
+    const languages = Object.values(applied.headers).filter((h) => h.name.toLowerCase() === "accept-language")
+    expect(languages.map((h) => h.value)).toEqual(["fr"])
+  })
+})
@@ -0,0 +1,54 @@
+import { generateUniqueId } from "@/lib/utils"
+import type { IdentityProfile, RequestState } from "@/types"
+
+/**
+ * Lists the headers an identity profile sends, skipping fields that are unset.
+ * @param profile The profile to read.
+ * @returns Header name and value pairs, the profile's extra headers last.
+ */
+export function identityHeaders(profile: IdentityProfile): [string, string][] {
+  const named: [string, string | undefined][] = [
+    ["User-Agent", profile.userAgent],
+    ["Accept-Language", profile.acceptLanguage],
+    ["sec-ch-ua", profile.secChUa],
+    ["sec-ch-ua-mobile", profile.secChUaMobile],
+    ["sec-ch-ua-platform", profile.secChUaPlatform],
+    ...Object.entries(profile.headers ?? {}),
+  ]
+  return named.filter((entry): entry is [string, string] => !!entry[0] && !!entry[1])
+}
+
+/**
+ * Adds a profile's headers to a request. Headers the request sets itself, and a User-Agent set
+ * in its options, take precedence over the profile.
+ *
+ * @param request The request to send.
+ * @param profile The identity profile it references.
+ * @returns A copy of the request with the profile applied.
+ */
+export function applyIdentityProfile(request: RequestState, profile: IdentityProfile): RequestState {
+  const existing = new Set(
+    Object.values(request.headers ?? {})
+      .filter((header) => header.enabled && header.name)
+      .map((header) => header.name.toLowerCase()),
+  )
+  const headers = { ...request.headers }
+  let options = request.options
+
+  for (const [name, value] of identityHeaders(profile)) {
+    if (existing.has(name.toLowerCase())) {
+      continue
+    }
+    // The backend sends options.userAgent in place of any User-Agent header
+    if (name.toLowerCase() === "user-agent") {
+      if (!options?.userAgent) {
+        options = { ...options, userAgent: value }
+      }
+      continue
+    }
+    const id = generateUniqueId()
+    headers[id] = { id, name, value, enabled: true, secure: false }
+  }
+
+  return { ...request, headers, options }
+}
@@ -0,0 +1,58 @@
+import { describe, expect, it } from "vitest"
+
+import { destinationHeader, webdavDefaults } from "@/lib/webdav"
+import type { RequestState } from "@/types"
+
+function makeRequest(patch: Partial<RequestState> = {}): RequestState {
+  return {
+    id: "r1",
+    collectionId: "c1",
+    name: "Req",
+    method: "GET",
+    url: "https://dav.example.com/files/report.txt",
+    headers: {},
+    queryParams: {},
+    body: { type: "none" },
+    ...patch,
+  } as RequestState
+}
+
+describe("webdavDefaults", () => {
+  it("adds Depth and an allprop body for PROPFIND", () => {
+    const changes = webdavDefaults(makeRequest(), "PROPFIND")
+    const headers = Object.values(changes.headers ?? {})
+    expect(headers.map((h) => [h.name, h.value])).toEqual([["Depth", "1"]])
+    expect(changes.body?.type).toBe("text")
+    expect(changes.body?.language).toBe("xml")
+    expect(changes.body?.content).toContain("<d:allprop/>")
+  })
+
+  it("keeps headers and bodies the request already has", () => {
+    const request = makeRequest({
+      headers: { h1: { id: "h1", name: "depth", value: "infinity", enabled: true, secure: false } },
+      body: { type: "text", content: "<custom/>" },
+    })
+    const changes = webdavDefaults(request, "PROPFIND")
+    expect(changes).toEqual({})
+  })
+
+  it("adds Destination and Overwrite for MOVE without a body", () => {
+    const changes = webdavDefaults(makeRequest(), "MOVE")
+    const names = Object.values(changes.headers ?? {}).map((h) => h.name)
+    expect(names).toEqual(["Destination", "Overwrite"])
+    expect(changes.body).toBeUndefined()
+  })
+
+  it("leaves non-WebDAV methods alone", () => {
+    expect(webdavDefaults(makeRequest(), "POST")).toEqual({})
+  })
+})
+
+describe("destinationHeader", () => {
+  it("resolves paths against the request URL", () => {
+    const url = "https://dav.example.com/files/report.txt"
+    expect(destinationHeader(url, "/archive/report.txt")).toBe("https://dav.example.com/archive/report.txt")
+    expect(destinationHeader(url, "copy.txt")).toBe("https://dav.example.com/files/copy.txt")
+    expect(destinationHeader(url, "https://other.example.com/x")).toBe("https://other.example.com/x")
+  })
+})
@@ -0,0 +1,105 @@
+import { generateUniqueId } from "@/lib/utils"
+import type { HttpMethod, RequestHeader, RequestState } from "@/types"
+
+/**
+ * Methods defined by WebDAV (RFC 4918), plus REPORT used by CalDAV and CardDAV.
+ */
+export const WebDavMethods: HttpMethod[] = [
+  "PROPFIND",
+  "PROPPATCH",
+  "MKCOL",
+  "COPY",
+  "MOVE",
+  "LOCK",
+  "UNLOCK",
+  "REPORT",
+]
+
+/**
+ * Headers each method needs, with the values most servers expect as a starting point.
+ * Destination and Lock-Token are left for the user to fill in.
+ */
+const DefaultHeaders: Partial<Record<HttpMethod, Record<string, string>>> = {
+  PROPFIND: { Depth: "1" },
+  REPORT: { Depth: "1" },
+  COPY: { Destination: "", Overwrite: "T" },
+  MOVE: { Destination: "", Overwrite: "T" },
+  LOCK: { Depth: "0", Timeout: "Second-3600" },
+  UNLOCK: { "Lock-Token": "" },
+}
+
+/**
+ * XML body templates; methods without one (MKCOL, COPY, MOVE, UNLOCK) are sent without a body.
+ */
+const BodyTemplates: Partial<Record<HttpMethod, string>> = {
+  PROPFIND: `<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:">
+  <d:allprop/>
+</d:propfind>`,
+  PROPPATCH: `<?xml version="1.0" encoding="utf-8"?>
+<d:propertyupdate xmlns:d="DAV:">
+  <d:set>
+    <d:prop>
+      <d:displayname>New name</d:displayname>
+    </d:prop>
+  </d:set>
+</d:propertyupdate>`,
+  LOCK: `<?xml version="1.0" encoding="utf-8"?>
+<d:lockinfo xmlns:d="DAV:">
+  <d:lockscope><d:exclusive/></d:lockscope>
+  <d:locktype><d:write/></d:locktype>
+  <d:owner>knurl</d:owner>
+</d:lockinfo>`,
+  REPORT: `<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop>
+    <d:getetag/>
+  </d:prop>
+  <c:filter>
+    <c:comp-filter name="VCALENDAR"/>
+  </c:filter>
+</c:calendar-query>`,
+}
+
+/**
+ * Builds a Destination header value for COPY/MOVE. RFC 4918 requires an absolute URI, so a
+ * path is resolved against the request URL.
+ *
+ * @param requestUrl The URL of the resource being copied or moved.
+ * @param target An absolute URL or a path on the same server.
+ */
+export function destinationHeader(requestUrl: string, target: string): string {
+  try {
+    return new URL(target, requestUrl).href
+  } catch {
+    return target
+  }
+}
+
+/**
+ * Changes that prepare a request for a WebDAV method: the headers the method needs that the
+ * request doesn't already have, and a body template when the request has no body. Returns an
+ * empty object for other methods.
+ */
+export function webdavDefaults(request: RequestState, method: HttpMethod): Partial<RequestState> {
+  const changes: Partial<RequestState> = {}
+
+  const existing = new Set(Object.values(request.headers ?? {}).map((header) => header.name.toLowerCase()))
+  const missing = Object.entries(DefaultHeaders[method] ?? {}).filter(([name]) => !existing.has(name.toLowerCase()))
+  if (missing.length > 0) {
+    const headers: Record<string, RequestHeader> = { ...request.headers }
+    for (const [name, value] of missing) {
+      const id = generateUniqueId()
+      headers[id] = { id, name, value, enabled: true, secure: false }
+    }
+    changes.headers = headers
+  }
+
+  const template = BodyTemplates[method]
+  const hasBody = request.body?.type !== undefined && request.body.type !== "none"
+  if (template && !hasBody) {
+    changes.body = { type: "text", language: "xml", content: template }
+  }
+
+  return changes
+}
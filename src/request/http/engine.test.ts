@@ -9,7 +9,7 @@ vi.mock("@/bindings/knurl", async (importOriginal) => {
   const actual = await importOriginal()
   return {
     ...actual,
-    sendHttpRequest: vi.fn(),
+    streamHttpRequest: vi.fn(),
   }
 })
 
@@ -43,7 +43,7 @@ describe("HttpEngine mapping", () => {
       body: { type: "text", content: "{\"a\":1}", language: "json" },
     })
 
-    vi.mocked(knurl.sendHttpRequest).mockResolvedValue({
+    vi.mocked(knurl.streamHttpRequest).mockResolvedValue({
       requestId: "corr-1",
       status: 200,
       statusText: "OK",
@@ -58,12 +58,12 @@ describe("HttpEngine mapping", () => {
     const res = await HttpEngine.execute(ctx)
     expect(res.data.type).toBe("http")
 
-    // Inspect the call to sendHttpRequest to verify mapping
-    expect(knurl.sendHttpRequest).toHaveBeenCalledOnce()
-    const call = vi.mocked(knurl.sendHttpRequest).mock.calls[0][0] as any
+    // Inspect the call to streamHttpRequest to verify mapping
+    expect(knurl.streamHttpRequest).toHaveBeenCalledOnce()
+    const call = vi.mocked(knurl.streamHttpRequest).mock.calls[0][0] as any
 
     // Only enabled header propagated and JSON inferred
-    expect(call.headers).toMatchObject({ "X-Enabled": "1", "Content-Type": "application/json" })
+    expect(Object.fromEntries(call.headers)).toMatchObject({ "X-Enabled": "1", "Content-Type": "application/json" })
     // Body should be bytes because method is POST
     expect(call.body).toBeDefined()
     expect(ArrayBuffer.isView(call.body)).toBe(true)
@@ -71,6 +71,26 @@ describe("HttpEngine mapping", () => {
     expect(call.maxLogBytes).toBe(2048)
     expect(call.redactSensitive).toBe(true)
     expect(call.logBodies).toBe(true)
+  })
+
+  it("passes on the spool file of a large response and whether it is compressed", async () => {
+    vi.mocked(knurl.streamHttpRequest).mockResolvedValue({
+      requestId: "corr-1",
+      status: 200,
+      statusText: "OK",
+      headers: [["Content-Type", "application/json"]],
+      cookies: [],
+      body: new Uint8Array(),
+      filePath: "/tmp/knurl-abc.zst",
+      fileCompressed: true,
+      size: 50_000_000,
+      duration: 1,
+      timestamp: new Date().toISOString(),
+    } as any)
+
+    const res = await HttpEngine.execute(makeBaseContext())
+    expect(res.data.type).toBe("http")
+    expect(res.data.data).toMatchObject({ filePath: "/tmp/knurl-abc.zst", fileCompressed: true })
   })
 
   it("merges cookies from authResult into Cookie header, preserving existing", async () => {
@@ -83,7 +103,7 @@ describe("HttpEngine mapping", () => {
       cookies: { b: "2", c: "3" },
     }
 
-    vi.mocked(knurl.sendHttpRequest).mockResolvedValue({
+    vi.mocked(knurl.streamHttpRequest).mockResolvedValue({
       requestId: "corr-1",
       status: 200,
       statusText: "OK",
@@ -97,8 +117,42 @@ describe("HttpEngine mapping", () => {
 
     await HttpEngine.execute({ ...ctx, authResult } as any)
 
-    const call = vi.mocked(knurl.sendHttpRequest).mock.calls[0][0] as any
-    expect(call.headers.Cookie ?? call.headers.cookie).toBe("a=1; b=2; c=3")
+    const call = vi.mocked(knurl.streamHttpRequest).mock.calls[0][0] as any
+    const headers = Object.fromEntries(call.headers)
+    expect(headers.Cookie ?? headers.cookie).toBe("a=1; b=2; c=3")
+  })
+
+  it("sends repeated headers in request order", async () => {
+    const ctx = makeBaseContext({
+      method: "GET",
+      headers: {
+        a: { id: "a", name: "X-Tag", value: "one", enabled: true },
+        b: { id: "b", name: "Accept", value: "*/*", enabled: true },
+        c: { id: "c", name: "X-Tag", value: "two", enabled: true },
+      },
+      body: undefined,
+    })
+
+    vi.mocked(knurl.streamHttpRequest).mockResolvedValue({
+      requestId: "corr-1",
+      status: 200,
+      statusText: "OK",
+      headers: [],
+      cookies: [],
+      body: new Uint8Array(),
+      size: 0,
+      duration: 1,
+      timestamp: new Date().toISOString(),
+    } as any)
+
+    await HttpEngine.execute(ctx)
+
+    const call = vi.mocked(knurl.streamHttpRequest).mock.calls[0][0] as any
+    expect(call.headers).toEqual([
+      ["X-Tag", "one"],
+      ["Accept", "*/*"],
+      ["X-Tag", "two"],
+    ])
   })
 
   it("auth query params override request params (last-wins)", async () => {
@@ -112,7 +166,7 @@ describe("HttpEngine mapping", () => {
 
     const authResult = { query: { q: "two", extra: "x" } }
 
-    vi.mocked(knurl.sendHttpRequest).mockResolvedValue({
+    vi.mocked(knurl.streamHttpRequest).mockResolvedValue({
       requestId: "corr-1",
       status: 200,
       statusText: "OK",
@@ -126,9 +180,12 @@ describe("HttpEngine mapping", () => {
 
     await HttpEngine.execute({ ...ctx, authResult } as any)
 
-    const call = vi.mocked(knurl.sendHttpRequest).mock.calls[0][0] as any
-    expect(call.url).toContain("q=two")
-    expect(call.url).toContain("extra=x")
+    const call = vi.mocked(knurl.streamHttpRequest).mock.calls[0][0] as any
+    expect(call.url).toBe("https://api.example.com/search")
+    expect(call.queryParams).toEqual([
+      ["q", "two"],
+      ["extra", "x"],
+    ])
   })
 
   it("throws when auth body placement used with non-form text body", async () => {
@@ -172,7 +229,7 @@ describe("HttpEngine errors", () => {
   it("surfaces backend BadRequest for invalid header name", async () => {
     const err: any = new Error("[BadRequest] invalid header name: '\nFoo'")
     err.appError = { kind: "BadRequest", message: "invalid header name: '\nFoo'", timestamp: new Date().toISOString() }
-    vi.mocked(knurl.sendHttpRequest).mockRejectedValue(err)
+    vi.mocked(knurl.streamHttpRequest).mockRejectedValue(err)
 
     const request = makeBase({
       headers: {
@@ -237,14 +294,14 @@ describe("HttpEngine multipart/form-data", () => {
   }
 
   it("delegates all multipart assembly to backend when no Content-Type provided", async () => {
-    vi.mocked(knurl.sendHttpRequest).mockResolvedValue(okResponse as any)
+    vi.mocked(knurl.streamHttpRequest).mockResolvedValue(okResponse as any)
 
     const request = makeBase()
     await HttpEngine.execute({ request, response: {} } as any)
 
-    expect(knurl.sendHttpRequest).toHaveBeenCalledTimes(1)
-    const call = vi.mocked(knurl.sendHttpRequest).mock.calls[0][0] as any
-    const ct = Object.entries(call.headers || {}).find(([k]) => k.toLowerCase() === "content-type")?.[1]
+    expect(knurl.streamHttpRequest).toHaveBeenCalledTimes(1)
+    const call = vi.mocked(knurl.streamHttpRequest).mock.calls[0][0] as any
+    const ct = (call.headers ?? []).find(([k]) => k.toLowerCase() === "content-type")?.[1]
     expect(ct).toBeUndefined()
     expect(call.body).toBeUndefined()
     expect(call.multipartParts).toEqual([
@@ -254,7 +311,7 @@ describe("HttpEngine multipart/form-data", () => {
   })
 
   it("passes through existing Content-Type with boundary; backend will use it", async () => {
-    vi.mocked(knurl.sendHttpRequest).mockResolvedValue(okResponse as any)
+    vi.mocked(knurl.streamHttpRequest).mockResolvedValue(okResponse as any)
 
     const boundary = "PreSetBoundary123"
     const request = makeBase({
@@ -270,8 +327,8 @@ describe("HttpEngine multipart/form-data", () => {
     })
 
     await HttpEngine.execute({ request, response: {} } as any)
-    const call = vi.mocked(knurl.sendHttpRequest).mock.calls[0][0] as any
-    const ct = Object.entries(call.headers || {}).find(([k]) => k.toLowerCase() === "content-type")?.[1]
+    const call = vi.mocked(knurl.streamHttpRequest).mock.calls[0][0] as any
+    const ct = (call.headers ?? []).find(([k]) => k.toLowerCase() === "content-type")?.[1]
     expect(ct).toBe(`multipart/form-data; boundary=${boundary}`)
     expect(call.body).toBeUndefined()
     expect(call.multipartParts).toEqual([
@@ -281,7 +338,7 @@ describe("HttpEngine multipart/form-data", () => {
   })
 
   it("delegates file parts to backend via multipartParts when filePath is present", async () => {
-    vi.mocked(knurl.sendHttpRequest).mockResolvedValue(okResponse as any)
+    vi.mocked(knurl.streamHttpRequest).mockResolvedValue(okResponse as any)
 
     const request = makeBase({
       body: {
@@ -304,7 +361,7 @@ describe("HttpEngine multipart/form-data", () => {
     })
 
     await HttpEngine.execute({ request, response: {} } as any)
-    const call = vi.mocked(knurl.sendHttpRequest).mock.calls[0][0] as any
+    const call = vi.mocked(knurl.streamHttpRequest).mock.calls[0][0] as any
     expect(call.multipartParts).toBeTruthy()
     expect(call.body).toBeUndefined()
     expect(call.multipartParts[0]).toEqual({
@@ -360,13 +417,13 @@ describe("HttpEngine binary body", () => {
   }
 
   it("passes bodyFilePath and sets Content-Type if provided", async () => {
-    vi.mocked(knurl.sendHttpRequest).mockResolvedValue(okResponse as any)
+    vi.mocked(knurl.streamHttpRequest).mockResolvedValue(okResponse as any)
     const request = base()
     await HttpEngine.execute({ request, response: {} } as any)
-    expect(knurl.sendHttpRequest).toHaveBeenCalledOnce()
-    const call = vi.mocked(knurl.sendHttpRequest).mock.calls[0][0] as any
+    expect(knurl.streamHttpRequest).toHaveBeenCalledOnce()
+    const call = vi.mocked(knurl.streamHttpRequest).mock.calls[0][0] as any
     expect(call.bodyFilePath).toBe("/tmp/blob.bin")
-    const ct = Object.entries(call.headers || {}).find(([k]) => k.toLowerCase() === "content-type")?.[1]
+    const ct = (call.headers ?? []).find(([k]) => k.toLowerCase() === "content-type")?.[1]
     expect(ct).toBe("application/octet-stream")
   })
 })
@@ -417,21 +474,21 @@ describe("HttpEngine auth body placement", () => {
   })
 
   it("injects auth body fields into urlencoded form (last-wins)", async () => {
-    vi.mocked(knurl.sendHttpRequest).mockResolvedValue(okResponse as any)
+    vi.mocked(knurl.streamHttpRequest).mockResolvedValue(okResponse as any)
     const request = makeFormUrl()
     const authResult = { body: { access_token: "XYZ" } }
 
     await HttpEngine.execute({ request, response: {}, authResult } as any)
-    const call = vi.mocked(knurl.sendHttpRequest).mock.calls[0][0] as any
+    const call = vi.mocked(knurl.streamHttpRequest).mock.calls[0][0] as any
     const bodyStr = new TextDecoder().decode(call.body)
     expect(bodyStr).toContain("field1=value1")
     expect(bodyStr).toContain("access_token=XYZ")
-    const ct = Object.entries(call.headers || {}).find(([k]) => k.toLowerCase() === "content-type")?.[1]
+    const ct = (call.headers ?? []).find(([k]) => k.toLowerCase() === "content-type")?.[1]
     expect(ct).toBe("application/x-www-form-urlencoded")
   })
 
   it("adds auth fields as text parts in multipart (backend assembly)", async () => {
-    vi.mocked(knurl.sendHttpRequest).mockResolvedValue(okResponse as any)
+    vi.mocked(knurl.streamHttpRequest).mockResolvedValue(okResponse as any)
     const request: RequestState = {
       id: "req2",
       name: "Form MP",
@@ -468,7 +525,7 @@ describe("HttpEngine auth body placement", () => {
     }
     const authResult = { body: { api_key: "K" } }
     await HttpEngine.execute({ request, response: {}, authResult } as any)
-    const call = vi.mocked(knurl.sendHttpRequest).mock.calls[0][0] as any
+    const call = vi.mocked(knurl.streamHttpRequest).mock.calls[0][0] as any
     expect(call.multipartParts).toBeTruthy()
     const textPart = call.multipartParts.find((p: any) => p.type === "text" && p.name === "api_key")
     expect(textPart).toBeTruthy()
@@ -476,7 +533,7 @@ describe("HttpEngine auth body placement", () => {
   })
 
   it("throws for auth body placement with text body", async () => {
-    vi.mocked(knurl.sendHttpRequest).mockResolvedValue(okResponse as any)
+    vi.mocked(knurl.streamHttpRequest).mockResolvedValue(okResponse as any)
     const request: RequestState = {
       id: "req3",
       name: "Text body",
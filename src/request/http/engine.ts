@@ -1,4 +1,10 @@
-import { sendHttpRequest, type MultipartPart } from "@/bindings/knurl"
+import {
+  type Cookie,
+  downloadHttpRequest,
+  type Request as HttpRequest,
+  streamHttpRequest,
+  type MultipartPart,
+} from "@/bindings/knurl"
 import { useApplication } from "@/state/application"
 import { generateUniqueId } from "@/lib/utils"
 import type { RequestContext, RequestEngine } from "@/request/pipeline"
@@ -8,6 +14,23 @@ function escapeRegExp(string: string): string {
   return string.replace(/[.*+?^${}()|[\\]/g, "\\$&")
 }
 
+/** Removes the params named in `names` from the URL's query string, leaving the rest of it as written */
+function withoutQueryParams(url: string, names: Set<string>): string {
+  const match = /^([^?#]*)(?:\?([^#]*))?(#.*)?$/.exec(url)
+  if (!match?.[2]) {
+    return url
+  }
+  const kept = match[2].split("&").filter((pair) => {
+    const name = pair.split("=")[0]
+    try {
+      return !names.has(decodeURIComponent(name.replace(/\+/g, " ")))
+    } catch {
+      return true
+    }
+  })
+  return `${match[1]}${kept.length > 0 ? `?${kept.join("&")}` : ""}${match[3] ?? ""}`
+}
+
 export const HttpEngine: RequestEngine = {
   async execute(context: RequestContext): Promise<ResponseState> {
     const { request, authResult } = context
@@ -24,26 +47,31 @@ export const HttpEngine: RequestEngine = {
       }
     }
 
-    const url = new URL(urlString)
-    if (request.queryParams) {
-      for (const param of Object.values(request.queryParams)) {
-        if (param.enabled) {
-          url.searchParams.append(param.name, param.value)
-        }
-      }
-    }
+    // The backend appends the params after substituting their {{variables}}, then percent-encodes them
+    let queryParams: Array<[string, string]> = Object.values(request.queryParams ?? {})
+      .filter((param) => param.enabled)
+      .map((param) => [param.name, param.value] as [string, string])
     if (authResult?.query) {
+      // Policy: auth last-wins for duplicate keys
+      const authKeys = new Set(Object.keys(authResult.query))
+      urlString = withoutQueryParams(urlString, authKeys)
+      queryParams = queryParams.filter(([name]) => !authKeys.has(name))
       for (const [key, value] of Object.entries(authResult.query)) {
-        // Policy: auth last-wins for duplicate keys
-        url.searchParams.set(key, value as string)
+        queryParams.push([key, value as string])
       }
     }
 
     // --- 2. Construct Final Headers ---
     const headers: Record<string, string> = {}
+    // Later headers with an already used name are sent as well, in addition to the first
+    const repeatedHeaders: Array<[string, string]> = []
     for (const header of Object.values(request.headers ?? {})) {
       if (header.enabled) {
-        headers[header.name] = header.value
+        if (Object.hasOwn(headers, header.name)) {
+          repeatedHeaders.push([header.name, header.value])
+        } else {
+          headers[header.name] = header.value
+        }
       }
     }
     // Merge cookieParams into Cookie header (before auth cookies)
@@ -213,11 +241,20 @@ export const HttpEngine: RequestEngine = {
       }
     })()
 
-    const response = await sendHttpRequest({
+    const connectionPool = (() => {
+      try {
+        return useApplication.getState().settingsState.requests.connectionPool
+      } catch {
+        return undefined
+      }
+    })()
+
+    const httpRequest: HttpRequest = {
       requestId: context.correlationId ?? generateUniqueId(),
-      url: url.toString(),
+      url: urlString,
+      queryParams: queryParams.length > 0 ? queryParams : undefined,
       method: request.method,
-      headers: headers,
+      headers: [...Object.entries(headers), ...repeatedHeaders],
       body:
         request.method !== "GET" && request.method !== "HEAD"
           ? (bodyBytes ?? (bodyText != null ? new TextEncoder().encode(bodyText) : undefined))
@@ -225,8 +262,20 @@ export const HttpEngine: RequestEngine = {
       bodyFilePath: request.body?.type === "binary" ? request.body.binaryPath : undefined,
       multipartParts,
       ...request.options,
+      connectionPool: request.options?.connectionPool ?? connectionPool,
+      connectionGroup: context.connectionGroup,
+      ntlm: authResult?.ntlm,
+      challengeAuth: authResult?.challenge,
+      signature: request.options?.signature?.enabled ? request.options.signature : undefined,
       previewMaxBytes,
-    })
+      variables: context.variables,
+    }
+    const response = request.options?.saveResponseToFile
+      ? await downloadHttpRequest(httpRequest, {
+          title: "Save Response",
+          defaultPath: urlString.split(/[?#]/)[0].split("/").pop() || "response",
+        })
+      : await streamHttpRequest(httpRequest)
 
     // --- 5. Parse Response ---
     let responseBody: string | undefined
@@ -271,27 +320,46 @@ export const HttpEngine: RequestEngine = {
         return response.timestamp
       }
     })()
-    const sanitizedCookies = (response.cookies ?? []).map((cookie) => {
-      if (!cookie.expires) {
-        return { ...cookie, expires: undefined }
-      }
-      try {
-        // Unconditionally parse and convert to ISO string
-        return { ...cookie, expires: new Date(cookie.expires).toISOString() }
-      } catch (_e) {
-        // If parsing fails for any reason, treat it as undefined
-        return { ...cookie, expires: undefined }
-      }
-    })
+    const sanitizeCookies = (cookies: Cookie[] | undefined) =>
+      (cookies ?? []).map((cookie) => {
+        if (!cookie.expires) {
+          return { ...cookie, expires: undefined }
+        }
+        try {
+          // Unconditionally parse and convert to ISO string
+          return { ...cookie, expires: new Date(cookie.expires).toISOString() }
+        } catch (_e) {
+          // If parsing fails for any reason, treat it as undefined
+          return { ...cookie, expires: undefined }
+        }
+      })
+    const sanitizedCookies = sanitizeCookies(response.cookies)
 
     const httpResponseData: HttpResponseData = zHttpResponseData.parse({
       status: response.status,
       statusText: response.statusText,
       headers: Object.fromEntries(response.headers ?? []),
+      headerList: response.headers,
+      binaryHeaders: response.binaryHeaders,
       cookies: sanitizedCookies,
       body: responseBody,
       bodyBase64: responseBodyBase64,
-      filePath: (response as unknown as { filePath?: string }).filePath,
+      filePath: response.filePath,
+      fileCompressed: response.fileCompressed,
+      truncated: response.truncated,
+      cancelled: response.cancelled,
+      savedTo: response.savedTo,
+      resumedFrom: response.resumedFrom,
+      contentRange: response.contentRange,
+      cache: response.cache,
+      timings: response.timings,
+      connection: response.connection,
+      redirectChain: response.redirectChain?.map((hop) => ({ ...hop, cookies: sanitizeCookies(hop.cookies) })),
+      rateLimit: response.rateLimit,
+      retries: response.retries,
+      logSummary: response.logSummary,
+      multistatus: response.multistatus,
+      assertions: response.assertions,
     })
 
     return zResponseState.parse({
@@ -0,0 +1,91 @@
+import { beforeEach, describe, expect, it, vi } from "vitest"
+import { monitoredRequests, runMonitorCheck } from "@/request/monitors"
+import { type RequestContext, runPipeline } from "@/request/pipeline"
+import { useApplication } from "@/state/application"
+import type { RequestState, ResponseState } from "@/types"
+
+vi.mock("@/request/pipeline", async (importOriginal) => {
+  const actual = await importOriginal()
+  return {
+    ...actual,
+    runPipeline: vi.fn(),
+  }
+})
+
+const request = (id: string, url: string, monitorIntervalSecs?: number) =>
+  ({
+    id,
+    name: `Request ${id}`,
+    collectionId: "col-1",
+    method: "GET",
+    url,
+    headers: {},
+    queryParams: {},
+    pathParams: {},
+    body: { type: "none" },
+    authentication: { type: "none" },
+    options: { monitorIntervalSecs, saveResponseToFile: true },
+    patch: { url: "https://example.com/unsaved" },
+  }) as unknown as RequestState
+
+describe("monitors", () => {
+  beforeEach(() => {
+    vi.clearAllMocks()
+    useApplication.setState((s) => {
+      s.collectionsState.cache["col-1"] = {
+        id: "col-1",
+        name: "C1",
+        updated: new Date().toISOString(),
+        encryption: { algorithm: "aes-gcm" },
+        activeEnvironmentId: "env-1",
+        environments: { "env-1": { id: "env-1", name: "Prod", variables: {} } },
+        requests: {
+          health: request("health", "https://example.com/health", 30),
+          unmonitored: request("unmonitored", "https://example.com/other"),
+          socket: request("socket", "wss://example.com/feed", 30),
+        },
+      } as any
+    })
+  })
+
+  it("monitors saved HTTP requests that set an interval", () => {
+    expect(Object.keys(monitoredRequests(useApplication.getState()))).toEqual(["health"])
+  })
+
+  it("sends the saved request with the active environment and reports error statuses as failures", async () => {
+    vi.mocked(runPipeline).mockImplementation(async (_phases, _context, notifier) => {
+      notifier.onSuccess({
+        responseTime: 42.4,
+        data: { type: "http", data: { status: 503 } },
+      } as unknown as ResponseState)
+    })
+
+    const check = await runMonitorCheck(request("health", "https://example.com/health", 30))
+
+    expect(check).toEqual({
+      monitorId: "health",
+      name: "Request health",
+      url: "https://example.com/health",
+      status: 503,
+      success: false,
+      latencyMs: 42,
+    })
+    const context = vi.mocked(runPipeline).mock.calls[0][1] as RequestContext
+    expect(context.environment?.id).toBe("env-1")
+    expect(context.request.url).toBe("https://example.com/health")
+    expect(context.request.patch).toEqual({})
+    expect(context.request.options?.saveResponseToFile).toBe(false)
+  })
+
+  it("fails the check when the send does", async () => {
+    vi.mocked(runPipeline).mockImplementation(async (_phases, _context, notifier) => {
+      notifier.onError(new Error("connection refused"))
+    })
+    vi.spyOn(console, "warn").mockImplementation(() => {})
+
+    const check = await runMonitorCheck(request("health", "https://example.com/health", 30))
+
+    expect(check.success).toBe(false)
+    expect(check.status).toBeUndefined()
+  })
+})
@@ -0,0 +1,169 @@
+import {
+  type MonitorCheck,
+  recordMonitorCheck,
+  removeMonitorMetrics,
+  startMonitorMetricsEndpoint,
+  stopMonitorMetricsEndpoint,
+  writeMonitorMetricsFile,
+} from "@/bindings/knurl"
+import { generateUniqueId } from "@/lib/utils"
+import {
+  createAuthPhase,
+  createIdentityPhase,
+  protocolDispatchPhase,
+  type RequestContext,
+  runPipeline,
+  variablesPhase,
+} from "@/request/pipeline"
+import { useApplication } from "@/state/application"
+import type { ApplicationState, RequestState, ResponseState } from "@/types"
+
+/** Shortest interval a request is monitored at, however short its own */
+export const MinMonitorIntervalSecs = 10
+
+/**
+ * The saved requests of the loaded collections that set a monitor interval, by request ID. WebSocket requests hold
+ * their connection open, so they aren't monitored.
+ */
+export function monitoredRequests(state: ApplicationState): Record<string, RequestState> {
+  const monitored: Record<string, RequestState> = {}
+  for (const collection of Object.values(state.collectionsState.cache)) {
+    for (const request of Object.values(collection.requests)) {
+      if (request.options?.monitorIntervalSecs && !/^wss?:/i.test(request.url)) {
+        monitored[request.id] = request
+      }
+    }
+  }
+  return monitored
+}
+
+/**
+ * Sends the saved `request` the way a tab would, with its collection's active environment, and reports the result.
+ * The check fails when the send does or the response has an error status.
+ */
+export async function runMonitorCheck(request: RequestState): Promise<MonitorCheck> {
+  const { getState, setState } = useApplication
+  const collection = getState().collectionsState.cache[request.collectionId]
+  const environmentId = collection?.activeEnvironmentId
+  const context: RequestContext = {
+    // A background send never asks where to save its response
+    request: structuredClone({ ...request, patch: {}, options: { ...request.options, saveResponseToFile: false } }),
+    environment: environmentId ? collection?.environments[environmentId] : undefined,
+    response: {},
+    correlationId: generateUniqueId(),
+  }
+
+  const started = Date.now()
+  const result: { response?: ResponseState } = {}
+  await runPipeline(
+    [createIdentityPhase(getState), variablesPhase, createAuthPhase(getState, setState), protocolDispatchPhase],
+    context,
+    {
+      onStart: () => {},
+      onSuccess: (response) => {
+        result.response = response
+      },
+      onError: (error) => console.warn(`Monitor check of "${request.name}" failed:`, error),
+      onLog: () => {},
+    },
+  )
+
+  const { response } = result
+  const status = response?.data?.type === "http" ? response.data.data.status : undefined
+  return {
+    monitorId: request.id,
+    name: request.name,
+    url: request.url,
+    status,
+    success: status !== undefined && status < 400,
+    latencyMs: Math.round(response?.responseTime ?? Date.now() - started),
+  }
+}
+
+// Starts and stops of the metrics endpoint, in order, so a restart never finds the old one still running
+let endpointChanges: Promise<unknown> = Promise.resolve()
+
+const changeEndpoint = (change: () => Promise<unknown>) => {
+  endpointChanges = endpointChanges.then(change).catch((error) => {
+    console.warn("Failed to change the monitor metrics endpoint:", error)
+  })
+}
+
+/**
+ * Checks each monitored request on its interval for as long as it stays monitored, and records the results for the
+ * metrics endpoint and file set in the request settings. Returns a function that stops the monitors.
+ */
+export function startMonitors(): () => void {
+  const timers = new Map<string, { intervalSecs: number; timer: ReturnType<typeof setInterval> }>()
+  // Requests being checked; a check that outlasts its interval isn't overlapped by the next
+  const checking = new Set<string>()
+
+  const check = async (requestId: string) => {
+    const request = monitoredRequests(useApplication.getState())[requestId]
+    if (!request || checking.has(requestId)) {
+      return
+    }
+    checking.add(requestId)
+    try {
+      await recordMonitorCheck(await runMonitorCheck(request))
+      const metricsFile = useApplication.getState().settingsState.requests.metricsFile
+      if (metricsFile) {
+        await writeMonitorMetricsFile(metricsFile)
+      }
+    } catch (error) {
+      console.warn("Failed to record a monitor check:", error)
+    } finally {
+      checking.delete(requestId)
+    }
+  }
+
+  const schedule = () => {
+    const monitored = monitoredRequests(useApplication.getState())
+    for (const [requestId, { intervalSecs, timer }] of timers) {
+      if (monitored[requestId]?.options?.monitorIntervalSecs !== intervalSecs) {
+        clearInterval(timer)
+        timers.delete(requestId)
+        if (!monitored[requestId]) {
+          removeMonitorMetrics(requestId).catch((error) => console.warn("Failed to remove monitor metrics:", error))
+        }
+      }
+    }
+    for (const [requestId, request] of Object.entries(monitored)) {
+      if (timers.has(requestId)) {
+        continue
+      }
+      const intervalSecs = request.options?.monitorIntervalSecs ?? MinMonitorIntervalSecs
+      const timer = setInterval(() => void check(requestId), Math.max(intervalSecs, MinMonitorIntervalSecs) * 1000)
+      timers.set(requestId, { intervalSecs, timer })
+      void check(requestId)
+    }
+  }
+
+  const unsubscribeRequests = useApplication.subscribe((state) => state.collectionsState.cache, schedule, {
+    fireImmediately: true,
+  })
+  const unsubscribeEndpoint = useApplication.subscribe(
+    (state) => state.settingsState.requests.metricsPort,
+    (port, previousPort) => {
+      if (previousPort !== undefined) {
+        changeEndpoint(stopMonitorMetricsEndpoint)
+      }
+      if (port !== undefined) {
+        changeEndpoint(() => startMonitorMetricsEndpoint(port))
+      }
+    },
+    { fireImmediately: true },
+  )
+
+  return () => {
+    unsubscribeRequests()
+    unsubscribeEndpoint()
+    for (const { timer } of timers.values()) {
+      clearInterval(timer)
+    }
+    timers.clear()
+    if (useApplication.getState().settingsState.requests.metricsPort !== undefined) {
+      changeEndpoint(stopMonitorMetricsEndpoint)
+    }
+  }
+}
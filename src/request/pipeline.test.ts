@@ -1,6 +1,6 @@
 import { describe, it, vi, expect, beforeEach } from "vitest"
 import {
-  resolveVariablesPhase,
+  variablesPhase,
   protocolDispatchPhase,
   type PipelineNotifier,
   type RequestContext,
@@ -15,8 +15,9 @@ vi.mock("@/bindings/knurl", async (importOriginal) => {
   return {
     ...actual,
     // Provide fakes used by these tests; tests will set return values per-case
-    sendHttpRequest: vi.fn(),
+    streamHttpRequest: vi.fn(),
     getAuthenticationResult: vi.fn(),
+    closeConnectionGroup: vi.fn(),
   }
 })
 vi.mock("@/request/ws/engine")
@@ -41,7 +42,7 @@ describe("Request Pipeline", () => {
   }
 
   it("should dispatch to HttpEngine for http URLs", async () => {
-    vi.mocked(knurl.sendHttpRequest).mockResolvedValue({
+    vi.mocked(knurl.streamHttpRequest).mockResolvedValue({
       requestId: "req-123",
       status: 200,
       statusText: "OK",
@@ -50,6 +51,16 @@ describe("Request Pipeline", () => {
       body: new ArrayBuffer(0),
       size: 0,
       duration: 10,
+      timings: {
+        dns: null,
+        tcpConnect: null,
+        tlsHandshake: null,
+        requestWrite: null,
+        timeToFirstByte: null,
+        download: null,
+        connectionReused: false,
+        total: 10,
+      },
       timestamp: new Date().toISOString(),
     })
 
@@ -64,7 +75,7 @@ describe("Request Pipeline", () => {
     const phases = [protocolDispatchPhase]
     await phases[0](initialContext) // Manually call phase
 
-    expect(knurl.sendHttpRequest).toHaveBeenCalledOnce()
+    expect(knurl.streamHttpRequest).toHaveBeenCalledOnce()
   })
 
   it("should dispatch to WebSocketEngine for ws URLs", async () => {
@@ -82,7 +93,7 @@ describe("Request Pipeline", () => {
     expect(WebSocketEngine.execute).toHaveBeenCalledOnce()
   })
 
-  it("should resolve environment variables", async () => {
+  it("should pass environment variables to the backend", async () => {
     const initialContext: RequestContext = {
       request: {
         ...baseRequest,
@@ -98,8 +109,9 @@ describe("Request Pipeline", () => {
       response: {},
     }
 
-    const finalContext = await resolveVariablesPhase(initialContext)
-    expect(finalContext.request.url).toBe("https://api.example.com/users")
+    const finalContext = await variablesPhase(initialContext)
+    expect(finalContext.request.url).toBe("{{baseUrl}}/users")
+    expect(finalContext.variables).toEqual({ baseUrl: "https://api.example.com" })
   })
 })
 
@@ -107,7 +119,7 @@ describe("Request Pipeline", () => {
 // Consolidated tests from: pipeline.resolveVariables.test.ts
 // ---------------------------------------------------------------------------
 import type { Environment } from "@/types"
-describe("resolveVariablesPhase", () => {
+describe("variablesPhase", () => {
   function makeCtx(partial: Partial<RequestState>, env?: Partial<Environment>): RequestContext {
     return {
       request: {
@@ -139,7 +151,7 @@ describe("resolveVariablesPhase", () => {
     }
   }
 
-  it("replaces placeholders across url, headers, query and path; repeated placeholders handled", async () => {
+  it("fills path and query param placeholders and leaves variables to the backend", async () => {
     const context = makeCtx({}, {
       variables: {
         host: { id: "v1", name: "host", value: "api.example.com", secure: false, enabled: true },
@@ -150,10 +162,35 @@ describe("resolveVariablesPhase", () => {
       },
     })
 
-    const out = await resolveVariablesPhase(context)
-    expect(out.request.url).toBe("https://api.example.com/users/42?q=hello&q2=hello")
+    const out = await variablesPhase(context)
+    expect(out.request.url).toBe("https://{{host}}/users/{{uid}}?q={{query}}&q2={{query}}")
     const headerValue = Object.values(out.request.headers!)[0].value
-    expect(headerValue).toBe("abc-{{ignored}}-123")
+    expect(headerValue).toBe("{{token}}")
+    expect(out.variables).toEqual({
+      host: "api.example.com",
+      uid: "42",
+      query: "hello",
+      token: "abc-{{ignored}}-123",
+      ignored: "REPLACED",
+    })
+  })
+
+  it("passes secure variables as references, never their values", async () => {
+    const context = makeCtx({}, {
+      variables: {
+        v1: { id: "v1", name: "host", value: "api.example.com", secure: false, enabled: true },
+        v4: { id: "v4", name: "token", value: "s3cret", secure: true, enabled: true },
+      },
+    })
+
+    const out = await variablesPhase(context)
+    expect(out.variables).toEqual({
+      host: "api.example.com",
+      token: {
+        secret: { source: "environment", collectionId: "col-1", environmentId: "env-1", variableId: "v4" },
+      },
+    })
+    expect(JSON.stringify([out.request, out.variables])).not.toContain("s3cret")
   })
 
   it("treats variables without explicit enabled flag as active", async () => {
@@ -168,20 +205,28 @@ describe("resolveVariablesPhase", () => {
       },
     )
 
-    const out = await resolveVariablesPhase(context)
-    expect(out.request.url).toBe("https://internal.example/status")
+    const out = await variablesPhase(context)
+    expect(out.variables).toEqual({ baseUrl: "https://internal.example" })
   })
 
-  it("leaves unknown placeholders intact", async () => {
+  it("lets variables take precedence over params of the same name", async () => {
     const ctx = makeCtx({}, {
       variables: {
         host: { id: "v1", name: "host", value: "api.example.com", secure: false, enabled: true },
       },
     })
 
-    const out = await resolveVariablesPhase(ctx)
-    expect(out.request.url).toContain("{{userId}}")
-    expect(out.request.url).toContain("{{q}}")
+    const out = await variablesPhase(ctx)
+    expect(out.request.url).toBe("https://{{host}}/users/{{uid}}?q={{query}}&q2={{query}}")
+
+    const shadowed = makeCtx({}, {
+      variables: {
+        q: { id: "v1", name: "q", value: "from-env", secure: false, enabled: true },
+      },
+    })
+    const shadowedOut = await variablesPhase(shadowed)
+    expect(shadowedOut.request.url).toContain("?q={{q}}&q2={{q}}")
+    expect(shadowedOut.variables).toEqual({ q: "from-env" })
   })
 })
 
@@ -224,7 +269,7 @@ describe("Request pipeline e2e smoke (mocked)", () => {
       onLog: vi.fn(),
     }
 
-    vi.mocked(knurl.sendHttpRequest).mockResolvedValue({
+    vi.mocked(knurl.streamHttpRequest).mockResolvedValue({
       requestId: "corr-p1",
       status: 200,
       statusText: "OK",
@@ -258,15 +303,16 @@ describe("Request pipeline e2e smoke (mocked)", () => {
       response: {},
     }
 
-    await runPipeline([resolveVariablesPhase, protocolDispatchPhase], initial, notifier)
+    await runPipeline([variablesPhase, protocolDispatchPhase], initial, notifier)
 
     expect(notifier.onStart).toHaveBeenCalledOnce()
     expect(notifier.onError).not.toHaveBeenCalled()
     expect(notifier.onSuccess).toHaveBeenCalledOnce()
     const resp = vi.mocked(notifier.onSuccess).mock.calls[0][0]
     expect(resp.data.type).toBe("http")
-    const call = vi.mocked(knurl.sendHttpRequest).mock.calls[0][0] as any
-    expect(call.url).toBe("https://example.com/ping")
+    const call = vi.mocked(knurl.streamHttpRequest).mock.calls[0][0] as any
+    expect(call.url).toBe("https://{{host}}/ping")
+    expect(call.variables).toEqual({ host: "example.com" })
   })
 
   it("surfaces backend errors via notifier.onError", async () => {
@@ -277,7 +323,7 @@ describe("Request pipeline e2e smoke (mocked)", () => {
       onLog: vi.fn(),
     }
 
-    vi.mocked(knurl.sendHttpRequest).mockRejectedValue(new Error("boom"))
+    vi.mocked(knurl.streamHttpRequest).mockRejectedValue(new Error("boom"))
 
     const initial: RequestContext = {
       request: {
@@ -307,7 +353,7 @@ describe("Request pipeline e2e smoke (mocked)", () => {
 // ---------------------------------------------------------------------------
 // Consolidated tests from: pipeline.inherit-cache.test.ts
 // ---------------------------------------------------------------------------
-import { createAuthPhase } from "@/request/pipeline"
+import { createAuthPhase, runChain } from "@/request/pipeline"
 import type { ApplicationState } from "@/types/application"
 import { getAuthenticationResult } from "@/bindings/knurl"
 describe("auth inherit cache + strategy", () => {
@@ -381,7 +427,10 @@ describe("auth inherit cache + strategy", () => {
     }
     await phase(ctx)
 
-    expect(getAuthenticationResult).toHaveBeenCalled()
+    // The request's own config goes to the backend with the collection's to inherit from
+    expect(getAuthenticationResult).toHaveBeenCalledWith({ type: "inherit" }, "auth-req", [
+      expect.objectContaining({ type: "oauth2", grantType: "client_credentials" }),
+    ])
     expect((state.credentialsCacheApi.get as any).mock.calls[0][0]).toBe("collection-auth-col-1")
   })
 
@@ -450,3 +499,68 @@ describe("auth inherit cache + strategy", () => {
     expect(ctx.authResult).toEqual(cached)
   })
 })
+
+describe("runChain", () => {
+  const step = (url: string): RequestContext => ({
+    request: { method: "GET", url, headers: {}, queryParams: {}, body: { type: "none" } } as RequestState,
+    response: {},
+  })
+  const respond = (reused: boolean) => ({
+    requestId: "step",
+    status: 200,
+    statusText: "OK",
+    headers: [],
+    cookies: [],
+    body: new TextEncoder().encode("{}"),
+    size: 2,
+    duration: 1,
+    connection: { localAddr: "127.0.0.1:50000", remoteAddr: "127.0.0.1:8080", reused },
+    timestamp: new Date().toISOString(),
+  })
+
+  beforeEach(() => {
+    vi.clearAllMocks()
+    vi.mocked(knurl.closeConnectionGroup).mockResolvedValue(true)
+  })
+
+  it("sends the steps over one connection group and closes it at the end", async () => {
+    vi.mocked(knurl.streamHttpRequest)
+      .mockResolvedValueOnce(respond(false) as any)
+      .mockResolvedValueOnce(respond(true) as any)
+
+    const responses = await runChain(
+      [protocolDispatchPhase],
+      [step("http://example.com/login"), (previous) => step(`http://example.com/after/${previous[0].requestId}`)],
+      { sameConnection: true },
+    )
+
+    const calls = vi.mocked(knurl.streamHttpRequest).mock.calls.map(([request]) => request)
+    expect(calls.map((request) => request.url)).toEqual(["http://example.com/login", "http://example.com/after/step"])
+    const group = calls[0].connectionGroup
+    expect(group).toMatch(/^chain-/)
+    expect(calls[1].connectionGroup).toBe(group)
+    expect(responses.map((response) => (response.data as any).data.connection.reused)).toEqual([false, true])
+    expect(knurl.closeConnectionGroup).toHaveBeenCalledOnce()
+    expect(knurl.closeConnectionGroup).toHaveBeenCalledWith(group)
+  })
+
+  it("closes the connection group when a step fails", async () => {
+    vi.mocked(knurl.streamHttpRequest).mockRejectedValueOnce(new Error("refused"))
+
+    await expect(
+      runChain([protocolDispatchPhase], [step("http://example.com/a"), step("http://example.com/b")], {
+        sameConnection: true,
+      }),
+    ).rejects.toThrow("refused")
+    expect(knurl.streamHttpRequest).toHaveBeenCalledOnce()
+    expect(knurl.closeConnectionGroup).toHaveBeenCalledOnce()
+  })
+
+  it("sends steps on connections of their own by default", async () => {
+    vi.mocked(knurl.streamHttpRequest).mockResolvedValue(respond(false) as any)
+
+    await runChain([protocolDispatchPhase], [step("http://example.com/a")])
+    expect(vi.mocked(knurl.streamHttpRequest).mock.calls[0][0].connectionGroup).toBeUndefined()
+    expect(knurl.closeConnectionGroup).not.toHaveBeenCalled()
+  })
+})
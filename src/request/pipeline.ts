@@ -1,5 +1,12 @@
-import { getAuthenticationResult, type AuthConfig as BindingAuthConfig } from "@/bindings/knurl"
-import { resolveRequestVariables } from "@/lib/environments"
+import {
+  closeConnectionGroup,
+  getAuthenticationResult,
+  type AuthConfig as BindingAuthConfig,
+  type VariableValue,
+} from "@/bindings/knurl"
+import { applyParamPlaceholders, requestVariables, substituteVariables } from "@/lib/environments"
+import { applyIdentityProfile } from "@/lib/identity"
+import { generateUniqueId } from "@/lib/utils"
 import { HttpEngine } from "@/request/http/engine"
 import { WebSocketEngine } from "@/request/ws/engine"
 import type { ApplicationState, AuthResult, Environment, LogEntry, RequestState, ResponseState } from "@/types"
@@ -11,10 +18,14 @@ import type { StoreApi } from "zustand"
 export type RequestContext = {
   request: RequestState
   environment?: Environment
+  /** Values of the request's `{{variables}}`, which the backend substitutes; secure ones are references */
+  variables?: Record<string, VariableValue>
   authResult?: AuthResult
   response: Partial<ResponseState>
   /** Unique correlation id for this pipeline run; used for log filtering and backend requestId */
   correlationId?: string
+  /** Connection group shared by the steps of a chain, so they're sent over one connection */
+  connectionGroup?: string
 }
 
 /**
@@ -41,8 +52,31 @@ export interface PipelineNotifier {
 
 // --- CORE PHASES ---
 
-export const resolveVariablesPhase: RequestPhase = async (context) => {
-  context.request = resolveRequestVariables(context.request, context.environment)
+/**
+ * Applies the collection identity profile the request references. Runs before the variables
+ * phase so profile values may use {{variables}}.
+ */
+export const createIdentityPhase = (get: StoreApi<ApplicationState>["getState"]): RequestPhase => {
+  return async (context) => {
+    const profileId = context.request.options?.identityProfileId
+    if (!profileId) {
+      return context
+    }
+    const profile = get().collectionsState.cache[context.request.collectionId]?.identityProfiles?.[profileId]
+    if (profile) {
+      context.request = applyIdentityProfile(context.request, profile)
+    }
+    return context
+  }
+}
+
+/**
+ * Collects the environment's variables for the backend to substitute just before sending, leaving the request's
+ * `{{variables}}` in place so secure values are only ever read there.
+ */
+export const variablesPhase: RequestPhase = async (context) => {
+  context.request = applyParamPlaceholders(context.request, context.environment)
+  context.variables = requestVariables(context.environment, context.request.collectionId)
   return context
 }
 
@@ -58,8 +92,6 @@ export const createAuthPhase = (
       throw new Error(`Collection not found for request: ${request.collectionId}`)
     }
 
-    const effectiveAuth = request.authentication.type === "inherit" ? collection.authentication : request.authentication
-
     const toBindingAuth = (auth: typeof request.authentication): BindingAuthConfig => {
       switch (auth.type) {
         case "none":
@@ -85,6 +117,16 @@ export const createAuthPhase = (
             value: auth.apiKey?.value,
             placement: auth.apiKey?.placement,
           }
+        case "ntlm":
+          return {
+            type: "ntlm",
+            username: auth.ntlm?.username,
+            password: auth.ntlm?.password,
+            domain: auth.ntlm?.domain,
+            workstation: auth.ntlm?.workstation,
+          }
+        case "digest":
+          return { type: "digest", username: auth.digest?.username, password: auth.digest?.password }
         case "oauth2": {
           const g = auth.oauth2?.grantType ?? "client_credentials"
           const cAuth = auth.oauth2?.clientAuth ?? "body"
@@ -93,6 +135,7 @@ export const createAuthPhase = (
             type: "oauth2",
             grantType: g,
             authUrl: auth.oauth2?.authUrl,
+            deviceAuthorizationUrl: auth.oauth2?.deviceAuthorizationUrl,
             tokenUrl: auth.oauth2?.tokenUrl,
             clientId: auth.oauth2?.clientId,
             clientSecret: auth.oauth2?.clientSecret,
@@ -100,12 +143,27 @@ export const createAuthPhase = (
             refreshToken: auth.oauth2?.refreshToken,
             tokenCaching: caching,
             clientAuth: cAuth,
+            assertionKeyName: auth.oauth2?.assertionKeyName,
+            assertionAlgorithm: auth.oauth2?.assertionAlgorithm,
+            assertionKeyId: auth.oauth2?.assertionKeyId,
             tokenExtraParams: auth.oauth2?.tokenExtraParams,
+            redirectUri: auth.oauth2?.redirectUri,
+            usePkce: auth.oauth2?.usePkce,
+            authorizationWindow: auth.oauth2?.authorizationWindow,
+            validateIdToken: auth.oauth2?.validateIdToken,
+            issuer: auth.oauth2?.issuer,
+            jwksUrl: auth.oauth2?.jwksUrl,
+            revocationUrl: auth.oauth2?.revocationUrl,
           }
         }
       }
     }
 
+    // The backend resolves inherited auth; the session cache only needs to know whose token it holds
+    const effectiveAuth = request.authentication.type === "inherit" ? collection.authentication : request.authentication
+    const bindingAuth = toBindingAuth(request.authentication)
+    const parents = [toBindingAuth(collection.authentication)]
+
     if (effectiveAuth && effectiveAuth.type !== "none" && effectiveAuth.type !== "inherit") {
       const { credentialsCacheApi } = get()
       const cacheKey =
@@ -118,12 +176,12 @@ export const createAuthPhase = (
       let authResult: AuthResult | undefined
       if (caching === "never") {
         // Always refresh: fetch a new token and also update the cache
-        authResult = await getAuthenticationResult(toBindingAuth(effectiveAuth), "auth-req")
+        authResult = await getAuthenticationResult(bindingAuth, "auth-req", parents)
         await credentialsCacheApi.set(cacheKey, authResult)
       } else {
         authResult = await credentialsCacheApi.get(cacheKey)
         if (!authResult) {
-          authResult = await getAuthenticationResult(toBindingAuth(effectiveAuth), "auth-req")
+          authResult = await getAuthenticationResult(bindingAuth, "auth-req", parents)
           await credentialsCacheApi.set(cacheKey, authResult)
         }
       }
@@ -142,8 +200,15 @@ const engineRegistry: Record<string, RequestEngine> = {
 }
 
 export const protocolDispatchPhase: RequestPhase = async (context) => {
-  const url = new URL(context.request.url)
-  const protocol = url.protocol.replace(":", "")
+  // Only plain variables are filled in, enough to tell the scheme of e.g. `{{baseUrl}}/users`
+  const plainVariables = Object.fromEntries(
+    Object.entries(context.variables ?? {}).filter((entry): entry is [string, string] => typeof entry[1] === "string"),
+  )
+  const url = substituteVariables(context.request.url, plainVariables)
+  const protocol = /^([a-z][a-z0-9+.-]*):/i.exec(url)?.[1].toLowerCase()
+  if (!protocol) {
+    throw new Error(`Invalid URL: ${context.request.url}`)
+  }
   const engine = engineRegistry[protocol]
 
   if (!engine) {
@@ -173,3 +238,41 @@ export const runPipeline = async (
     notifier.onError(error as Error)
   }
 }
+
+/**
+ * A step of a chain: its initial context, or a function building it from the responses of the
+ * steps before it.
+ */
+export type ChainStep = RequestContext | ((responses: ResponseState[]) => RequestContext | Promise<RequestContext>)
+
+/**
+ * Runs dependent requests one after another through `phases` and returns their responses. With
+ * `sameConnection`, the steps share one keep-alive connection, e.g. to check a load balancer's
+ * session affinity, and each response's `connection.reused` tells whether its step got it. The
+ * connection is closed once the chain ends, whether or not a step failed.
+ */
+export const runChain = async (
+  phases: RequestPhase[],
+  steps: ChainStep[],
+  options: { sameConnection?: boolean } = {},
+): Promise<ResponseState[]> => {
+  const connectionGroup = options.sameConnection ? `chain-${generateUniqueId()}` : undefined
+  const responses: ResponseState[] = []
+  try {
+    for (const step of steps) {
+      let context = typeof step === "function" ? await step(responses) : step
+      context = { ...context, connectionGroup }
+      for (const phase of phases) {
+        context = await phase(context)
+      }
+      responses.push(context.response as ResponseState)
+    }
+  } finally {
+    if (connectionGroup) {
+      await closeConnectionGroup(connectionGroup).catch((e) => {
+        console.warn(`Failed to close connection group ${connectionGroup}`, e)
+      })
+    }
+  }
+  return responses
+}
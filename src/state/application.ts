@@ -24,6 +24,7 @@ import {
   type Environment,
   type FormField,
   type HttpMethod,
+  type IdentityProfile,
   isRequestDirty,
   type RequestHeader,
   type RequestPathParam,
@@ -689,11 +690,15 @@ export const useRequestBody = (tabId: string): HookResult<RequestBodyState, Requ
   }
 }
 
+// Stable fallback so the selector doesn't return a new object each render
+const EmptyIdentityProfiles: Record<string, IdentityProfile> = {}
+
 type RequestOptionsState = {
   options: RequestState["patch"]["options"]
   original: RequestState["patch"]["options"]
   autoSave: RequestState["autoSave"]
   originalAutoSave: RequestState["autoSave"]
+  identityProfiles: Record<string, IdentityProfile>
 }
 
 type RequestOptionsActions = {
@@ -707,6 +712,9 @@ export const useRequestOptions = (tabId: string): HookResult<RequestOptionsState
   const {
     state: { request, original, activeTab },
   } = result
+  const identityProfiles = useApplication(
+    (app) => app.collectionsState.cache[activeTab.collectionId]?.identityProfiles ?? EmptyIdentityProfiles,
+  )
 
   return {
     state: {
@@ -714,6 +722,7 @@ export const useRequestOptions = (tabId: string): HookResult<RequestOptionsState
       original: original.options,
       autoSave: request.autoSave,
       originalAutoSave: original.autoSave,
+      identityProfiles,
     },
     actions: {
       updateClientOption: (updates: Partial<ClientOptionsData>) =>
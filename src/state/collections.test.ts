@@ -1,8 +1,9 @@
 // Consolidated test suite for src/state/collections.ts
 import { beforeEach, describe, expect, it, vi } from "vitest"
+import { mockIPC } from "@tauri-apps/api/mocks"
 import { useApplication } from "@/state/application"
 import { RootCollectionFolderId, ScratchCollectionId } from "@/types"
-import { saveScratchRequest } from "@/state/collections"
+import { applyMergePatch, diffMergePatch, saveScratchRequest } from "@/state/collections"
 
 // ------- from collections.crud.test.ts -------
 describe("collections CRUD", () => {
@@ -554,6 +555,59 @@ describe("Collections import/migration", () => {
     expect(rootRequest).toBeDefined()
     expect(rootRequest?.folderId).toBe(RootCollectionFolderId)
   })
+
+  it("snapshots changed environments when their collection is saved", async () => {
+    const snapshots: any[] = []
+    mockIPC((cmd, payload: any) => {
+      if (cmd === "snapshot_environment") {
+        snapshots.push(payload)
+        return null
+      }
+    })
+    const { collectionsApi } = store.getState()
+    const col = await collectionsApi.addCollection("Snapshots")
+    const env = await collectionsApi.createEnvironment(col.id, "Staging")
+    const sent = () => snapshots.filter((snapshot) => snapshot.collectionId === col.id)
+
+    await store.saveAll()
+    expect(sent()).toEqual([{ collectionId: col.id, environment: expect.objectContaining({ name: "Staging" }) }])
+
+    // Unchanged environments aren't sent again
+    await store.saveAll()
+    expect(sent()).toHaveLength(1)
+
+    await collectionsApi.updateEnvironment(col.id, env.id, { name: "Staging 2" })
+    await store.saveAll()
+    expect(sent()).toHaveLength(2)
+    expect(sent()[1].environment).toMatchObject({ id: env.id, name: "Staging 2" })
+  })
+
+  it("stages edited secure variable values with the backend", async () => {
+    const staged: any[] = []
+    mockIPC((cmd, payload: any) => {
+      if (cmd === "stage_secure_variable") {
+        staged.push(payload)
+      }
+      return null
+    })
+    const { collectionsApi } = store.getState()
+    const col = await collectionsApi.addCollection("Secrets")
+    const env = await collectionsApi.createEnvironment(col.id, "Prod")
+    await collectionsApi.addEnvironmentVariable(col.id, env.id, { name: "host", value: "api.example.com" })
+    await collectionsApi.addEnvironmentVariable(col.id, env.id, { name: "token", value: "s3cret", secure: true })
+    const variables = store.getState().collectionsState.cache[col.id]!.environments[env.id]!.variables
+    const token = Object.values(variables).find((variable) => variable.name === "token")!
+
+    await collectionsApi.updateEnvironmentVariable(col.id, env.id, token.id, { value: "unsaved" })
+    await collectionsApi.updateEnvironmentVariable(col.id, env.id, token.id, { secure: false })
+
+    const forToken = { collectionId: col.id, environmentId: env.id, variableId: token.id }
+    expect(staged).toEqual([
+      { ...forToken, value: "s3cret" },
+      { ...forToken, value: "unsaved" },
+      { ...forToken, value: null },
+    ])
+  })
 })
 
 // ------- focused regression test: empty patch stays empty on load -------
@@ -761,6 +815,86 @@ describe("collections and requests ordering", () => {
     expect(afterDelete.requestIndex[request.id]).toBeUndefined()
   })
 
+  it("diffs requests into merge patches that reproduce them", () => {
+    const before = { name: "A", headers: { h1: { name: "X", value: "1" } }, description: "gone", tags: ["a"] }
+    const after = { name: "A", headers: { h1: { name: "X", value: "2" }, h2: { name: "Y" } }, tags: ["a", "b"] }
+    const patch = diffMergePatch(before, after)
+    expect(patch).toEqual({ headers: { h1: { value: "2" }, h2: { name: "Y" } }, description: null, tags: ["a", "b"] })
+    expect(applyMergePatch(before, patch)).toEqual(after)
+    expect(diffMergePatch(after, after)).toEqual({})
+  })
+
+  it("journals request edits and replays them at startup", async () => {
+    vi.useFakeTimers()
+    try {
+      const appended: any[] = []
+      let journal: any[] = []
+      mockIPC((cmd, payload: any) => {
+        if (cmd === "append_journal") {
+          appended.push(...payload.edits)
+        } else if (cmd === "recover_journal") {
+          return journal
+        }
+      })
+      const { collectionsApi } = useApplication.getState()
+      const col = await collectionsApi.addCollection("Journal")
+      const request = await collectionsApi.createRequest(col.id, { name: "Draft", url: "https://a.example" })
+
+      await collectionsApi.updateRequestPatch(col.id, request.id, { url: "https://b.example" })
+      await collectionsApi.updateRequestPatch(col.id, request.id, { url: "https://c.example" })
+      expect(appended).toHaveLength(0)
+      await vi.advanceTimersByTimeAsync(1000)
+      // Debounced edits are folded into one record
+      expect(appended).toEqual([
+        {
+          targetId: request.id,
+          collectionId: col.id,
+          patch: expect.objectContaining({ patch: { url: "https://c.example" } }),
+        },
+      ])
+
+      journal = [{ targetId: request.id, collectionId: col.id, changes: { name: "Recovered" }, updated: "", edits: 1 }]
+      expect(await collectionsApi.recoverUnsavedEdits()).toBe(1)
+      const recovered = useApplication.getState().collectionsState.cache[col.id]!.requests[request.id]!
+      expect(recovered.name).toBe("Recovered")
+      expect(recovered.patch).toEqual({ url: "https://c.example" })
+    } finally {
+      vi.useRealTimers()
+    }
+  })
+
+  it("moves deleted requests to the trash and restores them", async () => {
+    const trashed: any[] = []
+    mockIPC((cmd, payload) => {
+      if (cmd === "trash_app_item") {
+        trashed.push(payload)
+        return { id: "t1", kind: "item", deletedAt: new Date().toISOString() }
+      }
+    })
+    const { collectionsApi } = useApplication.getState()
+    const col = await collectionsApi.addCollection("Trash")
+    const folder = await collectionsApi.createFolder(col.id, RootCollectionFolderId, "Folder")
+    const request = await collectionsApi.createRequest(col.id, { name: "Trashed", folderId: folder.id })
+
+    await collectionsApi.deleteRequest(col.id, request.id)
+    expect(trashed).toHaveLength(1)
+    expect(trashed[0]).toMatchObject({ label: "Trashed", parentId: col.id, data: { id: request.id } })
+
+    const restored = await collectionsApi.restoreRequest(col.id, trashed[0].data)
+    expect(restored.id).toBe(request.id)
+    expect(restored.folderId).toBe(folder.id)
+    expect(useApplication.getState().collectionsState.cache[col.id]!.requestIndex[request.id]).toBeDefined()
+
+    // Restoring again while the first copy exists gives the copy a new id; a deleted folder
+    // sends it to the root
+    await collectionsApi.deleteFolder(col.id, folder.id)
+    expect(trashed).toHaveLength(2)
+    const again = await collectionsApi.restoreRequest(col.id, trashed[0].data)
+    expect(again.folderId).toBe(RootCollectionFolderId)
+    const copy = await collectionsApi.restoreRequest(col.id, trashed[0].data)
+    expect(copy.id).not.toBe(request.id)
+  })
+
   it("rebuilds request index when saving a scratch request", async () => {
     const { collectionsApi } = useApplication.getState()
     const target = await collectionsApi.addCollection("Scratch Target")
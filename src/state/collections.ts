@@ -1,8 +1,17 @@
-import { isEqual, merge, mergeWith, toMerged } from "es-toolkit"
+import { debounce, isEqual, isPlainObject, merge, mergeWith, toMerged } from "es-toolkit"
 import { current, produceWithPatches } from "immer"
 import type { StateCreator } from "zustand"
 
-import { isAppError } from "@/bindings/knurl"
+import {
+  appendJournal,
+  checkpointJournal,
+  isAppError,
+  type JournalEdit,
+  recoverJournal,
+  snapshotEnvironment,
+  stageSecureVariable,
+  trashAppItem,
+} from "@/bindings/knurl"
 import { assert, generateUniqueId, isNotEmpty, nonNull } from "@/lib/utils"
 import { createStorage, type MigrateContext } from "@/state/middleware/storage"
 import { invalidateCollectionPromise } from "@/state/application"
@@ -23,6 +32,7 @@ import {
   type EnvironmentVariable,
   type ExportedCollection,
   type FormField,
+  type IdentityProfile,
   type RequestBodyData,
   type RequestHeader,
   type RequestCookieParam,
@@ -34,6 +44,7 @@ import {
   zEnvironment,
   zEnvironmentVariable,
   zFormField,
+  zIdentityProfile,
   zRequestHeader,
   zRequestCookieParam,
   zRequestPathParam,
@@ -535,26 +546,63 @@ const removeRequestFromFolder = (
   return { folder, request }
 }
 
-// sanitizeCollection - Create a persisted/export-friendly version of a collection by
-// removing runtime-only secrets and applying other future sanitization rules (e.g.,
-// trimming volatile fields, normalizing defaults, etc.).
-export const sanitizeCollection = (collection: CollectionCacheState): CollectionState => {
-  const sanitizeRequest = (request: RequestState): RequestState => {
-    const sanitized: RequestState = {
-      ...request,
-      authentication: redactAuth(request.authentication) ?? request.authentication,
+// diffMergePatch - A JSON merge patch (RFC 7386) that turns `before` into `after`. As merge
+// patches can't tell them apart, a member set to null is treated like a removed one.
+export const diffMergePatch = (before: unknown, after: unknown): unknown => {
+  if (!isPlainObject(before) || !isPlainObject(after)) {
+    return after ?? null
+  }
+  const patch: Record<string, unknown> = {}
+  for (const [key, value] of Object.entries(before)) {
+    if (value != null && after[key] == null) {
+      patch[key] = null
     }
-    if (request.patch?.authentication) {
-      sanitized.patch = {
-        ...request.patch,
-        authentication: redactAuth(
-          request.patch.authentication as unknown as AuthConfig,
-        ) as unknown as RequestState["authentication"],
-      }
+  }
+  for (const [key, value] of Object.entries(after)) {
+    if (value != null && !isEqual(before[key], value)) {
+      patch[key] = diffMergePatch(before[key], value)
+    }
+  }
+  return patch
+}
+
+// applyMergePatch - Applies a JSON merge patch (RFC 7386) to a copy of `target`
+export const applyMergePatch = (target: unknown, patch: unknown): unknown => {
+  if (!isPlainObject(patch)) {
+    return patch
+  }
+  const result: Record<string, unknown> = isPlainObject(target) ? { ...target } : {}
+  for (const [key, value] of Object.entries(patch)) {
+    if (value === null) {
+      delete result[key]
+    } else {
+      result[key] = applyMergePatch(result[key], value)
+    }
+  }
+  return result
+}
+
+// sanitizeRequest - The persisted version of a request, without runtime-only secrets
+const sanitizeRequest = (request: RequestState): RequestState => {
+  const sanitized: RequestState = {
+    ...request,
+    authentication: redactAuth(request.authentication) ?? request.authentication,
+  }
+  if (request.patch?.authentication) {
+    sanitized.patch = {
+      ...request.patch,
+      authentication: redactAuth(
+        request.patch.authentication as unknown as AuthConfig,
+      ) as unknown as RequestState["authentication"],
     }
-    return sanitized
   }
+  return sanitized
+}
 
+// sanitizeCollection - Create a persisted/export-friendly version of a collection by
+// removing runtime-only secrets and applying other future sanitization rules (e.g.,
+// trimming volatile fields, normalizing defaults, etc.).
+export const sanitizeCollection = (collection: CollectionCacheState): CollectionState => {
   const { requestIndex: _requestIndex, ...rest } = collection
 
   const sanitized: CollectionState = {
@@ -632,6 +680,16 @@ const CollectionStorage = createStorage<CollectionState>({
 
 const CollectionFileName = (id: string) => `collections/${id}.json`
 
+// Moves deleted requests to the trash so they can be restored. Fire & Forget: the
+// deletion itself has already happened in the store.
+const trashRequests = (collectionId: string, requests: RequestState[]) => {
+  for (const request of requests) {
+    trashAppItem(sanitizeRequest(request), request.name, collectionId).catch((e) => {
+      console.warn(`Failed to move request ${request.id} to the trash`, e)
+    })
+  }
+}
+
 export const ScratchCollectionId = "scratch"
 export const isScratchCollection = (collection: CollectionState | string) =>
   (typeof collection === "string" ? collection : collection.id) === ScratchCollectionId
@@ -642,12 +700,69 @@ export const createCollectionsSlice: StateCreator<
   [],
   CollectionsStateSlice
 > = (set, get, storeApi) => {
+  // Autosave journal. Request drafts only reach disk when their collection is next saved, so
+  // edits are journaled in the meantime (debounced) and replayed at startup after a crash.
+  // Each request's latest record is tracked by sequence number, so a save only checkpoints
+  // the records it covered.
+  const journalPending = new Map<string, { collectionId: string; before: RequestState; after: RequestState }>()
+  const journaled = new Map<string, { collectionId: string; seq: number }>()
+  let journalSeq = 0
+
+  const flushJournal = debounce(() => {
+    const edits: JournalEdit[] = []
+    for (const [targetId, { collectionId, before, after }] of journalPending) {
+      const patch = diffMergePatch(sanitizeRequest(before), sanitizeRequest(after))
+      if (isNotEmpty(patch)) {
+        edits.push({ targetId, collectionId, patch })
+        journalSeq += 1
+        journaled.set(targetId, { collectionId, seq: journalSeq })
+      }
+    }
+    journalPending.clear()
+    if (edits.length > 0) {
+      appendJournal(edits).catch((e) => console.warn("Failed to journal request edits", e))
+    }
+  }, 1000)
+
+  const checkpointSaved = (savedCollectionIds: Set<string>, savedSeq: number) => {
+    const targetIds: string[] = []
+    for (const [targetId, { collectionId, seq }] of journaled) {
+      if (seq <= savedSeq && savedCollectionIds.has(collectionId)) {
+        targetIds.push(targetId)
+        journaled.delete(targetId)
+      }
+    }
+    if (targetIds.length > 0) {
+      checkpointJournal(targetIds).catch((e) => console.warn("Failed to checkpoint the journal", e))
+    }
+  }
+
   // Hookup load/save
   const storageProvider: StorageProvider<CollectionsState> = (() => {
     const timestamps: Record<string, string> = {}
+    // Environments as last snapshotted. Immer keeps unchanged ones as they are, so a changed
+    // environment is a different object.
+    const snapshotted = new Map<string, Environment>()
 
     // Helpers now provided at module scope; no closure exposure required
 
+    // Records the collection's changed environments in their snapshot history. A failed
+    // snapshot doesn't fail the save.
+    const snapshotEnvironments = (collection: CollectionCacheState): Promise<void>[] =>
+      Object.values(collection.environments ?? {}).flatMap((environment) => {
+        const key = `${collection.id}/${environment.id}`
+        if (snapshotted.get(key) === environment) {
+          return []
+        }
+        snapshotted.set(key, environment)
+        return [
+          snapshotEnvironment(collection.id, environment).then(
+            () => undefined,
+            (e) => console.warn(`Failed to snapshot environment ${environment.id}`, e),
+          ),
+        ]
+      })
+
     return {
       key: "collections",
       selector: (app) => app.collectionsState,
@@ -663,8 +778,10 @@ export const createCollectionsSlice: StateCreator<
       },
       save: async (force: boolean | undefined) => {
         const state = get().collectionsState
+        const savedSeq = journalSeq
 
         const promises: Promise<void>[] = []
+        const saved = new Set<string>()
 
         // Always save the index
         promises.push(CollectionIndexStorage.save(CollectionIndexFileName(), state.index))
@@ -673,14 +790,78 @@ export const createCollectionsSlice: StateCreator<
           if (force || timestamps[collection.id] !== collection.updated) {
             promises.push(CollectionStorage.save(CollectionFileName(collection.id), sanitizeCollection(collection)))
             timestamps[collection.id] = collection.updated
+            saved.add(collection.id)
+            promises.push(...snapshotEnvironments(collection))
           }
         }
         await Promise.all(promises)
+
+        checkpointSaved(saved, savedSeq)
       },
     }
   })()
   storeApi.registerStorageProvider(storageProvider)
 
+  // Record request edits for the journal. Requests entering or leaving the cache aren't edits.
+  storeApi.subscribe((app, prev) => {
+    const cache = app.collectionsState.cache
+    const prevCache = prev.collectionsState.cache
+    if (cache === prevCache) {
+      return
+    }
+    for (const [collectionId, collection] of Object.entries(cache)) {
+      const prevCollection = prevCache[collectionId]
+      if (!prevCollection || prevCollection === collection) {
+        continue
+      }
+      for (const [requestId, request] of Object.entries(collection.requests)) {
+        const prevRequest = prevCollection.requests[requestId]
+        if (!prevRequest || prevRequest === request) {
+          continue
+        }
+        const pending = journalPending.get(requestId)
+        journalPending.set(requestId, { collectionId, before: pending?.before ?? prevRequest, after: request })
+      }
+    }
+    if (journalPending.size > 0) {
+      flushJournal()
+    }
+  })
+
+  // Stage edited secure variable values with the backend, which reads them for requests that refer to them
+  // before the collection file has them. Collections entering the cache are already saved.
+  storeApi.subscribe((app, prev) => {
+    const cache = app.collectionsState.cache
+    const prevCache = prev.collectionsState.cache
+    if (cache === prevCache) {
+      return
+    }
+    for (const [collectionId, collection] of Object.entries(cache)) {
+      const prevCollection = prevCache[collectionId]
+      if (!prevCollection || prevCollection === collection) {
+        continue
+      }
+      for (const [environmentId, environment] of Object.entries(collection.environments ?? {})) {
+        const prevEnvironment = prevCollection.environments?.[environmentId]
+        if (prevEnvironment === environment) {
+          continue
+        }
+        for (const [variableId, variable] of Object.entries(environment.variables ?? {})) {
+          const prevVariable = prevEnvironment?.variables?.[variableId]
+          const changed = variable.secure
+            ? !prevVariable?.secure || prevVariable.value !== variable.value
+            : prevVariable?.secure === true
+          if (!changed) {
+            continue
+          }
+          stageSecureVariable(collectionId, environmentId, variableId, variable.secure ? variable.value : null).catch(
+            (e) => console.warn(`Failed to stage secure variable ${variableId}`, e),
+          )
+        }
+      }
+    }
+  })
+
   const touch = (collection: CollectionState): CollectionState => {
     collection.updated = new Date().toISOString()
     return collection
@@ -766,6 +947,7 @@ export const createCollectionsSlice: StateCreator<
         key: undefined,
       },
       environments: {},
+      identityProfiles: {},
       requests: {},
       folders: {
         [RootCollectionFolderId]: createFolderNode(RootCollectionFolderId, "Scratch", null),
@@ -919,6 +1101,7 @@ export const createCollectionsSlice: StateCreator<
           key: undefined,
         },
         environments: {},
+        identityProfiles: {},
         requests: {},
         folders: {
           [RootCollectionFolderId]: createFolderNode(RootCollectionFolderId, "Root", null),
@@ -1295,11 +1478,78 @@ export const createCollectionsSlice: StateCreator<
 
       invalidateCollectionPromise(id)
 
-      // We need to manually delete the collection file since the save logic will only pick up the index change
+      // We need to manually delete the collection file since the save logic will only pick up the index change.
+      // The file goes to the trash, from where restoreCollection() brings it back.
       // Fire & Forget
       void CollectionStorage.delete(CollectionFileName(id))
     },
 
+    ///
+    async restoreCollection(id: string) {
+      const collection = nonNull(
+        await CollectionStorage.load(CollectionFileName(id)),
+        `Collection file ${CollectionFileName(id)} could not be loaded`,
+      )
+
+      invalidateCollectionPromise(id)
+      return internalAddCollection(collection)
+    },
+
+    ///
+    async createIdentityProfile(collectionId: string, name: string) {
+      assert(existsInIndex(collectionId), `createIdentityProfile called with an unknown collection.id: ${collectionId}`)
+      const collection = nonNull(
+        await collectionsApi.getCollection(collectionId),
+        `createIdentityProfile called with an unknown collection.id: ${collectionId}`,
+      )
+
+      const profile = zParse(zIdentityProfile, { id: generateUniqueId(), name })
+
+      setAndSync((app) => {
+        // biome-ignore lint/style/noNonNullAssertion: Safe via getCollection()
+        const draft = touch(app.collectionsState.cache[collection.id]!)
+        draft.identityProfiles = { ...(draft.identityProfiles ?? {}), [profile.id]: profile }
+      })
+
+      return profile
+    },
+
+    ///
+    async updateIdentityProfile(collectionId: string, id: string, update: Partial<IdentityProfile>) {
+      assert(existsInIndex(collectionId), `updateIdentityProfile called with an unknown collection.id: ${collectionId}`)
+      assert(
+        update.id === undefined || update.id === id,
+        `updateIdentityProfile expected update.id to be absent or equal to id:${id}. Found ${update.id}`,
+      )
+      const collection = nonNull(
+        await collectionsApi.getCollection(collectionId),
+        `updateIdentityProfile called with an unknown collection.id: ${collectionId}`,
+      )
+
+      setAndSync((app) => {
+        // biome-ignore lint/style/noNonNullAssertion: Safe via getCollection()
+        const profile = touch(app.collectionsState.cache[collection.id]!).identityProfiles?.[id]
+        if (profile) {
+          // Assign rather than merge so cleared extra headers are removed
+          Object.assign(profile, update)
+        }
+      })
+    },
+
+    ///
+    async deleteIdentityProfile(collectionId: string, id: string) {
+      assert(existsInIndex(collectionId), `deleteIdentityProfile called with an unknown collection.id: ${collectionId}`)
+      const collection = nonNull(
+        await collectionsApi.getCollection(collectionId),
+        `deleteIdentityProfile called with an unknown collection.id: ${collectionId}`,
+      )
+
+      setAndSync((app) => {
+        // biome-ignore lint/style/noNonNullAssertion: Safe via getCollection()
+        delete touch(app.collectionsState.cache[collection.id]!).identityProfiles?.[id]
+      })
+    },
+
     ///
     async createEnvironment(collectionId: string, name: string, description?: string) {
       assert(existsInIndex(collectionId), `updateCollection called with an unknown collection.id: ${collectionId}`)
@@ -1548,6 +1798,8 @@ export const createCollectionsSlice: StateCreator<
     async deleteRequest(collectionId: string, requestId: string) {
       assert(existsInIndex(collectionId), `createRequest called with an unknown collection.id: ${collectionId}`)
 
+      const deleted = get().collectionsState.cache[collectionId]?.requests[requestId]
+
       set((app) => {
         // It should not be possible to delete a request before its collection is loaded
         const collection = touch(
@@ -1566,6 +1818,41 @@ export const createCollectionsSlice: StateCreator<
         )
         index.count = countCollectionRequests(collection)
       })
+
+      // Scratch requests are transient and deleted along with their tab, so they aren't kept
+      if (deleted && !isScratchCollection(collectionId)) {
+        trashRequests(collectionId, [deleted])
+      }
+    },
+
+    async restoreRequest(collectionId: string, request: RequestState) {
+      await collectionsApi.getCollection(collectionId)
+
+      const restored = zParse(zRequestState, request)
+      set((app) => {
+        const collection = touch(
+          nonNull(
+            app.collectionsState.cache[collectionId],
+            `restoreRequest called with unloaded collection.id: ${collectionId}`,
+          ),
+        )
+        if (collection.requests[restored.id]) {
+          restored.id = generateUniqueId()
+        }
+        // Requests whose folder was deleted since go back to the root
+        const folderId =
+          restored.folderId && collection.folders[restored.folderId] ? restored.folderId : RootCollectionFolderId
+        insertRequestIntoFolder(collection, folderId, restored)
+
+        const index = nonNull(
+          app.collectionsState.index.find((m) => m.id === collectionId),
+          `restoreRequest called with non-indexed collection.id: ${collectionId}`,
+        )
+        index.count = countCollectionRequests(collection)
+      })
+
+      // biome-ignore lint/style/noNonNullAssertion: Known safe
+      return findRequestInCollection(get().collectionsState.cache[collectionId]!, restored.id).request
     },
 
     async reorderRequestsInFolder(collectionId: string, folderId: string, orderedIds: string[]) {
@@ -1667,6 +1954,7 @@ export const createCollectionsSlice: StateCreator<
       assert(collectionId !== ScratchCollectionId, "Scratch collection does not support folder deletion")
       await collectionsApi.getCollection(collectionId)
 
+      const deleted: RequestState[] = []
       set((app) => {
         const collection = touch(
           nonNull(
@@ -1702,6 +1990,10 @@ export const createCollectionsSlice: StateCreator<
             continue
           }
           for (const requestId of folder.requestIds) {
+            const request = collection.requests[requestId]
+            if (request) {
+              deleted.push(current(request))
+            }
             removeRequestIndexEntry(collection, requestId)
             delete collection.requests[requestId]
           }
@@ -1717,6 +2009,8 @@ export const createCollectionsSlice: StateCreator<
         )
         index.count = countCollectionRequests(collection)
       })
+
+      trashRequests(collectionId, deleted)
     },
 
     async moveFolder(collectionId: string, folderId: string, targetParentId: string | null, position?: number) {
@@ -2181,6 +2475,46 @@ export const createCollectionsSlice: StateCreator<
       assert(latestCollection, `commitRequestPatch called with unknown collectionId:${collectionId}`)
       return findRequestInCollection(latestCollection, requestId).request
     },
+
+    ///
+    async recoverUnsavedEdits() {
+      let recovered = 0
+      for (const edit of await recoverJournal()) {
+        const { collectionId, targetId } = edit
+        if (!collectionId || !existsInIndex(collectionId)) {
+          continue
+        }
+        const collection = await collectionsApi.getCollection(collectionId)
+        const request = collection.requests[targetId]
+        if (!request) {
+          continue
+        }
+        // The changes may already be saved if the app stopped before the journal was
+        // checkpointed; applying them again is harmless
+        const next = zParse(zRequestState, applyMergePatch(request, edit.changes))
+        set((app) => {
+          const draft = touch(
+            nonNull(app.collectionsState.cache[collectionId], `Collection ${collectionId} missing during recovery`),
+          )
+          // Moves aren't replayed, so the folder index stays valid
+          draft.requests[targetId] = { ...next, folderId: request.folderId, order: request.order }
+        })
+        recovered += 1
+      }
+      if (recovered > 0) {
+        console.info(`Recovered unsaved edits to ${recovered} request(s) from the journal`)
+      }
+      return recovered
+    },
+  }
+
+  // Replay edits that didn't reach a saved collection before the app last stopped
+  try {
+    storeApi.registerPostHydrate(async () => {
+      await collectionsApi.recoverUnsavedEdits()
+    })
+  } catch (_e) {
+    // no-op in tests without storage manager
   }
 
   return {
@@ -1,5 +1,6 @@
 import type { StateCreator } from "zustand"
 
+import type { ChallengeCredentials, NtlmCredentials } from "@/bindings/knurl"
 import type { ApplicationState } from "@/types"
 
 // This is a placeholder for the actual result from the backend
@@ -10,6 +11,9 @@ export type AuthResult = {
   cookies?: Record<string, string>
   body?: Record<string, unknown>
   expiresAt?: number // unix timestamp
+  ntlm?: NtlmCredentials
+  challenge?: ChallengeCredentials
+  idTokenClaims?: Record<string, unknown>
 }
 
 export type CredentialsCacheState = {
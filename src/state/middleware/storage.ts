@@ -1,6 +1,6 @@
 import { z } from "zod"
 
-import { deleteAppData, isAppError, loadAppData, saveAppData } from "@/bindings/knurl"
+import { deleteAppData, isAppError, loadAppData, type SecretFinding, saveAppData } from "@/bindings/knurl"
 
 interface FileStorage<S> {
   load: (fileName: string) => Promise<S | null>
@@ -33,6 +33,8 @@ export interface StorageOptions<S> {
   schema: z.ZodSchema<S>
   migrate?: (context: MigrateContext) => Promise<S>
   writeable?: boolean
+  /** Called when a save stored plaintext values that look like credentials */
+  onSecretsFound?: (fileName: string, findings: SecretFinding[]) => void
 }
 
 /**
@@ -41,7 +43,7 @@ export interface StorageOptions<S> {
  * @param options
  */
 export function createStorage<Schema>(options: StorageOptions<Schema>): FileStorage<Schema> {
-  const { version, schema, migrate, writeable = true } = options
+  const { version, schema, migrate, writeable = true, onSecretsFound } = options
 
   const tryLoad = async (fileName: string) => {
     try {
@@ -119,13 +121,20 @@ export function createStorage<Schema>(options: StorageOptions<Schema>): FileStor
         return Promise.resolve()
       }
 
-      return await saveAppData(fileName, {
+      const findings = await saveAppData(fileName, {
         header: {
           version,
           updated: new Date().toISOString(),
         },
         content,
       })
+      if (findings.length > 0) {
+        console.warn(
+          `createStorage: ${fileName} contains ${findings.length} plaintext value(s) that look like credentials`,
+          findings.map(({ pointer, kind }) => ({ pointer, kind })),
+        )
+        onSecretsFound?.(fileName, findings)
+      }
     },
 
     delete: async (fileName: string) => {
@@ -3,16 +3,16 @@ import { merge } from "es-toolkit"
 import type { StateCreator } from "zustand"
 import type { StoreApi } from "zustand"
 
-import { getAuthenticationResult, cancelHttpRequest, deleteFile } from "@/bindings/knurl"
+import { getAuthenticationResult, cancelHttpRequest, deleteFile, revokeToken } from "@/bindings/knurl"
 import type { AppError } from "@/bindings/knurl"
 import { assert, isNotEmpty, nonNull } from "@/lib"
-import { resolveRequestVariables } from "@/lib/environments"
 import { generateUniqueId } from "@/lib/utils"
 import {
   createAuthPhase,
+  createIdentityPhase,
   protocolDispatchPhase,
-  resolveVariablesPhase,
   runPipeline,
+  variablesPhase,
   type PipelineNotifier,
   type RequestContext,
   type RequestPhase,
@@ -441,7 +441,12 @@ export const requestTabsSliceCreator: StateCreator<
         }
 
         const authPhase = createAuthPhase(get, set)
-        const phases: RequestPhase[] = [resolveVariablesPhase, authPhase, protocolDispatchPhase]
+        const phases: RequestPhase[] = [
+          createIdentityPhase(get),
+          variablesPhase,
+          authPhase,
+          protocolDispatchPhase,
+        ]
 
         await runPipeline(phases, initialContext, notifier)
       } finally {
@@ -479,7 +484,11 @@ export const requestTabsSliceCreator: StateCreator<
       }
     },
 
-    async runAuthOnly(tabId: string): Promise<void> {
+    /**
+     * Fetches a token for the tab's effective auth into the session cache, or with `"revoke"` revokes the tokens
+     * the backend cached for it and forgets the session's copy.
+     */
+    async runAuthOnly(tabId: string, action: "fetch" | "revoke" = "fetch"): Promise<void> {
       const tab = get().requestTabsState.openTabs[tabId]
       assert(tab, `runAuthOnly called with unknown tabId:${tabId}`)
 
@@ -500,12 +509,6 @@ export const requestTabsSliceCreator: StateCreator<
         const merged = state.requestTabsState.openTabs[tabId]?.merged
         assert(merged, `runAuthOnly called with missing merged request for tabId:${tabId}`)
 
-        // Resolve environment variables
-        const environment = tab.selectedEnvironmentId
-          ? state.collectionsState.cache[tab.collectionId]?.environments[tab.selectedEnvironmentId]
-          : undefined
-        const resolvedRequest = resolveRequestVariables(merged, environment)
-
         // Listen for log events (same stream as normal send)
         logUnlisten = await listen<LogEntry>("http-request-log", (event) => {
           const logEntry = event.payload
@@ -522,16 +525,16 @@ export const requestTabsSliceCreator: StateCreator<
 
         // Compute effective auth
         const { collectionsState, credentialsCacheApi } = state
-        const collection = collectionsState.cache[resolvedRequest.collectionId]
-        assert(collection, `Collection not found for request: ${resolvedRequest.collectionId}`)
+        const collection = collectionsState.cache[merged.collectionId]
+        assert(collection, `Collection not found for request: ${merged.collectionId}`)
 
         const effectiveAuth =
-          resolvedRequest.authentication.type === "inherit"
+          merged.authentication.type === "inherit"
             ? collection.authentication
             : {
-                ...resolvedRequest.authentication,
+                ...merged.authentication,
                 // biome-ignore lint/suspicious/noExplicitAny: OK
-                ...((resolvedRequest.authentication as any)[resolvedRequest.authentication.type] ?? {}),
+                ...((merged.authentication as any)[merged.authentication.type] ?? {}),
               }
 
         if (!effectiveAuth || effectiveAuth.type === "none" || effectiveAuth.type === "inherit") {
@@ -539,8 +542,7 @@ export const requestTabsSliceCreator: StateCreator<
         }
 
         // Force fresh token; still correlated via parent request id for logs
-        // Map UI auth config to backend bindings before invoking
-        const auth = effectiveAuth.type === "inherit" ? effectiveAuth : effectiveAuth
+        // Map UI auth config to backend bindings before invoking; the backend resolves inherited auth
         const toBindingAuth = (authCfg: typeof effectiveAuth) => {
           switch (authCfg.type) {
             case "none":
@@ -562,11 +564,22 @@ export const requestTabsSliceCreator: StateCreator<
                 value: authCfg.apiKey?.value,
                 placement: authCfg.apiKey?.placement,
               }
+            case "ntlm":
+              return {
+                type: "ntlm",
+                username: authCfg.ntlm?.username,
+                password: authCfg.ntlm?.password,
+                domain: authCfg.ntlm?.domain,
+                workstation: authCfg.ntlm?.workstation,
+              }
+            case "digest":
+              return { type: "digest", username: authCfg.digest?.username, password: authCfg.digest?.password }
             case "oauth2":
               return {
                 type: "oauth2",
                 grantType: authCfg.oauth2?.grantType,
                 authUrl: authCfg.oauth2?.authUrl,
+                deviceAuthorizationUrl: authCfg.oauth2?.deviceAuthorizationUrl,
                 tokenUrl: authCfg.oauth2?.tokenUrl,
                 clientId: authCfg.oauth2?.clientId,
                 clientSecret: authCfg.oauth2?.clientSecret,
@@ -574,14 +587,31 @@ export const requestTabsSliceCreator: StateCreator<
                 refreshToken: authCfg.oauth2?.refreshToken,
                 tokenCaching: authCfg.oauth2?.tokenCaching,
                 clientAuth: authCfg.oauth2?.clientAuth,
+                assertionKeyName: authCfg.oauth2?.assertionKeyName,
+                assertionAlgorithm: authCfg.oauth2?.assertionAlgorithm,
+                assertionKeyId: authCfg.oauth2?.assertionKeyId,
                 tokenExtraParams: authCfg.oauth2?.tokenExtraParams,
+                redirectUri: authCfg.oauth2?.redirectUri,
+                usePkce: authCfg.oauth2?.usePkce,
+                authorizationWindow: authCfg.oauth2?.authorizationWindow,
+                validateIdToken: authCfg.oauth2?.validateIdToken,
+                issuer: authCfg.oauth2?.issuer,
+                jwksUrl: authCfg.oauth2?.jwksUrl,
+                revocationUrl: authCfg.oauth2?.revocationUrl,
               }
           }
         }
-        const authResult = await getAuthenticationResult(toBindingAuth(auth), requestId)
+        const auth = toBindingAuth(merged.authentication)
+        const parents = [toBindingAuth(collection.authentication)]
+        const cacheKey = credentialsCacheApi.generateCacheKey(merged.id)
+        if (action === "revoke") {
+          await revokeToken(auth, {}, requestId, parents)
+          credentialsCacheApi.remove(cacheKey)
+          return
+        }
+        const authResult = await getAuthenticationResult(auth, requestId, parents)
 
         // Store to session cache so UI token field updates
-        const cacheKey = credentialsCacheApi.generateCacheKey(merged.id)
         await credentialsCacheApi.set(cacheKey, authResult)
       } finally {
         if (logUnlisten) {
@@ -12,6 +12,7 @@ import { runTasks } from "@/lib"
 import { createStorage, type MigrateContext } from "@/state/middleware/storage"
 import {
   type ApplicationState,
+  type ConnectionPool,
   type Settings,
   type SettingsApi,
   type SettingsSlice,
@@ -253,6 +254,21 @@ export const createSettingsSlice: StateCreator<
         app.settingsState.requests.previewMaxBytes = b
       })
     },
+    setConnectionPool(pool?: ConnectionPool) {
+      setAndSync((app) => {
+        app.settingsState.requests.connectionPool = pool
+      })
+    },
+    setMetricsPort(port?: number) {
+      setAndSync((app) => {
+        app.settingsState.requests.metricsPort = port && port > 0 ? Math.floor(port) : undefined
+      })
+    },
+    setMetricsFile(path?: string) {
+      setAndSync((app) => {
+        app.settingsState.requests.metricsFile = path && path.length > 0 ? path : undefined
+      })
+    },
 
     // Advanced
     setDevMode(enabled: boolean) {
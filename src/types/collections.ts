@@ -5,12 +5,14 @@ import type { Patch, Some } from "./common"
 import {
   DefaultCollectionFolderId,
   type FormField,
+  type IdentityProfile,
   type RequestBodyData,
   type RequestHeader,
   type RequestPathParam,
   type RequestQueryParam,
   type RequestState,
   zAuthConfig,
+  zIdentityProfile,
   zRequestState,
 } from "./request"
 
@@ -114,6 +116,7 @@ export const zCollectionState = z.object({
   requests: z.record(zRequestState.shape.id, zRequestState).default({}),
   folders: z.record(z.string(), zCollectionFolderNode).default({}),
   authentication: zAuthConfig,
+  identityProfiles: z.record(z.string(), zIdentityProfile).default({}),
 })
 export type CollectionState = z.infer<typeof zCollectionState>
 
@@ -159,6 +162,29 @@ export interface CollectionsStateApi extends CollectionsIndexStateApi, Environme
    */
   saveCollection(collection: CollectionCacheState | string): void
 
+  /**
+   * Adds an identity profile to a collection
+   * @param collectionId - The collection ID
+   * @param name - The name of the profile
+   * @returns The newly created profile
+   */
+  createIdentityProfile(collectionId: string, name: string): Promise<IdentityProfile>
+
+  /**
+   * Updates an identity profile
+   * @param collectionId - The collection ID
+   * @param id - Profile ID
+   * @param update - Partial profile data to update
+   */
+  updateIdentityProfile(collectionId: string, id: string, update: Partial<IdentityProfile>): Promise<void>
+
+  /**
+   * Removes an identity profile. Requests referencing it are sent without it.
+   * @param collectionId - The collection ID
+   * @param id - Profile ID
+   */
+  deleteIdentityProfile(collectionId: string, id: string): Promise<void>
+
   /**
    * Retrieves a specific collection by ID
    * @param id - The collection ID to retrieve
@@ -224,6 +250,13 @@ export interface CollectionsStateApi extends CollectionsIndexStateApi, Environme
    */
   removeCollection(id: string): Promise<void>
 
+  /**
+   * Adds a collection whose file was restored from the trash back to the index
+   * @param id - The collection ID
+   * @returns The restored collection
+   */
+  restoreCollection(id: string): Promise<CollectionCacheState>
+
   /**
    * Get a request with any draft changes applied from a collection
    * @param collectionId - Collection ID
@@ -256,6 +289,15 @@ export interface CollectionsStateApi extends CollectionsIndexStateApi, Environme
    */
   deleteRequest(collectionId: string, requestId: string): Promise<void>
 
+  /**
+   * Re-inserts a request restored from the trash into its folder, or the root folder when
+   * that folder is gone. The request gets a new ID if its old one is taken.
+   * @param collectionId - Collection ID
+   * @param request - The request as it was trashed
+   * @returns The restored request
+   */
+  restoreRequest(collectionId: string, request: RequestState): Promise<RequestState>
+
   /**
    * Update the draft of a request without saving to the persistent store
    * @param collectionId - Collection ID
@@ -324,6 +366,13 @@ export interface CollectionsStateApi extends CollectionsIndexStateApi, Environme
    */
   commitRequestPatch(collectionId: string, requestId: string): Promise<RequestState>
 
+  /**
+   * Apply request edits left in the autosave journal by a session that ended before they were
+   * saved. Runs once after the store is hydrated.
+   * @returns Number of requests recovered
+   */
+  recoverUnsavedEdits(): Promise<number>
+
   /**
    * Reorder the collections index by assigning sequential order values.
    * Scratch collection remains first regardless of input order.
@@ -9,7 +9,25 @@ export const DefaultCollectionFolderId = "root" as const
 /**
  * Schema & type defining valid HTTP methods
  */
-export const zHttpMethod = z.enum(["GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS", "TRACE"])
+export const zHttpMethod = z.enum([
+  "GET",
+  "POST",
+  "PUT",
+  "PATCH",
+  "DELETE",
+  "HEAD",
+  "OPTIONS",
+  "TRACE",
+  // WebDAV (RFC 4918) and CalDAV/CardDAV REPORT
+  "PROPFIND",
+  "PROPPATCH",
+  "MKCOL",
+  "COPY",
+  "MOVE",
+  "LOCK",
+  "UNLOCK",
+  "REPORT",
+])
 export type HttpMethod = z.infer<typeof zHttpMethod>
 
 /**
@@ -116,7 +134,7 @@ export type RequestBodyData = z.infer<typeof zRequestBodyData>
 /**
  * Schema defining authentication types
  */
-export const zAuthType = z.enum(["none", "inherit", "bearer", "basic", "apiKey", "oauth2"])
+export const zAuthType = z.enum(["none", "inherit", "bearer", "basic", "apiKey", "oauth2", "ntlm", "digest"])
 export type AuthType = z.infer<typeof zAuthType>
 
 export const zAuthTypes = z.record(zAuthType, z.string())
@@ -128,6 +146,8 @@ export const AuthTypes: z.infer<typeof zAuthTypes> = {
   basic: "Basic",
   apiKey: "API Key",
   oauth2: "OAuth2",
+  ntlm: "NTLM",
+  digest: "Digest",
 }
 
 /**
@@ -152,6 +172,22 @@ const zBasicAuth = z.object({
 })
 export type BasicAuth = z.infer<typeof zBasicAuth>
 
+const zNtlmAuth = z.object({
+  // May include the domain as DOMAIN\user or user@domain
+  username: z.string().optional(),
+  password: z.string().optional(),
+  domain: z.string().optional(),
+  workstation: z.string().optional(),
+})
+export type NtlmAuth = z.infer<typeof zNtlmAuth>
+
+// Answered when the server challenges the request, since Digest needs the server's nonce
+const zDigestAuth = z.object({
+  username: z.string().optional(),
+  password: z.string().optional(),
+})
+export type DigestAuth = z.infer<typeof zDigestAuth>
+
 const zBearerAuth = z.object({
   token: z.string().optional(),
   // Optional scheme for Authorization header (e.g., "Bearer", "JWT", or custom)
@@ -168,10 +204,14 @@ const zApiKeyAuth = z.object({
 export type ApiKeyAuth = z.infer<typeof zApiKeyAuth>
 
 const zOauth2Auth = z.object({
-  grantType: z.enum(["client_credentials", "password", "refresh_token", "device_code"]).default("client_credentials"),
+  grantType: z
+    .enum(["client_credentials", "authorization_code", "password", "refresh_token", "device_code"])
+    .default("client_credentials"),
   // Optional OpenID Connect discovery endpoint (issuer base or full .well-known URL)
   discoveryUrl: z.string().optional(),
   authUrl: z.string().optional(),
+  // Device authorization endpoint for device_code (RFC 8628)
+  deviceAuthorizationUrl: z.string().optional(),
   tokenUrl: z.string().optional(),
   clientId: z.string().optional(),
   clientSecret: z.string().optional(),
@@ -181,8 +221,25 @@ const zOauth2Auth = z.object({
   password: z.string().optional(),
   refreshToken: z.string().optional(),
   tokenCaching: z.enum(["always", "never"]).default("always").optional(),
-  clientAuth: z.enum(["basic", "body"]).default("body").optional(),
+  clientAuth: z.enum(["basic", "body", "privateKeyJwt"]).default("body").optional(),
+  // Keyring signing key, algorithm and kid of private_key_jwt client assertions
+  assertionKeyName: z.string().optional(),
+  assertionAlgorithm: z.enum(["RS256", "PS256", "ES256"]).optional(),
+  assertionKeyId: z.string().optional(),
   tokenExtraParams: z.record(z.string(), z.string()).optional(),
+  // Loopback redirect for authorization_code, e.g. http://127.0.0.1:8765/callback (any free port if omitted)
+  redirectUri: z.string().optional(),
+  // Send a PKCE challenge with authorization_code (default true)
+  usePkce: z.boolean().optional(),
+  // Sign in with the system browser (loopback redirect) or a Knurl window that intercepts the redirect
+  authorizationWindow: z.enum(["system", "embedded"]).optional(),
+  // Check the ID token's signature against the provider's JWKS and its iss/aud/exp claims
+  validateIdToken: z.boolean().optional(),
+  // Expected ID token issuer; its discovery document locates the JWKS unless jwksUrl is set
+  issuer: z.string().optional(),
+  jwksUrl: z.string().optional(),
+  // Token revocation endpoint (RFC 7009)
+  revocationUrl: z.string().optional(),
 })
 export type OAuth2Auth = z.infer<typeof zOauth2Auth>
 
@@ -193,10 +250,185 @@ export const zAuthConfig = z.discriminatedUnion("type", [
   z.object({ type: z.literal("bearer"), bearer: zBearerAuth }),
   z.object({ type: z.literal("apiKey"), apiKey: zApiKeyAuth }),
   z.object({ type: z.literal("oauth2"), oauth2: zOauth2Auth }),
+  z.object({ type: z.literal("ntlm"), ntlm: zNtlmAuth }),
+  z.object({ type: z.literal("digest"), digest: zDigestAuth }),
 ])
 
 export type AuthConfig = z.infer<typeof zAuthConfig>
 
+/**
+ * Keep-alive connection pooling. When enabled, requests with the same connection settings
+ * reuse idle connections instead of paying a TCP + TLS handshake each time.
+ */
+export const zConnectionPool = z.object({
+  enabled: z.boolean(),
+  /**
+   * Seconds an idle connection is kept open (backend default 90)
+   */
+  idleTimeoutSecs: z.number().int().positive().optional(),
+  /**
+   * Idle connections kept per host (backend default 8)
+   */
+  maxIdlePerHost: z.number().int().min(0).optional(),
+  /**
+   * Share a client, and its TLS and DNS setup, with requests of the same connection settings
+   * (backend default true); false builds one for each request
+   */
+  shareClient: z.boolean().optional(),
+})
+export type ConnectionPool = z.infer<typeof zConnectionPool>
+
+/**
+ * Automatic retry of 429/503 responses, honoring their Retry-After header.
+ */
+export const zRetryPolicy = z.object({
+  enabled: z.boolean(),
+  /**
+   * Retries after the first attempt (backend default 3)
+   */
+  maxRetries: z.number().int().min(0).optional(),
+  /**
+   * Longest wait before a retry; a longer Retry-After ends the retries (backend default 60)
+   */
+  maxDelaySecs: z.number().int().min(0).optional(),
+})
+export type RetryPolicy = z.infer<typeof zRetryPolicy>
+
+/**
+ * Which of a request's log entries are sent to the log panel. Sampled and off keep large runs from flooding
+ * it; the response's log summary still counts every entry.
+ */
+export const zLogCapture = z.discriminatedUnion("mode", [
+  z.object({ mode: z.literal("full") }),
+  /**
+   * Every entry of a random `rate` (0 to 1) share of requests, none of the others
+   */
+  z.object({ mode: z.literal("sampled"), rate: z.number().min(0).max(1) }),
+  z.object({ mode: z.literal("off") }),
+])
+export type LogCapture = z.infer<typeof zLogCapture>
+
+/**
+ * A check of a response, evaluated by the backend once the response has arrived
+ */
+export const zAssertion = z.discriminatedUnion("type", [
+  z.object({ type: z.literal("status"), equals: z.number().int() }),
+  /**
+   * The header is present and, when given, a value of it equals `equals` or matches the regular expression
+   * `matches`
+   */
+  z.object({
+    type: z.literal("header"),
+    name: z.string(),
+    equals: z.string().optional(),
+    matches: z.string().optional(),
+  }),
+  /**
+   * The JSONPath selects a node of the JSON body and, when given, one equal to `equals`
+   */
+  z.object({ type: z.literal("jsonPath"), path: z.string(), equals: z.unknown().optional() }),
+  z.object({ type: z.literal("bodyContains"), text: z.string() }),
+  z.object({ type: z.literal("latencyUnder"), ms: z.number().int().min(0) }),
+])
+export type Assertion = z.infer<typeof zAssertion>
+
+/**
+ * A host whose certificate isn't fully verified; `*.example.test` matches every subdomain
+ */
+export const zInsecureHost = z.object({
+  host: z.string(),
+  hostnameOnly: z.boolean().optional(),
+})
+export type InsecureHost = z.infer<typeof zInsecureHost>
+
+/**
+ * TLS versions the engine can speak
+ */
+export const zTlsVersion = z.enum(["1.2", "1.3"])
+export type TlsVersion = z.infer<typeof zTlsVersion>
+
+/**
+ * Resolves a host to a fixed IP in place of a DNS lookup
+ */
+export const zDnsOverride = z.object({
+  host: z.string(),
+  ip: z.string(),
+})
+export type DnsOverride = z.infer<typeof zDnsOverride>
+
+/**
+ * Header that carries the SHA-256 digest of the body as sent: `Content-Digest` (RFC 9530), the legacy
+ * `Digest` (RFC 3230), or both
+ */
+export const zBodyDigest = z.enum(["contentDigest", "digest", "both"])
+export type BodyDigest = z.infer<typeof zBodyDigest>
+
+/**
+ * Algorithms for HTTP Message Signatures (RFC 9421)
+ */
+export const zSignatureAlgorithm = z.enum(["ed25519", "rsa-pss-sha512", "ecdsa-p256-sha256", "hmac-sha256"])
+export type SignatureAlgorithm = z.infer<typeof zSignatureAlgorithm>
+
+/**
+ * Signs the request with HTTP Message Signatures (RFC 9421). The key itself lives in the OS
+ * keyring under `keyName`.
+ */
+export const zMessageSignature = z.object({
+  enabled: z.boolean(),
+  keyName: z.string().default(""),
+  algorithm: zSignatureAlgorithm.default("ed25519"),
+  /**
+   * `keyid` parameter sent to the server
+   */
+  keyId: z.string().optional(),
+  /**
+   * Signature label (backend default "sig1")
+   */
+  label: z.string().optional(),
+  /**
+   * Covered components in order, e.g. ["@method", "@authority", "@path", "content-digest"]
+   */
+  components: z.array(z.string()).default([]),
+  expiresInSecs: z.number().int().positive().optional(),
+  nonce: z.boolean().optional(),
+  tag: z.string().optional(),
+})
+export type MessageSignature = z.infer<typeof zMessageSignature>
+
+/**
+ * A named set of headers that identify the client, e.g. a desktop browser. Stored on the
+ * collection and referenced by requests, so impersonation doesn't need hand-edited headers.
+ */
+export const zIdentityProfile = z.object({
+  id: z.string(),
+  name: z.string().default(""),
+  /**
+   * User-Agent header
+   */
+  userAgent: z.string().optional(),
+  /**
+   * Accept-Language header, e.g. "en-US,en;q=0.9"
+   */
+  acceptLanguage: z.string().optional(),
+  /**
+   * sec-ch-ua client hint, e.g. `"Chromium";v="124", "Google Chrome";v="124"`
+   */
+  secChUa: z.string().optional(),
+  /**
+   * sec-ch-ua-mobile client hint, "?0" or "?1"
+   */
+  secChUaMobile: z.string().optional(),
+  /**
+   * sec-ch-ua-platform client hint, e.g. `"Windows"`
+   */
+  secChUaPlatform: z.string().optional(),
+  /**
+   * Any other headers the profile sends, by name
+   */
+  headers: z.record(z.string(), z.string()).optional(),
+})
+export type IdentityProfile = z.infer<typeof zIdentityProfile>
+
 /**
  * Schema for HTTP client options configuration
  */
@@ -205,18 +437,42 @@ export const zClientOptionsData = z.object({
    * If true, disable SSL certificate verification
    */
   disableSsl: z.boolean().default(false).optional(),
+  /**
+   * Hosts whose certificates aren't verified (or, with hostnameOnly, aren't checked for their name)
+   */
+  insecureHosts: z.array(zInsecureHost).optional(),
+  /**
+   * Check the server's certificates for revocation over OCSP or CRLs
+   */
+  checkRevocation: z.boolean().optional(),
   /**
    * Path to a custom root CA bundle (PEM format)
    */
   caPath: z.string().optional(),
+  /**
+   * Oldest TLS version to offer (default 1.2)
+   */
+  tlsMinVersion: zTlsVersion.optional(),
+  /**
+   * Newest TLS version to offer (default 1.3)
+   */
+  tlsMaxVersion: zTlsVersion.optional(),
+  /**
+   * Cipher suites to offer, in order of preference, by IANA name (default: all supported)
+   */
+  cipherSuites: z.array(z.string()).optional(),
   /**
    * Hostname part for custom DNS override (e.g., "api.example.com")
    */
   hostOverride: z.string().optional(),
   /**
-   * IP to resolve hostOverride to (e.g., "127.0.0.1")
+   * IP to resolve the URL's host to (e.g., "127.0.0.1"); shorthand for a dnsOverrides entry
    */
   ipOverride: z.string().optional(),
+  /**
+   * Hosts to resolve to fixed IPs, like per-request /etc/hosts entries, redirects included
+   */
+  dnsOverrides: z.array(zDnsOverride).optional(),
   /**
    * Timeout in seconds for the request
    */
@@ -229,10 +485,99 @@ export const zClientOptionsData = z.object({
    * HTTP version preference for this request (ALPN offer / enforcement)
    */
   httpVersion: z.enum(["auto", "http1", "http2"]).optional(),
+  /**
+   * Address family to connect over; auto races IPv4 and IPv6 (Happy Eyeballs)
+   */
+  ipFamily: z.enum(["auto", "ipv4", "ipv6"]).optional(),
+  /**
+   * Local IP to connect from, on machines with several addresses
+   */
+  localAddress: z.string().optional(),
+  /**
+   * Network interface to connect through (not supported on Windows)
+   */
+  interface: z.string().optional(),
   /**
    * Maximum number of redirects to follow automatically. 0 disables.
    */
   maxRedirects: z.number().int().min(0).optional(),
+  /**
+   * Stop reading the response body after this many bytes; the partial body is flagged as truncated
+   */
+  maxResponseBytes: z.number().int().positive().optional(),
+  /**
+   * Ask where to save the response when sending, and write its body straight to that file
+   */
+  saveResponseToFile: z.boolean().optional(),
+  /**
+   * Continue an interrupted save to the same file, unless the resource changed since
+   */
+  resumeDownload: z.boolean().optional(),
+  /**
+   * Bytes of the resource to request, as start-end, start- or -suffix
+   */
+  range: z.string().optional(),
+  /**
+   * Keep GET responses with an ETag or Last-Modified, and revalidate them when the request is sent again
+   */
+  responseCache: z.boolean().optional(),
+  /**
+   * Connection pooling for this request. Falls back to the workspace setting when unset.
+   */
+  connectionPool: zConnectionPool.optional(),
+  /**
+   * Retry rate-limited (429) and unavailable (503) responses
+   */
+  retry: zRetryPolicy.optional(),
+  /**
+   * Send fewer log entries for this request
+   */
+  logCapture: zLogCapture.optional(),
+  /**
+   * Keep the exchange for HAR export, with secrets masked and response bodies over 256 KiB left out
+   */
+  keepHar: z.boolean().optional(),
+  /**
+   * Checks of the response, evaluated by the backend after each send
+   */
+  assertions: z.array(zAssertion).optional(),
+  /**
+   * Rewrite a JSON body in canonical form (RFC 8785 JCS) before it's digested, signed and sent
+   */
+  canonicalizeJson: z.boolean().optional(),
+  /**
+   * Send headers exactly as written: names as cased, in order, without automatic Host, User-Agent or
+   * Content-Length. Forces HTTP/1.1.
+   */
+  rawHeaders: z.boolean().optional(),
+  /**
+   * Turn off automatic User-Agent, Host, Accept (GraphQL) or Content-Length headers
+   */
+  autoHeaders: z
+    .object({
+      userAgent: z.boolean().optional(),
+      host: z.boolean().optional(),
+      accept: z.boolean().optional(),
+      contentLength: z.boolean().optional(),
+    })
+    .optional(),
+  /**
+   * Add a digest header for the body, computed before signing
+   */
+  bodyDigest: zBodyDigest.optional(),
+  /**
+   * Sign the request with HTTP Message Signatures
+   */
+  signature: zMessageSignature.optional(),
+  /**
+   * Identity profile of the collection whose headers are sent with the request
+   */
+  identityProfileId: z.string().optional(),
+  /**
+   * Re-send the saved request every this many seconds while its collection is open, and report each result as
+   * monitor metrics
+   */
+  monitorIntervalSecs: z.number().int().positive().optional(),
 })
 export type ClientOptionsData = z.infer<typeof zClientOptionsData>
 
@@ -599,6 +944,41 @@ export const zCookie = z.object({
 
 export type Cookie = z.infer<typeof zCookie>
 
+/**
+ * Per-phase timings in milliseconds (DNS, connect, TLS, write, first byte, download)
+ */
+export const zTimings = z.object({
+  dns: z.number().nullable(),
+  tcpConnect: z.number().nullable(),
+  tlsHandshake: z.number().nullable(),
+  requestWrite: z.number().nullable(),
+  timeToFirstByte: z.number().nullable(),
+  download: z.number().nullable(),
+  connectionReused: z.boolean(),
+  total: z.number(),
+})
+
+/**
+ * A redirect response followed on the way to the final response
+ */
+export const zRedirectHop = z.object({
+  /**
+   * URL of the request that was redirected
+   */
+  url: z.string(),
+  method: z.string(),
+  status: z.number(),
+  statusText: z.string(),
+  headers: z.array(z.tuple([z.string(), z.string()])),
+  cookies: z.array(zCookie),
+  /**
+   * Milliseconds from sending the request to the redirect's headers
+   */
+  duration: z.number(),
+  timings: zTimings,
+})
+export type RedirectHop = z.infer<typeof zRedirectHop>
+
 /**
  * Schema for HTTP-specific response data
  */
@@ -615,6 +995,14 @@ export const zHttpResponseData = z.object({
    * Response headers
    */
   headers: z.record(z.string(), z.string()),
+  /**
+   * Response headers in the order received, with repeated headers (e.g. Set-Cookie) kept apart
+   */
+  headerList: z.array(z.tuple([z.string(), z.string()])).optional(),
+  /**
+   * Exact bytes of header values that aren't valid UTF-8; `index` is the position in `headerList`
+   */
+  binaryHeaders: z.array(z.object({ index: z.number(), base64: z.string() })).optional(),
   /**
    * Response cookies
    */
@@ -631,6 +1019,126 @@ export const zHttpResponseData = z.object({
    * If present, body was streamed to this local file path on disk.
    */
   filePath: z.string().optional(),
+  /**
+   * Whether `filePath` holds the body compressed with zstd
+   */
+  fileCompressed: z.boolean().optional(),
+  /**
+   * Whether the body stopped at the request's maxResponseBytes
+   */
+  truncated: z.boolean().optional(),
+  /**
+   * Whether the request was cancelled while reading the body, which holds what arrived before
+   */
+  cancelled: z.boolean().optional(),
+  /**
+   * The file the body was written to, when the request saved its response to a file
+   */
+  savedTo: z.string().optional(),
+  /**
+   * Where in savedTo the body was appended, when it resumed an interrupted download
+   */
+  resumedFrom: z.number().optional(),
+  /**
+   * The part of the resource a 206 Partial Content response holds
+   */
+  contentRange: z
+    .object({
+      start: z.number(),
+      end: z.number(),
+      total: z.number().nullish(),
+    })
+    .optional(),
+  /**
+   * What the response cache did: stored the response, revalidated a cached one with a 304, or couldn't store it
+   */
+  cache: z.enum(["stored", "revalidated", "uncached"]).optional(),
+  /**
+   * Per-phase timings in milliseconds (DNS, connect, TLS, write, first byte, download)
+   */
+  timings: zTimings.optional(),
+  /**
+   * Addresses of the connection the response arrived on, and whether an earlier request opened it
+   */
+  connection: z
+    .object({
+      localAddr: z.string(),
+      remoteAddr: z.string(),
+      reused: z.boolean(),
+    })
+    .optional(),
+  /**
+   * Redirect responses followed on the way to this one, when redirects are followed
+   */
+  redirectChain: z.array(zRedirectHop).optional(),
+  /**
+   * Quota advertised by the server's RateLimit / X-RateLimit headers
+   */
+  rateLimit: z
+    .object({
+      limit: z.number().nullable(),
+      remaining: z.number().nullable(),
+      resetSecs: z.number().nullable(),
+      policy: z.string().nullable(),
+    })
+    .optional(),
+  /**
+   * Retries spent on 429/503 responses when the retry policy is enabled
+   */
+  retries: z
+    .object({
+      attempts: z.number(),
+      maxRetries: z.number(),
+      waitedMs: z.number(),
+    })
+    .optional(),
+  /**
+   * Log entries the request produced, when its log capture mode is set
+   */
+  logSummary: z
+    .object({
+      captured: z.boolean(),
+      entries: z.number(),
+      warnings: z.number(),
+      errors: z.number(),
+    })
+    .optional(),
+  /**
+   * Results of the request's assertions
+   */
+  assertions: z
+    .array(
+      z.object({
+        assertion: zAssertion,
+        passed: z.boolean(),
+        actual: z.string().optional(),
+        message: z.string(),
+      }),
+    )
+    .optional(),
+  /**
+   * Parsed WebDAV multistatus body of a 207 response
+   */
+  multistatus: z
+    .object({
+      responses: z.array(
+        z.object({
+          href: z.string(),
+          status: z.number().optional(),
+          propstats: z.array(
+            z.object({
+              status: z.number().nullable(),
+              properties: z.array(
+                z.object({ namespace: z.string().nullable(), name: z.string(), value: z.string() }),
+              ),
+            }),
+          ),
+          description: z.string().optional(),
+        }),
+      ),
+      description: z.string().optional(),
+    })
+    .optional(),
 })
 export type HttpResponseData = z.infer<typeof zHttpResponseData>
 
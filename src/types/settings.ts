@@ -1,5 +1,7 @@
 import { z } from "zod"
 
+import { type ConnectionPool, zConnectionPool } from "./request"
+
 export const zTheme = z.enum(["dark", "light", "system"])
 export type Theme = z.infer<typeof zTheme>
 
@@ -58,6 +60,12 @@ export const zRequestSettings = z.object({
   proxyServer: z.string().optional(),
   // maximum bytes to generate base64 previews for binary responses
   previewMaxBytes: z.number().int().positive().optional(),
+  // keep-alive connection pooling for requests that don't set their own
+  connectionPool: zConnectionPool.optional(),
+  // serve monitor metrics at http://127.0.0.1:<port>/metrics. Off when unset
+  metricsPort: z.number().int().min(1).max(65535).optional(),
+  // write monitor metrics to this file after each check, for node_exporter's textfile collector
+  metricsFile: z.string().optional(),
 })
 
 /**
@@ -101,6 +109,9 @@ export interface SettingsApi {
   setSslVerify(verify: boolean): void
   setProxyServer(url?: string): void
   setPreviewMaxBytes(bytes: number): void
+  setConnectionPool(pool?: ConnectionPool): void
+  setMetricsPort(port?: number): void
+  setMetricsFile(path?: string): void
 
   // Advanced
   setDevMode(enabled: boolean): void
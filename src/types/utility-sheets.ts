@@ -7,6 +7,7 @@ export const utilitySheetTypes = [
   "environment",
   "collection-settings",
   "theme-editor",
+  "trash",
 ] as const
 
 export type UtilitySheetType = (typeof utilitySheetTypes)[number]
@@ -30,6 +31,7 @@ export const zUtilitySheet = z.discriminatedUnion("type", [
   z.object({ type: z.literal("settings") }),
   z.object({ type: z.literal("import") }),
   z.object({ type: z.literal("theme-editor") }),
+  z.object({ type: z.literal("trash") }),
   z.object({ type: z.literal("export"), context: zExportContext }),
   z.object({ type: z.literal("environment"), context: zEnvironmentContext }),
   z.object({ type: z.literal("collection-settings"), context: zCollectionSettingsContext }),